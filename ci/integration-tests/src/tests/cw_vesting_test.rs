@@ -61,7 +61,8 @@ fn test_cw_vesting_staking(chain: &mut Chain) {
             "instantiate",
             &InstantiateMsg {
                 owner: Some(user_addr.clone()),
-                recipient: user_addr.to_string(),
+                recipient: Some(user_addr.to_string()),
+                vest_from_assignment: false,
 
                 title: "title".to_string(),
                 description: Some("description".to_string()),
@@ -73,6 +74,19 @@ fn test_cw_vesting_staking(chain: &mut Chain) {
                 start_time: None,
                 vesting_duration_seconds: 10,
                 unbonding_duration_seconds: 2 & 592000,
+                matching: None,
+                receipts: None,
+                swap: None,
+                off_ramp: None,
+                timesheet: None,
+                severance: None,
+                label: None,
+                metadata: None,
+                additional_vests: vec![],
+                voting_module: None,
+                clawback: None,
+                allow_governance_votes: false,
+                guardian: None,
             },
             &user_key,
             None,
@@ -1,30 +1,53 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    ensure, from_json, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response,
-    StdResult, Uint128, Uint256,
+    ensure, from_json, to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, Event,
+    MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128, Uint256, WasmMsg,
 };
 use cw2::{get_contract_version, set_contract_version};
-use cw20::{Cw20ReceiveMsg, Denom};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Denom, UncheckedDenom};
 use cw_storage_plus::Bound;
-use cw_utils::{must_pay, nonpayable, Duration, Expiration};
+use cw_utils::{must_pay, nonpayable, one_coin, Duration, Expiration};
 use dao_interface::voting::InfoResponse;
 
+use std::cmp::min;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Add;
 
-use crate::helpers::{get_transfer_msg, validate_voting_power_contract};
+use crate::helpers::{
+    get_cw20_pullable, get_denom_string, get_duration_scalar, get_transfer_msg,
+    validate_voting_power_contract, validate_vp_sources,
+};
 use crate::hooks::{
     execute_membership_changed, execute_nft_stake_changed, execute_stake_changed,
-    subscribe_distribution_to_hook, unsubscribe_distribution_from_hook,
+    execute_update_members, subscribe_distribution_to_hook, subscribe_distribution_to_typed_hook,
+    unsubscribe_distribution_from_hook, unsubscribe_distribution_from_typed_hook,
 };
 use crate::msg::{
-    CreateMsg, DistributionPendingRewards, DistributionsResponse, ExecuteMsg, FundMsg,
-    InstantiateMsg, MigrateMsg, PendingRewardsResponse, QueryMsg, ReceiveCw20Msg,
+    ClaimDelegate, ClaimDelegatesResponse, ClaimHistoryResponse, CreateMsg, DenomTotals,
+    DistributionHealth, DistributionPendingRewards, DistributionResponse, DistributionStats,
+    DistributionStatsResponse, DistributionsResponse, ExecuteMsg, FundMsg,
+    FundingSchedulesResponse, HookCallersResponse, InstantiateMsg, ManagerAction, MigrateMsg,
+    PendingRewardsResponse, QueryMsg, ReceiveCw20Msg, RolloversResponse, SimulateUpdateResponse,
+    TickPage, TotalsByDenomResponse, UndistributedRewards, UndistributedRewardsResponse,
+    VpSourceMsg,
+};
+use crate::raffles::{
+    execute_close_raffle, execute_create_raffle, execute_draw_raffle, execute_enter_raffle,
+    execute_receive_nft, query_raffle_draws,
 };
 use crate::rewards::{
-    get_accrued_rewards_not_yet_accounted_for, get_active_total_earned_puvp, update_rewards,
+    compact_user_rewards, get_accrued_rewards_not_yet_accounted_for, get_active_epoch_totals,
+    get_active_total_earned_puvp, update_rewards,
+};
+use crate::state::{
+    assert_owner_or_manager, claim_history, get_linear_funded_duration, ClaimRecord,
+    DenomTotalsState, DistributionManager, DistributionState, EmissionRate, Epoch, FundingSchedule,
+    FundingSource, HookCallers, HookType, KeeperFee, Rollover, CLAIM_DELEGATES, COUNT,
+    DENOM_TOTALS, DISTRIBUTIONS, DISTRIBUTION_CLAIMANTS, DISTRIBUTION_CLAIMANT_COUNTS,
+    DISTRIBUTION_MANAGERS, DISTRIBUTION_TOTALS, FUNDING_SCHEDULES, HOOK_SEQUENCES, RAFFLES,
+    ROLLOVERS, USER_REWARDS,
 };
-use crate::state::{DistributionState, EmissionRate, Epoch, COUNT, DISTRIBUTIONS, USER_REWARDS};
 use crate::ContractError;
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
@@ -68,12 +91,20 @@ pub fn execute(
         ExecuteMsg::UpdateOwnership(action) => execute_update_owner(deps, info, env, action),
         ExecuteMsg::Receive(msg) => execute_receive_cw20(deps, env, info, msg),
         ExecuteMsg::Create(create_msg) => execute_create(deps, env, info, create_msg),
+        ExecuteMsg::CreatePool {
+            id,
+            denom,
+            emission_rate,
+        } => execute_create_pool(deps, env, info, id, denom, emission_rate),
         ExecuteMsg::Update {
             id,
             emission_rate,
             vp_contract,
+            vp_sources,
             hook_caller,
             withdraw_destination,
+            max_rewards_per_user,
+            unclaimed_expiry,
         } => execute_update(
             deps,
             env,
@@ -81,12 +112,77 @@ pub fn execute(
             id,
             emission_rate,
             vp_contract,
+            vp_sources,
             hook_caller,
             withdraw_destination,
+            max_rewards_per_user,
+            unclaimed_expiry,
         ),
+        ExecuteMsg::UpdateHookCallers {
+            id,
+            hook_type,
+            to_add,
+            to_remove,
+        } => execute_update_hook_callers(deps, info, id, hook_type, to_add, to_remove),
+        ExecuteMsg::UpdateKeeperFee { id, keeper_fee } => {
+            execute_update_keeper_fee(deps, info, id, keeper_fee)
+        }
+        ExecuteMsg::UpdateMemberUpdaters { id, updaters } => {
+            execute_update_member_updaters(deps, info, id, updaters)
+        }
+        ExecuteMsg::UpdateMembers { id, addresses } => {
+            execute_update_members(deps, env, info, id, addresses)
+        }
         ExecuteMsg::Fund(FundMsg { id }) => execute_fund_native(deps, env, info, id),
         ExecuteMsg::Claim { id } => execute_claim(deps, env, info, id),
+        ExecuteMsg::ClaimAll { start_after, limit } => {
+            execute_claim_all(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::ClaimMany { ids, strict } => execute_claim_many(deps, env, info, ids, strict),
+        ExecuteMsg::SetClaimDelegate {
+            delegate,
+            destination,
+        } => execute_set_claim_delegate(deps, info, delegate, destination),
+        ExecuteMsg::RemoveClaimDelegate { delegate } => {
+            execute_remove_claim_delegate(deps, info, delegate)
+        }
+        ExecuteMsg::ClaimFor { id, staker } => execute_claim_for(deps, env, info, id, staker),
         ExecuteMsg::Withdraw { id } => execute_withdraw(deps, info, env, id),
+        ExecuteMsg::ForceWithdraw { id } => execute_force_withdraw(deps, info, env, id),
+        ExecuteMsg::Unfreeze { id } => execute_unfreeze(deps, info, env, id),
+        ExecuteMsg::Poke { id } => execute_poke(deps, env, info, id),
+        ExecuteMsg::UpdateManager { id, action } => {
+            execute_update_manager(deps, env, info, id, action)
+        }
+        ExecuteMsg::CreateFundingSchedule {
+            id,
+            amount,
+            interval,
+            source,
+        } => execute_create_funding_schedule(deps, env, info, id, amount, interval, source),
+        ExecuteMsg::RemoveFundingSchedule { id } => execute_remove_funding_schedule(deps, info, id),
+        ExecuteMsg::CreateRollover {
+            id,
+            top_up,
+            max_rollovers,
+            source,
+        } => execute_create_rollover(deps, info, id, top_up, max_rollovers, source),
+        ExecuteMsg::RemoveRollover { id } => execute_remove_rollover(deps, info, id),
+        ExecuteMsg::Tick {
+            schedules,
+            rollovers,
+        } => execute_tick(
+            deps,
+            env,
+            info,
+            schedules.unwrap_or_default(),
+            rollovers.unwrap_or_default(),
+        ),
+        ExecuteMsg::CreateRaffle(msg) => execute_create_raffle(deps, env, info, msg),
+        ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, info, msg),
+        ExecuteMsg::EnterRaffle { id } => execute_enter_raffle(deps, env, info, id),
+        ExecuteMsg::DrawRaffle { id, seed } => execute_draw_raffle(deps, env, info, id, seed),
+        ExecuteMsg::CloseRaffle { id } => execute_close_raffle(deps, env, info, id),
     }
 }
 
@@ -107,15 +203,20 @@ fn execute_receive_cw20(
                 .load(deps.storage, id)
                 .map_err(|_| ContractError::DistributionNotFound { id })?;
 
-            match &distribution.denom {
-                Denom::Native(_) => return Err(ContractError::InvalidFunds {}),
-                Denom::Cw20(addr) => {
-                    // ensure funding is coming from the cw20 we are currently
-                    // distributing
-                    if addr != info.sender {
-                        return Err(ContractError::InvalidCw20 {});
-                    }
-                }
+            // ensure funding is coming from a cw20 we are currently
+            // distributing, either in this distribution or one of its pools
+            let is_native = matches!(distribution.denom, Denom::Native(_));
+            let Some(distribution) = get_fund_target(
+                deps.storage,
+                distribution,
+                &Denom::Cw20(info.sender.clone()),
+            )?
+            else {
+                return Err(if is_native {
+                    ContractError::InvalidFunds {}
+                } else {
+                    ContractError::InvalidCw20 {}
+                });
             };
 
             execute_fund(deps, env, distribution, wrapper.amount)
@@ -141,6 +242,7 @@ fn execute_create(
     let checked_denom = msg.denom.into_checked(deps.as_ref())?;
     let hook_caller = deps.api.addr_validate(&msg.hook_caller)?;
     let vp_contract = validate_voting_power_contract(&deps, msg.vp_contract)?;
+    let vp_sources = validate_vp_sources(&deps, msg.vp_sources)?;
 
     let withdraw_destination = match msg.withdraw_destination {
         // if withdraw destination is specified, we validate it
@@ -151,6 +253,24 @@ fn execute_create(
 
     msg.emission_rate.validate()?;
 
+    ensure!(
+        msg.max_rewards_per_user != Some(Uint128::zero()),
+        ContractError::InvalidMaxRewardsPerUser {}
+    );
+
+    // snapshot distributions allocate rewards immediately based on past voting
+    // power, so the snapshot must be in the past
+    if let Some(snapshot_height) = msg.snapshot_height {
+        ensure!(
+            snapshot_height < env.block.height,
+            ContractError::InvalidSnapshotHeight {}
+        );
+        ensure!(
+            msg.emission_rate == EmissionRate::Immediate {},
+            ContractError::SnapshotRequiresImmediateEmission {}
+        );
+    }
+
     // Initialize the distribution state
     let distribution = DistributionState {
         id,
@@ -161,14 +281,39 @@ fn execute_create(
             emission_rate: msg.emission_rate,
             total_earned_puvp: Uint256::zero(),
             last_updated_total_earned_puvp: Expiration::Never {},
+            emitted: Uint128::zero(),
         },
         vp_contract,
-        hook_caller: hook_caller.clone(),
+        vp_sources,
+        hook_caller,
+        hook_callers: HookCallers::default(),
+        member_updaters: None,
         funded_amount: Uint128::zero(),
         withdraw_destination,
         historical_earned_puvp: Uint256::zero(),
+        snapshot_height: msg.snapshot_height,
+        frozen: false,
+        settled_puvp: None,
+        keeper_fee: None,
+        keeper_fee_next_payable: None,
+        max_rewards_per_user: msg.max_rewards_per_user,
+        capped_rewards: Uint128::zero(),
+        pools: vec![],
+        pool_of: None,
+        unclaimed_expiry: Expiration::Never {},
+        force_withdrawn: false,
     };
 
+    // a snapshot must have voting power to allocate rewards by
+    if let Some(snapshot_height) = distribution.snapshot_height {
+        ensure!(
+            !distribution
+                .get_total_vp_at_height(deps.as_ref(), snapshot_height)?
+                .is_zero(),
+            ContractError::NoVotingPowerNoRewards {}
+        );
+    }
+
     // store the new distribution state, erroring if it already exists. this
     // should never happen, but just in case.
     DISTRIBUTIONS.update(deps.storage, id, |existing| match existing {
@@ -176,14 +321,26 @@ fn execute_create(
         None => Ok(distribution.clone()),
     })?;
 
+    DISTRIBUTION_TOTALS.save(
+        deps.storage,
+        id,
+        &DenomTotalsState::new(distribution.denom.clone()),
+    )?;
+
     // update the registered hooks to include the new distribution
-    subscribe_distribution_to_hook(deps.storage, id, hook_caller.clone())?;
+    for hook_caller in distribution.get_all_hook_callers() {
+        subscribe_distribution_to_hook(deps.storage, id, hook_caller)?;
+    }
 
     let mut response = Response::new()
         .add_attribute("action", "create")
         .add_attribute("id", id.to_string())
         .add_attribute("denom", distribution.get_denom_string());
 
+    if let Some(snapshot_height) = distribution.snapshot_height {
+        response = response.add_attribute("snapshot_height", snapshot_height.to_string());
+    }
+
     // if native funds provided, ensure they are for this denom. if other native
     // funds present, return error. if no funds, do nothing and leave registered
     // denom with no funding, to be funded later.
@@ -204,6 +361,126 @@ fn execute_create(
     Ok(response)
 }
 
+/// creates a distribution of another denom as a pool of an existing one. the
+/// pool copies the distribution's voting power and hook configuration, which
+/// are kept in sync when the distribution is updated. only the owner can do
+/// this. if funds are provided for a native token pool, it will start
+/// distributing rewards immediately.
+fn execute_create_pool(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent_id: u64,
+    denom: UncheckedDenom,
+    emission_rate: EmissionRate,
+) -> Result<Response, ContractError> {
+    // only the owner can create a new distribution
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut parent = DISTRIBUTIONS
+        .load(deps.storage, parent_id)
+        .map_err(|_| ContractError::DistributionNotFound { id: parent_id })?;
+    ensure!(
+        parent.pool_of.is_none(),
+        ContractError::PoolOfPool { id: parent_id }
+    );
+
+    // each denom may only be distributed once among a distribution and its
+    // pools, so that funds can be routed by denom
+    let checked_denom = denom.into_checked(deps.as_ref())?;
+    if get_fund_target(deps.storage, parent.clone(), &checked_denom)?.is_some() {
+        return Err(ContractError::DuplicatePoolDenom {
+            id: parent_id,
+            denom: get_denom_string(&checked_denom),
+        });
+    }
+
+    emission_rate.validate()?;
+    if parent.snapshot_height.is_some() {
+        ensure!(
+            emission_rate == EmissionRate::Immediate {},
+            ContractError::SnapshotRequiresImmediateEmission {}
+        );
+    }
+
+    // update count and use as the new pool's ID
+    let id = COUNT.update(deps.storage, |count| -> StdResult<u64> { Ok(count + 1) })?;
+
+    let pool = DistributionState {
+        id,
+        denom: checked_denom,
+        active_epoch: Epoch {
+            started_at: Expiration::Never {},
+            ends_at: Expiration::Never {},
+            emission_rate,
+            total_earned_puvp: Uint256::zero(),
+            last_updated_total_earned_puvp: Expiration::Never {},
+            emitted: Uint128::zero(),
+        },
+        vp_contract: parent.vp_contract.clone(),
+        vp_sources: parent.vp_sources.clone(),
+        hook_caller: parent.hook_caller.clone(),
+        hook_callers: parent.hook_callers.clone(),
+        member_updaters: None,
+        funded_amount: Uint128::zero(),
+        withdraw_destination: parent.withdraw_destination.clone(),
+        historical_earned_puvp: Uint256::zero(),
+        snapshot_height: parent.snapshot_height,
+        frozen: false,
+        settled_puvp: None,
+        keeper_fee: None,
+        keeper_fee_next_payable: None,
+        max_rewards_per_user: None,
+        capped_rewards: Uint128::zero(),
+        pools: vec![],
+        pool_of: Some(parent_id),
+        unclaimed_expiry: Expiration::Never {},
+        force_withdrawn: false,
+    };
+
+    DISTRIBUTIONS.update(deps.storage, id, |existing| match existing {
+        Some(_) => Err(ContractError::UnexpectedDuplicateDistributionId { id }),
+        None => Ok(pool.clone()),
+    })?;
+
+    DISTRIBUTION_TOTALS.save(deps.storage, id, &DenomTotalsState::new(pool.denom.clone()))?;
+
+    parent.pools.push(id);
+    DISTRIBUTIONS.save(deps.storage, parent_id, &parent)?;
+
+    // subscribe the pool to the same hooks as its distribution
+    for hook_caller in pool.get_all_hook_callers() {
+        subscribe_distribution_to_hook(deps.storage, id, hook_caller)?;
+    }
+    for hook_type in [HookType::Stake, HookType::NftStake, HookType::Membership] {
+        for hook_caller in pool.hook_callers.get(hook_type) {
+            subscribe_distribution_to_typed_hook(deps.storage, id, hook_type, hook_caller.clone())?;
+        }
+    }
+
+    let mut response = Response::new()
+        .add_attribute("action", "create_pool")
+        .add_attribute("id", id.to_string())
+        .add_attribute("pool_of", parent_id.to_string())
+        .add_attribute("denom", pool.get_denom_string());
+
+    if !info.funds.is_empty() {
+        match &pool.denom {
+            Denom::Native(denom) => {
+                // ensures there is exactly 1 coin passed that matches the denom
+                let amount = must_pay(&info, denom)?;
+
+                execute_fund(deps, env, pool, amount)?;
+
+                response = response.add_attribute("amount_funded", amount);
+            }
+            Denom::Cw20(_) => return Err(ContractError::NoFundsOnCw20Create {}),
+        }
+    }
+
+    Ok(response)
+}
+
 /// updates the config for a distribution
 #[allow(clippy::too_many_arguments)]
 fn execute_update(
@@ -213,18 +490,48 @@ fn execute_update(
     id: u64,
     emission_rate: Option<EmissionRate>,
     vp_contract: Option<String>,
+    vp_sources: Option<Vec<VpSourceMsg>>,
     hook_caller: Option<String>,
     withdraw_destination: Option<String>,
+    max_rewards_per_user: Option<Uint128>,
+    unclaimed_expiry: Option<Expiration>,
 ) -> Result<Response, ContractError> {
     nonpayable(&info)?;
 
-    // only the owner can update a distribution
-    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    // only the owner or manager can update a distribution
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    // managers may tune emissions, but only the owner may change where funds
+    // go, whose voting power counts, or when unclaimed rewards expire
+    if vp_contract.is_some()
+        || vp_sources.is_some()
+        || hook_caller.is_some()
+        || withdraw_destination.is_some()
+        || unclaimed_expiry.is_some()
+    {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    }
 
     let mut distribution = DISTRIBUTIONS
         .load(deps.storage, id)
         .map_err(|_| ContractError::DistributionNotFound { id })?;
 
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn { id }
+    );
+
+    // pools take their voting power and hook configuration from their
+    // distribution
+    let updates_shared_config =
+        vp_contract.is_some() || vp_sources.is_some() || hook_caller.is_some();
+    if let Some(parent_id) = distribution.pool_of {
+        ensure!(
+            !updates_shared_config,
+            ContractError::PoolConfigFromParent { id: parent_id }
+        );
+    }
+
     if let Some(emission_rate) = emission_rate {
         emission_rate.validate()?;
 
@@ -236,20 +543,54 @@ fn execute_update(
         distribution.vp_contract = validate_voting_power_contract(&deps, vp_contract)?;
     }
 
-    if let Some(hook_caller) = hook_caller {
-        // remove existing from registered hooks
-        unsubscribe_distribution_from_hook(deps.storage, id, distribution.hook_caller)?;
+    let previous_hook_callers = distribution.get_all_hook_callers();
+
+    if let Some(vp_sources) = vp_sources {
+        distribution.vp_sources = validate_vp_sources(&deps, vp_sources)?;
+    }
 
+    if let Some(hook_caller) = hook_caller {
         distribution.hook_caller = deps.api.addr_validate(&hook_caller)?;
+    }
+
+    update_hook_subscriptions(
+        deps.storage,
+        id,
+        &previous_hook_callers,
+        distribution.get_all_hook_callers(),
+    )?;
 
-        // add new to registered hooks
-        subscribe_distribution_to_hook(deps.storage, id, distribution.hook_caller.clone())?;
+    // keep the distribution's pools in sync
+    if updates_shared_config {
+        for &pool_id in &distribution.pools {
+            let mut pool = DISTRIBUTIONS.load(deps.storage, pool_id)?;
+            let previous_hook_callers = pool.get_all_hook_callers();
+            pool.vp_contract = distribution.vp_contract.clone();
+            pool.vp_sources = distribution.vp_sources.clone();
+            pool.hook_caller = distribution.hook_caller.clone();
+            update_hook_subscriptions(
+                deps.storage,
+                pool_id,
+                &previous_hook_callers,
+                pool.get_all_hook_callers(),
+            )?;
+            DISTRIBUTIONS.save(deps.storage, pool_id, &pool)?;
+        }
     }
 
     if let Some(withdraw_destination) = withdraw_destination {
         distribution.withdraw_destination = deps.api.addr_validate(&withdraw_destination)?;
     }
 
+    if let Some(max_rewards_per_user) = max_rewards_per_user {
+        distribution.max_rewards_per_user = Some(max_rewards_per_user).filter(|max| !max.is_zero());
+    }
+
+    if let Some(unclaimed_expiry) = unclaimed_expiry {
+        distribution.validate_unclaimed_expiry(id, &unclaimed_expiry)?;
+        distribution.unclaimed_expiry = unclaimed_expiry;
+    }
+
     DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
 
     Ok(Response::new()
@@ -258,6 +599,93 @@ fn execute_update(
         .add_attribute("denom", distribution.get_denom_string()))
 }
 
+/// updates the registered hooks of a distribution to match its hook callers and
+/// those of its voting power sources, which may share addresses
+fn update_hook_subscriptions(
+    storage: &mut dyn Storage,
+    id: u64,
+    previous_hook_callers: &[Addr],
+    hook_callers: Vec<Addr>,
+) -> Result<(), ContractError> {
+    for hook_caller in previous_hook_callers {
+        if !hook_callers.contains(hook_caller) {
+            unsubscribe_distribution_from_hook(storage, id, hook_caller.clone())?;
+        }
+    }
+    for hook_caller in hook_callers {
+        if !previous_hook_callers.contains(&hook_caller) {
+            subscribe_distribution_to_hook(storage, id, hook_caller)?;
+        }
+    }
+    Ok(())
+}
+
+/// adds and removes addresses authorized to call a single type of hook for a
+/// distribution and its pools
+fn execute_update_hook_callers(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+    hook_type: HookType,
+    to_add: Vec<String>,
+    to_remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // like the hook_caller, only the owner may change whose hooks update a
+    // distribution
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+    if let Some(parent_id) = distribution.pool_of {
+        return Err(ContractError::PoolConfigFromParent { id: parent_id });
+    }
+
+    let to_remove = to_remove
+        .into_iter()
+        .map(|addr| deps.api.addr_validate(&addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    let to_add = to_add
+        .into_iter()
+        .map(|addr| deps.api.addr_validate(&addr))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for id in std::iter::once(id).chain(distribution.pools) {
+        let mut distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+        let callers = distribution.hook_callers.get_mut(hook_type);
+
+        for addr in &to_remove {
+            let Some(index) = callers.iter().position(|caller| caller == addr) else {
+                return Err(ContractError::HookCallerNotAuthorized {
+                    addr: addr.to_string(),
+                });
+            };
+            callers.remove(index);
+            unsubscribe_distribution_from_typed_hook(deps.storage, id, hook_type, addr.clone())?;
+        }
+
+        for addr in &to_add {
+            ensure!(
+                !callers.contains(addr),
+                ContractError::HookCallerAlreadyAuthorized {
+                    addr: addr.to_string()
+                }
+            );
+            callers.push(addr.clone());
+            subscribe_distribution_to_typed_hook(deps.storage, id, hook_type, addr.clone())?;
+        }
+
+        DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_hook_callers")
+        .add_attribute("id", id.to_string())
+        .add_attribute("hook_type", hook_type.as_str()))
+}
+
 fn execute_fund_native(
     deps: DepsMut,
     env: Env,
@@ -268,14 +696,32 @@ fn execute_fund_native(
         .load(deps.storage, id)
         .map_err(|_| ContractError::DistributionNotFound { id })?;
 
-    let amount = match &distribution.denom {
-        Denom::Native(denom) => {
-            must_pay(&info, denom).map_err(|_| ContractError::InvalidFunds {})?
-        }
-        Denom::Cw20(_) => return Err(ContractError::InvalidFunds {}),
-    };
+    // the funds go to this distribution or the pool of their denom
+    let coin = one_coin(&info).map_err(|_| ContractError::InvalidFunds {})?;
+    let distribution = get_fund_target(deps.storage, distribution, &Denom::Native(coin.denom))?
+        .ok_or(ContractError::InvalidFunds {})?;
+
+    execute_fund(deps, env, distribution, coin.amount)
+}
 
-    execute_fund(deps, env, distribution, amount)
+/// returns the distribution funded by funding `distribution` with `denom`:
+/// the distribution itself if it distributes `denom`, otherwise its pool that
+/// does, if any.
+fn get_fund_target(
+    storage: &dyn Storage,
+    distribution: DistributionState,
+    denom: &Denom,
+) -> StdResult<Option<DistributionState>> {
+    if distribution.denom == *denom {
+        return Ok(Some(distribution));
+    }
+    for id in distribution.pools {
+        let pool = DISTRIBUTIONS.load(storage, id)?;
+        if pool.denom == *denom {
+            return Ok(Some(pool));
+        }
+    }
+    Ok(None)
 }
 
 fn execute_fund(
@@ -284,6 +730,20 @@ fn execute_fund(
     mut distribution: DistributionState,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn {
+            id: distribution.id
+        }
+    );
+    ensure!(
+        !distribution.frozen,
+        ContractError::DistributionFrozen {
+            id: distribution.id
+        }
+    );
+    distribution.check_height(&env.block)?;
+
     // will only be true if emission rate is linear and continuous is true
     let continuous =
         if let EmissionRate::Linear { continuous, .. } = distribution.active_epoch.emission_rate {
@@ -303,6 +763,20 @@ fn execute_fund(
         !continuous && distribution.active_epoch.ends_at.is_expired(&env.block)
     };
 
+    // perpetual distributions never expire, so bring rewards up to date with
+    // the existing funding before adding to it. otherwise, the new funds would
+    // be emitted over any gap in which the distribution was paused for lack of
+    // funding.
+    if !restart_distribution
+        && matches!(
+            distribution.active_epoch.emission_rate,
+            EmissionRate::LinearPerpetual { .. }
+        )
+    {
+        distribution.update_active_epoch(deps.as_ref(), &env.block)?;
+        distribution.active_epoch.bump_last_updated(&env.block)?;
+    }
+
     // if necessary, restart the distribution from the current block so that the
     // new funds start being distributed from now instead of from the past, and
     // reset funded_amount to the new amount since we're effectively starting a
@@ -310,14 +784,10 @@ fn execute_fund(
     // funded_amount
     if restart_distribution {
         distribution.funded_amount = amount;
-        distribution.active_epoch.started_at = match distribution.active_epoch.emission_rate {
-            EmissionRate::Paused {} => Expiration::Never {},
-            EmissionRate::Immediate {} => Expiration::Never {},
-            EmissionRate::Linear { duration, .. } => match duration {
-                Duration::Height(_) => Expiration::AtHeight(env.block.height),
-                Duration::Time(_) => Expiration::AtTime(env.block.time),
-            },
-        };
+        distribution.active_epoch.started_at = distribution
+            .active_epoch
+            .emission_rate
+            .get_started_at(&env.block);
     } else {
         distribution.funded_amount += amount;
     }
@@ -349,7 +819,14 @@ fn execute_fund(
             get_active_total_earned_puvp(deps.as_ref(), &env.block, &distribution)?;
     }
 
-    distribution.active_epoch.bump_last_updated(&env.block);
+    distribution.active_epoch.bump_last_updated(&env.block)?;
+
+    // totals are updated first, so that a tick that fails to fund a
+    // distribution leaves it unchanged.
+    DenomTotalsState::update_for_distribution(deps.storage, &distribution, |totals| {
+        totals.funded = totals.funded.checked_add(amount)?;
+        Ok(())
+    })?;
 
     DISTRIBUTIONS.save(deps.storage, distribution.id, &distribution)?;
 
@@ -361,125 +838,1225 @@ fn execute_fund(
 }
 
 fn execute_claim(
-    mut deps: DepsMut,
+    deps: DepsMut,
     env: Env,
     info: MessageInfo,
     id: u64,
 ) -> Result<Response, ContractError> {
     nonpayable(&info)?;
 
-    // update the distribution for the sender. this updates the distribution
-    // state and the user reward state.
-    update_rewards(&mut deps, &env, &info.sender, id)?;
+    claim(deps, env, &info.sender, info.sender.clone(), id)
+}
 
-    // load the updated states. previous `update_rewards` call ensures that
-    // these states exist.
-    let distribution = DISTRIBUTIONS.load(deps.storage, id)?;
-    let mut user_reward_state = USER_REWARDS.load(deps.storage, info.sender.clone())?;
+fn execute_set_claim_delegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    delegate: String,
+    destination: String,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
 
-    // updating the map returns the previous value if it existed. we set the
-    // value to zero and get the amount of pending rewards until this point.
-    let claim_amount = user_reward_state
-        .pending_rewards
-        .insert(id, Uint128::zero())
-        .unwrap_or_default();
+    let delegate = deps.api.addr_validate(&delegate)?;
+    let destination = deps.api.addr_validate(&destination)?;
+    CLAIM_DELEGATES.save(deps.storage, (&info.sender, &delegate), &destination)?;
 
-    // if there are no rewards to claim, error out
-    if claim_amount.is_zero() {
-        return Err(ContractError::NoRewardsClaimable {});
-    }
+    Ok(Response::new()
+        .add_attribute("action", "set_claim_delegate")
+        .add_attribute("staker", info.sender)
+        .add_attribute("delegate", delegate)
+        .add_attribute("destination", destination))
+}
 
-    // otherwise reflect the updated user reward state and transfer out the
-    // claimed rewards
-    USER_REWARDS.save(deps.storage, info.sender.clone(), &user_reward_state)?;
+fn execute_remove_claim_delegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    delegate: String,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
 
-    let denom_str = distribution.get_denom_string();
+    let delegate = deps.api.addr_validate(&delegate)?;
+    if !CLAIM_DELEGATES.has(deps.storage, (&info.sender, &delegate)) {
+        return Err(ContractError::NotClaimDelegate {
+            staker: info.sender.into_string(),
+            delegate: delegate.into_string(),
+        });
+    }
+    CLAIM_DELEGATES.remove(deps.storage, (&info.sender, &delegate));
 
     Ok(Response::new()
-        .add_message(get_transfer_msg(
-            info.sender.clone(),
-            claim_amount,
-            distribution.denom,
-        )?)
-        .add_attribute("action", "claim")
-        .add_attribute("id", id.to_string())
-        .add_attribute("denom", denom_str)
-        .add_attribute("amount_claimed", claim_amount))
+        .add_attribute("action", "remove_claim_delegate")
+        .add_attribute("staker", info.sender)
+        .add_attribute("delegate", delegate))
 }
 
-/// withdraws the undistributed rewards for a distribution. members can claim
-/// whatever they earned until this point. this is effectively an inverse to
-/// fund and does not affect any already-distributed rewards. can only be called
-/// by the admin and only during the distribution period. updates the period
-/// finish expiration to the current block.
-fn execute_withdraw(
+fn execute_claim_for(
     deps: DepsMut,
-    info: MessageInfo,
     env: Env,
+    info: MessageInfo,
     id: u64,
+    staker: String,
 ) -> Result<Response, ContractError> {
     nonpayable(&info)?;
 
-    // only the owner can initiate a withdraw
-    cw_ownable::assert_owner(deps.storage, &info.sender)?;
-
-    let mut distribution = DISTRIBUTIONS
-        .load(deps.storage, id)
-        .map_err(|_| ContractError::DistributionNotFound { id })?;
-
-    // withdraw is only possible during the distribution period
-    ensure!(
-        !distribution.active_epoch.ends_at.is_expired(&env.block),
-        ContractError::RewardsAlreadyDistributed {}
-    );
-
-    // withdraw ends the epoch early
-    distribution.active_epoch.ends_at = match distribution.active_epoch.started_at {
-        Expiration::Never {} => Expiration::Never {},
-        Expiration::AtHeight(_) => Expiration::AtHeight(env.block.height),
-        Expiration::AtTime(_) => Expiration::AtTime(env.block.time),
-    };
+    let staker = deps.api.addr_validate(&staker)?;
+    let destination = CLAIM_DELEGATES
+        .may_load(deps.storage, (&staker, &info.sender))?
+        .ok_or_else(|| ContractError::NotClaimDelegate {
+            staker: staker.to_string(),
+            delegate: info.sender.to_string(),
+        })?;
+
+    Ok(claim(deps, env, &staker, destination.clone(), id)?
+        .add_attribute("staker", staker)
+        .add_attribute("delegate", info.sender)
+        .add_attribute("destination", destination))
+}
 
-    // get total rewards distributed based on newly updated ends_at
-    let rewards_distributed = distribution.get_total_rewards()?;
+/// the IDs claimed along with distribution `id`: the distribution itself and
+/// those of its pools that are neither frozen nor force withdrawn.
+fn get_claim_ids(storage: &dyn Storage, id: u64) -> StdResult<Vec<u64>> {
+    let mut ids = vec![id];
+    if let Some(distribution) = DISTRIBUTIONS.may_load(storage, id)? {
+        for pool_id in distribution.pools {
+            let pool = DISTRIBUTIONS.load(storage, pool_id)?;
+            if !pool.frozen && !pool.force_withdrawn {
+                ids.push(pool_id);
+            }
+        }
+    }
+    Ok(ids)
+}
 
-    let clawback_amount = distribution.funded_amount - rewards_distributed;
+/// claims `staker`'s rewards for a distribution and its pools and sends them to
+/// `recipient`, with a single transfer per denom. frozen and force withdrawn
+/// pools are skipped.
+fn claim(
+    mut deps: DepsMut,
+    env: Env,
+    staker: &Addr,
+    recipient: Addr,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let ids = get_claim_ids(deps.storage, id)?;
+    let claimed = take_pending_rewards(&mut deps, &env, staker, &ids)?
+        .into_iter()
+        .filter(|(_, claim_amount)| !claim_amount.is_zero())
+        .map(|(distribution, claim_amount)| (distribution.denom, claim_amount))
+        .collect::<Vec<_>>();
 
-    // remove withdrawn funds from amount funded since they are no longer funded
-    distribution.funded_amount = rewards_distributed;
+    // if there are no rewards to claim, error out
+    if claimed.is_empty() {
+        return Err(ContractError::NoRewardsClaimable {});
+    }
 
-    let clawback_msg = get_transfer_msg(
-        distribution.withdraw_destination.clone(),
-        clawback_amount,
-        distribution.denom.clone(),
-    )?;
+    // fold fully claimed, ended distributions out of the user's reward state
+    // so that it does not grow forever.
+    compact_user_rewards(&mut deps, &env, staker)?;
 
-    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+    let mut response = Response::new()
+        .add_attribute("action", "claim")
+        .add_attribute("id", id.to_string());
+    for (denom, amount) in claimed {
+        response = response
+            .add_attribute("denom", get_denom_string(&denom))
+            .add_attribute("amount_claimed", amount)
+            .add_message(get_transfer_msg(recipient.clone(), amount, denom)?);
+    }
 
-    Ok(Response::new()
-        .add_attribute("action", "withdraw")
-        .add_attribute("id", id.to_string())
-        .add_attribute("denom", distribution.get_denom_string())
-        .add_attribute("amount_withdrawn", clawback_amount)
-        .add_attribute("amount_distributed", rewards_distributed)
-        .add_message(clawback_msg))
+    Ok(response)
 }
 
-fn execute_update_owner(
-    deps: DepsMut,
-    info: MessageInfo,
+/// claims the sender's rewards from up to `limit` distributions after
+/// `start_after`, in ID order, sending a single transfer per denom. frozen and
+/// force withdrawn distributions and those with no pending rewards are
+/// skipped.
+fn execute_claim_all(
+    mut deps: DepsMut,
     env: Env,
-    action: cw_ownable::Action,
+    info: MessageInfo,
+    start_after: Option<u64>,
+    limit: Option<u32>,
 ) -> Result<Response, ContractError> {
     nonpayable(&info)?;
 
-    // Update the current contract owner. Note, this is a two step process, the
-    // new owner must accept this ownership transfer. First the owner specifies
-    // the new owner, then the new owner must accept.
-    let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
-    Ok(Response::new().add_attributes(ownership.into_attributes()))
-}
-
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<u64>::exclusive);
+    let distributions = DISTRIBUTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // claimed amounts keyed by denom string, so that transfers are sent in a
+    // deterministic order.
+    let mut claimed: BTreeMap<String, (Denom, Uint128)> = BTreeMap::new();
+    let mut claimed_ids = vec![];
+    for (id, distribution) in distributions {
+        if distribution.frozen || distribution.force_withdrawn {
+            continue;
+        }
+
+        let (distribution, claim_amount) =
+            take_pending_rewards(&mut deps, &env, &info.sender, &[id])?.remove(0);
+        if claim_amount.is_zero() {
+            continue;
+        }
+
+        let (_, amount) = claimed
+            .entry(distribution.get_denom_string())
+            .or_insert((distribution.denom, Uint128::zero()));
+        *amount = amount.checked_add(claim_amount)?;
+        claimed_ids.push(id.to_string());
+    }
+
+    if claimed.is_empty() {
+        return Err(ContractError::NoRewardsClaimable {});
+    }
+
+    compact_user_rewards(&mut deps, &env, &info.sender)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "claim_all")
+        .add_attribute("ids", claimed_ids.join(","));
+    for (denom_str, (denom, amount)) in claimed {
+        response = response
+            .add_message(get_transfer_msg(info.sender.clone(), amount, denom)?)
+            .add_attribute("denom", denom_str)
+            .add_attribute("amount_claimed", amount);
+    }
+
+    Ok(response)
+}
+
+/// claims the sender's rewards from each of `ids`, sending a single transfer
+/// per denom. IDs that cannot be claimed are reported and skipped, unless
+/// `strict` is set.
+fn execute_claim_many(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ids: Vec<u64>,
+    strict: bool,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    ensure!(
+        ids.len() <= MAX_LIMIT as usize,
+        ContractError::TooManyClaimIds { max: MAX_LIMIT }
+    );
+
+    // claimed amounts keyed by denom string, so that transfers are sent in a
+    // deterministic order.
+    let mut claimed: BTreeMap<String, (Denom, Uint128)> = BTreeMap::new();
+    let mut events = vec![];
+    for id in ids {
+        let event = Event::new("claim_many").add_attribute("id", id.to_string());
+
+        // like `claim`, a distribution is claimed along with its pools. a
+        // failed claim has not taken any rewards, only brought them up to
+        // date, so it may be skipped
+        let claim = get_claim_ids(deps.storage, id)
+            .map_err(ContractError::from)
+            .and_then(|claim_ids| take_pending_rewards(&mut deps, &env, &info.sender, &claim_ids))
+            .and_then(|claims| {
+                let claims = claims
+                    .into_iter()
+                    .filter(|(_, claim_amount)| !claim_amount.is_zero())
+                    .collect::<Vec<_>>();
+                ensure!(!claims.is_empty(), ContractError::NoRewardsClaimable {});
+                Ok(claims)
+            });
+        let claims = match claim {
+            Ok(claims) => claims,
+            Err(err) if !strict => {
+                events.push(
+                    event
+                        .add_attribute("status", "failed")
+                        .add_attribute("error", err.to_string()),
+                );
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut event = event.add_attribute("status", "claimed");
+        for (distribution, claim_amount) in claims {
+            event = event
+                .add_attribute("denom", distribution.get_denom_string())
+                .add_attribute("amount_claimed", claim_amount);
+            let (_, amount) = claimed
+                .entry(distribution.get_denom_string())
+                .or_insert((distribution.denom, Uint128::zero()));
+            *amount = amount.checked_add(claim_amount)?;
+        }
+        events.push(event);
+    }
+
+    if claimed.is_empty() {
+        return Err(ContractError::NoRewardsClaimable {});
+    }
+
+    compact_user_rewards(&mut deps, &env, &info.sender)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "claim_many")
+        .add_events(events);
+    for (denom_str, (denom, amount)) in claimed {
+        response = response
+            .add_message(get_transfer_msg(info.sender.clone(), amount, denom)?)
+            .add_attribute("denom", denom_str)
+            .add_attribute("amount_claimed", amount);
+    }
+
+    Ok(response)
+}
+
+/// updates `staker`'s rewards for each of `ids` and takes their pending
+/// rewards, recording them as claimed. returns each updated distribution and
+/// the amount claimed from it, which may be zero. every check that may fail is
+/// made before any rewards are taken, so an error leaves the user's pending
+/// rewards untouched, though they may have been brought up to date.
+fn take_pending_rewards(
+    deps: &mut DepsMut,
+    env: &Env,
+    staker: &Addr,
+    ids: &[u64],
+) -> Result<Vec<(DistributionState, Uint128)>, ContractError> {
+    // update the distributions for the staker. this updates the distribution
+    // states and the user reward state.
+    for &id in ids {
+        update_rewards(deps, env, staker, id)?;
+    }
+
+    // load the updated states. previous `update_rewards` calls ensure that
+    // these states exist.
+    let mut user_reward_state = USER_REWARDS.load(deps.storage, staker.clone())?;
+    let mut claims = vec![];
+    for &id in ids {
+        let distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+        // updating the map returns the previous value if it existed. we set
+        // the value to zero and get the amount of pending rewards until this
+        // point.
+        let claim_amount = user_reward_state
+            .pending_rewards
+            .insert(id, Uint128::zero())
+            .unwrap_or_default();
+        claims.push((distribution, claim_amount));
+    }
+    if claims
+        .iter()
+        .all(|(_, claim_amount)| claim_amount.is_zero())
+    {
+        return Ok(claims);
+    }
+
+    // the claimed totals are all that is left that may fail to update, so
+    // check them before saving anything.
+    let mut denom_claimed: BTreeMap<String, Uint128> = BTreeMap::new();
+    for (distribution, claim_amount) in &claims {
+        let claimed = denom_claimed
+            .entry(distribution.get_denom_string())
+            .or_default();
+        *claimed = claimed.checked_add(*claim_amount)?;
+        if let Some(totals) = DISTRIBUTION_TOTALS.may_load(deps.storage, distribution.id)? {
+            totals.claimed.checked_add(*claim_amount)?;
+        }
+    }
+    for (denom, claimed) in denom_claimed {
+        if let Some(totals) = DENOM_TOTALS.may_load(deps.storage, denom)? {
+            totals.claimed.checked_add(claimed)?;
+        }
+    }
+
+    // reflect the updated user reward state and record the claimed rewards
+    USER_REWARDS.save(deps.storage, staker.clone(), &user_reward_state)?;
+    for (distribution, claim_amount) in &claims {
+        if claim_amount.is_zero() {
+            continue;
+        }
+        let id = distribution.id;
+        ClaimRecord::save(deps.storage, &env.block, staker, id, *claim_amount)?;
+        if !DISTRIBUTION_CLAIMANTS.has(deps.storage, (id, staker)) {
+            DISTRIBUTION_CLAIMANTS.save(deps.storage, (id, staker), &Empty {})?;
+            DISTRIBUTION_CLAIMANT_COUNTS.update(deps.storage, id, |count| -> StdResult<_> {
+                Ok(count.unwrap_or_default() + 1)
+            })?;
+        }
+
+        DenomTotalsState::update_for_distribution(deps.storage, distribution, |totals| {
+            totals.claimed = totals.claimed.checked_add(*claim_amount)?;
+            Ok(())
+        })?;
+    }
+
+    Ok(claims)
+}
+
+/// withdraws the undistributed rewards for a distribution. members can claim
+/// whatever they earned until this point. this is effectively an inverse to
+/// fund and does not affect any already-distributed rewards. can only be called
+/// by the admin and only during the distribution period. updates the period
+/// finish expiration to the current block.
+fn execute_withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // only the owner or manager can initiate a withdraw
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    let mut distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn { id }
+    );
+
+    // withdraw ends the epoch at the current block, which must not be before
+    // a height that has already been accounted for. frozen distributions may
+    // still be withdrawn from so the owner can recover undistributed funds.
+    distribution.check_height(&env.block)?;
+
+    if let EmissionRate::LinearPerpetual { .. } = distribution.active_epoch.emission_rate {
+        // perpetual distributions have no end, so emit rewards up to the
+        // current block and withdraw the rest, pausing the distribution until
+        // it is funded again.
+        distribution.update_active_epoch(deps.as_ref(), &env.block)?;
+        distribution.active_epoch.bump_last_updated(&env.block)?;
+    } else if distribution.active_epoch.ends_at.is_expired(&env.block) {
+        // once the distribution period is over, only rewards withheld from
+        // users that reached the per-user cap remain to be withdrawn
+        ensure!(
+            !distribution.capped_rewards.is_zero(),
+            ContractError::RewardsAlreadyDistributed {}
+        );
+    } else {
+        // withdraw ends the epoch early
+        distribution.active_epoch.ends_at = match distribution.active_epoch.started_at {
+            Expiration::Never {} => Expiration::Never {},
+            Expiration::AtHeight(_) => Expiration::AtHeight(env.block.height),
+            Expiration::AtTime(_) => Expiration::AtTime(env.block.time),
+        };
+    }
+
+    // get total rewards distributed based on newly updated ends_at
+    let rewards_distributed = distribution.get_total_rewards()?;
+
+    let clawback_amount = (distribution.funded_amount - rewards_distributed)
+        .checked_add(distribution.capped_rewards)?;
+
+    // remove withdrawn funds from amount funded since they are no longer funded
+    distribution.funded_amount = rewards_distributed;
+    distribution.capped_rewards = Uint128::zero();
+
+    let clawback_msg = get_transfer_msg(
+        distribution.withdraw_destination.clone(),
+        clawback_amount,
+        distribution.denom.clone(),
+    )?;
+
+    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+
+    DenomTotalsState::update_for_distribution(deps.storage, &distribution, |totals| {
+        totals.withdrawn = totals.withdrawn.checked_add(clawback_amount)?;
+        Ok(())
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "withdraw")
+        .add_attribute("id", id.to_string())
+        .add_attribute("denom", distribution.get_denom_string())
+        .add_attribute("amount_withdrawn", clawback_amount)
+        .add_attribute("amount_distributed", rewards_distributed)
+        .add_message(clawback_msg))
+}
+
+/// withdraws every fund held for a distribution once its unclaimed rewards
+/// have expired, including rewards distributed but never claimed. the funds
+/// held are those funded less those claimed, withdrawn, and paid to keepers,
+/// so they can only be known for distributions whose funds have been tracked
+/// since they were created. users forfeit their unclaimed rewards, and the
+/// distribution no longer accrues, pays out, or accepts funds.
+fn execute_force_withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // only the owner can seize unclaimed rewards
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn { id }
+    );
+    ensure!(
+        distribution.active_epoch.ends_at.is_expired(&env.block),
+        ContractError::DistributionNotEnded { id }
+    );
+    // the distribution may have been extended since its expiry was set, so
+    // members must still have had the minimum window to claim since it ended
+    ensure!(
+        distribution.unclaimed_expiry.is_expired(&env.block)
+            && distribution
+                .earliest_unclaimed_expiry()?
+                .is_expired(&env.block),
+        ContractError::UnclaimedRewardsNotExpired { id }
+    );
+
+    let clawback_amount = DISTRIBUTION_TOTALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::UntrackedDistributionFunds { id })?
+        .get_pending()?;
+
+    distribution.force_withdrawn = true;
+    distribution.capped_rewards = Uint128::zero();
+    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+
+    DenomTotalsState::update_for_distribution(deps.storage, &distribution, |totals| {
+        totals.withdrawn = totals.withdrawn.checked_add(clawback_amount)?;
+        Ok(())
+    })?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "force_withdraw")
+        .add_attribute("id", id.to_string())
+        .add_attribute("denom", distribution.get_denom_string())
+        .add_attribute("amount_withdrawn", clawback_amount);
+    if !clawback_amount.is_zero() {
+        response = response.add_message(get_transfer_msg(
+            distribution.withdraw_destination,
+            clawback_amount,
+            distribution.denom,
+        )?);
+    }
+
+    Ok(response)
+}
+
+/// sets or removes the fee paid to keepers for bringing a distribution's
+/// rewards up to date
+fn execute_update_keeper_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+    keeper_fee: Option<KeeperFee>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // keeper fees are paid out of the distribution's funds, so only the owner
+    // may set them
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+
+    if let Some(keeper_fee) = &keeper_fee {
+        let interval_is_zero = match keeper_fee.interval {
+            Duration::Height(h) => h == 0,
+            Duration::Time(t) => t == 0,
+        };
+        ensure!(
+            !keeper_fee.amount.is_zero() && !interval_is_zero,
+            ContractError::InvalidKeeperFee {}
+        );
+    }
+    distribution.keeper_fee = keeper_fee;
+
+    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_keeper_fee")
+        .add_attribute("id", id.to_string())
+        .add_attribute("denom", distribution.get_denom_string()))
+}
+
+/// sets or removes the keepers allowed to sync members' voting power with
+/// `UpdateMembers`
+fn execute_update_member_updaters(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+    updaters: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // only the owner or manager can update a distribution
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    let mut distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+    if let Some(parent_id) = distribution.pool_of {
+        return Err(ContractError::PoolConfigFromParent { id: parent_id });
+    }
+
+    distribution.member_updaters = updaters
+        .map(|updaters| {
+            updaters
+                .iter()
+                .map(|updater| deps.api.addr_validate(updater))
+                .collect::<StdResult<Vec<_>>>()
+        })
+        .transpose()?;
+
+    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_member_updaters")
+        .add_attribute("id", id.to_string())
+        .add_attribute(
+            "member_updaters",
+            distribution
+                .member_updaters
+                .map(|updaters| updaters.len().to_string())
+                .unwrap_or_else(|| "anyone".to_string()),
+        ))
+}
+
+/// brings a distribution's rewards up to date, paying the caller the keeper
+/// fee if one is set, its interval has passed, and the update accounted for
+/// newly emitted rewards. linear distributions are only updated once a
+/// complete emission period has passed since the last update.
+fn execute_poke(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn { id }
+    );
+    ensure!(
+        !distribution.frozen,
+        ContractError::DistributionFrozen { id }
+    );
+    distribution.check_height(&env.block)?;
+
+    let updated = distribution.catch_up_active_epoch(deps.as_ref(), &env.block)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "poke")
+        .add_attribute("id", id.to_string())
+        .add_attribute("denom", distribution.get_denom_string());
+
+    let fee_payable = match distribution.keeper_fee_next_payable {
+        Some(next_payable) => next_payable.is_expired(&env.block),
+        None => true,
+    };
+    let keeper_fee = match distribution.keeper_fee.clone() {
+        Some(keeper_fee)
+            if updated
+                && fee_payable
+                && distribution.take_undistributed(&env.block, keeper_fee.amount)? =>
+        {
+            distribution.keeper_fee_next_payable = Some(keeper_fee.interval.after(&env.block));
+            response = response.add_message(get_transfer_msg(
+                info.sender,
+                keeper_fee.amount,
+                distribution.denom.clone(),
+            )?);
+            DenomTotalsState::update_for_distribution(deps.storage, &distribution, |totals| {
+                totals.keeper_fees = totals.keeper_fees.checked_add(keeper_fee.amount)?;
+                Ok(())
+            })?;
+            keeper_fee.amount
+        }
+        _ => Uint128::zero(),
+    };
+
+    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+
+    Ok(response.add_attribute("keeper_fee", keeper_fee))
+}
+
+/// registers a schedule that funds a distribution in installments. the first
+/// installment may be funded immediately.
+fn execute_create_funding_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    amount: Uint128,
+    interval: Duration,
+    source: Option<String>,
+) -> Result<Response, ContractError> {
+    // only the owner or manager can schedule funding for a distribution
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    let distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn { id }
+    );
+    ensure!(
+        !FUNDING_SCHEDULES.has(deps.storage, id),
+        ContractError::FundingScheduleExists { id }
+    );
+    ensure!(
+        !amount.is_zero() && get_duration_scalar(&interval) != 0,
+        ContractError::InvalidFundingSchedule {}
+    );
+
+    let source = get_funding_source(deps.as_ref(), &info, &distribution.denom, source)?;
+
+    let schedule = FundingSchedule {
+        id,
+        source,
+        amount,
+        interval,
+        next_funding: match interval {
+            Duration::Height(_) => Expiration::AtHeight(env.block.height),
+            Duration::Time(_) => Expiration::AtTime(env.block.time),
+        },
+    };
+    FUNDING_SCHEDULES.save(deps.storage, id, &schedule)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_funding_schedule")
+        .add_attribute("id", id.to_string())
+        .add_attribute("denom", distribution.get_denom_string())
+        .add_attribute("amount", amount))
+}
+
+/// removes a distribution's funding schedule, returning any escrowed tokens to
+/// the distribution's withdraw destination
+fn execute_remove_funding_schedule(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // only the owner or manager can remove a distribution's funding schedule
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    let schedule = FUNDING_SCHEDULES
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::FundingScheduleNotFound { id })?;
+    FUNDING_SCHEDULES.remove(deps.storage, id);
+
+    let response = Response::new()
+        .add_attribute("action", "remove_funding_schedule")
+        .add_attribute("id", id.to_string());
+
+    return_escrow(deps.storage, id, schedule.source, response)
+}
+
+/// registers a rollover that starts a new epoch of a linear or decaying
+/// distribution each time it ends.
+fn execute_create_rollover(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+    top_up: Uint128,
+    max_rollovers: u32,
+    source: Option<String>,
+) -> Result<Response, ContractError> {
+    // only the owner or manager can roll over a distribution
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    let distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn { id }
+    );
+    ensure!(
+        matches!(
+            distribution.active_epoch.emission_rate,
+            EmissionRate::Linear { .. } | EmissionRate::Decay { .. }
+        ),
+        ContractError::RolloverRequiresEndingEmission {}
+    );
+    ensure!(
+        !ROLLOVERS.has(deps.storage, id),
+        ContractError::RolloverExists { id }
+    );
+    ensure!(
+        !top_up.is_zero() && max_rollovers != 0,
+        ContractError::InvalidRollover {}
+    );
+
+    let source = get_funding_source(deps.as_ref(), &info, &distribution.denom, source)?;
+
+    let rollover = Rollover {
+        id,
+        source,
+        top_up,
+        remaining: max_rollovers,
+        count: 0,
+    };
+    ROLLOVERS.save(deps.storage, id, &rollover)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_rollover")
+        .add_attribute("id", id.to_string())
+        .add_attribute("denom", distribution.get_denom_string())
+        .add_attribute("top_up", top_up)
+        .add_attribute("max_rollovers", max_rollovers.to_string()))
+}
+
+/// removes a distribution's rollover, returning any escrowed tokens to the
+/// distribution's withdraw destination
+fn execute_remove_rollover(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // only the owner or manager can remove a distribution's rollover
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    let rollover = ROLLOVERS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::RolloverNotFound { id })?;
+    ROLLOVERS.remove(deps.storage, id);
+
+    let response = Response::new()
+        .add_attribute("action", "remove_rollover")
+        .add_attribute("id", id.to_string());
+
+    return_escrow(deps.storage, id, rollover.source, response)
+}
+
+/// gets where a distribution's installments or top-ups are taken from: the
+/// cw20 allowance `source` granted this contract for cw20 distributions, and
+/// the tokens sent with the message for native ones. only the owner may draw
+/// on an allowance granted by someone else, so that a distribution's manager
+/// can't route the owner's or a third party's allowance into their
+/// distribution.
+fn get_funding_source(
+    deps: Deps,
+    info: &MessageInfo,
+    denom: &Denom,
+    source: Option<String>,
+) -> Result<FundingSource, ContractError> {
+    match (denom, source) {
+        (Denom::Cw20(_), Some(owner)) => {
+            nonpayable(info)?;
+            let owner = deps.api.addr_validate(&owner)?;
+            if owner != info.sender {
+                cw_ownable::assert_owner(deps.storage, &info.sender)?;
+            }
+            Ok(FundingSource::Allowance { owner })
+        }
+        (Denom::Native(denom), None) => Ok(FundingSource::Escrow {
+            remaining: must_pay(info, denom).map_err(|_| ContractError::InvalidFunds {})?,
+        }),
+        _ => Err(ContractError::InvalidFundingSource {}),
+    }
+}
+
+/// sends any tokens still escrowed by a removed funding source to distribution
+/// `id`'s withdraw destination
+fn return_escrow(
+    storage: &dyn Storage,
+    id: u64,
+    source: FundingSource,
+    response: Response,
+) -> Result<Response, ContractError> {
+    match source {
+        FundingSource::Escrow { remaining } if !remaining.is_zero() => {
+            let distribution = DISTRIBUTIONS.load(storage, id)?;
+            Ok(response
+                .add_message(get_transfer_msg(
+                    distribution.withdraw_destination,
+                    remaining,
+                    distribution.denom,
+                )?)
+                .add_attribute("amount_returned", remaining))
+        }
+        _ => Ok(response),
+    }
+}
+
+/// gets the amount of `amount` that can be taken from `source` for a
+/// distribution of `denom`, along with the (token, owner) allowance it is
+/// drawn from for cw20 sources. `drawn` holds the cw20 tokens already drawn
+/// from each allowance.
+fn get_installment(
+    deps: Deps,
+    env: &Env,
+    source: &FundingSource,
+    denom: &Denom,
+    amount: Uint128,
+    drawn: &HashMap<(Addr, Addr), Uint128>,
+) -> StdResult<(Uint128, Option<(Addr, Addr)>)> {
+    Ok(match (source, denom) {
+        (FundingSource::Escrow { remaining }, _) => (min(amount, *remaining), None),
+        (FundingSource::Allowance { owner }, Denom::Cw20(token)) => {
+            let key = (token.clone(), owner.clone());
+            let pullable = get_cw20_pullable(deps, env, token, owner)?
+                .saturating_sub(drawn.get(&key).copied().unwrap_or_default());
+            if pullable < amount {
+                (Uint128::zero(), None)
+            } else {
+                (amount, Some(key))
+            }
+        }
+        (FundingSource::Allowance { .. }, Denom::Native(_)) => (Uint128::zero(), None),
+    })
+}
+
+/// takes an installment of `amount` from `source`, returning the message that
+/// transfers it from the cw20 allowance it is drawn from, if any.
+fn take_installment(
+    env: &Env,
+    source: &mut FundingSource,
+    amount: Uint128,
+    allowance: Option<(Addr, Addr)>,
+    drawn: &mut HashMap<(Addr, Addr), Uint128>,
+) -> StdResult<Option<WasmMsg>> {
+    if let FundingSource::Escrow { ref mut remaining } = source {
+        *remaining -= amount;
+    }
+    let Some((token, owner)) = allowance else {
+        return Ok(None);
+    };
+    let msg = WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+            owner: owner.to_string(),
+            recipient: env.contract.address.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    };
+    *drawn.entry((token, owner)).or_default() += amount;
+    Ok(Some(msg))
+}
+
+/// funds the distribution of a due funding schedule with one installment,
+/// returning the response of the funding with the message that transfers the
+/// installment, if any, or `None` if the installment cannot currently be
+/// funded. `drawn` is only updated if the distribution is funded.
+fn tick_schedule(
+    mut deps: DepsMut,
+    env: &Env,
+    mut schedule: FundingSchedule,
+    drawn: &mut HashMap<(Addr, Addr), Uint128>,
+) -> Result<Option<Response>, ContractError> {
+    let id = schedule.id;
+    let distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+
+    let (amount, allowance) = get_installment(
+        deps.as_ref(),
+        env,
+        &schedule.source,
+        &distribution.denom,
+        schedule.amount,
+        drawn,
+    )?;
+    if amount.is_zero() {
+        return Ok(None);
+    }
+
+    let mut schedule_drawn = drawn.clone();
+    let msg = take_installment(
+        env,
+        &mut schedule.source,
+        amount,
+        allowance,
+        &mut schedule_drawn,
+    )?;
+    let fund = execute_fund(deps.branch(), env.clone(), distribution, amount)?;
+    schedule.next_funding = schedule.interval.after(&env.block);
+    FUNDING_SCHEDULES.save(deps.storage, id, &schedule)?;
+    *drawn = schedule_drawn;
+
+    Ok(Some(fund.add_messages(msg)))
+}
+
+/// funds every distribution in a page of funding schedules that is due with
+/// one installment, then rolls over every distribution in a separate page of
+/// rollovers whose epoch has ended. schedules and rollovers that cannot
+/// currently be funded, because their escrow is exhausted or their source's
+/// cw20 balance or allowance is too low, are skipped until a later tick.
+/// schedules and rollovers that fail, e.g. because their distribution is
+/// frozen, are skipped and reported without reverting the others.
+fn execute_tick(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    schedules: TickPage,
+    rollovers: TickPage,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let limit = schedules.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let schedules = FUNDING_SCHEDULES
+        .range(
+            deps.storage,
+            schedules.start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, schedule)| schedule))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // cw20 tokens already drawn from each (token, owner) allowance by this tick
+    let mut drawn: HashMap<(Addr, Addr), Uint128> = HashMap::new();
+
+    let mut response = Response::new().add_attribute("action", "tick");
+    if schedules.len() == limit {
+        if let Some(last) = schedules.last() {
+            response = response.add_attribute("schedules_start_after", last.id.to_string());
+        }
+    }
+    for schedule in schedules {
+        if !schedule.next_funding.is_expired(&env.block) {
+            continue;
+        }
+        let id = schedule.id;
+        match tick_schedule(deps.branch(), &env, schedule, &mut drawn) {
+            Ok(Some(funded)) => {
+                response = response
+                    .add_attributes(funded.attributes)
+                    .add_submessages(funded.messages);
+            }
+            Ok(None) => response = response.add_attribute("skipped", id.to_string()),
+            Err(err) => response = response.add_event(tick_failed_event(id, err)),
+        }
+    }
+
+    let limit = rollovers.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let rollovers = ROLLOVERS
+        .range(
+            deps.storage,
+            rollovers.start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, rollover)| rollover))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if rollovers.len() == limit {
+        if let Some(last) = rollovers.last() {
+            response = response.add_attribute("rollovers_start_after", last.id.to_string());
+        }
+    }
+    for rollover in rollovers {
+        let id = rollover.id;
+        match tick_rollover(deps.branch(), &env, rollover, &mut drawn) {
+            Ok(rolled) => {
+                response = response
+                    .add_attributes(rolled.attributes)
+                    .add_submessages(rolled.messages)
+                    .add_events(rolled.events);
+            }
+            Err(err) => response = response.add_event(tick_failed_event(id, err)),
+        }
+    }
+
+    Ok(response)
+}
+
+/// rolls a distribution over into a new epoch if its rollover is due,
+/// returning a response with the rollover event and the message that
+/// transfers the top-up, if any. the response is empty if the rollover is not
+/// due, and has a `skipped` attribute if it cannot currently be topped up.
+/// `drawn` is only updated if the distribution is rolled over.
+fn tick_rollover(
+    deps: DepsMut,
+    env: &Env,
+    mut rollover: Rollover,
+    drawn: &mut HashMap<(Addr, Addr), Uint128>,
+) -> Result<Response, ContractError> {
+    let id = rollover.id;
+    let mut distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+    if rollover.remaining == 0
+        || distribution.force_withdrawn
+        || distribution.funded_amount.is_zero()
+        || !distribution.is_ended(&env.block)
+    {
+        return Ok(Response::new());
+    }
+
+    let (top_up, allowance) = get_installment(
+        deps.as_ref(),
+        env,
+        &rollover.source,
+        &distribution.denom,
+        rollover.top_up,
+        drawn,
+    )?;
+    if top_up.is_zero() || distribution.frozen || distribution.check_height(&env.block).is_err() {
+        return Ok(Response::new().add_attribute("skipped", id.to_string()));
+    }
+
+    let mut rollover_drawn = drawn.clone();
+    let msg = take_installment(
+        env,
+        &mut rollover.source,
+        top_up,
+        allowance,
+        &mut rollover_drawn,
+    )?;
+    let residual = distribution.roll_over(deps.as_ref(), &env.block, top_up)?;
+    DenomTotalsState::update_for_distribution(deps.storage, &distribution, |totals| {
+        totals.funded = totals.funded.checked_add(top_up)?;
+        Ok(())
+    })?;
+    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+    rollover.remaining -= 1;
+    rollover.count += 1;
+    ROLLOVERS.save(deps.storage, id, &rollover)?;
+    *drawn = rollover_drawn;
+
+    Ok(Response::new().add_messages(msg).add_event(
+        Event::new("rollover")
+            .add_attribute("id", id.to_string())
+            .add_attribute("denom", distribution.get_denom_string())
+            .add_attribute("rollover", rollover.count.to_string())
+            .add_attribute("residual", residual)
+            .add_attribute("top_up", top_up)
+            .add_attribute("ends_at", distribution.active_epoch.ends_at.to_string()),
+    ))
+}
+
+/// the event reporting that a funding schedule or rollover failed in a tick
+fn tick_failed_event(id: u64, err: ContractError) -> Event {
+    Event::new("tick_failed")
+        .add_attribute("id", id.to_string())
+        .add_attribute("error", err.to_string())
+}
+
+/// unfreezes a distribution that was frozen because the block height moved
+/// before a height it had already accounted for. only possible once the chain
+/// has caught back up to that height.
+fn execute_unfreeze(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // only the owner or manager can unfreeze a distribution
+    assert_owner_or_manager(deps.storage, &info.sender, id)?;
+
+    let mut distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+
+    distribution.check_height(&env.block)?;
+    distribution.frozen = false;
+
+    DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "unfreeze")
+        .add_attribute("id", id.to_string()))
+}
+
+/// transfers, accepts, or removes management of a single distribution
+fn execute_update_manager(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    action: ManagerAction,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    if !DISTRIBUTIONS.has(deps.storage, id) {
+        return Err(ContractError::DistributionNotFound { id });
+    }
+
+    let current = DISTRIBUTION_MANAGERS
+        .may_load(deps.storage, id)?
+        .unwrap_or_default();
+
+    let updated = match action {
+        ManagerAction::TransferManagement {
+            new_manager,
+            expiry,
+        } => {
+            assert_owner_or_manager(deps.storage, &info.sender, id)?;
+            DistributionManager {
+                manager: current.manager,
+                pending_manager: Some(deps.api.addr_validate(&new_manager)?),
+                pending_expiry: expiry,
+            }
+        }
+        ManagerAction::AcceptManagement {} => {
+            let Some(pending_manager) = current.pending_manager else {
+                return Err(ContractError::ManagerTransferNotFound { id });
+            };
+            ensure!(
+                pending_manager == info.sender,
+                ContractError::NotPendingManager { id }
+            );
+            if let Some(expiry) = current.pending_expiry {
+                ensure!(
+                    !expiry.is_expired(&env.block),
+                    ContractError::ManagerTransferExpired { id }
+                );
+            }
+            DistributionManager {
+                manager: Some(pending_manager),
+                pending_manager: None,
+                pending_expiry: None,
+            }
+        }
+        ManagerAction::RemoveManager {} => {
+            assert_owner_or_manager(deps.storage, &info.sender, id)?;
+            DistributionManager::default()
+        }
+    };
+
+    DISTRIBUTION_MANAGERS.save(deps.storage, id, &updated)?;
+
+    let none_or = |addr: &Option<Addr>| addr.as_ref().map_or("none".to_string(), Addr::to_string);
+    Ok(Response::new()
+        .add_attribute("action", "update_manager")
+        .add_attribute("id", id.to_string())
+        .add_attribute("manager", none_or(&updated.manager))
+        .add_attribute("pending_manager", none_or(&updated.pending_manager)))
+}
+
+fn execute_update_owner(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    action: cw_ownable::Action,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // Update the current contract owner. Note, this is a two step process, the
+    // new owner must accept this ownership transfer. First the owner specifies
+    // the new owner, then the new owner must accept.
+    let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+    Ok(Response::new().add_attributes(ownership.into_attributes()))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -496,13 +2073,80 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         )?)?),
-        QueryMsg::Distribution { id } => {
-            let state = DISTRIBUTIONS.load(deps.storage, id)?;
-            Ok(to_json_binary(&state)?)
-        }
+        QueryMsg::Distribution { id } => Ok(to_json_binary(&query_distribution(
+            deps,
+            &env,
+            DISTRIBUTIONS.load(deps.storage, id)?,
+        )?)?),
         QueryMsg::Distributions { start_after, limit } => Ok(to_json_binary(
-            &query_distributions(deps, start_after, limit)?,
+            &query_distributions(deps, env, start_after, limit)?,
+        )?),
+        QueryMsg::Health { id } => Ok(to_json_binary(&query_health(deps, env, id)?)?),
+        QueryMsg::SimulateUpdate { id, emission_rate } => Ok(to_json_binary(
+            &query_simulate_update(deps, env, id, emission_rate)?,
+        )?),
+        QueryMsg::HookCallers { id } => Ok(to_json_binary(&query_hook_callers(deps, id)?)?),
+        QueryMsg::HookSequence { caller } => {
+            let caller = deps.api.addr_validate(&caller)?;
+            Ok(to_json_binary(
+                &HOOK_SEQUENCES
+                    .may_load(deps.storage, caller)?
+                    .unwrap_or_default(),
+            )?)
+        }
+        QueryMsg::DistributionManager { id } => {
+            Ok(to_json_binary(&query_distribution_manager(deps, id)?)?)
+        }
+        QueryMsg::ClaimDelegates {
+            staker,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&query_claim_delegates(
+            deps,
+            staker,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::TotalsByDenom { start_after, limit } => Ok(to_json_binary(
+            &query_totals_by_denom(deps, start_after, limit)?,
+        )?),
+        QueryMsg::FundingSchedule { id } => {
+            to_json_binary(&FUNDING_SCHEDULES.load(deps.storage, id)?)
+        }
+        QueryMsg::FundingSchedules { start_after, limit } => Ok(to_json_binary(
+            &query_funding_schedules(deps, start_after, limit)?,
+        )?),
+        QueryMsg::Rollover { id } => to_json_binary(&ROLLOVERS.load(deps.storage, id)?),
+        QueryMsg::Rollovers { start_after, limit } => {
+            Ok(to_json_binary(&query_rollovers(deps, start_after, limit)?)?)
+        }
+        QueryMsg::ClaimHistory {
+            address,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&query_claim_history(
+            deps,
+            address,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::UndistributedRewards { start_after, limit } => Ok(to_json_binary(
+            &query_undistributed_rewards(deps, env, start_after, limit)?,
+        )?),
+        QueryMsg::DistributionStats { start_after, limit } => Ok(to_json_binary(
+            &query_distribution_stats(deps, env, start_after, limit)?,
         )?),
+        QueryMsg::Raffle { id } => to_json_binary(&RAFFLES.load(deps.storage, id)?),
+        QueryMsg::RaffleDraws {
+            id,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&query_raffle_draws(
+            deps,
+            id,
+            start_after,
+            limit,
+        )?)?),
     }
 }
 
@@ -540,6 +2184,17 @@ fn query_pending_rewards(
 
     // iterate over all distributions and calculate pending rewards for the user
     for (id, distribution) in distributions {
+        // users forfeit their unclaimed rewards from force withdrawn
+        // distributions
+        if distribution.force_withdrawn {
+            pending_rewards.push(DistributionPendingRewards {
+                id,
+                denom: distribution.denom,
+                pending_rewards: Uint128::zero(),
+            });
+            continue;
+        }
+
         // first we get the active epoch earned puvp value
         let active_total_earned_puvp =
             get_active_total_earned_puvp(deps, &env.block, &distribution)?;
@@ -554,14 +2209,17 @@ fn query_pending_rewards(
             .cloned()
             .unwrap_or_default();
 
-        let unaccounted_for_rewards = get_accrued_rewards_not_yet_accounted_for(
-            deps,
-            &env,
-            &addr,
-            total_earned_puvp,
+        let unaccounted_for_rewards = user_reward_state.apply_reward_cap(
             &distribution,
-            &user_reward_state,
-        )?;
+            get_accrued_rewards_not_yet_accounted_for(
+                deps,
+                &env,
+                &addr,
+                total_earned_puvp,
+                &distribution,
+                &user_reward_state,
+            )?,
+        );
 
         pending_rewards.push(DistributionPendingRewards {
             id,
@@ -573,8 +2231,21 @@ fn query_pending_rewards(
     Ok(PendingRewardsResponse { pending_rewards })
 }
 
+/// returns a distribution along with its normalized emission rate.
+fn query_distribution(
+    deps: Deps,
+    env: &Env,
+    distribution: DistributionState,
+) -> StdResult<DistributionResponse> {
+    Ok(DistributionResponse {
+        normalized_emission: distribution.get_normalized_emission(deps, &env.block)?,
+        state: distribution,
+    })
+}
+
 fn query_distributions(
     deps: Deps,
+    env: Env,
     start_after: Option<u64>,
     limit: Option<u32>,
 ) -> StdResult<DistributionsResponse> {
@@ -584,12 +2255,350 @@ fn query_distributions(
     let distributions = DISTRIBUTIONS
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
-        .map(|item| item.map(|(_, v)| v))
+        .map(|item| query_distribution(deps, &env, item?.1))
         .collect::<StdResult<Vec<_>>>()?;
 
     Ok(DistributionsResponse { distributions })
 }
 
+fn query_distribution_manager(deps: Deps, id: u64) -> StdResult<DistributionManager> {
+    // ensure the distribution exists
+    DISTRIBUTIONS.load(deps.storage, id)?;
+
+    Ok(DISTRIBUTION_MANAGERS
+        .may_load(deps.storage, id)?
+        .unwrap_or_default())
+}
+
+fn query_hook_callers(deps: Deps, id: u64) -> StdResult<HookCallersResponse> {
+    let distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+
+    // the hook_caller and those of the voting power sources are authorized to
+    // call every type of hook.
+    let callers = |hook_type: HookType| -> Vec<Addr> {
+        distribution
+            .get_all_hook_callers()
+            .into_iter()
+            .chain(distribution.hook_callers.get(hook_type).iter().cloned())
+            .collect()
+    };
+
+    Ok(HookCallersResponse {
+        stake: callers(HookType::Stake),
+        nft_stake: callers(HookType::NftStake),
+        membership: callers(HookType::Membership),
+    })
+}
+
+fn query_claim_delegates(
+    deps: Deps,
+    staker: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ClaimDelegatesResponse> {
+    let staker = deps.api.addr_validate(&staker)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let delegates = CLAIM_DELEGATES
+        .prefix(&staker)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(delegate, destination)| ClaimDelegate {
+                delegate,
+                destination,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ClaimDelegatesResponse { delegates })
+}
+
+fn query_totals_by_denom(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TotalsByDenomResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<String>::exclusive);
+
+    let totals = DENOM_TOTALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, totals) = item?;
+            Ok(DenomTotals {
+                pending: totals.get_pending()?,
+                denom: totals.denom,
+                funded: totals.funded,
+                claimed: totals.claimed,
+                withdrawn: totals.withdrawn,
+                keeper_fees: totals.keeper_fees,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TotalsByDenomResponse { totals })
+}
+
+fn query_funding_schedules(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<FundingSchedulesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<u64>::exclusive);
+
+    let schedules = FUNDING_SCHEDULES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, schedule)| schedule))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(FundingSchedulesResponse { schedules })
+}
+
+fn query_rollovers(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RolloversResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<u64>::exclusive);
+
+    let rollovers = ROLLOVERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, rollover)| rollover))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RolloversResponse { rollovers })
+}
+
+fn query_claim_history(
+    deps: Deps,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ClaimHistoryResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<u64>::exclusive);
+
+    let claims = claim_history()
+        .idx
+        .address
+        .prefix(address)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, claim)| claim))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ClaimHistoryResponse { claims })
+}
+
+fn query_undistributed_rewards(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<UndistributedRewardsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<u64>::exclusive);
+
+    let distributions = DISTRIBUTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (id, distribution) = item?;
+            Ok(UndistributedRewards {
+                id,
+                undistributed: distribution.get_undistributed(deps, &env.block)?,
+                denom: distribution.denom,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(UndistributedRewardsResponse { distributions })
+}
+
+/// returns the stats of distributions whose funds have been tracked since
+/// they were created. the amount distributed is derived from the running
+/// totals and the funds not yet emitted, so that it does not need to be
+/// recomputed from every claim.
+fn query_distribution_stats(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DistributionStatsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<u64>::exclusive);
+
+    let stats = DISTRIBUTION_TOTALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (id, totals) = item?;
+            let distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+            let undistributed = distribution.get_undistributed(deps, &env.block)?;
+            Ok(DistributionStats {
+                id,
+                funded: totals.funded,
+                distributed: totals
+                    .funded
+                    .checked_sub(totals.withdrawn)?
+                    .checked_sub(totals.keeper_fees)?
+                    .checked_sub(undistributed)?,
+                undistributed,
+                claimed: totals.claimed,
+                claimants: DISTRIBUTION_CLAIMANT_COUNTS
+                    .may_load(deps.storage, id)?
+                    .unwrap_or_default(),
+                runway: distribution.get_runway(deps, &env.block)?,
+                denom: totals.denom,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DistributionStatsResponse { stats })
+}
+
+/// the number of seconds in a day, used to estimate daily rewards.
+const SECONDS_PER_DAY: u128 = 86_400;
+
+/// returns what a distribution's end and emission rate would be if its
+/// emission rate were updated now, without changing any state.
+fn query_simulate_update(
+    deps: Deps,
+    env: Env,
+    id: u64,
+    emission_rate: EmissionRate,
+) -> StdResult<SimulateUpdateResponse> {
+    let mut distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+
+    // apply the update to a copy of the distribution, exactly as `update`
+    // would, without saving it
+    emission_rate
+        .validate()
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    distribution
+        .transition_epoch(deps, emission_rate.clone(), &env.block)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let (amount, duration) = match emission_rate {
+        EmissionRate::Linear {
+            amount, duration, ..
+        }
+        | EmissionRate::LinearPerpetual { amount, duration } => (amount, duration),
+        // a new decaying epoch starts at its initial rate
+        EmissionRate::Decay {
+            initial_amount,
+            duration,
+            ..
+        } => (initial_amount, duration),
+        EmissionRate::Paused {} | EmissionRate::Immediate {} => {
+            return Ok(SimulateUpdateResponse {
+                ends_at: distribution.active_epoch.ends_at,
+                rate: Decimal::zero(),
+                daily_reward_per_1000_power: None,
+            })
+        }
+    };
+
+    let scalar = Uint128::from(get_duration_scalar(&duration));
+    let rate = Decimal::checked_from_ratio(amount, scalar)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let total_vp = distribution.get_total_vp(deps, &env.block)?;
+    let daily_reward_per_1000_power = match duration {
+        Duration::Time(_) if !total_vp.is_zero() => Some(
+            Decimal::checked_from_ratio(
+                amount.checked_mul(Uint128::new(SECONDS_PER_DAY * 1_000))?,
+                scalar.checked_mul(total_vp)?,
+            )
+            .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        _ => None,
+    };
+
+    Ok(SimulateUpdateResponse {
+        ends_at: distribution.active_epoch.ends_at,
+        rate,
+        daily_reward_per_1000_power,
+    })
+}
+
+/// returns the health of a distribution based on its active epoch state.
+fn query_health(deps: Deps, env: Env, id: u64) -> StdResult<DistributionHealth> {
+    let distribution = DISTRIBUTIONS.load(deps.storage, id)?;
+
+    if distribution.force_withdrawn {
+        return Ok(DistributionHealth::ForceWithdrawn {});
+    }
+
+    if distribution.frozen {
+        return Ok(DistributionHealth::Frozen {});
+    }
+
+    // rewards cannot be updated or claimed if voting power cannot be queried
+    if distribution.get_total_vp(deps, &env.block).is_err() {
+        return Ok(DistributionHealth::VpUnreachable {});
+    }
+
+    if distribution.funded_amount.is_zero() {
+        return Ok(DistributionHealth::Underfunded {});
+    }
+
+    // perpetual distributions never expire, but pause once all funding has
+    // been emitted
+    if let EmissionRate::LinearPerpetual { amount, duration } =
+        distribution.active_epoch.emission_rate
+    {
+        let (_, emitted) = get_active_epoch_totals(deps, &env.block, &distribution)?;
+        let unemitted = distribution.funded_amount - emitted;
+        if unemitted.is_zero() {
+            return Ok(DistributionHealth::Underfunded {});
+        }
+
+        let remaining = get_linear_funded_duration(amount, &duration, unemitted)?;
+        return if get_duration_scalar(&remaining) < get_duration_scalar(&duration) {
+            Ok(DistributionHealth::EndingSoon { remaining })
+        } else {
+            Ok(DistributionHealth::Healthy {})
+        };
+    }
+
+    // only linear and decaying distributions can run out of funds over time
+    let (EmissionRate::Linear { duration, .. } | EmissionRate::Decay { duration, .. }) =
+        distribution.active_epoch.emission_rate
+    else {
+        return Ok(DistributionHealth::Healthy {});
+    };
+
+    if distribution.active_epoch.ends_at.is_expired(&env.block) {
+        return Ok(DistributionHealth::Expired {});
+    }
+
+    // not expired, so ends_at is in the future
+    let remaining = match distribution.active_epoch.ends_at {
+        Expiration::AtHeight(height) => Duration::Height(height - env.block.height),
+        Expiration::AtTime(time) => Duration::Time(time.seconds() - env.block.time.seconds()),
+        Expiration::Never {} => return Ok(DistributionHealth::Healthy {}),
+    };
+
+    // warn once less than one emission period of funding remains
+    if get_duration_scalar(&remaining) < get_duration_scalar(&duration) {
+        Ok(DistributionHealth::EndingSoon { remaining })
+    } else {
+        Ok(DistributionHealth::Healthy {})
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
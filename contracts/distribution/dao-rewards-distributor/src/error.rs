@@ -1,5 +1,6 @@
 use cosmwasm_std::{DivideByZeroError, OverflowError, StdError};
-use cw_utils::PaymentError;
+use cw_reward_accrual::AccrualError;
+use cw_utils::{Expiration, PaymentError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -34,24 +35,178 @@ pub enum ContractError {
     #[error("Voting power changed hook sender incorrect")]
     InvalidHookSender {},
 
+    #[error("{addr} is already authorized to call this type of hook")]
+    HookCallerAlreadyAuthorized { addr: String },
+
+    #[error("{addr} is not authorized to call this type of hook")]
+    HookCallerNotAuthorized { addr: String },
+
+    #[error("{delegate} is not authorized to claim rewards for {staker}")]
+    NotClaimDelegate { staker: String, delegate: String },
+
     #[error("No rewards claimable")]
     NoRewardsClaimable {},
 
+    #[error("At most {max} distributions may be claimed at once")]
+    TooManyClaimIds { max: u32 },
+
     #[error("All rewards have already been distributed")]
     RewardsAlreadyDistributed {},
 
     #[error("Distribution not found with ID {id}")]
     DistributionNotFound { id: u64 },
 
+    #[error("Caller is not the pending manager of distribution {id}")]
+    NotPendingManager { id: u64 },
+
+    #[error("There isn't a pending transfer of distribution {id}'s management")]
+    ManagerTransferNotFound { id: u64 },
+
+    #[error("The pending transfer of distribution {id}'s management has expired")]
+    ManagerTransferExpired { id: u64 },
+
     #[error("Unexpected duplicate distribution with ID {id}")]
     UnexpectedDuplicateDistributionId { id: u64 },
 
     #[error("Invalid emission rate: {field} cannot be zero")]
     InvalidEmissionRateFieldZero { field: String },
 
+    #[error("Invalid emission rate: decay_factor must be less than one")]
+    InvalidDecayFactor {},
+
     #[error("There is no voting power registered, so no one will receive these funds")]
     NoVotingPowerNoRewards {},
 
+    #[error("Snapshot height must be in the past")]
+    InvalidSnapshotHeight {},
+
+    #[error("Snapshot distributions must use an immediate emission rate")]
+    SnapshotRequiresImmediateEmission {},
+
+    #[error("Block height {height} is before the last accounted for height {last_updated}")]
+    HeightRegression { last_updated: u64, height: u64 },
+
+    #[error("Keeper fee amount and interval must be non-zero")]
+    InvalidKeeperFee {},
+
+    #[error("Distribution {id} is frozen pending owner review")]
+    DistributionFrozen { id: u64 },
+
+    #[error("Distribution {id} was force withdrawn and no longer pays out rewards")]
+    DistributionForceWithdrawn { id: u64 },
+
+    #[error("The unclaimed rewards of distribution {id} have not expired")]
+    UnclaimedRewardsNotExpired { id: u64 },
+
+    #[error("The unclaimed rewards of distribution {id} may not expire before {earliest}")]
+    UnclaimedExpiryTooSoon { id: u64, earliest: Expiration },
+
+    #[error("The unclaimed expiry of distribution {id} may not be shortened")]
+    UnclaimedExpiryShortened { id: u64 },
+
+    #[error("Distribution {id} has not ended")]
+    DistributionNotEnded { id: u64 },
+
+    #[error("The funds of distribution {id} were not tracked since it was created, so they cannot be force withdrawn")]
+    UntrackedDistributionFunds { id: u64 },
+
+    #[error("Max rewards per user must be non-zero")]
+    InvalidMaxRewardsPerUser {},
+
+    #[error("Funding schedule amount and interval must be non-zero")]
+    InvalidFundingSchedule {},
+
+    #[error("cw20 distributions must be funded from an allowance, and native distributions from tokens sent when the schedule or rollover is created")]
+    InvalidFundingSource {},
+
+    #[error("Distribution {id} already has a funding schedule")]
+    FundingScheduleExists { id: u64 },
+
+    #[error("Distribution {id} does not have a funding schedule")]
+    FundingScheduleNotFound { id: u64 },
+
+    #[error("Rollover top-up and max rollovers must be non-zero")]
+    InvalidRollover {},
+
+    #[error("Only linear and decaying distributions can be rolled over")]
+    RolloverRequiresEndingEmission {},
+
+    #[error("Distribution {id} already has a rollover")]
+    RolloverExists { id: u64 },
+
+    #[error("Distribution {id} does not have a rollover")]
+    RolloverNotFound { id: u64 },
+
+    #[error("Caller is not allowed to update the members of distribution {id}")]
+    NotMemberUpdater { id: u64 },
+
+    #[error("At most {max} members may be updated at once")]
+    TooManyMembers { max: u32 },
+
+    #[error("Distribution {id} is a denom pool and cannot have pools of its own")]
+    PoolOfPool { id: u64 },
+
+    #[error("Distribution {id} already distributes {denom}")]
+    DuplicatePoolDenom { id: u64, denom: String },
+
+    #[error("Denom pools take their voting power and hook configuration from distribution {id}")]
+    PoolConfigFromParent { id: u64 },
+
+    #[error("Invalid CW721")]
+    InvalidCw721 {},
+
+    #[error("Raffle not found with ID {id}")]
+    RaffleNotFound { id: u64 },
+
+    #[error("Raffle epochs must be at least one block long")]
+    InvalidRaffleEpoch {},
+
+    #[error("Raffle seeds must be revealed within at least one block")]
+    InvalidRaffleRevealWindow {},
+
+    #[error("Only addresses with voting power may enter raffle {id}")]
+    NoRaffleVotingPower { id: u64 },
+
+    #[error("The seed for epoch {epoch} of raffle {id} was not revealed in time")]
+    RaffleRevealExpired { id: u64, epoch: u64 },
+
+    #[error("Already entered raffle {id}")]
+    AlreadyEnteredRaffle { id: u64 },
+
+    #[error("Raffle {id} has the maximum number of entrants, none with less voting power than the sender")]
+    RaffleFull { id: u64 },
+
+    #[error("Epoch {epoch} of raffle {id} has not ended")]
+    RaffleEpochNotEnded { id: u64, epoch: u64 },
+
+    #[error("Raffle {id} has no NFTs left to raffle")]
+    NoRafflePrizes { id: u64 },
+
+    #[error("Seed does not match the raffle's seed commitment")]
+    InvalidRaffleSeed {},
+
+    #[error("Voting power source weights must be non-zero")]
+    InvalidVpSourceWeight {},
+
     #[error("Cannot update emission rate because this distribution has accumulated the maximum rewards. Start a new distribution with the new emission rate instead. (Overflow: {err})")]
     DistributionHistoryTooLarge { err: String },
 }
+
+impl From<AccrualError> for ContractError {
+    fn from(err: AccrualError) -> Self {
+        match err {
+            AccrualError::Std(err) => ContractError::Std(err),
+            AccrualError::InvalidEmissionRateFieldZero { field } => {
+                ContractError::InvalidEmissionRateFieldZero { field }
+            }
+            AccrualError::InvalidDecayFactor {} => ContractError::InvalidDecayFactor {},
+            AccrualError::HeightRegression {
+                last_updated,
+                height,
+            } => ContractError::HeightRegression {
+                last_updated,
+                height,
+            },
+        }
+    }
+}
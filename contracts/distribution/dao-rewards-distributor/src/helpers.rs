@@ -1,51 +1,57 @@
 use cosmwasm_std::{
-    coins, to_json_binary, Addr, BankMsg, BlockInfo, CosmosMsg, Deps, DepsMut, StdError, StdResult,
-    Uint128, Uint256, WasmMsg,
+    coins, to_json_binary, Addr, BankMsg, BlockInfo, CosmosMsg, Deps, DepsMut, Env, StdResult,
+    Uint128, WasmMsg,
 };
-use cw20::{Denom, Expiration};
-use cw_utils::Duration;
+use cw20::{AllowanceResponse, BalanceResponse, Cw20QueryMsg, Denom};
 use dao_interface::voting::{
     Query as VotingQueryMsg, TotalPowerAtHeightResponse, VotingPowerAtHeightResponse,
 };
 
-use crate::ContractError;
+use crate::{msg::VpSourceMsg, state::VpSource, ContractError};
+
+pub use cw_reward_accrual::{get_duration_scalar, get_exp_diff};
+
+pub fn get_denom_string(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => denom.to_string(),
+        Denom::Cw20(address) => address.to_string(),
+    }
+}
 
 pub fn get_prev_block_total_vp(
     deps: Deps,
     block: &BlockInfo,
     contract_addr: &Addr,
 ) -> StdResult<Uint128> {
+    get_total_vp_at_height(
+        deps,
+        block.height.checked_sub(1).unwrap_or_default(),
+        contract_addr,
+    )
+}
+
+pub fn get_total_vp_at_height(deps: Deps, height: u64, contract_addr: &Addr) -> StdResult<Uint128> {
     let msg = VotingQueryMsg::TotalPowerAtHeight {
-        height: Some(block.height.checked_sub(1).unwrap_or_default()),
+        height: Some(height),
     };
     let resp: TotalPowerAtHeightResponse = deps.querier.query_wasm_smart(contract_addr, &msg)?;
     Ok(resp.power)
 }
 
-pub fn get_voting_power_at_block(
+pub fn get_voting_power_at_height(
     deps: Deps,
-    block: &BlockInfo,
+    height: u64,
     contract_addr: &Addr,
     addr: &Addr,
 ) -> StdResult<Uint128> {
     let msg = VotingQueryMsg::VotingPowerAtHeight {
         address: addr.into(),
-        height: Some(block.height),
+        height: Some(height),
     };
     let resp: VotingPowerAtHeightResponse = deps.querier.query_wasm_smart(contract_addr, &msg)?;
     Ok(resp.power)
 }
 
-/// returns underlying scalar value for a given duration.
-/// if the duration is in blocks, returns the block height.
-/// if the duration is in time, returns the time in seconds.
-pub fn get_duration_scalar(duration: &Duration) -> u64 {
-    match duration {
-        Duration::Height(h) => *h,
-        Duration::Time(t) => *t,
-    }
-}
-
 /// Returns the appropriate CosmosMsg for transferring the reward token.
 pub fn get_transfer_msg(recipient: Addr, amount: Uint128, denom: Denom) -> StdResult<CosmosMsg> {
     match denom {
@@ -69,34 +75,26 @@ pub fn get_transfer_msg(recipient: Addr, amount: Uint128, denom: Denom) -> StdRe
     }
 }
 
-pub(crate) fn scale_factor() -> Uint256 {
-    Uint256::from(10u8).pow(39)
-}
-
-/// Calculate the duration from start to end. If the end is at or before the
-/// start, return 0. The first argument is end, and the second is start.
-pub fn get_exp_diff(end: &Expiration, start: &Expiration) -> StdResult<u64> {
-    match (end, start) {
-        (Expiration::AtHeight(end), Expiration::AtHeight(start)) => {
-            if end > start {
-                Ok(end - start)
-            } else {
-                Ok(0)
-            }
-        }
-        (Expiration::AtTime(end), Expiration::AtTime(start)) => {
-            if end > start {
-                Ok(end.seconds() - start.seconds())
-            } else {
-                Ok(0)
-            }
-        }
-        (Expiration::Never {}, Expiration::Never {}) => Ok(0),
-        _ => Err(StdError::generic_err(format!(
-            "incompatible expirations: got end {:?}, start {:?}",
-            end, start
-        ))),
+/// returns how many cw20 tokens this contract may currently transfer from
+/// `owner`, the lesser of its unexpired allowance and its balance
+pub fn get_cw20_pullable(deps: Deps, env: &Env, token: &Addr, owner: &Addr) -> StdResult<Uint128> {
+    let allowance: AllowanceResponse = deps.querier.query_wasm_smart(
+        token,
+        &Cw20QueryMsg::Allowance {
+            owner: owner.to_string(),
+            spender: env.contract.address.to_string(),
+        },
+    )?;
+    if allowance.expires.is_expired(&env.block) {
+        return Ok(Uint128::zero());
     }
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        token,
+        &Cw20QueryMsg::Balance {
+            address: owner.to_string(),
+        },
+    )?;
+    Ok(allowance.allowance.min(balance.balance))
 }
 
 pub fn validate_voting_power_contract(
@@ -110,3 +108,23 @@ pub fn validate_voting_power_contract(
     )?;
     Ok(vp_contract)
 }
+
+/// validates the contracts, weights, and hook callers of voting power sources
+pub fn validate_vp_sources(
+    deps: &DepsMut,
+    vp_sources: Vec<VpSourceMsg>,
+) -> Result<Vec<VpSource>, ContractError> {
+    vp_sources
+        .into_iter()
+        .map(|source| {
+            if source.weight.is_zero() {
+                return Err(ContractError::InvalidVpSourceWeight {});
+            }
+            Ok(VpSource {
+                vp_contract: validate_voting_power_contract(deps, source.vp_contract)?,
+                weight: source.weight,
+                hook_caller: deps.api.addr_validate(&source.hook_caller)?,
+            })
+        })
+        .collect()
+}
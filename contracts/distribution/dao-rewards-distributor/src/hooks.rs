@@ -1,8 +1,21 @@
-use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use cosmwasm_std::{
+    ensure, to_json_vec, Addr, Deps, DepsMut, Env, Event, HexBinary, MessageInfo, Response,
+    StdResult, Storage,
+};
 use cw4::MemberChangedHookMsg;
+use cw_utils::nonpayable;
 use dao_hooks::{nft_stake::NftStakeChangedHookMsg, stake::StakeChangedHookMsg};
+use sha2::{Digest, Sha256};
 
-use crate::{rewards::update_rewards, state::REGISTERED_HOOKS, ContractError};
+use crate::{
+    contract::MAX_LIMIT,
+    rewards::update_rewards_or_freeze,
+    state::{
+        HookType, DISTRIBUTIONS, HOOK_SEQUENCES, PROCESSED_HOOKS, REGISTERED_HOOKS,
+        REGISTERED_TYPED_HOOKS,
+    },
+    ContractError,
+};
 
 /// Register a hook caller contract for a given distribution ID.
 pub(crate) fn subscribe_distribution_to_hook(
@@ -39,18 +52,125 @@ pub(crate) fn unsubscribe_distribution_from_hook(
     Ok(())
 }
 
+/// Register a hook caller contract for a single type of hook for a given
+/// distribution ID.
+pub(crate) fn subscribe_distribution_to_typed_hook(
+    storage: &mut dyn Storage,
+    distribution_id: u64,
+    hook_type: HookType,
+    hook: Addr,
+) -> Result<(), ContractError> {
+    REGISTERED_TYPED_HOOKS.update(storage, (hook_type.as_str(), hook), |ids| -> StdResult<_> {
+        let mut ids = ids.unwrap_or_default();
+        ids.push(distribution_id);
+        Ok(ids)
+    })?;
+    Ok(())
+}
+
+/// Unregister a hook caller contract for a single type of hook for a given
+/// distribution ID.
+pub(crate) fn unsubscribe_distribution_from_typed_hook(
+    storage: &mut dyn Storage,
+    distribution_id: u64,
+    hook_type: HookType,
+    hook: Addr,
+) -> Result<(), ContractError> {
+    let key = (hook_type.as_str(), hook);
+    let mut ids = REGISTERED_TYPED_HOOKS
+        .may_load(storage, key.clone())?
+        .unwrap_or_default();
+
+    ids.retain(|id| *id != distribution_id);
+
+    if ids.is_empty() {
+        REGISTERED_TYPED_HOOKS.remove(storage, key);
+    } else {
+        REGISTERED_TYPED_HOOKS.save(storage, key, &ids)?;
+    }
+
+    Ok(())
+}
+
 /// Ensures hooks that update voting power are only called by a designated
-/// hook_caller contract.
+/// hook_caller contract, or one authorized for this type of hook.
 /// Returns a list of distribution IDs that the hook caller is registered for.
 pub(crate) fn get_hook_caller_registered_distribution_ids(
     deps: Deps,
     info: MessageInfo,
+    hook_type: HookType,
 ) -> Result<Vec<u64>, ContractError> {
-    // only a designated hook_caller contract can call this hook.
-    // failing to load the registered denoms for a given hook returns an error.
-    REGISTERED_HOOKS
-        .load(deps.storage, info.sender.clone())
-        .map_err(|_| ContractError::InvalidHookSender {})
+    let mut ids = REGISTERED_HOOKS
+        .may_load(deps.storage, info.sender.clone())?
+        .unwrap_or_default();
+    ids.extend(
+        REGISTERED_TYPED_HOOKS
+            .may_load(deps.storage, (hook_type.as_str(), info.sender))?
+            .unwrap_or_default(),
+    );
+
+    // only a designated hook caller can call this hook.
+    if ids.is_empty() {
+        return Err(ContractError::InvalidHookSender {});
+    }
+
+    // a hook caller may be registered both for all hooks and for this type of
+    // hook.
+    ids.sort_unstable();
+    ids.dedup();
+    Ok(ids)
+}
+
+/// Records a hook message from `caller` and returns its sequence number, or
+/// `None` if the caller already sent the same message in this block, such as
+/// when a relayer duplicates it. Voting power is queried rather than taken from
+/// hook messages, and no rewards accrue within a block, so ignoring a repeated
+/// message never changes anyone's rewards, even if it was sent on purpose.
+pub(crate) fn record_hook(
+    storage: &mut dyn Storage,
+    env: &Env,
+    caller: &Addr,
+    hook_type: HookType,
+    msg: &[u8],
+) -> Result<Option<u64>, ContractError> {
+    let hash = HexBinary::from(
+        Sha256::new()
+            .chain_update(hook_type.as_str())
+            .chain_update(msg)
+            .finalize()
+            .as_slice(),
+    );
+
+    // only the hashes processed in the current block are kept.
+    let mut processed = match PROCESSED_HOOKS.may_load(storage, caller)? {
+        Some((height, processed)) if height == env.block.height => processed,
+        _ => vec![],
+    };
+    if processed.contains(&hash) {
+        return Ok(None);
+    }
+    processed.push(hash);
+    PROCESSED_HOOKS.save(storage, caller, &(env.block.height, processed))?;
+
+    let mut sequence = HOOK_SEQUENCES
+        .may_load(storage, caller.clone())?
+        .unwrap_or_default();
+    sequence.nonce += 1;
+    HOOK_SEQUENCES.save(storage, caller.clone(), &sequence)?;
+
+    Ok(Some(sequence.nonce))
+}
+
+/// The response to a hook message that was ignored because it was already
+/// processed.
+fn duplicate_hook_response(caller: Addr, hook_type: HookType) -> Response {
+    Response::new()
+        .add_attribute("action", "duplicate_hook")
+        .add_event(
+            Event::new("duplicate_hook")
+                .add_attribute("hook_caller", caller)
+                .add_attribute("hook_type", hook_type.as_str()),
+        )
 }
 
 pub(crate) fn execute_stake_changed(
@@ -59,17 +179,33 @@ pub(crate) fn execute_stake_changed(
     info: MessageInfo,
     msg: StakeChangedHookMsg,
 ) -> Result<Response, ContractError> {
-    // Check that the sender is the vp_contract (or the hook_caller if configured).
-    let hooked_distribution_ids = get_hook_caller_registered_distribution_ids(deps.as_ref(), info)?;
+    let caller = info.sender.clone();
+
+    // Check that the sender is an authorized hook caller.
+    let hooked_distribution_ids =
+        get_hook_caller_registered_distribution_ids(deps.as_ref(), info, HookType::Stake)?;
 
-    match msg {
+    // Ignore messages that were already processed.
+    let Some(nonce) = record_hook(
+        deps.storage,
+        &env,
+        &caller,
+        HookType::Stake,
+        &to_json_vec(&msg)?,
+    )?
+    else {
+        return Ok(duplicate_hook_response(caller, HookType::Stake));
+    };
+
+    let response = match msg {
         StakeChangedHookMsg::Stake { addr, .. } => {
             update_for_stake(deps, env, addr, hooked_distribution_ids)
         }
         StakeChangedHookMsg::Unstake { addr, .. } => {
             execute_unstake(deps, env, addr, hooked_distribution_ids)
         }
-    }
+    }?;
+    Ok(response.add_attribute("nonce", nonce.to_string()))
 }
 
 pub(crate) fn execute_membership_changed(
@@ -78,18 +214,35 @@ pub(crate) fn execute_membership_changed(
     info: MessageInfo,
     msg: MemberChangedHookMsg,
 ) -> Result<Response, ContractError> {
-    // Check that the sender is the vp_contract (or the hook_caller if configured).
-    let hooked_distribution_ids = get_hook_caller_registered_distribution_ids(deps.as_ref(), info)?;
+    let caller = info.sender.clone();
+
+    // Check that the sender is an authorized hook caller.
+    let hooked_distribution_ids =
+        get_hook_caller_registered_distribution_ids(deps.as_ref(), info, HookType::Membership)?;
+
+    // Ignore messages that were already processed.
+    let Some(nonce) = record_hook(
+        deps.storage,
+        &env,
+        &caller,
+        HookType::Membership,
+        &to_json_vec(&msg)?,
+    )?
+    else {
+        return Ok(duplicate_hook_response(caller, HookType::Membership));
+    };
 
     // Get the addresses of members whose voting power has changed.
     for member in msg.diffs {
         let addr = deps.api.addr_validate(&member.key)?;
         for id in hooked_distribution_ids.clone() {
-            update_rewards(&mut deps, &env, &addr, id)?;
+            update_rewards_or_freeze(&mut deps, &env, &addr, id)?;
         }
     }
 
-    Ok(Response::new().add_attribute("action", "membership_changed"))
+    Ok(Response::new()
+        .add_attribute("action", "membership_changed")
+        .add_attribute("nonce", nonce.to_string()))
 }
 
 pub(crate) fn execute_nft_stake_changed(
@@ -98,17 +251,33 @@ pub(crate) fn execute_nft_stake_changed(
     info: MessageInfo,
     msg: NftStakeChangedHookMsg,
 ) -> Result<Response, ContractError> {
-    // Check that the sender is the vp_contract (or the hook_caller if configured).
-    let hooked_distribution_ids = get_hook_caller_registered_distribution_ids(deps.as_ref(), info)?;
+    let caller = info.sender.clone();
 
-    match msg {
+    // Check that the sender is an authorized hook caller.
+    let hooked_distribution_ids =
+        get_hook_caller_registered_distribution_ids(deps.as_ref(), info, HookType::NftStake)?;
+
+    // Ignore messages that were already processed.
+    let Some(nonce) = record_hook(
+        deps.storage,
+        &env,
+        &caller,
+        HookType::NftStake,
+        &to_json_vec(&msg)?,
+    )?
+    else {
+        return Ok(duplicate_hook_response(caller, HookType::NftStake));
+    };
+
+    let response = match msg {
         NftStakeChangedHookMsg::Stake { addr, .. } => {
             update_for_stake(deps, env, addr, hooked_distribution_ids)
         }
         NftStakeChangedHookMsg::Unstake { addr, .. } => {
             execute_unstake(deps, env, addr, hooked_distribution_ids)
         }
-    }
+    }?;
+    Ok(response.add_attribute("nonce", nonce.to_string()))
 }
 
 pub(crate) fn update_for_stake(
@@ -120,7 +289,7 @@ pub(crate) fn update_for_stake(
     // update rewards for every distribution ID that the hook caller is
     // registered for
     for id in hooked_distribution_ids {
-        update_rewards(&mut deps, &env, &addr, id)?;
+        update_rewards_or_freeze(&mut deps, &env, &addr, id)?;
     }
     Ok(Response::new().add_attribute("action", "stake"))
 }
@@ -134,7 +303,56 @@ pub(crate) fn execute_unstake(
     // update rewards for every distribution ID that the hook caller is
     // registered for
     for id in hooked_distribution_ids {
-        update_rewards(&mut deps, &env, &addr, id)?;
+        update_rewards_or_freeze(&mut deps, &env, &addr, id)?;
     }
     Ok(Response::new().add_attribute("action", "unstake"))
 }
+
+/// syncs the rewards of `addresses` in a distribution and its pools with their
+/// current voting power, for voting power contracts that cannot call hooks.
+/// voting power is queried rather than taken from hook messages, so this
+/// updates rewards exactly as a hook would have, as of when it is called.
+pub(crate) fn execute_update_members(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    ensure!(
+        addresses.len() <= MAX_LIMIT as usize,
+        ContractError::TooManyMembers { max: MAX_LIMIT }
+    );
+
+    let distribution = DISTRIBUTIONS
+        .load(deps.storage, id)
+        .map_err(|_| ContractError::DistributionNotFound { id })?;
+    if let Some(parent_id) = distribution.pool_of {
+        return Err(ContractError::PoolConfigFromParent { id: parent_id });
+    }
+    if let Some(updaters) = &distribution.member_updaters {
+        ensure!(
+            updaters.contains(&info.sender),
+            ContractError::NotMemberUpdater { id }
+        );
+    }
+
+    // pools share the distribution's voting power, so they are updated along
+    // with it like they are by hooks.
+    let ids = std::iter::once(id)
+        .chain(distribution.pools.iter().copied())
+        .collect::<Vec<_>>();
+    for addr in &addresses {
+        let addr = deps.api.addr_validate(addr)?;
+        for id in &ids {
+            update_rewards_or_freeze(&mut deps, &env, &addr, *id)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_members")
+        .add_attribute("id", id.to_string())
+        .add_attribute("members", addresses.len().to_string()))
+}
@@ -5,6 +5,7 @@ mod error;
 pub mod helpers;
 pub mod hooks;
 pub mod msg;
+pub mod raffles;
 pub mod rewards;
 pub mod state;
 
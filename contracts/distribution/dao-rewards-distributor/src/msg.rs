@@ -1,8 +1,10 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Addr, Decimal, HexBinary, Uint128};
 use cw20::{Cw20ReceiveMsg, Denom, UncheckedDenom};
 use cw4::MemberChangedHookMsg;
+use cw721::Cw721ReceiveMsg;
 use cw_ownable::cw_ownable_execute;
+use cw_utils::{Duration, Expiration};
 use dao_hooks::{nft_stake::NftStakeChangedHookMsg, stake::StakeChangedHookMsg};
 use dao_interface::voting::InfoResponse;
 
@@ -11,7 +13,10 @@ use dao_interface::voting::InfoResponse;
 pub use cw_controllers::ClaimsResponse;
 pub use cw_ownable::Ownership;
 
-use crate::state::{DistributionState, EmissionRate};
+use crate::state::{
+    ClaimRecord, DistributionManager, DistributionState, EmissionRate, FundingSchedule,
+    HookSequence, HookType, KeeperFee, NormalizedEmission, RaffleDraw, RaffleState, Rollover,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -32,7 +37,23 @@ pub enum ExecuteMsg {
     StakeChangeHook(StakeChangedHookMsg),
     /// registers a new distribution
     Create(CreateMsg),
-    /// updates the config for a distribution
+    /// registers a distribution of another denom as a pool of distribution
+    /// `id`. the pool has its own emission rate and funding, but takes its
+    /// voting power and hook configuration from `id`, is funded by funding
+    /// `id` with its denom, and is claimed whenever `id` is. only callable by
+    /// the owner.
+    CreatePool {
+        /// distribution ID to add the pool to
+        id: u64,
+        /// denom to distribute
+        denom: UncheckedDenom,
+        /// reward emission rate
+        emission_rate: EmissionRate,
+    },
+    /// updates the config for a distribution. only callable by the owner or
+    /// the distribution's manager, except for `vp_contract`, `vp_sources`,
+    /// `hook_caller`, `withdraw_destination`, and `unclaimed_expiry`, which
+    /// only the owner may update.
     Update {
         /// distribution ID to update
         id: u64,
@@ -40,22 +61,220 @@ pub enum ExecuteMsg {
         emission_rate: Option<EmissionRate>,
         /// address to query the voting power
         vp_contract: Option<String>,
+        /// additional weighted voting power contracts, replacing the existing
+        /// ones
+        vp_sources: Option<Vec<VpSourceMsg>>,
         /// address that will update the reward split when the voting power
         /// distribution changes
         hook_caller: Option<String>,
         /// destination address for reward clawbacks. defaults to owner
         withdraw_destination: Option<String>,
+        /// the most rewards a single address may accrue from the
+        /// distribution, or zero to remove the cap. the cap applies to rewards
+        /// when they are accounted for, including those earned before it
+        /// changed, but rewards accounted for before a cap is first set do not
+        /// count towards it.
+        max_rewards_per_user: Option<Uint128>,
+        /// when rewards distributed but not yet claimed expire, after which
+        /// they may be clawed back with `ForceWithdraw`. must be at least a
+        /// week after the distribution ends and may not be shortened once
+        /// set. `Expiration::Never` removes the expiry.
+        unclaimed_expiry: Option<Expiration>,
+    },
+    /// adds and removes addresses authorized to call a single type of hook for
+    /// a distribution, in addition to its hook_caller. only callable by the
+    /// owner.
+    UpdateHookCallers {
+        /// distribution ID to update
+        id: u64,
+        /// the type of hook the addresses are authorized to call
+        hook_type: HookType,
+        /// addresses to authorize
+        to_add: Vec<String>,
+        /// addresses to no longer authorize
+        to_remove: Vec<String>,
+    },
+    /// sets or removes the fee paid to callers of `Poke` for a distribution.
+    /// only callable by the owner.
+    UpdateKeeperFee {
+        /// distribution ID to update
+        id: u64,
+        /// the keeper fee, or none to stop paying keepers
+        keeper_fee: Option<KeeperFee>,
+    },
+    /// sets the keepers allowed to call `UpdateMembers` for a distribution
+    /// and its pools, or none to let anyone call it. only callable by the
+    /// owner or the distribution's manager.
+    UpdateMemberUpdaters {
+        /// distribution ID to update
+        id: u64,
+        /// the keepers allowed to update members, or none for anyone
+        updaters: Option<Vec<String>>,
     },
+    /// syncs the rewards of `addresses` (at most 50) in a distribution and its
+    /// pools with their current voting power, as if a hook had fired for each
+    /// of them, for voting power contracts that cannot call hooks. callable by
+    /// anyone, unless the distribution has member updaters, in which case only
+    /// by them.
+    UpdateMembers { id: u64, addresses: Vec<String> },
     /// Used to fund this contract with cw20 tokens.
     Receive(Cw20ReceiveMsg),
     /// Used to fund this contract with native tokens.
     Fund(FundMsg),
-    /// Claims rewards for the sender.
+    /// Claims rewards for the sender, including those of the distribution's
+    /// denom pools.
     Claim { id: u64 },
+    /// claims the sender's rewards from up to `limit` distributions after
+    /// `start_after`, in ID order, sending a single transfer per denom.
+    /// frozen distributions and those with no pending rewards are skipped.
+    ClaimAll {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// claims the sender's rewards from each of `ids` (at most 50), sending a
+    /// single transfer per denom. like `Claim`, each distribution is claimed
+    /// along with its pools. a `claim_many` event reports whether each ID was
+    /// claimed. IDs that cannot be claimed, including those with no pending
+    /// rewards, are skipped, unless `strict` is set, in which case the whole
+    /// claim fails.
+    ClaimMany {
+        ids: Vec<u64>,
+        #[serde(default)]
+        strict: bool,
+    },
+    /// authorizes `delegate` to claim the sender's rewards with `ClaimFor`,
+    /// sending them to `destination`. replaces the destination if the
+    /// delegate is already authorized.
+    SetClaimDelegate {
+        delegate: String,
+        destination: String,
+    },
+    /// revokes `delegate`'s authorization to claim the sender's rewards.
+    RemoveClaimDelegate { delegate: String },
+    /// claims `staker`'s rewards and sends them to the destination the staker
+    /// registered for the sender. only callable by the staker's claim
+    /// delegates.
+    ClaimFor { id: u64, staker: String },
     /// withdraws the undistributed rewards for a distribution. members can
     /// claim whatever they earned until this point. this is effectively an
     /// inverse to fund and does not affect any already-distributed rewards.
+    /// only callable by the owner or the distribution's manager.
     Withdraw { id: u64 },
+    /// withdraws every fund held for a distribution once it has ended and its
+    /// unclaimed rewards have expired: its undistributed rewards, like `Withdraw`, as well as
+    /// rewards distributed but never claimed. members forfeit their unclaimed
+    /// rewards, and the distribution no longer accrues, pays out, or accepts
+    /// funds. only callable by the owner.
+    ForceWithdraw { id: u64 },
+    /// unfreezes a distribution that was frozen after the block height moved
+    /// before a height it had already accounted for. only callable by the
+    /// owner or the distribution's manager once the block height has caught
+    /// back up.
+    Unfreeze { id: u64 },
+    /// delegates management of a single distribution (updating, withdrawing,
+    /// and unfreezing it) to another address, such as a subDAO, without
+    /// transferring ownership of the contract.
+    UpdateManager { id: u64, action: ManagerAction },
+    /// brings a distribution's rewards up to date so that queries and
+    /// integrations see fresh data. anyone may call this. if the distribution
+    /// has a keeper fee and its interval has passed, the caller is paid the
+    /// fee from the distribution's undistributed funds.
+    Poke { id: u64 },
+    /// registers a schedule that funds a distribution with `amount` at most
+    /// once per `interval`, whenever `Tick` is called. cw20 distributions are
+    /// funded from `source`'s cw20 allowance to this contract, and native
+    /// distributions from the tokens sent with this message, which are held
+    /// until released. only callable by the owner or the distribution's
+    /// manager, who may only fund it from their own allowance.
+    CreateFundingSchedule {
+        /// distribution ID to fund
+        id: u64,
+        /// the amount funded per installment
+        amount: Uint128,
+        /// the minimum time between installments
+        interval: Duration,
+        /// the address whose cw20 allowance installments are transferred
+        /// from. required for cw20 distributions and not allowed for native
+        /// ones. only the owner may use an address other than their own.
+        source: Option<String>,
+    },
+    /// removes a distribution's funding schedule, sending any tokens still
+    /// held for it to the distribution's withdraw destination. only callable
+    /// by the owner or the distribution's manager.
+    RemoveFundingSchedule { id: u64 },
+    /// rolls a linear or decaying distribution over into a new epoch with the
+    /// same emission rate each time it ends, up to `max_rollovers` times. each
+    /// new epoch is funded with the funds the ended epoch did not emit plus a
+    /// top-up of `top_up`. native top-ups are released from the tokens sent
+    /// with this message, which are held until released. only callable by the
+    /// owner or the distribution's manager, who may only top it up from their
+    /// own allowance.
+    CreateRollover {
+        /// distribution ID to roll over
+        id: u64,
+        /// the amount added to the funds carried over by each rollover
+        top_up: Uint128,
+        /// the most times the distribution is rolled over
+        max_rollovers: u32,
+        /// the address whose cw20 allowance top-ups are transferred from.
+        /// required for cw20 distributions and not allowed for native ones.
+        /// only the owner may use an address other than their own.
+        source: Option<String>,
+    },
+    /// removes a distribution's rollover, sending any tokens still held for
+    /// it to the distribution's withdraw destination. only callable by the
+    /// owner or the distribution's manager.
+    RemoveRollover { id: u64 },
+    /// funds every distribution in a page of funding schedules that is due
+    /// with one installment, and rolls over every distribution in a page of
+    /// rollovers whose epoch has ended. schedules and rollovers that fail are
+    /// skipped and reported with a `tick_failed` event instead of failing the
+    /// tick. if a page was full, the `schedules_start_after` or
+    /// `rollovers_start_after` attribute is the ID to continue from. anyone
+    /// may call this.
+    Tick {
+        /// the page of funding schedules to fund. defaults to the first.
+        schedules: Option<TickPage>,
+        /// the page of rollovers to roll over. defaults to the first.
+        rollovers: Option<TickPage>,
+    },
+    /// creates a raffle that gives away NFTs escrowed with this contract, one
+    /// per epoch, to an entrant chosen at random weighted by their voting
+    /// power at the end of the epoch. only callable by the owner.
+    CreateRaffle(CreateRaffleMsg),
+    /// Used to escrow NFTs to be raffled.
+    ReceiveNft(Cw721ReceiveMsg),
+    /// enters the sender into a raffle for the current epoch and every epoch
+    /// after it. the sender must have voting power. if the raffle is full, the
+    /// entrant with the least voting power is replaced, provided they have
+    /// less than the sender.
+    EnterRaffle { id: u64 },
+    /// draws the winner of a raffle's next ended epoch and sends them the next
+    /// escrowed NFT. `seed` must hash to the raffle's seed commitment, and be
+    /// revealed within the raffle's reveal window. anyone may call this with
+    /// the seed.
+    DrawRaffle { id: u64, seed: HexBinary },
+    /// ends a raffle, sending any NFTs not yet raffled to the owner. callable
+    /// by the owner, or by anyone once the next epoch's seed was not revealed
+    /// in time.
+    CloseRaffle { id: u64 },
+}
+
+#[cw_serde]
+pub enum ManagerAction {
+    /// proposes `new_manager` as the distribution's manager, optionally with
+    /// an expiry. overwrites any pending transfer. only callable by the owner
+    /// or the current manager.
+    TransferManagement {
+        new_manager: String,
+        expiry: Option<Expiration>,
+    },
+    /// accepts a pending transfer. only callable by the pending manager.
+    AcceptManagement {},
+    /// removes the distribution's manager and any pending transfer, leaving
+    /// it managed by the owner alone. only callable by the owner or the
+    /// current manager.
+    RemoveManager {},
 }
 
 #[cw_serde]
@@ -66,11 +285,60 @@ pub struct CreateMsg {
     pub emission_rate: EmissionRate,
     /// address to query the voting power
     pub vp_contract: String,
+    /// additional voting power contracts whose weighted voting power is added
+    /// to `vp_contract`'s voting power, which has a weight of one.
+    #[serde(default)]
+    pub vp_sources: Vec<VpSourceMsg>,
     /// address that will update the reward split when the voting power
     /// distribution changes
     pub hook_caller: String,
     /// destination address for reward clawbacks. defaults to owner
     pub withdraw_destination: Option<String>,
+    /// past block height whose voting power snapshot will be used to allocate
+    /// rewards, enabling retroactive distributions. requires an immediate
+    /// emission rate. if not provided, the latest voting power is used.
+    pub snapshot_height: Option<u64>,
+    /// the most rewards a single address may accrue from the distribution.
+    /// rewards beyond the cap are returned to the undistributed pool. must be
+    /// non-zero if set.
+    pub max_rewards_per_user: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct VpSourceMsg {
+    /// address to query the voting power
+    pub vp_contract: String,
+    /// the multiplier applied to this contract's voting power before it is
+    /// added to the distribution's voting power. must be non-zero.
+    pub weight: Decimal,
+    /// address that will update the reward split when this contract's voting
+    /// power distribution changes
+    pub hook_caller: String,
+}
+
+#[cw_serde]
+pub struct CreateRaffleMsg {
+    /// the cw721 contract whose NFTs are raffled
+    pub nft_contract: String,
+    /// address to query the voting power that weights entrants' chances
+    pub vp_contract: String,
+    /// the number of blocks in each epoch. the first epoch starts now.
+    pub epoch_blocks: u64,
+    /// the sha256 hash of the seed that will be revealed to draw the first
+    /// epoch. the seed revealed for each draw must in turn be the sha256 hash
+    /// of the seed for the next, so that every draw's randomness is committed
+    /// to before anyone enters.
+    pub seed_commitment: HexBinary,
+    /// the number of blocks after each epoch ends within which its seed must
+    /// be revealed. if it is not, anyone may close the raffle, returning its
+    /// NFTs to the owner.
+    pub reveal_blocks: u64,
+}
+
+#[cw_serde]
+pub enum ReceiveNftMsg {
+    /// escrows the NFT to be given away by a raffle.
+    FundRaffle { id: u64 },
 }
 
 #[cw_serde]
@@ -85,6 +353,17 @@ pub enum ReceiveCw20Msg {
     Fund(FundMsg),
 }
 
+/// a page of funding schedules or rollovers, by distribution ID, to process
+/// in a tick
+#[cw_serde]
+#[derive(Default)]
+pub struct TickPage {
+    /// the distribution ID to start after. defaults to the first.
+    pub start_after: Option<u64>,
+    /// the most entries to process. defaults to 10, and at most 50.
+    pub limit: Option<u32>,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -102,7 +381,7 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
     /// Returns the state of the given distribution.
-    #[returns(DistributionState)]
+    #[returns(DistributionResponse)]
     Distribution { id: u64 },
     /// Returns the state of all the distributions.
     #[returns(DistributionsResponse)]
@@ -110,11 +389,112 @@ pub enum QueryMsg {
         start_after: Option<u64>,
         limit: Option<u32>,
     },
+    /// Returns the health of the given distribution, so that bots and
+    /// frontends can monitor it without re-implementing the emission math.
+    #[returns(DistributionHealth)]
+    Health { id: u64 },
+    /// Returns what the given distribution's end and emission rate would be
+    /// if its emission rate were updated to `emission_rate` now, so that
+    /// proposed updates can be checked before they are voted on.
+    #[returns(SimulateUpdateResponse)]
+    SimulateUpdate {
+        id: u64,
+        emission_rate: EmissionRate,
+    },
+    /// Returns the addresses authorized to call each type of hook for the
+    /// given distribution, including its hook_caller.
+    #[returns(HookCallersResponse)]
+    HookCallers { id: u64 },
+    /// Returns the number of hook messages processed from the given hook
+    /// caller.
+    #[returns(HookSequence)]
+    HookSequence { caller: String },
+    /// Returns the manager of the given distribution, and any pending transfer
+    /// of its management.
+    #[returns(DistributionManager)]
+    DistributionManager { id: u64 },
+    /// Returns the addresses authorized to claim the given staker's rewards,
+    /// and the destination their claims are sent to.
+    #[returns(ClaimDelegatesResponse)]
+    ClaimDelegates {
+        staker: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the totals funded, claimed, withdrawn, and pending across all
+    /// distributions of each denom, so that balances can be reconciled per
+    /// denom.
+    #[returns(TotalsByDenomResponse)]
+    TotalsByDenom {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the funding schedule of the given distribution.
+    #[returns(FundingSchedule)]
+    FundingSchedule { id: u64 },
+    /// Returns the funding schedules of all distributions that have one.
+    #[returns(FundingSchedulesResponse)]
+    FundingSchedules {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the rollover of the given distribution.
+    #[returns(Rollover)]
+    Rollover { id: u64 },
+    /// Returns the rollovers of all distributions that have one.
+    #[returns(RolloversResponse)]
+    Rollovers {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the rewards an address has claimed, oldest first, paginated by
+    /// claim record ID.
+    #[returns(ClaimHistoryResponse)]
+    ClaimHistory {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the funds of each distribution that have not yet been
+    /// emitted, including rewards withheld from users that reached the
+    /// per-user cap.
+    #[returns(UndistributedRewardsResponse)]
+    UndistributedRewards {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the funding, distribution, and claim stats of each
+    /// distribution whose funds have been tracked since it was created.
+    #[returns(DistributionStatsResponse)]
+    DistributionStats {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the state of the given raffle.
+    #[returns(RaffleState)]
+    Raffle { id: u64 },
+    /// Returns the winners of the given raffle's drawn epochs.
+    #[returns(RaffleDrawsResponse)]
+    RaffleDraws {
+        id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// a distribution's state along with values computed when it is queried.
+#[cw_serde]
+pub struct DistributionResponse {
+    #[serde(flatten)]
+    pub state: DistributionState,
+    /// the active emission rate normalized to comparable units. `None` unless
+    /// rewards are emitted over time.
+    pub normalized_emission: Option<NormalizedEmission>,
 }
 
 #[cw_serde]
 pub struct DistributionsResponse {
-    pub distributions: Vec<DistributionState>,
+    pub distributions: Vec<DistributionResponse>,
 }
 
 #[cw_serde]
@@ -132,5 +512,146 @@ pub struct DistributionPendingRewards {
     pub pending_rewards: Uint128,
 }
 
+#[cw_serde]
+pub struct HookCallersResponse {
+    pub stake: Vec<Addr>,
+    pub nft_stake: Vec<Addr>,
+    pub membership: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct ClaimDelegatesResponse {
+    pub delegates: Vec<ClaimDelegate>,
+}
+
+#[cw_serde]
+pub struct ClaimDelegate {
+    /// address authorized to claim the staker's rewards
+    pub delegate: Addr,
+    /// address that rewards claimed by the delegate are sent to
+    pub destination: Addr,
+}
+
+#[cw_serde]
+pub struct TotalsByDenomResponse {
+    pub totals: Vec<DenomTotals>,
+}
+
+#[cw_serde]
+pub struct RaffleDrawsResponse {
+    pub draws: Vec<RaffleDraw>,
+}
+
+#[cw_serde]
+pub struct FundingSchedulesResponse {
+    pub schedules: Vec<FundingSchedule>,
+}
+
+#[cw_serde]
+pub struct RolloversResponse {
+    pub rollovers: Vec<Rollover>,
+}
+
+#[cw_serde]
+pub struct ClaimHistoryResponse {
+    pub claims: Vec<ClaimRecord>,
+}
+
+#[cw_serde]
+pub struct UndistributedRewardsResponse {
+    pub distributions: Vec<UndistributedRewards>,
+}
+
+#[cw_serde]
+pub struct UndistributedRewards {
+    /// distribution ID
+    pub id: u64,
+    /// denomination of the distribution
+    pub denom: Denom,
+    /// amount funded that has not yet been emitted
+    pub undistributed: Uint128,
+}
+
+#[cw_serde]
+pub struct DistributionStatsResponse {
+    pub stats: Vec<DistributionStats>,
+}
+
+#[cw_serde]
+pub struct DistributionStats {
+    /// distribution ID
+    pub id: u64,
+    /// denomination of the distribution
+    pub denom: Denom,
+    /// total amount funded
+    pub funded: Uint128,
+    /// total amount emitted to users so far, claimed or not
+    pub distributed: Uint128,
+    /// amount funded that has not yet been emitted
+    pub undistributed: Uint128,
+    /// total amount claimed by users
+    pub claimed: Uint128,
+    /// number of unique addresses that have claimed rewards
+    pub claimants: u64,
+    /// how much longer rewards will be emitted at the current emission rate
+    /// before funds run out, in blocks or seconds matching the emission
+    /// rate's duration. `None` unless rewards are emitted over time.
+    pub runway: Option<Duration>,
+}
+
+#[cw_serde]
+pub struct DenomTotals {
+    /// denomination of the totals
+    pub denom: Denom,
+    /// total amount funded
+    pub funded: Uint128,
+    /// total amount claimed by users
+    pub claimed: Uint128,
+    /// total amount withdrawn by the owner
+    pub withdrawn: Uint128,
+    /// total amount paid to keepers
+    pub keeper_fees: Uint128,
+    /// amount funded that has been neither claimed, withdrawn, nor paid to
+    /// keepers, which should be held by the contract
+    pub pending: Uint128,
+}
+
+#[cw_serde]
+pub struct SimulateUpdateResponse {
+    /// when the distribution would end
+    pub ends_at: Expiration,
+    /// rewards emitted per block, or per second, matching the emission
+    /// rate's duration, in the first period for a decaying rate. zero if
+    /// rewards would be paused or distributed immediately.
+    pub rate: Decimal,
+    /// rewards that 1,000 units of voting power would earn per day at the
+    /// current total voting power. `None` unless rewards would be emitted
+    /// linearly over a duration in seconds and there is voting power.
+    pub daily_reward_per_1000_power: Option<Decimal>,
+}
+
+#[cw_serde]
+pub enum DistributionHealth {
+    /// rewards are distributed as expected
+    Healthy {},
+    /// the funded period of a linear distribution ends within one emission
+    /// period. `remaining` is in blocks or seconds, matching the emission
+    /// rate's duration.
+    EndingSoon { remaining: Duration },
+    /// the distribution has no funds to distribute
+    Underfunded {},
+    /// the funded period of a linear distribution has ended and no more
+    /// rewards are being distributed
+    Expired {},
+    /// the voting power contract could not be queried, so rewards cannot be
+    /// updated or claimed
+    VpUnreachable {},
+    /// the distribution was frozen after a block height regression and needs
+    /// to be unfrozen by the owner
+    Frozen {},
+    /// the distribution was force withdrawn and no longer pays out rewards
+    ForceWithdrawn {},
+}
+
 #[cw_serde]
 pub enum MigrateMsg {}
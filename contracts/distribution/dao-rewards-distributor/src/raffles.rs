@@ -0,0 +1,381 @@
+use cosmwasm_std::{
+    ensure, from_json, to_json_binary, Addr, Deps, DepsMut, Env, HexBinary, MessageInfo, Order,
+    Response, StdResult, Storage, Uint128, WasmMsg,
+};
+use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+use cw_storage_plus::Bound;
+use cw_utils::nonpayable;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    contract::{DEFAULT_LIMIT, MAX_LIMIT},
+    helpers::{get_voting_power_at_height, validate_voting_power_contract},
+    msg::{CreateRaffleMsg, RaffleDrawsResponse, ReceiveNftMsg},
+    state::{RaffleDraw, RaffleState, RAFFLES, RAFFLE_COUNT, RAFFLE_DRAWS, RAFFLE_ENTRANTS},
+    ContractError,
+};
+
+/// The maximum number of addresses that may enter a raffle, which bounds the
+/// number of voting power queries made by each draw.
+pub const MAX_RAFFLE_ENTRANTS: u32 = 100;
+
+fn load_raffle(storage: &dyn Storage, id: u64) -> Result<RaffleState, ContractError> {
+    RAFFLES
+        .load(storage, id)
+        .map_err(|_| ContractError::RaffleNotFound { id })
+}
+
+fn get_transfer_nft_msg(
+    nft_contract: &Addr,
+    recipient: &Addr,
+    token_id: String,
+) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: nft_contract.to_string(),
+        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// creates a new raffle. only the owner can do this. the first epoch starts at
+/// the current block.
+pub(crate) fn execute_create_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: CreateRaffleMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // only the owner can create a raffle
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    ensure!(msg.epoch_blocks != 0, ContractError::InvalidRaffleEpoch {});
+    ensure!(
+        msg.reveal_blocks != 0,
+        ContractError::InvalidRaffleRevealWindow {}
+    );
+
+    let nft_contract = deps.api.addr_validate(&msg.nft_contract)?;
+    let vp_contract = validate_voting_power_contract(&deps, msg.vp_contract)?;
+
+    let id = RAFFLE_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    RAFFLE_COUNT.save(deps.storage, &id)?;
+
+    let raffle = RaffleState {
+        id,
+        nft_contract,
+        vp_contract,
+        epoch_blocks: msg.epoch_blocks,
+        started_at: env.block.height,
+        next_epoch: 0,
+        prizes: vec![],
+        seed_commitment: msg.seed_commitment,
+        entrants: 0,
+        reveal_blocks: msg.reveal_blocks,
+    };
+    RAFFLES.save(deps.storage, id, &raffle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_raffle")
+        .add_attribute("id", id.to_string())
+        .add_attribute("nft_contract", raffle.nft_contract))
+}
+
+pub(crate) fn execute_receive_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    wrapper: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let msg: ReceiveNftMsg = from_json(&wrapper.msg)?;
+
+    match msg {
+        ReceiveNftMsg::FundRaffle { id } => {
+            let mut raffle = load_raffle(deps.storage, id)?;
+
+            // ensure the NFT is from the collection being raffled
+            ensure!(
+                raffle.nft_contract == info.sender,
+                ContractError::InvalidCw721 {}
+            );
+
+            raffle.prizes.push(wrapper.token_id.clone());
+            RAFFLES.save(deps.storage, id, &raffle)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "fund_raffle")
+                .add_attribute("id", id.to_string())
+                .add_attribute("token_id", wrapper.token_id))
+        }
+    }
+}
+
+/// enters the sender into a raffle. entrants are eligible from the epoch in
+/// progress onwards, so entering never makes anyone eligible for epochs that
+/// are waiting to be drawn.
+///
+/// only addresses with voting power at the current block may enter, which
+/// does not yet count stake added in the same block. once the raffle is full,
+/// the entrant with the least voting power is replaced if they have less than
+/// the sender, so that the raffle can't be filled with powerless addresses to
+/// keep stakers out.
+pub(crate) fn execute_enter_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut raffle = load_raffle(deps.storage, id)?;
+
+    ensure!(
+        !RAFFLE_ENTRANTS.has(deps.storage, (id, &info.sender)),
+        ContractError::AlreadyEnteredRaffle { id }
+    );
+
+    let power = get_voting_power_at_height(
+        deps.as_ref(),
+        env.block.height,
+        &raffle.vp_contract,
+        &info.sender,
+    )?;
+    ensure!(!power.is_zero(), ContractError::NoRaffleVotingPower { id });
+
+    let mut response = Response::new()
+        .add_attribute("action", "enter_raffle")
+        .add_attribute("id", id.to_string())
+        .add_attribute("entrant", info.sender.to_string());
+
+    if raffle.entrants < MAX_RAFFLE_ENTRANTS {
+        raffle.entrants += 1;
+    } else {
+        let (weakest, weakest_power) =
+            get_weakest_entrant(deps.as_ref(), &raffle, env.block.height)?;
+        ensure!(weakest_power < power, ContractError::RaffleFull { id });
+        RAFFLE_ENTRANTS.remove(deps.storage, (id, &weakest));
+        response = response.add_attribute("replaced", weakest);
+    }
+
+    let first_epoch = raffle.get_current_epoch(&env.block);
+    RAFFLE_ENTRANTS.save(deps.storage, (id, &info.sender), &first_epoch)?;
+    RAFFLES.save(deps.storage, id, &raffle)?;
+
+    Ok(response.add_attribute("first_epoch", first_epoch.to_string()))
+}
+
+/// the entrant of a raffle with the least voting power at `height`, and their
+/// voting power. ties go to the entrant that sorts first.
+fn get_weakest_entrant(
+    deps: Deps,
+    raffle: &RaffleState,
+    height: u64,
+) -> Result<(Addr, Uint128), ContractError> {
+    let mut weakest: Option<(Addr, Uint128)> = None;
+    for entrant in
+        RAFFLE_ENTRANTS
+            .prefix(raffle.id)
+            .keys(deps.storage, None, None, Order::Ascending)
+    {
+        let entrant = entrant?;
+        let power = get_voting_power_at_height(deps, height, &raffle.vp_contract, &entrant)?;
+        match &weakest {
+            Some((_, least)) if *least <= power => {}
+            _ => weakest = Some((entrant, power)),
+        }
+    }
+    weakest.ok_or(ContractError::RaffleFull { id: raffle.id })
+}
+
+/// draws the winner of a raffle's next ended epoch. each entrant's chance of
+/// winning is proportional to their voting power at the end of the epoch. the
+/// randomness comes from the revealed seed, which was committed to before the
+/// epoch's entrants and voting power were known. the seed must be revealed
+/// within the raffle's reveal window, after which the raffle may only be
+/// closed.
+pub(crate) fn execute_draw_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    seed: HexBinary,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut raffle = load_raffle(deps.storage, id)?;
+
+    let epoch = raffle.next_epoch;
+    let epoch_end = raffle.get_epoch_end(epoch)?;
+    ensure!(
+        env.block.height > epoch_end,
+        ContractError::RaffleEpochNotEnded { id, epoch }
+    );
+    ensure!(
+        env.block.height <= raffle.get_reveal_deadline(epoch)?,
+        ContractError::RaffleRevealExpired { id, epoch }
+    );
+    ensure!(
+        !raffle.prizes.is_empty(),
+        ContractError::NoRafflePrizes { id }
+    );
+    ensure!(
+        Sha256::digest(seed.as_slice()).as_slice() == raffle.seed_commitment.as_slice(),
+        ContractError::InvalidRaffleSeed {}
+    );
+
+    let entrants = RAFFLE_ENTRANTS
+        .prefix(id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, first_epoch)) if *first_epoch > epoch))
+        .map(|item| {
+            let (entrant, _) = item?;
+            let power = get_voting_power_at_height(
+                deps.as_ref(),
+                epoch_end,
+                &raffle.vp_contract,
+                &entrant,
+            )?;
+            Ok((entrant, power))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let total_power = entrants
+        .iter()
+        .try_fold(Uint128::zero(), |total, (_, power)| {
+            total.checked_add(*power)
+        })?;
+
+    let winner = if total_power.is_zero() {
+        None
+    } else {
+        let hash = Sha256::new()
+            .chain_update(seed.as_slice())
+            .chain_update(id.to_be_bytes())
+            .chain_update(epoch.to_be_bytes())
+            .finalize();
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+
+        // walk the entrants' ranges of tickets until the drawn ticket is found
+        let mut ticket = Uint128::new(u128::from_be_bytes(bytes)) % total_power;
+        entrants
+            .into_iter()
+            .find(|(_, power)| {
+                if ticket < *power {
+                    true
+                } else {
+                    ticket -= *power;
+                    false
+                }
+            })
+            .map(|(entrant, _)| entrant)
+    };
+
+    let mut response = Response::new()
+        .add_attribute("action", "draw_raffle")
+        .add_attribute("id", id.to_string())
+        .add_attribute("epoch", epoch.to_string());
+
+    // if no entrant had voting power, the prize is kept for the next epoch
+    let token_id = match &winner {
+        Some(winner) => {
+            let token_id = raffle.prizes.remove(0);
+            response = response
+                .add_message(get_transfer_nft_msg(
+                    &raffle.nft_contract,
+                    winner,
+                    token_id.clone(),
+                )?)
+                .add_attribute("winner", winner)
+                .add_attribute("token_id", token_id.clone());
+            Some(token_id)
+        }
+        None => None,
+    };
+
+    RAFFLE_DRAWS.save(
+        deps.storage,
+        (id, epoch),
+        &RaffleDraw {
+            epoch,
+            winner,
+            token_id,
+        },
+    )?;
+
+    raffle.seed_commitment = seed;
+    raffle.next_epoch += 1;
+    RAFFLES.save(deps.storage, id, &raffle)?;
+
+    Ok(response)
+}
+
+/// ends a raffle, returning any NFTs not yet raffled to the owner. past draws
+/// remain queryable. the owner may close a raffle at any time, and anyone may
+/// close it once the seed for its next epoch was not revealed in time, so that
+/// a withheld seed never leaves its NFTs stuck.
+pub(crate) fn execute_close_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let raffle = load_raffle(deps.storage, id)?;
+
+    let owner = cw_ownable::get_ownership(deps.storage)?
+        .owner
+        .ok_or(cw_ownable::OwnershipError::NoOwner)?;
+    if info.sender != owner {
+        let epoch = raffle.next_epoch;
+        ensure!(
+            env.block.height > raffle.get_reveal_deadline(epoch)?,
+            cw_ownable::OwnershipError::NotOwner
+        );
+    }
+
+    RAFFLES.remove(deps.storage, id);
+
+    let entrants = RAFFLE_ENTRANTS
+        .prefix(id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for entrant in entrants {
+        RAFFLE_ENTRANTS.remove(deps.storage, (id, &entrant));
+    }
+
+    let messages = raffle
+        .prizes
+        .into_iter()
+        .map(|token_id| get_transfer_nft_msg(&raffle.nft_contract, &owner, token_id))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new()
+        .add_attribute("action", "close_raffle")
+        .add_attribute("id", id.to_string())
+        .add_messages(messages))
+}
+
+pub(crate) fn query_raffle_draws(
+    deps: Deps,
+    id: u64,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RaffleDrawsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::<u64>::exclusive);
+
+    let draws = RAFFLE_DRAWS
+        .prefix(id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, draw)| draw))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RaffleDrawsResponse { draws })
+}
@@ -1,14 +1,15 @@
-use cosmwasm_std::{Addr, BlockInfo, Deps, DepsMut, Env, StdResult, Uint128, Uint256};
+use cosmwasm_std::{ensure, Addr, BlockInfo, Deps, DepsMut, Env, StdResult, Uint128, Uint256};
+use cw_reward_accrual::get_accrued_rewards;
 
 use crate::{
-    helpers::{
-        get_duration_scalar, get_exp_diff, get_prev_block_total_vp, get_voting_power_at_block,
-        scale_factor,
-    },
-    state::{DistributionState, EmissionRate, UserRewardState, DISTRIBUTIONS, USER_REWARDS},
+    state::{DistributionState, UserRewardState, COUNT, DISTRIBUTIONS, USER_REWARDS},
     ContractError,
 };
 
+/// the maximum number of distributions compacted out of a user's reward state
+/// in a single claim, bounding the gas compaction adds to claims.
+pub const MAX_COMPACTIONS_PER_CLAIM: u64 = 10;
+
 /// updates the user reward state for a given distribution and user address.
 /// also syncs the global reward state with the latest puvp values.
 pub fn update_rewards(
@@ -29,10 +30,23 @@ pub fn update_rewards(
         .may_load(deps.storage, addr.clone())?
         .unwrap_or_default();
 
+    ensure!(
+        !distribution.force_withdrawn,
+        ContractError::DistributionForceWithdrawn {
+            id: distribution_id
+        }
+    );
+    ensure!(
+        !distribution.frozen,
+        ContractError::DistributionFrozen {
+            id: distribution_id
+        }
+    );
+    distribution.check_height(&env.block)?;
+
     // first update the active epoch earned puvp value up to the current block
-    distribution.active_epoch.total_earned_puvp =
-        get_active_total_earned_puvp(deps.as_ref(), &env.block, &distribution)?;
-    distribution.active_epoch.bump_last_updated(&env.block);
+    distribution.update_active_epoch(deps.as_ref(), &env.block)?;
+    distribution.active_epoch.bump_last_updated(&env.block)?;
 
     // then calculate the total applicable puvp, which is the sum of historical
     // rewards earned puvp and the active epoch total earned puvp we just
@@ -42,7 +56,7 @@ pub fn update_rewards(
         .total_earned_puvp
         .checked_add(distribution.historical_earned_puvp)?;
 
-    let unaccounted_for_rewards = get_accrued_rewards_not_yet_accounted_for(
+    let accrued_rewards = get_accrued_rewards_not_yet_accounted_for(
         deps.as_ref(),
         env,
         addr,
@@ -51,6 +65,19 @@ pub fn update_rewards(
         &user_reward_state,
     )?;
 
+    // rewards beyond the distribution's per-user cap are returned to the
+    // undistributed pool
+    let unaccounted_for_rewards =
+        user_reward_state.apply_reward_cap(&distribution, accrued_rewards);
+    if distribution.max_rewards_per_user.is_some() {
+        *user_reward_state
+            .capped_accrued_rewards
+            .entry(distribution_id)
+            .or_default() += unaccounted_for_rewards;
+        distribution
+            .return_capped_rewards(&env.block, accrued_rewards - unaccounted_for_rewards)?;
+    }
+
     // get the pre-existing pending reward amount for the distribution
     let previous_pending_reward_amount = user_reward_state
         .pending_rewards
@@ -77,66 +104,136 @@ pub fn update_rewards(
     Ok(())
 }
 
+/// updates rewards like `update_rewards`, but freezes the distribution instead
+/// of erroring if the block height is before a height that has already been
+/// accounted for, and skips distributions that are already frozen or force
+/// withdrawn. this is used by voting power change hooks so that a single
+/// inconsistent distribution does not block staking.
+pub fn update_rewards_or_freeze(
+    deps: &mut DepsMut,
+    env: &Env,
+    addr: &Addr,
+    distribution_id: u64,
+) -> Result<(), ContractError> {
+    match update_rewards(deps, env, addr, distribution_id) {
+        Err(ContractError::HeightRegression { .. }) => {
+            let mut distribution = DISTRIBUTIONS.load(deps.storage, distribution_id)?;
+            distribution.frozen = true;
+            DISTRIBUTIONS.save(deps.storage, distribution_id, &distribution)?;
+            Ok(())
+        }
+        Err(
+            ContractError::DistributionFrozen { .. }
+            | ContractError::DistributionForceWithdrawn { .. },
+        ) => Ok(()),
+        res => res,
+    }
+}
+
+/// removes the user's entries for ended distributions that they have claimed
+/// all of their rewards from, in ID order, folding them into the user's
+/// `compacted_through` ID. a distribution is compacted once the user has no
+/// pending rewards in it, and has either accounted for all of its rewards or
+/// has no entry and no voting power in it. compaction stops at the first
+/// distribution that can not be compacted, and never causes an error, so that
+/// it can not block claims. force withdrawn distributions are always compacted.
+pub fn compact_user_rewards(deps: &mut DepsMut, env: &Env, addr: &Addr) -> StdResult<()> {
+    let count = COUNT.load(deps.storage)?;
+    let mut user_reward_state = USER_REWARDS
+        .may_load(deps.storage, addr.clone())?
+        .unwrap_or_default();
+    let start = user_reward_state.compacted_through;
+
+    while user_reward_state.compacted_through < count
+        && user_reward_state.compacted_through - start < MAX_COMPACTIONS_PER_CLAIM
+    {
+        let id = user_reward_state.compacted_through + 1;
+        let Some(mut distribution) = DISTRIBUTIONS.may_load(deps.storage, id)? else {
+            break;
+        };
+        // users forfeit the rewards they had not claimed from force withdrawn
+        // distributions, which no longer pay out
+        if distribution.force_withdrawn {
+            user_reward_state.pending_rewards.remove(&id);
+            user_reward_state.accounted_for_rewards_puvp.remove(&id);
+            user_reward_state.compacted_through = id;
+            continue;
+        }
+        if distribution.frozen || !distribution.is_ended(&env.block) {
+            break;
+        }
+
+        let Ok(total_earned_puvp) =
+            get_active_total_earned_puvp(deps.as_ref(), &env.block, &distribution)
+                .and_then(|puvp| Ok(puvp.checked_add(distribution.historical_earned_puvp)?))
+        else {
+            break;
+        };
+        // once settled, a distribution that has earned more rewards can not be
+        // compacted again, as compacted users have only accounted for rewards
+        // up to the settled value.
+        if distribution
+            .settled_puvp
+            .is_some_and(|settled| settled != total_earned_puvp)
+        {
+            break;
+        }
+
+        let pending = user_reward_state
+            .pending_rewards
+            .get(&id)
+            .cloned()
+            .unwrap_or_default();
+        let settled = match user_reward_state.accounted_for_rewards_puvp.get(&id) {
+            Some(accounted_for) => *accounted_for == total_earned_puvp,
+            // without an entry the user has never had their rewards updated,
+            // so they must not have voting power to be owed nothing.
+            None => distribution
+                .get_voting_power(deps.as_ref(), &env.block, addr)
+                .is_ok_and(|vp| vp.is_zero()),
+        };
+        if !pending.is_zero() || !settled {
+            break;
+        }
+
+        if distribution.settled_puvp.is_none() {
+            distribution.settled_puvp = Some(total_earned_puvp);
+            DISTRIBUTIONS.save(deps.storage, id, &distribution)?;
+        }
+        user_reward_state.pending_rewards.remove(&id);
+        user_reward_state.accounted_for_rewards_puvp.remove(&id);
+        user_reward_state.compacted_through = id;
+    }
+
+    if user_reward_state.compacted_through != start {
+        USER_REWARDS.save(deps.storage, addr.clone(), &user_reward_state)?;
+    }
+
+    Ok(())
+}
+
 /// Calculate the total rewards per unit voting power in the active epoch.
 pub fn get_active_total_earned_puvp(
     deps: Deps,
     block: &BlockInfo,
     distribution: &DistributionState,
 ) -> StdResult<Uint256> {
-    match distribution.active_epoch.emission_rate {
-        EmissionRate::Paused {} => Ok(Uint256::zero()),
-        // this is updated manually during funding, so just return it here.
-        EmissionRate::Immediate {} => Ok(distribution.active_epoch.total_earned_puvp),
-        EmissionRate::Linear {
-            amount, duration, ..
-        } => {
-            let curr = distribution.active_epoch.total_earned_puvp;
-
-            let last_time_rewards_distributed =
-                distribution.get_latest_reward_distribution_time(block);
-
-            // get the duration from the last time rewards were updated to the
-            // last time rewards were distributed. this will be 0 if the rewards
-            // were updated at or after the last time rewards were distributed.
-            let new_reward_distribution_duration: Uint128 = get_exp_diff(
-                &last_time_rewards_distributed,
-                &distribution.active_epoch.last_updated_total_earned_puvp,
-            )?
-            .into();
-
-            // no need to query total voting power and do math if distribution
-            // is already up to date.
-            if new_reward_distribution_duration.is_zero() {
-                return Ok(curr);
-            }
-
-            let prev_total_power = get_prev_block_total_vp(deps, block, &distribution.vp_contract)?;
-
-            // if no voting power is registered, no one should receive rewards.
-            if prev_total_power.is_zero() {
-                Ok(curr)
-            } else {
-                // count intervals of the rewards emission that have passed
-                // since the last update which need to be distributed
-                let complete_distribution_periods = new_reward_distribution_duration
-                    .checked_div(get_duration_scalar(&duration).into())?;
-
-                // It is impossible for this to overflow as total rewards can
-                // never exceed max value of Uint128 as total tokens in
-                // existence cannot exceed Uint128 (because the bank module Coin
-                // type uses Uint128).
-                let new_rewards_distributed = amount
-                    .full_mul(complete_distribution_periods)
-                    .checked_mul(scale_factor())?;
-
-                // the new rewards per unit voting power that have been
-                // distributed since the last update
-                let new_rewards_puvp =
-                    new_rewards_distributed.checked_div(prev_total_power.into())?;
-                Ok(curr.checked_add(new_rewards_puvp)?)
-            }
-        }
-    }
+    get_active_epoch_totals(deps, block, distribution).map(|(puvp, _)| puvp)
+}
+
+/// Calculate the total rewards per unit voting power and the total rewards
+/// emitted in the active epoch. only perpetual emission tracks the emitted
+/// amount, which is returned unchanged for other emission rates.
+pub fn get_active_epoch_totals(
+    deps: Deps,
+    block: &BlockInfo,
+    distribution: &DistributionState,
+) -> StdResult<(Uint256, Uint128)> {
+    distribution
+        .active_epoch
+        .get_totals(block, distribution.funded_amount, || {
+            distribution.get_total_vp(deps, block)
+        })
 }
 
 // get a user's rewards not yet accounted for in their reward state (not pending
@@ -149,28 +246,11 @@ pub fn get_accrued_rewards_not_yet_accounted_for(
     distribution: &DistributionState,
     user_reward_state: &UserRewardState,
 ) -> StdResult<Uint128> {
-    // get the user's voting power at the current height
-    let voting_power: Uint256 =
-        get_voting_power_at_block(deps, &env.block, &distribution.vp_contract, addr)?.into();
+    // get the user's voting power at the current height (or snapshot height)
+    let voting_power = distribution.get_voting_power(deps, &env.block, addr)?;
 
     // get previous reward per unit voting power accounted for
-    let user_last_reward_puvp = user_reward_state
-        .accounted_for_rewards_puvp
-        .get(&distribution.id)
-        .cloned()
-        .unwrap_or_default();
-
-    // calculate the difference between the current total reward per unit
-    // voting power distributed and the user's latest reward per unit voting
-    // power accounted for.
-    let reward_factor = total_earned_puvp.checked_sub(user_last_reward_puvp)?;
-
-    // calculate the amount of rewards earned:
-    // voting_power * reward_factor / scale_factor
-    let accrued_rewards_amount: Uint128 = voting_power
-        .checked_mul(reward_factor)?
-        .checked_div(scale_factor())?
-        .try_into()?;
+    let user_last_reward_puvp = user_reward_state.get_accounted_for_rewards_puvp(distribution);
 
-    Ok(accrued_rewards_amount)
+    get_accrued_rewards(voting_power, total_earned_puvp, user_last_reward_puvp)
 }
@@ -1,19 +1,26 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    ensure, Addr, BlockInfo, Decimal, Deps, StdError, StdResult, Timestamp, Uint128, Uint256,
-    Uint64,
+    ensure, Addr, BlockInfo, Decimal, Deps, Empty, HexBinary, StdError, StdResult, Storage,
+    Timestamp, Uint128, Uint256,
 };
 use cw20::{Denom, Expiration};
-use cw_storage_plus::{Item, Map};
+use cw_reward_accrual::{get_decay_emitted, get_rewards_puvp};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 use cw_utils::Duration;
-use std::{cmp::min, collections::HashMap};
+use std::collections::HashMap;
+use std::ops::Add;
 
 use crate::{
-    helpers::{get_duration_scalar, get_exp_diff, get_prev_block_total_vp, scale_factor},
-    rewards::get_active_total_earned_puvp,
+    helpers::{
+        get_denom_string, get_duration_scalar, get_exp_diff, get_total_vp_at_height,
+        get_voting_power_at_height,
+    },
+    rewards::get_active_epoch_totals,
     ContractError,
 };
 
+pub use cw_reward_accrual::{get_linear_funded_duration, EmissionRate, Epoch};
+
 /// map user address to their unique reward state
 pub const USER_REWARDS: Map<Addr, UserRewardState> = Map::new("ur");
 
@@ -23,9 +30,90 @@ pub const DISTRIBUTIONS: Map<u64, DistributionState> = Map::new("d");
 /// map registered hooks to list of distribution IDs they're registered for
 pub const REGISTERED_HOOKS: Map<Addr, Vec<u64>> = Map::new("rh");
 
+/// map hook type and registered hook to list of distribution IDs it's
+/// registered for, for only that type of hook
+pub const REGISTERED_TYPED_HOOKS: Map<(&str, Addr), Vec<u64>> = Map::new("rth");
+
+/// map hook caller to the sequence of hook messages processed from it
+pub const HOOK_SEQUENCES: Map<Addr, HookSequence> = Map::new("hs");
+
+/// map hook caller to the height of the last block it sent hook messages in
+/// and the hashes of those processed in that block, used to ignore messages it
+/// sends more than once in a block. reset when the caller sends a message in a
+/// later block.
+pub const PROCESSED_HOOKS: Map<&Addr, (u64, Vec<HexBinary>)> = Map::new("ph");
+
 /// The number of distributions that have been created.
 pub const COUNT: Item<u64> = Item::new("count");
 
+/// map (staker, delegate) to the destination that rewards claimed by the
+/// delegate on behalf of the staker are sent to
+pub const CLAIM_DELEGATES: Map<(&Addr, &Addr), Addr> = Map::new("cd");
+
+/// The number of claims that have been recorded.
+pub const CLAIM_COUNT: Item<u64> = Item::new("claim_count");
+
+pub struct ClaimRecordIndexes<'a> {
+    pub address: MultiIndex<'a, Addr, ClaimRecord, u64>,
+}
+
+impl<'a> IndexList<ClaimRecord> for ClaimRecordIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<ClaimRecord>> + '_> {
+        let v: Vec<&dyn Index<ClaimRecord>> = vec![&self.address];
+        Box::new(v.into_iter())
+    }
+}
+
+/// map claim record ID to the record of a claim, indexed by the address whose
+/// rewards were claimed
+pub fn claim_history<'a>() -> IndexedMap<'a, u64, ClaimRecord, ClaimRecordIndexes<'a>> {
+    let indexes = ClaimRecordIndexes {
+        address: MultiIndex::new(
+            |_pk: &[u8], r: &ClaimRecord| r.address.clone(),
+            "ch",
+            "ch__a",
+        ),
+    };
+    IndexedMap::new("ch", indexes)
+}
+
+/// map distribution ID to the address delegated to manage it, if any
+pub const DISTRIBUTION_MANAGERS: Map<u64, DistributionManager> = Map::new("dm");
+
+/// map denom string to the totals across all distributions of that denom
+pub const DENOM_TOTALS: Map<String, DenomTotalsState> = Map::new("dt");
+
+/// map distribution ID to the totals of the funds that have moved in and out
+/// of the contract for that distribution alone. distributions created before
+/// these were tracked have none.
+pub const DISTRIBUTION_TOTALS: Map<u64, DenomTotalsState> = Map::new("dist_t");
+
+/// set of (distribution ID, address) that have claimed rewards from the
+/// distribution, so that each claimant is only counted once
+pub const DISTRIBUTION_CLAIMANTS: Map<(u64, &Addr), Empty> = Map::new("dcl");
+
+/// map distribution ID to the number of unique addresses that have claimed
+/// rewards from it
+pub const DISTRIBUTION_CLAIMANT_COUNTS: Map<u64, u64> = Map::new("dcc");
+
+/// map distribution ID to its recurring funding schedule, if any
+pub const FUNDING_SCHEDULES: Map<u64, FundingSchedule> = Map::new("fs");
+
+/// map distribution ID to its rollover, if any
+pub const ROLLOVERS: Map<u64, Rollover> = Map::new("ro");
+
+/// map raffle ID to its raffle state
+pub const RAFFLES: Map<u64, RaffleState> = Map::new("r");
+
+/// The number of raffles that have been created.
+pub const RAFFLE_COUNT: Item<u64> = Item::new("raffle_count");
+
+/// map (raffle ID, entrant) to the first epoch the entrant is eligible for
+pub const RAFFLE_ENTRANTS: Map<(u64, &Addr), u64> = Map::new("re");
+
+/// map (raffle ID, epoch) to the result of that epoch's draw
+pub const RAFFLE_DRAWS: Map<(u64, u64), RaffleDraw> = Map::new("rd");
+
 #[cw_serde]
 #[derive(Default)]
 pub struct UserRewardState {
@@ -36,135 +124,174 @@ pub struct UserRewardState {
     /// that have already been accounted for (added to pending and maybe
     /// claimed).
     pub accounted_for_rewards_puvp: HashMap<u64, Uint256>,
+    /// distributions with IDs up to and including this one that have no entry
+    /// above have been compacted: the user claimed all of their rewards up to
+    /// the distribution's `settled_puvp`, and their entries were removed to
+    /// bound the size of this state.
+    #[serde(default)]
+    pub compacted_through: u64,
+    /// map distribution ID to the rewards the user has accrued, claimed or
+    /// not, in distributions with a per-user cap. unlike the entries above,
+    /// these are kept when the user is compacted out of the distribution.
+    #[serde(default)]
+    pub capped_accrued_rewards: HashMap<u64, Uint128>,
 }
 
-/// defines how many tokens (amount) should be distributed per amount of time
-/// (duration). e.g. 5udenom per hour.
+impl UserRewardState {
+    /// get the rewards per unit voting power the user has accounted for in a
+    /// distribution.
+    pub fn get_accounted_for_rewards_puvp(&self, distribution: &DistributionState) -> Uint256 {
+        match self.accounted_for_rewards_puvp.get(&distribution.id) {
+            Some(puvp) => *puvp,
+            None if distribution.id <= self.compacted_through => {
+                distribution.settled_puvp.unwrap_or_default()
+            }
+            None => Uint256::zero(),
+        }
+    }
+
+    /// limit rewards newly accrued by the user in a distribution to what
+    /// remains of the distribution's per-user cap, if it has one.
+    pub fn apply_reward_cap(&self, distribution: &DistributionState, rewards: Uint128) -> Uint128 {
+        match distribution.max_rewards_per_user {
+            Some(max) => {
+                let accrued = self
+                    .capped_accrued_rewards
+                    .get(&distribution.id)
+                    .cloned()
+                    .unwrap_or_default();
+                rewards.min(max.saturating_sub(accrued))
+            }
+            None => rewards,
+        }
+    }
+}
+
+/// the running totals of a denom across all distributions, updated as funds
+/// move in and out of the contract.
 #[cw_serde]
-pub enum EmissionRate {
-    /// rewards are paused
-    Paused {},
-    /// rewards are distributed immediately
-    Immediate {},
-    /// rewards are distributed at a constant rate
-    Linear {
-        /// amount of tokens to distribute per amount of time
-        amount: Uint128,
-        /// duration of time to distribute amount
-        duration: Duration,
-        /// whether or not reward distribution is continuous: whether future
-        /// funding after distribution finishes should be applied to the past,
-        /// or rewards are paused once all funding has been distributed. all
-        /// continuously backfilled rewards are distributed based on the current
-        /// voting power.
-        continuous: bool,
-    },
+pub struct DenomTotalsState {
+    /// validated denom (native or cw20)
+    pub denom: Denom,
+    /// total amount funded
+    pub funded: Uint128,
+    /// total amount claimed by users
+    pub claimed: Uint128,
+    /// total amount withdrawn by the owner
+    pub withdrawn: Uint128,
+    /// total amount paid to keepers for updating distributions
+    #[serde(default)]
+    pub keeper_fees: Uint128,
 }
 
-impl EmissionRate {
-    /// validate non-zero amount and duration if necessary
-    pub fn validate(&self) -> Result<(), ContractError> {
-        match self {
-            EmissionRate::Paused {} => Ok(()),
-            EmissionRate::Immediate {} => Ok(()),
-            EmissionRate::Linear {
-                amount, duration, ..
-            } => {
-                if *amount == Uint128::zero() {
-                    return Err(ContractError::InvalidEmissionRateFieldZero {
-                        field: "amount".to_string(),
-                    });
-                }
-                if get_duration_scalar(duration) == 0 {
-                    return Err(ContractError::InvalidEmissionRateFieldZero {
-                        field: "duration".to_string(),
-                    });
-                }
-                Ok(())
-            }
+impl DenomTotalsState {
+    /// update the totals of a denom, initializing them to zero if the denom
+    /// has not been seen before.
+    pub fn update(
+        storage: &mut dyn Storage,
+        denom: &Denom,
+        action: impl FnOnce(&mut Self) -> StdResult<()>,
+    ) -> StdResult<Self> {
+        let key = get_denom_string(denom);
+        let mut totals = DENOM_TOTALS
+            .may_load(storage, key.clone())?
+            .unwrap_or_else(|| Self::new(denom.clone()));
+        action(&mut totals)?;
+        DENOM_TOTALS.save(storage, key, &totals)?;
+        Ok(totals)
+    }
+
+    /// update the totals of a distribution's denom, and those of the
+    /// distribution itself if they are tracked.
+    pub fn update_for_distribution(
+        storage: &mut dyn Storage,
+        distribution: &DistributionState,
+        action: impl Fn(&mut Self) -> StdResult<()>,
+    ) -> StdResult<()> {
+        Self::update(storage, &distribution.denom, &action)?;
+        if let Some(mut totals) = DISTRIBUTION_TOTALS.may_load(storage, distribution.id)? {
+            action(&mut totals)?;
+            DISTRIBUTION_TOTALS.save(storage, distribution.id, &totals)?;
         }
+        Ok(())
     }
 
-    /// find the duration of the funded period given funded amount. e.g. if the
-    /// funded amount is twice the emission rate amount, the funded period
-    /// should be twice the emission rate duration, since the funded amount
-    /// takes two emission cycles to be distributed.
-    pub fn get_funded_period_duration(
-        &self,
-        funded_amount: Uint128,
-    ) -> StdResult<Option<Duration>> {
-        match self {
-            // if rewards are paused, return no duration
-            EmissionRate::Paused {} => Ok(None),
-            // if rewards are immediate, return no duration
-            EmissionRate::Immediate {} => Ok(None),
-            // if rewards are linear, calculate based on funded amount
-            EmissionRate::Linear {
-                amount, duration, ..
-            } => {
-                let amount_to_emission_rate_ratio = Decimal::from_ratio(funded_amount, *amount);
-
-                let funded_duration = match duration {
-                    Duration::Height(h) => {
-                        let duration_height = Uint128::from(*h)
-                            .checked_mul_floor(amount_to_emission_rate_ratio)
-                            .map_err(|e| StdError::generic_err(e.to_string()))?;
-                        let duration = Uint64::try_from(duration_height)?.u64();
-                        Duration::Height(duration)
-                    }
-                    Duration::Time(t) => {
-                        let duration_time = Uint128::from(*t)
-                            .checked_mul_floor(amount_to_emission_rate_ratio)
-                            .map_err(|e| StdError::generic_err(e.to_string()))?;
-                        let duration = Uint64::try_from(duration_time)?.u64();
-                        Duration::Time(duration)
-                    }
-                };
+    /// zeroed totals for a denom.
+    pub fn new(denom: Denom) -> Self {
+        Self {
+            denom,
+            funded: Uint128::zero(),
+            claimed: Uint128::zero(),
+            withdrawn: Uint128::zero(),
+            keeper_fees: Uint128::zero(),
+        }
+    }
 
-                Ok(Some(funded_duration))
-            }
+    /// the amount funded that has been neither claimed, withdrawn, nor paid to
+    /// keepers, and should be held by the contract.
+    pub fn get_pending(&self) -> StdResult<Uint128> {
+        Ok(self
+            .funded
+            .checked_sub(self.claimed)?
+            .checked_sub(self.withdrawn)?
+            .checked_sub(self.keeper_fees)?)
+    }
+}
+
+/// the type of hook that updates the reward split when the voting power
+/// distribution changes
+#[cw_serde]
+#[derive(Copy)]
+pub enum HookType {
+    /// `StakeChangeHook`
+    Stake,
+    /// `NftStakeChangeHook`
+    NftStake,
+    /// `MemberChangedHook`
+    Membership,
+}
+
+impl HookType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookType::Stake => "stake",
+            HookType::NftStake => "nft_stake",
+            HookType::Membership => "membership",
         }
     }
 }
 
+/// the sequence of hook messages processed from a hook caller
 #[cw_serde]
-pub struct Epoch {
-    /// reward emission rate
-    pub emission_rate: EmissionRate,
-    /// the time when the current reward distribution period started. period
-    /// finishes iff it reaches its end.
-    pub started_at: Expiration,
-    /// the time when all funded rewards are allocated to users and thus the
-    /// distribution period ends.
-    pub ends_at: Expiration,
-    /// total rewards earned per unit voting power from started_at to
-    /// last_updated_total_earned_puvp
-    pub total_earned_puvp: Uint256,
-    /// time when total_earned_puvp was last updated
-    pub last_updated_total_earned_puvp: Expiration,
+#[derive(Default)]
+pub struct HookSequence {
+    /// the number of hook messages processed from the caller
+    pub nonce: u64,
 }
 
-impl Epoch {
-    /// bump the last_updated_total_earned_puvp field to the minimum of the
-    /// current block and ends_at since rewards cannot be distributed after
-    /// ends_at. this is necessary in the case that a future funding backfills
-    /// rewards after they've finished distributing. in order to compute over
-    /// the missed space, last_updated can never be greater than ends_at. if
-    /// ends_at is never, the epoch must be paused, so it should never be
-    /// updated.
-    pub fn bump_last_updated(&mut self, current_block: &BlockInfo) {
-        match self.ends_at {
-            Expiration::Never {} => {
-                self.last_updated_total_earned_puvp = Expiration::Never {};
-            }
-            Expiration::AtHeight(ends_at_height) => {
-                self.last_updated_total_earned_puvp =
-                    Expiration::AtHeight(std::cmp::min(current_block.height, ends_at_height));
-            }
-            Expiration::AtTime(ends_at_time) => {
-                self.last_updated_total_earned_puvp =
-                    Expiration::AtTime(std::cmp::min(current_block.time, ends_at_time));
-            }
+/// hook callers authorized to call only one type of hook for a distribution
+#[cw_serde]
+#[derive(Default)]
+pub struct HookCallers {
+    pub stake: Vec<Addr>,
+    pub nft_stake: Vec<Addr>,
+    pub membership: Vec<Addr>,
+}
+
+impl HookCallers {
+    pub fn get(&self, hook_type: HookType) -> &Vec<Addr> {
+        match hook_type {
+            HookType::Stake => &self.stake,
+            HookType::NftStake => &self.nft_stake,
+            HookType::Membership => &self.membership,
+        }
+    }
+
+    pub fn get_mut(&mut self, hook_type: HookType) -> &mut Vec<Addr> {
+        match hook_type {
+            HookType::Stake => &mut self.stake,
+            HookType::NftStake => &mut self.nft_stake,
+            HookType::Membership => &mut self.membership,
         }
     }
 }
@@ -180,9 +307,21 @@ pub struct DistributionState {
     pub active_epoch: Epoch,
     /// address to query the voting power
     pub vp_contract: Addr,
+    /// additional voting power contracts whose weighted voting power is added
+    /// to `vp_contract`'s voting power, which has a weight of one.
+    #[serde(default)]
+    pub vp_sources: Vec<VpSource>,
     /// address that will update the reward split when the voting power
-    /// distribution changes
+    /// distribution changes. authorized to call every type of hook.
     pub hook_caller: Addr,
+    /// additional addresses authorized to call a single type of hook, so
+    /// that several voting power sources may feed one distribution.
+    #[serde(default)]
+    pub hook_callers: HookCallers,
+    /// the keepers allowed to sync members' voting power with
+    /// `UpdateMembers`, or none if anyone may.
+    #[serde(default)]
+    pub member_updaters: Option<Vec<Addr>>,
     /// total amount of rewards funded that will be distributed in the active
     /// epoch.
     pub funded_amount: Uint128,
@@ -192,63 +331,705 @@ pub struct DistributionState {
     /// changes in the emission rate. each time emission rate is changed, this
     /// value is increased by the `active_epoch`'s rewards earned puvp.
     pub historical_earned_puvp: Uint256,
+    /// if set, rewards are allocated based on the voting power at this past
+    /// height instead of the latest voting power. only immediate emission is
+    /// supported for snapshot distributions.
+    pub snapshot_height: Option<u64>,
+    /// set when the current block height is found to be before a height this
+    /// distribution has already accounted for. frozen distributions do not
+    /// update or pay out rewards until the owner unfreezes them.
+    #[serde(default)]
+    pub frozen: bool,
+    /// the total rewards earned per unit voting power when users were first
+    /// compacted out of this distribution. compacted users have accounted for
+    /// rewards up to this value, and only users that have accounted for
+    /// exactly this value may be compacted.
+    #[serde(default)]
+    pub settled_puvp: Option<Uint256>,
+    /// the fee paid from undistributed funds to callers of `Poke` that bring
+    /// this distribution's rewards up to date.
+    #[serde(default)]
+    pub keeper_fee: Option<KeeperFee>,
+    /// when the keeper fee may next be paid.
+    #[serde(default)]
+    pub keeper_fee_next_payable: Option<Expiration>,
+    /// the most rewards a single address may accrue from this distribution.
+    #[serde(default)]
+    pub max_rewards_per_user: Option<Uint128>,
+    /// rewards withheld from users that reached `max_rewards_per_user` that
+    /// could not be emitted again, because the distribution was not emitting
+    /// over time. they are returned when the distribution is withdrawn from.
+    #[serde(default)]
+    pub capped_rewards: Uint128,
+    /// distributions of other denoms that share this distribution's voting
+    /// power and hook configuration and are claimed along with it.
+    #[serde(default)]
+    pub pools: Vec<u64>,
+    /// the distribution this is a denom pool of, if any. pools take their
+    /// voting power and hook configuration from that distribution.
+    #[serde(default)]
+    pub pool_of: Option<u64>,
+    /// when rewards distributed but not yet claimed expire. once expired, they
+    /// may be clawed back along with the undistributed funds with
+    /// `ForceWithdraw`.
+    #[serde(default)]
+    pub unclaimed_expiry: Expiration,
+    /// set once `ForceWithdraw` has clawed back every fund held for this
+    /// distribution. force withdrawn distributions no longer accrue rewards,
+    /// pay them out, or accept funds, and users forfeit their unclaimed
+    /// rewards.
+    #[serde(default)]
+    pub force_withdrawn: bool,
+}
+
+/// the number of seconds in a (non-leap) year, used to estimate yearly rewards.
+const SECONDS_PER_YEAR: u128 = 31_536_000;
+
+/// the minimum number of blocks between the end of a height-based distribution
+/// and the expiry of its unclaimed rewards, about a week of 6 second blocks.
+pub const MIN_UNCLAIMED_EXPIRY_BLOCKS: u64 = 100_800;
+
+/// the minimum number of seconds between the end of a time-based distribution
+/// and the expiry of its unclaimed rewards.
+pub const MIN_UNCLAIMED_EXPIRY_SECONDS: u64 = 604_800;
+
+/// a distribution's active emission rate normalized to rewards per block or
+/// per second, so that distributions with different emission durations can be
+/// compared. contracts can not know how long blocks take, so rates are only
+/// given in the unit of the emission rate's duration.
+#[cw_serde]
+pub struct NormalizedEmission {
+    /// rewards emitted per block, if the emission rate's duration is in blocks
+    pub per_block: Option<Decimal>,
+    /// rewards emitted per second, if the emission rate's duration is in
+    /// seconds
+    pub per_second: Option<Decimal>,
+    /// rewards that one unit of voting power would earn in a year at the
+    /// current rate and total voting power, like an APR. `None` unless the
+    /// emission rate's duration is in seconds and there is voting power.
+    pub yearly_per_unit_power: Option<Decimal>,
+}
+
+/// an additional voting power contract that a distribution allocates rewards
+/// by, alongside its `vp_contract`
+#[cw_serde]
+pub struct VpSource {
+    /// address to query the voting power
+    pub vp_contract: Addr,
+    /// the multiplier applied to this contract's voting power before it is
+    /// added to the distribution's voting power
+    pub weight: Decimal,
+    /// address that will update the reward split when this contract's voting
+    /// power distribution changes. authorized to call every type of hook.
+    pub hook_caller: Addr,
+}
+
+impl VpSource {
+    /// multiply voting power by this source's weight, rounding down
+    pub fn apply_weight(&self, power: Uint128) -> StdResult<Uint128> {
+        power
+            .checked_mul_floor(self.weight)
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+}
+
+/// a fee paid to keepers for bringing a distribution's rewards up to date
+#[cw_serde]
+pub struct KeeperFee {
+    /// the amount of the distribution's denom paid per update
+    pub amount: Uint128,
+    /// the minimum time between fee payments
+    pub interval: Duration,
+}
+
+/// funds a distribution in installments whenever `Tick` is called, so that it
+/// can emit rewards indefinitely without manual funding
+#[cw_serde]
+pub struct FundingSchedule {
+    /// distribution ID
+    pub id: u64,
+    /// where installments are taken from
+    pub source: FundingSource,
+    /// the amount of the distribution's denom funded per installment
+    pub amount: Uint128,
+    /// the minimum time between installments
+    pub interval: Duration,
+    /// when the next installment may be funded
+    pub next_funding: Expiration,
+}
+
+#[cw_serde]
+pub enum FundingSource {
+    /// installments are transferred from `owner` using the cw20 allowance it
+    /// granted this contract
+    Allowance { owner: Addr },
+    /// installments are released from tokens deposited with this contract
+    /// when the schedule was created
+    Escrow { remaining: Uint128 },
+}
+
+/// rolls a distribution over into a new epoch with the same emission rate each
+/// time it ends, so that recurring programs run without manual funding
+#[cw_serde]
+pub struct Rollover {
+    /// distribution ID
+    pub id: u64,
+    /// where top-ups are taken from
+    pub source: FundingSource,
+    /// the amount of the distribution's denom added by each rollover
+    pub top_up: Uint128,
+    /// the number of rollovers left
+    pub remaining: u32,
+    /// the number of times the distribution has been rolled over
+    pub count: u32,
+}
+
+/// a record of rewards claimed from a distribution
+#[cw_serde]
+pub struct ClaimRecord {
+    /// claim record ID, increasing with each claim
+    pub id: u64,
+    /// the address whose rewards were claimed
+    pub address: Addr,
+    /// distribution ID
+    pub distribution_id: u64,
+    /// the amount of the distribution's denom claimed
+    pub amount: Uint128,
+    /// the block height the claim was made at
+    pub height: u64,
+    /// the block time the claim was made at
+    pub time: Timestamp,
+}
+
+impl ClaimRecord {
+    /// records `address` claiming `amount` from a distribution in this block.
+    pub fn save(
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        address: &Addr,
+        distribution_id: u64,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let id = CLAIM_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+        CLAIM_COUNT.save(storage, &id)?;
+        claim_history().save(
+            storage,
+            id,
+            &ClaimRecord {
+                id,
+                address: address.clone(),
+                distribution_id,
+                amount,
+                height: block.height,
+                time: block.time,
+            },
+        )
+    }
+}
+
+/// gives away escrowed NFTs, one per epoch, to entrants chosen at random
+/// weighted by their voting power at the end of the epoch
+#[cw_serde]
+pub struct RaffleState {
+    /// raffle ID
+    pub id: u64,
+    /// the cw721 contract whose NFTs are raffled
+    pub nft_contract: Addr,
+    /// address to query the voting power that weights entrants' chances
+    pub vp_contract: Addr,
+    /// the number of blocks in each epoch
+    pub epoch_blocks: u64,
+    /// the block height the first epoch started at
+    pub started_at: u64,
+    /// the next epoch to be drawn
+    pub next_epoch: u64,
+    /// escrowed NFTs yet to be raffled, in the order they will be given away
+    pub prizes: Vec<String>,
+    /// the sha256 hash of the seed that must be revealed to draw the next
+    /// epoch. each revealed seed becomes the commitment for the following
+    /// draw.
+    pub seed_commitment: HexBinary,
+    /// the number of addresses that have entered
+    pub entrants: u32,
+    /// the number of blocks after an epoch ends within which its seed must be
+    /// revealed. once passed, anyone may close the raffle.
+    pub reveal_blocks: u64,
+}
+
+impl RaffleState {
+    /// the block height at which `epoch` ends, and at which its entrants'
+    /// voting power is measured
+    pub fn get_epoch_end(&self, epoch: u64) -> StdResult<u64> {
+        epoch
+            .checked_add(1)
+            .and_then(|epochs| epochs.checked_mul(self.epoch_blocks))
+            .and_then(|blocks| blocks.checked_add(self.started_at))
+            .ok_or_else(|| StdError::generic_err("raffle epoch end overflow"))
+    }
+
+    /// the last block height at which `epoch` may be drawn
+    pub fn get_reveal_deadline(&self, epoch: u64) -> StdResult<u64> {
+        self.get_epoch_end(epoch)?
+            .checked_add(self.reveal_blocks)
+            .ok_or_else(|| StdError::generic_err("raffle reveal deadline overflow"))
+    }
+
+    /// the epoch in progress at the given block
+    pub fn get_current_epoch(&self, block: &BlockInfo) -> u64 {
+        block.height.saturating_sub(self.started_at) / self.epoch_blocks
+    }
+}
+
+/// the result of a raffle epoch's draw
+#[cw_serde]
+pub struct RaffleDraw {
+    /// the epoch drawn
+    pub epoch: u64,
+    /// the winner, or none if no entrant had voting power
+    pub winner: Option<Addr>,
+    /// the NFT given to the winner
+    pub token_id: Option<String>,
+}
+
+/// an address, other than the owner, that may manage a single distribution.
+/// management is transferred in two steps, like ownership.
+#[cw_serde]
+#[derive(Default)]
+pub struct DistributionManager {
+    /// the current manager
+    pub manager: Option<Addr>,
+    /// the proposed manager, who must accept management
+    pub pending_manager: Option<Addr>,
+    /// when the pending transfer expires, if ever
+    pub pending_expiry: Option<Expiration>,
+}
+
+/// ensures `sender` may manage distribution `id`, i.e. that they are the owner
+/// or the distribution's manager
+pub fn assert_owner_or_manager(
+    storage: &dyn Storage,
+    sender: &Addr,
+    id: u64,
+) -> Result<(), ContractError> {
+    if cw_ownable::is_owner(storage, sender)? {
+        return Ok(());
+    }
+    let manager = DISTRIBUTION_MANAGERS.may_load(storage, id)?;
+    if manager.and_then(|m| m.manager).as_ref() == Some(sender) {
+        Ok(())
+    } else {
+        Err(cw_ownable::OwnershipError::NotOwner.into())
+    }
 }
 
 impl DistributionState {
     pub fn get_denom_string(&self) -> String {
-        match &self.denom {
-            Denom::Native(denom) => denom.to_string(),
-            Denom::Cw20(address) => address.to_string(),
-        }
+        get_denom_string(&self.denom)
+    }
+
+    /// the earliest unclaimed rewards may expire: a minimum window after the
+    /// distribution's active epoch ends, so members always have time to claim
+    /// what they earned. never if the epoch never ends.
+    pub fn earliest_unclaimed_expiry(&self) -> StdResult<Expiration> {
+        let window = match self.active_epoch.ends_at {
+            Expiration::AtHeight(_) => Duration::Height(MIN_UNCLAIMED_EXPIRY_BLOCKS),
+            Expiration::AtTime(_) => Duration::Time(MIN_UNCLAIMED_EXPIRY_SECONDS),
+            Expiration::Never {} => return Ok(Expiration::Never {}),
+        };
+        self.active_epoch.ends_at.add(window)
+    }
+
+    /// ensures `expiry` may replace the distribution's unclaimed expiry. it
+    /// must be in the same unit as the distribution's end and at least the
+    /// minimum window after it, and once set may never be made earlier.
+    pub fn validate_unclaimed_expiry(
+        &self,
+        id: u64,
+        expiry: &Expiration,
+    ) -> Result<(), ContractError> {
+        let not_before = |earliest: &Expiration| {
+            matches!(
+                expiry.partial_cmp(earliest),
+                Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal)
+            )
+        };
+        ensure!(
+            self.unclaimed_expiry == Expiration::Never {} || not_before(&self.unclaimed_expiry),
+            ContractError::UnclaimedExpiryShortened { id }
+        );
+        let earliest = self.earliest_unclaimed_expiry()?;
+        ensure!(
+            *expiry == Expiration::Never {} || not_before(&earliest),
+            ContractError::UnclaimedExpiryTooSoon { id, earliest }
+        );
+        Ok(())
     }
 
-    /// Returns the latest time when rewards were distributed. Works by
-    /// comparing `current_block` with the distribution end time:
-    /// - If the end is `Never`, then no rewards are currently being
-    ///   distributed, so return the last update.
-    /// - If the end is `AtHeight(h)` or `AtTime(t)`, we compare the current
-    ///   block height or time with `h` or `t` respectively.
-    /// - If current block respective value is before the end, rewards are still
-    ///   being distributed. We therefore return the current block `height` or
-    ///   `time`, as this block is the most recent time rewards were
-    ///   distributed.
-    /// - If current block respective value is after the end, rewards are no
-    ///   longer being distributed. We therefore return the end `height` or
-    ///   `time`, as that was the last date where rewards were distributed.
-    pub fn get_latest_reward_distribution_time(&self, current_block: &BlockInfo) -> Expiration {
-        match self.active_epoch.ends_at {
-            Expiration::Never {} => self.active_epoch.last_updated_total_earned_puvp,
-            Expiration::AtHeight(ends_at_height) => {
-                Expiration::AtHeight(min(current_block.height, ends_at_height))
+    /// ensure the current block height is not before any height that has
+    /// already been accounted for. if it is, epoch end and last updated
+    /// computations would move backwards and corrupt the rewards puvp totals.
+    pub fn check_height(&self, block: &BlockInfo) -> Result<(), ContractError> {
+        for exp in [
+            self.active_epoch.started_at,
+            self.active_epoch.last_updated_total_earned_puvp,
+        ] {
+            if let Expiration::AtHeight(last_updated) = exp {
+                ensure!(
+                    block.height >= last_updated,
+                    ContractError::HeightRegression {
+                        last_updated,
+                        height: block.height
+                    }
+                );
             }
-            Expiration::AtTime(ends_at_time) => {
-                Expiration::AtTime(min(current_block.time, ends_at_time))
+        }
+        Ok(())
+    }
+
+    /// every address authorized to call every type of hook for this
+    /// distribution: its hook caller and those of its voting power sources.
+    pub fn get_all_hook_callers(&self) -> Vec<Addr> {
+        let mut callers = vec![self.hook_caller.clone()];
+        for source in &self.vp_sources {
+            if !callers.contains(&source.hook_caller) {
+                callers.push(source.hook_caller.clone());
             }
         }
+        callers
+    }
+
+    /// get the total voting power used to allocate rewards. this is the total
+    /// voting power at the snapshot height if one is set, otherwise at the
+    /// previous block.
+    pub fn get_total_vp(&self, deps: Deps, block: &BlockInfo) -> StdResult<Uint128> {
+        let height = self
+            .snapshot_height
+            .unwrap_or(block.height.saturating_sub(1));
+        self.get_total_vp_at_height(deps, height)
+    }
+
+    /// get the weighted sum of the total voting power of `vp_contract` and
+    /// every voting power source at a height.
+    pub fn get_total_vp_at_height(&self, deps: Deps, height: u64) -> StdResult<Uint128> {
+        self.vp_sources.iter().try_fold(
+            get_total_vp_at_height(deps, height, &self.vp_contract)?,
+            |total, source| {
+                let power = get_total_vp_at_height(deps, height, &source.vp_contract)?;
+                Ok(total.checked_add(source.apply_weight(power)?)?)
+            },
+        )
+    }
+
+    /// get the voting power of an address used to allocate rewards. this is
+    /// the weighted sum of the address's voting power in `vp_contract` and
+    /// every voting power source, at the snapshot height if one is set,
+    /// otherwise at the current block.
+    pub fn get_voting_power(
+        &self,
+        deps: Deps,
+        block: &BlockInfo,
+        addr: &Addr,
+    ) -> StdResult<Uint128> {
+        let height = self.snapshot_height.unwrap_or(block.height);
+        self.vp_sources.iter().try_fold(
+            get_voting_power_at_height(deps, height, &self.vp_contract, addr)?,
+            |total, source| {
+                let power = get_voting_power_at_height(deps, height, &source.vp_contract, addr)?;
+                Ok(total.checked_add(source.apply_weight(power)?)?)
+            },
+        )
+    }
+
+    /// whether this distribution has finished emitting the rewards it was
+    /// funded with. ended distributions do not earn more rewards unless they
+    /// are funded or updated again. perpetual distributions never end.
+    pub fn is_ended(&self, block: &BlockInfo) -> bool {
+        !matches!(
+            self.active_epoch.emission_rate,
+            EmissionRate::LinearPerpetual { .. }
+        ) && self.active_epoch.ends_at.is_expired(block)
     }
 
     /// get the total rewards to be distributed based on the active epoch's
     /// emission rate
     pub fn get_total_rewards(&self) -> StdResult<Uint128> {
-        match self.active_epoch.emission_rate {
-            EmissionRate::Paused {} => Ok(Uint128::zero()),
-            EmissionRate::Immediate {} => Ok(self.funded_amount),
+        self.active_epoch.get_total_rewards(self.funded_amount)
+    }
+
+    /// get the funds that have not been emitted as of `block`, including
+    /// rewards withheld from users that reached the per-user cap.
+    pub fn get_undistributed(&self, deps: Deps, block: &BlockInfo) -> StdResult<Uint128> {
+        if self.force_withdrawn {
+            return Ok(Uint128::zero());
+        }
+        let emitted = match self.active_epoch.emission_rate {
+            EmissionRate::Linear { .. } | EmissionRate::Decay { .. } => {
+                let mut epoch = self.active_epoch.clone();
+                epoch.ends_at = epoch.get_latest_distribution_time(block);
+                epoch.get_total_rewards(self.funded_amount)?
+            }
+            EmissionRate::LinearPerpetual { .. } => get_active_epoch_totals(deps, block, self)?.1,
+            EmissionRate::Immediate {} => self.funded_amount,
+            EmissionRate::Paused {} => Uint128::zero(),
+        };
+        Ok(self
+            .funded_amount
+            .checked_sub(emitted)?
+            .checked_add(self.capped_rewards)?)
+    }
+
+    /// get the active emission rate normalized to rewards per block or per
+    /// second. decaying emission rates are normalized in their current
+    /// period. the rates are zero once the distribution stops emitting.
+    pub fn get_normalized_emission(
+        &self,
+        deps: Deps,
+        block: &BlockInfo,
+    ) -> StdResult<Option<NormalizedEmission>> {
+        let (amount, duration) = match self.active_epoch.emission_rate {
             EmissionRate::Linear {
                 amount, duration, ..
+            }
+            | EmissionRate::LinearPerpetual { amount, duration } => (amount, duration),
+            EmissionRate::Decay {
+                initial_amount,
+                duration,
+                decay_factor,
             } => {
-                let epoch_duration =
-                    get_exp_diff(&self.active_epoch.ends_at, &self.active_epoch.started_at)?;
+                let scalar = get_duration_scalar(&duration);
+                let elapsed = get_exp_diff(
+                    &self.active_epoch.get_latest_distribution_time(block),
+                    &self.active_epoch.started_at,
+                )?;
+                let period_start = elapsed - elapsed % scalar;
+                let amount = get_decay_emitted(
+                    initial_amount,
+                    &duration,
+                    decay_factor,
+                    period_start + scalar,
+                )?
+                .checked_sub(get_decay_emitted(
+                    initial_amount,
+                    &duration,
+                    decay_factor,
+                    period_start,
+                )?)?;
+                (amount, duration)
+            }
+            EmissionRate::Paused {} | EmissionRate::Immediate {} => return Ok(None),
+        };
+
+        let emitting = !self.funded_amount.is_zero()
+            && !self.frozen
+            && !self.force_withdrawn
+            && !self.is_ended(block);
+        let amount = if emitting { amount } else { Uint128::zero() };
 
-                let emission_rate_duration_scalar = match duration {
-                    Duration::Height(h) => h,
-                    Duration::Time(t) => t,
+        let scalar = Uint128::from(get_duration_scalar(&duration));
+        let rate = Decimal::checked_from_ratio(amount, scalar)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+        Ok(Some(match duration {
+            Duration::Height(_) => NormalizedEmission {
+                per_block: Some(rate),
+                per_second: None,
+                yearly_per_unit_power: None,
+            },
+            Duration::Time(_) => {
+                let total_vp = self.get_total_vp(deps, block).unwrap_or_default();
+                let yearly_per_unit_power = if total_vp.is_zero() {
+                    None
+                } else {
+                    Some(
+                        Decimal::checked_from_ratio(
+                            amount.checked_mul(Uint128::new(SECONDS_PER_YEAR))?,
+                            scalar.checked_mul(total_vp)?,
+                        )
+                        .map_err(|err| StdError::generic_err(err.to_string()))?,
+                    )
                 };
+                NormalizedEmission {
+                    per_block: None,
+                    per_second: Some(rate),
+                    yearly_per_unit_power,
+                }
+            }
+        }))
+    }
 
-                amount
-                    .checked_multiply_ratio(epoch_duration, emission_rate_duration_scalar)
-                    .map_err(|e| StdError::generic_err(e.to_string()))
+    /// get how much longer this distribution will emit rewards at its current
+    /// emission rate before running out of funds, in blocks or seconds
+    /// matching the emission rate's duration. `None` if it does not emit
+    /// rewards over time.
+    pub fn get_runway(&self, deps: Deps, block: &BlockInfo) -> StdResult<Option<Duration>> {
+        if self.force_withdrawn {
+            return Ok(None);
+        }
+        match self.active_epoch.emission_rate {
+            EmissionRate::LinearPerpetual { amount, duration } => {
+                let (_, emitted) = get_active_epoch_totals(deps, block, self)?;
+                let unemitted = self.funded_amount.checked_sub(emitted)?;
+                Ok(Some(get_linear_funded_duration(
+                    amount, &duration, unemitted,
+                )?))
+            }
+            EmissionRate::Linear { .. } | EmissionRate::Decay { .. } => {
+                Ok(match self.active_epoch.ends_at {
+                    Expiration::AtHeight(height) => {
+                        Some(Duration::Height(height.saturating_sub(block.height)))
+                    }
+                    Expiration::AtTime(time) => Some(Duration::Time(
+                        time.seconds().saturating_sub(block.time.seconds()),
+                    )),
+                    Expiration::Never {} => None,
+                })
             }
+            EmissionRate::Paused {} | EmissionRate::Immediate {} => Ok(None),
+        }
+    }
+
+    /// update the active epoch's total earned puvp, and the total emitted for
+    /// perpetual emission, up to the current block.
+    pub fn update_active_epoch(&mut self, deps: Deps, current_block: &BlockInfo) -> StdResult<()> {
+        let (total_earned_puvp, emitted) = get_active_epoch_totals(deps, current_block, self)?;
+        self.active_epoch.total_earned_puvp = total_earned_puvp;
+        self.active_epoch.emitted = emitted;
+        Ok(())
+    }
+
+    /// bring the active epoch up to date like `update_active_epoch` followed by
+    /// `bump_last_updated`, except that for linear emission only complete
+    /// emission periods are marked as accounted for. linear emission only
+    /// distributes complete periods, so this carries a partial period forward
+    /// instead of skipping it. returns whether any time was accounted for.
+    pub fn catch_up_active_epoch(
+        &mut self,
+        deps: Deps,
+        block: &BlockInfo,
+    ) -> Result<bool, ContractError> {
+        let last_updated = self.active_epoch.last_updated_total_earned_puvp;
+        self.update_active_epoch(deps, block)?;
+
+        if let EmissionRate::Linear { duration, .. } = self.active_epoch.emission_rate {
+            let scalar = get_duration_scalar(&duration);
+            let elapsed = get_exp_diff(
+                &self.active_epoch.get_latest_distribution_time(block),
+                &last_updated,
+            )?;
+            let accounted_for = elapsed - elapsed % scalar;
+            self.active_epoch.last_updated_total_earned_puvp = match last_updated {
+                Expiration::AtHeight(h) => Expiration::AtHeight(h + accounted_for),
+                Expiration::AtTime(t) => Expiration::AtTime(t.plus_seconds(accounted_for)),
+                Expiration::Never {} => Expiration::Never {},
+            };
+        } else {
+            self.active_epoch.bump_last_updated(block)?;
         }
+
+        Ok(self.active_epoch.last_updated_total_earned_puvp != last_updated)
+    }
+
+    /// remove `amount` from the funds that have not yet been emitted, ending
+    /// the distribution earlier. only linear, perpetual linear and decaying
+    /// emissions have undistributed funds that are emitted over time. returns whether
+    /// enough undistributed funds remained. the active epoch must be up to
+    /// date.
+    pub fn take_undistributed(&mut self, block: &BlockInfo, amount: Uint128) -> StdResult<bool> {
+        match self.active_epoch.emission_rate {
+            EmissionRate::Linear { .. } | EmissionRate::Decay { .. } => {
+                let Some(funded_amount) = self.funded_amount.checked_sub(amount).ok() else {
+                    return Ok(false);
+                };
+                let Some(duration) = self
+                    .active_epoch
+                    .emission_rate
+                    .get_funded_period_duration(funded_amount)?
+                else {
+                    return Ok(false);
+                };
+                // rewards up to the current block have already been earned,
+                // so the distribution must not end before it.
+                let ends_at = self.active_epoch.started_at.add(duration)?;
+                if ends_at.is_expired(block) {
+                    return Ok(false);
+                }
+                self.funded_amount = funded_amount;
+                self.active_epoch.ends_at = ends_at;
+                Ok(true)
+            }
+            EmissionRate::LinearPerpetual { .. } => {
+                let undistributed = self.funded_amount.checked_sub(self.active_epoch.emitted)?;
+                if undistributed < amount {
+                    return Ok(false);
+                }
+                self.funded_amount -= amount;
+                Ok(true)
+            }
+            EmissionRate::Paused {} | EmissionRate::Immediate {} => Ok(false),
+        }
+    }
+
+    /// return rewards withheld from users that reached the per-user cap to the
+    /// undistributed pool. linear, perpetual linear and decaying distributions
+    /// that are still emitting emit them again, extending the distribution. otherwise,
+    /// they are held until withdrawn. the active epoch must be up to date.
+    pub fn return_capped_rewards(&mut self, block: &BlockInfo, amount: Uint128) -> StdResult<()> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        match self.active_epoch.emission_rate {
+            EmissionRate::Linear { .. } | EmissionRate::Decay { .. }
+                if !self.active_epoch.ends_at.is_expired(block) =>
+            {
+                self.funded_amount = self.funded_amount.checked_add(amount)?;
+                if let Some(duration) = self
+                    .active_epoch
+                    .emission_rate
+                    .get_funded_period_duration(self.funded_amount)?
+                {
+                    self.active_epoch.ends_at = self.active_epoch.started_at.add(duration)?;
+                }
+            }
+            EmissionRate::LinearPerpetual { .. } => {
+                self.funded_amount = self.funded_amount.checked_add(amount)?;
+            }
+            _ => {
+                self.capped_rewards = self.capped_rewards.checked_add(amount)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// start a new epoch with the same emission rate once a linear or decaying
+    /// distribution's active epoch has ended, funded with the funds the ended
+    /// epoch did not emit plus `top_up`. the new epoch starts when the ended
+    /// one did, so that rewards are emitted without a gap no matter when the
+    /// rollover happens. returns the funds carried over.
+    pub fn roll_over(
+        &mut self,
+        deps: Deps,
+        block: &BlockInfo,
+        top_up: Uint128,
+    ) -> Result<Uint128, ContractError> {
+        // bring rewards up to date through the end of the ended epoch
+        self.update_active_epoch(deps, block)?;
+
+        let residual = self.funded_amount.checked_sub(self.get_total_rewards()?)?;
+        self.funded_amount = residual.checked_add(top_up)?;
+
+        let started_at = self.active_epoch.ends_at;
+        self.active_epoch.started_at = started_at;
+        self.active_epoch.last_updated_total_earned_puvp = started_at;
+        self.active_epoch.ends_at = match self
+            .active_epoch
+            .emission_rate
+            .get_funded_period_duration(self.funded_amount)?
+        {
+            Some(duration) => started_at.add(duration)?,
+            None => Expiration::Never {},
+        };
+
+        Ok(residual)
     }
 
     /// Finish current epoch early and start a new one with a new emission rate.
@@ -263,9 +1044,19 @@ impl DistributionState {
             return Ok(());
         }
 
+        ensure!(
+            !self.frozen,
+            ContractError::DistributionFrozen { id: self.id }
+        );
+        self.check_height(current_block)?;
+
+        // snapshot distributions can only distribute immediately
+        if self.snapshot_height.is_some() && (new_emission_rate != EmissionRate::Immediate {}) {
+            return Err(ContractError::SnapshotRequiresImmediateEmission {});
+        }
+
         // 1. finish current epoch by updating rewards and setting end to now
-        self.active_epoch.total_earned_puvp =
-            get_active_total_earned_puvp(deps, current_block, self)?;
+        self.update_active_epoch(deps, current_block)?;
         self.active_epoch.ends_at = match self.active_epoch.started_at {
             Expiration::Never {} => Expiration::Never {},
             Expiration::AtHeight(_) => Expiration::AtHeight(current_block.height),
@@ -314,14 +1105,7 @@ impl DistributionState {
             None => Expiration::Never {},
         };
 
-        let new_started_at = match new_emission_rate {
-            EmissionRate::Paused {} => Expiration::Never {},
-            EmissionRate::Immediate {} => Expiration::Never {},
-            EmissionRate::Linear { duration, .. } => match duration {
-                Duration::Height(_) => Expiration::AtHeight(current_block.height),
-                Duration::Time(_) => Expiration::AtTime(current_block.time),
-            },
-        };
+        let new_started_at = new_emission_rate.get_started_at(current_block);
 
         self.active_epoch = Epoch {
             emission_rate: new_emission_rate.clone(),
@@ -330,6 +1114,7 @@ impl DistributionState {
             // start the new active epoch with zero rewards earned
             total_earned_puvp: Uint256::zero(),
             last_updated_total_earned_puvp: new_started_at,
+            emitted: Uint128::zero(),
         };
 
         // if new emission rate is immediate, update total_earned_puvp with
@@ -367,7 +1152,7 @@ impl DistributionState {
 
         let curr = self.active_epoch.total_earned_puvp;
 
-        let prev_total_power = get_prev_block_total_vp(deps, block, &self.vp_contract)?;
+        let prev_total_power = self.get_total_vp(deps, block)?;
 
         // if no voting power is registered, error since rewards can't be
         // distributed.
@@ -375,10 +1160,7 @@ impl DistributionState {
             Err(ContractError::NoVotingPowerNoRewards {})
         } else {
             // the new rewards per unit voting power based on the funded amount
-            let new_rewards_puvp = Uint256::from(funded_amount_delta)
-                // this can never overflow since funded_amount is a Uint128
-                .checked_mul(scale_factor())?
-                .checked_div(prev_total_power.into())?;
+            let new_rewards_puvp = get_rewards_puvp(funded_amount_delta, prev_total_power)?;
 
             self.active_epoch.total_earned_puvp = curr.checked_add(new_rewards_puvp)?;
 
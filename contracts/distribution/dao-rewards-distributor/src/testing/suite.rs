@@ -1,20 +1,26 @@
 use std::borrow::BorrowMut;
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{coin, coins, to_json_binary, Addr, Coin, Empty, Timestamp, Uint128};
+use cosmwasm_std::{coin, coins, to_json_binary, Addr, Coin, Empty, StdResult, Timestamp, Uint128};
 use cw20::{Cw20Coin, Expiration, UncheckedDenom};
 use cw4::{Member, MemberListResponse};
-use cw_multi_test::{App, BankSudo, Executor, SudoMsg};
+use cw_multi_test::{App, AppResponse, BankSudo, Executor, SudoMsg};
 use cw_ownable::Action;
 use cw_utils::Duration;
 use dao_interface::voting::InfoResponse;
 
 use crate::{
     msg::{
-        CreateMsg, DistributionsResponse, ExecuteMsg, FundMsg, InstantiateMsg,
-        PendingRewardsResponse, QueryMsg, ReceiveCw20Msg,
+        ClaimDelegatesResponse, ClaimHistoryResponse, CreateMsg, DistributionHealth,
+        DistributionResponse, DistributionStats, DistributionStatsResponse, DistributionsResponse,
+        ExecuteMsg, FundMsg, HookCallersResponse, InstantiateMsg, ManagerAction,
+        PendingRewardsResponse, QueryMsg, ReceiveCw20Msg, SimulateUpdateResponse, TickPage,
+        TotalsByDenomResponse, UndistributedRewards, UndistributedRewardsResponse, VpSourceMsg,
+    },
+    state::{
+        ClaimRecord, DistributionManager, DistributionState, EmissionRate, FundingSchedule,
+        HookSequence, HookType, KeeperFee, Rollover,
     },
-    state::{DistributionState, EmissionRate},
     testing::cw20_setup::instantiate_cw20,
     ContractError,
 };
@@ -336,7 +342,7 @@ pub struct Suite {
 // SUITE QUERIES
 impl Suite {
     pub fn get_time_until_rewards_expiration(&mut self) -> u64 {
-        let distribution = &self.get_distributions().distributions[0];
+        let distribution = &self.get_distributions().distributions[0].state;
         let current_block = self.app.block_info();
         let (expiration_unit, current_unit) = match distribution.active_epoch.ends_at {
             cw20::Expiration::AtHeight(h) => (h, current_block.height),
@@ -394,15 +400,167 @@ impl Suite {
     }
 
     pub fn get_distribution(&mut self, id: u64) -> DistributionState {
-        let resp: DistributionState = self
-            .app
+        self.get_distribution_response(id).state
+    }
+
+    pub fn get_distribution_response(&mut self, id: u64) -> DistributionResponse {
+        self.app
             .wrap()
             .query_wasm_smart(
                 self.distribution_contract.clone(),
                 &QueryMsg::Distribution { id },
             )
+            .unwrap()
+    }
+
+    pub fn get_health(&mut self, id: u64) -> DistributionHealth {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.distribution_contract.clone(), &QueryMsg::Health { id })
+            .unwrap()
+    }
+
+    pub fn simulate_update(
+        &mut self,
+        id: u64,
+        emission_rate: EmissionRate,
+    ) -> StdResult<SimulateUpdateResponse> {
+        self.app.wrap().query_wasm_smart(
+            self.distribution_contract.clone(),
+            &QueryMsg::SimulateUpdate { id, emission_rate },
+        )
+    }
+
+    pub fn get_hook_callers(&mut self, id: u64) -> HookCallersResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::HookCallers { id },
+            )
+            .unwrap()
+    }
+
+    pub fn get_hook_sequence(&mut self, caller: &str) -> HookSequence {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::HookSequence {
+                    caller: caller.to_string(),
+                },
+            )
+            .unwrap()
+    }
+
+    pub fn get_claim_delegates(&mut self, staker: &str) -> ClaimDelegatesResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::ClaimDelegates {
+                    staker: staker.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    }
+
+    pub fn get_distribution_manager(&mut self, id: u64) -> DistributionManager {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::DistributionManager { id },
+            )
+            .unwrap()
+    }
+
+    pub fn get_totals_by_denom(&mut self) -> TotalsByDenomResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::TotalsByDenom {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    }
+
+    pub fn get_funding_schedule(&mut self, id: u64) -> FundingSchedule {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::FundingSchedule { id },
+            )
+            .unwrap()
+    }
+
+    pub fn get_rollover(&mut self, id: u64) -> Rollover {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::Rollover { id },
+            )
+            .unwrap()
+    }
+
+    pub fn get_claim_history(
+        &mut self,
+        address: &str,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<ClaimRecord> {
+        let res: ClaimHistoryResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::ClaimHistory {
+                    address: address.to_string(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap();
+        res.claims
+    }
+
+    pub fn get_undistributed_rewards(
+        &mut self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<UndistributedRewards> {
+        let res: UndistributedRewardsResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::UndistributedRewards { start_after, limit },
+            )
+            .unwrap();
+        res.distributions
+    }
+
+    pub fn get_distribution_stats(
+        &mut self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<DistributionStats> {
+        let res: DistributionStatsResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                self.distribution_contract.clone(),
+                &QueryMsg::DistributionStats { start_after, limit },
+            )
             .unwrap();
-        resp
+        res.stats
     }
 
     pub fn get_owner(&mut self) -> Addr {
@@ -427,30 +585,37 @@ impl Suite {
 // SUITE ASSERTIONS
 impl Suite {
     pub fn assert_ends_at(&mut self, expected: Expiration) {
-        let distribution = &self.get_distributions().distributions[0];
+        let distribution = &self.get_distributions().distributions[0].state;
         assert_eq!(distribution.active_epoch.ends_at, expected);
     }
 
     pub fn assert_started_at(&mut self, expected: Expiration) {
-        let distribution = &self.get_distributions().distributions[0];
+        let distribution = &self.get_distributions().distributions[0].state;
         assert_eq!(distribution.active_epoch.started_at, expected);
     }
 
     pub fn assert_amount(&mut self, expected: u128) {
-        let distribution = &self.get_distributions().distributions[0];
+        let distribution = &self.get_distributions().distributions[0].state;
         match distribution.active_epoch.emission_rate {
             EmissionRate::Paused {} => panic!("expected non-paused emission rate"),
             EmissionRate::Immediate {} => panic!("expected non-immediate emission rate"),
-            EmissionRate::Linear { amount, .. } => assert_eq!(amount, Uint128::new(expected)),
+            EmissionRate::Linear { amount, .. }
+            | EmissionRate::LinearPerpetual { amount, .. }
+            | EmissionRate::Decay {
+                initial_amount: amount,
+                ..
+            } => assert_eq!(amount, Uint128::new(expected)),
         }
     }
 
     pub fn assert_duration(&mut self, expected: u64) {
-        let distribution = &self.get_distributions().distributions[0];
+        let distribution = &self.get_distributions().distributions[0].state;
         match distribution.active_epoch.emission_rate {
             EmissionRate::Paused {} => panic!("expected non-paused emission rate"),
             EmissionRate::Immediate {} => panic!("expected non-immediate emission rate"),
-            EmissionRate::Linear { duration, .. } => assert_eq!(
+            EmissionRate::Linear { duration, .. }
+            | EmissionRate::LinearPerpetual { duration, .. }
+            | EmissionRate::Decay { duration, .. } => assert_eq!(
                 match duration {
                     Duration::Height(h) => h,
                     Duration::Time(t) => t,
@@ -530,6 +695,32 @@ impl Suite {
             .unwrap()
     }
 
+    pub fn force_withdraw(&mut self, id: u64) {
+        let msg = ExecuteMsg::ForceWithdraw { id };
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn force_withdraw_error(&mut self, id: u64) -> ContractError {
+        let msg = ExecuteMsg::ForceWithdraw { id };
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap_err()
+            .downcast()
+            .unwrap()
+    }
+
     pub fn register_hook(&mut self, addr: Addr) {
         let msg = cw4_group::msg::ExecuteMsg::AddHook {
             addr: self.distribution_contract.to_string(),
@@ -554,7 +745,10 @@ impl Suite {
             },
             hook_caller: hook_caller.to_string(),
             vp_contract: self.voting_power_addr.to_string(),
+            vp_sources: vec![],
             withdraw_destination: reward_config.destination,
+            snapshot_height: None,
+            max_rewards_per_user: None,
         });
 
         // include funds if provided
@@ -578,6 +772,27 @@ impl Suite {
             .unwrap();
     }
 
+    pub fn create_pool(
+        &mut self,
+        id: u64,
+        denom: UncheckedDenom,
+        emission_rate: EmissionRate,
+    ) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::CreatePool {
+                    id,
+                    denom,
+                    emission_rate,
+                },
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
     pub fn mint_native(&mut self, coin: Coin, dest: &str) {
         // mint the tokens to be funded
         self.app
@@ -661,6 +876,90 @@ impl Suite {
             .unwrap();
     }
 
+    pub fn claim_all_rewards(
+        &mut self,
+        address: &str,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(address),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::ClaimAll { start_after, limit },
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
+    pub fn claim_many_rewards(
+        &mut self,
+        address: &str,
+        ids: Vec<u64>,
+        strict: bool,
+    ) -> Result<AppResponse, ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(address),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::ClaimMany { ids, strict },
+                &[],
+            )
+            .map_err(|e| e.downcast().unwrap())
+    }
+
+    pub fn set_claim_delegate(&mut self, staker: &str, delegate: &str, destination: &str) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(staker),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::SetClaimDelegate {
+                    delegate: delegate.to_string(),
+                    destination: destination.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn claim_rewards_for(
+        &mut self,
+        delegate: &str,
+        staker: &str,
+        id: u64,
+    ) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(delegate),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::ClaimFor {
+                    id,
+                    staker: staker.to_string(),
+                },
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
+    pub fn update_manager(
+        &mut self,
+        sender: &str,
+        id: u64,
+        action: ManagerAction,
+    ) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::UpdateManager { id, action },
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
     #[allow(dead_code)]
     pub fn stake_cw20_tokens(&mut self, amount: u128, sender: &str) {
         let msg = cw20::Cw20ExecuteMsg::Send {
@@ -721,16 +1020,26 @@ impl Suite {
         epoch_rewards: u128,
         continuous: bool,
     ) {
-        let msg: ExecuteMsg = ExecuteMsg::Update {
+        self.update_emission_rate_to(
             id,
-            emission_rate: Some(EmissionRate::Linear {
+            EmissionRate::Linear {
                 amount: Uint128::new(epoch_rewards),
                 duration: epoch_duration,
                 continuous,
-            }),
+            },
+        );
+    }
+
+    pub fn update_emission_rate_to(&mut self, id: u64, emission_rate: EmissionRate) {
+        let msg: ExecuteMsg = ExecuteMsg::Update {
+            id,
+            emission_rate: Some(emission_rate),
             vp_contract: None,
+            vp_sources: None,
             hook_caller: None,
             withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
         };
 
         let _resp = self
@@ -749,8 +1058,11 @@ impl Suite {
             id,
             emission_rate: Some(EmissionRate::Immediate {}),
             vp_contract: None,
+            vp_sources: None,
             hook_caller: None,
             withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
         };
 
         let _resp = self
@@ -769,8 +1081,11 @@ impl Suite {
             id,
             emission_rate: Some(EmissionRate::Paused {}),
             vp_contract: None,
+            vp_sources: None,
             hook_caller: None,
             withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
         };
 
         let _resp = self
@@ -789,8 +1104,34 @@ impl Suite {
             id,
             emission_rate: None,
             vp_contract: Some(vp_contract.to_string()),
+            vp_sources: None,
             hook_caller: None,
             withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
+        };
+
+        let _resp = self
+            .app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn update_vp_sources(&mut self, id: u64, vp_sources: Vec<VpSourceMsg>) {
+        let msg: ExecuteMsg = ExecuteMsg::Update {
+            id,
+            emission_rate: None,
+            vp_contract: None,
+            vp_sources: Some(vp_sources),
+            hook_caller: None,
+            withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
         };
 
         let _resp = self
@@ -809,8 +1150,11 @@ impl Suite {
             id,
             emission_rate: None,
             vp_contract: None,
+            vp_sources: None,
             hook_caller: Some(hook_caller.to_string()),
             withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
         };
 
         let _resp = self
@@ -824,13 +1168,182 @@ impl Suite {
             .unwrap();
     }
 
+    pub fn update_hook_callers(
+        &mut self,
+        id: u64,
+        hook_type: HookType,
+        to_add: &[&str],
+        to_remove: &[&str],
+    ) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::UpdateHookCallers {
+                    id,
+                    hook_type,
+                    to_add: to_add.iter().map(|a| a.to_string()).collect(),
+                    to_remove: to_remove.iter().map(|a| a.to_string()).collect(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn update_keeper_fee(&mut self, id: u64, keeper_fee: Option<KeeperFee>) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::UpdateKeeperFee { id, keeper_fee },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn poke(&mut self, sender: &str, id: u64) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::Poke { id },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn update_member_updaters(&mut self, id: u64, updaters: Option<Vec<&str>>) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::UpdateMemberUpdaters {
+                    id,
+                    updaters: updaters.map(|u| u.into_iter().map(String::from).collect()),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn sync_members(
+        &mut self,
+        sender: &str,
+        id: u64,
+        addresses: Vec<&str>,
+    ) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::UpdateMembers {
+                    id,
+                    addresses: addresses.into_iter().map(String::from).collect(),
+                },
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
+    pub fn create_funding_schedule(
+        &mut self,
+        id: u64,
+        amount: u128,
+        interval: Duration,
+        source: Option<&str>,
+        funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::CreateFundingSchedule {
+                    id,
+                    amount: Uint128::new(amount),
+                    interval,
+                    source: source.map(|source| source.to_string()),
+                },
+                funds,
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
+    pub fn remove_funding_schedule(&mut self, id: u64) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::RemoveFundingSchedule { id },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn create_rollover(
+        &mut self,
+        id: u64,
+        top_up: u128,
+        max_rollovers: u32,
+        source: Option<&str>,
+        funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::CreateRollover {
+                    id,
+                    top_up: Uint128::new(top_up),
+                    max_rollovers,
+                    source: source.map(|source| source.to_string()),
+                },
+                funds,
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
+    pub fn remove_rollover(&mut self, id: u64) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::RemoveRollover { id },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn tick(&mut self) {
+        self.tick_page(TickPage::default(), TickPage::default());
+    }
+
+    pub fn tick_page(&mut self, schedules: TickPage, rollovers: TickPage) -> AppResponse {
+        self.app
+            .execute_contract(
+                Addr::unchecked("keeper"),
+                self.distribution_contract.clone(),
+                &ExecuteMsg::Tick {
+                    schedules: Some(schedules),
+                    rollovers: Some(rollovers),
+                },
+                &[],
+            )
+            .unwrap()
+    }
+
     pub fn update_withdraw_destination(&mut self, id: u64, withdraw_destination: &str) {
         let msg: ExecuteMsg = ExecuteMsg::Update {
             id,
             emission_rate: None,
             vp_contract: None,
+            vp_sources: None,
             hook_caller: None,
             withdraw_destination: Some(withdraw_destination.to_string()),
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
         };
 
         let _resp = self
@@ -844,6 +1357,33 @@ impl Suite {
             .unwrap();
     }
 
+    pub fn update_unclaimed_expiry(
+        &mut self,
+        id: u64,
+        unclaimed_expiry: Expiration,
+    ) -> Result<(), ContractError> {
+        let msg: ExecuteMsg = ExecuteMsg::Update {
+            id,
+            emission_rate: None,
+            vp_contract: None,
+            vp_sources: None,
+            hook_caller: None,
+            withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: Some(unclaimed_expiry),
+        };
+
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.distribution_contract.clone(),
+                &msg,
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
     pub fn update_members(&mut self, add: Vec<Member>, remove: Vec<String>) {
         let msg = cw4_group::msg::ExecuteMsg::UpdateMembers { remove, add };
 
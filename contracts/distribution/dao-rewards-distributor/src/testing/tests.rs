@@ -1,16 +1,31 @@
 use std::borrow::BorrowMut;
 
-use cosmwasm_std::{coin, coins, to_json_binary, Addr, Timestamp};
+use cosmwasm_std::{
+    coin, coins, to_json_binary, Addr, Decimal, Empty, Event, HexBinary, Timestamp,
+};
 use cosmwasm_std::{Uint128, Uint256};
 use cw2::ContractVersion;
-use cw20::{Cw20Coin, Expiration, UncheckedDenom};
-use cw4::Member;
+use cw20::{Cw20Coin, Denom, Expiration, UncheckedDenom};
+use cw4::{Member, MemberChangedHookMsg};
 use cw_multi_test::Executor;
 use cw_utils::Duration;
+use dao_hooks::stake::StakeChangedHookMsg;
 use dao_interface::voting::InfoResponse;
+use dao_testing::contracts::cw721_base_contract;
+use sha2::{Digest, Sha256};
 
-use crate::msg::{CreateMsg, FundMsg};
-use crate::state::{EmissionRate, Epoch};
+use crate::msg::{
+    ClaimDelegate, CreateMsg, CreateRaffleMsg, DenomTotals, DistributionHealth, DistributionStats,
+    FundMsg, ManagerAction, QueryMsg, RaffleDrawsResponse, ReceiveNftMsg, SimulateUpdateResponse,
+    TickPage, UndistributedRewards, VpSourceMsg,
+};
+use crate::raffles::MAX_RAFFLE_ENTRANTS;
+use crate::state::{
+    ClaimRecord, DistributionManager, EmissionRate, Epoch, FundingSchedule, FundingSource,
+    HookType, KeeperFee, NormalizedEmission, RaffleDraw, RaffleState, Rollover, UserRewardState,
+    MIN_UNCLAIMED_EXPIRY_BLOCKS, PROCESSED_HOOKS, USER_REWARDS,
+};
+use crate::testing::cw4_setup::setup_cw4_test;
 use crate::testing::native_setup::setup_native_token_test;
 use crate::ContractError;
 use crate::{
@@ -19,7 +34,7 @@ use crate::{
 };
 
 use super::{
-    suite::{RewardsConfig, SuiteBuilder},
+    suite::{RewardsConfig, Suite, SuiteBuilder},
     ALT_DENOM, OWNER,
 };
 
@@ -835,7 +850,10 @@ fn test_immediate_emission() {
         emission_rate: EmissionRate::Immediate {},
         hook_caller: suite.staking_addr.to_string(),
         vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
         withdraw_destination: None,
+        snapshot_height: None,
+        max_rewards_per_user: None,
     });
 
     // create distribution
@@ -923,7 +941,10 @@ fn test_immediate_emission_fails_if_no_voting_power() {
         emission_rate: EmissionRate::Immediate {},
         hook_caller: suite.staking_addr.to_string(),
         vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
         withdraw_destination: None,
+        snapshot_height: None,
+        max_rewards_per_user: None,
     });
 
     // create and fund distribution
@@ -938,6 +959,159 @@ fn test_immediate_emission_fails_if_no_voting_power() {
         .unwrap();
 }
 
+#[test]
+fn test_snapshot_immediate_emission() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // skip 2 blocks so the initial stakes are reflected in the snapshot
+    suite.skip_blocks(2);
+    let snapshot_height = suite.app.block_info().height - 1;
+
+    // voting power changes after the snapshot should not affect rewards
+    suite.unstake_native_tokens(ADDR1, 100);
+    suite.mint_native(coin(200, DENOM), ADDR4);
+    suite.stake_native_tokens(ADDR4, 200);
+    suite.skip_blocks(2);
+
+    suite.mint_native(coin(100_000_000, ALT_DENOM), OWNER);
+
+    let execute_create_msg = ExecuteMsg::Create(CreateMsg {
+        denom: cw20::UncheckedDenom::Native(ALT_DENOM.to_string()),
+        emission_rate: EmissionRate::Immediate {},
+        hook_caller: suite.staking_addr.to_string(),
+        vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
+        withdraw_destination: None,
+        snapshot_height: Some(snapshot_height),
+        max_rewards_per_user: None,
+    });
+
+    // create distribution
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &execute_create_msg,
+            &coins(100_000_000, ALT_DENOM),
+        )
+        .unwrap();
+
+    assert_eq!(
+        suite.get_distribution(2).snapshot_height,
+        Some(snapshot_height)
+    );
+
+    // rewards are allocated based on the snapshot
+    suite.assert_pending_rewards(ADDR1, 2, 50_000_000);
+    suite.assert_pending_rewards(ADDR2, 2, 25_000_000);
+    suite.assert_pending_rewards(ADDR3, 2, 25_000_000);
+    suite.assert_pending_rewards(ADDR4, 2, 0);
+
+    // later funding continues to use the snapshot
+    suite.skip_blocks(2);
+    suite.fund_native(2, coin(100_000_000, ALT_DENOM));
+
+    suite.assert_pending_rewards(ADDR1, 2, 2 * 50_000_000);
+    suite.assert_pending_rewards(ADDR2, 2, 2 * 25_000_000);
+    suite.assert_pending_rewards(ADDR3, 2, 2 * 25_000_000);
+    suite.assert_pending_rewards(ADDR4, 2, 0);
+
+    // ADDR1 can claim despite having unstaked since the snapshot
+    suite.claim_rewards(ADDR1, 2);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 2 * 50_000_000);
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+
+    // snapshot distributions cannot switch to a linear emission rate
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Update {
+                id: 2,
+                emission_rate: Some(EmissionRate::Linear {
+                    amount: Uint128::new(1_000),
+                    duration: Duration::Height(10),
+                    continuous: true,
+                }),
+                vp_contract: None,
+                vp_sources: None,
+                hook_caller: None,
+                withdraw_destination: None,
+                max_rewards_per_user: None,
+                unclaimed_expiry: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::SnapshotRequiresImmediateEmission {});
+}
+
+#[test]
+#[should_panic(expected = "Snapshot height must be in the past")]
+fn test_snapshot_height_in_future() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.skip_blocks(2);
+
+    let execute_create_msg = ExecuteMsg::Create(CreateMsg {
+        denom: cw20::UncheckedDenom::Native(ALT_DENOM.to_string()),
+        emission_rate: EmissionRate::Immediate {},
+        hook_caller: suite.staking_addr.to_string(),
+        vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
+        withdraw_destination: None,
+        snapshot_height: Some(suite.app.block_info().height),
+        max_rewards_per_user: None,
+    });
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &execute_create_msg,
+            &[],
+        )
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Snapshot distributions must use an immediate emission rate")]
+fn test_snapshot_requires_immediate_emission() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.skip_blocks(2);
+
+    let execute_create_msg = ExecuteMsg::Create(CreateMsg {
+        denom: cw20::UncheckedDenom::Native(ALT_DENOM.to_string()),
+        emission_rate: EmissionRate::Linear {
+            amount: Uint128::new(1_000),
+            duration: Duration::Height(10),
+            continuous: true,
+        },
+        hook_caller: suite.staking_addr.to_string(),
+        vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
+        withdraw_destination: None,
+        snapshot_height: Some(1),
+        max_rewards_per_user: None,
+    });
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &execute_create_msg,
+            &[],
+        )
+        .unwrap();
+}
+
 #[test]
 fn test_transition_to_immediate() {
     let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
@@ -1755,6 +1929,121 @@ fn test_withdraw_404() {
     suite.withdraw(3);
 }
 
+#[test]
+fn test_force_withdraw() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // skip 1/10th of the time
+    suite.skip_blocks(100_000);
+
+    // only user 1 claims their rewards
+    suite.claim_rewards(ADDR1, 1);
+
+    // rewards can't be swept while they are still being distributed, nor may
+    // they expire right away.
+    assert_eq!(
+        suite.force_withdraw_error(1),
+        ContractError::DistributionNotEnded { id: 1 }
+    );
+    let height = suite.app.block_info().height;
+    assert_eq!(
+        suite
+            .update_unclaimed_expiry(1, Expiration::AtHeight(height))
+            .unwrap_err(),
+        ContractError::UnclaimedExpiryTooSoon {
+            id: 1,
+            earliest: Expiration::AtHeight(1_000_000 + MIN_UNCLAIMED_EXPIRY_BLOCKS),
+        }
+    );
+    assert_eq!(
+        suite
+            .update_unclaimed_expiry(1, Expiration::AtTime(suite.app.block_info().time))
+            .unwrap_err(),
+        ContractError::UnclaimedExpiryTooSoon {
+            id: 1,
+            earliest: Expiration::AtHeight(1_000_000 + MIN_UNCLAIMED_EXPIRY_BLOCKS),
+        }
+    );
+
+    // withdrawing ends the distribution, but unclaimed rewards never expire
+    // by default.
+    suite.withdraw(1);
+    assert_eq!(
+        suite.force_withdraw_error(1),
+        ContractError::UnclaimedRewardsNotExpired { id: 1 }
+    );
+
+    let expiry = height + MIN_UNCLAIMED_EXPIRY_BLOCKS;
+    suite
+        .update_unclaimed_expiry(1, Expiration::AtHeight(expiry + 10))
+        .unwrap();
+
+    // once set, the expiry may be extended but not shortened.
+    assert_eq!(
+        suite
+            .update_unclaimed_expiry(1, Expiration::AtHeight(expiry))
+            .unwrap_err(),
+        ContractError::UnclaimedExpiryShortened { id: 1 }
+    );
+    suite
+        .update_unclaimed_expiry(1, Expiration::AtHeight(expiry + 20))
+        .unwrap();
+
+    suite.skip_blocks(MIN_UNCLAIMED_EXPIRY_BLOCKS + 10);
+    assert_eq!(
+        suite.force_withdraw_error(1),
+        ContractError::UnclaimedRewardsNotExpired { id: 1 }
+    );
+
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR2, 1, 2_500_000);
+    suite.assert_pending_rewards(ADDR3, 1, 2_500_000);
+
+    // everything held for the distribution is clawed back, including the
+    // rewards users 2 and 3 never claimed
+    let distribution_contract = suite.distribution_contract.to_string();
+    let owner_balance = suite.get_balance_native(OWNER, DENOM);
+    suite.force_withdraw(1);
+    suite.assert_native_balance(distribution_contract.as_str(), DENOM, 0);
+    suite.assert_native_balance(OWNER, DENOM, owner_balance + 5_000_000);
+
+    let totals = suite.get_totals_by_denom().totals;
+    assert_eq!(totals[0].claimed, Uint128::new(5_000_000));
+    assert_eq!(totals[0].withdrawn, Uint128::new(95_000_000));
+    assert_eq!(totals[0].pending, Uint128::zero());
+
+    // users forfeit their unclaimed rewards
+    suite.assert_pending_rewards(ADDR2, 1, 0);
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ADDR2),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Claim { id: 1 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::DistributionForceWithdrawn { id: 1 });
+    assert_eq!(
+        suite.claim_all_rewards(ADDR3, None, None).unwrap_err(),
+        ContractError::NoRewardsClaimable {}
+    );
+
+    // the distribution no longer changes, but staking is not blocked
+    assert_eq!(suite.get_health(1), DistributionHealth::ForceWithdrawn {});
+    assert_eq!(
+        suite.force_withdraw_error(1),
+        ContractError::DistributionForceWithdrawn { id: 1 }
+    );
+    assert_eq!(
+        suite.withdraw_error(1),
+        ContractError::DistributionForceWithdrawn { id: 1 }
+    );
+    suite.unstake_native_tokens(ADDR2, 50);
+}
+
 #[test]
 #[should_panic(expected = "Distribution not found with ID 3")]
 fn test_claim_404() {
@@ -2136,6 +2425,7 @@ fn test_fund_native_on_create() {
             ends_at: Expiration::AtHeight(10_000_000),
             total_earned_puvp: Uint256::zero(),
             last_updated_total_earned_puvp: Expiration::AtHeight(0),
+            emitted: Uint128::zero(),
         }
     );
 
@@ -2162,7 +2452,10 @@ fn test_fund_native_with_other_denom() {
         },
         hook_caller: suite.staking_addr.to_string(),
         vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
         withdraw_destination: None,
+        snapshot_height: None,
+        max_rewards_per_user: None,
     });
 
     // create distribution with other denom provided
@@ -2194,7 +2487,10 @@ fn test_fund_native_multiple_denoms() {
         },
         hook_caller: suite.staking_addr.to_string(),
         vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
         withdraw_destination: None,
+        snapshot_height: None,
+        max_rewards_per_user: None,
     });
 
     // create distribution with 0 amount
@@ -2235,7 +2531,10 @@ fn test_fund_native_on_create_cw20() {
         },
         hook_caller: suite.staking_addr.to_string(),
         vp_contract: suite.voting_power_addr.to_string(),
+        vp_sources: vec![],
         withdraw_destination: None,
+        snapshot_height: None,
+        max_rewards_per_user: None,
     });
 
     // create cw20 distribution with native funds provided
@@ -2361,3 +2660,2689 @@ fn test_query_info() {
         }
     );
 }
+
+#[test]
+fn test_height_regression_freezes_distribution() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // skip 1/10th of the time and claim so rewards are accounted for up to
+    // the current height
+    suite.skip_blocks(100_000);
+    suite.claim_rewards(ADDR1, 1);
+    suite.assert_native_balance(ADDR1, DENOM, 5_000_000);
+
+    let last_updated = suite.app.block_info().height;
+    assert_eq!(
+        suite
+            .get_distribution(1)
+            .active_epoch
+            .last_updated_total_earned_puvp,
+        Expiration::AtHeight(last_updated)
+    );
+
+    // move the block height back to before the last update
+    suite.app.update_block(|b| b.height -= 10);
+
+    // claiming errors instead of double counting the regressed blocks
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ADDR2),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Claim { id: 1 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::HeightRegression {
+            last_updated,
+            height: last_updated - 10,
+        }
+    );
+
+    // voting power changes are not blocked, but freeze the distribution
+    suite.mint_native(coin(100, DENOM), ADDR4);
+    suite.stake_native_tokens(ADDR4, 100);
+    assert!(suite.get_distribution(1).frozen);
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ADDR2),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Claim { id: 1 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::DistributionFrozen { id: 1 });
+
+    // the owner can only unfreeze once the chain has caught up
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Unfreeze { id: 1 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::HeightRegression {
+            last_updated,
+            height: last_updated - 10,
+        }
+    );
+
+    suite.skip_blocks(10);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Unfreeze { id: 1 },
+            &[],
+        )
+        .unwrap();
+    assert!(!suite.get_distribution(1).frozen);
+
+    suite.claim_rewards(ADDR2, 1);
+    suite.assert_native_balance(ADDR2, DENOM, 2_500_000);
+}
+
+#[test]
+fn test_health() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.assert_ends_at(Expiration::AtHeight(1_000_000));
+    assert_eq!(suite.get_health(1), DistributionHealth::Healthy {});
+
+    // less than one emission period (10 blocks) of funding remains
+    let height = suite.app.block_info().height;
+    suite.skip_blocks(1_000_000 - height - 5);
+    assert_eq!(
+        suite.get_health(1),
+        DistributionHealth::EndingSoon {
+            remaining: Duration::Height(5)
+        }
+    );
+
+    suite.skip_blocks(5);
+    assert_eq!(suite.get_health(1), DistributionHealth::Expired {});
+
+    // a distribution that has not been funded yet
+    let hook_caller = suite.staking_addr.to_string();
+    suite.create(
+        RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: true,
+        },
+        &hook_caller,
+        None,
+    );
+    assert_eq!(suite.get_health(2), DistributionHealth::Underfunded {});
+}
+
+#[test]
+fn test_simulate_update() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.skip_blocks(100_000);
+
+    // 90_000_000 tokens remain to be distributed at 200 per block.
+    let emission_rate = EmissionRate::Linear {
+        amount: Uint128::new(2_000),
+        duration: Duration::Height(10),
+        continuous: true,
+    };
+    let simulated = suite.simulate_update(1, emission_rate).unwrap();
+    let height = suite.app.block_info().height;
+    assert_eq!(
+        simulated,
+        SimulateUpdateResponse {
+            ends_at: Expiration::AtHeight(height + 450_000),
+            rate: Decimal::from_atomics(200u128, 0).unwrap(),
+            daily_reward_per_1000_power: None,
+        }
+    );
+
+    // simulating does not update the distribution.
+    suite.assert_ends_at(Expiration::AtHeight(1_000_000));
+    suite.update_emission_rate(1, Duration::Height(10), 2_000, true);
+    suite.assert_ends_at(simulated.ends_at);
+
+    let simulated = suite.simulate_update(1, EmissionRate::Paused {}).unwrap();
+    assert_eq!(simulated.ends_at, Expiration::Never {});
+    assert_eq!(simulated.rate, Decimal::zero());
+
+    let err = suite
+        .simulate_update(
+            1,
+            EmissionRate::Linear {
+                amount: Uint128::zero(),
+                duration: Duration::Height(10),
+                continuous: true,
+            },
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("amount"));
+}
+
+#[test]
+fn test_simulate_update_time_based() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::CW4)
+        .with_rewards_config(RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(DENOM.to_string()),
+            duration: Duration::Time(10),
+            destination: None,
+            continuous: true,
+        })
+        .build();
+
+    // voting power is read from the previous block.
+    suite.skip_seconds(10);
+
+    // 100 tokens per second are split between 4 units of voting power.
+    let simulated = suite
+        .simulate_update(
+            1,
+            EmissionRate::LinearPerpetual {
+                amount: Uint128::new(1_000),
+                duration: Duration::Time(10),
+            },
+        )
+        .unwrap();
+    assert_eq!(simulated.rate, Decimal::from_atomics(100u128, 0).unwrap());
+    assert_eq!(
+        simulated.daily_reward_per_1000_power,
+        Some(Decimal::from_atomics(2_160_000_000u128, 0).unwrap())
+    );
+}
+
+#[test]
+fn test_linear_perpetual() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.mint_native(coin(1_000, ALT_DENOM), OWNER);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Create(CreateMsg {
+                denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+                emission_rate: EmissionRate::LinearPerpetual {
+                    amount: Uint128::new(100),
+                    duration: Duration::Height(10),
+                },
+                hook_caller: suite.staking_addr.to_string(),
+                vp_contract: suite.voting_power_addr.to_string(),
+                vp_sources: vec![],
+                withdraw_destination: None,
+                snapshot_height: None,
+                max_rewards_per_user: None,
+            }),
+            &coins(1_000, ALT_DENOM),
+        )
+        .unwrap();
+
+    // perpetual distributions never end
+    let distribution = suite.get_distribution(2);
+    assert_eq!(distribution.active_epoch.ends_at, Expiration::Never {});
+
+    suite.skip_blocks(50);
+    suite.assert_pending_rewards(ADDR1, 2, 250);
+    suite.assert_pending_rewards(ADDR2, 2, 125);
+    suite.assert_pending_rewards(ADDR3, 2, 125);
+    assert_eq!(suite.get_health(2), DistributionHealth::Healthy {});
+
+    suite.skip_blocks(45);
+    assert_eq!(
+        suite.get_health(2),
+        DistributionHealth::EndingSoon {
+            remaining: Duration::Height(5)
+        }
+    );
+
+    // emission stops once all funding has been emitted, never promising more
+    // than was funded
+    suite.skip_blocks(55);
+    suite.assert_pending_rewards(ADDR1, 2, 500);
+    suite.assert_pending_rewards(ADDR2, 2, 250);
+    suite.assert_pending_rewards(ADDR3, 2, 250);
+    assert_eq!(suite.get_health(2), DistributionHealth::Underfunded {});
+
+    suite.claim_rewards(ADDR1, 2);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 500);
+
+    // funding resumes emission from the current block without backfilling
+    // the gap
+    suite.skip_blocks(100);
+    suite.fund_native(2, coin(1_000, ALT_DENOM));
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+    suite.assert_pending_rewards(ADDR2, 2, 250);
+
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR1, 2, 50);
+    suite.assert_pending_rewards(ADDR2, 2, 275);
+    suite.assert_pending_rewards(ADDR3, 2, 275);
+
+    // withdrawing claws back all unemitted funds and pauses emission
+    suite.withdraw(2);
+    suite.assert_native_balance(OWNER, ALT_DENOM, 900);
+    assert_eq!(suite.get_health(2), DistributionHealth::Underfunded {});
+
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR1, 2, 50);
+    suite.assert_pending_rewards(ADDR2, 2, 275);
+    suite.assert_pending_rewards(ADDR3, 2, 275);
+}
+
+#[test]
+fn test_decay() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.mint_native(coin(1_750, ALT_DENOM), OWNER);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Create(CreateMsg {
+                denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+                emission_rate: EmissionRate::Decay {
+                    initial_amount: Uint128::new(1_000),
+                    duration: Duration::Height(10),
+                    decay_factor: Decimal::percent(50),
+                },
+                hook_caller: suite.staking_addr.to_string(),
+                vp_contract: suite.voting_power_addr.to_string(),
+                vp_sources: vec![],
+                withdraw_destination: None,
+                snapshot_height: None,
+                max_rewards_per_user: None,
+            }),
+            &coins(1_750, ALT_DENOM),
+        )
+        .unwrap();
+
+    // 1_000 + 500 + 250 is funded, so the distribution ends after three
+    // periods
+    let height = suite.app.block_info().height;
+    let distribution = suite.get_distribution(2);
+    assert_eq!(
+        distribution.active_epoch.ends_at,
+        Expiration::AtHeight(height + 30)
+    );
+
+    suite.skip_blocks(5);
+    suite.assert_pending_rewards(ADDR1, 2, 250);
+    suite.assert_pending_rewards(ADDR2, 2, 125);
+    suite.assert_pending_rewards(ADDR3, 2, 125);
+
+    // each period emits half as much as the one before it
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR1, 2, 625);
+    suite.assert_pending_rewards(ADDR2, 2, 312);
+    suite.assert_pending_rewards(ADDR3, 2, 312);
+    assert_eq!(suite.get_health(2), DistributionHealth::Healthy {});
+
+    suite.skip_blocks(10);
+    assert_eq!(
+        suite.get_health(2),
+        DistributionHealth::EndingSoon {
+            remaining: Duration::Height(5)
+        }
+    );
+
+    suite.skip_blocks(100);
+    suite.assert_pending_rewards(ADDR1, 2, 875);
+    suite.assert_pending_rewards(ADDR2, 2, 437);
+    suite.assert_pending_rewards(ADDR3, 2, 437);
+    assert_eq!(suite.get_health(2), DistributionHealth::Expired {});
+
+    suite.claim_rewards(ADDR1, 2);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 875);
+}
+
+#[test]
+#[should_panic(expected = "Invalid emission rate: decay_factor must be less than one")]
+fn test_validate_emission_rate_decay_factor() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    suite.update_emission_rate_to(
+        1,
+        EmissionRate::Decay {
+            initial_amount: Uint128::new(1_000),
+            duration: Duration::Height(10),
+            decay_factor: Decimal::percent(150),
+        },
+    );
+}
+
+#[test]
+fn test_compact_user_rewards() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.mint_native(coin(1_000, ALT_DENOM), OWNER);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Create(CreateMsg {
+                denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+                emission_rate: EmissionRate::Linear {
+                    amount: Uint128::new(100),
+                    duration: Duration::Height(10),
+                    continuous: false,
+                },
+                hook_caller: suite.staking_addr.to_string(),
+                vp_contract: suite.voting_power_addr.to_string(),
+                vp_sources: vec![],
+                withdraw_destination: None,
+                snapshot_height: None,
+                max_rewards_per_user: None,
+            }),
+            &coins(1_000, ALT_DENOM),
+        )
+        .unwrap();
+
+    // end the first distribution early, and the second by running out of
+    // funds.
+    suite.skip_blocks(10);
+    suite.withdraw(1);
+    suite.skip_blocks(90);
+
+    let user_reward_state = |suite: &Suite, addr: &str| -> UserRewardState {
+        USER_REWARDS
+            .query(
+                &suite.app.wrap(),
+                suite.distribution_contract.clone(),
+                Addr::unchecked(addr),
+            )
+            .unwrap()
+            .unwrap_or_default()
+    };
+
+    // claiming the first distribution compacts it, but not the second, which
+    // has not been claimed yet.
+    suite.claim_rewards(ADDR1, 1);
+    suite.assert_native_balance(ADDR1, DENOM, 500);
+    let state = user_reward_state(&suite, ADDR1);
+    assert_eq!(state.compacted_through, 1);
+    assert!(state.accounted_for_rewards_puvp.is_empty());
+    assert!(state.pending_rewards.is_empty());
+
+    suite.claim_rewards(ADDR1, 2);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 500);
+    let state = user_reward_state(&suite, ADDR1);
+    assert_eq!(state.compacted_through, 2);
+    assert!(state.accounted_for_rewards_puvp.is_empty());
+    assert!(state.pending_rewards.is_empty());
+
+    // compacted rewards are not earned again.
+    suite.assert_pending_rewards(ADDR1, 1, 0);
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+    suite.assert_pending_rewards(ADDR2, 1, 250);
+    suite.assert_pending_rewards(ADDR2, 2, 250);
+
+    // funding a compacted distribution again earns new rewards from where the
+    // compacted users left off.
+    suite.fund_native(2, coin(1_000, ALT_DENOM));
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR1, 2, 50);
+    suite.assert_pending_rewards(ADDR2, 2, 275);
+
+    suite.claim_rewards(ADDR1, 2);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 550);
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+
+    // the second distribution has earned more since it was settled, so users
+    // that had not yet been compacted can no longer be.
+    suite.skip_blocks(100);
+    suite.claim_rewards(ADDR2, 1);
+    suite.claim_rewards(ADDR2, 2);
+    assert_eq!(user_reward_state(&suite, ADDR2).compacted_through, 1);
+    suite.assert_native_balance(ADDR2, ALT_DENOM, 500);
+}
+
+#[test]
+fn test_totals_by_denom() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // a second distribution of the same denom, and one of another denom.
+    let hook_caller = suite.staking_addr.to_string();
+    for denom in [DENOM, ALT_DENOM] {
+        suite.create(
+            RewardsConfig {
+                amount: 1_000,
+                denom: UncheckedDenom::Native(denom.to_string()),
+                duration: Duration::Height(10),
+                destination: None,
+                continuous: true,
+            },
+            &hook_caller,
+            None,
+        );
+    }
+    suite.fund_native(2, coin(1_000, DENOM));
+    suite.fund_native(3, coin(1_000, ALT_DENOM));
+
+    suite.skip_blocks(100);
+    suite.claim_rewards(ADDR1, 1);
+    suite.claim_rewards(ADDR1, 2);
+    suite.claim_rewards(ADDR2, 3);
+    suite.withdraw(1);
+
+    let totals = suite.get_totals_by_denom().totals;
+    assert_eq!(
+        totals,
+        vec![
+            DenomTotals {
+                denom: Denom::Native(DENOM.to_string()),
+                funded: Uint128::new(100_001_000),
+                claimed: Uint128::new(5_500),
+                withdrawn: Uint128::new(99_990_000),
+                keeper_fees: Uint128::zero(),
+                pending: Uint128::new(5_500),
+            },
+            DenomTotals {
+                denom: Denom::Native(ALT_DENOM.to_string()),
+                funded: Uint128::new(1_000),
+                claimed: Uint128::new(250),
+                withdrawn: Uint128::zero(),
+                keeper_fees: Uint128::zero(),
+                pending: Uint128::new(750),
+            },
+        ]
+    );
+
+    // pending is what the contract holds.
+    let contract = suite.distribution_contract.to_string();
+    suite.assert_native_balance(&contract, DENOM, 5_500);
+    suite.assert_native_balance(&contract, ALT_DENOM, 750);
+}
+
+#[test]
+fn test_update_hook_callers() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let staking = suite.staking_addr.clone();
+
+    let membership_hook = |suite: &mut Suite, sender: &str| {
+        suite.app.execute_contract(
+            Addr::unchecked(sender),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::MemberChangedHook(MemberChangedHookMsg { diffs: vec![] }),
+            &[],
+        )
+    };
+    let stake_hook = |suite: &mut Suite, sender: &str| {
+        suite.app.execute_contract(
+            Addr::unchecked(sender),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::StakeChangeHook(StakeChangedHookMsg::Stake {
+                addr: Addr::unchecked(ADDR1),
+                amount: Uint128::new(1),
+            }),
+            &[],
+        )
+    };
+
+    let err: ContractError = membership_hook(&mut suite, "group")
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidHookSender {});
+
+    // a second voting power source may only call its type of hook.
+    suite.update_hook_callers(1, HookType::Membership, &["group"], &[]);
+    membership_hook(&mut suite, "group").unwrap();
+    let err: ContractError = stake_hook(&mut suite, "group")
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidHookSender {});
+
+    // the hook caller may still call every type of hook.
+    stake_hook(&mut suite, staking.as_str()).unwrap();
+    membership_hook(&mut suite, staking.as_str()).unwrap();
+
+    let callers = suite.get_hook_callers(1);
+    assert_eq!(callers.stake, vec![staking.clone()]);
+    assert_eq!(callers.nft_stake, vec![staking.clone()]);
+    assert_eq!(
+        callers.membership,
+        vec![staking.clone(), Addr::unchecked("group")]
+    );
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::UpdateHookCallers {
+                id: 1,
+                hook_type: HookType::Membership,
+                to_add: vec!["group".to_string()],
+                to_remove: vec![],
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::HookCallerAlreadyAuthorized {
+            addr: "group".to_string()
+        }
+    );
+
+    suite.update_hook_callers(1, HookType::Membership, &[], &["group"]);
+    let err: ContractError = membership_hook(&mut suite, "group")
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidHookSender {});
+    assert_eq!(suite.get_hook_callers(1).membership, vec![staking]);
+}
+
+#[test]
+fn test_poke_keeper_fee() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    suite.assert_ends_at(Expiration::AtHeight(1_000_000));
+
+    // poking brings the distribution up to date without a fee.
+    suite.skip_blocks(10);
+    suite.poke("keeper", 1);
+    let distribution = suite.get_distribution(1);
+    assert_eq!(
+        distribution.active_epoch.last_updated_total_earned_puvp,
+        Expiration::AtHeight(suite.app.block_info().height)
+    );
+    assert!(!distribution.active_epoch.total_earned_puvp.is_zero());
+    suite.assert_native_balance("keeper", DENOM, 0);
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::UpdateKeeperFee {
+                id: 1,
+                keeper_fee: Some(KeeperFee {
+                    amount: Uint128::new(100),
+                    interval: Duration::Height(0),
+                }),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidKeeperFee {});
+
+    suite.update_keeper_fee(
+        1,
+        Some(KeeperFee {
+            amount: Uint128::new(1_000),
+            interval: Duration::Height(20),
+        }),
+    );
+
+    // pokes that do not account for a complete emission period are not paid,
+    // and do not skip the partial period.
+    suite.poke("keeper", 1);
+    suite.skip_blocks(5);
+    suite.poke("keeper", 1);
+    suite.assert_native_balance("keeper", DENOM, 0);
+    assert_eq!(
+        suite
+            .get_distribution(1)
+            .active_epoch
+            .last_updated_total_earned_puvp,
+        Expiration::AtHeight(10)
+    );
+
+    // the fee is paid from undistributed funds, ending the distribution one
+    // emission period (1_000 tokens) earlier.
+    suite.skip_blocks(5);
+    suite.poke("keeper", 1);
+    suite.assert_native_balance("keeper", DENOM, 1_000);
+    suite.assert_ends_at(Expiration::AtHeight(999_990));
+    suite.assert_amount(1_000);
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_000_000 - 1_000)
+    );
+
+    // at most once per interval.
+    suite.skip_blocks(10);
+    suite.poke("keeper", 1);
+    suite.assert_native_balance("keeper", DENOM, 1_000);
+    suite.skip_blocks(10);
+    suite.poke("other_keeper", 1);
+    suite.assert_native_balance("other_keeper", DENOM, 1_000);
+    suite.assert_ends_at(Expiration::AtHeight(999_980));
+
+    let totals = suite.get_totals_by_denom().totals;
+    assert_eq!(totals[0].keeper_fees, Uint128::new(2_000));
+    assert_eq!(totals[0].pending, Uint128::new(100_000_000 - 2_000));
+
+    // the remaining funds are still distributed to stakers.
+    suite.skip_blocks(1_000_000);
+    suite.assert_pending_rewards(ADDR1, 1, 50_000_000 - 1_000);
+    suite.assert_pending_rewards(ADDR2, 1, 25_000_000 - 500);
+    suite.assert_pending_rewards(ADDR3, 1, 25_000_000 - 500);
+
+    // no fee is paid once the distribution has ended.
+    suite.poke("keeper", 1);
+    suite.assert_native_balance("keeper", DENOM, 1_000);
+
+    suite.update_keeper_fee(1, None);
+    assert_eq!(suite.get_distribution(1).keeper_fee, None);
+}
+
+#[test]
+fn test_claim_all() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // a second distribution of the same denom, and one of another denom.
+    let hook_caller = suite.staking_addr.to_string();
+    for denom in [DENOM, ALT_DENOM] {
+        suite.create(
+            RewardsConfig {
+                amount: 1_000,
+                denom: UncheckedDenom::Native(denom.to_string()),
+                duration: Duration::Height(10),
+                destination: None,
+                continuous: true,
+            },
+            &hook_caller,
+            None,
+        );
+    }
+    suite.fund_native(2, coin(1_000, DENOM));
+    suite.fund_native(3, coin(1_000, ALT_DENOM));
+
+    suite.skip_blocks(100);
+    suite.assert_pending_rewards(ADDR1, 1, 5_000);
+    suite.assert_pending_rewards(ADDR1, 2, 500);
+    suite.assert_pending_rewards(ADDR1, 3, 500);
+
+    // the first two distributions share a denom and are paid together.
+    suite.claim_all_rewards(ADDR1, None, Some(2)).unwrap();
+    suite.assert_native_balance(ADDR1, DENOM, 5_500);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 0);
+    suite.assert_pending_rewards(ADDR1, 1, 0);
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+    suite.assert_pending_rewards(ADDR1, 3, 500);
+
+    suite.claim_all_rewards(ADDR1, Some(2), None).unwrap();
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 500);
+
+    let err = suite.claim_all_rewards(ADDR1, None, None).unwrap_err();
+    assert_eq!(err, ContractError::NoRewardsClaimable {});
+
+    // frozen distributions are skipped rather than failing the claim.
+    suite.skip_blocks(100);
+    suite.claim_rewards(ADDR2, 1);
+    suite.app.update_block(|b| b.height -= 10);
+    suite.mint_native(coin(100, DENOM), ADDR4);
+    suite.stake_native_tokens(ADDR4, 100);
+    assert!(suite.get_distribution(1).frozen);
+    assert!(!suite.get_distribution(2).frozen);
+
+    let err = suite.claim_all_rewards(ADDR1, None, None).unwrap_err();
+    assert_eq!(err, ContractError::NoRewardsClaimable {});
+
+    // totals track rewards claimed from every distribution.
+    let totals = suite.get_totals_by_denom().totals;
+    assert_eq!(totals[0].claimed, Uint128::new(5_500 + 5_000));
+    assert_eq!(totals[1].claimed, Uint128::new(500));
+}
+
+#[test]
+fn test_claim_many() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // a second distribution of the same denom, and one of another denom.
+    let hook_caller = suite.staking_addr.to_string();
+    for denom in [DENOM, ALT_DENOM] {
+        suite.create(
+            RewardsConfig {
+                amount: 1_000,
+                denom: UncheckedDenom::Native(denom.to_string()),
+                duration: Duration::Height(10),
+                destination: None,
+                continuous: true,
+            },
+            &hook_caller,
+            None,
+        );
+    }
+    suite.fund_native(2, coin(1_000, DENOM));
+    suite.fund_native(3, coin(1_000, ALT_DENOM));
+
+    suite.skip_blocks(100);
+
+    // IDs that fail are reported, and only fail the claim if strict.
+    let err = suite
+        .claim_many_rewards(ADDR1, vec![1, 4], true)
+        .unwrap_err();
+    assert_eq!(err, ContractError::DistributionNotFound { id: 4 });
+    suite.assert_pending_rewards(ADDR1, 1, 5_000);
+
+    let res = suite
+        .claim_many_rewards(ADDR1, vec![1, 4, 3], false)
+        .unwrap();
+    let results = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-claim_many")
+        .map(|event| {
+            event
+                .attributes
+                .iter()
+                .filter(|attr| attr.key != "_contract_address")
+                .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        vec![
+            vec![
+                ("id", "1"),
+                ("status", "claimed"),
+                ("denom", DENOM),
+                ("amount_claimed", "5000"),
+            ],
+            vec![
+                ("id", "4"),
+                ("status", "failed"),
+                ("error", "Distribution not found with ID 4"),
+            ],
+            vec![
+                ("id", "3"),
+                ("status", "claimed"),
+                ("denom", ALT_DENOM),
+                ("amount_claimed", "500"),
+            ],
+        ]
+    );
+    suite.assert_native_balance(ADDR1, DENOM, 5_000);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 500);
+
+    // IDs not given are not claimed.
+    suite.assert_pending_rewards(ADDR1, 2, 500);
+
+    // IDs with no pending rewards fail too.
+    let err = suite
+        .claim_many_rewards(ADDR1, vec![2, 3], true)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoRewardsClaimable {});
+    suite.claim_many_rewards(ADDR1, vec![2, 3], false).unwrap();
+    suite.assert_native_balance(ADDR1, DENOM, 5_500);
+
+    let err = suite
+        .claim_many_rewards(ADDR1, vec![2, 3], false)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoRewardsClaimable {});
+
+    let err = suite
+        .claim_many_rewards(ADDR1, (1..=51).collect(), false)
+        .unwrap_err();
+    assert_eq!(err, ContractError::TooManyClaimIds { max: 50 });
+}
+
+#[test]
+fn test_claim_many_pools() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite
+        .create_pool(
+            1,
+            UncheckedDenom::Native(ALT_DENOM.to_string()),
+            EmissionRate::Linear {
+                amount: Uint128::new(1_000),
+                duration: Duration::Height(10),
+                continuous: true,
+            },
+        )
+        .unwrap();
+    suite.fund_native(1, coin(1_000, ALT_DENOM));
+
+    suite.skip_blocks(100);
+
+    // like claim, a distribution is claimed along with its pools.
+    let res = suite.claim_many_rewards(ADDR1, vec![1], true).unwrap();
+    let results = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-claim_many")
+        .map(|event| {
+            event
+                .attributes
+                .iter()
+                .filter(|attr| attr.key != "_contract_address")
+                .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        vec![vec![
+            ("id", "1"),
+            ("status", "claimed"),
+            ("denom", DENOM),
+            ("amount_claimed", "5000"),
+            ("denom", ALT_DENOM),
+            ("amount_claimed", "500"),
+        ]]
+    );
+    suite.assert_native_balance(ADDR1, DENOM, 5_000);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 500);
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+
+    let err = suite.claim_many_rewards(ADDR1, vec![2], true).unwrap_err();
+    assert_eq!(err, ContractError::NoRewardsClaimable {});
+}
+
+#[test]
+fn test_denom_pools() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    let cw20 = suite.mint_cw20(
+        Cw20Coin {
+            address: OWNER.to_string(),
+            amount: Uint128::new(1_000),
+        },
+        "newcoin",
+    );
+    let emission_rate = EmissionRate::Linear {
+        amount: Uint128::new(1_000),
+        duration: Duration::Height(10),
+        continuous: true,
+    };
+    suite
+        .create_pool(
+            1,
+            UncheckedDenom::Native(ALT_DENOM.to_string()),
+            emission_rate.clone(),
+        )
+        .unwrap();
+    suite
+        .create_pool(
+            1,
+            UncheckedDenom::Cw20(cw20.to_string()),
+            emission_rate.clone(),
+        )
+        .unwrap();
+
+    let distribution = suite.get_distribution(1);
+    assert_eq!(distribution.pools, vec![2, 3]);
+    let pool = suite.get_distribution(2);
+    assert_eq!(pool.pool_of, Some(1));
+    assert_eq!(pool.hook_caller, distribution.hook_caller);
+    assert_eq!(pool.vp_contract, distribution.vp_contract);
+
+    // each denom may only be distributed once per distribution, and pools may
+    // not have pools of their own.
+    let err = suite
+        .create_pool(
+            1,
+            UncheckedDenom::Native(DENOM.to_string()),
+            emission_rate.clone(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DuplicatePoolDenom {
+            id: 1,
+            denom: DENOM.to_string(),
+        }
+    );
+    let err = suite
+        .create_pool(
+            2,
+            UncheckedDenom::Native("uother".to_string()),
+            emission_rate,
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::PoolOfPool { id: 2 });
+
+    // funding the distribution with a pool's denom funds the pool.
+    suite.fund_native(1, coin(1_000, ALT_DENOM));
+    suite.fund_cw20(
+        1,
+        Cw20Coin {
+            address: cw20.to_string(),
+            amount: Uint128::new(1_000),
+        },
+    );
+    assert_eq!(suite.get_distribution(2).funded_amount, Uint128::new(1_000));
+    assert_eq!(suite.get_distribution(3).funded_amount, Uint128::new(1_000));
+
+    // claiming the distribution pays out every denom at once.
+    suite.skip_blocks(100);
+    suite.assert_pending_rewards(ADDR1, 1, 5_000);
+    suite.assert_pending_rewards(ADDR1, 2, 500);
+    suite.assert_pending_rewards(ADDR1, 3, 500);
+    suite.claim_rewards(ADDR1, 1);
+    suite.assert_native_balance(ADDR1, DENOM, 5_000);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 500);
+    suite.assert_cw20_balance(cw20.as_str(), ADDR1, 500);
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+    suite.assert_pending_rewards(ADDR1, 3, 0);
+
+    // other stakers receive their share of every denom.
+    suite.claim_rewards(ADDR2, 1);
+    suite.assert_native_balance(ADDR2, ALT_DENOM, 250);
+
+    // voting power and hook configuration are updated through the
+    // distribution and kept in sync with its pools.
+    suite.update_hook_caller(1, "new_hook_caller");
+    assert_eq!(
+        suite.get_distribution(3).hook_caller,
+        Addr::unchecked("new_hook_caller")
+    );
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Update {
+                id: 2,
+                emission_rate: None,
+                vp_contract: None,
+                vp_sources: None,
+                hook_caller: Some(OWNER.to_string()),
+                withdraw_destination: None,
+                max_rewards_per_user: None,
+                unclaimed_expiry: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::PoolConfigFromParent { id: 1 });
+}
+
+#[test]
+fn test_claim_history() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let hook_caller = suite.staking_addr.to_string();
+    suite.create(
+        RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: true,
+        },
+        &hook_caller,
+        None,
+    );
+    suite.fund_native(2, coin(1_000, ALT_DENOM));
+
+    assert_eq!(suite.get_claim_history(ADDR1, None, None), vec![]);
+
+    suite.skip_blocks(100);
+    suite.claim_rewards(ADDR1, 1);
+    let first = suite.app.block_info();
+    suite.skip_blocks(10);
+    suite.claim_all_rewards(ADDR1, None, None).unwrap();
+    let second = suite.app.block_info();
+    suite.claim_rewards(ADDR2, 1);
+
+    // every claimed distribution is recorded, and only for the claimer.
+    let history = suite.get_claim_history(ADDR1, None, None);
+    assert_eq!(
+        history,
+        vec![
+            ClaimRecord {
+                id: 1,
+                address: Addr::unchecked(ADDR1),
+                distribution_id: 1,
+                amount: Uint128::new(5_000),
+                height: first.height,
+                time: first.time,
+            },
+            ClaimRecord {
+                id: 2,
+                address: Addr::unchecked(ADDR1),
+                distribution_id: 1,
+                amount: Uint128::new(500),
+                height: second.height,
+                time: second.time,
+            },
+            ClaimRecord {
+                id: 3,
+                address: Addr::unchecked(ADDR1),
+                distribution_id: 2,
+                amount: Uint128::new(500),
+                height: second.height,
+                time: second.time,
+            },
+        ]
+    );
+    assert_eq!(suite.get_claim_history(ADDR2, None, None)[0].id, 4);
+    assert_eq!(suite.get_claim_history(ADDR3, None, None), vec![]);
+
+    // history is paginated by claim record ID.
+    assert_eq!(
+        suite.get_claim_history(ADDR1, None, Some(1)),
+        history[..1].to_vec()
+    );
+    assert_eq!(
+        suite.get_claim_history(ADDR1, Some(1), None),
+        history[1..].to_vec()
+    );
+}
+
+#[test]
+fn test_update_members() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::CW4).build();
+
+    // a distribution whose hook caller never calls it, like a voting power
+    // contract without hooks.
+    suite.create(
+        RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: true,
+        },
+        "no_hooks",
+        None,
+    );
+    suite.fund_native(2, coin(100_000, ALT_DENOM));
+
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR3, 2, 250);
+
+    // only keepers may sync members once set.
+    suite.update_member_updaters(2, Some(vec!["keeper"]));
+    assert_eq!(
+        suite.sync_members(ADDR1, 2, vec![ADDR3]).unwrap_err(),
+        ContractError::NotMemberUpdater { id: 2 }
+    );
+    assert_eq!(
+        suite
+            .sync_members("keeper", 2, vec![ADDR1; 51])
+            .unwrap_err(),
+        ContractError::TooManyMembers { max: 50 }
+    );
+
+    // ADDR3 leaves the group, and the keeper syncs it in the same block,
+    // as a hook would have.
+    suite.update_members(vec![], vec![ADDR3.to_string()]);
+    suite.sync_members("keeper", 2, vec![ADDR3]).unwrap();
+
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR3, 2, 250);
+    suite.claim_rewards(ADDR3, 2);
+    suite.assert_native_balance(ADDR3, ALT_DENOM, 250);
+
+    // anyone may sync members once the keepers are cleared.
+    suite.update_member_updaters(2, None);
+    suite.sync_members(ADDR1, 2, vec![ADDR1, ADDR2]).unwrap();
+}
+
+#[test]
+fn test_distribution_stats() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let hook_caller = suite.staking_addr.to_string();
+    suite.create(
+        RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: true,
+        },
+        &hook_caller,
+        None,
+    );
+    suite.fund_native(2, coin(1_000, ALT_DENOM));
+
+    suite.skip_blocks(105);
+    suite.claim_rewards(ADDR1, 1);
+    suite.skip_blocks(10);
+    suite.claim_rewards(ADDR1, 1);
+    suite.claim_rewards(ADDR2, 1);
+
+    // each claimant is counted once.
+    let stats = suite.get_distribution_stats(None, None);
+    assert_eq!(
+        stats[0],
+        DistributionStats {
+            id: 1,
+            denom: Denom::Native(DENOM.to_string()),
+            funded: Uint128::new(100_000_000),
+            distributed: Uint128::new(11_500),
+            undistributed: Uint128::new(99_988_500),
+            claimed: Uint128::new(8_250),
+            claimants: 2,
+            runway: Some(Duration::Height(999_885)),
+        }
+    );
+    assert_eq!(stats[1].distributed, Uint128::new(1_000));
+    assert_eq!(stats[1].undistributed, Uint128::zero());
+    assert_eq!(stats[1].claimants, 0);
+    assert_eq!(stats[1].runway, Some(Duration::Height(0)));
+
+    // withdrawn funds are neither distributed nor undistributed.
+    suite.withdraw(1);
+    let stats = suite.get_distribution_stats(None, Some(1));
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].distributed, Uint128::new(11_500));
+    assert_eq!(stats[0].undistributed, Uint128::zero());
+    assert_eq!(stats[0].runway, Some(Duration::Height(0)));
+
+    assert_eq!(
+        suite.get_undistributed_rewards(None, None),
+        vec![
+            UndistributedRewards {
+                id: 1,
+                denom: Denom::Native(DENOM.to_string()),
+                undistributed: Uint128::zero(),
+            },
+            UndistributedRewards {
+                id: 2,
+                denom: Denom::Native(ALT_DENOM.to_string()),
+                undistributed: Uint128::zero(),
+            },
+        ]
+    );
+    assert_eq!(suite.get_undistributed_rewards(Some(1), None).len(), 1);
+    assert_eq!(suite.get_distribution_stats(Some(1), None)[0].id, 2);
+}
+
+#[test]
+fn test_normalized_emission() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let hook_caller = suite.staking_addr.to_string();
+    suite.create(
+        RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+            duration: Duration::Time(10),
+            destination: None,
+            continuous: true,
+        },
+        &hook_caller,
+        None,
+    );
+    suite.fund_native(2, coin(1_000, ALT_DENOM));
+    suite.skip_blocks(10);
+
+    // 1,000 per 10 blocks.
+    assert_eq!(
+        suite.get_distribution_response(1).normalized_emission,
+        Some(NormalizedEmission {
+            per_block: Some(Decimal::from_ratio(100u128, 1u128)),
+            per_second: None,
+            yearly_per_unit_power: None,
+        })
+    );
+
+    // 1,000 per 10 seconds, split over 200 units of voting power.
+    let distributions = suite.get_distributions().distributions;
+    assert_eq!(distributions[1].state, suite.get_distribution(2));
+    assert_eq!(
+        distributions[1].normalized_emission,
+        Some(NormalizedEmission {
+            per_block: None,
+            per_second: Some(Decimal::from_ratio(100u128, 1u128)),
+            yearly_per_unit_power: Some(Decimal::from_ratio(15_768_000u128, 1u128)),
+        })
+    );
+
+    // nothing is emitted once the funds run out.
+    suite.skip_seconds(10);
+    let emission = suite
+        .get_distribution_response(2)
+        .normalized_emission
+        .unwrap();
+    assert_eq!(emission.per_second, Some(Decimal::zero()));
+    assert_eq!(emission.yearly_per_unit_power, Some(Decimal::zero()));
+}
+
+#[test]
+fn test_claim_delegates() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    suite.set_claim_delegate(ADDR1, "keeper", "treasury");
+    suite.set_claim_delegate(ADDR1, "other_keeper", "other_treasury");
+    assert_eq!(
+        suite.get_claim_delegates(ADDR1).delegates,
+        vec![
+            ClaimDelegate {
+                delegate: Addr::unchecked("keeper"),
+                destination: Addr::unchecked("treasury"),
+            },
+            ClaimDelegate {
+                delegate: Addr::unchecked("other_keeper"),
+                destination: Addr::unchecked("other_treasury"),
+            },
+        ]
+    );
+    assert!(suite.get_claim_delegates(ADDR2).delegates.is_empty());
+
+    // delegates claim to the destination registered for them, not to
+    // themselves or the staker.
+    suite.skip_blocks(10_000);
+    suite.assert_pending_rewards(ADDR1, 1, 500_000);
+    suite.claim_rewards_for("keeper", ADDR1, 1).unwrap();
+    suite.assert_native_balance("treasury", DENOM, 500_000);
+    suite.assert_native_balance("keeper", DENOM, 0);
+    suite.assert_native_balance(ADDR1, DENOM, 0);
+    suite.assert_pending_rewards(ADDR1, 1, 0);
+
+    // only the staker's delegates may claim for them.
+    let err = suite.claim_rewards_for("keeper", ADDR2, 1).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotClaimDelegate {
+            staker: ADDR2.to_string(),
+            delegate: "keeper".to_string(),
+        }
+    );
+
+    // the staker may still claim for themselves.
+    suite.skip_blocks(10_000);
+    suite.claim_rewards(ADDR1, 1);
+    suite.assert_native_balance(ADDR1, DENOM, 500_000);
+
+    // revoked delegates may no longer claim.
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ADDR1),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::RemoveClaimDelegate {
+                delegate: "keeper".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    suite.skip_blocks(10_000);
+    let err = suite.claim_rewards_for("keeper", ADDR1, 1).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotClaimDelegate {
+            staker: ADDR1.to_string(),
+            delegate: "keeper".to_string(),
+        }
+    );
+    suite.claim_rewards_for("other_keeper", ADDR1, 1).unwrap();
+    suite.assert_native_balance("other_treasury", DENOM, 500_000);
+    assert_eq!(suite.get_claim_delegates(ADDR1).delegates.len(), 1);
+}
+
+#[test]
+fn test_distribution_manager() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    assert_eq!(
+        suite.get_distribution_manager(1),
+        DistributionManager::default()
+    );
+
+    let withdraw = |suite: &mut Suite, sender: &str| -> Result<(), ContractError> {
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(sender),
+                suite.distribution_contract.clone(),
+                &ExecuteMsg::Withdraw { id: 1 },
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    };
+
+    // only the owner may delegate management.
+    let err = suite
+        .update_manager(
+            "subdao",
+            1,
+            ManagerAction::TransferManagement {
+                new_manager: "subdao".to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+
+    suite
+        .update_manager(
+            OWNER,
+            1,
+            ManagerAction::TransferManagement {
+                new_manager: "subdao".to_string(),
+                expiry: Some(Expiration::AtHeight(suite.app.block_info().height + 10)),
+            },
+        )
+        .unwrap();
+
+    // the pending manager can't manage the distribution until they accept.
+    let err = withdraw(&mut suite, "subdao").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+    let err = suite
+        .update_manager(ADDR1, 1, ManagerAction::AcceptManagement {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotPendingManager { id: 1 });
+
+    // transfers can't be accepted once expired.
+    suite.skip_blocks(10);
+    let err = suite
+        .update_manager("subdao", 1, ManagerAction::AcceptManagement {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::ManagerTransferExpired { id: 1 });
+
+    suite
+        .update_manager(
+            OWNER,
+            1,
+            ManagerAction::TransferManagement {
+                new_manager: "subdao".to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap();
+    suite
+        .update_manager("subdao", 1, ManagerAction::AcceptManagement {})
+        .unwrap();
+    assert_eq!(
+        suite.get_distribution_manager(1),
+        DistributionManager {
+            manager: Some(Addr::unchecked("subdao")),
+            pending_manager: None,
+            pending_expiry: None,
+        }
+    );
+    let err = suite
+        .update_manager("subdao", 1, ManagerAction::AcceptManagement {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::ManagerTransferNotFound { id: 1 });
+
+    // the manager may update the emission rate and withdraw from the
+    // distribution to its withdraw destination, but has no other privileges.
+    let update = |suite: &mut Suite, sender: &str, msg: ExecuteMsg| -> Result<(), ContractError> {
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(sender),
+                suite.distribution_contract.clone(),
+                &msg,
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    };
+    update(
+        &mut suite,
+        "subdao",
+        ExecuteMsg::Update {
+            id: 1,
+            emission_rate: Some(EmissionRate::Linear {
+                amount: Uint128::new(2_000),
+                duration: Duration::Height(10),
+                continuous: true,
+            }),
+            vp_contract: None,
+            vp_sources: None,
+            hook_caller: None,
+            withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
+        },
+    )
+    .unwrap();
+
+    // where funds go, whose voting power and hooks count, when unclaimed
+    // rewards expire, and keeper fees are left to the owner.
+    let not_owner = ContractError::Ownable(cw_ownable::OwnershipError::NotOwner);
+    let owner_only = [
+        ExecuteMsg::Update {
+            id: 1,
+            emission_rate: None,
+            vp_contract: None,
+            vp_sources: None,
+            hook_caller: None,
+            withdraw_destination: Some("subdao".to_string()),
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
+        },
+        ExecuteMsg::Update {
+            id: 1,
+            emission_rate: None,
+            vp_contract: Some(suite.voting_power_addr.to_string()),
+            vp_sources: None,
+            hook_caller: None,
+            withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
+        },
+        ExecuteMsg::Update {
+            id: 1,
+            emission_rate: None,
+            vp_contract: None,
+            vp_sources: Some(vec![]),
+            hook_caller: None,
+            withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
+        },
+        ExecuteMsg::Update {
+            id: 1,
+            emission_rate: None,
+            vp_contract: None,
+            vp_sources: None,
+            hook_caller: Some("subdao".to_string()),
+            withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: None,
+        },
+        ExecuteMsg::Update {
+            id: 1,
+            emission_rate: None,
+            vp_contract: None,
+            vp_sources: None,
+            hook_caller: None,
+            withdraw_destination: None,
+            max_rewards_per_user: None,
+            unclaimed_expiry: Some(Expiration::AtHeight(suite.app.block_info().height)),
+        },
+        ExecuteMsg::UpdateHookCallers {
+            id: 1,
+            hook_type: HookType::Membership,
+            to_add: vec!["subdao".to_string()],
+            to_remove: vec![],
+        },
+        ExecuteMsg::ForceWithdraw { id: 1 },
+        ExecuteMsg::UpdateKeeperFee {
+            id: 1,
+            keeper_fee: Some(KeeperFee {
+                amount: Uint128::new(100),
+                interval: Duration::Height(10),
+            }),
+        },
+    ];
+    for msg in owner_only {
+        assert_eq!(update(&mut suite, "subdao", msg).unwrap_err(), not_owner);
+    }
+
+    let pre_withdraw_balance =
+        suite.get_balance_native(suite.distribution_contract.to_string(), DENOM);
+    let pre_withdraw_owner_balance = suite.get_balance_native(OWNER, DENOM);
+    withdraw(&mut suite, "subdao").unwrap();
+    let post_withdraw_balance =
+        suite.get_balance_native(suite.distribution_contract.to_string(), DENOM);
+    suite.assert_native_balance("subdao", DENOM, 0);
+    suite.assert_native_balance(
+        OWNER,
+        DENOM,
+        pre_withdraw_owner_balance + pre_withdraw_balance - post_withdraw_balance,
+    );
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked("subdao"),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::UpdateOwnership(cw_ownable::Action::RenounceOwnership),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+
+    // the manager may hand management to another address.
+    suite
+        .update_manager(
+            "subdao",
+            1,
+            ManagerAction::TransferManagement {
+                new_manager: "other_subdao".to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap();
+    suite
+        .update_manager("other_subdao", 1, ManagerAction::AcceptManagement {})
+        .unwrap();
+    let err = withdraw(&mut suite, "subdao").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+
+    // the owner may always remove the manager.
+    suite
+        .update_manager(OWNER, 1, ManagerAction::RemoveManager {})
+        .unwrap();
+    assert_eq!(
+        suite.get_distribution_manager(1),
+        DistributionManager::default()
+    );
+    let err = suite
+        .update_manager(
+            "other_subdao",
+            1,
+            ManagerAction::TransferManagement {
+                new_manager: "other_subdao".to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+
+    let err = suite
+        .update_manager(OWNER, 3, ManagerAction::RemoveManager {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::DistributionNotFound { id: 3 });
+}
+
+#[test]
+fn test_weighted_vp_sources() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let staking = suite.staking_addr.clone();
+    let vp_contract = suite.voting_power_addr.clone();
+
+    // a second voting power source, weighted 5x relative to the staked tokens.
+    let (group_vp, group) = setup_cw4_test(
+        suite.app.borrow_mut(),
+        vec![
+            Member {
+                addr: ADDR2.to_string(),
+                weight: 10,
+            },
+            Member {
+                addr: ADDR3.to_string(),
+                weight: 30,
+            },
+        ],
+    );
+    suite.register_hook(group.clone());
+    suite.skip_blocks(2);
+
+    suite.mint_native(coin(500_000_000, ALT_DENOM), OWNER);
+
+    let create_msg = |weight: Decimal| {
+        ExecuteMsg::Create(CreateMsg {
+            denom: cw20::UncheckedDenom::Native(ALT_DENOM.to_string()),
+            emission_rate: EmissionRate::Immediate {},
+            hook_caller: staking.to_string(),
+            vp_contract: vp_contract.to_string(),
+            vp_sources: vec![VpSourceMsg {
+                vp_contract: group_vp.to_string(),
+                weight,
+                hook_caller: group.to_string(),
+            }],
+            withdraw_destination: None,
+            snapshot_height: None,
+            max_rewards_per_user: None,
+        })
+    };
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &create_msg(Decimal::zero()),
+            &coins(100_000_000, ALT_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidVpSourceWeight {});
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &create_msg(Decimal::from_ratio(5u128, 1u128)),
+            &coins(100_000_000, ALT_DENOM),
+        )
+        .unwrap();
+
+    // voting power is 100, 50 + 5 * 10, and 50 + 5 * 30.
+    suite.assert_pending_rewards(ADDR1, 2, 25_000_000);
+    suite.assert_pending_rewards(ADDR2, 2, 25_000_000);
+    suite.assert_pending_rewards(ADDR3, 2, 50_000_000);
+
+    // each source's hook caller may call every type of hook.
+    let callers = suite.get_hook_callers(2);
+    assert_eq!(callers.stake, vec![staking.clone(), group.clone()]);
+    assert_eq!(callers.membership, vec![staking.clone(), group.clone()]);
+
+    // membership changes in the second source update rewards.
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            group.clone(),
+            &cw4_group::msg::ExecuteMsg::UpdateMembers {
+                remove: vec![ADDR3.to_string()],
+                add: vec![],
+            },
+            &[],
+        )
+        .unwrap();
+    suite.skip_blocks(2);
+
+    // voting power is now 100, 100, and 50.
+    suite.fund_native(2, coin(100_000_000, ALT_DENOM));
+    suite.assert_pending_rewards(ADDR1, 2, 25_000_000 + 40_000_000);
+    suite.assert_pending_rewards(ADDR2, 2, 25_000_000 + 40_000_000);
+    suite.assert_pending_rewards(ADDR3, 2, 50_000_000 + 20_000_000);
+
+    // removing the source unsubscribes its hook caller.
+    suite.update_vp_sources(2, vec![]);
+    let callers = suite.get_hook_callers(2);
+    assert_eq!(callers.membership, vec![staking.clone()]);
+    assert_eq!(suite.get_distribution(2).vp_sources, vec![]);
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            group,
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::MemberChangedHook(MemberChangedHookMsg { diffs: vec![] }),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidHookSender {});
+}
+
+#[test]
+fn test_duplicate_hooks() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let staking = suite.staking_addr.clone();
+
+    let hook = |suite: &mut Suite, msg: StakeChangedHookMsg| {
+        suite
+            .app
+            .execute_contract(
+                staking.clone(),
+                suite.distribution_contract.clone(),
+                &ExecuteMsg::StakeChangeHook(msg),
+                &[],
+            )
+            .unwrap()
+    };
+    let stake = StakeChangedHookMsg::Stake {
+        addr: Addr::unchecked(ADDR1),
+        amount: Uint128::new(1),
+    };
+    let is_duplicate =
+        |res: &cw_multi_test::AppResponse| res.has_event(&Event::new("wasm-duplicate_hook"));
+
+    suite.skip_blocks(1);
+    let nonce = suite.get_hook_sequence(staking.as_str()).nonce;
+
+    let res = hook(&mut suite, stake.clone());
+    assert!(!is_duplicate(&res));
+    assert_eq!(suite.get_hook_sequence(staking.as_str()).nonce, nonce + 1);
+
+    // the same message is ignored if sent again in the same block.
+    let res = hook(&mut suite, stake.clone());
+    assert!(is_duplicate(&res));
+    assert_eq!(suite.get_hook_sequence(staking.as_str()).nonce, nonce + 1);
+
+    // other messages are still processed.
+    let res = hook(
+        &mut suite,
+        StakeChangedHookMsg::Unstake {
+            addr: Addr::unchecked(ADDR1),
+            amount: Uint128::new(1),
+        },
+    );
+    assert!(!is_duplicate(&res));
+    assert_eq!(suite.get_hook_sequence(staking.as_str()).nonce, nonce + 2);
+    let processed = |suite: &Suite| {
+        PROCESSED_HOOKS
+            .query(
+                &suite.app.wrap(),
+                suite.distribution_contract.clone(),
+                &staking,
+            )
+            .unwrap()
+    };
+    let (height, hashes) = processed(&suite);
+    assert_eq!(height, suite.app.block_info().height);
+    assert_eq!(hashes.len(), 2);
+
+    // as is the same message in a later block, which only keeps the hashes
+    // processed in that block.
+    suite.skip_blocks(1);
+    let res = hook(&mut suite, stake);
+    assert!(!is_duplicate(&res));
+    assert_eq!(suite.get_hook_sequence(staking.as_str()).nonce, nonce + 3);
+    let (height, hashes) = processed(&suite);
+    assert_eq!(height, suite.app.block_info().height);
+    assert_eq!(hashes.len(), 1);
+
+    // staking through the staking contract is unaffected.
+    suite.mint_native(coin(100, DENOM), ADDR4);
+    suite.stake_native_tokens(ADDR4, 100);
+    assert_eq!(suite.get_hook_sequence(staking.as_str()).nonce, nonce + 4);
+}
+
+#[test]
+fn test_native_funding_schedule() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    suite.mint_native(coin(2_500, DENOM), OWNER);
+
+    // native schedules are funded from escrow, not an allowance.
+    let err = suite
+        .create_funding_schedule(
+            1,
+            1_000,
+            Duration::Height(100),
+            Some(OWNER),
+            &coins(2_500, DENOM),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFundingSource {});
+    let err = suite
+        .create_funding_schedule(1, 0, Duration::Height(100), None, &coins(2_500, DENOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFundingSchedule {});
+
+    suite
+        .create_funding_schedule(1, 1_000, Duration::Height(100), None, &coins(2_500, DENOM))
+        .unwrap();
+    let err = suite
+        .create_funding_schedule(1, 1_000, Duration::Height(100), None, &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::FundingScheduleExists { id: 1 });
+
+    // the first installment is funded immediately, and later ones at most
+    // once per interval.
+    suite.tick();
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_001_000)
+    );
+    assert_eq!(
+        suite.get_funding_schedule(1),
+        FundingSchedule {
+            id: 1,
+            source: FundingSource::Escrow {
+                remaining: Uint128::new(1_500),
+            },
+            amount: Uint128::new(1_000),
+            interval: Duration::Height(100),
+            next_funding: Expiration::AtHeight(suite.app.block_info().height + 100),
+        }
+    );
+
+    suite.skip_blocks(100);
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_002_000)
+    );
+
+    let totals = suite.get_totals_by_denom().totals;
+    assert_eq!(totals[0].funded, Uint128::new(100_002_000));
+
+    // removing the schedule returns what remains in escrow.
+    suite.remove_funding_schedule(1);
+    suite.assert_native_balance(OWNER, DENOM, 500);
+    suite.skip_blocks(100);
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_002_000)
+    );
+}
+
+#[test]
+fn test_cw20_funding_schedule() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native)
+        .with_rewards_config(RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Cw20(DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: true,
+        })
+        .build();
+    let cw20 = suite.reward_denom.clone();
+
+    let err = suite
+        .create_funding_schedule(1, 1_000, Duration::Time(60), None, &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFundingSource {});
+    suite
+        .create_funding_schedule(1, 1_000, Duration::Time(60), Some(OWNER), &[])
+        .unwrap();
+
+    // nothing is funded without an allowance.
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_000_000)
+    );
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            Addr::unchecked(&cw20),
+            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                spender: suite.distribution_contract.to_string(),
+                amount: Uint128::new(2_000),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    suite.tick();
+    suite.skip_seconds(60);
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_002_000)
+    );
+    suite.assert_cw20_balance(&cw20, OWNER, 900_000_000 - 2_000);
+
+    // once the allowance is spent, installments are skipped.
+    suite.skip_seconds(60);
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_002_000)
+    );
+    suite.assert_cw20_balance(&cw20, suite.distribution_contract.as_str(), 100_002_000);
+}
+
+#[test]
+fn test_manager_funding_source() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native)
+        .with_rewards_config(RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Cw20(DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: false,
+        })
+        .build();
+    suite
+        .update_manager(
+            OWNER,
+            1,
+            ManagerAction::TransferManagement {
+                new_manager: "subdao".to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap();
+    suite
+        .update_manager("subdao", 1, ManagerAction::AcceptManagement {})
+        .unwrap();
+
+    let execute = |suite: &mut Suite, msg: ExecuteMsg| -> Result<(), ContractError> {
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked("subdao"),
+                suite.distribution_contract.clone(),
+                &msg,
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    };
+
+    // the manager may not draw on the owner's allowance.
+    let err = execute(
+        &mut suite,
+        ExecuteMsg::CreateFundingSchedule {
+            id: 1,
+            amount: Uint128::new(1_000),
+            interval: Duration::Height(10),
+            source: Some(OWNER.to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+    let err = execute(
+        &mut suite,
+        ExecuteMsg::CreateRollover {
+            id: 1,
+            top_up: Uint128::new(1_000),
+            max_rollovers: 2,
+            source: Some(OWNER.to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+
+    // but may draw on their own.
+    execute(
+        &mut suite,
+        ExecuteMsg::CreateFundingSchedule {
+            id: 1,
+            amount: Uint128::new(1_000),
+            interval: Duration::Height(10),
+            source: Some("subdao".to_string()),
+        },
+    )
+    .unwrap();
+    execute(
+        &mut suite,
+        ExecuteMsg::CreateRollover {
+            id: 1,
+            top_up: Uint128::new(1_000),
+            max_rollovers: 2,
+            source: Some("subdao".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        suite.get_funding_schedule(1).source,
+        FundingSource::Allowance {
+            owner: Addr::unchecked("subdao"),
+        }
+    );
+    assert_eq!(
+        suite.get_rollover(1).source,
+        FundingSource::Allowance {
+            owner: Addr::unchecked("subdao"),
+        }
+    );
+}
+
+#[test]
+fn test_tick_pages() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let hook_caller = suite.staking_addr.to_string();
+    suite.create(
+        RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: true,
+        },
+        &hook_caller,
+        None,
+    );
+    suite.fund_native(2, coin(1_000, DENOM));
+
+    suite.mint_native(coin(2_000, DENOM), OWNER);
+    for id in [1, 2] {
+        suite
+            .create_funding_schedule(id, 1_000, Duration::Height(100), None, &coins(1_000, DENOM))
+            .unwrap();
+    }
+
+    // freeze the first distribution.
+    suite.skip_blocks(100);
+    suite.claim_rewards(ADDR2, 1);
+    suite.app.update_block(|b| b.height -= 10);
+    suite.mint_native(coin(100, DENOM), ADDR4);
+    suite.stake_native_tokens(ADDR4, 100);
+    assert!(suite.get_distribution(1).frozen);
+
+    // the frozen distribution's schedule fails without reverting the tick,
+    // and the next page continues after it.
+    let res = suite.tick_page(
+        TickPage {
+            start_after: None,
+            limit: Some(1),
+        },
+        TickPage::default(),
+    );
+    assert!(res.has_event(
+        &Event::new("wasm-tick_failed")
+            .add_attribute("id", "1")
+            .add_attribute(
+                "error",
+                ContractError::DistributionFrozen { id: 1 }.to_string()
+            )
+    ));
+    assert!(res.has_event(&Event::new("wasm").add_attribute("schedules_start_after", "1")));
+    assert_eq!(suite.get_distribution(2).funded_amount, Uint128::new(1_000));
+
+    let res = suite.tick_page(
+        TickPage {
+            start_after: Some(1),
+            limit: None,
+        },
+        TickPage::default(),
+    );
+    assert!(!res.has_event(&Event::new("wasm-tick_failed")));
+    assert!(!res.has_event(&Event::new("wasm").add_attribute("schedules_start_after", "2")));
+    assert_eq!(suite.get_distribution(2).funded_amount, Uint128::new(2_000));
+    assert_eq!(
+        suite.get_funding_schedule(1).source,
+        FundingSource::Escrow {
+            remaining: Uint128::new(1_000),
+        }
+    );
+}
+
+#[test]
+fn test_rollover() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    suite.mint_native(coin(15_000, ALT_DENOM), OWNER);
+    let hook_caller = suite.staking_addr.to_string();
+    // ten periods are funded.
+    suite.create(
+        RewardsConfig {
+            amount: 1_000,
+            denom: UncheckedDenom::Native(ALT_DENOM.to_string()),
+            duration: Duration::Height(10),
+            destination: None,
+            continuous: false,
+        },
+        &hook_caller,
+        Some(Uint128::new(10_000)),
+    );
+    let started_at = suite.app.block_info().height;
+    assert_eq!(
+        suite.get_distribution(2).active_epoch.ends_at,
+        Expiration::AtHeight(started_at + 100)
+    );
+
+    let err = suite
+        .create_rollover(2, 0, 2, None, &coins(5_000, ALT_DENOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidRollover {});
+    suite
+        .create_rollover(2, 2_000, 2, None, &coins(5_000, ALT_DENOM))
+        .unwrap();
+    let err = suite.create_rollover(2, 2_000, 2, None, &[]).unwrap_err();
+    assert_eq!(err, ContractError::RolloverExists { id: 2 });
+
+    // nothing is rolled over until the distribution ends.
+    suite.skip_blocks(50);
+    suite.tick();
+    assert_eq!(suite.get_rollover(2).count, 0);
+
+    // each rollover carries over anything left unemitted and tops it up, starting
+    // when the last epoch ended even if the tick is late.
+    suite.skip_blocks(55);
+    suite.tick();
+    let distribution = suite.get_distribution(2);
+    assert_eq!(distribution.funded_amount, Uint128::new(2_000));
+    assert_eq!(
+        distribution.active_epoch.started_at,
+        Expiration::AtHeight(started_at + 100)
+    );
+    assert_eq!(
+        distribution.active_epoch.ends_at,
+        Expiration::AtHeight(started_at + 120)
+    );
+    assert_eq!(
+        suite.get_rollover(2),
+        Rollover {
+            id: 2,
+            source: FundingSource::Escrow {
+                remaining: Uint128::new(3_000),
+            },
+            top_up: Uint128::new(2_000),
+            remaining: 1,
+            count: 1,
+        }
+    );
+
+    suite.skip_blocks(15);
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(2).active_epoch.ends_at,
+        Expiration::AtHeight(started_at + 140)
+    );
+
+    // once the rollovers are used up, the distribution ends.
+    suite.skip_blocks(20);
+    suite.tick();
+    assert_eq!(
+        suite.get_distribution(2).active_epoch.ends_at,
+        Expiration::AtHeight(started_at + 140)
+    );
+    assert_eq!(suite.get_rollover(2).remaining, 0);
+
+    // every epoch's rewards were distributed.
+    suite.assert_pending_rewards(ADDR1, 2, 7_000);
+    suite.assert_pending_rewards(ADDR2, 2, 3_500);
+    suite.assert_pending_rewards(ADDR3, 2, 3_500);
+
+    let totals = suite.get_totals_by_denom().totals;
+    let alt_totals = totals
+        .iter()
+        .find(|t| t.denom == Denom::Native(ALT_DENOM.to_string()))
+        .unwrap();
+    assert_eq!(alt_totals.funded, Uint128::new(14_000));
+
+    // removing the rollover returns what remains in escrow.
+    suite.remove_rollover(2);
+    suite.assert_native_balance(OWNER, ALT_DENOM, 1_000);
+}
+
+#[test]
+fn test_tick_rollover_pages() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    let hook_caller = suite.staking_addr.to_string();
+
+    // distribution 2 emits one token per block, so that rolling it over
+    // with a top-up of more than u64::MAX tokens overflows its duration.
+    let top_up = u64::MAX as u128 + 1;
+    suite.mint_native(coin(10 + 2 * top_up, ALT_DENOM), OWNER);
+    suite.mint_native(coin(14_000, DENOM), OWNER);
+    for (denom, amount, duration, funds) in [(ALT_DENOM, 1, 1, 10), (DENOM, 1_000, 10, 10_000)] {
+        suite.create(
+            RewardsConfig {
+                amount,
+                denom: UncheckedDenom::Native(denom.to_string()),
+                duration: Duration::Height(duration),
+                destination: None,
+                continuous: false,
+            },
+            &hook_caller,
+            Some(Uint128::new(funds)),
+        );
+    }
+    suite
+        .create_rollover(2, top_up, 2, None, &coins(2 * top_up, ALT_DENOM))
+        .unwrap();
+    suite
+        .create_rollover(3, 2_000, 2, None, &coins(4_000, DENOM))
+        .unwrap();
+
+    // the first page only includes distribution 2, whose rollover fails
+    // without reverting the tick.
+    suite.skip_blocks(100);
+    let res = suite.tick_page(
+        TickPage::default(),
+        TickPage {
+            start_after: None,
+            limit: Some(1),
+        },
+    );
+    assert!(res.has_event(&Event::new("wasm-tick_failed").add_attribute("id", "2")));
+    assert!(res.has_event(&Event::new("wasm").add_attribute("rollovers_start_after", "2")));
+    assert_eq!(suite.get_rollover(2).count, 0);
+    assert_eq!(suite.get_distribution(2).funded_amount, Uint128::new(10));
+    assert_eq!(suite.get_rollover(3).count, 0);
+
+    // the next page rolls distribution 3 over.
+    let res = suite.tick_page(
+        TickPage::default(),
+        TickPage {
+            start_after: Some(2),
+            limit: None,
+        },
+    );
+    assert!(!res.has_event(&Event::new("wasm-tick_failed")));
+    assert_eq!(suite.get_rollover(3).count, 1);
+    assert_eq!(suite.get_distribution(3).funded_amount, Uint128::new(2_000));
+
+    // ticking every rollover rolls distribution 3 over again once it ends,
+    // even though distribution 2 still fails.
+    suite.skip_blocks(20);
+    let res = suite.tick_page(TickPage::default(), TickPage::default());
+    assert!(res.has_event(&Event::new("wasm-tick_failed").add_attribute("id", "2")));
+    assert_eq!(suite.get_rollover(3).count, 2);
+    assert_eq!(suite.get_rollover(2).count, 0);
+}
+
+#[test]
+fn test_rollover_requires_ending_emission() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+    suite.update_emission_rate_to(1, EmissionRate::Paused {});
+    suite.mint_native(coin(5_000, DENOM), OWNER);
+    let err = suite
+        .create_rollover(1, 2_000, 2, None, &coins(5_000, DENOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::RolloverRequiresEndingEmission {});
+}
+
+/// creates raffle 1 of NFTs with the given token IDs, drawn with seeds
+/// committed to by `commitment`, and returns the NFT contract.
+fn setup_raffle(suite: &mut Suite, commitment: HexBinary, token_ids: &[&str]) -> Addr {
+    let cw721_code_id = suite.app.store_code(cw721_base_contract());
+    let nft = suite
+        .app
+        .instantiate_contract(
+            cw721_code_id,
+            Addr::unchecked(OWNER),
+            &cw721_base::msg::InstantiateMsg {
+                name: "Prizes".to_string(),
+                symbol: "PRIZE".to_string(),
+                minter: OWNER.to_string(),
+            },
+            &[],
+            "prizes",
+            None,
+        )
+        .unwrap();
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::CreateRaffle(CreateRaffleMsg {
+                nft_contract: nft.to_string(),
+                vp_contract: suite.voting_power_addr.to_string(),
+                epoch_blocks: 10,
+                seed_commitment: commitment,
+                reveal_blocks: 10,
+            }),
+            &[],
+        )
+        .unwrap();
+
+    for token_id in token_ids {
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                nft.clone(),
+                &cw721_base::msg::ExecuteMsg::<Empty, Empty>::Mint {
+                    token_id: token_id.to_string(),
+                    owner: OWNER.to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                nft.clone(),
+                &cw721_base::msg::ExecuteMsg::<Empty, Empty>::SendNft {
+                    contract: suite.distribution_contract.to_string(),
+                    token_id: token_id.to_string(),
+                    msg: to_json_binary(&ReceiveNftMsg::FundRaffle { id: 1 }).unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    nft
+}
+
+fn execute_raffle(suite: &mut Suite, sender: &str, msg: ExecuteMsg) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(sender),
+            suite.distribution_contract.clone(),
+            &msg,
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|e| e.downcast().unwrap())
+}
+
+fn query_nft_owner(suite: &Suite, nft: &Addr, token_id: &str) -> String {
+    let owner: cw721::OwnerOfResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            nft,
+            &cw721::Cw721QueryMsg::OwnerOf {
+                token_id: token_id.to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    owner.owner
+}
+
+#[test]
+fn test_nft_raffle() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // the seed for each draw hashes to the seed for the draw before it.
+    let second_seed = HexBinary::from(b"second seed".as_slice());
+    let first_seed = HexBinary::from(Sha256::digest(second_seed.as_slice()).as_slice());
+    let commitment = HexBinary::from(Sha256::digest(first_seed.as_slice()).as_slice());
+
+    let nft = setup_raffle(&mut suite, commitment, &["1", "2"]);
+
+    let enter = |suite: &mut Suite, entrant: &str| {
+        execute_raffle(suite, entrant, ExecuteMsg::EnterRaffle { id: 1 })
+    };
+    let draw = |suite: &mut Suite, seed: &HexBinary| {
+        execute_raffle(
+            suite,
+            "anyone",
+            ExecuteMsg::DrawRaffle {
+                id: 1,
+                seed: seed.clone(),
+            },
+        )
+    };
+
+    // stake only counts from the block after it is made.
+    suite.skip_blocks(1);
+
+    // ADDR4 has no voting power, so it may not enter and only ADDR1 may win.
+    enter(&mut suite, ADDR1).unwrap();
+    assert_eq!(
+        enter(&mut suite, ADDR4).unwrap_err(),
+        ContractError::NoRaffleVotingPower { id: 1 }
+    );
+    assert_eq!(
+        enter(&mut suite, ADDR1).unwrap_err(),
+        ContractError::AlreadyEnteredRaffle { id: 1 }
+    );
+
+    assert_eq!(
+        draw(&mut suite, &first_seed).unwrap_err(),
+        ContractError::RaffleEpochNotEnded { id: 1, epoch: 0 }
+    );
+
+    suite.skip_blocks(11);
+    assert_eq!(
+        draw(&mut suite, &second_seed).unwrap_err(),
+        ContractError::InvalidRaffleSeed {}
+    );
+
+    draw(&mut suite, &first_seed).unwrap();
+    assert_eq!(query_nft_owner(&suite, &nft, "1"), ADDR1);
+
+    let raffle: RaffleState = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.distribution_contract, &QueryMsg::Raffle { id: 1 })
+        .unwrap();
+    assert_eq!(raffle.prizes, vec!["2".to_string()]);
+    assert_eq!(raffle.next_epoch, 1);
+    assert_eq!(raffle.entrants, 1);
+
+    // entrants are weighted by voting power, so the draw is won by a staker.
+    enter(&mut suite, ADDR2).unwrap();
+    suite.skip_blocks(10);
+    draw(&mut suite, &second_seed).unwrap();
+    let draws: RaffleDrawsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.distribution_contract,
+            &QueryMsg::RaffleDraws {
+                id: 1,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        draws.draws[0],
+        RaffleDraw {
+            epoch: 0,
+            winner: Some(Addr::unchecked(ADDR1)),
+            token_id: Some("1".to_string()),
+        }
+    );
+    assert_eq!(draws.draws[1].epoch, 1);
+    assert!([Some(Addr::unchecked(ADDR1)), Some(Addr::unchecked(ADDR2))]
+        .contains(&draws.draws[1].winner));
+
+    suite.skip_blocks(10);
+    assert_eq!(
+        draw(&mut suite, &HexBinary::default()).unwrap_err(),
+        ContractError::NoRafflePrizes { id: 1 }
+    );
+
+    execute_raffle(&mut suite, OWNER, ExecuteMsg::CloseRaffle { id: 1 }).unwrap();
+    assert_eq!(
+        enter(&mut suite, ADDR3).unwrap_err(),
+        ContractError::RaffleNotFound { id: 1 }
+    );
+}
+
+#[test]
+fn test_nft_raffle_reveal_deadline() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    let seed = HexBinary::from(b"seed".as_slice());
+    let commitment = HexBinary::from(Sha256::digest(seed.as_slice()).as_slice());
+    let nft = setup_raffle(&mut suite, commitment, &["1"]);
+    suite.skip_blocks(1);
+    execute_raffle(&mut suite, ADDR1, ExecuteMsg::EnterRaffle { id: 1 }).unwrap();
+
+    // the first epoch ends 10 blocks after the raffle was created and must be
+    // drawn within 10 more. until then, only the owner may close the raffle.
+    suite.skip_blocks(19);
+    assert_eq!(
+        execute_raffle(&mut suite, ADDR1, ExecuteMsg::CloseRaffle { id: 1 }).unwrap_err(),
+        ContractError::Ownable(cw_ownable::OwnershipError::NotOwner)
+    );
+
+    // a seed withheld past the deadline may no longer be revealed, and anyone
+    // may close the raffle, returning its NFTs to the owner.
+    suite.skip_blocks(1);
+    assert_eq!(
+        execute_raffle(
+            &mut suite,
+            ADDR1,
+            ExecuteMsg::DrawRaffle {
+                id: 1,
+                seed: seed.clone(),
+            },
+        )
+        .unwrap_err(),
+        ContractError::RaffleRevealExpired { id: 1, epoch: 0 }
+    );
+    execute_raffle(&mut suite, ADDR1, ExecuteMsg::CloseRaffle { id: 1 }).unwrap();
+    assert_eq!(query_nft_owner(&suite, &nft, "1"), OWNER);
+}
+
+#[test]
+fn test_nft_raffle_full() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    let commitment = HexBinary::from(Sha256::digest(b"seed").as_slice());
+    setup_raffle(&mut suite, commitment, &["1"]);
+
+    // fill the raffle with the smallest stakers possible.
+    let entrants = (0..MAX_RAFFLE_ENTRANTS)
+        .map(|i| format!("entrant{i:03}"))
+        .collect::<Vec<_>>();
+    for entrant in &entrants {
+        suite.mint_native(coin(1, DENOM), entrant);
+        suite.stake_native_tokens(entrant, 1);
+    }
+
+    // stake only counts from the next block.
+    assert_eq!(
+        execute_raffle(&mut suite, &entrants[0], ExecuteMsg::EnterRaffle { id: 1 }).unwrap_err(),
+        ContractError::NoRaffleVotingPower { id: 1 }
+    );
+    suite.skip_blocks(1);
+    for entrant in &entrants {
+        execute_raffle(&mut suite, entrant, ExecuteMsg::EnterRaffle { id: 1 }).unwrap();
+    }
+
+    // a staker with more voting power replaces the weakest entrant, who can't
+    // get back in without more voting power than the weakest remaining.
+    execute_raffle(&mut suite, ADDR2, ExecuteMsg::EnterRaffle { id: 1 }).unwrap();
+    assert_eq!(
+        execute_raffle(&mut suite, &entrants[0], ExecuteMsg::EnterRaffle { id: 1 }).unwrap_err(),
+        ContractError::RaffleFull { id: 1 }
+    );
+
+    let raffle: RaffleState = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.distribution_contract, &QueryMsg::Raffle { id: 1 })
+        .unwrap();
+    assert_eq!(raffle.entrants, MAX_RAFFLE_ENTRANTS);
+}
+
+#[test]
+fn test_max_rewards_per_user() {
+    let mut suite = SuiteBuilder::base(super::suite::DaoType::Native).build();
+
+    // cap each user's rewards from the linear distribution at 1_000.
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Update {
+                id: 1,
+                emission_rate: None,
+                vp_contract: None,
+                vp_sources: None,
+                hook_caller: None,
+                withdraw_destination: None,
+                max_rewards_per_user: Some(Uint128::new(1_000)),
+                unclaimed_expiry: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    suite.skip_blocks(100);
+    suite.assert_pending_rewards(ADDR1, 1, 1_000);
+    suite.assert_pending_rewards(ADDR2, 1, 1_000);
+
+    // the 4_000 rewards ADDR1 earned beyond the cap are emitted again,
+    // extending the distribution by 40 blocks.
+    suite.claim_rewards(ADDR1, 1);
+    suite.assert_native_balance(ADDR1, DENOM, 1_000);
+    assert_eq!(
+        suite.get_distribution(1).funded_amount,
+        Uint128::new(100_004_000)
+    );
+    suite.assert_ends_at(Expiration::AtHeight(1_000_040));
+
+    suite.skip_blocks(100);
+    suite.assert_pending_rewards(ADDR1, 1, 0);
+    suite.claim_rewards(ADDR2, 1);
+    suite.assert_native_balance(ADDR2, DENOM, 1_000);
+
+    // removing the cap lets users earn again. caps apply when rewards are
+    // accounted for, so this includes rewards earned since ADDR1 last claimed.
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &ExecuteMsg::Update {
+                id: 1,
+                emission_rate: None,
+                vp_contract: None,
+                vp_sources: None,
+                hook_caller: None,
+                withdraw_destination: None,
+                max_rewards_per_user: Some(Uint128::zero()),
+                unclaimed_expiry: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.get_distribution(1).max_rewards_per_user, None);
+    suite.skip_blocks(10);
+    suite.assert_pending_rewards(ADDR1, 1, 5_500);
+
+    // rewards beyond the cap of an immediate distribution are held until
+    // withdrawn.
+    suite.mint_native(coin(200_000_000, ALT_DENOM), OWNER);
+    let create_msg = |max_rewards_per_user: u128| {
+        ExecuteMsg::Create(CreateMsg {
+            denom: cw20::UncheckedDenom::Native(ALT_DENOM.to_string()),
+            emission_rate: EmissionRate::Immediate {},
+            hook_caller: suite.staking_addr.to_string(),
+            vp_contract: suite.voting_power_addr.to_string(),
+            vp_sources: vec![],
+            withdraw_destination: None,
+            snapshot_height: None,
+            max_rewards_per_user: Some(Uint128::new(max_rewards_per_user)),
+        })
+    };
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &create_msg(0),
+            &coins(100_000_000, ALT_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidMaxRewardsPerUser {});
+
+    let create_msg = create_msg(30_000_000);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.distribution_contract.clone(),
+            &create_msg,
+            &coins(100_000_000, ALT_DENOM),
+        )
+        .unwrap();
+    suite.assert_pending_rewards(ADDR1, 2, 30_000_000);
+    suite.assert_pending_rewards(ADDR2, 2, 25_000_000);
+
+    suite.claim_rewards(ADDR1, 2);
+    suite.assert_native_balance(ADDR1, ALT_DENOM, 30_000_000);
+    assert_eq!(
+        suite.get_distribution(2).capped_rewards,
+        Uint128::new(20_000_000)
+    );
+
+    suite.fund_native(2, coin(100_000_000, ALT_DENOM));
+    suite.assert_pending_rewards(ADDR1, 2, 0);
+    suite.assert_pending_rewards(ADDR2, 2, 30_000_000);
+
+    suite.withdraw(2);
+    suite.assert_native_balance(OWNER, ALT_DENOM, 100_000_000 + 20_000_000);
+    assert_eq!(suite.get_distribution(2).capped_rewards, Uint128::zero());
+}
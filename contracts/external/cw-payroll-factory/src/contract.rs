@@ -60,6 +60,7 @@ pub fn execute(
         ExecuteMsg::UpdateCodeId { vesting_code_id } => {
             execute_update_code_id(deps, info, vesting_code_id)
         }
+        ExecuteMsg::ReindexRecipient { contract } => execute_reindex_recipient(deps, contract),
     }
 }
 
@@ -171,6 +172,28 @@ pub fn execute_update_code_id(
         .add_attribute("vesting_code_id", vesting_code_id.to_string()))
 }
 
+pub fn execute_reindex_recipient(
+    deps: DepsMut,
+    contract: String,
+) -> Result<Response, ContractError> {
+    let mut vesting_contract = vesting_contracts()
+        .may_load(deps.storage, &contract)?
+        .ok_or_else(|| ContractError::UnknownVestingContract {
+            contract: contract.clone(),
+        })?;
+
+    let vest: Vest = deps
+        .querier
+        .query_wasm_smart(&contract, &PayrollQueryMsg::Info {})?;
+    vesting_contract.recipient = vest.recipient.map(String::from).unwrap_or_default();
+    vesting_contracts().save(deps.storage, &contract, &vesting_contract)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "reindex_recipient")
+        .add_attribute("contract", contract)
+        .add_attribute("recipient", vesting_contract.recipient))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -314,7 +337,10 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
                 contract_addr.as_ref(),
                 &VestingContract {
                     instantiator: instantiator.to_string(),
-                    recipient: vest.recipient.to_string(),
+                    // Payments instantiated without a recipient are
+                    // indexed under the empty string until reindexed
+                    // with `ReindexRecipient`.
+                    recipient: vest.recipient.clone().map(String::from).unwrap_or_default(),
                     contract: contract_addr.to_string(),
                 },
             )?;
@@ -23,6 +23,9 @@ pub enum ContractError {
     #[error("Got a submessage reply with unknown id: {id}")]
     UnknownReplyId { id: u64 },
 
+    #[error("({contract}) was not instantiated by this factory")]
+    UnknownVestingContract { contract: String },
+
     #[error("reentered factory during payroll instantiation")]
     Reentrancy,
 
@@ -9,6 +9,7 @@ pub struct InstantiateMsg {
     pub vesting_code_id: u64,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[cw_ownable_execute]
 #[cw_serde]
 pub enum ExecuteMsg {
@@ -23,6 +24,11 @@ pub enum ExecuteMsg {
     /// Callable only by the current owner. Updates the code ID used
     /// while instantiating vesting contracts.
     UpdateCodeId { vesting_code_id: u64 },
+    /// Lists a vesting contract under its current recipient. Vesting
+    /// contracts instantiated without a recipient are listed under
+    /// the empty string until this is called once one is assigned.
+    /// Callable by anyone.
+    ReindexRecipient { contract: String },
 }
 
 // Receiver setup
@@ -91,7 +91,8 @@ pub fn test_instantiate_native_payroll_contract() {
     let instantiate_payroll_msg = ExecuteMsg::InstantiateNativePayrollContract {
         instantiate_msg: PayrollInstantiateMsg {
             owner: Some(ALICE.to_string()),
-            recipient: BOB.to_string(),
+            recipient: Some(BOB.to_string()),
+            vest_from_assignment: false,
             title: "title".to_string(),
             description: Some("desc".to_string()),
             total: amount,
@@ -99,6 +100,19 @@ pub fn test_instantiate_native_payroll_contract() {
             schedule: Schedule::SaturatingLinear,
             vesting_duration_seconds: 200,
             unbonding_duration_seconds: 2592000, // 30 days
+            matching: None,
+            receipts: None,
+            swap: None,
+            off_ramp: None,
+            timesheet: None,
+            severance: None,
+            label: None,
+            metadata: None,
+            additional_vests: vec![],
+            voting_module: None,
+            clawback: None,
+            allow_governance_votes: false,
+            guardian: None,
             start_time: None,
         },
         label: "Payroll".to_string(),
@@ -266,7 +280,8 @@ pub fn test_instantiate_cw20_payroll_contract() {
 
     let instantiate_payroll_msg = PayrollInstantiateMsg {
         owner: Some(ALICE.to_string()),
-        recipient: BOB.to_string(),
+        recipient: Some(BOB.to_string()),
+        vest_from_assignment: false,
         title: "title".to_string(),
         description: Some("desc".to_string()),
         total: amount,
@@ -274,6 +289,19 @@ pub fn test_instantiate_cw20_payroll_contract() {
         schedule: Schedule::SaturatingLinear,
         vesting_duration_seconds: 200,
         unbonding_duration_seconds: 2592000, // 30 days
+        matching: None,
+        receipts: None,
+        swap: None,
+        off_ramp: None,
+        timesheet: None,
+        severance: None,
+        label: None,
+        metadata: None,
+        additional_vests: vec![],
+        voting_module: None,
+        clawback: None,
+        allow_governance_votes: false,
+        guardian: None,
         start_time: None,
     };
 
@@ -388,7 +416,8 @@ fn test_instantiate_wrong_ownership_native() {
             &ExecuteMsg::InstantiateNativePayrollContract {
                 instantiate_msg: PayrollInstantiateMsg {
                     owner: Some(ALICE.to_string()),
-                    recipient: BOB.to_string(),
+                    recipient: Some(BOB.to_string()),
+                    vest_from_assignment: false,
                     title: "title".to_string(),
                     description: Some("desc".to_string()),
                     total: amount,
@@ -396,6 +425,19 @@ fn test_instantiate_wrong_ownership_native() {
                     schedule: Schedule::SaturatingLinear,
                     vesting_duration_seconds: 200,
                     unbonding_duration_seconds: 2592000, // 30 days
+                    matching: None,
+                    receipts: None,
+                    swap: None,
+                    off_ramp: None,
+                    timesheet: None,
+                    severance: None,
+                    label: None,
+                    metadata: None,
+                    additional_vests: vec![],
+                    voting_module: None,
+                    clawback: None,
+                    allow_governance_votes: false,
+                    guardian: None,
                     start_time: None,
                 },
                 label: "vesting".to_string(),
@@ -472,7 +514,8 @@ fn test_update_vesting_code_id() {
     let instantiate_payroll_msg = ExecuteMsg::InstantiateNativePayrollContract {
         instantiate_msg: PayrollInstantiateMsg {
             owner: Some(ALICE.to_string()),
-            recipient: BOB.to_string(),
+            recipient: Some(BOB.to_string()),
+            vest_from_assignment: false,
             title: "title".to_string(),
             description: Some("desc".to_string()),
             total: amount,
@@ -480,6 +523,19 @@ fn test_update_vesting_code_id() {
             schedule: Schedule::SaturatingLinear,
             vesting_duration_seconds: 200,
             unbonding_duration_seconds: 2592000, // 30 days
+            matching: None,
+            receipts: None,
+            swap: None,
+            off_ramp: None,
+            timesheet: None,
+            severance: None,
+            label: None,
+            metadata: None,
+            additional_vests: vec![],
+            voting_module: None,
+            clawback: None,
+            allow_governance_votes: false,
+            guardian: None,
             start_time: None,
         },
         label: "Payroll".to_string(),
@@ -562,7 +618,8 @@ pub fn test_inconsistent_cw20_amount() {
     let unchecked_denom = UncheckedDenom::Cw20(cw20_addr.to_string());
     let instantiate_payroll_msg = PayrollInstantiateMsg {
         owner: Some(ALICE.to_string()),
-        recipient: BOB.to_string(),
+        recipient: Some(BOB.to_string()),
+        vest_from_assignment: false,
         title: "title".to_string(),
         description: Some("desc".to_string()),
         total: amount - Uint128::new(1), // lesser amount than sent
@@ -570,6 +627,19 @@ pub fn test_inconsistent_cw20_amount() {
         schedule: Schedule::SaturatingLinear,
         vesting_duration_seconds: 200,
         unbonding_duration_seconds: 2592000, // 30 days
+        matching: None,
+        receipts: None,
+        swap: None,
+        off_ramp: None,
+        timesheet: None,
+        severance: None,
+        label: None,
+        metadata: None,
+        additional_vests: vec![],
+        voting_module: None,
+        clawback: None,
+        allow_governance_votes: false,
+        guardian: None,
         start_time: None,
     };
     let err: ContractError = app
@@ -598,3 +668,150 @@ pub fn test_inconsistent_cw20_amount() {
         }
     );
 }
+
+#[test]
+fn test_reindex_recipient() {
+    let mut app = App::default();
+    let code_id = app.store_code(factory_contract());
+    let cw_vesting_code_id = app.store_code(cw_vesting_contract());
+
+    let factory_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked("CREATOR"),
+            &InstantiateMsg {
+                owner: Some(ALICE.to_string()),
+                vesting_code_id: cw_vesting_code_id,
+            },
+            &[],
+            "cw-admin-factory",
+            None,
+        )
+        .unwrap();
+
+    app.sudo(SudoMsg::Bank({
+        BankSudo::Mint {
+            to_address: ALICE.to_string(),
+            amount: coins(INITIAL_BALANCE, NATIVE_DENOM),
+        }
+    }))
+    .unwrap();
+
+    let amount = Uint128::new(1000000);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            factory_addr.clone(),
+            &ExecuteMsg::InstantiateNativePayrollContract {
+                instantiate_msg: PayrollInstantiateMsg {
+                    owner: Some(ALICE.to_string()),
+                    recipient: None,
+                    vest_from_assignment: false,
+                    title: "title".to_string(),
+                    description: None,
+                    total: amount,
+                    denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+                    schedule: Schedule::SaturatingLinear,
+                    vesting_duration_seconds: 200,
+                    unbonding_duration_seconds: 2592000, // 30 days
+                    matching: None,
+                    receipts: None,
+                    swap: None,
+                    off_ramp: None,
+                    timesheet: None,
+                    severance: None,
+                    label: None,
+                    metadata: None,
+                    additional_vests: vec![],
+                    voting_module: None,
+                    clawback: None,
+                    allow_governance_votes: false,
+                    guardian: None,
+                    start_time: None,
+                },
+                label: "Payroll".to_string(),
+            },
+            &coins(amount.into(), NATIVE_DENOM),
+        )
+        .unwrap();
+    let cw_vesting_addr = res.events[2].attributes[0].value.clone();
+
+    let by_recipient = |app: &App, recipient: &str| -> Vec<VestingContract> {
+        app.wrap()
+            .query_wasm_smart(
+                factory_addr.clone(),
+                &QueryMsg::ListVestingContractsByRecipient {
+                    recipient: recipient.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    };
+
+    let all = |app: &App| -> Vec<VestingContract> {
+        app.wrap()
+            .query_wasm_smart(
+                factory_addr.clone(),
+                &QueryMsg::ListVestingContracts {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    };
+
+    // Without a recipient the contract is listed under the empty string.
+    assert_eq!(all(&app)[0].recipient, "");
+    assert_eq!(by_recipient(&app, BOB).len(), 0);
+
+    app.execute_contract(
+        Addr::unchecked(ALICE),
+        Addr::unchecked(&cw_vesting_addr),
+        &cw_vesting::msg::ExecuteMsg::AssignRecipient {
+            recipient: BOB.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    // Assignment alone does not update the factory's index.
+    assert_eq!(by_recipient(&app, BOB).len(), 0);
+
+    // Anyone may reindex.
+    app.execute_contract(
+        Addr::unchecked("anyone"),
+        factory_addr.clone(),
+        &ExecuteMsg::ReindexRecipient {
+            contract: cw_vesting_addr.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let contracts = by_recipient(&app, BOB);
+    assert_eq!(contracts.len(), 1);
+    assert_eq!(contracts[0].contract, cw_vesting_addr);
+    assert_eq!(contracts[0].recipient, BOB);
+    assert_eq!(all(&app).len(), 1);
+
+    // Contracts not instantiated by the factory can not be indexed.
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked("anyone"),
+            factory_addr.clone(),
+            &ExecuteMsg::ReindexRecipient {
+                contract: "other".to_string(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::UnknownVestingContract {
+            contract: "other".to_string()
+        }
+    );
+}
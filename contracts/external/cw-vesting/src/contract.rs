@@ -1,30 +1,68 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
+#[cfg(feature = "governance")]
+use cosmwasm_std::GovMsg;
 use cosmwasm_std::{
-    from_json, to_json_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Uint128,
+    coins, from_json, to_json_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, Event,
+    MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResult,
+    Timestamp, Uint128, WasmMsg,
 };
 #[cfg(feature = "staking")]
-use cosmwasm_std::{
-    Coin, DelegationResponse, DistributionMsg, StakingMsg, StakingQuery, Timestamp,
-};
+use cosmwasm_std::{Coin, DelegationResponse, DistributionMsg, StakingMsg, StakingQuery};
 use cw2::set_contract_version;
-use cw20::Cw20ReceiveMsg;
-use cw_denom::CheckedDenom;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_denom::{CheckedDenom, UncheckedDenom};
 use cw_ownable::OwnershipError;
-use cw_utils::{must_pay, nonpayable};
+use cw_storage_plus::Bound;
+use cw_utils::{must_pay, nonpayable, parse_reply_instantiate_data, PaymentError};
+use dao_hooks::stake::{StakeChangedExecuteMsg, StakeChangedHookMsg};
+use dao_interface::voting::{TotalPowerAtHeightResponse, VotingPowerAtHeightResponse};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use crate::state::{PAYMENT, UNBONDING_DURATION_SECONDS};
-use crate::vesting::{Status, VestInit};
+use crate::msg::{
+    DistributionScheduleResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiptCollectionMsg,
+    ReceiptMetadata, ReceiveMsg, TransferResponse,
+};
+use crate::state::{
+    AdditionalPayment, DistributionMemo, Guardian, Matching, OffRamp, Swap, Transfer,
+    TransferApproval, ADDITIONAL_DENOMS, ALLOW_GOVERNANCE_VOTES, CLAWBACK, DISTRIBUTION_COUNT,
+    DISTRIBUTION_MEMOS, DISTRIBUTION_SCHEDULE, GUARDIAN, HELD_POWER, HOOKS, MATCHING, OFF_RAMP,
+    OFF_RAMP_PAYOUTS, PAYMENT, PENDING_MATCH, PENDING_SWAP, PENDING_TRANSFER, RECEIPTS,
+    RECEIPT_COUNT, SWAP, TOTAL_VOTING_POWER, TRANSFER, TRANSFER_APPROVAL,
+    UNBONDING_DURATION_SECONDS, VEST_FROM_ASSIGNMENT, VOTING_MODULE, VOTING_POWER,
+};
+use crate::vesting::{
+    BalanceCheck, Payment, Schedule, Status, Summary, VerifyBalancesResponse, VestInit,
+};
 
 const CONTRACT_NAME: &str = "crates.io:cw-vesting";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const RECEIPT_REPLY_ID: u64 = 0;
+const SWAP_REPLY_ID: u64 = 1;
+const TRANSFER_REPLY_ID: u64 = 2;
+const MATCH_REPLY_ID: u64 = 3;
+
+/// The default number of off-ramp payouts listed at once.
+const DEFAULT_OFF_RAMP_PAYOUTS_LIMIT: u32 = 30;
+
+/// The maximum length, in bytes, of a distribution's memo.
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+/// The number of most recent distributions whose memos are kept.
+pub const MAX_STORED_MEMOS: u64 = 100;
+
+/// The default number of distribution memos listed at once.
+const DEFAULT_DISTRIBUTION_MEMOS_LIMIT: u32 = 30;
+
+/// The maximum number of validators undelegated from when canceling
+/// with `unbond`, so that a recipient can not make cancelation run out
+/// of gas by delegating to many validators.
+pub const MAX_CANCEL_UNDELEGATIONS: usize = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
@@ -33,13 +71,55 @@ pub fn instantiate(
     cw_ownable::initialize_owner(deps.storage, deps.api, msg.owner.as_deref())?;
 
     let denom = msg.denom.into_checked(deps.as_ref())?;
-    let recipient = deps.api.addr_validate(&msg.recipient)?;
+    let recipient = msg
+        .recipient
+        .map(|r| deps.api.addr_validate(&r))
+        .transpose()?;
     let start_time = msg.start_time.unwrap_or(env.block.time);
+    if let Schedule::ValueTargeted { ref oracle, .. } = msg.schedule {
+        deps.api.addr_validate(oracle)?;
+    }
+    if let Some(ref timesheet) = msg.timesheet {
+        deps.api.addr_validate(&timesheet.oracle)?;
+    }
 
     if start_time.plus_seconds(msg.vesting_duration_seconds) <= env.block.time {
         return Err(ContractError::Instavest);
     }
 
+    let mut denoms = vec![denom.clone()];
+    let mut additional_vests = vec![];
+    for additional in msg.additional_vests {
+        let denom = additional.denom.into_checked(deps.as_ref())?;
+        if denoms.contains(&denom) {
+            return Err(ContractError::DuplicateDenom {
+                denom: denom.to_string(),
+            });
+        }
+        if matches!(additional.schedule, Schedule::ValueTargeted { .. }) {
+            return Err(ContractError::AdditionalValueTargeted);
+        }
+        if start_time.plus_seconds(additional.vesting_duration_seconds) <= env.block.time {
+            return Err(ContractError::Instavest);
+        }
+        denoms.push(denom.clone());
+        additional_vests.push(VestInit {
+            total: additional.total,
+            schedule: additional.schedule,
+            start_time,
+            duration_seconds: additional.vesting_duration_seconds,
+            denom,
+            recipient: recipient.clone(),
+            title: msg.title.clone(),
+            description: msg.description.clone(),
+            severance: msg.severance.unwrap_or_default(),
+            label: msg.label.clone(),
+            metadata: msg.metadata.clone(),
+            timesheet: None,
+        });
+    }
+    let additional_denoms = denoms[1..].to_vec();
+
     let vest = PAYMENT.initialize(
         deps.storage,
         VestInit {
@@ -51,13 +131,128 @@ pub fn instantiate(
             recipient,
             title: msg.title,
             description: msg.description,
+            severance: msg.severance.unwrap_or_default(),
+            label: msg.label,
+            metadata: msg.metadata,
+            timesheet: msg.timesheet,
         },
     )?;
     UNBONDING_DURATION_SECONDS.save(deps.storage, &msg.unbonding_duration_seconds)?;
 
+    // Native additional denoms are funded on instantiation, along with
+    // the primary denom if it is native.
+    for (index, init) in additional_vests.into_iter().enumerate() {
+        let keys = AdditionalPayment::new(index);
+        let additional = keys.payment().initialize(deps.storage, init)?;
+        if let CheckedDenom::Native(ref denom) = additional.denom {
+            let sent = sent_amount(&info, denom);
+            if additional.total() != sent {
+                return Err(ContractError::WrongFundAmount {
+                    sent,
+                    expected: additional.total(),
+                });
+            }
+            keys.payment().set_funded(deps.storage)?;
+        }
+    }
+    if !additional_denoms.is_empty() {
+        if let Some(coin) = info
+            .funds
+            .iter()
+            .find(|coin| !denoms.iter().any(|d| d.is_native(&coin.denom)))
+        {
+            return Err(PaymentError::ExtraDenom(coin.denom.clone()).into());
+        }
+    }
+    ADDITIONAL_DENOMS.save(deps.storage, &additional_denoms)?;
+
+    if vest.recipient.is_none() {
+        // Only the owner may assign a recipient, so without one the
+        // funds would be locked forever.
+        if msg.owner.is_none() {
+            return Err(ContractError::UnassignedWithoutOwner);
+        }
+        VEST_FROM_ASSIGNMENT.save(deps.storage, &msg.vest_from_assignment)?;
+    }
+
+    if let Some(voting_module) = msg.voting_module {
+        let voting_module = deps.api.addr_validate(&voting_module)?;
+        VOTING_MODULE.save(deps.storage, &voting_module)?;
+    }
+
+    if let Some(clawback) = msg.clawback {
+        // Only the owner may cancel, so without one nothing would
+        // ever be clawed back.
+        if msg.owner.is_none() {
+            return Err(ContractError::ClawbackWithoutOwner);
+        }
+        CLAWBACK.save(deps.storage, &deps.api.addr_validate(&clawback)?)?;
+    }
+
+    ALLOW_GOVERNANCE_VOTES.save(deps.storage, &msg.allow_governance_votes)?;
+
+    if let Some(guardian) = msg.guardian {
+        GUARDIAN.save(
+            deps.storage,
+            &Guardian {
+                guardian: Some(deps.api.addr_validate(&guardian)?),
+                pending: None,
+                paused: false,
+            },
+        )?;
+    }
+
+    if let Some(receipts) = msg.receipts {
+        RECEIPTS.save(deps.storage, &deps.api.addr_validate(&receipts)?)?;
+    }
+
+    if let Some(matching) = msg.matching {
+        // Only the owner may fund the matching pool, so without one
+        // matching would never happen.
+        if msg.owner.is_none() {
+            return Err(ContractError::MatchingWithoutOwner);
+        }
+        let matching = Matching {
+            distributor: deps.api.addr_validate(&matching.distributor)?,
+            distribution_id: matching.distribution_id,
+            ratio: matching.ratio,
+            denom: matching.denom.into_checked(deps.as_ref())?,
+            pool: Uint128::zero(),
+        };
+        MATCHING.save(deps.storage, &matching)?;
+    }
+
+    if let Some(swap) = msg.swap {
+        let swap = Swap {
+            adapter: deps.api.addr_validate(&swap.adapter)?,
+            ask_denom: swap.ask_denom.into_checked(deps.as_ref())?,
+            max_slippage: None,
+        };
+        SWAP.save(deps.storage, &swap)?;
+    }
+
+    if let Some(off_ramp) = msg.off_ramp {
+        if SWAP.exists(deps.storage) {
+            return Err(ContractError::SwapWithOffRamp);
+        }
+        let off_ramp = OffRamp {
+            escrow: deps.api.addr_validate(&off_ramp.escrow)?,
+            recipient_id: off_ramp.recipient_id,
+            payouts: 0,
+            acknowledged: 0,
+            amount_paid_out: Uint128::zero(),
+            amount_acknowledged: Uint128::zero(),
+        };
+        OFF_RAMP.save(deps.storage, &off_ramp)?;
+    }
+
     let resp: Option<CosmosMsg> = match vest.denom {
         CheckedDenom::Native(ref denom) => {
-            let sent = must_pay(&info, denom)?;
+            let sent = if additional_denoms.is_empty() {
+                must_pay(&info, denom)?
+            } else {
+                sent_amount(&info, denom)
+            };
             if vest.total() != sent {
                 return Err(ContractError::WrongFundAmount {
                     sent,
@@ -71,33 +266,106 @@ pub fn instantiate(
             // payment receiver so that when they stake vested tokens
             // they receive the rewards.
             #[cfg(feature = "staking")]
-            if denom.as_str() == deps.querier.query_bonded_denom()? {
-                Some(CosmosMsg::Distribution(
-                    DistributionMsg::SetWithdrawAddress {
-                        address: vest.recipient.to_string(),
-                    },
-                ))
-            } else {
-                None
+            match vest.recipient {
+                Some(ref recipient) if denom.as_str() == deps.querier.query_bonded_denom()? => {
+                    Some(CosmosMsg::Distribution(
+                        DistributionMsg::SetWithdrawAddress {
+                            address: recipient.to_string(),
+                        },
+                    ))
+                }
+                _ => None,
             }
 
             #[cfg(not(feature = "staking"))]
             None
         }
         CheckedDenom::Cw20(_) => {
-            nonpayable(&info)?; // Funding happens in ExecuteMsg::Receive.
+            if additional_denoms.is_empty() {
+                nonpayable(&info)?; // Funding happens in ExecuteMsg::Receive.
+            }
             None
         }
     };
 
+    // The voting module is told about the recipient's held power from
+    // the start.
+    let hook_msgs = checkpoint_voting_power(deps.branch(), &env)?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", msg.owner.unwrap_or_else(|| "None".to_string()))
-        .add_messages(resp))
+        .add_messages(resp)
+        .add_submessages(hook_msgs))
+}
+
+/// The amount of native `denom` sent with a message.
+fn sent_amount(info: &MessageInfo, denom: &str) -> Uint128 {
+    info.funds
+        .iter()
+        .filter(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+        .sum()
+}
+
+/// Finds the index of `denom` among the additional denoms, or `None`
+/// if it is the primary denom.
+fn additional_denom_index(
+    deps: Deps,
+    denom: UncheckedDenom,
+) -> Result<Option<usize>, ContractError> {
+    let denom = match denom {
+        UncheckedDenom::Native(denom) => CheckedDenom::Native(denom),
+        UncheckedDenom::Cw20(addr) => CheckedDenom::Cw20(deps.api.addr_validate(&addr)?),
+    };
+    if PAYMENT.get_vest(deps.storage)?.denom == denom {
+        return Ok(None);
+    }
+    ADDITIONAL_DENOMS
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .iter()
+        .position(|d| *d == denom)
+        .map(Some)
+        .ok_or(ContractError::UnknownDenom {
+            denom: denom.to_string(),
+        })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let checkpoint = changes_balance(&msg);
+    let response = dispatch_execute(deps.branch(), env.clone(), info, msg)?;
+    if !checkpoint {
+        return Ok(response);
+    }
+    let hook_msgs = checkpoint_voting_power(deps, &env)?;
+    Ok(response.add_submessages(hook_msgs))
+}
+
+/// True if `msg` may fund, cancel, pay out of, slash, transfer, or
+/// assign the vest, and so change the recipient's voting power.
+/// `Checkpoint` is included to count tokens vested since the last one.
+fn changes_balance(msg: &ExecuteMsg) -> bool {
+    matches!(
+        msg,
+        ExecuteMsg::Receive(_)
+            | ExecuteMsg::Cancel { .. }
+            | ExecuteMsg::Distribute { .. }
+            | ExecuteMsg::CompleteCancellation {}
+            | ExecuteMsg::AssignRecipient { .. }
+            | ExecuteMsg::ApproveTransfer { .. }
+            | ExecuteMsg::RegisterSlash { .. }
+            | ExecuteMsg::Checkpoint {}
+    )
+}
+
+fn dispatch_execute(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
@@ -105,11 +373,51 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => execute_receive_cw20(env, deps, info, msg),
-        ExecuteMsg::Cancel {} => execute_cancel_vesting_payment(env, deps, info),
-        ExecuteMsg::Distribute { amount } => execute_distribute(env, deps, amount),
+        ExecuteMsg::Cancel {
+            require_consistent_balances,
+            unbond,
+        } => execute_cancel_vesting_payment(
+            env,
+            deps,
+            info,
+            require_consistent_balances.unwrap_or_default(),
+            unbond.unwrap_or_default(),
+        ),
+        ExecuteMsg::Distribute {
+            amount,
+            memo,
+            denom,
+        } => execute_distribute(env, deps, info, amount, memo, denom),
         ExecuteMsg::WithdrawCanceledPayment { amount } => {
             execute_withdraw_canceled_payment(deps, env, amount)
         }
+        ExecuteMsg::CompleteCancellation {} => execute_complete_cancellation(deps, env),
+        ExecuteMsg::FundMatchingPool {} => execute_fund_matching_pool(deps, info),
+        ExecuteMsg::WithdrawMatchingPool { amount } => {
+            execute_withdraw_matching_pool(deps, info, amount)
+        }
+        ExecuteMsg::AssignRecipient { recipient } => {
+            execute_assign_recipient(deps, env, info, recipient)
+        }
+        ExecuteMsg::SetMaxSlippage { max_slippage } => {
+            execute_set_max_slippage(deps, info, max_slippage)
+        }
+        ExecuteMsg::SetDistributionInterval { interval } => {
+            execute_set_distribution_interval(deps, info, interval)
+        }
+        ExecuteMsg::AcknowledgeOffRampPayouts { payout_ids } => {
+            execute_acknowledge_off_ramp_payouts(deps, env, info, payout_ids)
+        }
+        ExecuteMsg::ApproveTransfer { code_id } => {
+            execute_approve_transfer(deps, env, info, code_id)
+        }
+        ExecuteMsg::RevokeTransferApproval {} => execute_revoke_transfer_approval(deps, info),
+        ExecuteMsg::UpdateGuardian { guardian } => execute_update_guardian(deps, info, guardian),
+        ExecuteMsg::AcceptGuardian {} => execute_accept_guardian(deps, info),
+        ExecuteMsg::SetPaused { paused } => execute_set_paused(deps, info, paused),
+        ExecuteMsg::Checkpoint {} => Ok(Response::new().add_attribute("method", "checkpoint")),
+        ExecuteMsg::AddHook { addr } => execute_add_hook(deps, info, addr),
+        ExecuteMsg::RemoveHook { addr } => execute_remove_hook(deps, info, addr),
         ExecuteMsg::UpdateOwnership(action) => execute_update_owner(deps, info, env, action),
         #[cfg(feature = "staking")]
         ExecuteMsg::Delegate { validator, amount } => {
@@ -131,6 +439,30 @@ pub fn execute(
         }
         #[cfg(feature = "staking")]
         ExecuteMsg::WithdrawDelegatorReward { validator } => execute_withdraw_rewards(validator),
+        #[cfg(feature = "governance")]
+        ExecuteMsg::Vote {
+            proposal_id,
+            option,
+        } => execute_vote(
+            deps,
+            info,
+            GovMsg::Vote {
+                proposal_id,
+                vote: option,
+            },
+        ),
+        #[cfg(feature = "governance")]
+        ExecuteMsg::VoteWeighted {
+            proposal_id,
+            options,
+        } => execute_vote(
+            deps,
+            info,
+            GovMsg::VoteWeighted {
+                proposal_id,
+                options,
+            },
+        ),
         #[cfg(feature = "staking")]
         ExecuteMsg::RegisterSlash {
             validator,
@@ -141,6 +473,185 @@ pub fn execute(
     }
 }
 
+/// Records the recipient's vested but unclaimed tokens as their voting
+/// power and, if a voting module is configured, every token held for
+/// them as their held power, returning hook messages for the changes.
+fn checkpoint_voting_power(deps: DepsMut, env: &Env) -> Result<Vec<SubMsg>, ContractError> {
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let Some(recipient) = vest.recipient.clone() else {
+        return Ok(vec![]);
+    };
+    let (unclaimed, held) = match vest.status {
+        Status::Unfunded => (Uint128::zero(), Uint128::zero()),
+        _ => (
+            vest.vested(env.block.time).saturating_sub(vest.claimed),
+            vest.total()
+                .saturating_sub(vest.claimed)
+                .saturating_sub(vest.slashed),
+        ),
+    };
+
+    let height = env.block.height;
+    let mut msgs = vec![];
+    let previous = VOTING_POWER
+        .may_load(deps.storage, &recipient)?
+        .unwrap_or_default();
+    if let Some(change) = power_change(recipient.clone(), previous, unclaimed) {
+        VOTING_POWER.save(deps.storage, &recipient, &unclaimed, height)?;
+        TOTAL_VOTING_POWER.save(deps.storage, &unclaimed, height)?;
+        msgs.extend(HOOKS.prepare_hooks(deps.storage, |hook| hook_msg(&hook, &change))?);
+    }
+    if let Some(voting_module) = VOTING_MODULE.may_load(deps.storage)? {
+        let previous = HELD_POWER.may_load(deps.storage)?.unwrap_or_default();
+        if let Some(change) = power_change(recipient, previous, held) {
+            HELD_POWER.save(deps.storage, &held, height)?;
+            msgs.push(hook_msg(&voting_module, &change)?);
+        }
+    }
+    Ok(msgs)
+}
+
+/// The hook message for `addr`'s power changing from `previous` to
+/// `power`, if it changed.
+fn power_change(addr: Addr, previous: Uint128, power: Uint128) -> Option<StakeChangedHookMsg> {
+    if power == previous {
+        return None;
+    }
+    Some(if power > previous {
+        StakeChangedHookMsg::Stake {
+            addr,
+            amount: power - previous,
+        }
+    } else {
+        StakeChangedHookMsg::Unstake {
+            addr,
+            amount: previous - power,
+        }
+    })
+}
+
+fn hook_msg(contract: &Addr, change: &StakeChangedHookMsg) -> StdResult<SubMsg> {
+    Ok(SubMsg::new(WasmMsg::Execute {
+        contract_addr: contract.to_string(),
+        msg: to_json_binary(&StakeChangedExecuteMsg::StakeChangeHook(change.clone()))?,
+        funds: vec![],
+    }))
+}
+
+pub fn execute_update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut state = GUARDIAN.may_load(deps.storage)?.unwrap_or_default();
+    let event = match guardian {
+        Some(guardian) => {
+            let guardian = deps.api.addr_validate(&guardian)?;
+            state.pending = Some(guardian.clone());
+            Event::new("guardian_proposed").add_attribute("guardian", guardian)
+        }
+        None => {
+            let removed = state.guardian.take();
+            state.pending = None;
+            Event::new("guardian_removed").add_attribute(
+                "guardian",
+                removed.map_or_else(|| "none".to_string(), |g| g.into_string()),
+            )
+        }
+    };
+    GUARDIAN.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_guardian")
+        .add_event(event))
+}
+
+pub fn execute_accept_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut state = GUARDIAN.may_load(deps.storage)?.unwrap_or_default();
+    if state.pending.as_ref() != Some(&info.sender) {
+        return Err(ContractError::NotPendingGuardian);
+    }
+    state.guardian = state.pending.take();
+    GUARDIAN.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_guardian")
+        .add_event(Event::new("guardian_accepted").add_attribute("guardian", info.sender)))
+}
+
+pub fn execute_set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut state = GUARDIAN.may_load(deps.storage)?.unwrap_or_default();
+    if state.guardian.as_ref() != Some(&info.sender)
+        && !cw_ownable::is_owner(deps.storage, &info.sender)?
+    {
+        return Err(ContractError::NotGuardian);
+    }
+    state.paused = paused;
+    GUARDIAN.save(deps.storage, &state)?;
+
+    let event = if paused {
+        Event::new("paused")
+    } else {
+        Event::new("unpaused")
+    };
+    Ok(Response::new()
+        .add_attribute("method", "set_paused")
+        .add_event(event.add_attribute("sender", info.sender)))
+}
+
+/// Errors if the guardian or the owner has paused distributions and
+/// delegation.
+fn assert_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if GUARDIAN.may_load(storage)?.is_some_and(|g| g.paused) {
+        return Err(ContractError::Paused);
+    }
+    Ok(())
+}
+
+pub fn execute_add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.add_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_hook")
+        .add_attribute("hook", addr))
+}
+
+pub fn execute_remove_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.remove_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_hook")
+        .add_attribute("hook", addr))
+}
+
 pub fn execute_receive_cw20(
     _env: Env,
     deps: DepsMut,
@@ -154,7 +665,17 @@ pub fn execute_receive_cw20(
 
     match msg {
         ReceiveMsg::Fund {} => {
-            let vest = PAYMENT.get_vest(deps.storage)?;
+            let additional = ADDITIONAL_DENOMS
+                .may_load(deps.storage)?
+                .unwrap_or_default()
+                .iter()
+                .position(|denom| denom.is_cw20(&info.sender));
+            let keys = additional.map(AdditionalPayment::new);
+            let payment = match keys {
+                Some(ref keys) => keys.payment(),
+                None => PAYMENT,
+            };
+            let vest = payment.get_vest(deps.storage)?;
 
             if vest.total() != receive_msg.amount {
                 return Err(ContractError::WrongFundAmount {
@@ -171,11 +692,35 @@ pub fn execute_receive_cw20(
                 return Err(ContractError::Funded);
             } // correct status
 
-            PAYMENT.set_funded(deps.storage)?;
+            payment.set_funded(deps.storage)?;
 
             Ok(Response::new()
                 .add_attribute("method", "fund_cw20_vesting_payment")
-                .add_attribute("receiver", vest.recipient.to_string()))
+                .add_attribute("denom", vest.denom.to_string())
+                .add_attribute(
+                    "receiver",
+                    vest.recipient
+                        .map(String::from)
+                        .unwrap_or_else(|| "None".to_string()),
+                ))
+        }
+        ReceiveMsg::FundMatchingPool {} => {
+            cw_ownable::assert_owner(deps.storage, &deps.api.addr_validate(&receive_msg.sender)?)?;
+
+            let mut matching = MATCHING
+                .may_load(deps.storage)?
+                .ok_or(ContractError::NoMatching)?;
+            if !matching.denom.is_cw20(&info.sender) {
+                return Err(ContractError::WrongCw20);
+            }
+
+            matching.pool += receive_msg.amount;
+            MATCHING.save(deps.storage, &matching)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "fund_matching_pool")
+                .add_attribute("amount", receive_msg.amount)
+                .add_attribute("pool", matching.pool))
         }
     }
 }
@@ -184,52 +729,742 @@ pub fn execute_cancel_vesting_payment(
     env: Env,
     deps: DepsMut,
     info: MessageInfo,
+    require_consistent_balances: bool,
+    unbond: bool,
 ) -> Result<Response, ContractError> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    let msgs = PAYMENT.cancel(deps.storage, env.block.time, &info.sender)?;
+    if require_consistent_balances && !query_verify_balances(deps.as_ref(), &env)?.consistent {
+        return Err(ContractError::InconsistentBalances);
+    }
+
+    let clawback = CLAWBACK
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| info.sender.clone());
+
+    PAYMENT.index_value(deps.storage, deps.querier, env.block.time)?;
+    let mut msgs = PAYMENT.cancel(deps.storage, env.block.time, &clawback)?;
+    let additional_denoms = ADDITIONAL_DENOMS
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    for index in 0..additional_denoms.len() {
+        msgs.extend(AdditionalPayment::new(index).payment().cancel_unstaked(
+            deps.storage,
+            env.block.time,
+            &clawback,
+        )?);
+    }
+    if unbond {
+        msgs.extend(cancel_undelegations(deps, &env)?);
+    }
 
     Ok(Response::new()
         .add_attribute("method", "remove_vesting_payment")
         .add_attribute("owner", info.sender)
+        .add_attribute("clawback", clawback)
         .add_attribute("removed_time", env.block.time.to_string())
         .add_messages(msgs))
 }
 
+/// Undelegates the contract's delegations with up to
+/// `MAX_CANCEL_UNDELEGATIONS` validators after the payment has been
+/// canceled.
+#[cfg(feature = "staking")]
+fn cancel_undelegations(deps: DepsMut, env: &Env) -> Result<Vec<CosmosMsg>, ContractError> {
+    let bonded_denom = deps.querier.query_bonded_denom()?;
+    let ubs = UNBONDING_DURATION_SECONDS.load(deps.storage)?;
+    let delegations = deps
+        .querier
+        .query_all_delegations(&env.contract.address)?
+        .into_iter()
+        .filter(|delegation| {
+            delegation.amount.denom == bonded_denom && !delegation.amount.amount.is_zero()
+        })
+        .take(MAX_CANCEL_UNDELEGATIONS);
+
+    let mut msgs = vec![];
+    for delegation in delegations {
+        PAYMENT.on_undelegate(
+            deps.storage,
+            env.block.time,
+            delegation.validator.clone(),
+            delegation.amount.amount,
+            ubs,
+        )?;
+        msgs.push(
+            StakingMsg::Undelegate {
+                validator: delegation.validator,
+                amount: delegation.amount,
+            }
+            .into(),
+        );
+    }
+    Ok(msgs)
+}
+
+#[cfg(not(feature = "staking"))]
+fn cancel_undelegations(_deps: DepsMut, _env: &Env) -> Result<Vec<CosmosMsg>, ContractError> {
+    Ok(vec![])
+}
+
+/// Gets the address unvested tokens are returned to on cancellation:
+/// the clawback address if one is configured, and the owner
+/// otherwise.
+fn get_clawback(storage: &dyn Storage) -> StdResult<Option<Addr>> {
+    match CLAWBACK.may_load(storage)? {
+        Some(clawback) => Ok(Some(clawback)),
+        None => Ok(cw_ownable::get_ownership(storage)?.owner),
+    }
+}
+
 pub fn execute_distribute(
     env: Env,
     deps: DepsMut,
+    info: MessageInfo,
     request: Option<Uint128>,
+    memo: Option<String>,
+    denom: Option<UncheckedDenom>,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
+    if let Some(memo) = memo.as_ref() {
+        validate_memo(deps.storage, &info.sender, memo)?;
+    }
+    record_distribution(deps.storage, &env, &info.sender)?;
+    let additional = match denom {
+        Some(denom) => additional_denom_index(deps.as_ref(), denom)?,
+        None => None,
+    };
+    if let Some(index) = additional {
+        return execute_distribute_additional(env, deps, index, request, memo);
+    }
+
+    PAYMENT.index_value(deps.storage, deps.querier, env.block.time)?;
+    let claimed = PAYMENT.get_vest(deps.storage)?.claimed;
     let msg = PAYMENT.distribute(deps.storage, env.block.time, request)?;
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let distributed = vest.claimed - claimed;
+
+    // Errors funding the match, e.g. because the distribution is
+    // frozen, are handled in reply so that they never block the
+    // payment.
+    let (matched, match_msg) = match MATCHING.may_load(deps.storage)? {
+        Some(mut matching) => {
+            let (matched, msg) = matching.take_match(distributed)?;
+            MATCHING.save(deps.storage, &matching)?;
+            PENDING_MATCH.save(deps.storage, &matched)?;
+            (
+                matched,
+                msg.map(|msg| SubMsg::reply_on_error(msg, MATCH_REPLY_ID)),
+            )
+        }
+        None => (Uint128::zero(), None),
+    };
+
+    let recipient = vest.recipient.clone().ok_or(ContractError::Unassigned)?;
+
+    // If the recipient has opted into swapping, the distribution is
+    // sent through the swap adapter instead. Failed swaps are paid
+    // out directly in reply. Only the recipient's own distributions
+    // are swapped, so that no one else may choose when their tokens
+    // are sold, e.g. right after moving the price against them.
+    let swap_msg = match SWAP.may_load(deps.storage)? {
+        Some(swap) if info.sender == recipient => {
+            swap.swap_msg(deps.querier, &vest.denom, distributed, &recipient)?
+        }
+        _ => None,
+    };
+    let (swapped, mut payment) = match swap_msg {
+        Some(swap_msg) => {
+            PENDING_SWAP.save(deps.storage, &distributed)?;
+            (true, SubMsg::reply_on_error(swap_msg, SWAP_REPLY_ID))
+        }
+        None => (false, SubMsg::new(msg)),
+    };
+
+    // If off-ramping, the distribution is sent to the escrow instead,
+    // which pays the recipient off-chain.
+    let mut off_ramp_payout = None;
+    if let Some(mut off_ramp) = OFF_RAMP.may_load(deps.storage)? {
+        let (payout, msg) = off_ramp.payout_msg(
+            &env.contract.address,
+            &recipient,
+            &vest.denom,
+            distributed,
+            env.block.time,
+        )?;
+        OFF_RAMP.save(deps.storage, &off_ramp)?;
+        OFF_RAMP_PAYOUTS.save(deps.storage, payout.payload.payout_id, &payout)?;
+        off_ramp_payout = Some(payout.payload.payout_id);
+        payment = SubMsg::new(msg);
+    }
+
+    let receipt = ReceiptMetadata {
+        vesting_contract: env.contract.address.to_string(),
+        amount: distributed,
+        denom: vest.denom.clone(),
+        distributed_at: env.block.time,
+        claimed: vest.claimed,
+        vested: vest.vested(env.block.time),
+        memo,
+    };
+    let distribution = record_memo(deps.storage, &receipt)?;
+    let receipt_event = distribution_receipt_event(&recipient, &receipt);
+
+    // Errors minting the receipt are handled in reply so that they
+    // never block the payment.
+    let receipt_msg = match RECEIPTS.may_load(deps.storage)? {
+        Some(collection) => {
+            let count = RECEIPT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+            RECEIPT_COUNT.save(deps.storage, &count)?;
+            let mint = ReceiptCollectionMsg::Mint {
+                token_id: format!("{}/{}", env.contract.address, count),
+                owner: recipient.into_string(),
+                token_uri: Some(format!(
+                    "data:application/json;base64,{}",
+                    to_json_binary(&receipt)?.to_base64()
+                )),
+                extension: None,
+            };
+            Some(SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: collection.into_string(),
+                    msg: to_json_binary(&mint)?,
+                    funds: vec![],
+                },
+                RECEIPT_REPLY_ID,
+            ))
+        }
+        None => None,
+    };
 
     Ok(Response::new()
         .add_attribute("method", "distribute")
+        .add_attribute("distribution", distribution.to_string())
+        .add_attribute("amount", distributed)
+        .add_attribute("matched", matched)
+        .add_attribute("swapped", swapped.to_string())
+        .add_attribute(
+            "off_ramp_payout",
+            off_ramp_payout
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        )
+        .add_event(receipt_event)
+        .add_submessage(payment)
+        .add_submessages(match_msg)
+        .add_submessages(receipt_msg))
+}
+
+/// Distributes vested tokens of an additional denom. These are always
+/// sent directly to the recipient, without matching, swapping, or
+/// off-ramping.
+fn execute_distribute_additional(
+    env: Env,
+    deps: DepsMut,
+    index: usize,
+    request: Option<Uint128>,
+    memo: Option<String>,
+) -> Result<Response, ContractError> {
+    let keys = AdditionalPayment::new(index);
+    let payment = keys.payment();
+    let claimed = payment.get_vest(deps.storage)?.claimed;
+    let msg = payment.distribute(deps.storage, env.block.time, request)?;
+    let vest = payment.get_vest(deps.storage)?;
+    let distributed = vest.claimed - claimed;
+    let recipient = vest.recipient.clone().ok_or(ContractError::Unassigned)?;
+
+    let receipt = ReceiptMetadata {
+        vesting_contract: env.contract.address.to_string(),
+        amount: distributed,
+        denom: vest.denom.clone(),
+        distributed_at: env.block.time,
+        claimed: vest.claimed,
+        vested: vest.vested(env.block.time),
+        memo,
+    };
+    let distribution = record_memo(deps.storage, &receipt)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "distribute")
+        .add_attribute("distribution", distribution.to_string())
+        .add_attribute("amount", distributed)
+        .add_attribute("denom", vest.denom.to_string())
+        .add_event(distribution_receipt_event(&recipient, &receipt))
         .add_message(msg))
 }
 
+/// Errors if `memo` is too long, or if `sender` is neither the
+/// recipient nor the owner, so that no one else may annotate the
+/// recipient's payment records.
+fn validate_memo(storage: &dyn Storage, sender: &Addr, memo: &str) -> Result<(), ContractError> {
+    if memo.len() > MAX_MEMO_LENGTH {
+        return Err(ContractError::MemoTooLong {
+            max: MAX_MEMO_LENGTH,
+        });
+    }
+    if PAYMENT.get_vest(storage)?.recipient.as_ref() != Some(sender)
+        && !cw_ownable::is_owner(storage, sender)?
+    {
+        return Err(ContractError::MemoUnauthorized);
+    }
+    Ok(())
+}
+
+/// Numbers the distribution described by `receipt` and stores its
+/// memo, if any, dropping the memo of the distribution that is now
+/// `MAX_STORED_MEMOS` distributions old. Returns the distribution's
+/// number.
+fn record_memo(storage: &mut dyn Storage, receipt: &ReceiptMetadata) -> StdResult<u64> {
+    let distribution = DISTRIBUTION_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    DISTRIBUTION_COUNT.save(storage, &distribution)?;
+    if let Some(memo) = &receipt.memo {
+        DISTRIBUTION_MEMOS.save(
+            storage,
+            distribution,
+            &DistributionMemo {
+                distribution,
+                memo: memo.clone(),
+                amount: receipt.amount,
+                denom: receipt.denom.clone(),
+                distributed_at: receipt.distributed_at,
+            },
+        )?;
+    }
+    if let Some(expired) = distribution.checked_sub(MAX_STORED_MEMOS) {
+        DISTRIBUTION_MEMOS.remove(storage, expired);
+    }
+    Ok(distribution)
+}
+
+fn distribution_receipt_event(recipient: &Addr, receipt: &ReceiptMetadata) -> Event {
+    let event = Event::new("distribution_receipt")
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", receipt.amount)
+        .add_attribute("denom", receipt.denom.to_string())
+        .add_attribute("distributed_at", receipt.distributed_at.to_string())
+        .add_attribute("claimed", receipt.claimed)
+        .add_attribute("vested", receipt.vested);
+    match &receipt.memo {
+        Some(memo) => event.add_attribute("memo", memo),
+        None => event,
+    }
+}
+
+pub fn execute_fund_matching_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut matching = MATCHING
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoMatching)?;
+    let sent = match matching.denom {
+        CheckedDenom::Native(ref denom) => must_pay(&info, denom)?,
+        CheckedDenom::Cw20(_) => {
+            nonpayable(&info)?; // Funding happens in ExecuteMsg::Receive.
+            return Err(ContractError::WrongCw20);
+        }
+    };
+
+    matching.pool += sent;
+    MATCHING.save(deps.storage, &matching)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "fund_matching_pool")
+        .add_attribute("amount", sent)
+        .add_attribute("pool", matching.pool))
+}
+
+pub fn execute_withdraw_matching_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut matching = MATCHING
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoMatching)?;
+    let request = amount.unwrap_or(matching.pool);
+    if request > matching.pool || request.is_zero() {
+        return Err(ContractError::InvalidWithdrawal {
+            request,
+            claimable: matching.pool,
+        });
+    }
+
+    matching.pool -= request;
+    MATCHING.save(deps.storage, &matching)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "withdraw_matching_pool")
+        .add_attribute("amount", request)
+        .add_message(
+            matching
+                .denom
+                .get_transfer_to_message(&info.sender, request)?,
+        ))
+}
+
 pub fn execute_withdraw_canceled_payment(
     deps: DepsMut,
     env: Env,
     amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let owner = cw_ownable::get_ownership(deps.storage)?
-        .owner
-        .ok_or(OwnershipError::NoOwner)?;
-    let msg = PAYMENT.withdraw_canceled_payment(deps.storage, env.block.time, amount, &owner)?;
+    let clawback = get_clawback(deps.storage)?.ok_or(OwnershipError::NoOwner)?;
+    let msg = PAYMENT.withdraw_canceled_payment(deps.storage, env.block.time, amount, &clawback)?;
 
     Ok(Response::new()
         .add_attribute("method", "withdraw_canceled_payment")
         .add_message(msg))
 }
 
+pub fn execute_complete_cancellation(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let clawback = get_clawback(deps.storage)?;
+    let msgs = PAYMENT.complete_cancellation(deps.storage, env.block.time, clawback.as_ref())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "complete_cancellation")
+        .add_messages(msgs))
+}
+
+pub fn execute_assign_recipient(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let start_time = VEST_FROM_ASSIGNMENT
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .then_some(env.block.time);
+    let vest = PAYMENT.assign_recipient(deps.storage, recipient.clone(), start_time)?;
+    let additional_denoms = ADDITIONAL_DENOMS
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    for index in 0..additional_denoms.len() {
+        AdditionalPayment::new(index).payment().assign_recipient(
+            deps.storage,
+            recipient.clone(),
+            start_time,
+        )?;
+    }
+
+    // As in instantiate, direct staking rewards to the new receiver.
+    #[cfg(feature = "staking")]
+    let msg = match vest.denom {
+        CheckedDenom::Native(ref denom) if *denom == deps.querier.query_bonded_denom()? => {
+            Some(DistributionMsg::SetWithdrawAddress {
+                address: recipient.to_string(),
+            })
+        }
+        _ => None,
+    };
+    #[cfg(not(feature = "staking"))]
+    let msg: Option<CosmosMsg> = None;
+
+    Ok(Response::new()
+        .add_attribute("method", "assign_recipient")
+        .add_attribute("recipient", recipient)
+        .add_attribute("vested", vest.vested(env.block.time))
+        .add_messages(msg))
+}
+
+/// Records a distribution by `sender` at the current time. Errors if
+/// `sender` is not the recipient and the recipient's interval has not
+/// elapsed since the last distribution.
+fn record_distribution(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    let mut schedule = DISTRIBUTION_SCHEDULE.may_load(storage)?.unwrap_or_default();
+    if let Some(next) = schedule.next_distribution() {
+        if env.block.time < next && PAYMENT.get_vest(storage)?.recipient.as_ref() != Some(sender) {
+            return Err(ContractError::DistributionIntervalNotElapsed { next });
+        }
+    }
+    schedule.last_distributed = Some(env.block.time);
+    DISTRIBUTION_SCHEDULE.save(storage, &schedule)?;
+    Ok(())
+}
+
+pub fn execute_set_distribution_interval(
+    deps: DepsMut,
+    info: MessageInfo,
+    interval: Option<u64>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    if vest.recipient.as_ref() != Some(&info.sender) {
+        return Err(ContractError::NotReceiver);
+    }
+    let mut schedule = DISTRIBUTION_SCHEDULE
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    schedule.interval = interval;
+    DISTRIBUTION_SCHEDULE.save(deps.storage, &schedule)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_distribution_interval")
+        .add_attribute(
+            "interval",
+            interval
+                .map(|i| i.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        ))
+}
+
+pub fn execute_set_max_slippage(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_slippage: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    if vest.recipient.as_ref() != Some(&info.sender) {
+        return Err(ContractError::NotReceiver);
+    }
+    let mut swap = SWAP.may_load(deps.storage)?.ok_or(ContractError::NoSwap)?;
+    if let Some(max_slippage) = max_slippage {
+        if max_slippage > Decimal::one() {
+            return Err(ContractError::InvalidSlippage { max_slippage });
+        }
+    }
+
+    swap.max_slippage = max_slippage;
+    SWAP.save(deps.storage, &swap)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_max_slippage")
+        .add_attribute(
+            "max_slippage",
+            max_slippage
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        ))
+}
+
+pub fn execute_acknowledge_off_ramp_payouts(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    payout_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut off_ramp = OFF_RAMP
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoOffRamp)?;
+    if info.sender != off_ramp.escrow {
+        return Err(ContractError::NotOffRampEscrow);
+    }
+
+    for id in &payout_ids {
+        let mut payout = OFF_RAMP_PAYOUTS
+            .may_load(deps.storage, *id)?
+            .ok_or(ContractError::UnknownOffRampPayout { id: *id })?;
+        if payout.acknowledged_at.is_some() {
+            return Err(ContractError::OffRampPayoutAcknowledged { id: *id });
+        }
+        payout.acknowledged_at = Some(env.block.time);
+        off_ramp.acknowledged += 1;
+        off_ramp.amount_acknowledged += payout.payload.amount;
+        OFF_RAMP_PAYOUTS.save(deps.storage, *id, &payout)?;
+    }
+    OFF_RAMP.save(deps.storage, &off_ramp)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "acknowledge_off_ramp_payouts")
+        .add_attribute("count", payout_ids.len().to_string())
+        .add_attribute("acknowledged", off_ramp.acknowledged.to_string())
+        .add_attribute("payouts", off_ramp.payouts.to_string()))
+}
+
+pub fn execute_approve_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    code_id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+    // Otherwise a paused payment could escape its guardian.
+    assert_not_paused(deps.storage)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let owner = cw_ownable::get_ownership(deps.storage)?.owner;
+    let is_owner = owner.as_ref() == Some(&info.sender);
+    let is_recipient = vest.recipient.as_ref() == Some(&info.sender);
+    if !is_owner && !is_recipient {
+        return Err(ContractError::NotOwnerOrReceiver);
+    }
+    // Matching, swap, and off-ramp settings can not be carried over,
+    // so vests with them may not be transferred rather than silently
+    // losing them.
+    if vest.status != Status::Funded
+        || !ADDITIONAL_DENOMS
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .is_empty()
+        || MATCHING.exists(deps.storage)
+        || SWAP.exists(deps.storage)
+        || OFF_RAMP.exists(deps.storage)
+    {
+        return Err(ContractError::Untransferable);
+    }
+
+    let mut approval = match TRANSFER_APPROVAL.may_load(deps.storage)? {
+        Some(approval) if approval.code_id == code_id => approval,
+        _ => TransferApproval {
+            code_id,
+            owner: None,
+            recipient: None,
+        },
+    };
+    if is_owner {
+        approval.owner = Some(info.sender.clone());
+    }
+    if is_recipient {
+        approval.recipient = Some(info.sender.clone());
+    }
+
+    let resp = Response::new()
+        .add_attribute("method", "approve_transfer")
+        .add_attribute("approver", info.sender)
+        .add_attribute("code_id", code_id.to_string());
+
+    // Approvals only count while the approver is still the owner or
+    // the recipient.
+    let approved = approval.owner.is_some()
+        && approval.owner == owner
+        && approval.recipient.is_some()
+        && approval.recipient == vest.recipient;
+    if !approved {
+        TRANSFER_APPROVAL.save(deps.storage, &approval)?;
+        return Ok(resp);
+    }
+
+    TRANSFER_APPROVAL.remove(deps.storage);
+    let (vest, steps) = PAYMENT.transfer(deps.storage, env.block.time)?;
+    let amount = vest.total() - vest.claimed;
+    PENDING_TRANSFER.save(deps.storage, &amount)?;
+
+    let (denom, funds) = match vest.denom {
+        CheckedDenom::Native(ref denom) => (
+            UncheckedDenom::Native(denom.clone()),
+            coins(amount.u128(), denom),
+        ),
+        // cw20 vests are funded once the new contract is
+        // instantiated.
+        CheckedDenom::Cw20(ref token) => (UncheckedDenom::Cw20(token.to_string()), vec![]),
+    };
+    let start_time = vest.schedule_summary().start_time;
+    let vesting_duration_seconds = steps.last().map(|(end, _)| *end).unwrap_or_default();
+    let msg = InstantiateMsg {
+        owner: owner.as_ref().map(|owner| owner.to_string()),
+        recipient: vest
+            .recipient
+            .as_ref()
+            .map(|recipient| recipient.to_string()),
+        vest_from_assignment: false,
+        title: vest.title.clone(),
+        description: vest.description,
+        total: amount,
+        denom,
+        schedule: Schedule::PiecewiseLinear(steps),
+        start_time: Some(start_time),
+        vesting_duration_seconds,
+        unbonding_duration_seconds: UNBONDING_DURATION_SECONDS.load(deps.storage)?,
+        matching: None,
+        receipts: RECEIPTS
+            .may_load(deps.storage)?
+            .map(|receipts| receipts.to_string()),
+        swap: None,
+        off_ramp: None,
+        timesheet: None,
+        severance: Some(vest.severance),
+        label: vest.label,
+        metadata: vest.metadata,
+        additional_vests: vec![],
+        voting_module: VOTING_MODULE
+            .may_load(deps.storage)?
+            .map(|voting_module| voting_module.to_string()),
+        clawback: CLAWBACK
+            .may_load(deps.storage)?
+            .map(|clawback| clawback.to_string()),
+        allow_governance_votes: ALLOW_GOVERNANCE_VOTES
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        guardian: GUARDIAN
+            .may_load(deps.storage)?
+            .and_then(|guardian| guardian.guardian)
+            .map(|guardian| guardian.to_string()),
+    };
+
+    Ok(resp
+        .add_attribute("amount", amount)
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Instantiate {
+                admin: owner.map(|owner| owner.to_string()),
+                code_id,
+                msg: to_json_binary(&msg)?,
+                funds,
+                label: format!("cw-vesting: {}", vest.title),
+            },
+            TRANSFER_REPLY_ID,
+        )))
+}
+
+pub fn execute_revoke_transfer_approval(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let is_owner = cw_ownable::is_owner(deps.storage, &info.sender)?;
+    let is_recipient = vest.recipient.as_ref() == Some(&info.sender);
+    if !is_owner && !is_recipient {
+        return Err(ContractError::NotOwnerOrReceiver);
+    }
+
+    if let Some(mut approval) = TRANSFER_APPROVAL.may_load(deps.storage)? {
+        if is_owner {
+            approval.owner = None;
+        }
+        if is_recipient {
+            approval.recipient = None;
+        }
+        TRANSFER_APPROVAL.save(deps.storage, &approval)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_transfer_approval")
+        .add_attribute("revoker", info.sender))
+}
+
 pub fn execute_update_owner(
     deps: DepsMut,
     info: MessageInfo,
     env: Env,
     action: cw_ownable::Action,
 ) -> Result<Response, ContractError> {
-    if let Status::Canceled { owner_withdrawable } = PAYMENT.get_vest(deps.storage)?.status {
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    if vest.recipient.is_none()
+        && !matches!(vest.status, Status::Canceled { .. })
+        && action == cw_ownable::Action::RenounceOwnership
+    {
+        // Renouncing ownership would leave no one able to assign a
+        // recipient, locking the payment in the contract.
+        return Err(ContractError::UnassignedWithoutOwner);
+    }
+    if let Status::Canceled { owner_withdrawable } = vest.status {
         if action == cw_ownable::Action::RenounceOwnership && !owner_withdrawable.is_zero() {
             // Ownership cannot be removed if there are withdrawable
             // funds as this would lock those funds in the contract.
@@ -249,13 +1484,14 @@ pub fn execute_delegate(
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     nonpayable(&info)?;
+    assert_not_paused(deps.storage)?;
 
     let vest = PAYMENT.get_vest(deps.storage)?;
 
     match vest.status {
         Status::Unfunded => return Err(ContractError::NotFunded),
         Status::Funded => {
-            if info.sender != vest.recipient {
+            if vest.recipient.as_ref() != Some(&info.sender) {
                 return Err(ContractError::NotReceiver);
             }
         }
@@ -291,13 +1527,14 @@ pub fn execute_redelegate(
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     nonpayable(&info)?;
+    assert_not_paused(deps.storage)?;
 
     let vest = PAYMENT.get_vest(deps.storage)?;
 
     match vest.status {
         Status::Unfunded => return Err(ContractError::NotFunded),
         Status::Funded => {
-            if info.sender != vest.recipient {
+            if vest.recipient.as_ref() != Some(&info.sender) {
                 return Err(ContractError::NotReceiver);
             }
         }
@@ -363,7 +1600,7 @@ pub fn execute_undelegate(
     match vest.status {
         Status::Unfunded => return Err(ContractError::NotFunded),
         Status::Funded => {
-            if info.sender != vest.recipient {
+            if vest.recipient.as_ref() != Some(&info.sender) {
                 return Err(ContractError::NotReceiver);
             }
         }
@@ -401,7 +1638,7 @@ pub fn execute_set_withdraw_address(
     let vest = PAYMENT.get_vest(deps.storage)?;
     match vest.status {
         Status::Unfunded | Status::Funded => {
-            if info.sender != vest.recipient {
+            if vest.recipient.as_ref() != Some(&info.sender) {
                 return Err(ContractError::NotReceiver);
             }
         }
@@ -424,6 +1661,49 @@ pub fn execute_set_withdraw_address(
         .add_message(msg))
 }
 
+#[cfg(feature = "governance")]
+pub fn execute_vote(
+    deps: DepsMut,
+    info: MessageInfo,
+    vote: GovMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    if !ALLOW_GOVERNANCE_VOTES
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+    {
+        return Err(ContractError::GovernanceVotesNotAllowed);
+    }
+
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    match vest.status {
+        Status::Unfunded => return Err(ContractError::NotFunded),
+        Status::Funded => {
+            if vest.recipient.as_ref() != Some(&info.sender) {
+                return Err(ContractError::NotReceiver);
+            }
+        }
+        // Once cancelled, the staked tokens are owed to the owner
+        // first, so the recipient may no longer vote with them.
+        Status::Canceled { .. } => return Err(ContractError::Cancelled),
+    }
+
+    let denom = deps.querier.query_bonded_denom()?;
+    if !vest.denom.is_native(&denom) {
+        return Err(ContractError::NotStakeable);
+    }
+
+    let proposal_id = match vote {
+        GovMsg::Vote { proposal_id, .. } | GovMsg::VoteWeighted { proposal_id, .. } => proposal_id,
+    };
+
+    Ok(Response::default()
+        .add_attribute("method", "vote")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_message(vote))
+}
+
 #[cfg(feature = "staking")]
 pub fn execute_withdraw_rewards(validator: String) -> Result<Response, ContractError> {
     let withdraw_msg = DistributionMsg::WithdrawDelegatorReward { validator };
@@ -462,6 +1742,82 @@ pub fn execute_register_slash(
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        RECEIPT_REPLY_ID => match msg.result {
+            SubMsgResult::Err(err) => Ok(Response::new()
+                .add_attribute("method", "receipt_failed")
+                .add_attribute("error", err)),
+            SubMsgResult::Ok(_) => Ok(Response::default()),
+        },
+        SWAP_REPLY_ID => match msg.result {
+            // The swap's state changes, including the transfer to the
+            // adapter, were reverted, so pay the recipient in the
+            // vested token instead.
+            SubMsgResult::Err(err) => {
+                let amount = PENDING_SWAP.load(deps.storage)?;
+                let vest = PAYMENT.get_vest(deps.storage)?;
+                let recipient = vest.recipient.ok_or(ContractError::Unassigned)?;
+                Ok(Response::new()
+                    .add_attribute("method", "swap_failed")
+                    .add_attribute("error", err)
+                    .add_message(vest.denom.get_transfer_to_message(&recipient, amount)?))
+            }
+            SubMsgResult::Ok(_) => Ok(Response::default()),
+        },
+        MATCH_REPLY_ID => match msg.result {
+            // The match was not sent to the distributor, so return it
+            // to the matching pool.
+            SubMsgResult::Err(err) => {
+                let amount = PENDING_MATCH.load(deps.storage)?;
+                MATCHING.update(deps.storage, |mut matching| -> StdResult<_> {
+                    matching.pool += amount;
+                    Ok(matching)
+                })?;
+                Ok(Response::new()
+                    .add_attribute("method", "match_failed")
+                    .add_attribute("error", err)
+                    .add_attribute("returned", amount))
+            }
+            SubMsgResult::Ok(_) => Ok(Response::default()),
+        },
+        TRANSFER_REPLY_ID => {
+            let contract = deps
+                .api
+                .addr_validate(&parse_reply_instantiate_data(msg)?.contract_address)?;
+            let amount = PENDING_TRANSFER.load(deps.storage)?;
+            PENDING_TRANSFER.remove(deps.storage);
+            TRANSFER.save(
+                deps.storage,
+                &Transfer {
+                    contract: contract.clone(),
+                    amount,
+                },
+            )?;
+
+            let fund = match PAYMENT.get_vest(deps.storage)?.denom {
+                CheckedDenom::Cw20(token) => Some(WasmMsg::Execute {
+                    contract_addr: token.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                        contract: contract.to_string(),
+                        amount,
+                        msg: to_json_binary(&ReceiveMsg::Fund {})?,
+                    })?,
+                    funds: vec![],
+                }),
+                CheckedDenom::Native(_) => None,
+            };
+
+            Ok(Response::new()
+                .add_attribute("method", "transfer")
+                .add_attribute("contract", contract)
+                .add_messages(fund))
+        }
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -480,5 +1836,177 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         ),
         QueryMsg::TotalToVest {} => to_json_binary(&PAYMENT.get_vest(deps.storage)?.total()),
         QueryMsg::VestDuration {} => to_json_binary(&PAYMENT.duration(deps.storage)?),
+        QueryMsg::CancellationStatus { t } => {
+            to_json_binary(&PAYMENT.cancellation_status(deps.storage, t.unwrap_or(env.block.time))?)
+        }
+        QueryMsg::Matching {} => to_json_binary(&MATCHING.may_load(deps.storage)?),
+        QueryMsg::Swap {} => to_json_binary(&SWAP.may_load(deps.storage)?),
+        QueryMsg::OffRamp {} => to_json_binary(&OFF_RAMP.may_load(deps.storage)?),
+        QueryMsg::OffRampPayouts { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_OFF_RAMP_PAYOUTS_LIMIT) as usize;
+            let payouts = OFF_RAMP_PAYOUTS
+                .range(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit)
+                .map(|item| item.map(|(_, payout)| payout))
+                .collect::<StdResult<Vec<_>>>()?;
+            to_json_binary(&payouts)
+        }
+        QueryMsg::DistributionMemos { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_DISTRIBUTION_MEMOS_LIMIT) as usize;
+            let memos = DISTRIBUTION_MEMOS
+                .range(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit)
+                .map(|item| item.map(|(_, memo)| memo))
+                .collect::<StdResult<Vec<_>>>()?;
+            to_json_binary(&memos)
+        }
+        QueryMsg::Summary { t } => {
+            to_json_binary(&query_summary(deps, &PAYMENT, t.unwrap_or(env.block.time))?)
+        }
+        QueryMsg::Denoms {} => {
+            let mut denoms = vec![PAYMENT.get_vest(deps.storage)?.denom];
+            denoms.extend(
+                ADDITIONAL_DENOMS
+                    .may_load(deps.storage)?
+                    .unwrap_or_default(),
+            );
+            to_json_binary(&denoms)
+        }
+        QueryMsg::DenomSummary { denom, t } => {
+            let t = t.unwrap_or(env.block.time);
+            match additional_denom_index(deps, denom)
+                .map_err(|e| StdError::generic_err(e.to_string()))?
+            {
+                Some(index) => to_json_binary(&query_summary(
+                    deps,
+                    &AdditionalPayment::new(index).payment(),
+                    t,
+                )?),
+                None => to_json_binary(&query_summary(deps, &PAYMENT, t)?),
+            }
+        }
+        QueryMsg::VerifyBalances {} => to_json_binary(&query_verify_balances(deps, &env)?),
+        QueryMsg::Transfer {} => to_json_binary(&TransferResponse {
+            approval: TRANSFER_APPROVAL.may_load(deps.storage)?,
+            transfer: TRANSFER.may_load(deps.storage)?,
+        }),
+        QueryMsg::VotingPowerAtHeight { address, height } => {
+            let address = deps.api.addr_validate(&address)?;
+            let height = height.unwrap_or(env.block.height);
+            let power = VOTING_POWER
+                .may_load_at_height(deps.storage, &address, height)?
+                .unwrap_or_default();
+            to_json_binary(&VotingPowerAtHeightResponse { power, height })
+        }
+        QueryMsg::TotalPowerAtHeight { height } => {
+            let height = height.unwrap_or(env.block.height);
+            let power = TOTAL_VOTING_POWER
+                .may_load_at_height(deps.storage, height)?
+                .unwrap_or_default();
+            to_json_binary(&TotalPowerAtHeightResponse { power, height })
+        }
+        QueryMsg::HeldPowerAtHeight { address, height } => {
+            let address = deps.api.addr_validate(&address)?;
+            let height = height.unwrap_or(env.block.height);
+            let power = if PAYMENT.get_vest(deps.storage)?.recipient == Some(address) {
+                HELD_POWER
+                    .may_load_at_height(deps.storage, height)?
+                    .unwrap_or_default()
+            } else {
+                Uint128::zero()
+            };
+            to_json_binary(&VotingPowerAtHeightResponse { power, height })
+        }
+        QueryMsg::Hooks {} => to_json_binary(&HOOKS.query_hooks(deps)?),
+        QueryMsg::VotingModule {} => to_json_binary(&VOTING_MODULE.may_load(deps.storage)?),
+        QueryMsg::Clawback {} => to_json_binary(&get_clawback(deps.storage)?),
+        QueryMsg::Guardian {} => {
+            to_json_binary(&GUARDIAN.may_load(deps.storage)?.unwrap_or_default())
+        }
+        QueryMsg::DistributionSchedule {} => {
+            let schedule = DISTRIBUTION_SCHEDULE
+                .may_load(deps.storage)?
+                .unwrap_or_default();
+            to_json_binary(&DistributionScheduleResponse {
+                interval: schedule.interval,
+                last_distributed: schedule.last_distributed,
+                next_distribution: schedule.next_distribution(),
+            })
+        }
     }
 }
+
+/// Gets the summary of `payment` at time `t`, along with the
+/// contract's guardian.
+fn query_summary(deps: Deps, payment: &Payment, t: Timestamp) -> StdResult<Summary> {
+    let Guardian {
+        guardian, paused, ..
+    } = GUARDIAN.may_load(deps.storage)?.unwrap_or_default();
+    Ok(Summary {
+        guardian,
+        paused,
+        ..payment.summary(deps.storage, t)?
+    })
+}
+
+pub fn query_verify_balances(deps: Deps, env: &Env) -> StdResult<VerifyBalancesResponse> {
+    let vest = PAYMENT.get_vest(deps.storage)?;
+    let (mut expected_liquid, expected_bonded) =
+        PAYMENT.expected_balances(deps.storage, env.block.time)?;
+    if let Some(matching) = MATCHING.may_load(deps.storage)? {
+        if matching.denom == vest.denom {
+            expected_liquid += matching.pool;
+        }
+    }
+
+    let liquid = BalanceCheck {
+        expected: expected_liquid,
+        actual: vest
+            .denom
+            .query_balance(&deps.querier, &env.contract.address)?,
+    };
+    let delegated = query_delegated(deps, env, &vest.denom)?.map(|actual| BalanceCheck {
+        expected: expected_bonded,
+        actual,
+    });
+    let consistent = liquid.is_covered() && delegated.iter().all(BalanceCheck::is_covered);
+
+    Ok(VerifyBalancesResponse {
+        liquid,
+        delegated,
+        consistent,
+    })
+}
+
+/// Gets the number of tokens the contract has delegated, or `None` if
+/// `denom` is not the chain's staking denom.
+#[cfg(feature = "staking")]
+fn query_delegated(deps: Deps, env: &Env, denom: &CheckedDenom) -> StdResult<Option<Uint128>> {
+    let bonded_denom = deps.querier.query_bonded_denom()?;
+    if !denom.is_native(&bonded_denom) {
+        return Ok(None);
+    }
+    let delegated = deps
+        .querier
+        .query_all_delegations(&env.contract.address)?
+        .into_iter()
+        .filter(|delegation| delegation.amount.denom == bonded_denom)
+        .map(|delegation| delegation.amount.amount)
+        .sum();
+    Ok(Some(delegated))
+}
+
+#[cfg(not(feature = "staking"))]
+fn query_delegated(_deps: Deps, _env: &Env, _denom: &CheckedDenom) -> StdResult<Option<Uint128>> {
+    Ok(None)
+}
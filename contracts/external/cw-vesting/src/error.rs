@@ -1,9 +1,9 @@
-use cosmwasm_std::{StdError, Uint128};
+use cosmwasm_std::{Decimal, StdError, Timestamp, Uint128};
 use cw_denom::DenomError;
 use cw_ownable::OwnershipError;
-use cw_utils::PaymentError;
+use cw_schedule::{CurveError, ScheduleError};
+use cw_utils::{ParseReplyError, PaymentError};
 use thiserror::Error;
-use wynd_utils::CurveError;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
@@ -19,9 +19,15 @@ pub enum ContractError {
     #[error(transparent)]
     Ownable(#[from] OwnershipError),
 
+    #[error(transparent)]
+    Hook(#[from] cw_hooks::HookError),
+
     #[error("{0}")]
     PaymentError(#[from] PaymentError),
 
+    #[error(transparent)]
+    ParseReplyError(#[from] ParseReplyError),
+
     #[error("vesting curve values be in [0, total]`. got [{min}, {max}]")]
     VestRange { min: Uint128, max: Uint128 },
 
@@ -40,6 +46,21 @@ pub enum ContractError {
     #[error("can not vest a constant amount, specifiy two or more points")]
     ConstantVest,
 
+    #[error("cliffs must be non-zero and shorter than the vest")]
+    InvalidCliff,
+
+    #[error("value targeted schedules must have a non-zero period no longer than the vest, and a non-zero max_per_period >= min_per_period")]
+    InvalidValueTarget,
+
+    #[error("timesheets must have a non-zero period no longer than the vest, and non-zero units_per_period")]
+    InvalidTimesheet,
+
+    #[error("timesheets may not be combined with value targeted schedules")]
+    TimesheetWithValueTarget,
+
+    #[error("severance ({severance}) must be no more than 1")]
+    InvalidSeverance { severance: Decimal },
+
     #[error("payment is cancelled")]
     Cancelled,
 
@@ -61,6 +82,9 @@ pub enum ContractError {
     #[error("vesting denom may not be staked")]
     NotStakeable,
 
+    #[error("governance votes were not allowed on instantiation")]
+    GovernanceVotesNotAllowed,
+
     #[error("no delegation to validator {0}")]
     NoDelegation(String),
 
@@ -81,4 +105,102 @@ pub enum ContractError {
 
     #[error("can't register a slash event occuring in the future")]
     FutureSlash,
+
+    #[error("({bonded}) tokens must be undelegated before cancelation can complete")]
+    StillBonded { bonded: Uint128 },
+
+    #[error("tokens are unbonding, cancelation can complete at ({completes_at})")]
+    UnbondingNotMatured { completes_at: Timestamp },
+
+    #[error("cancelation has already completed")]
+    CancellationCompleted,
+
+    #[error("contract balances are inconsistent with the vesting accounting")]
+    InconsistentBalances,
+
+    #[error("matching is not configured for this payment")]
+    NoMatching,
+
+    #[error("matching requires an owner to fund the matching pool")]
+    MatchingWithoutOwner,
+
+    #[error("a clawback address requires an owner to cancel the payment")]
+    ClawbackWithoutOwner,
+
+    #[error("distributions and delegation are paused")]
+    Paused,
+
+    #[error("only the recipient may distribute before ({next})")]
+    DistributionIntervalNotElapsed { next: Timestamp },
+
+    #[error("only the guardian or the owner may pause the payment")]
+    NotGuardian,
+
+    #[error("only the proposed guardian may accept the role")]
+    NotPendingGuardian,
+
+    #[error("swapping is not configured for this payment")]
+    NoSwap,
+
+    #[error("max slippage must be <= 1, got ({max_slippage})")]
+    InvalidSlippage { max_slippage: Decimal },
+
+    #[error("off-ramping is not configured for this payment")]
+    NoOffRamp,
+
+    #[error("only the off-ramp escrow may perform this action")]
+    NotOffRampEscrow,
+
+    #[error("distributions may not be both swapped and off-ramped")]
+    SwapWithOffRamp,
+
+    #[error("no off-ramp payout with id ({id})")]
+    UnknownOffRampPayout { id: u64 },
+
+    #[error("off-ramp payout ({id}) has already been acknowledged")]
+    OffRampPayoutAcknowledged { id: u64 },
+
+    #[error("no recipient has been assigned to this payment")]
+    Unassigned,
+
+    #[error("a recipient has already been assigned to this payment")]
+    RecipientAssigned,
+
+    #[error("payments without a recipient must have an owner to assign one")]
+    UnassignedWithoutOwner,
+
+    #[error("only the owner or the vest receiver may perform this action")]
+    NotOwnerOrReceiver,
+
+    #[error("only funded single denom vests with a receiver, no staked, unbonding, or slashed tokens, no matching, swap, or off-ramp, and a linear, cliff, or piecewise schedule that has not finished may be transferred")]
+    Untransferable,
+
+    #[error("({denom}) may only be vested once")]
+    DuplicateDenom { denom: String },
+
+    #[error("this contract does not vest ({denom})")]
+    UnknownDenom { denom: String },
+
+    #[error("only the primary denom may be vested with a value targeted schedule")]
+    AdditionalValueTargeted,
+
+    #[error("memos may be at most {max} bytes long")]
+    MemoTooLong { max: usize },
+
+    #[error("only the recipient or the owner may attach a memo")]
+    MemoUnauthorized,
+
+    #[error("got a submessage reply with unknown id: {id}")]
+    UnknownReplyId { id: u64 },
+}
+
+impl From<ScheduleError> for ContractError {
+    fn from(err: ScheduleError) -> Self {
+        match err {
+            ScheduleError::Curve(err) => ContractError::Curve(err),
+            ScheduleError::Range { min, max } => ContractError::VestRange { min, max },
+            ScheduleError::Constant => ContractError::ConstantVest,
+            ScheduleError::InvalidCliff => ContractError::InvalidCliff,
+        }
+    }
 }
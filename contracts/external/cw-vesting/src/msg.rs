@@ -1,7 +1,9 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Timestamp, Uint128};
+use cosmwasm_std::{Addr, Decimal, Empty, Timestamp, Uint128};
+#[cfg(feature = "governance")]
+use cosmwasm_std::{VoteOption, WeightedVoteOption};
 use cw20::Cw20ReceiveMsg;
-use cw_denom::UncheckedDenom;
+use cw_denom::{CheckedDenom, UncheckedDenom};
 use cw_ownable::cw_ownable_execute;
 use cw_stake_tracker::StakeTrackerQuery;
 
@@ -13,8 +15,17 @@ pub struct InstantiateMsg {
     /// specified, the owner may cancel the vesting contract at any
     /// time and withdraw unvested funds.
     pub owner: Option<String>,
-    /// The receiver address of the vesting tokens.
-    pub recipient: String,
+    /// The receiver address of the vesting tokens. If `None`, funds
+    /// are locked until the owner assigns a recipient with
+    /// `AssignRecipient`, which is useful for reserving allocations
+    /// for roles that have not yet been filled. Requires that `owner`
+    /// is set.
+    pub recipient: Option<String>,
+    /// If `recipient` is `None` and this is true, vesting starts when
+    /// the recipient is assigned instead of at `start_time`. Has no
+    /// effect if `recipient` is set.
+    #[serde(default)]
+    pub vest_from_assignment: bool,
 
     /// The a name or title for this payment.
     pub title: String,
@@ -27,7 +38,8 @@ pub struct InstantiateMsg {
     pub denom: UncheckedDenom,
 
     /// The vesting schedule, can be either `SaturatingLinear` vesting
-    /// (which vests evenly over time), or `PiecewiseLinear` which can
+    /// (which vests evenly over time), `Cliff` vesting (which vests
+    /// evenly over time after a cliff), or `PiecewiseLinear` which can
     /// represent a more complicated vesting schedule.
     pub schedule: Schedule,
     /// The time to start vesting, or None to start vesting when the
@@ -58,6 +70,150 @@ pub struct InstantiateMsg {
     /// external calculations with correct values to withdraw
     /// avaliable funds from the contract.
     pub unbonding_duration_seconds: u64,
+
+    /// Optional employer matching. If set, each `Distribute` also
+    /// funds a dao-rewards-distributor distribution from a matching
+    /// pool that the owner deposits into this contract. Requires
+    /// that `owner` is set.
+    pub matching: Option<MatchingConfig>,
+
+    /// Optional cw721 collection that a receipt NFT is minted from
+    /// to the recipient for each `Distribute`, as portable proof of
+    /// payment. This contract must be allowed to mint from the
+    /// collection. Failing to mint a receipt never prevents a
+    /// distribution.
+    pub receipts: Option<String>,
+
+    /// Optional swap adapter that distributions may be paid out
+    /// through, so that the payment is vested in one token while the
+    /// recipient is paid in another (for example, a stable coin).
+    /// Distributions are only swapped once the recipient has opted in
+    /// by setting a maximum slippage with `SetMaxSlippage`, and only
+    /// when the recipient calls `Distribute`.
+    pub swap: Option<SwapConfig>,
+
+    /// Optional escrow contract of an off-chain fiat payout processor
+    /// that all distributions are sent to, along with a payload
+    /// identifying the recipient and invoice, instead of being sent
+    /// to the recipient. May not be combined with `swap`.
+    pub off_ramp: Option<OffRampConfig>,
+
+    /// Optional timesheet oracle reporting the units of work (for
+    /// example, hours) the recipient had approved each period. If
+    /// set, the amount the schedule vests each period is scaled by
+    /// the portion of a full period's units approved. May not be
+    /// combined with a value targeted schedule.
+    pub timesheet: Option<TimesheetConfig>,
+
+    /// The portion of the unvested tokens paid to the recipient as
+    /// severance if the vest is canceled, for example
+    /// `Decimal::percent(25)`. The rest is returned to the owner as
+    /// usual. Can not be changed after instantiation. Defaults to
+    /// zero.
+    pub severance: Option<Decimal>,
+
+    /// An optional short label identifying the payment, for example
+    /// in an address book of vesting contracts.
+    pub label: Option<String>,
+    /// Optional arbitrary metadata about the payment, for example a
+    /// JSON object used by a payroll dashboard.
+    pub metadata: Option<String>,
+
+    /// Optional payments in other denoms, each with its own schedule,
+    /// vested to the same recipient from the same `start_time`. This
+    /// allows one contract to carry a contributor package made up of
+    /// several tokens. Native denoms must be sent on instantiation,
+    /// and cw20 denoms funded with `ReceiveMsg::Fund`. Matching,
+    /// swapping, off-ramping, staking, transfers, and voting power
+    /// only apply to the primary `denom`.
+    #[serde(default)]
+    pub additional_vests: Vec<AdditionalVest>,
+
+    /// Optional voting module of a DAO that the recipient keeps
+    /// governance rights in while their tokens vest. If set, every
+    /// token held for the recipient, vested or not and including
+    /// staked tokens, is recorded as their held power, and the voting
+    /// module is notified with `StakeChangedHookMsg` whenever this
+    /// changes. Voting power reported by `VotingPowerAtHeight` is
+    /// unaffected.
+    pub voting_module: Option<String>,
+
+    /// Optional address that unvested tokens are returned to if the
+    /// payment is canceled, for example a DAO's treasury when the
+    /// owner is a subDAO or multisig that manages payroll. Defaults
+    /// to the owner. Requires that `owner` is set.
+    pub clawback: Option<String>,
+    /// If true, the recipient may vote on the chain's governance
+    /// proposals with the contract's staked tokens using `Vote` and
+    /// `VoteWeighted`, so that they keep their say in governance
+    /// while their tokens vest. Can not be changed after
+    /// instantiation.
+    #[serde(default)]
+    pub allow_governance_votes: bool,
+    /// Optional guardian, who may pause distributions and delegation
+    /// without being able to cancel the payment or redirect its
+    /// funds. Unlike a guardian proposed with `UpdateGuardian`, it
+    /// takes the role without accepting it, so that a transferred
+    /// payment keeps its guardian.
+    pub guardian: Option<String>,
+}
+
+#[cw_serde]
+pub struct AdditionalVest {
+    /// The type and denom of token being vested. Must differ from
+    /// the primary denom and every other additional denom.
+    pub denom: UncheckedDenom,
+    /// The total amount of tokens to be vested.
+    pub total: Uint128,
+    /// The vesting schedule. May not be `ValueTargeted`.
+    pub schedule: Schedule,
+    /// The length of the vesting schedule in seconds.
+    pub vesting_duration_seconds: u64,
+}
+
+#[cw_serde]
+pub struct TimesheetConfig {
+    /// The timesheet oracle. Must implement `TimesheetQueryMsg`.
+    pub oracle: String,
+    /// The length of each timesheet period. Must be non-zero and no
+    /// longer than the vest.
+    pub period_seconds: u64,
+    /// The number of approved units in a full period. Periods with at
+    /// least this many units approved vest the full scheduled amount.
+    pub units_per_period: Uint128,
+}
+
+#[cw_serde]
+pub struct MatchingConfig {
+    /// The dao-rewards-distributor contract to fund.
+    pub distributor: String,
+    /// The ID of the distribution to fund. The distribution's denom
+    /// must be the same as `denom`.
+    pub distribution_id: u64,
+    /// The number of matching tokens sent to the distribution for
+    /// each token distributed to the vestee.
+    pub ratio: Decimal,
+    /// The type and denom of token in the matching pool.
+    pub denom: UncheckedDenom,
+}
+
+#[cw_serde]
+pub struct SwapConfig {
+    /// The swap adapter contract. Must implement `SwapAdapterMsg` and
+    /// `SwapAdapterQueryMsg`.
+    pub adapter: String,
+    /// The type and denom of token that distributions are swapped
+    /// into.
+    pub ask_denom: UncheckedDenom,
+}
+
+#[cw_serde]
+pub struct OffRampConfig {
+    /// The escrow contract. Must implement `OffRampEscrowMsg`.
+    pub escrow: String,
+    /// The processor's identifier for the recipient, included in the
+    /// payload of every payout.
+    pub recipient_id: String,
 }
 
 #[cw_ownable_execute]
@@ -70,11 +226,23 @@ pub enum ExecuteMsg {
     /// yet been funded.
     Receive(Cw20ReceiveMsg),
     /// Distribute vested tokens to the vest receiver. Anyone may call
-    /// this method.
+    /// this method, though only the recipient may call it before the
+    /// interval set with `SetDistributionInterval` has elapsed since
+    /// the last distribution.
     Distribute {
         /// The amount of tokens to distribute. If none are specified
         /// all claimable tokens will be distributed.
         amount: Option<Uint128>,
+        /// An optional note, such as an invoice number or payroll
+        /// period, emitted with the distribution, recorded in its
+        /// receipt, and stored for the `DistributionMemos` query. At
+        /// most `MAX_MEMO_LENGTH` bytes long, and only accepted from
+        /// the recipient or the owner.
+        memo: Option<String>,
+        /// The denom to distribute, or `None` for the primary
+        /// denom. Distributions of additional denoms are always sent
+        /// directly to the recipient.
+        denom: Option<UncheckedDenom>,
     },
     /// Cancels the vesting payment. The current amount vested becomes
     /// the total amount that will ever vest, and all pending and
@@ -84,16 +252,31 @@ pub enum ExecuteMsg {
     ///
     /// Upon canceling, the contract will use any liquid tokens in the
     /// contract to settle pending payments to the vestee, and then
-    /// returns the rest to the owner. Staked tokens are then split
-    /// between the owner and the vestee according to the number of
-    /// tokens that the vestee is entitled to.
+    /// returns the rest to the clawback address (the owner, unless
+    /// one was set on instantiation). Staked tokens are then split
+    /// between the clawback address and the vestee according to the
+    /// number of tokens that the vestee is entitled to.
     ///
     /// The vestee will no longer receive staking rewards after
     /// cancelation, and may unbond and distribute (vested - claimed)
     /// tokens at their leisure. the owner will receive staking
     /// rewards and may unbond and withdraw (staked - (vested -
     /// claimed)) tokens at their leisure.
-    Cancel {},
+    ///
+    /// If `require_consistent_balances` is true, canceling fails
+    /// unless the `VerifyBalances` query reports that the contract's
+    /// balances are consistent with its accounting, for example
+    /// because a slash has not been registered.
+    ///
+    /// If `unbond` is true, the contract's delegations with up to
+    /// `MAX_CANCEL_UNDELEGATIONS` validators are undelegated as part
+    /// of canceling, so that cancellation may be completed once they
+    /// have unbonded. Any remaining delegations may be undelegated by
+    /// anyone with `Undelegate`.
+    Cancel {
+        require_consistent_balances: Option<bool>,
+        unbond: Option<bool>,
+    },
     /// This is translated to a
     /// [MsgDelegate](https://github.com/cosmos/cosmos-sdk/blob/v0.40.0/proto/cosmos/staking/v1beta1/tx.proto#L81-L90).
     /// `delegator_address` is automatically filled with the current
@@ -146,6 +329,27 @@ pub enum ExecuteMsg {
         /// The validator to claim rewards for.
         validator: String,
     },
+    /// This is translated to a
+    /// [MsgVote](https://github.com/cosmos/cosmos-sdk/blob/v0.42.5/proto/cosmos/gov/v1beta1/tx.proto#L46-L56).
+    /// `voter` is automatically filled with the current contract's
+    /// address, so the vote is cast with the contract's staked
+    /// tokens. Only callable by Vesting Payment Recipient, and only
+    /// if `allow_governance_votes` was set on instantiation.
+    #[cfg(feature = "governance")]
+    Vote {
+        proposal_id: u64,
+        option: VoteOption,
+    },
+    /// This is translated to a
+    /// [MsgVoteWeighted](https://github.com/cosmos/cosmos-sdk/blob/v0.45.8/proto/cosmos/gov/v1beta1/tx.proto#L66-L78).
+    /// `voter` is automatically filled with the current contract's
+    /// address. Only callable by Vesting Payment Recipient, and only
+    /// if `allow_governance_votes` was set on instantiation.
+    #[cfg(feature = "governance")]
+    VoteWeighted {
+        proposal_id: u64,
+        options: Vec<WeightedVoteOption>,
+    },
     /// If the owner cancels a payment and there are not enough liquid
     /// tokens to settle the owner may become entitled to some number
     /// of staked tokens. They may then unbond those tokens and then
@@ -154,6 +358,86 @@ pub enum ExecuteMsg {
         /// The amount to withdraw.
         amount: Option<Uint128>,
     },
+    /// Completes a cancelation once all of the contract's tokens
+    /// have been undelegated and finished unbonding. Distributes
+    /// any remaining vested tokens to the vestee and returns the rest
+    /// to the owner. Anyone may call this method.
+    CompleteCancellation {},
+    /// Deposits native tokens into the matching pool. Only callable
+    /// by the owner, and only if matching was configured during
+    /// instantiation. Cw20 tokens are deposited via `Receive` with
+    /// the message `{"fund_matching_pool":{}}`.
+    FundMatchingPool {},
+    /// Withdraws tokens from the matching pool and returns them to
+    /// the owner. Only callable by the owner.
+    WithdrawMatchingPool {
+        /// The amount to withdraw, or none to withdraw the entire
+        /// pool.
+        amount: Option<Uint128>,
+    },
+    /// Assigns the receiver of a payment instantiated without one.
+    /// Only callable by the owner, and only once.
+    AssignRecipient { recipient: String },
+    /// Sets the maximum slippage the recipient will accept when
+    /// their distributions are swapped, or `None` to stop swapping
+    /// and be paid in the vested token. Distributions by anyone else
+    /// are never swapped. Only callable by the recipient, and only if
+    /// a swap adapter was configured during instantiation.
+    SetMaxSlippage { max_slippage: Option<Decimal> },
+    /// Sets the minimum number of seconds between distributions by
+    /// anyone but the recipient, or `None` to let anyone distribute at
+    /// any time. The recipient may always distribute. Only callable by
+    /// the recipient.
+    SetDistributionInterval { interval: Option<u64> },
+    /// Acknowledges that off-ramp payouts have been processed. Only
+    /// callable by the off-ramp escrow.
+    AcknowledgeOffRampPayouts { payout_ids: Vec<u64> },
+    /// Approves transferring the vest to a new vesting contract
+    /// instantiated from `code_id`, for example a newer version of
+    /// this contract. Only callable by the owner or the recipient,
+    /// and replaces any approval of a different code ID. Once both
+    /// have approved the same code ID, the new contract is
+    /// instantiated and funded with the unclaimed tokens, and
+    /// continues the vest where this one left off. This contract is
+    /// then canceled with nothing left to distribute or withdraw.
+    ///
+    /// Only funded vests with no staked, unbonding, or slashed tokens,
+    /// no matching, swap, or off-ramp, and a linear, cliff, or
+    /// piecewise schedule that has not finished may be transferred,
+    /// and not while paused. The guardian is carried over.
+    ApproveTransfer { code_id: u64 },
+    /// Revokes the sender's approval to transfer the vest. Only
+    /// callable by the owner or the recipient.
+    RevokeTransferApproval {},
+    /// Proposes `guardian` as the payment's guardian, replacing any
+    /// pending proposal, or removes the guardian and any pending
+    /// proposal if `None`. The proposed address becomes the guardian
+    /// once it accepts with `AcceptGuardian`. Only callable by the
+    /// owner.
+    UpdateGuardian { guardian: Option<String> },
+    /// Accepts the role of guardian. Only callable by the address the
+    /// owner proposed.
+    AcceptGuardian {},
+    /// Pauses or resumes `Distribute`, `Delegate`, `Redelegate`, and
+    /// `ApproveTransfer`, for example while a security council
+    /// responds to a compromised recipient key. Pausing never prevents the payment from being
+    /// canceled or canceled funds from being settled. Only callable by
+    /// the guardian or the owner.
+    SetPaused { paused: bool },
+    /// Records the recipient's vested but unclaimed tokens as their
+    /// voting power. Every message that funds, cancels, pays out of,
+    /// slashes, transfers, or assigns the vest does this, so this is
+    /// only needed to account for tokens that have vested since the
+    /// last one. Anyone may call this.
+    Checkpoint {},
+    /// Adds a contract that is notified with `StakeChangedHookMsg`
+    /// when the recipient's voting power changes, for example a
+    /// rewards distributor using this contract as its voting power
+    /// source. Only callable by the owner.
+    AddHook { addr: String },
+    /// Removes a voting power change hook. Only callable by the
+    /// owner.
+    RemoveHook { addr: String },
     /// Registers a slash event bonded or unbonding tokens with the
     /// contract. Only callable by the owner as the contract is unable
     /// to verify that the slash actually occured. The owner is
@@ -191,6 +475,154 @@ pub enum ExecuteMsg {
 pub enum ReceiveMsg {
     /// Funds a vesting contract with a cw20 token
     Fund {},
+    /// Deposits cw20 tokens into the matching pool. Only callable
+    /// on behalf of the owner.
+    FundMatchingPool {},
+}
+
+/// The subset of dao-rewards-distributor's execute messages used to
+/// fund a distribution with matching tokens. Used as both the execute
+/// message for native tokens and the cw20 `Send` hook.
+#[cw_serde]
+pub enum DistributorMsg {
+    Fund { id: u64 },
+}
+
+/// The interface a swap adapter must implement. The adapter swaps
+/// the tokens it is sent for `ask_denom` and sends the result to
+/// `recipient`, erroring if it would be less than `min_return`. Used
+/// as both the execute message for native tokens and the cw20 `Send`
+/// hook.
+#[cw_serde]
+pub enum SwapAdapterMsg {
+    Swap {
+        ask_denom: CheckedDenom,
+        min_return: Uint128,
+        recipient: String,
+    },
+}
+
+/// The interface an off-ramp escrow must implement. Used as both the
+/// execute message for native tokens and the cw20 `Send` hook. The
+/// escrow should acknowledge payouts with `AcknowledgeOffRampPayouts`
+/// once they have been processed.
+#[cw_serde]
+pub enum OffRampEscrowMsg {
+    Payout(OffRampPayload),
+}
+
+/// The payload of a distribution sent to an off-ramp escrow.
+#[cw_serde]
+pub struct OffRampPayload {
+    /// The vesting contract that made the distribution.
+    pub vesting_contract: String,
+    /// The ID of the payout, counting up from 1.
+    pub payout_id: u64,
+    /// The processor's identifier for the recipient.
+    pub recipient_id: String,
+    /// A reference for the invoice of the payout, of the form
+    /// `<vesting contract>/<payout ID>`.
+    pub invoice_ref: String,
+    /// The recipient of the payment.
+    pub recipient: String,
+    /// The number of tokens paid out.
+    pub amount: Uint128,
+    /// The denom of the tokens paid out.
+    pub denom: CheckedDenom,
+    /// The time of the distribution.
+    pub distributed_at: Timestamp,
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum SwapAdapterQueryMsg {
+    /// Returns the number of `ask_denom` tokens that swapping
+    /// `offer_amount` tokens of `offer_denom` would currently return.
+    #[returns(SimulateSwapResponse)]
+    SimulateSwap {
+        offer_denom: CheckedDenom,
+        offer_amount: Uint128,
+        ask_denom: CheckedDenom,
+    },
+}
+
+#[cw_serde]
+pub struct SimulateSwapResponse {
+    pub return_amount: Uint128,
+}
+
+/// The interface a price oracle must implement to be used by
+/// `Schedule::ValueTargeted` vests.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum PriceOracleQueryMsg {
+    /// Returns the price of one `denom` token at `time`, in units of
+    /// the value vests target per period. `time` may be in the past.
+    #[returns(PriceResponse)]
+    Price {
+        denom: CheckedDenom,
+        time: Timestamp,
+    },
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    pub price: Decimal,
+}
+
+/// The interface a timesheet oracle must implement to be used by
+/// vests with a `timesheet`.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum TimesheetQueryMsg {
+    /// Returns the number of units of work `recipient` had approved
+    /// between `start` and `end`.
+    #[returns(ApprovedUnitsResponse)]
+    ApprovedUnits {
+        recipient: Addr,
+        start: Timestamp,
+        end: Timestamp,
+    },
+}
+
+#[cw_serde]
+pub struct ApprovedUnitsResponse {
+    pub units: Uint128,
+}
+
+/// The subset of the cw721-base execute message used to mint
+/// distribution receipts.
+#[cw_serde]
+pub enum ReceiptCollectionMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: Option<Empty>,
+    },
+}
+
+/// Details of a distribution, encoded as a base64 JSON data URI in
+/// the token URI of its receipt.
+#[cw_serde]
+pub struct ReceiptMetadata {
+    /// The vesting contract that made the distribution.
+    pub vesting_contract: String,
+    /// The number of tokens distributed.
+    pub amount: Uint128,
+    /// The denom of the tokens distributed.
+    pub denom: CheckedDenom,
+    /// The time of the distribution.
+    pub distributed_at: Timestamp,
+    /// The total number of tokens distributed to the recipient so
+    /// far, including this distribution.
+    pub claimed: Uint128,
+    /// The total number of tokens vested at the time of the
+    /// distribution.
+    pub vested: Uint128,
+    /// The memo attached to the distribution, if any.
+    #[serde(default)]
+    pub memo: Option<String>,
 }
 
 #[cw_serde]
@@ -233,4 +665,125 @@ pub enum QueryMsg {
     /// query methods and their return types.
     #[returns(::cosmwasm_std::Uint128)]
     Stake(StakeTrackerQuery),
+    /// Gets the progress of a cancelation at time `t`, including
+    /// any undelegations that have not yet completed. If `t` is
+    /// `None`, the current time is used.
+    #[returns(crate::vesting::CancellationStatus)]
+    CancellationStatus { t: Option<Timestamp> },
+    /// Gets the matching configuration and the number of tokens left
+    /// in the matching pool, or `None` if matching is not configured.
+    #[returns(Option<crate::state::Matching>)]
+    Matching {},
+    /// Gets the swap adapter configuration and the recipient's
+    /// maximum slippage, or `None` if swapping is not configured.
+    #[returns(Option<crate::state::Swap>)]
+    Swap {},
+    /// Gets the off-ramp configuration and the number and amount of
+    /// payouts sent to and acknowledged by the escrow, or `None` if
+    /// off-ramping is not configured.
+    #[returns(Option<crate::state::OffRamp>)]
+    OffRamp {},
+    /// Lists the payouts sent to the off-ramp escrow, and when they
+    /// were acknowledged, in order of payout ID.
+    #[returns(Vec<crate::state::OffRampPayout>)]
+    OffRampPayouts {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lists the memos of the most recent `MAX_STORED_MEMOS`
+    /// distributions that had one, in order of distribution number.
+    #[returns(Vec<crate::state::DistributionMemo>)]
+    DistributionMemos {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Gets everything needed to display the payment at time `t`,
+    /// including its label, schedule, and vested, claimed, staked,
+    /// and unbonding amounts. If `t` is `None`, the current time is
+    /// used.
+    #[returns(crate::vesting::Summary)]
+    Summary { t: Option<Timestamp> },
+    /// Lists the denoms vested by this contract, starting with the
+    /// primary denom.
+    #[returns(Vec<::cw_denom::CheckedDenom>)]
+    Denoms {},
+    /// Gets the summary of the payment in `denom` at time `t`. If `t`
+    /// is `None`, the current time is used.
+    #[returns(crate::vesting::Summary)]
+    DenomSummary {
+        denom: UncheckedDenom,
+        t: Option<Timestamp>,
+    },
+    /// Cross-checks the payment's accounting against the contract's
+    /// bank (or cw20) balance and delegations, returning the expected
+    /// and actual amounts of each.
+    #[returns(crate::vesting::VerifyBalancesResponse)]
+    VerifyBalances {},
+    /// Returns the pending approvals to transfer the vest to a new
+    /// vesting contract, and the transfer once complete.
+    #[returns(TransferResponse)]
+    Transfer {},
+    /// The recipient's voting power at a height, which is the number
+    /// of tokens that had vested but not been claimed as of the last
+    /// checkpoint before that height. Addresses have none before
+    /// they are assigned as the recipient, and other addresses have
+    /// none at all.
+    #[returns(::dao_interface::voting::VotingPowerAtHeightResponse)]
+    VotingPowerAtHeight {
+        address: String,
+        height: Option<u64>,
+    },
+    /// The total voting power at a height, which is the recipient's.
+    #[returns(::dao_interface::voting::TotalPowerAtHeightResponse)]
+    TotalPowerAtHeight { height: Option<u64> },
+    /// The recipient's held power at a height, which is every token
+    /// held for them, vested or not, less what had been claimed or
+    /// slashed as of the last checkpoint before that height.
+    /// This is what is reported to the voting module, and is zero if
+    /// none is configured. Other addresses have none.
+    #[returns(::dao_interface::voting::VotingPowerAtHeightResponse)]
+    HeldPowerAtHeight {
+        address: String,
+        height: Option<u64>,
+    },
+    /// The contracts notified when the recipient's voting power
+    /// changes.
+    #[returns(::cw_hooks::HooksResponse)]
+    Hooks {},
+    /// The voting module the recipient's unvested tokens count
+    /// towards, if one is configured.
+    #[returns(Option<::cosmwasm_std::Addr>)]
+    VotingModule {},
+    /// The address unvested tokens are returned to if the payment is
+    /// canceled: the clawback address if one is configured, and the
+    /// owner otherwise. `None` if neither is set.
+    #[returns(Option<::cosmwasm_std::Addr>)]
+    Clawback {},
+    /// The payment's guardian, the guardian the owner has proposed,
+    /// and whether distributions and delegation are paused.
+    #[returns(crate::state::Guardian)]
+    Guardian {},
+    /// The recipient's preferred interval between distributions, when
+    /// tokens were last distributed, and when anyone may next
+    /// distribute.
+    #[returns(DistributionScheduleResponse)]
+    DistributionSchedule {},
+}
+
+#[cw_serde]
+pub struct DistributionScheduleResponse {
+    /// The minimum number of seconds between distributions by anyone
+    /// but the recipient, if set.
+    pub interval: Option<u64>,
+    /// When tokens were last distributed.
+    pub last_distributed: Option<Timestamp>,
+    /// When anyone may next distribute, or `None` if anyone may
+    /// distribute now.
+    pub next_distribution: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct TransferResponse {
+    pub approval: Option<crate::state::TransferApproval>,
+    pub transfer: Option<crate::state::Transfer>,
 }
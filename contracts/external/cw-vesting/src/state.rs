@@ -1,6 +1,394 @@
-use cw_storage_plus::Item;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coins, to_json_binary, Addr, CosmosMsg, Decimal, QuerierWrapper, Timestamp, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_denom::CheckedDenom;
+use cw_hooks::Hooks;
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
 
-use crate::vesting::Payment;
+use crate::{
+    msg::{
+        DistributorMsg, OffRampEscrowMsg, OffRampPayload, SimulateSwapResponse, SwapAdapterMsg,
+        SwapAdapterQueryMsg,
+    },
+    vesting::Payment,
+    ContractError,
+};
 
-pub const PAYMENT: Payment = Payment::new("vesting", "staked", "validator", "cardinality");
+pub const PAYMENT: Payment =
+    Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
+/// The denoms vested in addition to the primary payment's, in the
+/// order they were given on instantiation. The payment in the denom at
+/// index `i` is stored under `AdditionalPayment::new(i)`.
+pub const ADDITIONAL_DENOMS: Item<Vec<CheckedDenom>> = Item::new("additional_denoms");
 pub const UNBONDING_DURATION_SECONDS: Item<u64> = Item::new("ubs");
+pub const MATCHING: Item<Matching> = Item::new("matching");
+/// If true, vesting of an unassigned payment starts when its recipient
+/// is assigned.
+pub const VEST_FROM_ASSIGNMENT: Item<bool> = Item::new("vfa");
+/// The cw721 collection distribution receipts are minted from.
+pub const RECEIPTS: Item<Addr> = Item::new("receipts");
+/// The number of distribution receipts that have been minted.
+pub const RECEIPT_COUNT: Item<u64> = Item::new("receipt_count");
+/// The swap adapter distributions may be paid out through.
+pub const SWAP: Item<Swap> = Item::new("swap");
+/// The number of tokens sent to the swap adapter by the last swapped
+/// distribution, which are paid to the recipient directly if the
+/// swap fails.
+pub const PENDING_SWAP: Item<Uint128> = Item::new("pending_swap");
+/// The number of matching tokens sent to the distributor by the last
+/// matched distribution, which are returned to the matching pool if
+/// funding the distribution fails.
+pub const PENDING_MATCH: Item<Uint128> = Item::new("pending_match");
+/// The pending approvals to transfer the vest to a new vesting
+/// contract.
+pub const TRANSFER_APPROVAL: Item<TransferApproval> = Item::new("transfer_approval");
+/// The number of tokens sent to the new vesting contract by a transfer
+/// that is being instantiated.
+pub const PENDING_TRANSFER: Item<Uint128> = Item::new("pending_transfer");
+/// The transfer of the vest to a new vesting contract, once complete.
+pub const TRANSFER: Item<Transfer> = Item::new("transfer");
+/// The recipient's vested but unclaimed tokens, as of the last
+/// checkpoint, which is the recipient's voting power. Keyed by
+/// recipient so that an address only has power from its assignment.
+pub const VOTING_POWER: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "recipient_voting_power",
+    "recipient_voting_power__checkpoints",
+    "recipient_voting_power__changelog",
+    Strategy::EveryBlock,
+);
+/// The total voting power, which is the recipient's.
+pub const TOTAL_VOTING_POWER: SnapshotItem<Uint128> = SnapshotItem::new(
+    "voting_power",
+    "voting_power__checkpoints",
+    "voting_power__changelog",
+    Strategy::EveryBlock,
+);
+/// Every token held for the recipient, vested or not, less what has
+/// been claimed or slashed, as of the last checkpoint. Only recorded
+/// if a voting module is configured.
+pub const HELD_POWER: SnapshotItem<Uint128> = SnapshotItem::new(
+    "held_power",
+    "held_power__checkpoints",
+    "held_power__changelog",
+    Strategy::EveryBlock,
+);
+/// Contracts notified with `StakeChangedHookMsg` when the recipient's
+/// voting power changes.
+pub const HOOKS: Hooks = Hooks::new("hooks");
+/// The voting module the recipient's unvested tokens count towards,
+/// which is notified with `StakeChangedHookMsg` when the recipient's
+/// held power changes.
+pub const VOTING_MODULE: Item<Addr> = Item::new("voting_module");
+/// The address unvested tokens are returned to on cancellation, if
+/// not the owner.
+pub const CLAWBACK: Item<Addr> = Item::new("clawback");
+/// If true, the recipient may vote on governance proposals with the
+/// contract's staked tokens.
+pub const ALLOW_GOVERNANCE_VOTES: Item<bool> = Item::new("allow_gov_votes");
+/// The guardian of the payment and whether it has paused it.
+pub const GUARDIAN: Item<Guardian> = Item::new("guardian");
+/// The recipient's preferred interval between distributions and when
+/// tokens were last distributed.
+pub const DISTRIBUTION_SCHEDULE: Item<DistributionSchedule> = Item::new("distribution_schedule");
+/// The number of distributions made, in any denom, which numbers
+/// each distribution.
+pub const DISTRIBUTION_COUNT: Item<u64> = Item::new("distribution_count");
+/// The memos attached to the most recent `MAX_STORED_MEMOS`
+/// distributions.
+///
+/// Map distribution number to memo.
+pub const DISTRIBUTION_MEMOS: Map<u64, DistributionMemo> = Map::new("distribution_memos");
+/// The escrow distributions are paid out through for off-ramping.
+pub const OFF_RAMP: Item<OffRamp> = Item::new("off_ramp");
+/// The payouts sent to the off-ramp escrow.
+///
+/// Map payout ID to payout.
+pub const OFF_RAMP_PAYOUTS: Map<u64, OffRampPayout> = Map::new("off_ramp_payouts");
+
+/// The storage namespaces of a payment in an additional denom, which
+/// are those of the primary payment suffixed with the denom's index.
+pub struct AdditionalPayment {
+    namespaces: [String; 5],
+}
+
+impl AdditionalPayment {
+    pub fn new(index: usize) -> Self {
+        Self {
+            namespaces: ["vesting", "staked", "validator", "cardinality", "unbonding"]
+                .map(|prefix| format!("{prefix}_{index}")),
+        }
+    }
+
+    pub fn payment(&self) -> Payment<'_> {
+        let [vesting, staked, validator, cardinality, unbonding] = &self.namespaces;
+        Payment::new(vesting, staked, validator, cardinality, unbonding)
+    }
+}
+
+/// A guardian, such as a security council, may pause distributions
+/// and delegation, but can not cancel the payment or redirect its
+/// funds.
+#[cw_serde]
+#[derive(Default)]
+pub struct Guardian {
+    /// The guardian, if one has accepted the role.
+    pub guardian: Option<Addr>,
+    /// The address the owner has proposed as guardian, which becomes
+    /// the guardian once it accepts.
+    pub pending: Option<Addr>,
+    /// If true, distributions and delegation are paused.
+    pub paused: bool,
+}
+
+/// How often anyone but the recipient may distribute, so that keepers
+/// may automate distributions without distributing more often than
+/// the recipient would like.
+#[cw_serde]
+#[derive(Default)]
+pub struct DistributionSchedule {
+    /// The minimum number of seconds between distributions by anyone
+    /// but the recipient, or `None` if anyone may distribute at any
+    /// time.
+    pub interval: Option<u64>,
+    /// When tokens were last distributed, by anyone.
+    pub last_distributed: Option<Timestamp>,
+}
+
+impl DistributionSchedule {
+    /// When anyone may next distribute, or `None` if anyone may
+    /// distribute now.
+    pub fn next_distribution(&self) -> Option<Timestamp> {
+        self.interval
+            .zip(self.last_distributed)
+            .map(|(interval, last)| last.plus_seconds(interval))
+    }
+}
+
+#[cw_serde]
+pub struct TransferApproval {
+    /// The code ID of the vesting contract the vest will be
+    /// transferred to.
+    pub code_id: u64,
+    /// The owner that approved the transfer, if they have.
+    pub owner: Option<Addr>,
+    /// The recipient that approved the transfer, if they have.
+    pub recipient: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct Transfer {
+    /// The vesting contract that continues the vest.
+    pub contract: Addr,
+    /// The number of tokens sent to it.
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct Matching {
+    /// The dao-rewards-distributor contract that is funded.
+    pub distributor: Addr,
+    /// The ID of the distribution that is funded.
+    pub distribution_id: u64,
+    /// Matching tokens sent per distributed token.
+    pub ratio: Decimal,
+    /// The type and denom of token in the matching pool.
+    pub denom: CheckedDenom,
+    /// The number of tokens in the matching pool that have not yet
+    /// been sent to the distributor.
+    pub pool: Uint128,
+}
+
+impl Matching {
+    /// Removes the match for `distributed` tokens from the pool and
+    /// returns the amount matched and a message funding the
+    /// distribution with it. If the pool can not cover the full
+    /// match, the remainder of the pool is used.
+    pub fn take_match(
+        &mut self,
+        distributed: Uint128,
+    ) -> Result<(Uint128, Option<CosmosMsg>), ContractError> {
+        let matched = distributed.mul_floor(self.ratio).min(self.pool);
+        if matched.is_zero() {
+            return Ok((matched, None));
+        }
+        self.pool -= matched;
+
+        let fund = DistributorMsg::Fund {
+            id: self.distribution_id,
+        };
+        let msg = match &self.denom {
+            CheckedDenom::Native(denom) => WasmMsg::Execute {
+                contract_addr: self.distributor.to_string(),
+                msg: to_json_binary(&fund)?,
+                funds: coins(matched.u128(), denom),
+            },
+            CheckedDenom::Cw20(address) => WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: self.distributor.to_string(),
+                    amount: matched,
+                    msg: to_json_binary(&fund)?,
+                })?,
+                funds: vec![],
+            },
+        };
+        Ok((matched, Some(msg.into())))
+    }
+}
+
+#[cw_serde]
+pub struct Swap {
+    /// The swap adapter contract distributions are sent through.
+    pub adapter: Addr,
+    /// The type and denom of token distributions are swapped into.
+    pub ask_denom: CheckedDenom,
+    /// The maximum slippage the recipient accepts, or `None` if they
+    /// have not opted into swapping.
+    pub max_slippage: Option<Decimal>,
+}
+
+impl Swap {
+    /// Returns a message swapping `amount` tokens of `denom` for
+    /// `ask_denom` and sending them to `recipient`, or `None` if the
+    /// recipient has not opted into swapping or the adapter can not
+    /// simulate the swap. The minimum return is the adapter's
+    /// simulated return less the recipient's maximum slippage.
+    pub fn swap_msg(
+        &self,
+        querier: QuerierWrapper,
+        denom: &CheckedDenom,
+        amount: Uint128,
+        recipient: &Addr,
+    ) -> Result<Option<CosmosMsg>, ContractError> {
+        let Some(max_slippage) = self.max_slippage else {
+            return Ok(None);
+        };
+        // An adapter that can not simulate the swap, e.g. because its
+        // pool is empty, should not block the distribution, which is
+        // then paid in the vested token.
+        let Ok(simulation) = querier.query_wasm_smart::<SimulateSwapResponse>(
+            &self.adapter,
+            &SwapAdapterQueryMsg::SimulateSwap {
+                offer_denom: denom.clone(),
+                offer_amount: amount,
+                ask_denom: self.ask_denom.clone(),
+            },
+        ) else {
+            return Ok(None);
+        };
+
+        let swap = SwapAdapterMsg::Swap {
+            ask_denom: self.ask_denom.clone(),
+            min_return: simulation
+                .return_amount
+                .mul_floor(Decimal::one() - max_slippage),
+            recipient: recipient.to_string(),
+        };
+        let msg = match denom {
+            CheckedDenom::Native(denom) => WasmMsg::Execute {
+                contract_addr: self.adapter.to_string(),
+                msg: to_json_binary(&swap)?,
+                funds: coins(amount.u128(), denom),
+            },
+            CheckedDenom::Cw20(address) => WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: self.adapter.to_string(),
+                    amount,
+                    msg: to_json_binary(&swap)?,
+                })?,
+                funds: vec![],
+            },
+        };
+        Ok(Some(msg.into()))
+    }
+}
+
+#[cw_serde]
+pub struct OffRamp {
+    /// The escrow contract distributions are sent to.
+    pub escrow: Addr,
+    /// The processor's identifier for the recipient.
+    pub recipient_id: String,
+    /// The number of payouts sent to the escrow.
+    pub payouts: u64,
+    /// The number of payouts acknowledged by the escrow.
+    pub acknowledged: u64,
+    /// The number of tokens sent to the escrow.
+    pub amount_paid_out: Uint128,
+    /// The number of tokens in payouts acknowledged by the escrow.
+    pub amount_acknowledged: Uint128,
+}
+
+#[cw_serde]
+pub struct DistributionMemo {
+    /// The number of the distribution, counting from one.
+    pub distribution: u64,
+    pub memo: String,
+    /// The number of tokens distributed.
+    pub amount: Uint128,
+    /// The denom of the tokens distributed.
+    pub denom: CheckedDenom,
+    /// The time of the distribution.
+    pub distributed_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct OffRampPayout {
+    pub payload: OffRampPayload,
+    /// When the escrow acknowledged the payout, if it has.
+    pub acknowledged_at: Option<Timestamp>,
+}
+
+impl OffRamp {
+    /// Records a payout of `amount` tokens of `denom` to the escrow,
+    /// and returns a message sending them along with its payload.
+    pub fn payout_msg(
+        &mut self,
+        vesting_contract: &Addr,
+        recipient: &Addr,
+        denom: &CheckedDenom,
+        amount: Uint128,
+        distributed_at: Timestamp,
+    ) -> Result<(OffRampPayout, CosmosMsg), ContractError> {
+        self.payouts += 1;
+        self.amount_paid_out += amount;
+
+        let payload = OffRampPayload {
+            vesting_contract: vesting_contract.to_string(),
+            payout_id: self.payouts,
+            recipient_id: self.recipient_id.clone(),
+            invoice_ref: format!("{}/{}", vesting_contract, self.payouts),
+            recipient: recipient.to_string(),
+            amount,
+            denom: denom.clone(),
+            distributed_at,
+        };
+        let payout = to_json_binary(&OffRampEscrowMsg::Payout(payload.clone()))?;
+        let msg = match denom {
+            CheckedDenom::Native(denom) => WasmMsg::Execute {
+                contract_addr: self.escrow.to_string(),
+                msg: payout,
+                funds: coins(amount.u128(), denom),
+            },
+            CheckedDenom::Cw20(address) => WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: self.escrow.to_string(),
+                    amount,
+                    msg: payout,
+                })?,
+                funds: vec![],
+            },
+        };
+        Ok((
+            OffRampPayout {
+                payload,
+                acknowledged_at: None,
+            },
+            msg.into(),
+        ))
+    }
+}
@@ -5,8 +5,9 @@ use cw_multi_test::{App, BankSudo, Executor, StakingInfo, StakingSudo};
 use dao_testing::contracts::cw_vesting_contract;
 
 use crate::{
-    msg::{ExecuteMsg, InstantiateMsg, QueryMsg},
-    vesting::{Schedule, Vest},
+    msg::{DistributionScheduleResponse, ExecuteMsg, InstantiateMsg, QueryMsg},
+    state::Guardian,
+    vesting::{CancellationStatus, Schedule, Summary, Vest},
     StakeTrackerQuery,
 };
 
@@ -30,7 +31,20 @@ impl Default for SuiteBuilder {
         Self {
             instantiate: InstantiateMsg {
                 owner: Some("owner".to_string()),
-                recipient: "recipient".to_string(),
+                recipient: Some("recipient".to_string()),
+                vest_from_assignment: false,
+                receipts: None,
+                swap: None,
+                off_ramp: None,
+                timesheet: None,
+                severance: None,
+                label: None,
+                metadata: None,
+                additional_vests: vec![],
+                voting_module: None,
+                clawback: None,
+                allow_governance_votes: false,
+                guardian: None,
                 title: "title".to_string(),
                 description: Some("description".to_string()),
                 total: Uint128::new(100_000_000),
@@ -39,6 +53,7 @@ impl Default for SuiteBuilder {
                 start_time: None,
                 vesting_duration_seconds: 60 * 60 * 24 * 7, // one week
                 unbonding_duration_seconds: staking_defaults.unbonding_time,
+                matching: None,
             },
         }
     }
@@ -108,7 +123,7 @@ impl SuiteBuilder {
             app,
             owner: self.instantiate.owner.map(Addr::unchecked),
             total: self.instantiate.total,
-            receiver: Addr::unchecked(self.instantiate.recipient),
+            receiver: Addr::unchecked(self.instantiate.recipient.unwrap_or_default()),
             vesting,
         }
     }
@@ -123,6 +138,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_clawback<S: Into<String>>(mut self, clawback: S) -> Self {
+        self.instantiate.clawback = Some(clawback.into());
+        self
+    }
+
     pub fn with_curve(mut self, s: Schedule) -> Self {
         self.instantiate.schedule = s;
         self
@@ -185,7 +205,11 @@ impl Suite {
             .execute_contract(
                 Addr::unchecked(sender),
                 self.vesting.clone(),
-                &ExecuteMsg::Distribute { amount },
+                &ExecuteMsg::Distribute {
+                    amount,
+                    memo: None,
+                    denom: None,
+                },
                 &[],
             )
             .map(|_| ())
@@ -196,7 +220,24 @@ impl Suite {
             .execute_contract(
                 Addr::unchecked(sender),
                 self.vesting.clone(),
-                &ExecuteMsg::Cancel {},
+                &ExecuteMsg::Cancel {
+                    require_consistent_balances: None,
+                    unbond: None,
+                },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    pub fn cancel_and_unbond<S: Into<String>>(&mut self, sender: S) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.vesting.clone(),
+                &ExecuteMsg::Cancel {
+                    require_consistent_balances: None,
+                    unbond: Some(true),
+                },
                 &[],
             )
             .map(|_| ())
@@ -279,6 +320,18 @@ impl Suite {
             .map(|_| ())
     }
 
+    pub fn complete_cancellation(&mut self) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                // anyone may call this method on a canceled vesting contract
+                Addr::unchecked("random"),
+                self.vesting.clone(),
+                &ExecuteMsg::CompleteCancellation {},
+                &[],
+            )
+            .map(|_| ())
+    }
+
     pub fn set_withdraw_address<S: Into<String>>(
         &mut self,
         sender: S,
@@ -296,6 +349,60 @@ impl Suite {
             .map(|_| ())
     }
 
+    pub fn update_guardian<S: Into<String>>(
+        &mut self,
+        sender: S,
+        guardian: Option<&str>,
+    ) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.vesting.clone(),
+                &ExecuteMsg::UpdateGuardian {
+                    guardian: guardian.map(|g| g.to_string()),
+                },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    pub fn accept_guardian<S: Into<String>>(&mut self, sender: S) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.vesting.clone(),
+                &ExecuteMsg::AcceptGuardian {},
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    pub fn set_paused<S: Into<String>>(&mut self, sender: S, paused: bool) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.vesting.clone(),
+                &ExecuteMsg::SetPaused { paused },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    pub fn set_distribution_interval<S: Into<String>>(
+        &mut self,
+        sender: S,
+        interval: Option<u64>,
+    ) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.vesting.clone(),
+                &ExecuteMsg::SetDistributionInterval { interval },
+                &[],
+            )
+            .map(|_| ())
+    }
+
     pub fn register_bonded_slash<S: Into<String>>(
         &mut self,
         sender: S,
@@ -357,7 +464,7 @@ impl Suite {
 
     pub fn query_receiver_vesting_token_balance(&self) -> Uint128 {
         let vest = self.query_vest();
-        self.query_vesting_token_balance(vest.recipient)
+        self.query_vesting_token_balance(vest.recipient.unwrap())
     }
 
     pub fn query_vesting_token_balance<S: Into<String>>(&self, who: S) -> Uint128 {
@@ -394,4 +501,39 @@ impl Suite {
             .query_wasm_smart(&self.vesting, &QueryMsg::VestDuration {})
             .unwrap()
     }
+
+    pub fn query_clawback(&self) -> Option<Addr> {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.vesting, &QueryMsg::Clawback {})
+            .unwrap()
+    }
+
+    pub fn query_guardian(&self) -> Guardian {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.vesting, &QueryMsg::Guardian {})
+            .unwrap()
+    }
+
+    pub fn query_distribution_schedule(&self) -> DistributionScheduleResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.vesting, &QueryMsg::DistributionSchedule {})
+            .unwrap()
+    }
+
+    pub fn query_summary(&self) -> Summary {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.vesting, &QueryMsg::Summary { t: None })
+            .unwrap()
+    }
+
+    pub fn query_cancellation_status(&self) -> CancellationStatus {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.vesting, &QueryMsg::CancellationStatus { t: None })
+            .unwrap()
+    }
 }
@@ -1,9 +1,11 @@
-use cosmwasm_std::{Timestamp, Uint128, Uint64};
+use cosmwasm_std::{Addr, Timestamp, Uint128, Uint64};
 use cw_multi_test::App;
 use cw_ownable::OwnershipError;
 
 use crate::{
-    vesting::{Schedule, Status},
+    msg::DistributionScheduleResponse,
+    state::Guardian,
+    vesting::{CancellationStatus, PendingUnbond, Schedule, Status},
     ContractError,
 };
 
@@ -179,6 +181,280 @@ fn test_cancel_can_not_settle_receiver() {
     );
 }
 
+#[test]
+fn test_complete_cancellation_after_unbonding() {
+    let mut suite = SuiteBuilder::default().build();
+
+    assert_eq!(
+        suite.query_cancellation_status(),
+        CancellationStatus::NotCanceled
+    );
+    let res = suite.complete_cancellation();
+    is_error!(res, &ContractError::NotCancelled.to_string());
+
+    suite.delegate(Uint128::new(90_000_000)).unwrap();
+    suite.a_day_passes();
+    suite.cancel(suite.owner.clone().unwrap()).unwrap();
+
+    // tokens are still bonded so cancelation can not complete.
+    assert_eq!(
+        suite.query_cancellation_status(),
+        CancellationStatus::Unbonding {
+            bonded: Uint128::new(90_000_000),
+            pending: vec![],
+        }
+    );
+    let res = suite.complete_cancellation();
+    is_error!(
+        res,
+        &ContractError::StillBonded {
+            bonded: Uint128::new(90_000_000)
+        }
+        .to_string()
+    );
+
+    suite
+        .undelegate("random", Uint128::new(90_000_000))
+        .unwrap();
+
+    let unbonding_duration_seconds = SuiteBuilder::default()
+        .instantiate
+        .unbonding_duration_seconds;
+    let completes_at = Timestamp::from_seconds(suite.time().seconds() + unbonding_duration_seconds);
+    assert_eq!(
+        suite.query_cancellation_status(),
+        CancellationStatus::Unbonding {
+            bonded: Uint128::zero(),
+            pending: vec![PendingUnbond {
+                validator: "validator".to_string(),
+                amount: Uint128::new(90_000_000),
+                completes_at,
+            }],
+        }
+    );
+    let res = suite.complete_cancellation();
+    is_error!(
+        res,
+        &ContractError::UnbondingNotMatured { completes_at }.to_string()
+    );
+
+    suite.a_day_passes();
+    suite.process_unbonds();
+
+    assert_eq!(
+        suite.query_cancellation_status(),
+        CancellationStatus::Matured
+    );
+    suite.complete_cancellation().unwrap();
+    assert_eq!(
+        suite.query_cancellation_status(),
+        CancellationStatus::Completed
+    );
+
+    // vestee receives the tokens vested over one day, the owner
+    // receives the rest.
+    let vested = suite.total.multiply_ratio(1u128, 7u128);
+    let vestee = suite.query_receiver_vesting_token_balance();
+    let owner = suite.query_vesting_token_balance(suite.owner.clone().unwrap());
+    assert_eq!(vestee + owner, suite.total);
+    assert!(vestee >= vested);
+
+    let res = suite.complete_cancellation();
+    is_error!(res, &ContractError::CancellationCompleted.to_string());
+}
+
+/// Canceling with `unbond` undelegates the contract's staked tokens,
+/// and unvested tokens are returned to the clawback address instead
+/// of the owner.
+#[test]
+fn test_cancel_to_clawback_and_unbond() {
+    let mut suite = SuiteBuilder::default().with_clawback("treasury").build();
+    assert_eq!(suite.query_clawback(), Some(Addr::unchecked("treasury")));
+
+    suite.delegate(Uint128::new(90_000_000)).unwrap();
+    suite.a_day_passes();
+    suite.withdraw_delegator_reward("validator").unwrap();
+    let expected_staking_rewards = Uint128::new(90_000_000)
+        .multiply_ratio(1u128, 10u128) // default rewards rate is 10%/yr
+        .multiply_ratio(1u128, 365u128);
+
+    suite
+        .cancel_and_unbond(suite.owner.clone().unwrap())
+        .unwrap();
+
+    // the liquid tokens all settle the vestee, and the staked tokens
+    // are already unbonding.
+    let unbonding_duration_seconds = SuiteBuilder::default()
+        .instantiate
+        .unbonding_duration_seconds;
+    assert_eq!(
+        suite.query_cancellation_status(),
+        CancellationStatus::Unbonding {
+            bonded: Uint128::zero(),
+            pending: vec![PendingUnbond {
+                validator: "validator".to_string(),
+                amount: Uint128::new(90_000_000),
+                completes_at: Timestamp::from_seconds(
+                    suite.time().seconds() + unbonding_duration_seconds
+                ),
+            }],
+        }
+    );
+
+    suite.a_day_passes();
+    suite.process_unbonds();
+    suite.complete_cancellation().unwrap();
+
+    // the vestee is paid what vested before cancelation, and the
+    // rest is clawed back.
+    let vested = suite.total.multiply_ratio(1u128, 7u128);
+    assert_eq!(
+        suite.query_receiver_vesting_token_balance(),
+        vested + expected_staking_rewards
+    );
+    assert_eq!(
+        suite.query_vesting_token_balance("treasury"),
+        suite.total - vested
+    );
+    assert_eq!(
+        suite.query_vesting_token_balance(suite.owner.clone().unwrap()),
+        Uint128::zero()
+    );
+}
+
+/// Without a clawback address, unvested tokens are returned to the
+/// owner.
+#[test]
+fn test_clawback_defaults_to_owner() {
+    let suite = SuiteBuilder::default().build();
+    assert_eq!(suite.query_clawback(), suite.owner);
+}
+
+/// A clawback address is meaningless without an owner to cancel.
+#[test]
+#[should_panic(expected = "a clawback address requires an owner to cancel the payment")]
+fn test_clawback_without_owner() {
+    let mut builder = SuiteBuilder::default().with_clawback("treasury");
+    builder.instantiate.owner = None;
+    builder.build();
+}
+
+/// A guardian is assigned in two steps and may only pause
+/// distributions and delegation.
+#[test]
+fn test_guardian() {
+    let mut suite = SuiteBuilder::default().build();
+    let owner = suite.owner.clone().unwrap();
+
+    let res = suite.update_guardian("council", Some("council"));
+    is_error!(
+        res,
+        ContractError::Ownable(OwnershipError::NotOwner)
+            .to_string()
+            .as_str()
+    );
+
+    // the proposed guardian has no powers until it accepts.
+    suite
+        .update_guardian(owner.clone(), Some("council"))
+        .unwrap();
+    let res = suite.set_paused("council", true);
+    is_error!(res, ContractError::NotGuardian.to_string().as_str());
+    let res = suite.accept_guardian("random");
+    is_error!(res, ContractError::NotPendingGuardian.to_string().as_str());
+    suite.accept_guardian("council").unwrap();
+    assert_eq!(
+        suite.query_guardian(),
+        Guardian {
+            guardian: Some(Addr::unchecked("council")),
+            pending: None,
+            paused: false,
+        }
+    );
+
+    // pausing stops distributions and delegation.
+    suite.a_day_passes();
+    suite.set_paused("council", true).unwrap();
+    let summary = suite.query_summary();
+    assert_eq!(summary.guardian, Some(Addr::unchecked("council")));
+    assert!(summary.paused);
+    let res = suite.distribute(suite.receiver.clone(), None);
+    is_error!(res, ContractError::Paused.to_string().as_str());
+    let res = suite.delegate(Uint128::new(1_000));
+    is_error!(res, ContractError::Paused.to_string().as_str());
+
+    // the guardian can not cancel, and canceling is never paused.
+    let res = suite.cancel("council");
+    is_error!(
+        res,
+        ContractError::Ownable(OwnershipError::NotOwner)
+            .to_string()
+            .as_str()
+    );
+
+    // the owner may resume, and remove the guardian.
+    suite.set_paused(owner.clone(), false).unwrap();
+    suite.distribute(suite.receiver.clone(), None).unwrap();
+    suite.update_guardian(owner.clone(), None).unwrap();
+    let res = suite.set_paused("council", true);
+    is_error!(res, ContractError::NotGuardian.to_string().as_str());
+    assert_eq!(suite.query_guardian(), Guardian::default());
+
+    suite.cancel(owner).unwrap();
+}
+
+#[test]
+fn test_distribution_interval() {
+    let mut suite = SuiteBuilder::default().build();
+    let two_days = 2 * 24 * 60 * 60;
+
+    let res = suite.set_distribution_interval("keeper", Some(two_days));
+    is_error!(res, ContractError::NotReceiver.to_string().as_str());
+    suite
+        .set_distribution_interval(suite.receiver.clone(), Some(two_days))
+        .unwrap();
+
+    // anyone may distribute until the first distribution.
+    suite.a_day_passes();
+    suite.distribute("keeper", None).unwrap();
+    let next = suite.time().plus_seconds(two_days);
+    assert_eq!(
+        suite.query_distribution_schedule(),
+        DistributionScheduleResponse {
+            interval: Some(two_days),
+            last_distributed: Some(suite.time()),
+            next_distribution: Some(next),
+        }
+    );
+
+    // only the recipient may distribute before the interval elapses.
+    suite.a_day_passes();
+    let res = suite.distribute("keeper", None);
+    is_error!(
+        res,
+        ContractError::DistributionIntervalNotElapsed { next }
+            .to_string()
+            .as_str()
+    );
+    suite.distribute(suite.receiver.clone(), None).unwrap();
+    assert_eq!(
+        suite.query_distribution_schedule().next_distribution,
+        Some(suite.time().plus_seconds(two_days))
+    );
+
+    suite.a_day_passes();
+    suite.a_day_passes();
+    suite.distribute("keeper", None).unwrap();
+
+    // removing the interval lets anyone distribute at any time.
+    suite
+        .set_distribution_interval(suite.receiver.clone(), None)
+        .unwrap();
+    suite.a_day_passes();
+    suite.distribute("keeper", None).unwrap();
+    assert_eq!(suite.query_distribution_schedule().next_distribution, None);
+}
+
 #[test]
 fn test_set_withdraw_address_permissions() {
     let mut suite = SuiteBuilder::default().build();
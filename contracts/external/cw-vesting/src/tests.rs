@@ -1,17 +1,36 @@
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{coins, to_json_binary, Addr, Coin, Decimal, Empty, Uint128, Validator};
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty,
+    Env, MessageInfo, Response, StdError, StdResult, Uint128, Validator, WasmMsg,
+};
 use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20ReceiveMsg};
 use cw_denom::{CheckedDenom, UncheckedDenom};
 use cw_multi_test::{
-    App, AppBuilder, BankSudo, Contract, ContractWrapper, Executor, StakingInfo, SudoMsg,
+    App, AppBuilder, AppResponse, BankSudo, Contract, ContractWrapper, Executor, StakingInfo,
+    StakingSudo, SudoMsg,
+};
+use cw_ownable::{Action, OwnershipError};
+use cw_storage_plus::{Item, Map};
+use dao_hooks::stake::{StakeChangedExecuteMsg, StakeChangedHookMsg};
+use dao_interface::voting::{TotalPowerAtHeightResponse, VotingPowerAtHeightResponse};
+use dao_testing::contracts::{cw20_base_contract, cw721_base_contract};
+
+use crate::contract::{
+    execute, execute_receive_cw20, instantiate, query, MAX_MEMO_LENGTH, MAX_STORED_MEMOS,
+};
+use crate::msg::{
+    AdditionalVest, ApprovedUnitsResponse, DistributorMsg, ExecuteMsg, InstantiateMsg,
+    MatchingConfig, OffRampConfig, OffRampEscrowMsg, OffRampPayload, PriceOracleQueryMsg,
+    PriceResponse, QueryMsg, ReceiptMetadata, ReceiveMsg, SimulateSwapResponse,
+    SwapAdapterQueryMsg, SwapConfig, TimesheetConfig, TimesheetQueryMsg, TransferResponse,
+};
+use crate::state::{
+    DistributionMemo, Guardian, Matching, OffRamp, OffRampPayout, Swap, TransferApproval, PAYMENT,
+};
+use crate::vesting::{
+    BalanceCheck, Schedule, ScheduleKind, Status, Summary, VerifyBalancesResponse, Vest, VestInit,
 };
-use cw_ownable::Action;
-use dao_testing::contracts::cw20_base_contract;
-
-use crate::contract::{execute, execute_receive_cw20};
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use crate::state::PAYMENT;
-use crate::vesting::{Schedule, Status, Vest, VestInit};
 use crate::ContractError;
 
 const ALICE: &str = "alice";
@@ -26,7 +45,8 @@ fn cw_vesting_contract() -> Box<dyn Contract<Empty>> {
         crate::contract::execute,
         crate::contract::instantiate,
         crate::contract::query,
-    );
+    )
+    .with_reply(crate::contract::reply);
     Box::new(contract)
 }
 
@@ -129,7 +149,8 @@ impl Default for InstantiateMsg {
     fn default() -> Self {
         Self {
             owner: Some(OWNER.to_string()),
-            recipient: BOB.to_string(),
+            recipient: Some(BOB.to_string()),
+            vest_from_assignment: false,
             title: "title".to_string(),
             description: Some("desc".to_string()),
             total: Uint128::new(TOTAL_VEST),
@@ -139,6 +160,19 @@ impl Default for InstantiateMsg {
             start_time: None,
             vesting_duration_seconds: 604800,    // one week
             unbonding_duration_seconds: 2592000, // 30 days
+            matching: None,
+            receipts: None,
+            swap: None,
+            off_ramp: None,
+            timesheet: None,
+            severance: None,
+            label: None,
+            metadata: None,
+            additional_vests: vec![],
+            voting_module: None,
+            clawback: None,
+            allow_governance_votes: false,
+            guardian: None,
         }
     }
 }
@@ -188,7 +222,7 @@ fn setup_test_case(app: &mut App, msg: InstantiateMsg, funds: &[Coin]) -> TestCa
     TestCase {
         cw20_addr,
         cw_vesting_addr,
-        recipient: Addr::unchecked(msg.recipient),
+        recipient: Addr::unchecked(msg.recipient.unwrap_or_default()),
         vesting_payment,
     }
 }
@@ -218,7 +252,11 @@ fn test_happy_cw20_path() {
         .execute_contract(
             bob.clone(),
             cw_vesting_addr.clone(),
-            &ExecuteMsg::Distribute { amount: None },
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
             &[],
         )
         .unwrap_err()
@@ -241,7 +279,11 @@ fn test_happy_cw20_path() {
     app.execute_contract(
         bob,
         cw_vesting_addr,
-        &ExecuteMsg::Distribute { amount: None },
+        &ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
         &[],
     )
     .unwrap();
@@ -287,7 +329,11 @@ fn test_happy_native_path() {
         .execute_contract(
             bob.clone(),
             cw_vesting_addr.clone(),
-            &ExecuteMsg::Distribute { amount: None },
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
             &[],
         )
         .unwrap_err()
@@ -310,7 +356,11 @@ fn test_happy_native_path() {
     app.execute_contract(
         bob,
         cw_vesting_addr,
-        &ExecuteMsg::Distribute { amount: None },
+        &ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
         &[],
     )
     .unwrap();
@@ -424,7 +474,10 @@ fn test_cancel_vesting() {
         .execute_contract(
             Addr::unchecked(ALICE),
             cw_vesting_addr.clone(),
-            &ExecuteMsg::Cancel {},
+            &ExecuteMsg::Cancel {
+                require_consistent_balances: None,
+                unbond: None,
+            },
             &[],
         )
         .unwrap_err()
@@ -445,7 +498,10 @@ fn test_cancel_vesting() {
     app.execute_contract(
         Addr::unchecked(OWNER),
         cw_vesting_addr.clone(),
-        &ExecuteMsg::Cancel {},
+        &ExecuteMsg::Cancel {
+            require_consistent_balances: None,
+            unbond: None,
+        },
         &[],
     )
     .unwrap();
@@ -455,7 +511,11 @@ fn test_cancel_vesting() {
         .execute_contract(
             Addr::unchecked(BOB),
             cw_vesting_addr,
-            &ExecuteMsg::Distribute { amount: None },
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
             &[],
         )
         .unwrap_err()
@@ -579,9 +639,13 @@ fn test_execution_rejection_recv() {
                 start_time: env().block.time,
                 duration_seconds: 60 * 60 * 24 * 7,
                 denom: CheckedDenom::Cw20(Addr::unchecked("cw20")),
-                recipient: Addr::unchecked("recipient"),
+                recipient: Some(Addr::unchecked("recipient")),
                 title: "title".to_string(),
                 description: Some("description".to_string()),
+                severance: Decimal::zero(),
+                label: None,
+                metadata: None,
+                timesheet: None,
             },
         )
         .unwrap();
@@ -637,9 +701,13 @@ fn test_illiquid_when_unfunfed() {
                 start_time: env().block.time,
                 duration_seconds: 60 * 60 * 24 * 7,
                 denom: CheckedDenom::Cw20(Addr::unchecked("cw20")),
-                recipient: Addr::unchecked("recipient"),
+                recipient: Some(Addr::unchecked("recipient")),
                 title: "title".to_string(),
                 description: Some("description".to_string()),
+                severance: Decimal::zero(),
+                label: None,
+                metadata: None,
+                timesheet: None,
             },
         )
         .unwrap();
@@ -675,9 +743,13 @@ fn test_update_owner() {
                 start_time: env().block.time,
                 duration_seconds: 60 * 60 * 24 * 7,
                 denom: CheckedDenom::Cw20(Addr::unchecked("cw20")),
-                recipient: Addr::unchecked("recipient"),
+                recipient: Some(Addr::unchecked("recipient")),
                 title: "title".to_string(),
                 description: Some("description".to_string()),
+                severance: Decimal::zero(),
+                label: None,
+                metadata: None,
+                timesheet: None,
             },
         )
         .unwrap();
@@ -704,14 +776,2609 @@ fn test_update_owner() {
     assert_eq!(err, ContractError::Cancelled);
 }
 
+/// Each distribution should be matched from the owner funded pool
+/// into the configured rewards distribution until the pool runs dry.
 #[test]
-#[should_panic(expected = "can not vest a constant amount, specifiy two or more points")]
-fn test_constant_piecewise_not_allowed() {
+fn test_matching_distribution() {
+    let mut env = mock_env();
+    let mut deps = mock_dependencies();
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(100, NATIVE_DENOM)),
+        InstantiateMsg {
+            total: Uint128::new(100),
+            denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+            matching: Some(MatchingConfig {
+                distributor: "distributor".to_string(),
+                distribution_id: 1,
+                ratio: Decimal::percent(50),
+                denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // only the owner may fund the matching pool.
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ALICE, &coins(30, NATIVE_DENOM)),
+        ExecuteMsg::FundMatchingPool {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Ownable(OwnershipError::NotOwner));
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(30, NATIVE_DENOM)),
+        ExecuteMsg::FundMatchingPool {},
+    )
+    .unwrap();
+
+    env.block.time = env.block.time.plus_seconds(604800);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ALICE, &[]),
+        ExecuteMsg::Distribute {
+            amount: Some(Uint128::new(40)),
+            memo: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[1].msg,
+        WasmMsg::Execute {
+            contract_addr: "distributor".to_string(),
+            msg: to_json_binary(&DistributorMsg::Fund { id: 1 }).unwrap(),
+            funds: coins(20, NATIVE_DENOM),
+        }
+        .into()
+    );
+
+    // the remaining 10 tokens in the pool only partially match this
+    // distribution.
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ALICE, &[]),
+        ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[1].msg,
+        WasmMsg::Execute {
+            contract_addr: "distributor".to_string(),
+            msg: to_json_binary(&DistributorMsg::Fund { id: 1 }).unwrap(),
+            funds: coins(10, NATIVE_DENOM),
+        }
+        .into()
+    );
+
+    let matching: Option<Matching> =
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::Matching {}).unwrap()).unwrap();
+    assert_eq!(matching.unwrap().pool, Uint128::zero());
+
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info(OWNER, &[]),
+        ExecuteMsg::WithdrawMatchingPool { amount: None },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidWithdrawal {
+            request: Uint128::zero(),
+            claimable: Uint128::zero()
+        }
+    );
+}
+
+/// Mocks a dao-rewards-distributor whose distribution may be frozen, in
+/// which case funding it fails.
+#[cw_serde]
+enum MockDistributorMsg {
+    Fund { id: u64 },
+    SetFrozen { frozen: bool },
+}
+
+const MOCK_DISTRIBUTOR_FROZEN: Item<bool> = Item::new("frozen");
+
+fn mock_distributor_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: MockDistributorMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            MockDistributorMsg::Fund { id } => {
+                if MOCK_DISTRIBUTOR_FROZEN
+                    .may_load(deps.storage)?
+                    .unwrap_or_default()
+                {
+                    return Err(StdError::generic_err(format!(
+                        "Distribution {id} is frozen"
+                    )));
+                }
+                Ok(Response::default())
+            }
+            MockDistributorMsg::SetFrozen { frozen } => {
+                MOCK_DISTRIBUTOR_FROZEN.save(deps.storage, &frozen)?;
+                Ok(Response::default())
+            }
+        }
+    }
+
+    fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&Empty {})
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// A match that the distributor rejects, e.g. because its
+/// distribution is frozen, does not block the distribution and is
+/// returned to the matching pool.
+#[test]
+fn test_matching_frozen_distribution() {
     let mut app = setup_app();
-    let instantiate = InstantiateMsg {
-        schedule: Schedule::PiecewiseLinear(vec![(1, Uint128::new(10))]),
+    let (_, _, cw_vesting_code_id) = setup_contracts(&mut app);
+    let distributor_code_id = app.store_code(mock_distributor_contract());
+    let distributor = app
+        .instantiate_contract(
+            distributor_code_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "distributor",
+            None,
+        )
+        .unwrap();
+    let vest = app
+        .instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                total: Uint128::new(100),
+                denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+                matching: Some(MatchingConfig {
+                    distributor: distributor.to_string(),
+                    distribution_id: 1,
+                    ratio: Decimal::percent(50),
+                    denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+                }),
+                ..Default::default()
+            },
+            &coins(100, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        vest.clone(),
+        &ExecuteMsg::FundMatchingPool {},
+        &coins(30, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    let distribute = |app: &mut App| {
+        app.execute_contract(
+            Addr::unchecked(ALICE),
+            vest.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap()
+    };
+    let pool = |app: &App| {
+        let matching: Option<Matching> = app
+            .wrap()
+            .query_wasm_smart(&vest, &QueryMsg::Matching {})
+            .unwrap();
+        matching.unwrap().pool
+    };
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        distributor.clone(),
+        &MockDistributorMsg::SetFrozen { frozen: true },
+        &[],
+    )
+    .unwrap();
+    let balance = get_balance_native(&app, BOB, NATIVE_DENOM);
+    app.update_block(|b| b.time = b.time.plus_seconds(302400));
+    let res = distribute(&mut app);
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "method" && a.value == "match_failed"));
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(50)
+    );
+    assert_eq!(pool(&app), Uint128::new(30));
+    assert_eq!(
+        get_balance_native(&app, &distributor, NATIVE_DENOM),
+        Uint128::zero()
+    );
+
+    // once the distribution is unfrozen, matches are funded again.
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        distributor.clone(),
+        &MockDistributorMsg::SetFrozen { frozen: false },
+        &[],
+    )
+    .unwrap();
+    app.update_block(|b| b.time = b.time.plus_seconds(302400));
+    distribute(&mut app);
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(100)
+    );
+    assert_eq!(pool(&app), Uint128::new(5));
+    assert_eq!(
+        get_balance_native(&app, &distributor, NATIVE_DENOM),
+        Uint128::new(25)
+    );
+}
+
+#[test]
+fn test_assign_recipient() {
+    let mut env = mock_env();
+    let mut deps = mock_dependencies();
+
+    // payments without a recipient need an owner to assign one.
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ALICE, &coins(100, NATIVE_DENOM)),
+        InstantiateMsg {
+            owner: None,
+            recipient: None,
+            total: Uint128::new(100),
+            denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::UnassignedWithoutOwner);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(100, NATIVE_DENOM)),
+        InstantiateMsg {
+            recipient: None,
+            vest_from_assignment: true,
+            total: Uint128::new(100),
+            denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // funds are locked until a recipient is assigned.
+    env.block.time = env.block.time.plus_seconds(604800);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ALICE, &[]),
+        ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unassigned);
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateOwnership(Action::RenounceOwnership),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::UnassignedWithoutOwner);
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ALICE, &[]),
+        ExecuteMsg::AssignRecipient {
+            recipient: ALICE.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Ownable(OwnershipError::NotOwner));
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AssignRecipient {
+            recipient: BOB.to_string(),
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AssignRecipient {
+            recipient: ALICE.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RecipientAssigned);
+
+    // vesting started at assignment, so half has vested half a week
+    // later.
+    env.block.time = env.block.time.plus_seconds(302400);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ALICE, &[]),
+        ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            to_address: BOB.to_string(),
+            amount: coins(50, NATIVE_DENOM),
+        }
+        .into()
+    );
+}
+
+#[test]
+#[cfg(feature = "governance")]
+fn test_governance_votes() {
+    use cosmwasm_std::{GovMsg, SubMsg, VoteOption, WeightedVoteOption};
+
+    let env = mock_env();
+    let native = InstantiateMsg {
+        denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
         ..Default::default()
     };
+    let vote = ExecuteMsg::Vote {
+        proposal_id: 1,
+        option: VoteOption::Yes,
+    };
 
-    setup_test_case(&mut app, instantiate, &[]);
+    // votes must be allowed on instantiation.
+    let mut deps = mock_dependencies();
+    deps.querier.update_staking(NATIVE_DENOM, &[], &[]);
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(TOTAL_VEST, NATIVE_DENOM)),
+        native.clone(),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(BOB, &[]),
+        vote.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::GovernanceVotesNotAllowed);
+
+    let mut deps = mock_dependencies();
+    deps.querier.update_staking(NATIVE_DENOM, &[], &[]);
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(TOTAL_VEST, NATIVE_DENOM)),
+        InstantiateMsg {
+            allow_governance_votes: true,
+            ..native
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &[]),
+        vote.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotReceiver);
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(BOB, &[]),
+        vote.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(GovMsg::Vote {
+            proposal_id: 1,
+            vote: VoteOption::Yes,
+        })]
+    );
+
+    let options = vec![
+        WeightedVoteOption {
+            option: VoteOption::Yes,
+            weight: Decimal::percent(60),
+        },
+        WeightedVoteOption {
+            option: VoteOption::Abstain,
+            weight: Decimal::percent(40),
+        },
+    ];
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(BOB, &[]),
+        ExecuteMsg::VoteWeighted {
+            proposal_id: 2,
+            options: options.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(GovMsg::VoteWeighted {
+            proposal_id: 2,
+            options,
+        })]
+    );
+
+    // once cancelled, staked tokens are owed to the owner first.
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::Cancel {
+            require_consistent_balances: None,
+            unbond: None,
+        },
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), env, mock_info(BOB, &[]), vote).unwrap_err();
+    assert_eq!(err, ContractError::Cancelled);
+}
+
+#[test]
+fn test_distribution_receipts() {
+    let mut app = setup_app();
+    let (_, _, cw_vesting_code_id) = setup_contracts(&mut app);
+    let cw721_code_id = app.store_code(cw721_base_contract());
+
+    // the first vesting contract instantiated below mints receipts.
+    let receipts = app
+        .instantiate_contract(
+            cw721_code_id,
+            Addr::unchecked(OWNER),
+            &cw721_base::InstantiateMsg {
+                name: "receipts".to_string(),
+                symbol: "RCPT".to_string(),
+                minter: "contract2".to_string(),
+            },
+            &[],
+            "receipts",
+            None,
+        )
+        .unwrap();
+
+    let msg = InstantiateMsg {
+        total: Uint128::new(100),
+        denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+        receipts: Some(receipts.to_string()),
+        ..Default::default()
+    };
+    let instantiate_vest = |app: &mut App| {
+        app.instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &msg,
+            &coins(100, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap()
+    };
+    let minter = instantiate_vest(&mut app);
+    assert_eq!(minter, Addr::unchecked("contract2"));
+    let not_minter = instantiate_vest(&mut app);
+
+    app.update_block(|b| b.time = b.time.plus_seconds(302400));
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            minter.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(res
+        .events
+        .iter()
+        .any(|e| e.ty == "wasm-distribution_receipt"));
+
+    let token_id = format!("{minter}/1");
+    let owner: cw721::OwnerOfResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &receipts,
+            &cw721::Cw721QueryMsg::OwnerOf {
+                token_id: token_id.clone(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner.owner, BOB);
+
+    let info: cw721::NftInfoResponse<Option<Empty>> = app
+        .wrap()
+        .query_wasm_smart(&receipts, &cw721::Cw721QueryMsg::NftInfo { token_id })
+        .unwrap();
+    let metadata: ReceiptMetadata = from_json(
+        cosmwasm_std::Binary::from_base64(
+            info.token_uri
+                .unwrap()
+                .strip_prefix("data:application/json;base64,")
+                .unwrap(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        metadata,
+        ReceiptMetadata {
+            vesting_contract: minter.to_string(),
+            amount: Uint128::new(50),
+            denom: CheckedDenom::Native(NATIVE_DENOM.to_string()),
+            distributed_at: app.block_info().time,
+            claimed: Uint128::new(50),
+            vested: Uint128::new(50),
+            memo: None,
+        }
+    );
+
+    // failing to mint a receipt does not prevent the distribution.
+    let balance = get_balance_native(&app, BOB, NATIVE_DENOM);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            not_minter,
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "method" && a.value == "receipt_failed"));
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(50)
+    );
+
+    // memos are emitted with the distribution and recorded in its
+    // receipt. Only the recipient or the owner may attach them.
+    app.update_block(|b| b.time = b.time.plus_seconds(302400));
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            minter.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: Some("a".repeat(MAX_MEMO_LENGTH + 1)),
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::MemoTooLong {
+            max: MAX_MEMO_LENGTH
+        }
+    );
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            minter.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: Some("invoice 42".to_string()),
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::MemoUnauthorized);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(BOB),
+            minter.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: Some("invoice 42".to_string()),
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(res
+        .events
+        .iter()
+        .filter(|e| e.ty == "wasm-distribution_receipt")
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "memo" && a.value == "invoice 42"));
+
+    let info: cw721::NftInfoResponse<Option<Empty>> = app
+        .wrap()
+        .query_wasm_smart(
+            &receipts,
+            &cw721::Cw721QueryMsg::NftInfo {
+                token_id: format!("{minter}/2"),
+            },
+        )
+        .unwrap();
+    let metadata: ReceiptMetadata = from_json(
+        cosmwasm_std::Binary::from_base64(
+            info.token_uri
+                .unwrap()
+                .strip_prefix("data:application/json;base64,")
+                .unwrap(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(metadata.memo, Some("invoice 42".to_string()));
+}
+
+#[test]
+fn test_distribution_memos() {
+    let mut env = mock_env();
+    let mut deps = mock_dependencies();
+    let total = MAX_STORED_MEMOS as u128 + 2;
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(total, NATIVE_DENOM)),
+        InstantiateMsg {
+            total: Uint128::new(total),
+            denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    env.block.time = env.block.time.plus_seconds(604800);
+
+    let distribute = |deps: DepsMut, sender: &str, memo: Option<String>| {
+        execute(
+            deps,
+            env.clone(),
+            mock_info(sender, &[]),
+            ExecuteMsg::Distribute {
+                amount: Some(Uint128::one()),
+                memo,
+                denom: None,
+            },
+        )
+    };
+    let memos = |deps: Deps, start_after: Option<u64>| -> Vec<DistributionMemo> {
+        from_json(
+            query(
+                deps,
+                env.clone(),
+                QueryMsg::DistributionMemos {
+                    start_after,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // only the recipient or the owner may attach a memo, though anyone
+    // may distribute.
+    let err = distribute(deps.as_mut(), ALICE, Some("invoice".to_string())).unwrap_err();
+    assert_eq!(err, ContractError::MemoUnauthorized);
+    distribute(deps.as_mut(), ALICE, None).unwrap();
+    distribute(deps.as_mut(), OWNER, Some("invoice 2".to_string())).unwrap();
+    assert_eq!(
+        memos(deps.as_ref(), None),
+        vec![DistributionMemo {
+            distribution: 2,
+            memo: "invoice 2".to_string(),
+            amount: Uint128::one(),
+            denom: CheckedDenom::Native(NATIVE_DENOM.to_string()),
+            distributed_at: env.block.time,
+        }]
+    );
+
+    // only the memos of the most recent distributions are kept.
+    for n in 3..=total as u64 {
+        distribute(deps.as_mut(), BOB, Some(format!("invoice {n}"))).unwrap();
+    }
+    let kept = memos(deps.as_ref(), None);
+    assert_eq!(kept[0].distribution, 3);
+    assert_eq!(
+        memos(deps.as_ref(), Some(total as u64 - 1))[0].memo,
+        format!("invoice {total}")
+    );
+}
+
+/// A swap adapter that simulates a return of two ask tokens per
+/// offered token, but swaps at a rate that may be changed to simulate
+/// the price moving. With a rate of zero it can not simulate swaps.
+#[cw_serde]
+enum MockAdapterMsg {
+    Swap {
+        ask_denom: CheckedDenom,
+        min_return: Uint128,
+        recipient: String,
+    },
+    SetRate {
+        rate: Decimal,
+    },
+}
+
+const MOCK_ADAPTER_RATE: Item<Decimal> = Item::new("rate");
+
+fn mock_adapter_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        rate: Decimal,
+    ) -> StdResult<Response> {
+        MOCK_ADAPTER_RATE.save(deps.storage, &rate)?;
+        Ok(Response::default())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: MockAdapterMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            MockAdapterMsg::Swap {
+                ask_denom,
+                min_return,
+                recipient,
+            } => {
+                let rate = MOCK_ADAPTER_RATE.load(deps.storage)?;
+                let amount = info.funds[0].amount.mul_floor(rate);
+                if amount < min_return {
+                    return Err(StdError::generic_err("slippage"));
+                }
+                Ok(Response::default().add_message(
+                    ask_denom
+                        .get_transfer_to_message(&Addr::unchecked(recipient), amount)
+                        .unwrap(),
+                ))
+            }
+            MockAdapterMsg::SetRate { rate } => {
+                MOCK_ADAPTER_RATE.save(deps.storage, &rate)?;
+                Ok(Response::default())
+            }
+        }
+    }
+
+    fn query(deps: Deps, _env: Env, msg: SwapAdapterQueryMsg) -> StdResult<Binary> {
+        match msg {
+            SwapAdapterQueryMsg::SimulateSwap { offer_amount, .. } => {
+                if MOCK_ADAPTER_RATE.load(deps.storage)?.is_zero() {
+                    return Err(StdError::generic_err("no liquidity"));
+                }
+                to_json_binary(&SimulateSwapResponse {
+                    return_amount: offer_amount * Uint128::new(2),
+                })
+            }
+        }
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[test]
+fn test_swap() {
+    const ASK_DENOM: &str = "uusdc";
+
+    let mut app = setup_app();
+    let (_, _, cw_vesting_code_id) = setup_contracts(&mut app);
+    let adapter_code_id = app.store_code(mock_adapter_contract());
+    let adapter = app
+        .instantiate_contract(
+            adapter_code_id,
+            Addr::unchecked(OWNER),
+            &Decimal::percent(200),
+            &[],
+            "adapter",
+            None,
+        )
+        .unwrap();
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: adapter.to_string(),
+        amount: coins(1000, ASK_DENOM),
+    }))
+    .unwrap();
+
+    let vest = app
+        .instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                total: Uint128::new(100),
+                denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+                swap: Some(SwapConfig {
+                    adapter: adapter.to_string(),
+                    ask_denom: UncheckedDenom::Native(ASK_DENOM.to_string()),
+                }),
+                ..Default::default()
+            },
+            &coins(100, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+    let distribute = |app: &mut App, sender: &str, amount: Option<u128>| {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            vest.clone(),
+            &ExecuteMsg::Distribute {
+                amount: amount.map(Uint128::new),
+                memo: None,
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap()
+    };
+    let set_rate = |app: &mut App, rate: Decimal| {
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            adapter.clone(),
+            &MockAdapterMsg::SetRate { rate },
+            &[],
+        )
+        .unwrap();
+    };
+    let swap_failed = |res: &AppResponse| {
+        res.events
+            .iter()
+            .flat_map(|e| &e.attributes)
+            .any(|a| a.key == "method" && a.value == "swap_failed")
+    };
+    let set_max_slippage = |app: &mut App, sender: &str, max_slippage: Decimal| {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            vest.clone(),
+            &ExecuteMsg::SetMaxSlippage {
+                max_slippage: Some(max_slippage),
+            },
+            &[],
+        )
+        .map_err(|e| e.downcast::<ContractError>().unwrap())
+    };
+
+    // only the recipient may opt into swapping.
+    let err = set_max_slippage(&mut app, OWNER, Decimal::percent(10)).unwrap_err();
+    assert_eq!(err, ContractError::NotReceiver);
+    let err = set_max_slippage(&mut app, BOB, Decimal::percent(101)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSlippage {
+            max_slippage: Decimal::percent(101)
+        }
+    );
+
+    // until the recipient opts in, distributions are not swapped.
+    let balance = get_balance_native(&app, BOB, NATIVE_DENOM);
+    app.update_block(|b| b.time = b.time.plus_seconds(302400));
+    distribute(&mut app, BOB, None);
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(50)
+    );
+
+    set_max_slippage(&mut app, BOB, Decimal::percent(10)).unwrap();
+    let swap: Option<Swap> = app
+        .wrap()
+        .query_wasm_smart(&vest, &QueryMsg::Swap {})
+        .unwrap();
+    assert_eq!(
+        swap,
+        Some(Swap {
+            adapter: adapter.clone(),
+            ask_denom: CheckedDenom::Native(ASK_DENOM.to_string()),
+            max_slippage: Some(Decimal::percent(10)),
+        })
+    );
+
+    // distributions by anyone but the recipient are not swapped, so
+    // that only the recipient chooses when their tokens are sold.
+    app.update_block(|b| b.time = b.time.plus_seconds(151200));
+    distribute(&mut app, ALICE, Some(10));
+    assert_eq!(get_balance_native(&app, BOB, ASK_DENOM), Uint128::zero());
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(60)
+    );
+
+    distribute(&mut app, BOB, None);
+    assert_eq!(get_balance_native(&app, BOB, ASK_DENOM), Uint128::new(30));
+    assert_eq!(
+        get_balance_native(&app, &adapter, NATIVE_DENOM),
+        Uint128::new(15)
+    );
+
+    // if the price moves by more than the max slippage the swap fails
+    // and the recipient is paid in the vested token.
+    set_rate(&mut app, Decimal::percent(150));
+    app.update_block(|b| b.time = b.time.plus_seconds(151200));
+    let res = distribute(&mut app, BOB, Some(10));
+    assert!(swap_failed(&res));
+    assert_eq!(get_balance_native(&app, BOB, ASK_DENOM), Uint128::new(30));
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(70)
+    );
+
+    // if the adapter can not simulate the swap, the recipient is paid
+    // in the vested token without attempting it.
+    set_rate(&mut app, Decimal::zero());
+    let res = distribute(&mut app, BOB, None);
+    assert!(!swap_failed(&res));
+    assert_eq!(get_balance_native(&app, BOB, ASK_DENOM), Uint128::new(30));
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(85)
+    );
+    assert_eq!(
+        get_balance_native(&app, &adapter, NATIVE_DENOM),
+        Uint128::new(15)
+    );
+}
+
+/// An off-ramp escrow that accepts all payouts.
+fn mock_escrow_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: OffRampEscrowMsg,
+    ) -> StdResult<Response> {
+        let OffRampEscrowMsg::Payout(payload) = msg;
+        Ok(Response::default().add_attribute("invoice_ref", payload.invoice_ref))
+    }
+
+    fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&Empty {})
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[test]
+fn test_off_ramp() {
+    let mut app = setup_app();
+    let (_, _, cw_vesting_code_id) = setup_contracts(&mut app);
+    let escrow_code_id = app.store_code(mock_escrow_contract());
+    let escrow = app
+        .instantiate_contract(
+            escrow_code_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "escrow",
+            None,
+        )
+        .unwrap();
+    let msg = InstantiateMsg {
+        total: Uint128::new(100),
+        denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+        off_ramp: Some(OffRampConfig {
+            escrow: escrow.to_string(),
+            recipient_id: "employee-42".to_string(),
+        }),
+        ..Default::default()
+    };
+
+    // distributions may not be both swapped and off-ramped.
+    let err: ContractError = app
+        .instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                swap: Some(SwapConfig {
+                    adapter: "adapter".to_string(),
+                    ask_denom: UncheckedDenom::Native("uusdc".to_string()),
+                }),
+                ..msg.clone()
+            },
+            &coins(100, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::SwapWithOffRamp);
+
+    let vest = app
+        .instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &msg,
+            &coins(100, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+    let distribute = |app: &mut App| {
+        app.execute_contract(
+            Addr::unchecked(ALICE),
+            vest.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap()
+    };
+    let acknowledge = |app: &mut App, sender: &Addr, payout_ids: Vec<u64>| {
+        app.execute_contract(
+            sender.clone(),
+            vest.clone(),
+            &ExecuteMsg::AcknowledgeOffRampPayouts { payout_ids },
+            &[],
+        )
+        .map_err(|e| e.downcast::<ContractError>().unwrap())
+    };
+    let payouts = |app: &App, start_after: Option<u64>| -> Vec<OffRampPayout> {
+        app.wrap()
+            .query_wasm_smart(
+                &vest,
+                &QueryMsg::OffRampPayouts {
+                    start_after,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    };
+
+    // distributions are sent to the escrow instead of the recipient.
+    let balance = get_balance_native(&app, BOB, NATIVE_DENOM);
+    app.update_block(|b| b.time = b.time.plus_seconds(302400));
+    let res = distribute(&mut app);
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "invoice_ref" && a.value == format!("{vest}/1")));
+    assert_eq!(get_balance_native(&app, BOB, NATIVE_DENOM), balance);
+    assert_eq!(
+        get_balance_native(&app, &escrow, NATIVE_DENOM),
+        Uint128::new(50)
+    );
+    assert_eq!(
+        payouts(&app, None),
+        vec![OffRampPayout {
+            payload: OffRampPayload {
+                vesting_contract: vest.to_string(),
+                payout_id: 1,
+                recipient_id: "employee-42".to_string(),
+                invoice_ref: format!("{vest}/1"),
+                recipient: BOB.to_string(),
+                amount: Uint128::new(50),
+                denom: CheckedDenom::Native(NATIVE_DENOM.to_string()),
+                distributed_at: app.block_info().time,
+            },
+            acknowledged_at: None,
+        }]
+    );
+
+    // only the escrow may acknowledge payouts, and only once.
+    let err = acknowledge(&mut app, &Addr::unchecked(OWNER), vec![1]).unwrap_err();
+    assert_eq!(err, ContractError::NotOffRampEscrow);
+    let err = acknowledge(&mut app, &escrow, vec![2]).unwrap_err();
+    assert_eq!(err, ContractError::UnknownOffRampPayout { id: 2 });
+    acknowledge(&mut app, &escrow, vec![1]).unwrap();
+    let err = acknowledge(&mut app, &escrow, vec![1]).unwrap_err();
+    assert_eq!(err, ContractError::OffRampPayoutAcknowledged { id: 1 });
+    assert_eq!(
+        payouts(&app, None)[0].acknowledged_at,
+        Some(app.block_info().time)
+    );
+
+    app.update_block(|b| b.time = b.time.plus_seconds(151200));
+    distribute(&mut app);
+    let off_ramp: Option<OffRamp> = app
+        .wrap()
+        .query_wasm_smart(&vest, &QueryMsg::OffRamp {})
+        .unwrap();
+    assert_eq!(
+        off_ramp,
+        Some(OffRamp {
+            escrow: escrow.clone(),
+            recipient_id: "employee-42".to_string(),
+            payouts: 2,
+            acknowledged: 1,
+            amount_paid_out: Uint128::new(75),
+            amount_acknowledged: Uint128::new(50),
+        })
+    );
+    let unacknowledged = payouts(&app, Some(1));
+    assert_eq!(unacknowledged.len(), 1);
+    assert_eq!(unacknowledged[0].payload.amount, Uint128::new(25));
+    assert_eq!(unacknowledged[0].acknowledged_at, None);
+}
+
+/// A price oracle whose prices are set for specific times. Querying
+/// the price at any other time fails.
+const MOCK_ORACLE_PRICES: Map<u64, Decimal> = Map::new("prices");
+
+fn mock_oracle_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _: Env,
+        _: MessageInfo,
+        (time, price): (u64, Decimal),
+    ) -> StdResult<Response> {
+        MOCK_ORACLE_PRICES.save(deps.storage, time, &price)?;
+        Ok(Response::default())
+    }
+
+    fn query(deps: Deps, _: Env, msg: PriceOracleQueryMsg) -> StdResult<Binary> {
+        let PriceOracleQueryMsg::Price { time, .. } = msg;
+        to_json_binary(&PriceResponse {
+            price: MOCK_ORACLE_PRICES.load(deps.storage, time.seconds())?,
+        })
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[test]
+fn test_value_targeted_schedule() {
+    const WEEK: u64 = 604800;
+
+    let mut app = setup_app();
+    let (_, _, cw_vesting_code_id) = setup_contracts(&mut app);
+    let oracle_code_id = app.store_code(mock_oracle_contract());
+    let oracle = app
+        .instantiate_contract(
+            oracle_code_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "oracle",
+            None,
+        )
+        .unwrap();
+    let start = app.block_info().time.seconds();
+    let set_price = |app: &mut App, period: u64, price: Decimal| {
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            oracle.clone(),
+            &(start + period * WEEK, price),
+            &[],
+        )
+        .unwrap();
+    };
+
+    let msg = InstantiateMsg {
+        total: Uint128::new(1000),
+        denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+        schedule: Schedule::ValueTargeted {
+            oracle: oracle.to_string(),
+            period_seconds: WEEK,
+            value_per_period: Uint128::new(100),
+            min_per_period: Uint128::new(50),
+            max_per_period: Uint128::new(300),
+        },
+        vesting_duration_seconds: 4 * WEEK,
+        ..Default::default()
+    };
+    let err: ContractError = app
+        .instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                vesting_duration_seconds: WEEK - 1,
+                ..msg.clone()
+            },
+            &coins(1000, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidValueTarget);
+
+    let vest = app
+        .instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &msg,
+            &coins(1000, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+    let distribute = |app: &mut App| {
+        app.execute_contract(
+            Addr::unchecked(ALICE),
+            vest.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
+            &[],
+        )
+    };
+
+    // each period vests the period's value at the price at its end,
+    // bounded by the min and max per period.
+    set_price(&mut app, 1, Decimal::one());
+    set_price(&mut app, 2, Decimal::percent(25));
+    set_price(&mut app, 3, Decimal::percent(400));
+    let balance = get_balance_native(&app, BOB, NATIVE_DENOM);
+    app.update_block(|b| b.time = b.time.plus_seconds(3 * WEEK + 1));
+    distribute(&mut app).unwrap();
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(100 + 300 + 50)
+    );
+
+    // periods whose price can not be queried do not vest until it can.
+    app.update_block(|b| b.time = b.time.plus_seconds(WEEK));
+    let err: ContractError = distribute(&mut app).unwrap_err().downcast().unwrap();
+    assert!(matches!(err, ContractError::InvalidWithdrawal { .. }));
+
+    set_price(&mut app, 4, Decimal::percent(200));
+    distribute(&mut app).unwrap();
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(500)
+    );
+    let vested: Uint128 = app
+        .wrap()
+        .query_wasm_smart(&vest, &QueryMsg::Vested { t: None })
+        .unwrap();
+    assert_eq!(vested, Uint128::new(500));
+
+    // tokens left unvested once every period has passed are returned
+    // to the owner on cancelation.
+    let owner_balance = get_balance_native(&app, OWNER, NATIVE_DENOM);
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        vest.clone(),
+        &ExecuteMsg::Cancel {
+            require_consistent_balances: None,
+            unbond: None,
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        get_balance_native(&app, OWNER, NATIVE_DENOM),
+        owner_balance + Uint128::new(500)
+    );
+}
+
+const MOCK_TIMESHEET_UNITS: Map<u64, Uint128> = Map::new("units");
+
+fn mock_timesheet_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _: Env,
+        _: MessageInfo,
+        (end, units): (u64, Uint128),
+    ) -> StdResult<Response> {
+        MOCK_TIMESHEET_UNITS.save(deps.storage, end, &units)?;
+        Ok(Response::default())
+    }
+
+    fn query(deps: Deps, _: Env, msg: TimesheetQueryMsg) -> StdResult<Binary> {
+        let TimesheetQueryMsg::ApprovedUnits { end, .. } = msg;
+        to_json_binary(&ApprovedUnitsResponse {
+            units: MOCK_TIMESHEET_UNITS.load(deps.storage, end.seconds())?,
+        })
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[test]
+fn test_timesheet() {
+    const WEEK: u64 = 604800;
+
+    let mut app = setup_app();
+    let (_, _, cw_vesting_code_id) = setup_contracts(&mut app);
+    let timesheet_code_id = app.store_code(mock_timesheet_contract());
+    let timesheet = app
+        .instantiate_contract(
+            timesheet_code_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "timesheet",
+            None,
+        )
+        .unwrap();
+    let start = app.block_info().time.seconds();
+    let approve = |app: &mut App, end: u64, units: u128| {
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            timesheet.clone(),
+            &(start + end, Uint128::new(units)),
+            &[],
+        )
+        .unwrap();
+    };
+
+    // vests 200 tokens a week for three and a half weeks, for 40
+    // hours of work a week.
+    let msg = InstantiateMsg {
+        total: Uint128::new(700),
+        denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+        vesting_duration_seconds: 3 * WEEK + WEEK / 2,
+        timesheet: Some(TimesheetConfig {
+            oracle: timesheet.to_string(),
+            period_seconds: WEEK,
+            units_per_period: Uint128::new(40),
+        }),
+        ..Default::default()
+    };
+    let instantiate_err = |app: &mut App, msg: InstantiateMsg| -> ContractError {
+        app.instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &msg,
+            &coins(700, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+    };
+    assert_eq!(
+        instantiate_err(
+            &mut app,
+            InstantiateMsg {
+                timesheet: Some(TimesheetConfig {
+                    oracle: timesheet.to_string(),
+                    period_seconds: WEEK,
+                    units_per_period: Uint128::zero(),
+                }),
+                ..msg.clone()
+            }
+        ),
+        ContractError::InvalidTimesheet
+    );
+    assert_eq!(
+        instantiate_err(
+            &mut app,
+            InstantiateMsg {
+                schedule: Schedule::ValueTargeted {
+                    oracle: timesheet.to_string(),
+                    period_seconds: WEEK,
+                    value_per_period: Uint128::new(100),
+                    min_per_period: Uint128::new(50),
+                    max_per_period: Uint128::new(300),
+                },
+                ..msg.clone()
+            }
+        ),
+        ContractError::TimesheetWithValueTarget
+    );
+
+    let vest = app
+        .instantiate_contract(
+            cw_vesting_code_id,
+            Addr::unchecked(OWNER),
+            &msg,
+            &coins(700, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+    let distribute = |app: &mut App| {
+        app.execute_contract(
+            Addr::unchecked(ALICE),
+            vest.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: None,
+            },
+            &[],
+        )
+    };
+
+    // each period vests the scheduled amount scaled by the units
+    // approved, never more than the schedule.
+    approve(&mut app, WEEK, 40);
+    approve(&mut app, 2 * WEEK, 20);
+    approve(&mut app, 3 * WEEK, 60);
+    let balance = get_balance_native(&app, BOB, NATIVE_DENOM);
+    app.update_block(|b| b.time = b.time.plus_seconds(3 * WEEK + 1));
+    distribute(&mut app).unwrap();
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(200 + 100 + 200)
+    );
+
+    // the last period ends with the vest, and does not vest until
+    // its timesheet is reported.
+    app.update_block(|b| b.time = b.time.plus_seconds(WEEK));
+    let err: ContractError = distribute(&mut app).unwrap_err().downcast().unwrap();
+    assert!(matches!(err, ContractError::InvalidWithdrawal { .. }));
+
+    approve(&mut app, 3 * WEEK + WEEK / 2, 10);
+    distribute(&mut app).unwrap();
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        balance + Uint128::new(525)
+    );
+    let vest_info: Vest = app
+        .wrap()
+        .query_wasm_smart(&vest, &QueryMsg::Info {})
+        .unwrap();
+    assert_eq!(vest_info.timesheet.unwrap().periods_indexed, 4);
+
+    // tokens left unvested for lack of approved units are returned
+    // to the owner on cancelation.
+    let owner_balance = get_balance_native(&app, OWNER, NATIVE_DENOM);
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        vest.clone(),
+        &ExecuteMsg::Cancel {
+            require_consistent_balances: None,
+            unbond: None,
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        get_balance_native(&app, OWNER, NATIVE_DENOM),
+        owner_balance + Uint128::new(175)
+    );
+}
+
+#[test]
+fn test_summary() {
+    let mut app = setup_app();
+
+    let msg = InstantiateMsg {
+        denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+        label: Some("engineering/bob".to_string()),
+        metadata: Some(r#"{"team":"engineering"}"#.to_string()),
+        ..Default::default()
+    };
+    let TestCase {
+        cw_vesting_addr,
+        recipient: bob,
+        ..
+    } = setup_test_case(&mut app, msg, &coins(TOTAL_VEST, NATIVE_DENOM));
+    let start_time = app.block_info().time;
+
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(604800 / 4);
+    });
+    app.execute_contract(
+        bob.clone(),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::Distribute {
+            amount: Some(Uint128::new(TOTAL_VEST / 8)),
+            memo: None,
+            denom: None,
+        },
+        &[],
+    )
+    .unwrap();
+
+    let summary: Summary = app
+        .wrap()
+        .query_wasm_smart(&cw_vesting_addr, &QueryMsg::Summary { t: None })
+        .unwrap();
+    assert_eq!(summary.label, Some("engineering/bob".to_string()));
+    assert_eq!(
+        summary.metadata,
+        Some(r#"{"team":"engineering"}"#.to_string())
+    );
+    assert_eq!(summary.recipient, Some(bob));
+    assert_eq!(summary.status, Status::Funded);
+    assert_eq!(summary.schedule.kind, ScheduleKind::SaturatingLinear);
+    assert_eq!(summary.schedule.start_time, start_time);
+    assert_eq!(
+        summary.schedule.end_time,
+        Some(start_time.plus_seconds(604800))
+    );
+    assert_eq!(summary.total, Uint128::new(TOTAL_VEST));
+    assert_eq!(summary.vested, Uint128::new(TOTAL_VEST / 4));
+    assert_eq!(summary.claimed, Uint128::new(TOTAL_VEST / 8));
+    assert_eq!(summary.distributable, Uint128::new(TOTAL_VEST / 8));
+    assert_eq!(summary.staked, Uint128::zero());
+    assert!(summary.pending_unbonds.is_empty());
+
+    // the summary may be queried at a future time.
+    let summary: Summary = app
+        .wrap()
+        .query_wasm_smart(
+            &cw_vesting_addr,
+            &QueryMsg::Summary {
+                t: Some(start_time.plus_seconds(604800)),
+            },
+        )
+        .unwrap();
+    assert_eq!(summary.vested, Uint128::new(TOTAL_VEST));
+    assert_eq!(
+        summary.distributable,
+        Uint128::new(TOTAL_VEST - TOTAL_VEST / 8)
+    );
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::Cancel {
+            require_consistent_balances: None,
+            unbond: None,
+        },
+        &[],
+    )
+    .unwrap();
+    let summary: Summary = app
+        .wrap()
+        .query_wasm_smart(&cw_vesting_addr, &QueryMsg::Summary { t: None })
+        .unwrap();
+    assert_eq!(summary.schedule.kind, ScheduleKind::Constant);
+    assert_eq!(summary.schedule.end_time, None);
+    assert_eq!(summary.total, Uint128::new(TOTAL_VEST / 4));
+}
+
+#[test]
+fn test_verify_balances() {
+    let validator = Validator {
+        address: "testvaloper1".to_string(),
+        commission: Decimal::percent(1),
+        max_commission: Decimal::percent(100),
+        max_change_rate: Decimal::percent(1),
+    };
+
+    let mut app = AppBuilder::default().build(|router, api, storage| {
+        router
+            .staking
+            .setup(
+                storage,
+                StakingInfo {
+                    bonded_denom: NATIVE_DENOM.to_string(),
+                    unbonding_time: 60,
+                    apr: Decimal::percent(10),
+                },
+            )
+            .unwrap();
+        router
+            .staking
+            .add_validator(api, storage, &mock_env().block, validator)
+            .unwrap();
+    });
+
+    let vesting_id = app.store_code(cw_vesting_contract());
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: OWNER.to_string(),
+        amount: coins(101, NATIVE_DENOM),
+    }))
+    .unwrap();
+
+    let vesting = app
+        .instantiate_contract(
+            vesting_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+                total: Uint128::new(100),
+                ..Default::default()
+            },
+            &coins(100, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(BOB),
+        vesting.clone(),
+        &ExecuteMsg::Delegate {
+            validator: "testvaloper1".to_string(),
+            amount: Uint128::new(60),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let verify_balances = |app: &App| -> VerifyBalancesResponse {
+        app.wrap()
+            .query_wasm_smart(&vesting, &QueryMsg::VerifyBalances {})
+            .unwrap()
+    };
+
+    assert_eq!(
+        verify_balances(&app),
+        VerifyBalancesResponse {
+            liquid: BalanceCheck {
+                expected: Uint128::new(40),
+                actual: Uint128::new(40),
+            },
+            delegated: Some(BalanceCheck {
+                expected: Uint128::new(60),
+                actual: Uint128::new(60),
+            }),
+            consistent: true,
+        }
+    );
+
+    // Tokens sent to the contract directly are a surplus, which is
+    // reported but consistent.
+    app.send_tokens(
+        Addr::unchecked(OWNER),
+        vesting.clone(),
+        &coins(1, NATIVE_DENOM),
+    )
+    .unwrap();
+    let check = verify_balances(&app);
+    assert_eq!(check.liquid.actual, Uint128::new(41));
+    assert!(check.consistent);
+
+    // An unregistered slash leaves fewer tokens delegated than
+    // expected.
+    app.sudo(
+        StakingSudo::Slash {
+            validator: "testvaloper1".to_string(),
+            percentage: Decimal::percent(10),
+        }
+        .into(),
+    )
+    .unwrap();
+    let check = verify_balances(&app);
+    assert_eq!(
+        check.delegated,
+        Some(BalanceCheck {
+            expected: Uint128::new(60),
+            actual: Uint128::new(54),
+        })
+    );
+    assert!(!check.consistent);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            vesting.clone(),
+            &ExecuteMsg::Cancel {
+                require_consistent_balances: Some(true),
+                unbond: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InconsistentBalances);
+
+    // Once the slash is registered balances are consistent again, and
+    // the payment may be canceled.
+    let time = app.block_info().time;
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        vesting.clone(),
+        &ExecuteMsg::RegisterSlash {
+            validator: "testvaloper1".to_string(),
+            time,
+            amount: Uint128::new(6),
+            during_unbonding: false,
+        },
+        &[],
+    )
+    .unwrap();
+    let check = verify_balances(&app);
+    assert_eq!(
+        check.delegated,
+        Some(BalanceCheck {
+            expected: Uint128::new(54),
+            actual: Uint128::new(54),
+        })
+    );
+    assert!(check.consistent);
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        vesting.clone(),
+        &ExecuteMsg::Cancel {
+            require_consistent_balances: Some(true),
+            unbond: None,
+        },
+        &[],
+    )
+    .unwrap();
+    assert!(verify_balances(&app).consistent);
+}
+
+#[test]
+fn test_transfer() {
+    let mut app = setup_app();
+    let vesting_id = app.store_code(cw_vesting_contract());
+    // A second upload of the same contract stands in for a newer
+    // version.
+    let next_vesting_id = app.store_code(cw_vesting_contract());
+
+    let vesting = app
+        .instantiate_contract(
+            vesting_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+                ..Default::default()
+            },
+            &coins(TOTAL_VEST, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(604800 / 4));
+    app.execute_contract(
+        Addr::unchecked(BOB),
+        vesting.clone(),
+        &ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.update_block(|block| block.time = block.time.plus_seconds(604800 / 4));
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        vesting.clone(),
+        &ExecuteMsg::UpdateGuardian {
+            guardian: Some("guardian".to_string()),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked("guardian"),
+        vesting.clone(),
+        &ExecuteMsg::AcceptGuardian {},
+        &[],
+    )
+    .unwrap();
+    let set_paused = |app: &mut App, paused: bool| {
+        app.execute_contract(
+            Addr::unchecked("guardian"),
+            vesting.clone(),
+            &ExecuteMsg::SetPaused { paused },
+            &[],
+        )
+        .unwrap();
+    };
+
+    let approve = ExecuteMsg::ApproveTransfer {
+        code_id: next_vesting_id,
+    };
+    let err: ContractError = app
+        .execute_contract(Addr::unchecked(ALICE), vesting.clone(), &approve, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NotOwnerOrReceiver);
+
+    // A paused vest may not escape its guardian.
+    set_paused(&mut app, true);
+    let err: ContractError = app
+        .execute_contract(Addr::unchecked(OWNER), vesting.clone(), &approve, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Paused);
+    set_paused(&mut app, false);
+
+    // The owner's approval alone does not transfer the vest.
+    app.execute_contract(Addr::unchecked(OWNER), vesting.clone(), &approve, &[])
+        .unwrap();
+    let transfer: TransferResponse = app
+        .wrap()
+        .query_wasm_smart(&vesting, &QueryMsg::Transfer {})
+        .unwrap();
+    assert_eq!(
+        transfer,
+        TransferResponse {
+            approval: Some(TransferApproval {
+                code_id: next_vesting_id,
+                owner: Some(Addr::unchecked(OWNER)),
+                recipient: None,
+            }),
+            transfer: None,
+        }
+    );
+
+    app.execute_contract(Addr::unchecked(BOB), vesting.clone(), &approve, &[])
+        .unwrap();
+    let transfer: TransferResponse = app
+        .wrap()
+        .query_wasm_smart(&vesting, &QueryMsg::Transfer {})
+        .unwrap();
+    assert_eq!(transfer.approval, None);
+    let transfer = transfer.transfer.unwrap();
+    assert_eq!(transfer.amount, Uint128::new(TOTAL_VEST * 3 / 4));
+    let next = transfer.contract;
+
+    // All remaining funds moved, and the old vest is closed.
+    assert_eq!(
+        get_balance_native(&app, &vesting, NATIVE_DENOM),
+        Uint128::zero()
+    );
+    assert_eq!(
+        get_balance_native(&app, &next, NATIVE_DENOM),
+        Uint128::new(TOTAL_VEST * 3 / 4)
+    );
+    let old = get_vesting_payment(&app, vesting.clone());
+    assert_eq!(
+        old.status,
+        Status::Canceled {
+            owner_withdrawable: Uint128::zero()
+        }
+    );
+    let distributable: Uint128 = app
+        .wrap()
+        .query_wasm_smart(&vesting, &QueryMsg::Distributable { t: None })
+        .unwrap();
+    assert_eq!(distributable, Uint128::zero());
+
+    // The new vest picks up where the old one left off, with the same
+    // guardian.
+    let guardian: Guardian = app
+        .wrap()
+        .query_wasm_smart(&next, &QueryMsg::Guardian {})
+        .unwrap();
+    assert_eq!(
+        guardian,
+        Guardian {
+            guardian: Some(Addr::unchecked("guardian")),
+            pending: None,
+            paused: false,
+        }
+    );
+    let new = get_vesting_payment(&app, next.clone());
+    assert_eq!(new.status, Status::Funded);
+    assert_eq!(new.recipient, Some(Addr::unchecked(BOB)));
+    assert_eq!(
+        new.vested(app.block_info().time),
+        Uint128::new(TOTAL_VEST / 4)
+    );
+    app.update_block(|block| block.time = block.time.plus_seconds(604800 / 2));
+    app.execute_contract(
+        Addr::unchecked(BOB),
+        next,
+        &ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        Uint128::new(INITIAL_BALANCE + TOTAL_VEST)
+    );
+
+    // A closed vest may not be transferred again.
+    let err: ContractError = app
+        .execute_contract(Addr::unchecked(OWNER), vesting, &approve, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Untransferable);
+}
+
+#[test]
+fn test_transfer_with_swap() {
+    let mut app = setup_app();
+    let vesting_id = app.store_code(cw_vesting_contract());
+    let next_vesting_id = app.store_code(cw_vesting_contract());
+
+    let vesting = app
+        .instantiate_contract(
+            vesting_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+                swap: Some(SwapConfig {
+                    adapter: "adapter".to_string(),
+                    ask_denom: UncheckedDenom::Native("uusdc".to_string()),
+                }),
+                ..Default::default()
+            },
+            &coins(TOTAL_VEST, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap();
+
+    // The swap could not be carried over, so the vest may not be
+    // transferred.
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            vesting,
+            &ExecuteMsg::ApproveTransfer {
+                code_id: next_vesting_id,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Untransferable);
+}
+
+#[test]
+#[should_panic(expected = "can not vest a constant amount, specifiy two or more points")]
+fn test_constant_piecewise_not_allowed() {
+    let mut app = setup_app();
+    let instantiate = InstantiateMsg {
+        schedule: Schedule::PiecewiseLinear(vec![(1, Uint128::new(10))]),
+        ..Default::default()
+    };
+
+    setup_test_case(&mut app, instantiate, &[]);
+}
+
+const MOCK_HOOK_MSGS: Item<Vec<StakeChangedHookMsg>> = Item::new("mock_hook_msgs");
+
+fn mock_hook_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        MOCK_HOOK_MSGS.save(deps.storage, &vec![])?;
+        Ok(Response::default())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: StakeChangedExecuteMsg,
+    ) -> StdResult<Response> {
+        let StakeChangedExecuteMsg::StakeChangeHook(msg) = msg;
+        MOCK_HOOK_MSGS.update(deps.storage, |mut msgs| -> StdResult<_> {
+            msgs.push(msg);
+            Ok(msgs)
+        })?;
+        Ok(Response::default())
+    }
+
+    fn query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&MOCK_HOOK_MSGS.load(deps.storage)?)
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[test]
+fn test_voting_power() {
+    let mut app = setup_app();
+    let hook_id = app.store_code(mock_hook_contract());
+    let TestCase {
+        cw_vesting_addr,
+        recipient: bob,
+        ..
+    } = setup_test_case(&mut app, InstantiateMsg::default(), &[]);
+
+    let hook = app
+        .instantiate_contract(
+            hook_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "hook",
+            None,
+        )
+        .unwrap();
+
+    let voting_power = |app: &App, address: &str, height: Option<u64>| -> Uint128 {
+        let res: VotingPowerAtHeightResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &cw_vesting_addr,
+                &QueryMsg::VotingPowerAtHeight {
+                    address: address.to_string(),
+                    height,
+                },
+            )
+            .unwrap();
+        res.power
+    };
+    let total_power = |app: &App, height: Option<u64>| -> Uint128 {
+        let res: TotalPowerAtHeightResponse = app
+            .wrap()
+            .query_wasm_smart(&cw_vesting_addr, &QueryMsg::TotalPowerAtHeight { height })
+            .unwrap();
+        res.power
+    };
+    let hook_msgs = |app: &App| -> Vec<StakeChangedHookMsg> {
+        app.wrap().query_wasm_smart(&hook, &Empty {}).unwrap()
+    };
+
+    // Only the owner may add hooks.
+    let err: ContractError = app
+        .execute_contract(
+            bob.clone(),
+            cw_vesting_addr.clone(),
+            &ExecuteMsg::AddHook {
+                addr: hook.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Ownable(OwnershipError::NotOwner));
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::AddHook {
+            addr: hook.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    // Nothing has vested yet.
+    assert_eq!(voting_power(&app, BOB, None), Uint128::zero());
+    assert_eq!(total_power(&app, None), Uint128::zero());
+
+    // Half of the vest vests, and is counted once checkpointed.
+    // Messages that do not change the vest's balance do not record it.
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(604800 / 2);
+        block.height += 1;
+    });
+    app.execute_contract(
+        bob.clone(),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::SetDistributionInterval { interval: Some(60) },
+        &[],
+    )
+    .unwrap();
+    app.update_block(|block| block.height += 1);
+    assert_eq!(voting_power(&app, BOB, None), Uint128::zero());
+    assert!(hook_msgs(&app).is_empty());
+    app.execute_contract(
+        Addr::unchecked("anyone"),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::Checkpoint {},
+        &[],
+    )
+    .unwrap();
+    let checkpointed = app.block_info().height;
+    app.update_block(|block| block.height += 1);
+
+    assert_eq!(voting_power(&app, BOB, None), Uint128::new(TOTAL_VEST / 2));
+    assert_eq!(voting_power(&app, ALICE, None), Uint128::zero());
+    assert_eq!(total_power(&app, None), Uint128::new(TOTAL_VEST / 2));
+    assert_eq!(
+        hook_msgs(&app),
+        vec![StakeChangedHookMsg::Stake {
+            addr: bob.clone(),
+            amount: Uint128::new(TOTAL_VEST / 2)
+        }]
+    );
+
+    // Claiming removes the claimed tokens' voting power.
+    app.execute_contract(
+        bob.clone(),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.update_block(|block| block.height += 1);
+
+    assert_eq!(voting_power(&app, BOB, None), Uint128::zero());
+    assert_eq!(total_power(&app, None), Uint128::zero());
+    assert_eq!(
+        voting_power(&app, BOB, Some(checkpointed + 1)),
+        Uint128::new(TOTAL_VEST / 2)
+    );
+    assert_eq!(
+        hook_msgs(&app)[1],
+        StakeChangedHookMsg::Unstake {
+            addr: bob,
+            amount: Uint128::new(TOTAL_VEST / 2)
+        }
+    );
+}
+
+#[test]
+fn test_voting_power_assigned_recipient() {
+    let mut app = setup_app();
+    let TestCase {
+        cw_vesting_addr, ..
+    } = setup_test_case(
+        &mut app,
+        InstantiateMsg {
+            recipient: None,
+            denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+            ..Default::default()
+        },
+        &coins(TOTAL_VEST, NATIVE_DENOM),
+    );
+
+    let voting_power = |app: &App, address: &str, height: Option<u64>| -> Uint128 {
+        let res: VotingPowerAtHeightResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &cw_vesting_addr,
+                &QueryMsg::VotingPowerAtHeight {
+                    address: address.to_string(),
+                    height,
+                },
+            )
+            .unwrap();
+        res.power
+    };
+
+    // Half of the vest vests before a recipient is assigned.
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(604800 / 2);
+        block.height += 1;
+    });
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::AssignRecipient {
+            recipient: BOB.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    let assigned = app.block_info().height;
+    app.update_block(|block| block.height += 1);
+
+    // The recipient only has voting power from their assignment.
+    assert_eq!(voting_power(&app, BOB, Some(assigned)), Uint128::zero());
+    assert_eq!(
+        voting_power(&app, BOB, Some(assigned + 1)),
+        Uint128::new(TOTAL_VEST / 2)
+    );
+    assert_eq!(voting_power(&app, OWNER, None), Uint128::zero());
+}
+
+#[test]
+fn test_voting_module() {
+    let mut app = setup_app();
+    let hook_id = app.store_code(mock_hook_contract());
+    let voting_module = app
+        .instantiate_contract(
+            hook_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "voting module",
+            None,
+        )
+        .unwrap();
+
+    let TestCase {
+        cw_vesting_addr,
+        recipient: bob,
+        ..
+    } = setup_test_case(
+        &mut app,
+        InstantiateMsg {
+            denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+            voting_module: Some(voting_module.to_string()),
+            clawback: None,
+            ..Default::default()
+        },
+        &coins(TOTAL_VEST, NATIVE_DENOM),
+    );
+
+    let power = |app: &App, msg: QueryMsg| -> Uint128 {
+        let res: VotingPowerAtHeightResponse =
+            app.wrap().query_wasm_smart(&cw_vesting_addr, &msg).unwrap();
+        res.power
+    };
+    let held_power = |app: &App| {
+        power(
+            app,
+            QueryMsg::HeldPowerAtHeight {
+                address: BOB.to_string(),
+                height: None,
+            },
+        )
+    };
+    let voting_power = |app: &App| {
+        power(
+            app,
+            QueryMsg::VotingPowerAtHeight {
+                address: BOB.to_string(),
+                height: None,
+            },
+        )
+    };
+    let hook_msgs = |app: &App| -> Vec<StakeChangedHookMsg> {
+        app.wrap()
+            .query_wasm_smart(&voting_module, &Empty {})
+            .unwrap()
+    };
+
+    let configured: Option<Addr> = app
+        .wrap()
+        .query_wasm_smart(&cw_vesting_addr, &QueryMsg::VotingModule {})
+        .unwrap();
+    assert_eq!(configured, Some(voting_module.clone()));
+
+    // The voting module is told about the unvested tokens from the
+    // start, without being registered as a voting power hook.
+    let hooks: cw_hooks::HooksResponse = app
+        .wrap()
+        .query_wasm_smart(&cw_vesting_addr, &QueryMsg::Hooks {})
+        .unwrap();
+    assert!(hooks.hooks.is_empty());
+    app.update_block(|block| block.height += 1);
+    assert_eq!(held_power(&app), Uint128::new(TOTAL_VEST));
+    assert_eq!(voting_power(&app), Uint128::zero());
+    assert_eq!(
+        hook_msgs(&app),
+        vec![StakeChangedHookMsg::Stake {
+            addr: bob.clone(),
+            amount: Uint128::new(TOTAL_VEST)
+        }]
+    );
+
+    // Vesting does not change the recipient's held power, only their
+    // voting power.
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(604800 / 2);
+        block.height += 1;
+    });
+    app.execute_contract(
+        Addr::unchecked("anyone"),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::Checkpoint {},
+        &[],
+    )
+    .unwrap();
+    app.update_block(|block| block.height += 1);
+    assert_eq!(held_power(&app), Uint128::new(TOTAL_VEST));
+    assert_eq!(voting_power(&app), Uint128::new(TOTAL_VEST / 2));
+    assert_eq!(hook_msgs(&app).len(), 1);
+
+    // Claiming removes the claimed tokens from both.
+    app.execute_contract(
+        bob.clone(),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::Distribute {
+            amount: None,
+            memo: None,
+            denom: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.update_block(|block| block.height += 1);
+    assert_eq!(held_power(&app), Uint128::new(TOTAL_VEST / 2));
+    assert_eq!(voting_power(&app), Uint128::zero());
+    assert_eq!(
+        hook_msgs(&app)[1],
+        StakeChangedHookMsg::Unstake {
+            addr: bob,
+            amount: Uint128::new(TOTAL_VEST / 2)
+        }
+    );
+}
+
+#[test]
+fn test_additional_denoms() {
+    let mut app = setup_app();
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: OWNER.to_string(),
+        amount: coins(INITIAL_BALANCE, "uatom"),
+    }))
+    .unwrap();
+
+    let atom_vest = AdditionalVest {
+        denom: UncheckedDenom::Native("uatom".to_string()),
+        total: Uint128::new(TOTAL_VEST / 2),
+        schedule: Schedule::SaturatingLinear,
+        vesting_duration_seconds: 604800 / 2,
+    };
+    let msg = InstantiateMsg {
+        denom: UncheckedDenom::Native(NATIVE_DENOM.to_string()),
+        additional_vests: vec![
+            atom_vest.clone(),
+            AdditionalVest {
+                denom: UncheckedDenom::Cw20("contract0".to_string()),
+                total: Uint128::new(TOTAL_VEST * 2),
+                schedule: Schedule::SaturatingLinear,
+                vesting_duration_seconds: 604800 * 2,
+            },
+        ],
+        ..Default::default()
+    };
+    let funds = vec![
+        Coin::new(TOTAL_VEST / 2, "uatom"),
+        Coin::new(TOTAL_VEST, NATIVE_DENOM),
+    ];
+    let TestCase {
+        cw20_addr,
+        cw_vesting_addr,
+        recipient: bob,
+        ..
+    } = setup_test_case(&mut app, msg.clone(), &funds);
+
+    // Each denom may only be vested once.
+    let code_id = app.store_code(cw_vesting_contract());
+    let err: ContractError = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                additional_vests: vec![atom_vest.clone(), atom_vest.clone()],
+                ..msg.clone()
+            },
+            &funds,
+            "cw-vesting",
+            None,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::DuplicateDenom {
+            denom: "uatom".to_string()
+        }
+    );
+
+    // Native additional denoms must be funded on instantiation.
+    let err: ContractError = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &msg,
+            &coins(TOTAL_VEST, NATIVE_DENOM),
+            "cw-vesting",
+            None,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::WrongFundAmount {
+            sent: Uint128::zero(),
+            expected: Uint128::new(TOTAL_VEST / 2)
+        }
+    );
+
+    let denoms: Vec<CheckedDenom> = app
+        .wrap()
+        .query_wasm_smart(&cw_vesting_addr, &QueryMsg::Denoms {})
+        .unwrap();
+    assert_eq!(
+        denoms,
+        vec![
+            CheckedDenom::Native(NATIVE_DENOM.to_string()),
+            CheckedDenom::Native("uatom".to_string()),
+            CheckedDenom::Cw20(cw20_addr.clone()),
+        ]
+    );
+
+    // The cw20 payment is funded separately.
+    let summary = |app: &App, denom: UncheckedDenom| -> Summary {
+        app.wrap()
+            .query_wasm_smart(&cw_vesting_addr, &QueryMsg::DenomSummary { denom, t: None })
+            .unwrap()
+    };
+    let cw20_denom = UncheckedDenom::Cw20(cw20_addr.to_string());
+    assert_eq!(summary(&app, cw20_denom.clone()).status, Status::Unfunded);
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        cw20_addr.clone(),
+        &Cw20ExecuteMsg::Send {
+            contract: cw_vesting_addr.to_string(),
+            amount: Uint128::new(TOTAL_VEST * 2),
+            msg: to_json_binary(&ReceiveMsg::Fund {}).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(summary(&app, cw20_denom.clone()).status, Status::Funded);
+
+    // Each denom vests on its own schedule. Halfway through the
+    // primary vest, all of the atom and a quarter of the cw20 tokens
+    // have vested.
+    app.update_block(|block| block.time = block.time.plus_seconds(604800 / 2));
+    let atom_denom = UncheckedDenom::Native("uatom".to_string());
+    assert_eq!(
+        summary(&app, atom_denom.clone()).distributable,
+        Uint128::new(TOTAL_VEST / 2)
+    );
+    for denom in [atom_denom.clone(), cw20_denom.clone()] {
+        app.execute_contract(
+            bob.clone(),
+            cw_vesting_addr.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: Some(denom),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        get_balance_native(&app, BOB, "uatom"),
+        Uint128::new(TOTAL_VEST / 2)
+    );
+    assert_eq!(
+        get_balance_cw20(&app, &cw20_addr, BOB),
+        Uint128::new(INITIAL_BALANCE + TOTAL_VEST / 2)
+    );
+    assert_eq!(
+        summary(&app, cw20_denom.clone()).claimed,
+        Uint128::new(TOTAL_VEST / 2)
+    );
+
+    let err: ContractError = app
+        .execute_contract(
+            bob.clone(),
+            cw_vesting_addr.clone(),
+            &ExecuteMsg::Distribute {
+                amount: None,
+                memo: None,
+                denom: Some(UncheckedDenom::Native("uosmo".to_string())),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::UnknownDenom {
+            denom: "uosmo".to_string()
+        }
+    );
+
+    // Multi-denom vests may not be transferred.
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            cw_vesting_addr.clone(),
+            &ExecuteMsg::ApproveTransfer { code_id },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Untransferable);
+
+    // Canceling settles every denom.
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        cw_vesting_addr.clone(),
+        &ExecuteMsg::Cancel {
+            require_consistent_balances: None,
+            unbond: None,
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        get_balance_native(&app, BOB, NATIVE_DENOM),
+        Uint128::new(INITIAL_BALANCE + TOTAL_VEST / 2)
+    );
+    assert_eq!(
+        get_balance_native(&app, OWNER, NATIVE_DENOM),
+        Uint128::new(INITIAL_BALANCE - TOTAL_VEST / 2)
+    );
+    assert_eq!(
+        get_balance_cw20(&app, &cw20_addr, OWNER),
+        Uint128::new(INITIAL_BALANCE - TOTAL_VEST / 2)
+    );
+    assert!(matches!(
+        summary(&app, cw20_denom).status,
+        Status::Canceled { .. }
+    ));
+    assert_eq!(
+        get_balance_native(&app, &cw_vesting_addr, "uatom"),
+        Uint128::zero()
+    );
 }
@@ -3,18 +3,33 @@ use std::cmp::min;
 use cosmwasm_schema::cw_serde;
 #[cfg(feature = "staking")]
 use cosmwasm_std::DistributionMsg;
-use cosmwasm_std::{Addr, Binary, CosmosMsg, StdResult, Storage, Timestamp, Uint128, Uint64};
+use cosmwasm_std::{
+    Addr, Binary, CosmosMsg, Decimal, Order, QuerierWrapper, StdResult, Storage, Timestamp,
+    Uint128, Uint64,
+};
 use cw_denom::CheckedDenom;
-use cw_storage_plus::Item;
-use wynd_utils::{Curve, PiecewiseLinear, SaturatingLinear};
+use cw_ownable::OwnershipError;
+use cw_schedule::{get_curve_bounds, get_curve_points, Curve};
+use cw_storage_plus::{Item, Map};
 
 use cw_stake_tracker::{StakeTracker, StakeTrackerQuery};
 
 use crate::error::ContractError;
+use crate::msg::{
+    ApprovedUnitsResponse, PriceOracleQueryMsg, PriceResponse, TimesheetConfig, TimesheetQueryMsg,
+};
+
+/// The maximum number of periods of a value targeted or timesheet
+/// vest that are indexed at once.
+pub const MAX_PERIODS_PER_INDEX: u64 = 100;
 
 pub struct Payment<'a> {
     vesting: Item<'a, Vest>,
     staking: StakeTracker<'a>,
+    /// unbonding(v, t) := the number of tokens that will finish
+    /// unbonding from validator v at time t (in seconds). Entries are
+    /// removed once they have matured.
+    unbonding: Map<'a, (String, u64), Uint128>,
 }
 
 #[cw_serde]
@@ -24,7 +39,9 @@ pub struct Vest {
     start_time: Timestamp,
 
     pub status: Status,
-    pub recipient: Addr,
+    /// The vest receiver, or `None` if the owner has not yet assigned
+    /// one. Funds are locked until a recipient is assigned.
+    pub recipient: Option<Addr>,
     pub denom: CheckedDenom,
 
     /// The number of tokens that have been claimed by the vest receiver.
@@ -36,6 +53,62 @@ pub struct Vest {
 
     pub title: String,
     pub description: Option<String>,
+
+    /// If set, the vest targets a fixed value per period and only
+    /// the amount vested by periods that have been indexed has
+    /// vested. See `Schedule::ValueTargeted`.
+    #[serde(default)]
+    pub value_index: Option<ValueIndex>,
+
+    /// If set, the amount vested each period is scaled by the units
+    /// of work approved by a timesheet oracle, and only the amount
+    /// vested by periods that have been indexed has vested.
+    #[serde(default)]
+    pub timesheet: Option<TimesheetIndex>,
+
+    /// The portion of the unvested tokens that vests immediately if
+    /// the vest is canceled, paid to the vest receiver as severance.
+    #[serde(default)]
+    pub severance: Decimal,
+
+    /// A short label identifying the payment, for example in an
+    /// address book of vesting contracts.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Arbitrary metadata about the payment, for example a JSON
+    /// object used by a payroll dashboard.
+    #[serde(default)]
+    pub metadata: Option<String>,
+}
+
+/// The progress of a value targeted vest.
+#[cw_serde]
+pub struct ValueIndex {
+    /// The price oracle queried for the price at the end of each
+    /// period.
+    pub oracle: Addr,
+    pub period_seconds: u64,
+    pub value_per_period: Uint128,
+    pub min_per_period: Uint128,
+    pub max_per_period: Uint128,
+    /// The number of periods whose vested amount has been computed.
+    pub periods_indexed: u64,
+    /// The number of tokens vested by the indexed periods.
+    pub indexed: Uint128,
+}
+
+/// The progress of a vest scaled by a timesheet oracle.
+#[cw_serde]
+pub struct TimesheetIndex {
+    /// The timesheet oracle queried for the units approved each
+    /// period.
+    pub oracle: Addr,
+    pub period_seconds: u64,
+    pub units_per_period: Uint128,
+    /// The number of periods whose vested amount has been computed.
+    pub periods_indexed: u64,
+    /// The number of tokens vested by the indexed periods.
+    pub indexed: Uint128,
 }
 
 #[cw_serde]
@@ -50,6 +123,122 @@ pub enum Status {
     },
 }
 
+/// An undelegation that has not yet completed.
+#[cw_serde]
+pub struct PendingUnbond {
+    pub validator: String,
+    pub amount: Uint128,
+    pub completes_at: Timestamp,
+}
+
+/// Tracks the progress of a cancelation. Canceling a vest with
+/// delegated tokens requires those tokens be undelegated and their
+/// unbonding period elapse before the owner and vestee may be fully
+/// settled.
+#[cw_serde]
+pub enum CancellationStatus {
+    /// The vest has not been canceled.
+    NotCanceled,
+    /// The vest has been canceled, but some tokens are still bonded
+    /// or unbonding. `bonded` tokens must be undelegated before the
+    /// cancelation can complete.
+    ///
+    /// Note that slashes which occur during unbonding are not
+    /// reflected in `pending` amounts.
+    Unbonding {
+        bonded: Uint128,
+        pending: Vec<PendingUnbond>,
+    },
+    /// All tokens have unbonded. `CompleteCancellation` may be called
+    /// to settle the owner and vestee.
+    Matured,
+    /// The owner and vestee have received all the tokens they are
+    /// entitled to.
+    Completed,
+}
+
+/// The state of a payment at a point in time, so that dashboards
+/// may display it with a single query.
+#[cw_serde]
+pub struct Summary {
+    pub title: String,
+    pub description: Option<String>,
+    pub label: Option<String>,
+    pub metadata: Option<String>,
+    pub recipient: Option<Addr>,
+    pub denom: CheckedDenom,
+    pub status: Status,
+    pub schedule: ScheduleSummary,
+
+    /// The total number of tokens that will vest.
+    pub total: Uint128,
+    pub vested: Uint128,
+    pub claimed: Uint128,
+    pub slashed: Uint128,
+    /// The number of tokens that may currently be distributed.
+    pub distributable: Uint128,
+    /// The number of tokens staked, including those that are
+    /// unbonding.
+    pub staked: Uint128,
+    /// Undelegations that have not yet completed.
+    pub pending_unbonds: Vec<PendingUnbond>,
+
+    /// The payment's guardian, if any.
+    pub guardian: Option<Addr>,
+    /// If true, the guardian or the owner has paused distributions
+    /// and delegation.
+    pub paused: bool,
+}
+
+/// The number of tokens the contract's accounting expects it to hold
+/// compared with the number it actually holds.
+#[cw_serde]
+pub struct BalanceCheck {
+    pub expected: Uint128,
+    pub actual: Uint128,
+}
+
+impl BalanceCheck {
+    /// True if the contract holds at least as many tokens as
+    /// expected.
+    pub fn is_covered(&self) -> bool {
+        self.actual >= self.expected
+    }
+}
+
+#[cw_serde]
+pub struct VerifyBalancesResponse {
+    /// Unstaked tokens, including those in the matching pool if it is
+    /// of the vested denom. Unbonding tokens become liquid once their
+    /// unbonding completes.
+    pub liquid: BalanceCheck,
+    /// Tokens delegated to validators, excluding those that are
+    /// unbonding. `None` if the vested token can not be staked.
+    pub delegated: Option<BalanceCheck>,
+    /// True if no balance is less than expected. Tokens sent to the
+    /// contract directly are a surplus, and do not make balances
+    /// inconsistent.
+    pub consistent: bool,
+}
+
+#[cw_serde]
+pub struct ScheduleSummary {
+    pub kind: ScheduleKind,
+    pub start_time: Timestamp,
+    /// When the last token vests, or `None` if the vest has been
+    /// canceled.
+    pub end_time: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub enum ScheduleKind {
+    SaturatingLinear,
+    PiecewiseLinear,
+    ValueTargeted,
+    /// The vest has been canceled, and no more tokens will vest.
+    Constant,
+}
+
 #[cw_serde]
 pub enum Schedule {
     /// Vests linearally from `0` to `total`.
@@ -67,6 +256,32 @@ pub enum Schedule {
     ///
     /// <https://github.com/cosmorama/wynddao/pull/4>
     PiecewiseLinear(Vec<(u64, Uint128)>),
+    /// Vests nothing until `cliff_seconds` after the vest start time,
+    /// at which point the amount that would have vested linearally
+    /// vests at once. Vests linearally to `total` after that.
+    /// `cliff_seconds` must be non-zero and less than the vest's
+    /// duration.
+    Cliff { cliff_seconds: u64 },
+    /// Experimental. Vests `value_per_period / price` tokens at the
+    /// end of each `period_seconds` long period, where `price` is the
+    /// price of the vested token at the end of the period according
+    /// to `oracle`, so that the recipient is paid a stable value in
+    /// the vested token. The amount vested each period is bounded by
+    /// `min_per_period` and `max_per_period`, and the total amount
+    /// vested never exceeds the total.
+    ///
+    /// Periods are indexed when the payment is distributed or
+    /// canceled. Tokens that have not vested once every period has
+    /// passed may be returned to the owner by canceling.
+    ValueTargeted {
+        /// The address of a contract implementing
+        /// `PriceOracleQueryMsg`.
+        oracle: String,
+        period_seconds: u64,
+        value_per_period: Uint128,
+        min_per_period: Uint128,
+        max_per_period: Uint128,
+    },
 }
 
 pub struct VestInit {
@@ -75,9 +290,13 @@ pub struct VestInit {
     pub start_time: Timestamp,
     pub duration_seconds: u64,
     pub denom: CheckedDenom,
-    pub recipient: Addr,
+    pub recipient: Option<Addr>,
     pub title: String,
     pub description: Option<String>,
+    pub severance: Decimal,
+    pub label: Option<String>,
+    pub metadata: Option<String>,
+    pub timesheet: Option<TimesheetConfig>,
 }
 
 impl<'a> Payment<'a> {
@@ -86,10 +305,12 @@ impl<'a> Payment<'a> {
         staked_prefix: &'a str,
         validator_prefix: &'a str,
         cardinality_prefix: &'a str,
+        unbonding_prefix: &'a str,
     ) -> Self {
         Self {
             vesting: Item::new(vesting_prefix),
             staking: StakeTracker::new(staked_prefix, validator_prefix, cardinality_prefix),
+            unbonding: Map::new(unbonding_prefix),
         }
     }
 
@@ -160,6 +381,9 @@ impl<'a> Payment<'a> {
         vesting: &Vest,
         t: Timestamp,
     ) -> StdResult<Uint128> {
+        if vesting.recipient.is_none() {
+            return Ok(Uint128::zero());
+        }
         let staked = self.staking.total_staked(storage, t)?;
 
         let liquid = self.liquid(vesting, staked);
@@ -178,6 +402,7 @@ impl<'a> Payment<'a> {
         request: Option<Uint128>,
     ) -> Result<CosmosMsg, ContractError> {
         let vesting = self.vesting.load(storage)?;
+        let recipient = vesting.recipient.clone().ok_or(ContractError::Unassigned)?;
 
         let distributable = self.distributable(storage, &vesting, t)?;
         let request = request.unwrap_or(distributable);
@@ -192,12 +417,26 @@ impl<'a> Payment<'a> {
                 claimable: distributable,
             })
         } else {
-            Ok(vesting
-                .denom
-                .get_transfer_to_message(&vesting.recipient, request)?)
+            Ok(vesting.denom.get_transfer_to_message(&recipient, request)?)
         }
     }
 
+    /// Indexes the periods of a value targeted or timesheet vest that
+    /// have ended by `t`. See `Vest::index_value`.
+    pub fn index_value(
+        &self,
+        storage: &mut dyn Storage,
+        querier: QuerierWrapper,
+        t: Timestamp,
+    ) -> StdResult<u64> {
+        let mut vest = self.vesting.load(storage)?;
+        let indexed = vest.index_value(querier, t);
+        if indexed != 0 {
+            self.vesting.save(storage, &vest)?;
+        }
+        Ok(indexed)
+    }
+
     /// Cancels the vesting payment. The current amount vested becomes
     /// the total amount that will ever vest, and all staked tokens
     /// are unbonded. note that canceling does not impact already
@@ -205,16 +444,44 @@ impl<'a> Payment<'a> {
     ///
     /// Upon canceling, the contract will use any liquid tokens in the
     /// contract to settle pending payments to the vestee, and then
-    /// return the rest to the owner. If there are not enough liquid
+    /// return the rest to `clawback`. If there are not enough liquid
     /// tokens to settle the vestee immediately, the vestee may
     /// distribute tokens as normal until they have received the
-    /// amount of tokens they are entitled to. The owner may withdraw
-    /// the remaining tokens via the `withdraw_canceled` method.
+    /// amount of tokens they are entitled to. The remaining tokens
+    /// may be withdrawn to `clawback` via the `withdraw_canceled`
+    /// method.
     pub fn cancel(
         &self,
         storage: &mut dyn Storage,
         t: Timestamp,
-        owner: &Addr,
+        clawback: &Addr,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        let settle = self.cancel_unstaked(storage, t, clawback)?;
+
+        // As the vest is cancelled, the veste is no longer
+        // entitled to staking rewards that may accure before the
+        // owner has a chance to undelegate from validators. Set
+        // the clawback address to the reward receiver.
+        let mut msgs = vec![
+            #[cfg(feature = "staking")]
+            DistributionMsg::SetWithdrawAddress {
+                address: clawback.to_string(),
+            }
+            .into(),
+        ];
+        msgs.extend(settle);
+
+        Ok(msgs)
+    }
+
+    /// Cancels the vesting payment like `cancel`, without redirecting
+    /// staking rewards to `clawback`. Used for payments that are
+    /// never staked.
+    pub fn cancel_unstaked(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        clawback: &Addr,
     ) -> Result<Vec<CosmosMsg>, ContractError> {
         let mut vesting = self.vesting.load(storage)?;
         if matches!(vesting.status, Status::Canceled { .. }) {
@@ -222,10 +489,21 @@ impl<'a> Payment<'a> {
         } else {
             let staked = self.staking.total_staked(storage, t)?;
 
+            // An unassigned vest has no vestee to settle, so nothing
+            // will ever vest. Otherwise, the vestee's severance vests
+            // immediately, and is settled like any other vested
+            // tokens.
+            let vested = if vesting.recipient.is_some() {
+                let vested = vesting.vested(t);
+                vested + (vesting.total() - vested).mul_floor(vesting.severance)
+            } else {
+                Uint128::zero()
+            };
+
             // Use liquid tokens to settle vestee as much as possible
             // and return any remaining liquid funds to the owner.
             let liquid = self.liquid(&vesting, staked);
-            let claimable = (vesting.vested(t) - vesting.claimed).saturating_sub(vesting.slashed);
+            let claimable = (vested - vesting.claimed).saturating_sub(vesting.slashed);
             let to_vestee = min(claimable, liquid);
             let to_owner = liquid - to_vestee;
 
@@ -236,44 +514,70 @@ impl<'a> Payment<'a> {
             // staked tokens that are not needed to settle the
             // vestee.
             let owner_outstanding =
-                staked - (vesting.vested(t) - vesting.claimed).saturating_sub(vesting.slashed);
+                staked - (vested - vesting.claimed).saturating_sub(vesting.slashed);
 
-            vesting.cancel(t, owner_outstanding);
+            vesting.cancel(vested, owner_outstanding);
             self.vesting.save(storage, &vesting)?;
 
-            // As the vest is cancelled, the veste is no longer
-            // entitled to staking rewards that may accure before the
-            // owner has a chance to undelegate from validators. Set
-            // the owner to the reward receiver.
-            let mut msgs = vec![
-                #[cfg(feature = "staking")]
-                DistributionMsg::SetWithdrawAddress {
-                    address: owner.to_string(),
-                }
-                .into(),
-            ];
-
+            let mut msgs = vec![];
             if !to_owner.is_zero() {
-                msgs.push(vesting.denom.get_transfer_to_message(owner, to_owner)?);
+                msgs.push(vesting.denom.get_transfer_to_message(clawback, to_owner)?);
             }
             if !to_vestee.is_zero() {
-                msgs.push(
-                    vesting
-                        .denom
-                        .get_transfer_to_message(&vesting.recipient, to_vestee)?,
-                );
+                // Unassigned vests never have anything to settle.
+                if let Some(recipient) = &vesting.recipient {
+                    msgs.push(
+                        vesting
+                            .denom
+                            .get_transfer_to_message(recipient, to_vestee)?,
+                    );
+                }
             }
 
             Ok(msgs)
         }
     }
 
+    /// Zeroes the vest so that it may be continued by a new vesting
+    /// contract, returning the vest as it was and the `(seconds,
+    /// vested)` points of the schedule that remains at `t`. See
+    /// `Vest::remaining_steps`. Afterwards, the vest is canceled with
+    /// everything it vested claimed, so nothing more may be
+    /// distributed or withdrawn.
+    ///
+    /// Only funded vests with a recipient and no staked, unbonding,
+    /// or slashed tokens may be transferred, so that every unclaimed
+    /// token is liquid.
+    pub fn transfer(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+    ) -> Result<(Vest, Vec<(u64, Uint128)>), ContractError> {
+        let mut vesting = self.vesting.load(storage)?;
+        if vesting.status != Status::Funded
+            || vesting.recipient.is_none()
+            || !vesting.slashed.is_zero()
+            || !self.staking.total_staked(storage, t)?.is_zero()
+        {
+            return Err(ContractError::Untransferable);
+        }
+        let steps = vesting
+            .remaining_steps(t)
+            .ok_or(ContractError::Untransferable)?;
+
+        let transferred = vesting.clone();
+        vesting.cancel(vesting.claimed, Uint128::zero());
+        self.vesting.save(storage, &vesting)?;
+
+        Ok((transferred, steps))
+    }
+
     pub fn withdraw_canceled_payment(
         &self,
         storage: &mut dyn Storage,
         t: Timestamp,
         request: Option<Uint128>,
-        owner: &Addr,
+        clawback: &Addr,
     ) -> Result<CosmosMsg, ContractError> {
         let vesting = self.vesting.load(storage)?;
         let staked = self.staking.total_staked(storage, t)?;
@@ -290,7 +594,7 @@ impl<'a> Payment<'a> {
                 };
                 self.vesting.save(storage, &vesting)?;
 
-                Ok(vesting.denom.get_transfer_to_message(owner, request)?)
+                Ok(vesting.denom.get_transfer_to_message(clawback, request)?)
             }
         } else {
             Err(ContractError::NotCancelled)
@@ -305,11 +609,140 @@ impl<'a> Payment<'a> {
         amount: Uint128,
         unbonding_duration_seconds: u64,
     ) -> Result<(), ContractError> {
-        self.staking
-            .on_undelegate(storage, t, validator, amount, unbonding_duration_seconds)?;
+        self.staking.on_undelegate(
+            storage,
+            t,
+            validator.clone(),
+            amount,
+            unbonding_duration_seconds,
+        )?;
+        self.prune_unbonds(storage, t)?;
+        self.unbonding.update(
+            storage,
+            (validator, t.seconds() + unbonding_duration_seconds),
+            |pending| -> StdResult<_> { Ok(pending.unwrap_or_default() + amount) },
+        )?;
+        Ok(())
+    }
+
+    /// Gets the undelegations that will not have completed by time
+    /// `t`.
+    pub fn pending_unbonds(
+        &self,
+        storage: &dyn Storage,
+        t: Timestamp,
+    ) -> StdResult<Vec<PendingUnbond>> {
+        self.unbonding
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref()
+                    .map_or(true, |((_, completes_at), _)| *completes_at > t.seconds())
+            })
+            .map(|item| {
+                item.map(|((validator, completes_at), amount)| PendingUnbond {
+                    validator,
+                    amount,
+                    completes_at: Timestamp::from_seconds(completes_at),
+                })
+            })
+            .collect()
+    }
+
+    /// Removes undelegations that have completed by time `t`.
+    fn prune_unbonds(&self, storage: &mut dyn Storage, t: Timestamp) -> StdResult<()> {
+        let matured = self
+            .unbonding
+            .keys(storage, None, None, Order::Ascending)
+            .filter(|key| {
+                key.as_ref()
+                    .map_or(true, |(_, completes_at)| *completes_at <= t.seconds())
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        for key in matured {
+            self.unbonding.remove(storage, key);
+        }
         Ok(())
     }
 
+    /// Gets the status of a cancelation at time `t`.
+    pub fn cancellation_status(
+        &self,
+        storage: &dyn Storage,
+        t: Timestamp,
+    ) -> StdResult<CancellationStatus> {
+        let vesting = self.vesting.load(storage)?;
+        let owner_withdrawable = match vesting.status {
+            Status::Canceled { owner_withdrawable } => owner_withdrawable,
+            _ => return Ok(CancellationStatus::NotCanceled),
+        };
+
+        let staked = self.staking.total_staked(storage, t)?;
+        if !staked.is_zero() {
+            let pending = self.pending_unbonds(storage, t)?;
+            let unbonding = pending
+                .iter()
+                .fold(Uint128::zero(), |sum, unbond| sum + unbond.amount);
+            return Ok(CancellationStatus::Unbonding {
+                bonded: staked.saturating_sub(unbonding),
+                pending,
+            });
+        }
+
+        if owner_withdrawable.is_zero() && self.distributable(storage, &vesting, t)?.is_zero() {
+            Ok(CancellationStatus::Completed)
+        } else {
+            Ok(CancellationStatus::Matured)
+        }
+    }
+
+    /// Settles a canceled vest once all of its tokens have
+    /// unbonded. The vestee receives the remainder of their vested
+    /// tokens, and `clawback` the remainder of the owner's.
+    pub fn complete_cancellation(
+        &self,
+        storage: &mut dyn Storage,
+        t: Timestamp,
+        clawback: Option<&Addr>,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        match self.cancellation_status(storage, t)? {
+            CancellationStatus::NotCanceled => Err(ContractError::NotCancelled),
+            CancellationStatus::Completed => Err(ContractError::CancellationCompleted),
+            CancellationStatus::Unbonding { bonded, pending } => {
+                if !bonded.is_zero() {
+                    Err(ContractError::StillBonded { bonded })
+                } else {
+                    Err(ContractError::UnbondingNotMatured {
+                        completes_at: pending
+                            .into_iter()
+                            .map(|unbond| unbond.completes_at)
+                            .max()
+                            .unwrap_or(t),
+                    })
+                }
+            }
+            CancellationStatus::Matured => {
+                self.prune_unbonds(storage, t)?;
+
+                let mut msgs = vec![];
+
+                let vesting = self.vesting.load(storage)?;
+                if !self.distributable(storage, &vesting, t)?.is_zero() {
+                    msgs.push(self.distribute(storage, t, None)?);
+                }
+
+                if let Status::Canceled { owner_withdrawable } = self.vesting.load(storage)?.status
+                {
+                    if !owner_withdrawable.is_zero() {
+                        let clawback = clawback.ok_or(OwnershipError::NoOwner)?;
+                        msgs.push(self.withdraw_canceled_payment(storage, t, None, clawback)?);
+                    }
+                }
+
+                Ok(msgs)
+            }
+        }
+    }
+
     pub fn on_redelegate(
         &self,
         storage: &mut dyn Storage,
@@ -333,6 +766,29 @@ impl<'a> Payment<'a> {
         Ok(())
     }
 
+    /// Assigns the vest receiver. If `start_time` is set, vesting
+    /// restarts from that time.
+    pub fn assign_recipient(
+        &self,
+        storage: &mut dyn Storage,
+        recipient: Addr,
+        start_time: Option<Timestamp>,
+    ) -> Result<Vest, ContractError> {
+        let mut v = self.vesting.load(storage)?;
+        if matches!(v.status, Status::Canceled { .. }) {
+            return Err(ContractError::Cancelled);
+        }
+        if v.recipient.is_some() {
+            return Err(ContractError::RecipientAssigned);
+        }
+        v.recipient = Some(recipient);
+        if let Some(start_time) = start_time {
+            v.start_time = start_time;
+        }
+        self.vesting.save(storage, &v)?;
+        Ok(v)
+    }
+
     pub fn set_funded(&self, storage: &mut dyn Storage) -> Result<(), ContractError> {
         let mut v = self.vesting.load(storage)?;
         debug_assert!(v.status == Status::Unfunded);
@@ -398,6 +854,51 @@ impl<'a> Payment<'a> {
         self.staking.query(storage, q)
     }
 
+    /// Gets a summary of the payment at time `t`.
+    pub fn summary(&self, storage: &dyn Storage, t: Timestamp) -> StdResult<Summary> {
+        let vesting = self.vesting.load(storage)?;
+        Ok(Summary {
+            schedule: vesting.schedule_summary(),
+            total: vesting.total(),
+            vested: vesting.vested(t),
+            distributable: self.distributable(storage, &vesting, t)?,
+            staked: self.staking.total_staked(storage, t)?,
+            pending_unbonds: self.pending_unbonds(storage, t)?,
+            claimed: vesting.claimed,
+            slashed: vesting.slashed,
+            title: vesting.title,
+            description: vesting.description,
+            label: vesting.label,
+            metadata: vesting.metadata,
+            recipient: vesting.recipient,
+            denom: vesting.denom,
+            status: vesting.status,
+            // the guardian is the contract's, not the payment's, and
+            // is filled in by the contract.
+            guardian: None,
+            paused: false,
+        })
+    }
+
+    /// Gets the number of liquid and bonded tokens that the contract
+    /// should hold at time `t` according to the payment's accounting.
+    pub fn expected_balances(
+        &self,
+        storage: &dyn Storage,
+        t: Timestamp,
+    ) -> StdResult<(Uint128, Uint128)> {
+        let vesting = self.vesting.load(storage)?;
+        let staked = self.staking.total_staked(storage, t)?;
+        let unbonding = self
+            .pending_unbonds(storage, t)?
+            .iter()
+            .fold(Uint128::zero(), |sum, unbond| sum + unbond.amount);
+        Ok((
+            self.liquid(&vesting, staked),
+            staked.saturating_sub(unbonding),
+        ))
+    }
+
     /// Returns the duration of the vesting agreement (not the
     /// remaining time) in seconds, or `None` if the vest has been cancelled.
     pub fn duration(&self, storage: &dyn Storage) -> StdResult<Option<Uint64>> {
@@ -411,10 +912,68 @@ impl Vest {
             Err(ContractError::ZeroVest)
         } else if init.duration_seconds == 0 {
             Err(ContractError::Instavest)
+        } else if init.severance > Decimal::one() {
+            Err(ContractError::InvalidSeverance {
+                severance: init.severance,
+            })
         } else {
+            let value_index = match init.schedule {
+                Schedule::ValueTargeted {
+                    ref oracle,
+                    period_seconds,
+                    value_per_period,
+                    min_per_period,
+                    max_per_period,
+                } => {
+                    if period_seconds == 0
+                        || period_seconds > init.duration_seconds
+                        || min_per_period > max_per_period
+                        || max_per_period.is_zero()
+                    {
+                        return Err(ContractError::InvalidValueTarget);
+                    }
+                    Some(ValueIndex {
+                        oracle: Addr::unchecked(oracle),
+                        period_seconds,
+                        value_per_period,
+                        min_per_period,
+                        max_per_period,
+                        periods_indexed: 0,
+                        indexed: Uint128::zero(),
+                    })
+                }
+                _ => None,
+            };
+            let timesheet = match init.timesheet {
+                Some(_) if value_index.is_some() => {
+                    return Err(ContractError::TimesheetWithValueTarget)
+                }
+                Some(TimesheetConfig {
+                    oracle,
+                    period_seconds,
+                    units_per_period,
+                }) => {
+                    if period_seconds == 0
+                        || period_seconds > init.duration_seconds
+                        || units_per_period.is_zero()
+                    {
+                        return Err(ContractError::InvalidTimesheet);
+                    }
+                    Some(TimesheetIndex {
+                        oracle: Addr::unchecked(oracle),
+                        period_seconds,
+                        units_per_period,
+                        periods_indexed: 0,
+                        indexed: Uint128::zero(),
+                    })
+                }
+                None => None,
+            };
             Ok(Self {
                 claimed: Uint128::zero(),
                 slashed: Uint128::zero(),
+                value_index,
+                timesheet,
                 vested: init
                     .schedule
                     .into_curve(init.total, init.duration_seconds)?,
@@ -424,6 +983,9 @@ impl Vest {
                 status: Status::Unfunded,
                 title: init.title,
                 description: init.description,
+                severance: init.severance,
+                label: init.label,
+                metadata: init.metadata,
             })
         }
     }
@@ -436,66 +998,193 @@ impl Vest {
 
     /// Gets the number of tokens that have vested at `time`.
     pub fn vested(&self, t: Timestamp) -> Uint128 {
+        // value targeted and timesheet vests only vest as periods are
+        // indexed.
+        if let Some(ref index) = self.value_index {
+            return index.indexed;
+        }
+        if let Some(ref index) = self.timesheet {
+            return index.indexed;
+        }
         let elapsed = t.seconds().saturating_sub(self.start_time.seconds());
         self.vested.value(elapsed)
     }
 
-    /// Cancels the current vest. No additional tokens will vest after `t`.
-    pub fn cancel(&mut self, t: Timestamp, owner_withdrawable: Uint128) {
+    /// Computes the amount vested by each period of a value targeted
+    /// vest that has ended by `t` and has not yet been indexed, up to
+    /// `MAX_PERIODS_PER_INDEX` periods. Indexing stops at the first
+    /// period whose price can not be queried, so that a failing
+    /// oracle does not prevent already vested tokens from being
+    /// distributed. Returns the number of periods indexed.
+    ///
+    /// Timesheet vests are indexed by `index_timesheet` instead.
+    pub fn index_value(&mut self, querier: QuerierWrapper, t: Timestamp) -> u64 {
+        if self.timesheet.is_some() {
+            return self.index_timesheet(querier, t);
+        }
+        let total = self.total();
+        let duration = self.duration().map(|d| d.u64()).unwrap_or_default();
+        let Some(ref mut index) = self.value_index else {
+            return 0;
+        };
+        let elapsed = t.seconds().saturating_sub(self.start_time.seconds());
+        let ended = min(elapsed, duration) / index.period_seconds;
+        let start = index.periods_indexed;
+
+        while index.periods_indexed < ended && index.periods_indexed - start < MAX_PERIODS_PER_INDEX
+        {
+            let period = index.periods_indexed + 1;
+            let price: StdResult<PriceResponse> = querier.query_wasm_smart(
+                &index.oracle,
+                &PriceOracleQueryMsg::Price {
+                    denom: self.denom.clone(),
+                    time: self.start_time.plus_seconds(period * index.period_seconds),
+                },
+            );
+            let Ok(PriceResponse { price }) = price else {
+                break;
+            };
+            // a zero price, or one so low the amount overflows, vests
+            // the maximum.
+            let amount = index
+                .value_per_period
+                .checked_div_floor(price)
+                .unwrap_or(index.max_per_period)
+                .clamp(index.min_per_period, index.max_per_period);
+            index.indexed = index.indexed.saturating_add(amount).min(total);
+            index.periods_indexed = period;
+        }
+
+        index.periods_indexed - start
+    }
+
+    /// Computes the amount vested by each period of a timesheet vest
+    /// that has ended by `t` and has not yet been indexed, up to
+    /// `MAX_PERIODS_PER_INDEX` periods. Each period vests the amount
+    /// the schedule vests during it, scaled by the portion of
+    /// `units_per_period` approved by the oracle, so never more than
+    /// the schedule. The last period ends with the vest. Indexing
+    /// stops at the first period the oracle can not be queried for,
+    /// or if the vest has no recipient. Returns the number of periods
+    /// indexed.
+    fn index_timesheet(&mut self, querier: QuerierWrapper, t: Timestamp) -> u64 {
+        let duration = self.duration().map(|d| d.u64()).unwrap_or_default();
+        let Some(ref mut index) = self.timesheet else {
+            return 0;
+        };
+        let Some(ref recipient) = self.recipient else {
+            return 0;
+        };
+        let elapsed = t.seconds().saturating_sub(self.start_time.seconds());
+        let start = index.periods_indexed;
+
+        while index.periods_indexed - start < MAX_PERIODS_PER_INDEX {
+            let period_start = index.periods_indexed * index.period_seconds;
+            let period_end = min(period_start + index.period_seconds, duration);
+            if period_start >= duration || period_end > elapsed {
+                break;
+            }
+            let units: StdResult<ApprovedUnitsResponse> = querier.query_wasm_smart(
+                &index.oracle,
+                &TimesheetQueryMsg::ApprovedUnits {
+                    recipient: recipient.clone(),
+                    start: self.start_time.plus_seconds(period_start),
+                    end: self.start_time.plus_seconds(period_end),
+                },
+            );
+            let Ok(ApprovedUnitsResponse { units }) = units else {
+                break;
+            };
+            let scheduled = self.vested.value(period_end) - self.vested.value(period_start);
+            let amount = scheduled
+                .multiply_ratio(min(units, index.units_per_period), index.units_per_period);
+            index.indexed += amount;
+            index.periods_indexed += 1;
+        }
+
+        index.periods_indexed - start
+    }
+
+    /// The `(seconds, vested)` points of the schedule that remains
+    /// at `t`, less the tokens already claimed, or `None` if the vest
+    /// has finished or is value targeted, scaled by a timesheet, or
+    /// canceled. Tokens that have vested but have not been claimed
+    /// vest at `t`, and the rest vest as they would have.
+    pub fn remaining_steps(&self, t: Timestamp) -> Option<Vec<(u64, Uint128)>> {
+        if self.value_index.is_some() || self.timesheet.is_some() {
+            return None;
+        }
+        let steps = get_curve_points(&self.vested)?;
+        let elapsed = t.seconds().saturating_sub(self.start_time.seconds());
+        let unclaimed = self.vested(t) - self.claimed;
+
+        // curves start at zero, so tokens have only vested if
+        // `elapsed` is non-zero.
+        let mut remaining = vec![];
+        if !unclaimed.is_zero() {
+            remaining.push((elapsed - 1, Uint128::zero()));
+        }
+        remaining.push((elapsed, unclaimed));
+        remaining.extend(
+            steps
+                .into_iter()
+                .filter(|(x, _)| *x > elapsed)
+                .map(|(x, y)| (x, y - self.claimed)),
+        );
+
+        let (end, _) = remaining.last()?;
+        (*end > elapsed).then_some(remaining)
+    }
+
+    /// Cancels the current vest. No additional tokens will vest
+    /// beyond `vested`.
+    pub fn cancel(&mut self, vested: Uint128, owner_withdrawable: Uint128) {
         debug_assert!(!matches!(self.status, Status::Canceled { .. }));
 
         self.status = Status::Canceled { owner_withdrawable };
-        self.vested = Curve::Constant { y: self.vested(t) };
+        self.vested = Curve::Constant { y: vested };
+        self.value_index = None;
+        self.timesheet = None;
+    }
+
+    /// Summarizes the vesting schedule.
+    pub fn schedule_summary(&self) -> ScheduleSummary {
+        let kind = match &self.vested {
+            Curve::Constant { .. } => ScheduleKind::Constant,
+            Curve::SaturatingLinear(_) if self.value_index.is_some() => ScheduleKind::ValueTargeted,
+            Curve::SaturatingLinear(_) => ScheduleKind::SaturatingLinear,
+            Curve::PiecewiseLinear(_) => ScheduleKind::PiecewiseLinear,
+        };
+        let end = get_curve_bounds(&self.vested).map(|(_, end)| end);
+        ScheduleSummary {
+            kind,
+            start_time: self.start_time,
+            end_time: end.map(|end| self.start_time.plus_seconds(end)),
+        }
     }
 
     /// Gets the duration of the vest. For constant curves, `None` is
     /// returned.
     pub fn duration(&self) -> Option<Uint64> {
-        let (start, end) = match &self.vested {
-            Curve::Constant { .. } => return None,
-            Curve::SaturatingLinear(SaturatingLinear { min_x, max_x, .. }) => (*min_x, *max_x),
-            Curve::PiecewiseLinear(PiecewiseLinear { steps }) => {
-                (steps[0].0, steps[steps.len() - 1].0)
-            }
-        };
+        let (start, end) = get_curve_bounds(&self.vested)?;
         Some(Uint64::new(end - start))
     }
 }
 
 impl Schedule {
-    /// The vesting schedule tracks vested(t), so for a curve to be
-    /// valid:
-    ///
-    /// 1. it must start at 0,
-    /// 2. it must end at total,
-    /// 3. it must never decrease.
-    ///
-    /// Piecewise curves must have at least two steps. One step would
-    /// be a constant vest (why would you want this?).
-    ///
-    /// A schedule is valid if `total` is zero: nothing will ever be
-    /// paid out. Consumers should consider validating that `total` is
-    /// non-zero.
+    /// Gets the vesting curve, vested(t). See
+    /// `cw_schedule::Schedule::into_curve` for the requirements of a
+    /// valid curve.
     pub fn into_curve(self, total: Uint128, duration_seconds: u64) -> Result<Curve, ContractError> {
-        let c = match self {
-            Schedule::SaturatingLinear => {
-                Curve::saturating_linear((0, 0), (duration_seconds, total.u128()))
-            }
-            Schedule::PiecewiseLinear(steps) => {
-                if steps.len() < 2 {
-                    return Err(ContractError::ConstantVest);
-                }
-                Curve::PiecewiseLinear(wynd_utils::PiecewiseLinear { steps })
+        let schedule = match self {
+            // the amount vested by value targeted vests is tracked
+            // separately, and the curve only bounds it by the total.
+            Schedule::SaturatingLinear | Schedule::ValueTargeted { .. } => {
+                cw_schedule::Schedule::SaturatingLinear
             }
+            Schedule::PiecewiseLinear(steps) => cw_schedule::Schedule::PiecewiseLinear(steps),
+            Schedule::Cliff { cliff_seconds } => cw_schedule::Schedule::Cliff { cliff_seconds },
         };
-        c.validate_monotonic_increasing()?; // => max >= curve(t) \forall t
-        let range = c.range();
-        if range != (0, total.u128()) {
-            return Err(ContractError::VestRange {
-                min: Uint128::new(range.0),
-                max: Uint128::new(range.1),
-            });
-        }
-        Ok(c)
+        Ok(schedule.into_curve(total, duration_seconds)?)
     }
 }
@@ -1,6 +1,6 @@
-use cosmwasm_std::{testing::mock_dependencies, Addr, Timestamp, Uint128};
+use cosmwasm_std::{testing::mock_dependencies, Addr, Decimal, Timestamp, Uint128};
 use cw_denom::CheckedDenom;
-use wynd_utils::CurveError;
+use cw_schedule::CurveError;
 
 use crate::{
     error::ContractError,
@@ -16,9 +16,13 @@ impl Default for VestInit {
             start_time: Timestamp::from_seconds(0),
             duration_seconds: 100,
             denom: CheckedDenom::Native("native".to_string()),
-            recipient: Addr::unchecked("recv"),
+            recipient: Some(Addr::unchecked("recv")),
             title: "title".to_string(),
             description: Some("desc".to_string()),
+            severance: Decimal::zero(),
+            label: None,
+            metadata: None,
+            timesheet: None,
         }
     }
 }
@@ -26,7 +30,7 @@ impl Default for VestInit {
 #[test]
 fn test_distribute_funded() {
     let storage = &mut mock_dependencies().storage;
-    let payment = Payment::new("vesting", "staked", "validator", "cardinality");
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
 
     payment.initialize(storage, VestInit::default()).unwrap();
     payment.set_funded(storage).unwrap();
@@ -39,7 +43,7 @@ fn test_distribute_funded() {
 #[test]
 fn test_distribute_nothing_to_claim() {
     let storage = &mut mock_dependencies().storage;
-    let payment = Payment::new("vesting", "staked", "validator", "cardinality");
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
 
     payment.initialize(storage, VestInit::default()).unwrap();
 
@@ -61,7 +65,7 @@ fn test_distribute_nothing_to_claim() {
 #[test]
 fn test_distribute_half_way() {
     let storage = &mut mock_dependencies().storage;
-    let payment = Payment::new("vesting", "staked", "validator", "cardinality");
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
 
     payment.initialize(storage, VestInit::default()).unwrap();
 
@@ -86,7 +90,7 @@ fn test_distribute_half_way() {
 #[test]
 fn test_distribute() {
     let storage = &mut mock_dependencies().storage;
-    let payment = Payment::new("vesting", "staked", "validator", "cardinality");
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
 
     payment.initialize(storage, VestInit::default()).unwrap();
 
@@ -158,6 +162,52 @@ fn test_vesting_validation() {
             max: Uint128::new(2)
         })
     );
+
+    // Can not pay more than the unvested tokens as severance.
+    let init = VestInit {
+        severance: Decimal::percent(101),
+        ..Default::default()
+    };
+    assert_eq!(
+        Vest::new(init),
+        Err(ContractError::InvalidSeverance {
+            severance: Decimal::percent(101)
+        })
+    );
+}
+
+#[test]
+fn test_cliff_schedule() {
+    let init = VestInit {
+        schedule: Schedule::Cliff { cliff_seconds: 25 },
+        ..Default::default()
+    };
+    let vest = Vest::new(init).unwrap();
+
+    // nothing vests before the cliff, then what would have vested
+    // linearally vests at once.
+    assert_eq!(vest.vested(Timestamp::from_seconds(24)), Uint128::zero());
+    assert_eq!(
+        vest.vested(Timestamp::from_seconds(25)),
+        Uint128::new(25_000_000)
+    );
+    assert_eq!(
+        vest.vested(Timestamp::from_seconds(50)),
+        Uint128::new(50_000_000)
+    );
+    assert_eq!(
+        vest.vested(Timestamp::from_seconds(100)),
+        Uint128::new(100_000_000)
+    );
+
+    // the cliff must fall within the vest.
+    for cliff_seconds in [0, 100, 101] {
+        let init = VestInit {
+            schedule: Schedule::Cliff { cliff_seconds },
+            ..Default::default()
+        };
+        assert_eq!(Vest::new(init), Err(ContractError::InvalidCliff));
+    }
 }
 
 // owner and vestee. vestee has vested 50 tokens out of 100. 10 are
@@ -182,11 +232,15 @@ fn test_complex_close() {
         start_time: time,
         duration_seconds: 100,
         denom: CheckedDenom::Native("ujuno".to_string()),
-        recipient: Addr::unchecked("recv"),
+        recipient: Some(Addr::unchecked("recv")),
         title: "t".to_string(),
         description: Some("d".to_string()),
+        severance: Decimal::zero(),
+        label: None,
+        metadata: None,
+        timesheet: None,
     };
-    let payment = Payment::new("vesting", "staked", "validator", "cardinality");
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
 
     payment.initialize(storage, init).unwrap();
     payment.set_funded(storage).unwrap();
@@ -254,10 +308,72 @@ fn test_complex_close() {
     );
 }
 
+// same as `test_complex_close`, but the vestee is paid half of the
+// unvested tokens as severance. the vestee is now owed 75 tokens, 15
+// of which are settled from liquid tokens, and the owner 25.
+#[test]
+fn test_close_with_severance() {
+    let storage = &mut mock_dependencies().storage;
+    let mut time = Timestamp::default();
+
+    let init = VestInit {
+        total: Uint128::new(100),
+        start_time: time,
+        severance: Decimal::percent(50),
+        ..Default::default()
+    };
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
+
+    payment.initialize(storage, init).unwrap();
+    payment.set_funded(storage).unwrap();
+
+    time = time.plus_seconds(50);
+
+    payment
+        .distribute(storage, time, Some(Uint128::new(10)))
+        .unwrap();
+    payment
+        .on_delegate(storage, time, "v1".to_string(), Uint128::new(75))
+        .unwrap();
+
+    payment
+        .cancel(storage, time, &Addr::unchecked("owner"))
+        .unwrap();
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.total(), Uint128::new(75));
+    assert_eq!(vest.claimed, Uint128::new(25));
+    assert_eq!(
+        vest.status,
+        Status::Canceled {
+            owner_withdrawable: Uint128::new(25)
+        }
+    );
+
+    payment
+        .on_undelegate(storage, time, "v1".to_string(), Uint128::new(75), 25)
+        .unwrap();
+    time = time.plus_seconds(25);
+
+    payment.distribute(storage, time, None).unwrap();
+    payment
+        .withdraw_canceled_payment(storage, time, None, &Addr::unchecked("owner"))
+        .unwrap();
+
+    let vest = payment.get_vest(storage).unwrap();
+    assert_eq!(vest.claimed, Uint128::new(75));
+    assert_eq!(
+        vest.status,
+        Status::Canceled {
+            owner_withdrawable: Uint128::zero()
+        }
+    );
+}
+
 #[test]
 fn test_piecewise_linear() {
     let storage = &mut mock_dependencies().storage;
-    let payment = Payment::new("vesting", "staked", "validator", "cardinality");
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
 
     let vest = VestInit {
         schedule: Schedule::PiecewiseLinear(vec![
@@ -332,11 +448,15 @@ fn test_redelegate_should_increase_cardinality() {
         start_time: time,
         duration_seconds: 100,
         denom: CheckedDenom::Native("ujuno".to_string()),
-        recipient: Addr::unchecked("recv"),
+        recipient: Some(Addr::unchecked("recv")),
         title: "t".to_string(),
         description: Some("d".to_string()),
+        severance: Decimal::zero(),
+        label: None,
+        metadata: None,
+        timesheet: None,
     };
-    let payment = Payment::new("vesting", "staked", "validator", "cardinality");
+    let payment = Payment::new("vesting", "staked", "validator", "cardinality", "unbonding");
 
     payment.initialize(storage, init).unwrap();
     payment.set_funded(storage).unwrap();
@@ -1,12 +1,17 @@
+use std::cmp::{min, Ordering};
+use std::collections::HashSet;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response,
-    StdResult, SubMsg, Uint128, Uint256,
+    to_json_binary, to_json_vec, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env,
+    MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResult,
+    Timestamp, Uint128, Uint256, WasmMsg,
 };
 use cw2::{get_contract_version, set_contract_version, ContractVersion};
+use cw4::{MemberChangedHookMsg, MemberDiff};
 use cw_storage_plus::Bound;
-use cw_utils::Duration;
+use cw_utils::{Duration, Expiration};
 use dao_hooks::nft_stake::{stake_nft_hook_msgs, unstake_nft_hook_msgs};
 use dao_interface::voting::IsActiveResponse;
 use dao_voting::duration::validate_duration;
@@ -15,17 +20,46 @@ use dao_voting::threshold::{
     ActiveThresholdResponse,
 };
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, OnftCollection, QueryMsg};
+use crate::events::{
+    claim_events, hook_event, stake_events, unstake_events, unstake_queued_events,
+};
+use crate::msg::{
+    ClaimCallback, ClaimCallbackMsg, CollectionResponse, CollectionTokenIds, DelegationResponse,
+    ExecuteMsg, ExportedClaim, InstantiateMsg, MigrateMsg, NftClaimInfoResponse, OnftCollection,
+    QueryMsg, QueuedUnstakeResponse, ReferrerStatsResponse, StakedNftResponse, StakerClaims,
+    StorageEntry, TokenWeight, TokenWeightResponse, UnstakeBudgetResponse, UnstakingDurationPolicy,
+    VotingPowerProofResponse, WeightedRandomStakerResponse,
+};
 use crate::omniflix::{get_onft_transfer_msg, query_onft_owner, query_onft_supply};
 use crate::state::{
-    register_staked_nfts, register_unstaked_nfts, Config, ACTIVE_THRESHOLD, CONFIG, DAO, HOOKS,
-    MAX_CLAIMS, NFT_BALANCES, NFT_CLAIMS, PREPARED_ONFTS, STAKED_NFTS_PER_OWNER, TOTAL_STAKED_NFTS,
+    add_staker_weight, load_total_staked_nfts_at_height, nft_power, nfts_power, queue_unstakes,
+    register_referral, register_staked_nfts, register_unstaked_nfts, reweigh_staked_nft,
+    sample_staker, save_total_staked_nfts, set_delegate, set_token_weight, staker_weight,
+    sub_staker_weight, sync_unique_staker, unstake_queue_head, unstaking_duration, voting_power,
+    Callback, ClaimInfo, ClaimShortening, Config, QueuedUnstake, ACTIVE_THRESHOLD, CLAIM_CALLBACKS,
+    CLAIM_INFO, CLAIM_SHORTENING, COLLECTIONS, COLLECTION_UNSTAKING_DURATIONS, CONFIG,
+    CURRENT_STATE_VERSION, DAO, DELEGATED_AWAY, DELEGATED_POWER, DELEGATES, HOOKS, MAX_CLAIMS,
+    MEMBER_HOOKS, MIGRATION_CURSOR, NFT_BALANCES, NFT_CLAIMS, NFT_STAKERS, PREPARED_ONFTS,
+    QUEUED_UNSTAKES, REFERRAL_STATS, REFERRERS, STAKED_NFTS_PER_OWNER, STAKED_NFT_COUNT,
+    STATE_VERSION, TOKEN_WEIGHTS, TOKEN_WEIGHT_TOTALS, TOTAL_STAKED_NFTS, TOTAL_STAKED_NFTS_KEY,
+    UNIQUE_STAKER_COUNT, UNSTAKE_BUDGET, UNSTAKE_EPOCH, UNSTAKE_QUEUE, UNSTAKE_QUEUE_NEXT,
 };
 use crate::ContractError;
 
 pub(crate) const CONTRACT_NAME: &str = "crates.io:dao-voting-onft-staked";
 pub(crate) const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const CLAIM_CALLBACK_REPLY_ID: u64 = 0;
+
+/// The default number of queued unstakes processed at once.
+const DEFAULT_UNSTAKE_QUEUE_LIMIT: u32 = 30;
+
+/// The default number of stakers migrated per migration.
+const DEFAULT_MIGRATION_LIMIT: u32 = 30;
+
+/// The default number of stakers whose claims are shortened at once.
+const DEFAULT_SHORTEN_CLAIMS_LIMIT: u32 = 30;
+
 // We multiply by this when calculating needed power for being active
 // when using active threshold with percent
 const PRECISION_FACTOR: u128 = 10u128.pow(9);
@@ -35,14 +69,48 @@ pub fn instantiate(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    msg: InstantiateMsg,
+    mut msg: InstantiateMsg,
 ) -> Result<Response<Empty>, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    STATE_VERSION.save(deps.storage, &CURRENT_STATE_VERSION)?;
 
     DAO.save(deps.storage, &info.sender)?;
 
     // Validate unstaking duration
     validate_duration(msg.unstaking_duration)?;
+    validate_duration(msg.bootstrap_period)?;
+
+    if let Some(unstake_budget) = msg.unstake_budget {
+        if unstake_budget.max_percent.is_zero()
+            || unstake_budget.max_percent > Decimal::one()
+            || validate_duration(Some(unstake_budget.epoch)).is_err()
+        {
+            return Err(ContractError::InvalidUnstakeBudget {});
+        }
+        UNSTAKE_BUDGET.save(deps.storage, &unstake_budget)?;
+    }
+
+    if msg.onft_collections.is_empty() {
+        return Err(ContractError::NoCollections {});
+    }
+    for collection in &msg.onft_collections {
+        let OnftCollection::Existing { ref id } = collection.collection;
+        if collection.weight.is_zero() {
+            return Err(ContractError::ZeroWeight {
+                collection_id: id.clone(),
+            });
+        }
+        if COLLECTIONS.has(deps.storage, id) {
+            return Err(ContractError::DuplicateCollection {
+                collection_id: id.clone(),
+            });
+        }
+        COLLECTIONS.save(deps.storage, id, &collection.weight)?;
+        if let Some(duration) = collection.unstaking_duration {
+            validate_duration(Some(duration))?;
+            COLLECTION_UNSTAKING_DURATIONS.save(deps.storage, id, &duration)?;
+        }
+    }
 
     // Validate active threshold if configured
     if let Some(active_threshold) = msg.active_threshold.as_ref() {
@@ -51,35 +119,115 @@ pub fn instantiate(
                 assert_valid_percentage_threshold(*percent)?;
             }
             ActiveThreshold::AbsoluteCount { count } => {
-                // Check absolute count is less than the supply of NFTs for
-                // existing NFT collection.
-
-                let OnftCollection::Existing { ref id } = msg.onft_collection;
-                let nft_supply = query_onft_supply(deps.as_ref(), id)?;
-
-                // Check the absolute count is less than the supply of NFTs and
-                // greater than zero.
-                assert_valid_absolute_count_threshold(*count, Uint128::new(nft_supply.into()))?;
+                // Check the absolute count is less than the potential voting
+                // power and greater than zero.
+                let potential_power = query_potential_power(deps.as_ref())?;
+                assert_valid_absolute_count_threshold(*count, potential_power)?;
             }
         }
         ACTIVE_THRESHOLD.save(deps.storage, active_threshold)?;
     }
+    assert_valid_unique_stakers_threshold(msg.unique_stakers_threshold)?;
+
+    save_total_staked_nfts(deps.storage, env.block.height, Uint128::zero())?;
+    STAKED_NFT_COUNT.save(deps.storage, &0)?;
+    UNIQUE_STAKER_COUNT.save(deps.storage, &0)?;
+
+    let OnftCollection::Existing { id } = msg.onft_collections.swap_remove(0).collection;
+    let config = Config {
+        onft_collection_id: id.clone(),
+        unstaking_duration: msg.unstaking_duration,
+        unstaking_locked_until: msg.bootstrap_period.map(|period| period.after(&env.block)),
+        dissolved: false,
+        unique_stakers_threshold: msg.unique_stakers_threshold,
+    };
+    CONFIG.save(deps.storage, &config)?;
 
-    TOTAL_STAKED_NFTS.save(deps.storage, &Uint128::zero(), env.block.height)?;
+    Ok(Response::default()
+        .add_attribute("method", "instantiate")
+        .add_attribute("onft_collection_id", id))
+}
 
-    match msg.onft_collection {
-        OnftCollection::Existing { id } => {
-            let config = Config {
-                onft_collection_id: id.clone(),
-                unstaking_duration: msg.unstaking_duration,
-            };
-            CONFIG.save(deps.storage, &config)?;
+/// The voting power of every NFT in every collection, were they all
+/// staked.
+fn query_potential_power(deps: Deps) -> StdResult<Uint128> {
+    COLLECTIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |power, item| {
+            let (collection_id, weight) = item?;
+            let supply = query_onft_supply(deps, &collection_id)?;
+            // NFTs with a weight override count for their override
+            // instead of the collection's weight.
+            let overrides = TOKEN_WEIGHT_TOTALS
+                .may_load(deps.storage, &collection_id)?
+                .unwrap_or_default();
+            let weighted = weight
+                .checked_mul(Uint128::from(supply.saturating_sub(overrides.count)))?
+                .checked_add(overrides.power)?;
+            power.checked_add(weighted).map_err(StdError::overflow)
+        })
+}
 
-            Ok(Response::default()
-                .add_attribute("method", "instantiate")
-                .add_attribute("onft_collection_id", id))
+/// Resolves the collection a message refers to, the default collection
+/// if `None`, to its ID.
+fn resolve_collection(
+    storage: &dyn Storage,
+    config: &Config,
+    collection_id: Option<String>,
+) -> Result<String, ContractError> {
+    let collection_id = collection_id.unwrap_or_else(|| config.onft_collection_id.clone());
+    if !COLLECTIONS.has(storage, &collection_id) {
+        return Err(ContractError::UnknownCollection { collection_id });
+    }
+    Ok(collection_id)
+}
+
+/// Resolves the NFTs of a message that may include several collections
+/// to pairs of collection ID and token ID.
+fn resolve_nfts(
+    storage: &dyn Storage,
+    config: &Config,
+    token_ids: Vec<CollectionTokenIds>,
+) -> Result<Vec<(String, String)>, ContractError> {
+    let mut nfts = vec![];
+    for CollectionTokenIds {
+        collection_id,
+        token_ids,
+    } in token_ids
+    {
+        let collection_id = resolve_collection(storage, config, collection_id)?;
+        nfts.extend(
+            token_ids
+                .into_iter()
+                .map(|token_id| (collection_id.clone(), token_id)),
+        );
+    }
+    Ok(nfts)
+}
+
+/// Resolves the NFTs of a stake message, which must be non-empty and
+/// unique, and may only be staked if the module has not been dissolved.
+fn resolve_stake_nfts(
+    storage: &dyn Storage,
+    config: &Config,
+    token_ids: Vec<CollectionTokenIds>,
+) -> Result<Vec<(String, String)>, ContractError> {
+    let nfts = resolve_nfts(storage, config, token_ids)?;
+    if nfts.is_empty() {
+        return Err(ContractError::ZeroStake {});
+    }
+    let mut seen = HashSet::with_capacity(nfts.len());
+    for (collection_id, token_id) in &nfts {
+        if !seen.insert(config.nft_key(collection_id, token_id)) {
+            return Err(ContractError::DuplicateTokenId {
+                token_id: token_id.clone(),
+            });
         }
     }
+    if config.dissolved {
+        return Err(ContractError::Dissolved {});
+    }
+    Ok(nfts)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -90,20 +238,75 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response<Empty>, ContractError> {
     match msg {
-        ExecuteMsg::PrepareStake { token_ids } => execute_prepare_stake(deps, info, token_ids),
-        ExecuteMsg::ConfirmStake { token_ids } => execute_confirm_stake(deps, env, info, token_ids),
+        ExecuteMsg::PrepareStake {
+            token_ids,
+            collection_id,
+        } => execute_prepare_stake(deps, info, token_ids, collection_id),
+        ExecuteMsg::ConfirmStake {
+            token_ids,
+            collection_id,
+            referrer,
+        } => execute_confirm_stake(deps, env, info, token_ids, collection_id, referrer),
         ExecuteMsg::CancelStake {
             token_ids,
+            collection_id,
             recipient,
-        } => execute_cancel_stake(deps, env, info, token_ids, recipient),
-        ExecuteMsg::Unstake { token_ids } => execute_unstake(deps, env, info, token_ids),
-        ExecuteMsg::ClaimNfts {} => execute_claim_nfts(deps, env, info),
-        ExecuteMsg::UpdateConfig { duration } => execute_update_config(info, deps, duration),
+        } => execute_cancel_stake(deps, env, info, token_ids, collection_id, recipient),
+        ExecuteMsg::PrepareStakeMany { token_ids } => {
+            execute_prepare_stake_many(deps, info, token_ids)
+        }
+        ExecuteMsg::ConfirmStakeMany {
+            token_ids,
+            referrer,
+        } => execute_confirm_stake_many(deps, env, info, token_ids, referrer),
+        ExecuteMsg::Unstake {
+            token_ids,
+            collection_id,
+        } => execute_unstake(deps, env, info, token_ids, collection_id),
+        ExecuteMsg::UnstakeMany { token_ids } => execute_unstake_many(deps, env, info, token_ids),
+        ExecuteMsg::ProcessUnstakeQueue { limit } => {
+            execute_process_unstake_queue(deps, env, limit)
+        }
+        ExecuteMsg::ShortenClaims { limit } => execute_shorten_claims(deps, limit),
+        ExecuteMsg::ClaimNfts {} => execute_claim_nfts(deps, env, info.sender),
+        ExecuteMsg::ClaimNftsFor { staker } => execute_claim_nfts_for(deps, env, staker),
+        ExecuteMsg::SetClaimCallback { callback } => {
+            execute_set_claim_callback(deps, info, callback)
+        }
+        ExecuteMsg::UpdateConfig { duration, policy } => {
+            execute_update_config(info, deps, env, duration, policy)
+        }
+        ExecuteMsg::UpdateCollectionUnstakingDuration {
+            collection_id,
+            duration,
+            policy,
+        } => execute_update_collection_unstaking_duration(
+            deps,
+            env,
+            info,
+            collection_id,
+            duration,
+            policy,
+        ),
+        ExecuteMsg::SetTokenWeights { weights } => {
+            execute_set_token_weights(deps, env, info, weights)
+        }
         ExecuteMsg::AddHook { addr } => execute_add_hook(deps, info, addr),
         ExecuteMsg::RemoveHook { addr } => execute_remove_hook(deps, info, addr),
+        ExecuteMsg::AddMemberHook { addr } => execute_add_member_hook(deps, info, addr),
+        ExecuteMsg::RemoveMemberHook { addr } => execute_remove_member_hook(deps, info, addr),
         ExecuteMsg::UpdateActiveThreshold { new_threshold } => {
             execute_update_active_threshold(deps, env, info, new_threshold)
         }
+        ExecuteMsg::UpdateUniqueStakersThreshold { count } => {
+            execute_update_unique_stakers_threshold(deps, info, count)
+        }
+        ExecuteMsg::Dissolve {} => execute_dissolve(deps, info),
+        ExecuteMsg::Delegate { delegate } => execute_delegate(deps, env, info, Some(delegate)),
+        ExecuteMsg::Undelegate {} => execute_delegate(deps, env, info, None),
+        ExecuteMsg::ImportClaims { claims, total } => {
+            execute_import_claims(deps, env, info, claims, total)
+        }
     }
 }
 
@@ -111,35 +314,79 @@ pub fn execute_prepare_stake(
     deps: DepsMut,
     info: MessageInfo,
     token_ids: Vec<String>,
+    collection_id: Option<String>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    let nfts = resolve_stake_nfts(
+        deps.storage,
+        &config,
+        vec![CollectionTokenIds {
+            collection_id,
+            token_ids: token_ids.clone(),
+        }],
+    )?;
+    let collection_id = nfts[0].0.clone();
+    prepare_stake(deps, &config, &info.sender, &nfts)?;
 
-    // verify sender owns all the tokens
-    let owns_all = token_ids
-        .iter()
-        .map(|token_id| -> StdResult<bool> {
-            let owner = query_onft_owner(deps.as_ref(), &config.onft_collection_id, token_id)?;
+    Ok(Response::default()
+        .add_attribute("action", "prepare_stake")
+        .add_attribute("preparer", info.sender.to_string())
+        .add_attribute("collection_id", collection_id)
+        .add_attribute("token_ids", token_ids.join(",")))
+}
 
-            Ok(owner == info.sender)
-        })
-        .collect::<StdResult<Vec<bool>>>()?
-        .into_iter()
-        .all(|b| b);
+pub fn execute_prepare_stake_many(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_ids: Vec<CollectionTokenIds>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let nfts = resolve_stake_nfts(deps.storage, &config, token_ids)?;
+    let keys = prepare_stake(deps, &config, &info.sender, &nfts)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "prepare_stake")
+        .add_attribute("preparer", info.sender.to_string())
+        .add_attribute("token_ids", keys.join(",")))
+}
 
-    if !owns_all {
-        return Err(ContractError::OnlyOwnerCanPrepareStake {});
+/// Prepares `nfts`, pairs of collection ID and token ID, to be staked by
+/// `sender`, returning their keys.
+fn prepare_stake(
+    deps: DepsMut,
+    config: &Config,
+    sender: &Addr,
+    nfts: &[(String, String)],
+) -> Result<Vec<String>, ContractError> {
+    // verify sender owns all the tokens
+    for (collection_id, token_id) in nfts {
+        let owner = query_onft_owner(deps.as_ref(), collection_id, token_id)?;
+        if owner != *sender {
+            // if the sender already staked this NFT, the staking contract
+            // owns it. tell them so instead of that they don't own it.
+            let key = config.nft_key(collection_id, token_id);
+            if STAKED_NFTS_PER_OWNER.has(deps.storage, (sender, &key)) {
+                return Err(ContractError::AlreadyStaked {
+                    token_id: token_id.clone(),
+                });
+            }
+            return Err(ContractError::NotTokenOwner {
+                token_id: token_id.clone(),
+            });
+        }
     }
 
     // save and override prepared ONFTS, readying them to be transferred and
     // staked
-    for token_id in &token_ids {
-        PREPARED_ONFTS.save(deps.storage, token_id.to_string(), &info.sender)?;
+    let keys = nfts
+        .iter()
+        .map(|(collection_id, token_id)| config.nft_key(collection_id, token_id))
+        .collect::<Vec<_>>();
+    for key in &keys {
+        PREPARED_ONFTS.save(deps.storage, key.clone(), sender)?;
     }
 
-    Ok(Response::default()
-        .add_attribute("action", "prepare_stake")
-        .add_attribute("preparer", info.sender.to_string())
-        .add_attribute("token_ids", token_ids.join(",")))
+    Ok(keys)
 }
 
 pub fn execute_confirm_stake(
@@ -147,53 +394,128 @@ pub fn execute_confirm_stake(
     env: Env,
     info: MessageInfo,
     token_ids: Vec<String>,
+    collection_id: Option<String>,
+    referrer: Option<String>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    let nfts = resolve_stake_nfts(
+        deps.storage,
+        &config,
+        vec![CollectionTokenIds {
+            collection_id,
+            token_ids: token_ids.clone(),
+        }],
+    )?;
+    let collection_id = nfts[0].0.clone();
+    let (response, referrer) = confirm_stake(deps, &env, &config, &info.sender, &nfts, referrer)?;
+
+    Ok(response
+        .add_attribute("action", "stake")
+        .add_attribute("from", info.sender)
+        .add_attribute("collection_id", collection_id)
+        .add_attribute("token_ids", token_ids.join(","))
+        .add_attributes(referrer.map(|referrer| ("referrer", referrer))))
+}
 
-    // verify sender prepared and transferred all the tokens
-    let sender_prepared_all = token_ids
+pub fn execute_confirm_stake_many(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_ids: Vec<CollectionTokenIds>,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let nfts = resolve_stake_nfts(deps.storage, &config, token_ids)?;
+    let keys = nfts
         .iter()
-        .map(|token_id| -> StdResult<bool> {
-            // check if sender prepared
-            let prepared = PREPARED_ONFTS
-                .may_load(deps.storage, token_id.to_string())?
-                .map_or(false, |preparer| preparer == info.sender);
+        .map(|(collection_id, token_id)| config.nft_key(collection_id, token_id))
+        .collect::<Vec<_>>();
+    let (response, referrer) = confirm_stake(deps, &env, &config, &info.sender, &nfts, referrer)?;
 
-            // check that NFT was transferred to this contract
-            let owner = query_onft_owner(deps.as_ref(), &config.onft_collection_id, token_id)?;
+    Ok(response
+        .add_attribute("action", "stake")
+        .add_attribute("from", info.sender)
+        .add_attribute("token_ids", keys.join(","))
+        .add_attributes(referrer.map(|referrer| ("referrer", referrer))))
+}
 
-            Ok(prepared && owner == env.contract.address)
-        })
-        .collect::<StdResult<Vec<bool>>>()?
-        .into_iter()
-        .all(|b| b);
+/// Stakes `nfts`, pairs of collection ID and token ID, that `sender`
+/// prepared and transferred to this contract, returning a response
+/// with the hook messages and events of the stake, and the validated
+/// referrer.
+fn confirm_stake(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    sender: &Addr,
+    nfts: &[(String, String)],
+    referrer: Option<String>,
+) -> Result<(Response, Option<Addr>), ContractError> {
+    // verify sender prepared and transferred all the tokens
+    for (collection_id, token_id) in nfts {
+        let key = config.nft_key(collection_id, token_id);
+
+        // an NFT held for any staker, including pending claims, can not be
+        // staked again until it has been returned.
+        if STAKED_NFTS_PER_OWNER.has(deps.storage, (sender, &key))
+            || NFT_STAKERS.has(deps.storage, &key)
+        {
+            return Err(ContractError::AlreadyStaked {
+                token_id: token_id.clone(),
+            });
+        }
 
-    if !sender_prepared_all {
-        return Err(ContractError::StakeMustBePrepared {});
-    }
+        // check if sender prepared
+        let prepared = PREPARED_ONFTS
+            .may_load(deps.storage, key)?
+            .is_some_and(|preparer| preparer == *sender);
+        if !prepared {
+            return Err(ContractError::StakeNotPrepared {
+                token_id: token_id.clone(),
+            });
+        }
 
-    register_staked_nfts(deps.storage, env.block.height, &info.sender, &token_ids)?;
+        // check that NFT was transferred to this contract
+        let owner = query_onft_owner(deps.as_ref(), collection_id, token_id)?;
+        if owner != env.contract.address {
+            return Err(ContractError::StakeNotTransferred {
+                token_id: token_id.clone(),
+            });
+        }
+    }
 
-    // remove preparations
-    for token_id in &token_ids {
-        PREPARED_ONFTS.remove(deps.storage, token_id.to_string());
+    // the referral must be registered before the stake so that it is
+    // counted towards the referrer's referred power.
+    let referrer = referrer
+        .map(|referrer| deps.api.addr_validate(&referrer))
+        .transpose()?;
+    if let Some(ref referrer) = referrer {
+        register_referral(deps.storage, sender, referrer)?;
     }
 
-    let hook_msgs = token_ids
+    // NFTs from every collection are registered at once, so that the
+    // voting power snapshots are updated once per stake.
+    let keys = nfts
         .iter()
-        .map(|token_id| {
-            stake_nft_hook_msgs(HOOKS, deps.storage, info.sender.clone(), token_id.clone())
-        })
+        .map(|(collection_id, token_id)| config.nft_key(collection_id, token_id))
+        .collect::<Vec<_>>();
+    let power = nfts_power(deps.storage, config, &keys)?;
+    register_staked_nfts(deps.storage, env.block.height, sender, &keys, power)?;
+    let events = stake_events(deps.storage, config, sender, &keys)?;
+
+    let mut hook_msgs = keys
+        .iter()
+        .map(|key| stake_nft_hook_msgs(HOOKS, deps.storage, sender.clone(), key.clone()))
         .collect::<StdResult<Vec<Vec<SubMsg>>>>()?
         .into_iter()
         .flatten()
         .collect::<Vec<SubMsg>>();
+    hook_msgs.extend(delegate_hook_msgs(deps.storage, sender, power, true)?);
 
-    Ok(Response::default()
+    let response = Response::default()
         .add_submessages(hook_msgs)
-        .add_attribute("action", "stake")
-        .add_attribute("from", info.sender)
-        .add_attribute("token_ids", token_ids.join(",")))
+        .add_events(events);
+    Ok((response, referrer))
 }
 
 /// CancelStake serves as an undo function in case an NFT or stake gets into a
@@ -219,32 +541,45 @@ pub fn execute_cancel_stake(
     env: Env,
     info: MessageInfo,
     token_ids: Vec<String>,
+    collection_id: Option<String>,
     recipient: Option<String>,
 ) -> Result<Response, ContractError> {
+    assert_unique_token_ids(&token_ids)?;
+
     let dao = DAO.load(deps.storage)?;
     let config = CONFIG.load(deps.storage)?;
+    let collection_id = resolve_collection(deps.storage, &config, collection_id)?;
 
     // get preparers and owners of NFTs
     let token_ids_with_owners_and_preparers = token_ids
         .iter()
         .map(|token_id| {
-            let preparer = PREPARED_ONFTS.may_load(deps.storage, token_id.clone())?;
+            let key = config.nft_key(&collection_id, token_id);
+            let preparer = PREPARED_ONFTS.may_load(deps.storage, key.clone())?;
+
+            let owner = query_onft_owner(deps.as_ref(), &collection_id, token_id)?;
 
-            let owner = query_onft_owner(deps.as_ref(), &config.onft_collection_id, token_id)?;
+            // NFTs held for stakers may only be returned by unstaking and
+            // claiming them, not even by the DAO.
+            if owner == env.contract.address && NFT_STAKERS.has(deps.storage, &key) {
+                return Err(ContractError::CancelStaked {
+                    token_id: token_id.clone(),
+                });
+            }
 
-            Ok((token_id, owner, preparer))
+            Ok((token_id, key, owner, preparer))
         })
-        .collect::<StdResult<Vec<(&String, String, Option<Addr>)>>>()?;
+        .collect::<Result<Vec<(&String, String, String, Option<Addr>)>, ContractError>>()?;
 
     let mut transfer_msgs: Vec<CosmosMsg> = vec![];
 
     // If DAO, cancel preparations (if any) and send NFTs to the specified
     // recipient.
     if info.sender == dao {
-        for (token_id, owner, preparer) in token_ids_with_owners_and_preparers {
+        for (token_id, key, owner, preparer) in token_ids_with_owners_and_preparers {
             // cancel preparation if it exists
             if preparer.is_some() {
-                PREPARED_ONFTS.remove(deps.storage, token_id.to_string());
+                PREPARED_ONFTS.remove(deps.storage, key);
             }
 
             // if this contract owns the NFT, send it to the recipient (or
@@ -256,33 +591,37 @@ pub fn execute_cancel_stake(
 
                 if let Some(recipient) = recipient {
                     transfer_msgs.push(get_onft_transfer_msg(
-                        &config.onft_collection_id,
+                        &collection_id,
                         token_id,
                         env.contract.address.as_str(),
                         &recipient,
                     ));
                 } else {
-                    return Err(ContractError::NoRecipient {});
+                    return Err(ContractError::NoRecipient {
+                        token_id: token_id.clone(),
+                    });
                 }
             }
         }
     } else {
-        for (token_id, owner, preparer) in token_ids_with_owners_and_preparers {
-            let is_preparer = preparer.as_ref().map_or(false, |p| *p == info.sender);
+        for (token_id, key, owner, preparer) in token_ids_with_owners_and_preparers {
+            let is_preparer = preparer.as_ref().is_some_and(|p| *p == info.sender);
             // only owner or preparer can cancel stake
             if info.sender != owner && !is_preparer {
-                return Err(ContractError::NotPreparerNorOwner {});
+                return Err(ContractError::NotPreparerNorOwner {
+                    token_id: token_id.clone(),
+                });
             }
 
             // cancel preparation
-            PREPARED_ONFTS.remove(deps.storage, token_id.to_string());
+            PREPARED_ONFTS.remove(deps.storage, key);
 
             // if owner is this staking contract, send it back to the preparer,
             // who must also be the sender (but let's force unwrap the preparer
             // just to make sure)
             if owner == env.contract.address {
                 transfer_msgs.push(get_onft_transfer_msg(
-                    &config.onft_collection_id,
+                    &collection_id,
                     token_id,
                     env.contract.address.as_str(),
                     preparer.unwrap().as_ref(),
@@ -307,12 +646,172 @@ pub fn execute_unstake(
     env: Env,
     info: MessageInfo,
     token_ids: Vec<String>,
+    collection_id: Option<String>,
+) -> Result<Response, ContractError> {
+    if token_ids.is_empty() {
+        return Err(ContractError::ZeroUnstake {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let collection_id = resolve_collection(deps.storage, &config, collection_id)?;
+    let token_ids = token_ids
+        .iter()
+        .map(|token_id| config.nft_key(&collection_id, token_id))
+        .collect::<Vec<_>>();
+    unstake(deps, &env, &config, &info.sender, token_ids)
+}
+
+pub fn execute_unstake_many(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_ids: Vec<CollectionTokenIds>,
 ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let token_ids = resolve_nfts(deps.storage, &config, token_ids)?
+        .iter()
+        .map(|(collection_id, token_id)| config.nft_key(collection_id, token_id))
+        .collect::<Vec<_>>();
     if token_ids.is_empty() {
         return Err(ContractError::ZeroUnstake {});
     }
+    unstake(deps, &env, &config, &info.sender, token_ids)
+}
+
+/// Unstakes the NFTs stored under `token_ids` on behalf of `staker`,
+/// subject to the unstaking lock and unstake budget.
+fn unstake(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    staker: &Addr,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    if config.dissolved {
+        // the unstake budget no longer applies, so NFTs that are waiting
+        // in the queue may be unstaked right away.
+        for token_id in &token_ids {
+            if let Some(sequence) = QUEUED_UNSTAKES.may_load(deps.storage, (staker, token_id))? {
+                QUEUED_UNSTAKES.remove(deps.storage, (staker, token_id));
+                UNSTAKE_QUEUE.remove(deps.storage, sequence);
+            }
+        }
+        return unstake_nfts(deps, env, config, staker, token_ids);
+    }
+    if let Some(until) = config.unstaking_locked_until {
+        if !until.is_expired(&env.block) {
+            return Err(ContractError::UnstakingLocked { until });
+        }
+    }
+
+    let Some(unstake_budget) = UNSTAKE_BUDGET.may_load(deps.storage)? else {
+        return unstake_nfts(deps, env, config, staker, token_ids);
+    };
+
+    assert_unique_token_ids(&token_ids)?;
+    for token_id in &token_ids {
+        if QUEUED_UNSTAKES.has(deps.storage, (staker, token_id)) {
+            return Err(ContractError::AlreadyQueued {
+                token_id: token_id.clone(),
+            });
+        }
+        if !STAKED_NFTS_PER_OWNER.has(deps.storage, (staker, token_id)) {
+            return Err(ContractError::NotStaked {
+                token_id: token_id.clone(),
+            });
+        }
+    }
+
+    // queued unstakes count against the maximum number of claims
+    // regardless of the unstaking duration, so that processing the
+    // queue never fails.
+    let outstanding_claims = NFT_CLAIMS
+        .query_claims(deps.as_ref(), staker)?
+        .nft_claims
+        .len()
+        + QUEUED_UNSTAKES
+            .prefix(staker)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count();
+    if outstanding_claims + token_ids.len() > MAX_CLAIMS as usize {
+        return Err(ContractError::TooManyClaims {});
+    }
+
+    // new unstakes wait behind those that are already queued.
+    let mut epoch = unstake_budget.load_epoch(deps.storage, &env.block)?;
+    let available = if UNSTAKE_QUEUE.is_empty(deps.storage) {
+        epoch.remaining().u128()
+    } else {
+        0
+    };
+    let (now, queued) = token_ids.split_at(min(available, token_ids.len() as u128) as usize);
+
+    epoch.used += Uint128::new(now.len() as u128);
+    UNSTAKE_EPOCH.save(deps.storage, &epoch)?;
+    queue_unstakes(deps.storage, staker, queued)?;
+
+    let response = if now.is_empty() {
+        Response::default()
+            .add_attribute("action", "unstake")
+            .add_attribute("from", staker)
+    } else {
+        unstake_nfts(deps, env, config, staker, now.to_vec())?
+    };
+    Ok(response
+        .add_events(unstake_queued_events(config, staker, queued))
+        .add_attribute("queued", queued.len().to_string()))
+}
+
+pub fn execute_process_unstake_queue(
+    mut deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut response = Response::default().add_attribute("action", "process_unstake_queue");
+    let Some(unstake_budget) = UNSTAKE_BUDGET.may_load(deps.storage)? else {
+        return Ok(response.add_attribute("processed", "0"));
+    };
+    let config = CONFIG.load(deps.storage)?;
+    let mut epoch = unstake_budget.load_epoch(deps.storage, &env.block)?;
+
+    // once dissolved, the queue is drained regardless of the budget.
+    let limit = limit.unwrap_or(DEFAULT_UNSTAKE_QUEUE_LIMIT) as u128;
+    let limit = if config.dissolved {
+        limit
+    } else {
+        min(limit, epoch.remaining().u128())
+    };
+    let queued = UNSTAKE_QUEUE
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit as usize)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (sequence, QueuedUnstake { staker, token_id }) in &queued {
+        UNSTAKE_QUEUE.remove(deps.storage, *sequence);
+        QUEUED_UNSTAKES.remove(deps.storage, (staker, token_id));
+        let unstaked = unstake_nfts(deps.branch(), &env, &config, staker, vec![token_id.clone()])?;
+        response = response
+            .add_submessages(unstaked.messages)
+            .add_events(unstaked.events);
+    }
+
+    epoch.used += Uint128::new(queued.len() as u128);
+    UNSTAKE_EPOCH.save(deps.storage, &epoch)?;
+
+    Ok(response.add_attribute("processed", queued.len().to_string()))
+}
 
-    register_unstaked_nfts(deps.storage, env.block.height, &info.sender, &token_ids)?;
+/// Unstakes the NFTs stored under `token_ids` staked by `staker`,
+/// either returning them immediately or creating claims for them.
+fn unstake_nfts(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    staker: &Addr,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let power = nfts_power(deps.storage, config, &token_ids)?;
+    register_unstaked_nfts(deps.storage, env.block.height, staker, &token_ids, power)?;
 
     // Provided that the backing cw721 contract is non-malicious:
     //
@@ -337,100 +836,244 @@ pub fn execute_unstake(
     // has not errored and thus:
     //
     // 5. token_ids contains no duplicate values.
-    // 6. all NFTs in token_ids were staked by `info.sender`
+    // 6. all NFTs in token_ids were staked by `staker`
     // 7. (4) && (6) => none of the tokens in token_ids are in the
-    //    claims queue for `info.sender`
+    //    claims queue for `staker`
     //
     // (5) && (7) are the invariants for calling `create_nft_claims`
     // so if we reach this point in execution, we may safely create
     // claims.
 
-    let hook_msgs =
-        unstake_nft_hook_msgs(HOOKS, deps.storage, info.sender.clone(), token_ids.clone())?;
+    let mut hook_msgs =
+        unstake_nft_hook_msgs(HOOKS, deps.storage, staker.clone(), token_ids.clone())?;
+    hook_msgs.extend(delegate_hook_msgs(deps.storage, staker, power, false)?);
+
+    // NFTs from different collections may have different unstaking
+    // durations, so they are grouped by duration.
+    let mut groups: Vec<(Option<Duration>, Vec<String>)> = vec![];
+    for key in token_ids {
+        let (collection_id, _) = config.parse_nft_key(&key);
+        let duration = unstaking_duration(deps.storage, config, collection_id)?;
+        match groups.iter_mut().find(|(d, _)| *d == duration) {
+            Some((_, keys)) => keys.push(key),
+            None => groups.push((duration, vec![key])),
+        }
+    }
 
-    let config = CONFIG.load(deps.storage)?;
-    match config.unstaking_duration {
-        None => {
-            let return_messages = token_ids
-                .into_iter()
-                .map(|token_id| -> CosmosMsg {
+    let claimed = groups
+        .iter()
+        .filter(|(duration, _)| duration.is_some())
+        .map(|(_, keys)| keys.len())
+        .sum::<usize>();
+    if claimed > 0 {
+        let outstanding_claims = NFT_CLAIMS.query_claims(deps.as_ref(), staker)?.nft_claims;
+        if outstanding_claims.len() + claimed > MAX_CLAIMS as usize {
+            return Err(ContractError::TooManyClaims {});
+        }
+    }
+
+    let mut return_messages = vec![];
+    let mut events = vec![];
+    for (duration, token_ids) in &groups {
+        events.extend(unstake_events(
+            deps.storage,
+            config,
+            staker,
+            token_ids,
+            *duration,
+        )?);
+        match duration {
+            None => {
+                for token_id in token_ids {
+                    NFT_STAKERS.remove(deps.storage, token_id);
+                }
+
+                return_messages.extend(token_ids.iter().map(|key| -> CosmosMsg {
+                    let (collection_id, token_id) = config.parse_nft_key(key);
                     get_onft_transfer_msg(
-                        &config.onft_collection_id,
-                        &token_id,
+                        collection_id,
+                        token_id,
                         env.contract.address.as_str(),
-                        info.sender.as_str(),
+                        staker.as_str(),
                     )
-                })
-                .collect::<Vec<_>>();
-
-            Ok(Response::default()
-                .add_messages(return_messages)
-                .add_submessages(hook_msgs)
-                .add_attribute("action", "unstake")
-                .add_attribute("from", info.sender)
-                .add_attribute("claim_duration", "None"))
-        }
-
-        Some(duration) => {
-            let outstanding_claims = NFT_CLAIMS
-                .query_claims(deps.as_ref(), &info.sender)?
-                .nft_claims;
-            if outstanding_claims.len() + token_ids.len() > MAX_CLAIMS as usize {
-                return Err(ContractError::TooManyClaims {});
+                }));
             }
 
-            // Out of gas here is fine - just try again with fewer
-            // tokens.
-            NFT_CLAIMS.create_nft_claims(
-                deps.storage,
-                &info.sender,
-                token_ids,
-                duration.after(&env.block),
-            )?;
-
-            Ok(Response::default()
-                .add_attribute("action", "unstake")
-                .add_submessages(hook_msgs)
-                .add_attribute("from", info.sender)
-                .add_attribute("claim_duration", format!("{duration}")))
+            Some(duration) => {
+                // Out of gas here is fine - just try again with fewer
+                // tokens.
+                let claim_info = ClaimInfo {
+                    duration: *duration,
+                    unstaked_at_height: env.block.height,
+                    unstaked_at_time: env.block.time,
+                };
+                for token_id in token_ids {
+                    CLAIM_INFO.save(deps.storage, (staker, token_id), &claim_info)?;
+                }
+                NFT_CLAIMS.create_nft_claims(
+                    deps.storage,
+                    staker,
+                    token_ids.clone(),
+                    duration.after(&env.block),
+                )?;
+            }
         }
     }
+
+    let claim_duration = groups
+        .iter()
+        .map(|(duration, _)| {
+            duration
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "None".to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    Ok(Response::default()
+        .add_messages(return_messages)
+        .add_submessages(hook_msgs)
+        .add_events(events)
+        .add_attribute("action", "unstake")
+        .add_attribute("from", staker)
+        .add_attribute("claim_duration", claim_duration))
 }
 
 pub fn execute_claim_nfts(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    staker: Addr,
 ) -> Result<Response, ContractError> {
-    let nfts = NFT_CLAIMS.claim_nfts(deps.storage, &info.sender, &env.block)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    // once dissolved, pending claims no longer wait to mature. this
+    // includes claims whose unstaking is not recorded in CLAIM_INFO,
+    // such as imported or migrated claims.
+    let nfts = if config.dissolved {
+        NFT_CLAIMS.claim_all_nfts(deps.storage, &staker)?
+    } else {
+        NFT_CLAIMS.claim_nfts(deps.storage, &staker, &env.block)?
+    };
     if nfts.is_empty() {
-        return Err(ContractError::NothingToClaim {});
+        // if there are pending claims, let the sender know when the next one
+        // will be ready.
+        let next_release = NFT_CLAIMS
+            .query_claims(deps.as_ref(), &staker)?
+            .nft_claims
+            .into_iter()
+            .map(|claim| claim.release_at)
+            .reduce(|next, release_at| if release_at < next { release_at } else { next });
+
+        return Err(match next_release {
+            Some(ready_at) => ContractError::ClaimNotMatured { ready_at },
+            None => ContractError::NothingToClaim {},
+        });
     }
 
-    let config = CONFIG.load(deps.storage)?;
+    for token_id in &nfts {
+        CLAIM_INFO.remove(deps.storage, (&staker, token_id));
+        NFT_STAKERS.remove(deps.storage, token_id);
+    }
 
     let msgs = nfts
-        .into_iter()
-        .map(|nft| -> CosmosMsg {
+        .iter()
+        .map(|key| -> CosmosMsg {
+            let (collection_id, token_id) = config.parse_nft_key(key);
             get_onft_transfer_msg(
-                &config.onft_collection_id,
-                &nft,
+                collection_id,
+                token_id,
                 env.contract.address.as_str(),
-                info.sender.as_str(),
+                staker.as_str(),
             )
         })
         .collect::<Vec<_>>();
+    let events = claim_events(&config, &staker, &nfts);
+
+    // errors executing the callback are handled in reply so that they never
+    // prevent the NFTs from being claimed.
+    let callback = CLAIM_CALLBACKS
+        .may_load(deps.storage, &staker)?
+        .map(|callback| -> StdResult<SubMsg> {
+            Ok(SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: callback.contract.into_string(),
+                    msg: to_json_binary(&ClaimCallbackMsg::NftsClaimed {
+                        staker: staker.to_string(),
+                        token_ids: nfts,
+                        msg: callback.msg,
+                    })?,
+                    funds: vec![],
+                },
+                CLAIM_CALLBACK_REPLY_ID,
+            ))
+        })
+        .transpose()?;
 
     Ok(Response::default()
         .add_messages(msgs)
+        .add_submessages(callback)
+        .add_events(events)
         .add_attribute("action", "claim_nfts")
-        .add_attribute("from", info.sender))
+        .add_attribute("from", staker))
+}
+
+pub fn execute_claim_nfts_for(
+    deps: DepsMut,
+    env: Env,
+    staker: String,
+) -> Result<Response, ContractError> {
+    let staker = deps.api.addr_validate(&staker)?;
+
+    // only stakers that registered a callback have opted into others
+    // claiming for them.
+    if !CLAIM_CALLBACKS.has(deps.storage, &staker) {
+        return Err(ContractError::NoClaimCallback {
+            staker: staker.into_string(),
+        });
+    }
+
+    execute_claim_nfts(deps, env, staker)
+}
+
+pub fn execute_set_claim_callback(
+    deps: DepsMut,
+    info: MessageInfo,
+    callback: Option<ClaimCallback>,
+) -> Result<Response, ContractError> {
+    match callback {
+        Some(callback) => {
+            // only contracts have contract info.
+            if deps.querier.query_wasm_contract_info(&info.sender).is_err() {
+                return Err(ContractError::NotContract {});
+            }
+
+            let callback = Callback {
+                contract: deps.api.addr_validate(&callback.contract)?,
+                msg: callback.msg,
+            };
+            CLAIM_CALLBACKS.save(deps.storage, &info.sender, &callback)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_claim_callback")
+                .add_attribute("staker", info.sender)
+                .add_attribute("contract", callback.contract))
+        }
+        None => {
+            CLAIM_CALLBACKS.remove(deps.storage, &info.sender);
+
+            Ok(Response::default()
+                .add_attribute("action", "remove_claim_callback")
+                .add_attribute("staker", info.sender))
+        }
+    }
 }
 
 pub fn execute_update_config(
     info: MessageInfo,
     deps: DepsMut,
+    env: Env,
     duration: Option<Duration>,
+    policy: Option<UnstakingDurationPolicy>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage)?;
     let dao = DAO.load(deps.storage)?;
@@ -440,14 +1083,28 @@ pub fn execute_update_config(
         return Err(ContractError::Unauthorized {});
     }
 
+    // the unstaking duration stays waived once dissolved.
+    if config.dissolved {
+        return Err(ContractError::Dissolved {});
+    }
+
     // Validate unstaking duration
     validate_duration(duration)?;
 
     config.unstaking_duration = duration;
     CONFIG.save(deps.storage, &config)?;
 
+    let shortened = match policy.unwrap_or(UnstakingDurationPolicy::NewClaimsOnly {}) {
+        UnstakingDurationPolicy::NewClaimsOnly {} => 0,
+        UnstakingDurationPolicy::ShortenExisting {} => {
+            start_claim_shortening(deps.storage, &env, &config, duration, None)?
+        }
+    };
+
     Ok(Response::default()
         .add_attribute("action", "update_config")
+        .add_attribute("claims_shortened", shortened.to_string())
+        .add_attribute("done", (!CLAIM_SHORTENING.exists(deps.storage)).to_string())
         .add_attribute(
             "unstaking_duration",
             config
@@ -457,27 +1114,468 @@ pub fn execute_update_config(
         ))
 }
 
-pub fn execute_add_hook(
-    deps: DepsMut,
-    info: MessageInfo,
-    addr: String,
-) -> Result<Response, ContractError> {
+pub fn execute_dissolve(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
     let dao = DAO.load(deps.storage)?;
-
-    // Only the DAO can add a hook
     if info.sender != dao {
         return Err(ContractError::Unauthorized {});
     }
 
-    let hook = deps.api.addr_validate(&addr)?;
-    HOOKS.add_hook(deps.storage, hook)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.dissolved {
+        return Err(ContractError::Dissolved {});
+    }
 
-    Ok(Response::default()
-        .add_attribute("action", "add_hook")
-        .add_attribute("hook", addr))
+    config.dissolved = true;
+    config.unstaking_duration = None;
+    config.unstaking_locked_until = None;
+    CONFIG.save(deps.storage, &config)?;
+    COLLECTION_UNSTAKING_DURATIONS.clear(deps.storage);
+    CLAIM_SHORTENING.remove(deps.storage);
+
+    // pending claims are released when claimed rather than here, so that
+    // dissolving takes the same gas however many claims are pending.
+    Ok(Response::default().add_attribute("action", "dissolve"))
 }
 
-pub fn execute_remove_hook(
+pub fn execute_update_collection_unstaking_duration(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection_id: String,
+    duration: Option<Duration>,
+    policy: Option<UnstakingDurationPolicy>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let dao = DAO.load(deps.storage)?;
+
+    // Only the DAO can update the config.
+    if info.sender != dao {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the unstaking duration stays waived once dissolved.
+    if config.dissolved {
+        return Err(ContractError::Dissolved {});
+    }
+
+    let collection_id = resolve_collection(deps.storage, &config, Some(collection_id))?;
+    validate_duration(duration)?;
+
+    match duration {
+        Some(duration) => {
+            COLLECTION_UNSTAKING_DURATIONS.save(deps.storage, &collection_id, &duration)?
+        }
+        None => COLLECTION_UNSTAKING_DURATIONS.remove(deps.storage, &collection_id),
+    }
+    let effective = unstaking_duration(deps.storage, &config, &collection_id)?;
+
+    let shortened = match policy.unwrap_or(UnstakingDurationPolicy::NewClaimsOnly {}) {
+        UnstakingDurationPolicy::NewClaimsOnly {} => 0,
+        UnstakingDurationPolicy::ShortenExisting {} => start_claim_shortening(
+            deps.storage,
+            &env,
+            &config,
+            effective,
+            Some(collection_id.clone()),
+        )?,
+    };
+
+    Ok(Response::default()
+        .add_attribute("action", "update_collection_unstaking_duration")
+        .add_attribute("collection_id", collection_id)
+        .add_attribute("claims_shortened", shortened.to_string())
+        .add_attribute("done", (!CLAIM_SHORTENING.exists(deps.storage)).to_string())
+        .add_attribute(
+            "unstaking_duration",
+            effective
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+        ))
+}
+
+/// Overrides the voting power of individual NFTs. NFTs that are staked
+/// change the voting power of their stakers without staking or
+/// unstaking, so hooks are notified of it as a change in the voting
+/// power of the addresses involved.
+pub fn execute_set_token_weights(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    weights: Vec<TokenWeight>,
+) -> Result<Response, ContractError> {
+    let dao = DAO.load(deps.storage)?;
+    if info.sender != dao {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut addresses: Vec<Addr> = vec![];
+    let mut before = vec![];
+    for TokenWeight {
+        collection_id,
+        token_id,
+        weight,
+    } in weights
+    {
+        let collection_id = resolve_collection(deps.storage, &config, collection_id)?;
+        if weight.is_some_and(|weight| weight.is_zero()) {
+            return Err(ContractError::ZeroTokenWeight { token_id });
+        }
+        let key = config.nft_key(&collection_id, &token_id);
+        let previous = nft_power(deps.storage, &config, &key)?;
+        set_token_weight(deps.storage, &config, &key, weight)?;
+        let power = nft_power(deps.storage, &config, &key)?;
+
+        let Some(staker) = NFT_STAKERS
+            .may_load(deps.storage, &key)?
+            .filter(|staker| STAKED_NFTS_PER_OWNER.has(deps.storage, (staker, &key)))
+        else {
+            continue;
+        };
+        let mut affected = vec![staker.clone()];
+        affected.extend(DELEGATES.may_load(deps.storage, &staker)?);
+        for address in affected {
+            if !addresses.contains(&address) {
+                before.push(voting_power(deps.storage, &address, None)?);
+                addresses.push(address);
+            }
+        }
+        reweigh_staked_nft(deps.storage, env.block.height, &staker, previous, power)?;
+    }
+
+    let mut changes = vec![];
+    for (address, before) in addresses.into_iter().zip(before) {
+        let after = voting_power(deps.storage, &address, None)?;
+        changes.push((address, before, after));
+    }
+    let hook_msgs = power_changed_hook_msgs(deps.storage, changes)?;
+
+    Ok(Response::default()
+        .add_submessages(hook_msgs)
+        .add_attribute("action", "set_token_weights"))
+}
+
+/// Delegates the sender's voting power to `delegate`, or stops
+/// delegating it if `None`. Delegation changes voting power without
+/// staking or unstaking, so hooks are notified of it as a change in the
+/// voting power of the addresses involved.
+pub fn execute_delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delegate: Option<String>,
+) -> Result<Response, ContractError> {
+    let delegate = delegate
+        .map(|delegate| deps.api.addr_validate(&delegate))
+        .transpose()?;
+    let previous = DELEGATES.may_load(deps.storage, &info.sender)?;
+    match (&delegate, &previous) {
+        (Some(delegate), _) if *delegate == info.sender => {
+            return Err(ContractError::SelfDelegation {})
+        }
+        (Some(delegate), Some(previous)) if delegate == previous => {
+            return Err(ContractError::AlreadyDelegated {
+                delegate: delegate.to_string(),
+            })
+        }
+        (None, None) => return Err(ContractError::NotDelegated {}),
+        _ => {}
+    }
+
+    let mut addresses = vec![info.sender.clone()];
+    addresses.extend(previous);
+    addresses.extend(delegate.clone());
+    let before = addresses
+        .iter()
+        .map(|address| voting_power(deps.storage, address, None))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    set_delegate(
+        deps.storage,
+        env.block.height,
+        &info.sender,
+        delegate.as_ref(),
+    )?;
+
+    let mut changes = vec![];
+    for (address, before) in addresses.into_iter().zip(before) {
+        let after = voting_power(deps.storage, &address, None)?;
+        changes.push((address, before, after));
+    }
+    let hook_msgs = power_changed_hook_msgs(deps.storage, changes)?;
+
+    let mut response = Response::default()
+        .add_submessages(hook_msgs)
+        .add_attribute("action", "delegate")
+        .add_attribute("staker", info.sender);
+    if let Some(delegate) = delegate {
+        response = response.add_attribute("delegate", delegate);
+    }
+    Ok(response)
+}
+
+/// Prepares member hooks notifying that the voting power of each address in
+/// `changes` changed from the first amount to the second, for changes
+/// that staking hooks don't report, such as delegation. Unchanged
+/// voting power is left out.
+fn power_changed_hook_msgs(
+    storage: &dyn Storage,
+    changes: Vec<(Addr, Uint128, Uint128)>,
+) -> StdResult<Vec<SubMsg>> {
+    let weight = |power: Uint128| Some(u64::try_from(power.u128()).unwrap_or(u64::MAX));
+    let diffs = changes
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(address, before, after)| MemberDiff::new(address, weight(before), weight(after)))
+        .collect::<Vec<_>>();
+    if diffs.is_empty() {
+        return Ok(vec![]);
+    }
+    let msg = MemberChangedHookMsg::new(diffs);
+    MEMBER_HOOKS.prepare_hooks(storage, |hook| {
+        Ok(SubMsg::new(msg.clone().into_cosmos_msg(hook)?))
+    })
+}
+
+/// Prepares hooks notifying that the voting power of `staker`'s
+/// delegate, if it has one, changed by `power` as a result of the
+/// staker staking or unstaking.
+fn delegate_hook_msgs(
+    storage: &dyn Storage,
+    staker: &Addr,
+    power: Uint128,
+    staked: bool,
+) -> StdResult<Vec<SubMsg>> {
+    let Some(delegate) = DELEGATES.may_load(storage, staker)? else {
+        return Ok(vec![]);
+    };
+    let after = voting_power(storage, &delegate, None)?;
+    let before = if staked {
+        after.saturating_sub(power)
+    } else {
+        after.saturating_add(power)
+    };
+    power_changed_hook_msgs(storage, vec![(delegate, before, after)])
+}
+
+pub fn execute_import_claims(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    claims: Vec<StakerClaims>,
+    total: u64,
+) -> Result<Response, ContractError> {
+    let dao = DAO.load(deps.storage)?;
+    if info.sender != dao {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let actual = claims.iter().map(|staker| staker.claims.len() as u64).sum();
+    if actual != total {
+        return Err(ContractError::ImportTotalMismatch {
+            expected: total,
+            actual,
+        });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    for StakerClaims { address, claims } in &claims {
+        let staker = deps.api.addr_validate(address)?;
+
+        let outstanding_claims = NFT_CLAIMS.query_claims(deps.as_ref(), &staker)?.nft_claims;
+        if outstanding_claims.len() + claims.len() > MAX_CLAIMS as usize {
+            return Err(ContractError::TooManyClaims {});
+        }
+
+        for claim in claims {
+            let collection_id =
+                resolve_collection(deps.storage, &config, Some(claim.collection_id.clone()))?;
+            let key = config.nft_key(&collection_id, &claim.token_id);
+
+            // stakers are recorded as claims are imported, so this also
+            // catches NFTs included more than once.
+            if NFT_STAKERS.has(deps.storage, &key) {
+                return Err(ContractError::AlreadyStaked { token_id: key });
+            }
+            let owner = query_onft_owner(deps.as_ref(), &collection_id, &claim.token_id)?;
+            if owner != env.contract.address {
+                return Err(ContractError::ClaimNotTransferred { token_id: key });
+            }
+
+            NFT_STAKERS.save(deps.storage, &key, &staker)?;
+            if let Some(claim_info) = &claim.claim_info {
+                CLAIM_INFO.save(deps.storage, (&staker, &key), claim_info)?;
+            }
+            NFT_CLAIMS.create_nft_claims(deps.storage, &staker, vec![key], claim.release_at)?;
+        }
+    }
+
+    Ok(Response::default()
+        .add_attribute("action", "import_claims")
+        .add_attribute("stakers", claims.len().to_string())
+        .add_attribute("total", total.to_string()))
+}
+
+/// Starts applying a new unstaking duration to pending claims with the
+/// `ShortenExisting` policy, and shortens the claims of the first page
+/// of stakers. Returns the number of claims that were shortened.
+fn start_claim_shortening(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    duration: Option<Duration>,
+    collection_id: Option<String>,
+) -> Result<u64, ContractError> {
+    if CLAIM_SHORTENING.exists(storage) {
+        return Err(ContractError::ClaimShorteningInProgress {});
+    }
+    let shortening = ClaimShortening {
+        duration,
+        collection_id,
+        set_at_height: env.block.height,
+        set_at_time: env.block.time,
+        cursor: None,
+    };
+    Ok(shorten_claims(
+        storage,
+        config,
+        shortening,
+        DEFAULT_SHORTEN_CLAIMS_LIMIT,
+    )?)
+}
+
+pub fn execute_shorten_claims(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let response = Response::default().add_attribute("action", "shorten_claims");
+    let Some(shortening) = CLAIM_SHORTENING.may_load(deps.storage)? else {
+        return Ok(response.add_attribute("claims_shortened", "0"));
+    };
+    let config = CONFIG.load(deps.storage)?;
+    let shortened = shorten_claims(
+        deps.storage,
+        &config,
+        shortening,
+        limit.unwrap_or(DEFAULT_SHORTEN_CLAIMS_LIMIT),
+    )?;
+
+    Ok(response
+        .add_attribute("claims_shortened", shortened.to_string())
+        .add_attribute("done", (!CLAIM_SHORTENING.exists(deps.storage)).to_string()))
+}
+
+/// Applies `shortening` to the pending claims of up to `limit` stakers
+/// after its cursor that it would release sooner, then saves it if
+/// stakers may remain. Applies to the claims of the shortening's
+/// collection, or to those of every collection without an unstaking
+/// duration override if it has none. Returns the number of claims that
+/// were shortened.
+fn shorten_claims(
+    storage: &mut dyn Storage,
+    config: &Config,
+    mut shortening: ClaimShortening,
+    limit: u32,
+) -> StdResult<u64> {
+    let limit = limit.max(1) as usize;
+    let stakers = NFT_CLAIMS.range_claims(storage, shortening.cursor.as_ref(), limit)?;
+
+    let mut shortened = 0;
+    for (staker, claims) in &stakers {
+        for claim in claims {
+            let (claim_collection_id, _) = config.parse_nft_key(&claim.token_id);
+            let applies = match &shortening.collection_id {
+                Some(collection_id) => claim_collection_id == collection_id,
+                None => !COLLECTION_UNSTAKING_DURATIONS.has(storage, claim_collection_id),
+            };
+            if !applies {
+                continue;
+            }
+
+            let release_at = match CLAIM_INFO.may_load(storage, (staker, &claim.token_id))? {
+                Some(mut claim_info) => {
+                    let (duration, release_at) = match (shortening.duration, claim_info.duration) {
+                        // no unstaking duration, so release immediately.
+                        (None, _) => (
+                            Duration::Height(0),
+                            Expiration::AtHeight(claim_info.unstaked_at_height),
+                        ),
+                        (Some(Duration::Height(new)), Duration::Height(old)) if new < old => (
+                            Duration::Height(new),
+                            Expiration::AtHeight(claim_info.unstaked_at_height + new),
+                        ),
+                        (Some(Duration::Time(new)), Duration::Time(old)) if new < old => (
+                            Duration::Time(new),
+                            Expiration::AtTime(claim_info.unstaked_at_time.plus_seconds(new)),
+                        ),
+                        // longer durations or durations in different units
+                        // are not applied to pending claims.
+                        _ => continue,
+                    };
+                    claim_info.duration = duration;
+                    CLAIM_INFO.save(storage, (staker, &claim.token_id), &claim_info)?;
+                    release_at
+                }
+                // without claim info, when the NFT was unstaked is unknown,
+                // so the claim is released at most the new duration after
+                // it was set.
+                None => {
+                    let release_at = match shortening.duration {
+                        None => Expiration::AtHeight(shortening.set_at_height),
+                        Some(Duration::Height(new)) => {
+                            Expiration::AtHeight(shortening.set_at_height + new)
+                        }
+                        Some(Duration::Time(new)) => {
+                            Expiration::AtTime(shortening.set_at_time.plus_seconds(new))
+                        }
+                    };
+                    let sooner = shortening.duration.is_none()
+                        || release_at.partial_cmp(&claim.release_at) == Some(Ordering::Less);
+                    if !sooner {
+                        continue;
+                    }
+                    release_at
+                }
+            };
+
+            NFT_CLAIMS.set_release_at(storage, staker, &claim.token_id, release_at)?;
+            shortened += 1;
+        }
+    }
+
+    match stakers.last() {
+        Some((staker, _)) if stakers.len() == limit => {
+            shortening.cursor = Some(staker.clone());
+            CLAIM_SHORTENING.save(storage, &shortening)?;
+        }
+        _ => CLAIM_SHORTENING.remove(storage),
+    }
+
+    Ok(shortened)
+}
+
+pub fn execute_add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let dao = DAO.load(deps.storage)?;
+
+    // Only the DAO can add a hook
+    if info.sender != dao {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.add_hook(deps.storage, hook.clone())?;
+
+    Ok(Response::default()
+        .add_event(hook_event(&hook, true))
+        .add_attribute("action", "add_hook")
+        .add_attribute("hook", addr))
+}
+
+pub fn execute_remove_hook(
     deps: DepsMut,
     info: MessageInfo,
     addr: String,
@@ -490,13 +1588,54 @@ pub fn execute_remove_hook(
     }
 
     let hook = deps.api.addr_validate(&addr)?;
-    HOOKS.remove_hook(deps.storage, hook)?;
+    HOOKS.remove_hook(deps.storage, hook.clone())?;
 
     Ok(Response::default()
+        .add_event(hook_event(&hook, false))
         .add_attribute("action", "remove_hook")
         .add_attribute("hook", addr))
 }
 
+pub fn execute_add_member_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let dao = DAO.load(deps.storage)?;
+
+    // Only the DAO can add a hook
+    if info.sender != dao {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    MEMBER_HOOKS.add_hook(deps.storage, hook)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "add_member_hook")
+        .add_attribute("hook", addr))
+}
+
+pub fn execute_remove_member_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let dao = DAO.load(deps.storage)?;
+
+    // Only the DAO can remove a hook
+    if info.sender != dao {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    MEMBER_HOOKS.remove_hook(deps.storage, hook)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "remove_member_hook")
+        .add_attribute("hook", addr))
+}
+
 pub fn execute_update_active_threshold(
     deps: DepsMut,
     _env: Env,
@@ -508,15 +1647,14 @@ pub fn execute_update_active_threshold(
         return Err(ContractError::Unauthorized {});
     }
 
-    let config = CONFIG.load(deps.storage)?;
     if let Some(active_threshold) = new_active_threshold {
         match active_threshold {
             ActiveThreshold::Percentage { percent } => {
                 assert_valid_percentage_threshold(percent)?;
             }
             ActiveThreshold::AbsoluteCount { count } => {
-                let nft_supply = query_onft_supply(deps.as_ref(), &config.onft_collection_id)?;
-                assert_valid_absolute_count_threshold(count, Uint128::new(nft_supply.into()))?;
+                let potential_power = query_potential_power(deps.as_ref())?;
+                assert_valid_absolute_count_threshold(count, potential_power)?;
             }
         }
         ACTIVE_THRESHOLD.save(deps.storage, &active_threshold)?;
@@ -527,21 +1665,105 @@ pub fn execute_update_active_threshold(
     Ok(Response::new().add_attribute("action", "update_active_threshold"))
 }
 
+pub fn execute_update_unique_stakers_threshold(
+    deps: DepsMut,
+    info: MessageInfo,
+    count: Option<u64>,
+) -> Result<Response, ContractError> {
+    let dao = DAO.load(deps.storage)?;
+    if info.sender != dao {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_valid_unique_stakers_threshold(count)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.unique_stakers_threshold = count;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_unique_stakers_threshold")
+        .add_attribute(
+            "count",
+            count
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+        ))
+}
+
+fn assert_valid_unique_stakers_threshold(count: Option<u64>) -> Result<(), ContractError> {
+    if count == Some(0) {
+        return Err(ContractError::ZeroUniqueStakersThreshold {});
+    }
+    Ok(())
+}
+
+/// Errors if any token ID appears more than once.
+fn assert_unique_token_ids(token_ids: &[String]) -> Result<(), ContractError> {
+    let mut seen = HashSet::with_capacity(token_ids.len());
+    for token_id in token_ids {
+        if !seen.insert(token_id) {
+            return Err(ContractError::DuplicateTokenId {
+                token_id: token_id.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::ActiveThreshold {} => query_active_threshold(deps),
         QueryMsg::Config {} => query_config(deps),
+        QueryMsg::Collections {} => query_collections(deps),
+        QueryMsg::TokenWeight { token_id } => query_token_weight(deps, token_id),
+        QueryMsg::TokenWeights { start_after, limit } => {
+            query_token_weights(deps, start_after, limit)
+        }
         QueryMsg::Dao {} => query_dao(deps),
         QueryMsg::Info {} => query_info(deps),
         QueryMsg::IsActive {} => query_is_active(deps, env),
         QueryMsg::NftClaims { address } => query_nft_claims(deps, address),
+        QueryMsg::NftClaimInfo { address } => query_nft_claim_info(deps, address),
+        QueryMsg::ExportClaims { start_after, limit } => {
+            query_export_claims(deps, start_after, limit)
+        }
         QueryMsg::Hooks {} => query_hooks(deps),
+        QueryMsg::MemberHooks {} => query_member_hooks(deps),
+        QueryMsg::ClaimCallback { address } => query_claim_callback(deps, address),
+        QueryMsg::UnstakeBudget {} => query_unstake_budget(deps, env),
+        QueryMsg::ClaimShortening {} => to_json_binary(&CLAIM_SHORTENING.may_load(deps.storage)?),
+        QueryMsg::QueuedUnstakes { address } => query_queued_unstakes(deps, address),
+        QueryMsg::VotingPowerProof { address, height } => {
+            query_voting_power_proof(deps, env, address, height)
+        }
+        QueryMsg::WeightedRandomStaker { seed } => query_weighted_random_staker(deps, seed),
+        QueryMsg::Delegation { address } => query_delegation(deps, address),
+        QueryMsg::Referrer { address } => query_referrer(deps, address),
+        QueryMsg::ReferralStats { address } => query_referral_stats(deps, address),
+        QueryMsg::ListReferralStats { start_after, limit } => {
+            query_list_referral_stats(deps, start_after, limit)
+        }
         QueryMsg::StakedNfts {
             address,
             start_after,
             limit,
         } => query_staked_nfts(deps, address, start_after, limit),
+        QueryMsg::StakedNftsInRange {
+            min,
+            max,
+            weight,
+            start_after,
+            limit,
+        } => query_staked_nfts_in_range(deps, min, max, weight, start_after, limit),
+        QueryMsg::OwnerOf { token_id, .. } => query_owner_of(deps, token_id),
+        QueryMsg::NumTokens {} => query_num_tokens(deps),
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => query_tokens(deps, owner, start_after, limit),
+        QueryMsg::AllTokens { start_after, limit } => query_all_tokens(deps, start_after, limit),
         QueryMsg::TotalPowerAtHeight { height } => query_total_power_at_height(deps, env, height),
         QueryMsg::VotingPowerAtHeight { address, height } => {
             query_voting_power_at_height(deps, env, address, height)
@@ -549,6 +1771,106 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
+pub fn query_unstake_budget(deps: Deps, env: Env) -> StdResult<Binary> {
+    let Some(unstake_budget) = UNSTAKE_BUDGET.may_load(deps.storage)? else {
+        return to_json_binary(&None::<UnstakeBudgetResponse>);
+    };
+    let epoch = unstake_budget.load_epoch(deps.storage, &env.block)?;
+    let queued = UNSTAKE_QUEUE_NEXT
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        - unstake_queue_head(deps.storage)?;
+
+    to_json_binary(&Some(UnstakeBudgetResponse {
+        budget: epoch.budget,
+        used: epoch.used,
+        next_epoch_starts: unstake_budget.next_epoch_starts(&env.block),
+        queued,
+        unstake_budget,
+    }))
+}
+
+pub fn query_queued_unstakes(deps: Deps, address: String) -> StdResult<Binary> {
+    let staker = validate_query_address(deps, &address)?;
+    let head = unstake_queue_head(deps.storage)?;
+
+    let mut queued = QUEUED_UNSTAKES
+        .prefix(&staker)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(token_id, sequence)| QueuedUnstakeResponse {
+                token_id,
+                position: sequence - head,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    queued.sort_by_key(|queued| queued.position);
+
+    to_json_binary(&queued)
+}
+
+pub fn query_weighted_random_staker(deps: Deps, seed: Binary) -> StdResult<Binary> {
+    let staker = sample_staker(deps.storage, seed.as_slice())?.map(|(address, staked_weight)| {
+        WeightedRandomStakerResponse {
+            address,
+            staked_weight,
+        }
+    });
+    to_json_binary(&staker)
+}
+
+pub fn query_delegation(deps: Deps, address: String) -> StdResult<Binary> {
+    let address = validate_query_address(deps, &address)?;
+    to_json_binary(&DelegationResponse {
+        delegate: DELEGATES.may_load(deps.storage, &address)?,
+        delegated_power: DELEGATED_POWER
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default(),
+    })
+}
+
+pub fn query_referrer(deps: Deps, address: String) -> StdResult<Binary> {
+    let staker = validate_query_address(deps, &address)?;
+    to_json_binary(&REFERRERS.may_load(deps.storage, &staker)?)
+}
+
+pub fn query_referral_stats(deps: Deps, address: String) -> StdResult<Binary> {
+    let referrer = validate_query_address(deps, &address)?;
+    to_json_binary(
+        &REFERRAL_STATS
+            .may_load(deps.storage, &referrer)?
+            .unwrap_or_default(),
+    )
+}
+
+pub fn query_list_referral_stats(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let start_after = start_after
+        .map(|address| validate_query_address(deps, &address))
+        .transpose()?;
+    let range = REFERRAL_STATS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(referrer, stats)| ReferrerStatsResponse { referrer, stats }));
+    let stats: StdResult<Vec<_>> = match limit {
+        Some(l) => range.take(l as usize).collect(),
+        None => range.collect(),
+    };
+    to_json_binary(&stats?)
+}
+
+pub fn query_claim_callback(deps: Deps, address: String) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    to_json_binary(&CLAIM_CALLBACKS.may_load(deps.storage, &address)?)
+}
+
 pub fn query_active_threshold(deps: Deps) -> StdResult<Binary> {
     to_json_binary(&ActiveThresholdResponse {
         active_threshold: ACTIVE_THRESHOLD.may_load(deps.storage)?,
@@ -556,13 +1878,19 @@ pub fn query_active_threshold(deps: Deps) -> StdResult<Binary> {
 }
 
 pub fn query_is_active(deps: Deps, env: Env) -> StdResult<Binary> {
+    if let Some(count) = CONFIG.load(deps.storage)?.unique_stakers_threshold {
+        let stakers = UNIQUE_STAKER_COUNT
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        if stakers < count {
+            return to_json_binary(&IsActiveResponse { active: false });
+        }
+    }
+
     let threshold = ACTIVE_THRESHOLD.may_load(deps.storage)?;
     if let Some(threshold) = threshold {
-        let config = CONFIG.load(deps.storage)?;
-        let staked_nfts = TOTAL_STAKED_NFTS
-            .may_load_at_height(deps.storage, env.block.height)?
-            .unwrap_or_default();
-        let total_nfts = query_onft_supply(deps, &config.onft_collection_id)?;
+        let staked_nfts = load_total_staked_nfts_at_height(deps.storage, env.block.height)?;
+        let total_nfts = query_potential_power(deps)?;
 
         match threshold {
             ActiveThreshold::AbsoluteCount { count } => to_json_binary(&IsActiveResponse {
@@ -578,13 +1906,13 @@ pub fn query_is_active(deps: Deps, env: Env) -> StdResult<Binary> {
                 // represents percents in u128 terms as p *
                 // 10^15. this bounds percent between [0, 10^17].
                 //
-                // total_potential_power is bounded between [0, 2^64]
-                // as it tracks the count of NFT tokens which has
-                // a max supply of 2^64.
+                // total_potential_power is bounded between [0, 2^128]
+                // as it is the weighted count of NFT tokens, which
+                // is a Uint128.
                 //
                 // with our precision factor being 10^9:
                 //
-                // total_nfts <= 2^64 * 10^9 <= 2^256
+                // total_nfts <= 2^128 * 10^9 <= 2^256
                 //
                 // so we're good to put that in a u256.
                 //
@@ -596,7 +1924,7 @@ pub fn query_is_active(deps: Deps, env: Env) -> StdResult<Binary> {
                 // rounding is rounding down, so the whole thing can
                 // be safely unwrapped at the end of the day thank you
                 // for coming to my ted talk.
-                let total_nfts_count = Uint128::from(total_nfts).full_mul(PRECISION_FACTOR);
+                let total_nfts_count = total_nfts.full_mul(PRECISION_FACTOR);
 
                 // under the hood decimals are `atomics / 10^decimal_places`.
                 // cosmwasm doesn't give us a Decimal * Uint256
@@ -627,52 +1955,171 @@ pub fn query_voting_power_at_height(
     address: String,
     height: Option<u64>,
 ) -> StdResult<Binary> {
-    let address = deps.api.addr_validate(&address)?;
+    let address = validate_query_address(deps, &address)?;
     let height = height.unwrap_or(env.block.height);
-    let power = NFT_BALANCES
-        .may_load_at_height(deps.storage, &address, height)?
-        .unwrap_or_default();
+    let power = voting_power(deps.storage, &address, Some(height))?;
     to_json_binary(&dao_interface::voting::VotingPowerAtHeightResponse { power, height })
 }
 
+pub fn query_voting_power_proof(
+    deps: Deps,
+    env: Env,
+    address: String,
+    height: Option<u64>,
+) -> StdResult<Binary> {
+    let address = validate_query_address(deps, &address)?;
+    let height = height.unwrap_or(env.block.height);
+
+    // snapshots loaded at `height` hold the values stored as of the
+    // start of that block, which are the values a proof of this
+    // contract's state at the previous height commits to.
+    let entry = |key: &[u8], value: Option<Uint128>| -> StdResult<(StorageEntry, Uint128)> {
+        Ok((
+            StorageEntry {
+                key: Binary::from(key),
+                value: value
+                    .map(|value| to_json_vec(&value))
+                    .transpose()?
+                    .map(Binary::from),
+            },
+            value.unwrap_or_default(),
+        ))
+    };
+    let (staked, staked_power) = entry(
+        &NFT_BALANCES.key(&address),
+        NFT_BALANCES.may_load_at_height(deps.storage, &address, height)?,
+    )?;
+    let (delegated_away, delegated_away_power) = entry(
+        &DELEGATED_AWAY.key(&address),
+        DELEGATED_AWAY.may_load_at_height(deps.storage, &address, height)?,
+    )?;
+    let (delegated, delegated_power) = entry(
+        &DELEGATED_POWER.key(&address),
+        DELEGATED_POWER.may_load_at_height(deps.storage, &address, height)?,
+    )?;
+    let (total, total_power) = entry(
+        TOTAL_STAKED_NFTS_KEY.as_bytes(),
+        TOTAL_STAKED_NFTS.may_load_at_height(deps.storage, height)?,
+    )?;
+
+    let power = staked_power
+        .saturating_sub(delegated_away_power)
+        .checked_add(delegated_power)?;
+    to_json_binary(&VotingPowerProofResponse {
+        height,
+        power,
+        total_power,
+        staked,
+        delegated_away,
+        delegated,
+        total,
+    })
+}
+
 pub fn query_total_power_at_height(deps: Deps, env: Env, height: Option<u64>) -> StdResult<Binary> {
     let height = height.unwrap_or(env.block.height);
-    let power = TOTAL_STAKED_NFTS
-        .may_load_at_height(deps.storage, height)?
-        .unwrap_or_default();
+    let power = load_total_staked_nfts_at_height(deps.storage, height)?;
     to_json_binary(&dao_interface::voting::TotalPowerAtHeightResponse { power, height })
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Binary> {
-    let config = CONFIG.load(deps.storage)?;
+    let config = CONFIG
+        .load(deps.storage)
+        .map_err(|err| StdError::generic_err(format!("failed to load config: {err}")))?;
     to_json_binary(&config)
 }
 
 pub fn query_dao(deps: Deps) -> StdResult<Binary> {
-    let dao = DAO.load(deps.storage)?;
+    let dao = DAO
+        .load(deps.storage)
+        .map_err(|err| StdError::generic_err(format!("failed to load DAO address: {err}")))?;
     to_json_binary(&dao)
 }
 
 pub fn query_nft_claims(deps: Deps, address: String) -> StdResult<Binary> {
-    to_json_binary(&NFT_CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
+    to_json_binary(&NFT_CLAIMS.query_claims(deps, &validate_query_address(deps, &address)?)?)
+}
+
+pub fn query_nft_claim_info(deps: Deps, address: String) -> StdResult<Binary> {
+    let address = validate_query_address(deps, &address)?;
+    let claim_info = CLAIM_INFO
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(token_id, claim_info)| NftClaimInfoResponse {
+                token_id,
+                claim_info,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&claim_info)
+}
+
+pub fn query_export_claims(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let start_after = start_after
+        .map(|address| validate_query_address(deps, &address))
+        .transpose()?;
+    let limit = limit.map_or(usize::MAX, |l| l as usize);
+
+    let claims = NFT_CLAIMS
+        .range_claims(deps.storage, start_after.as_ref(), limit)?
+        .into_iter()
+        .map(|(staker, nft_claims)| {
+            let claims = nft_claims
+                .into_iter()
+                .map(|claim| {
+                    let (collection_id, token_id) = config.parse_nft_key(&claim.token_id);
+                    Ok(ExportedClaim {
+                        collection_id: collection_id.to_string(),
+                        token_id: token_id.to_string(),
+                        release_at: claim.release_at,
+                        claim_info: CLAIM_INFO
+                            .may_load(deps.storage, (&staker, &claim.token_id))?,
+                    })
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            Ok(StakerClaims {
+                address: staker.into_string(),
+                claims,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&claims)
 }
 
 pub fn query_hooks(deps: Deps) -> StdResult<Binary> {
     to_json_binary(&HOOKS.query_hooks(deps)?)
 }
 
+pub fn query_member_hooks(deps: Deps) -> StdResult<Binary> {
+    to_json_binary(&MEMBER_HOOKS.query_hooks(deps)?)
+}
+
 pub fn query_info(deps: Deps) -> StdResult<Binary> {
     let info = cw2::get_contract_version(deps.storage)?;
     to_json_binary(&dao_interface::voting::InfoResponse { info })
 }
 
+/// Validates an address provided to a query, including the offending address
+/// in the error so callers can tell which input was rejected.
+fn validate_query_address(deps: Deps, address: &str) -> StdResult<Addr> {
+    deps.api
+        .addr_validate(address)
+        .map_err(|err| StdError::generic_err(format!("invalid address ({address}): {err}")))
+}
+
 pub fn query_staked_nfts(
     deps: Deps,
     address: String,
     start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<Binary> {
-    let prefix = deps.api.addr_validate(&address)?;
+    let prefix = validate_query_address(deps, &address)?;
     let prefix = STAKED_NFTS_PER_OWNER.prefix(&prefix);
 
     let start_after = start_after.as_deref().map(Bound::exclusive);
@@ -689,8 +2136,175 @@ pub fn query_staked_nfts(
     to_json_binary(&range?)
 }
 
+pub fn query_staked_nfts_in_range(
+    deps: Deps,
+    min: Option<String>,
+    max: Option<String>,
+    weight: Option<Uint128>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    // resume after `start_after`, unless it is below `min`.
+    let (start, inclusive) = match (min, start_after) {
+        (Some(min), Some(start_after)) if min > start_after => (Some(min), true),
+        (_, Some(start_after)) => (Some(start_after), false),
+        (min, None) => (min, true),
+    };
+    let start = start.as_deref().map(|key| match inclusive {
+        true => Bound::inclusive(key),
+        false => Bound::exclusive(key),
+    });
+    // NFT_STAKERS also contains NFTs that are pending claim, which
+    // are skipped.
+    let nfts = NFT_STAKERS
+        .range(
+            deps.storage,
+            start,
+            max.as_deref().map(Bound::exclusive),
+            Order::Ascending,
+        )
+        .filter_map(|item| {
+            let (token_id, staker) = match item {
+                Ok(item) => item,
+                Err(err) => return Some(Err(err)),
+            };
+            if !STAKED_NFTS_PER_OWNER.has(deps.storage, (&staker, token_id.as_str())) {
+                return None;
+            }
+            match nft_power(deps.storage, &config, &token_id) {
+                Ok(power) if weight.is_some_and(|weight| weight != power) => None,
+                Ok(power) => Some(Ok(StakedNftResponse {
+                    token_id,
+                    staker,
+                    weight: power,
+                })),
+                Err(err) => Some(Err(err)),
+            }
+        })
+        .take(limit.map_or(usize::MAX, |l| l as usize))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&nfts)
+}
+
+pub fn query_owner_of(deps: Deps, token_id: String) -> StdResult<Binary> {
+    let staker = NFT_STAKERS
+        .may_load(deps.storage, &token_id)?
+        .filter(|staker| STAKED_NFTS_PER_OWNER.has(deps.storage, (staker, &token_id)))
+        .ok_or_else(|| StdError::not_found(format!("staked NFT {token_id}")))?;
+    to_json_binary(&cw721::OwnerOfResponse {
+        owner: staker.into_string(),
+        approvals: vec![],
+    })
+}
+
+pub fn query_num_tokens(deps: Deps) -> StdResult<Binary> {
+    let count = STAKED_NFT_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    to_json_binary(&cw721::NumTokensResponse { count })
+}
+
+pub fn query_collections(deps: Deps) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let collections = COLLECTIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (collection_id, weight) = item?;
+            Ok(CollectionResponse {
+                unstaking_duration_override: COLLECTION_UNSTAKING_DURATIONS
+                    .may_load(deps.storage, &collection_id)?,
+                unstaking_duration: unstaking_duration(deps.storage, &config, &collection_id)?,
+                collection_id,
+                weight,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&collections)
+}
+
+pub fn query_token_weight(deps: Deps, token_id: String) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    to_json_binary(&nft_power(deps.storage, &config, &token_id)?)
+}
+
+pub fn query_token_weights(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let weights = TOKEN_WEIGHTS
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(token_id, weight)| TokenWeightResponse { token_id, weight }))
+        .take(limit.map_or(usize::MAX, |l| l as usize))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&weights)
+}
+
+pub fn query_tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let owner = validate_query_address(deps, &owner)?;
+    let tokens = STAKED_NFTS_PER_OWNER
+        .prefix(&owner)
+        .keys(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.map_or(usize::MAX, |l| l as usize))
+        .collect::<StdResult<Vec<String>>>()?;
+    to_json_binary(&cw721::TokensResponse { tokens })
+}
+
+pub fn query_all_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    // NFT_STAKERS also contains NFTs that are pending claim, which
+    // are skipped.
+    let tokens = NFT_STAKERS
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| match item {
+            Ok((token_id, staker)) => {
+                STAKED_NFTS_PER_OWNER.has(deps.storage, (staker, token_id.as_str()))
+            }
+            Err(_) => true,
+        })
+        .map(|item| item.map(|(token_id, _)| token_id))
+        .take(limit.map_or(usize::MAX, |l| l as usize))
+        .collect::<StdResult<Vec<String>>>()?;
+    to_json_binary(&cw721::TokensResponse { tokens })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        CLAIM_CALLBACK_REPLY_ID => match msg.result {
+            SubMsgResult::Err(err) => Ok(Response::new()
+                .add_attribute("action", "claim_callback_failed")
+                .add_attribute("error", err)),
+            SubMsgResult::Ok(_) => Ok(Response::default()),
+        },
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let storage_version: ContractVersion = get_contract_version(deps.storage)?;
 
     // Only migrate if newer
@@ -699,5 +2313,233 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
         set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     }
 
-    Ok(Response::new().add_attribute("action", "migrate"))
+    // Upgrade the state layout one version at a time. Each step may be
+    // paginated, in which case the migration must be run again to
+    // continue from where it left off.
+    let limit = msg.limit.unwrap_or(DEFAULT_MIGRATION_LIMIT).max(1) as usize;
+    let mut state_version = STATE_VERSION.may_load(deps.storage)?.unwrap_or(1);
+    // Collections are recorded before any paginated step, as staking and
+    // unstaking between pages look up the collection of each NFT.
+    if state_version < 5 {
+        backfill_collections(deps.storage)?;
+    }
+    if state_version == 1 && backfill_claim_info(deps.storage, limit)? {
+        state_version = 2;
+        STATE_VERSION.save(deps.storage, &state_version)?;
+    }
+    if state_version == 2 && backfill_nft_stakers(deps.branch(), limit)? {
+        state_version = 3;
+        STATE_VERSION.save(deps.storage, &state_version)?;
+    }
+    if state_version == 3 && backfill_staker_weights(deps.storage, limit)? {
+        state_version = 4;
+        STATE_VERSION.save(deps.storage, &state_version)?;
+    }
+    if state_version == 4 {
+        state_version = 5;
+        STATE_VERSION.save(deps.storage, &state_version)?;
+    }
+    if state_version == 5 && backfill_unique_stakers(deps.storage, limit)? {
+        state_version = 6;
+        STATE_VERSION.save(deps.storage, &state_version)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("state_version", state_version.to_string())
+        .add_attribute(
+            "complete",
+            (state_version == CURRENT_STATE_VERSION).to_string(),
+        ))
+}
+
+/// Backfills claim info for the pending claims of up to `limit` stakers,
+/// continuing from the last migrated staker. Returns true once all stakers
+/// have been migrated.
+fn backfill_claim_info(storage: &mut dyn Storage, limit: usize) -> StdResult<bool> {
+    let start_after = MIGRATION_CURSOR.may_load(storage)?;
+    let claims = NFT_CLAIMS.range_claims(storage, start_after.as_ref(), limit)?;
+
+    let Some((last, _)) = claims.last() else {
+        MIGRATION_CURSOR.remove(storage);
+        return Ok(true);
+    };
+    MIGRATION_CURSOR.save(storage, last)?;
+
+    // Pending claims are assumed to have been created with the current
+    // unstaking duration, which is used to recover when they were unstaked.
+    let unstaking_duration = CONFIG.load(storage)?.unstaking_duration;
+    for (staker, nft_claims) in &claims {
+        for claim in nft_claims {
+            if CLAIM_INFO.has(storage, (staker, &claim.token_id)) {
+                continue;
+            }
+            let claim_info = match (unstaking_duration, claim.release_at) {
+                (Some(Duration::Height(duration)), Expiration::AtHeight(height)) => ClaimInfo {
+                    duration: Duration::Height(duration),
+                    unstaked_at_height: height.saturating_sub(duration),
+                    unstaked_at_time: Timestamp::default(),
+                },
+                (Some(Duration::Time(duration)), Expiration::AtTime(time)) => ClaimInfo {
+                    duration: Duration::Time(duration),
+                    unstaked_at_height: 0,
+                    unstaked_at_time: Timestamp::from_seconds(
+                        time.seconds().saturating_sub(duration),
+                    ),
+                },
+                // claims in a different unit than the current unstaking
+                // duration can not be recovered, and are left without claim
+                // info as before.
+                _ => continue,
+            };
+            CLAIM_INFO.save(storage, (staker, &claim.token_id), &claim_info)?;
+        }
+    }
+
+    if claims.len() < limit {
+        MIGRATION_CURSOR.remove(storage);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Records the staker of the staked and pending claim NFTs of up to `limit`
+/// stakers, continuing from the last migrated staker. Returns true once all
+/// stakers have been migrated.
+fn backfill_nft_stakers(deps: DepsMut, limit: usize) -> StdResult<bool> {
+    // every address that has staked has a balance entry, even once it has
+    // unstaked everything, so this includes stakers with only pending claims.
+    let start_after = MIGRATION_CURSOR.may_load(deps.storage)?;
+    let stakers = NFT_BALANCES
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    let Some(last) = stakers.last() else {
+        MIGRATION_CURSOR.remove(deps.storage);
+        return Ok(true);
+    };
+    MIGRATION_CURSOR.save(deps.storage, last)?;
+
+    for staker in &stakers {
+        let mut token_ids = STAKED_NFTS_PER_OWNER
+            .prefix(staker)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<String>>>()?;
+        token_ids.extend(
+            NFT_CLAIMS
+                .query_claims(deps.as_ref(), staker)?
+                .nft_claims
+                .into_iter()
+                .map(|claim| claim.token_id),
+        );
+        for token_id in &token_ids {
+            NFT_STAKERS.save(deps.storage, token_id, staker)?;
+        }
+    }
+
+    if stakers.len() < limit {
+        MIGRATION_CURSOR.remove(deps.storage);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Tracks the voting power of up to `limit` stakers in `STAKER_WEIGHTS`,
+/// continuing from the last migrated staker. Returns true once all stakers
+/// have been migrated.
+fn backfill_staker_weights(storage: &mut dyn Storage, limit: usize) -> StdResult<bool> {
+    let start_after = MIGRATION_CURSOR.may_load(storage)?;
+    let balances = NFT_BALANCES
+        .range(
+            storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<(Addr, Uint128)>>>()?;
+
+    let Some((last, _)) = balances.last() else {
+        MIGRATION_CURSOR.remove(storage);
+        return Ok(true);
+    };
+    MIGRATION_CURSOR.save(storage, last)?;
+
+    // stakers that staked or unstaked after the contract was migrated,
+    // but before they were backfilled, may already have some voting power
+    // tracked, so only the difference is applied.
+    for (staker, balance) in &balances {
+        let weight = staker_weight(storage, staker)?;
+        if *balance > weight {
+            add_staker_weight(storage, staker, *balance - weight)?;
+        } else if weight > *balance {
+            sub_staker_weight(storage, staker, weight - *balance)?;
+        }
+    }
+
+    if balances.len() < limit {
+        MIGRATION_CURSOR.remove(storage);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Records the collection of deployments from before multiple
+/// collections were supported, whose NFTs each have a voting power of
+/// one, and the number of NFTs staked with them. Does nothing once the
+/// collection has been recorded, after which stakes are counted as they
+/// are made.
+fn backfill_collections(storage: &mut dyn Storage) -> StdResult<()> {
+    let config = CONFIG.load(storage)?;
+    if COLLECTIONS.has(storage, &config.onft_collection_id) {
+        return Ok(());
+    }
+    COLLECTIONS.save(storage, &config.onft_collection_id, &Uint128::one())?;
+
+    let staked = TOTAL_STAKED_NFTS.may_load(storage)?.unwrap_or_default();
+    STAKED_NFT_COUNT.save(storage, &(staked.u128() as u64))
+}
+
+/// Tracks up to `limit` stakers with staked NFTs in `UNIQUE_STAKERS`,
+/// continuing from the last migrated staker. Returns true once all
+/// stakers have been migrated.
+fn backfill_unique_stakers(storage: &mut dyn Storage, limit: usize) -> StdResult<bool> {
+    let start_after = MIGRATION_CURSOR.may_load(storage)?;
+    let stakers = NFT_BALANCES
+        .keys(
+            storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    let Some(last) = stakers.last() else {
+        MIGRATION_CURSOR.remove(storage);
+        return Ok(true);
+    };
+    MIGRATION_CURSOR.save(storage, last)?;
+
+    // stakers that staked or unstaked after the contract was migrated
+    // are already tracked, which syncing leaves as is.
+    for staker in &stakers {
+        sync_unique_staker(storage, staker)?;
+    }
+
+    if stakers.len() < limit {
+        MIGRATION_CURSOR.remove(storage);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
 }
@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use cw_utils::Expiration;
 use dao_voting::threshold::ActiveThresholdError;
 use thiserror::Error;
 
@@ -19,30 +20,118 @@ pub enum ContractError {
     #[error("Nothing to claim")]
     NothingToClaim {},
 
-    #[error("Only an NFT's owner can prepare it to be staked")]
-    OnlyOwnerCanPrepareStake {},
+    #[error(
+        "Pending claims are still being shortened. Execute ShortenClaims until they are done first"
+    )]
+    ClaimShorteningInProgress {},
 
-    #[error("NFTs must be prepared and transferred before they can be staked")]
-    StakeMustBePrepared {},
+    #[error("No NFTs are ready to be claimed yet. The next claim is ready at {ready_at}")]
+    ClaimNotMatured { ready_at: Expiration },
 
-    #[error("Recipient must be set when the DAO is cancelling a stake that was not prepared")]
-    NoRecipient {},
+    #[error("Only an NFT's owner can prepare it to be staked (not the owner of {token_id})")]
+    NotTokenOwner { token_id: String },
 
-    #[error("Only the owner or preparer can cancel a prepared stake")]
-    NotPreparerNorOwner {},
+    #[error("NFT {token_id} is already staked")]
+    AlreadyStaked { token_id: String },
+
+    #[error("NFT {token_id} does not exist in collection {collection_id}")]
+    CollectionMismatch {
+        collection_id: String,
+        token_id: String,
+    },
+
+    #[error("NFT {token_id} was included more than once")]
+    DuplicateTokenId { token_id: String },
+
+    #[error("NFT {token_id} must be prepared by the sender before it can be staked")]
+    StakeNotPrepared { token_id: String },
+
+    #[error("NFT {token_id} must be transferred to the staking contract before it can be staked")]
+    StakeNotTransferred { token_id: String },
+
+    #[error("Recipient must be set when the DAO is cancelling a stake that was not prepared (cancelling {token_id})")]
+    NoRecipient { token_id: String },
+
+    #[error("NFT {token_id} is staked or pending claim, so its stake can not be cancelled")]
+    CancelStaked { token_id: String },
+
+    #[error("Only the owner or preparer can cancel a prepared stake (cancelling {token_id})")]
+    NotPreparerNorOwner { token_id: String },
 
     #[error("Can not unstake that which you have not staked (unstaking {token_id})")]
     NotStaked { token_id: String },
 
+    #[error("Unstaking is locked until {until} while governance is bootstrapped")]
+    UnstakingLocked { until: Expiration },
+
+    #[error("NFT {token_id} is already queued to be unstaked")]
+    AlreadyQueued { token_id: String },
+
+    #[error(
+        "Unstake budget must have a non-zero epoch and a max_percent greater than 0 and at most 1"
+    )]
+    InvalidUnstakeBudget {},
+
+    #[error("At least one collection must be staked")]
+    NoCollections {},
+
+    #[error("Collection {collection_id} was included more than once")]
+    DuplicateCollection { collection_id: String },
+
+    #[error("Collection {collection_id} must have a non-zero weight")]
+    ZeroWeight { collection_id: String },
+
+    #[error("NFT {token_id} must have a non-zero weight")]
+    ZeroTokenWeight { token_id: String },
+
+    #[error("Collection {collection_id} can not be staked with this module")]
+    UnknownCollection { collection_id: String },
+
+    #[error("This voting module has been dissolved")]
+    Dissolved {},
+
+    #[error("Can not refer yourself")]
+    SelfReferral {},
+
+    #[error("A referrer may only be named on an address's first stake")]
+    ReferralAfterFirstStake {},
+
+    #[error("Can not delegate to yourself")]
+    SelfDelegation {},
+
+    #[error("Voting power is already delegated to {delegate}")]
+    AlreadyDelegated { delegate: String },
+
+    #[error("Voting power is not delegated")]
+    NotDelegated {},
+
     #[error("Too many outstanding claims. Claim some tokens before unstaking more.")]
     TooManyClaims {},
 
+    #[error("NFT {token_id} must be owned by this module before its claim can be imported")]
+    ClaimNotTransferred { token_id: String },
+
+    #[error("Expected to import {expected} claims, but got {actual}")]
+    ImportTotalMismatch { expected: u64, actual: u64 },
+
+    #[error("Only smart contracts can register a claim callback")]
+    NotContract {},
+
+    #[error("{staker} has not registered a claim callback, so only it can claim its NFTs")]
+    NoClaimCallback { staker: String },
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Unique stakers threshold must be greater than zero")]
+    ZeroUniqueStakersThreshold {},
+
     #[error("Got a submessage reply with unknown id: {id}")]
     UnknownReplyId { id: u64 },
 
+    #[error("Can't stake zero NFTs.")]
+    ZeroStake {},
+
     #[error("Can't unstake zero NFTs.")]
     ZeroUnstake {},
 }
@@ -0,0 +1,125 @@
+//! Events emitted as NFTs move through staking. Unlike the attributes
+//! of each response, whose `action`s differ between single and batched
+//! messages, these events and their attribute keys are stable across
+//! versions of this contract, so that indexers can follow stakes with a
+//! single parser.
+//!
+//! Every NFT is reported with its own event, emitted by the chain with
+//! a `wasm-` prefix (e.g. `wasm-onft_stake`).
+//!
+//! | Event                 | Attributes                                                                       |
+//! |-----------------------|----------------------------------------------------------------------------------|
+//! | `onft_stake`          | `staker`, `collection`, `token_id`, `new_power`, `total_power`                   |
+//! | `onft_unstake_queued` | `staker`, `collection`, `token_id`                                               |
+//! | `onft_unstake`        | `staker`, `collection`, `token_id`, `new_power`, `total_power`, `claim_duration` |
+//! | `onft_claim`          | `staker`, `collection`, `token_id`                                               |
+//! | `onft_hook_added`     | `hook`                                                                           |
+//! | `onft_hook_removed`   | `hook`                                                                           |
+//!
+//! `new_power` is the voting power of the NFTs the staker has staked
+//! after the change, before any delegation, and `total_power` the
+//! voting power of all staked NFTs. Both take effect the following
+//! block. `claim_duration` is the unstaking duration of the NFT's
+//! claim, or `none` if it was returned right away.
+
+use cosmwasm_std::{Addr, Event, StdResult, Storage};
+use cw_utils::Duration;
+
+use crate::state::{Config, NFT_BALANCES, TOTAL_STAKED_NFTS};
+
+pub const STAKE_EVENT: &str = "onft_stake";
+pub const UNSTAKE_QUEUED_EVENT: &str = "onft_unstake_queued";
+pub const UNSTAKE_EVENT: &str = "onft_unstake";
+pub const CLAIM_EVENT: &str = "onft_claim";
+pub const HOOK_ADDED_EVENT: &str = "onft_hook_added";
+pub const HOOK_REMOVED_EVENT: &str = "onft_hook_removed";
+
+pub const STAKER_KEY: &str = "staker";
+pub const COLLECTION_KEY: &str = "collection";
+pub const TOKEN_ID_KEY: &str = "token_id";
+pub const NEW_POWER_KEY: &str = "new_power";
+pub const TOTAL_POWER_KEY: &str = "total_power";
+pub const CLAIM_DURATION_KEY: &str = "claim_duration";
+pub const HOOK_KEY: &str = "hook";
+
+/// An event of type `ty` for the NFT stored under `key`.
+fn nft_event(ty: &str, config: &Config, staker: &Addr, key: &str) -> Event {
+    let (collection_id, token_id) = config.parse_nft_key(key);
+    Event::new(ty)
+        .add_attribute(STAKER_KEY, staker)
+        .add_attribute(COLLECTION_KEY, collection_id)
+        .add_attribute(TOKEN_ID_KEY, token_id)
+}
+
+/// Adds the staker's voting power and the total power, as they are
+/// after the change, to `event`.
+fn with_power(storage: &dyn Storage, staker: &Addr, event: Event) -> StdResult<Event> {
+    let new_power = NFT_BALANCES.may_load(storage, staker)?.unwrap_or_default();
+    let total_power = TOTAL_STAKED_NFTS.may_load(storage)?.unwrap_or_default();
+    Ok(event
+        .add_attribute(NEW_POWER_KEY, new_power)
+        .add_attribute(TOTAL_POWER_KEY, total_power))
+}
+
+/// The events of `staker` staking the NFTs stored under `keys`, once
+/// the stake has been registered.
+pub(crate) fn stake_events(
+    storage: &dyn Storage,
+    config: &Config,
+    staker: &Addr,
+    keys: &[String],
+) -> StdResult<Vec<Event>> {
+    keys.iter()
+        .map(|key| with_power(storage, staker, nft_event(STAKE_EVENT, config, staker, key)))
+        .collect()
+}
+
+/// The events of `staker` queueing the NFTs stored under `keys` to be
+/// unstaked once the unstake budget allows.
+pub(crate) fn unstake_queued_events(config: &Config, staker: &Addr, keys: &[String]) -> Vec<Event> {
+    keys.iter()
+        .map(|key| nft_event(UNSTAKE_QUEUED_EVENT, config, staker, key))
+        .collect()
+}
+
+/// The events of `staker` unstaking the NFTs stored under `keys`, whose
+/// claims have an unstaking duration of `duration`, once the unstake
+/// has been registered.
+pub(crate) fn unstake_events(
+    storage: &dyn Storage,
+    config: &Config,
+    staker: &Addr,
+    keys: &[String],
+    duration: Option<Duration>,
+) -> StdResult<Vec<Event>> {
+    let claim_duration = duration
+        .map(|duration| duration.to_string())
+        .unwrap_or_else(|| "none".to_string());
+    keys.iter()
+        .map(|key| {
+            with_power(
+                storage,
+                staker,
+                nft_event(UNSTAKE_EVENT, config, staker, key),
+            )
+            .map(|event| event.add_attribute(CLAIM_DURATION_KEY, &claim_duration))
+        })
+        .collect()
+}
+
+/// The events of `staker` claiming the NFTs stored under `keys`.
+pub(crate) fn claim_events(config: &Config, staker: &Addr, keys: &[String]) -> Vec<Event> {
+    keys.iter()
+        .map(|key| nft_event(CLAIM_EVENT, config, staker, key))
+        .collect()
+}
+
+/// The event of `hook` being added, or removed if not `added`.
+pub(crate) fn hook_event(hook: &Addr, added: bool) -> Event {
+    let ty = if added {
+        HOOK_ADDED_EVENT
+    } else {
+        HOOK_REMOVED_EVENT
+    };
+    Event::new(ty).add_attribute(HOOK_KEY, hook)
+}
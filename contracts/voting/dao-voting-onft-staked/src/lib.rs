@@ -2,6 +2,7 @@
 
 pub mod contract;
 mod error;
+pub mod events;
 pub mod msg;
 mod omniflix;
 pub mod state;
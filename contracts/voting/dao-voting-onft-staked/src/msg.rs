@@ -1,5 +1,6 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cw_utils::Duration;
+use cosmwasm_std::{Addr, Binary, Uint128};
+use cw_utils::{Duration, Expiration};
 use dao_dao_macros::{active_query, voting_module_query};
 use dao_voting::threshold::{ActiveThreshold, ActiveThresholdResponse};
 
@@ -13,16 +14,87 @@ pub enum OnftCollection {
     },
 }
 
+#[cw_serde]
+pub struct WeightedOnftCollection {
+    pub collection: OnftCollection,
+    /// The voting power of each NFT staked from this collection.
+    pub weight: Uint128,
+    /// Overrides the module's `unstaking_duration` for NFTs staked
+    /// from this collection. If `None`, the module's default applies.
+    #[serde(default)]
+    pub unstaking_duration: Option<Duration>,
+}
+
 #[cw_serde]
 pub struct InstantiateMsg {
-    /// ONFT collection that will be staked.
-    pub onft_collection: OnftCollection,
+    /// ONFT collections that may be staked, with the voting power of
+    /// each NFT staked from them. The first collection is the
+    /// default, which NFTs are staked from when a message does not
+    /// specify a collection.
+    pub onft_collections: Vec<WeightedOnftCollection>,
     /// Amount of time between unstaking and tokens being available. To unstake
     /// with no delay, leave as `None`.
     pub unstaking_duration: Option<Duration>,
-    /// The number or percentage of tokens that must be staked for the DAO to be
-    /// active
+    /// The voting power or percentage of the potential voting power (the
+    /// weighted supply of every collection) that must be staked for the
+    /// DAO to be active
     pub active_threshold: Option<ActiveThreshold>,
+    /// Amount of time after instantiation during which no NFTs may be
+    /// unstaked, regardless of the unstaking duration, to stabilize
+    /// governance while the DAO is bootstrapped. To allow unstaking from the
+    /// start, leave as `None`.
+    pub bootstrap_period: Option<Duration>,
+    /// If set, limits the number of NFTs that may be unstaked each
+    /// epoch to smooth changes in voting power. Unstakes beyond the
+    /// budget are queued for later epochs, and keep their voting
+    /// power until they are processed.
+    pub unstake_budget: Option<crate::state::UnstakeBudget>,
+    /// If set, the number of distinct addresses that must have NFTs
+    /// staked for the DAO to be active, in addition to the active
+    /// threshold.
+    pub unique_stakers_threshold: Option<u64>,
+}
+
+/// NFTs from one collection, in messages that may include NFTs from
+/// several collections.
+#[cw_serde]
+pub struct CollectionTokenIds {
+    /// The collection the NFTs are from, or the default collection if
+    /// `None`.
+    pub collection_id: Option<String>,
+    pub token_ids: Vec<String>,
+}
+
+/// The voting power of an individual NFT, overriding its
+/// collection's weight.
+#[cw_serde]
+pub struct TokenWeight {
+    /// The collection the NFT is from, or the default collection if
+    /// `None`.
+    pub collection_id: Option<String>,
+    pub token_id: String,
+    /// The voting power of the NFT, or `None` to remove its override
+    /// so that its collection's weight applies.
+    pub weight: Option<Uint128>,
+}
+
+#[cw_serde]
+pub enum UnstakingDurationPolicy {
+    /// The new unstaking duration only applies to NFTs unstaked after the
+    /// update. Pending claims keep the duration they were created with.
+    NewClaimsOnly {},
+    /// The new unstaking duration also applies to pending claims, measured
+    /// from when they were unstaked, if it would release them sooner. Claims
+    /// whose duration is in a different unit (height vs. time) than the new
+    /// duration are left unchanged, unless the new duration is `None`, in
+    /// which case all pending claims are released immediately. Claims with
+    /// no claim info, such as imported ones, are released at most the new
+    /// duration after the update.
+    ///
+    /// The claims of up to 30 stakers are shortened right away. If there
+    /// are more, the rest are shortened with `ShortenClaims`, and no other
+    /// `ShortenExisting` update may be made until they are done.
+    ShortenExisting {},
 }
 
 #[cw_serde]
@@ -39,7 +111,13 @@ pub enum ExecuteMsg {
     ///
     /// PrepareStake overrides any previous PrepareStake calls, as long as the
     /// sender owns the NFT(s).
-    PrepareStake { token_ids: Vec<String> },
+    ///
+    /// The NFTs are from `collection_id`, or the default collection if
+    /// `None`.
+    PrepareStake {
+        token_ids: Vec<String>,
+        collection_id: Option<String>,
+    },
     /// Step 3/3 of the NFT staking process. x/onft doesn't support executing a
     /// smart contract on NFT transfer like cw721s do, so the stake process is
     /// broken up:
@@ -49,7 +127,24 @@ pub enum ExecuteMsg {
     /// 2. The sender then transfers the NFT(s) to the staking contract.
     /// 3. The sender calls `ConfirmStake` on this staking contract which
     ///    confirms the NFTs were transferred to it and registers the stake.
-    ConfirmStake { token_ids: Vec<String> },
+    ///
+    /// On the sender's first stake, they may name the address that referred
+    /// them as `referrer`.
+    ConfirmStake {
+        token_ids: Vec<String>,
+        collection_id: Option<String>,
+        referrer: Option<String>,
+    },
+    /// Prepares NFTs from any number of collections to be staked, like
+    /// `PrepareStake`, in one message.
+    PrepareStakeMany { token_ids: Vec<CollectionTokenIds> },
+    /// Confirms the stake of NFTs from any number of collections, like
+    /// `ConfirmStake`, in one message. The sender's voting power and the
+    /// total voting power are updated once for all of the NFTs.
+    ConfirmStakeMany {
+        token_ids: Vec<CollectionTokenIds>,
+        referrer: Option<String>,
+    },
     /// CancelStake serves as an undo function in case an NFT or stake gets into
     /// a bad state, either because the stake process was never completed, or
     /// because someone sent an NFT to the staking contract without preparing
@@ -70,40 +165,193 @@ pub enum ExecuteMsg {
     /// only the DAO will be able to correct this and send them somewhere.
     CancelStake {
         token_ids: Vec<String>,
+        collection_id: Option<String>,
         recipient: Option<String>,
     },
-    /// Unstakes the specified token_ids on behalf of the sender. token_ids must
-    /// have unique values and have non-zero length.
-    Unstake { token_ids: Vec<String> },
+    /// Unstakes the specified token_ids from `collection_id`, or the default
+    /// collection if `None`, on behalf of the sender. token_ids must have
+    /// unique values and have non-zero length.
+    Unstake {
+        token_ids: Vec<String>,
+        collection_id: Option<String>,
+    },
+    /// Unstakes NFTs from any number of collections, like `Unstake`, in
+    /// one message. The sender's voting power and the total voting power
+    /// are updated once for all of the NFTs.
+    UnstakeMany { token_ids: Vec<CollectionTokenIds> },
+    /// Unstakes queued NFTs, in the order they were queued, while the
+    /// current epoch's unstake budget allows. Anyone may call this.
+    /// Processes up to `limit` (default 30) queued unstakes.
+    ProcessUnstakeQueue { limit: Option<u32> },
+    /// Applies an unstaking duration update made with the
+    /// `ShortenExisting` policy to the claims of up to `limit` (default
+    /// 30) more stakers. Anyone may call this.
+    ShortenClaims { limit: Option<u32> },
     /// Claim NFTs that have been unstaked for the specified duration.
     ClaimNfts {},
+    /// Claim NFTs that have been unstaked for the specified duration on behalf
+    /// of a staker that has registered a claim callback. Anyone may call this,
+    /// so that contracts staking NFTs do not need to poll for their claims to
+    /// mature. The NFTs are always sent to the staker.
+    ClaimNftsFor { staker: String },
+    /// Registers a callback that is executed on a contract whenever the
+    /// sender's NFTs are claimed, or removes it if `None`. Only smart contracts
+    /// (e.g. vaults) may register a callback. A failing callback does not
+    /// prevent the NFTs from being claimed.
+    SetClaimCallback { callback: Option<ClaimCallback> },
     /// Updates the contract configuration, namely unstaking duration. Only
     /// callable by the DAO that initialized this voting contract.
-    UpdateConfig { duration: Option<Duration> },
+    UpdateConfig {
+        duration: Option<Duration>,
+        /// How the new duration applies to pending claims. Defaults to
+        /// `NewClaimsOnly`.
+        policy: Option<UnstakingDurationPolicy>,
+    },
+    /// Overrides the unstaking duration of NFTs staked from
+    /// `collection_id`, or removes its override if `duration` is `None`
+    /// so that the module's default applies. Only callable by the DAO
+    /// that initialized this voting contract.
+    UpdateCollectionUnstakingDuration {
+        collection_id: String,
+        duration: Option<Duration>,
+        /// How the new duration applies to the collection's pending
+        /// claims. Defaults to `NewClaimsOnly`.
+        policy: Option<UnstakingDurationPolicy>,
+    },
+    /// Overrides the voting power of individual NFTs, so that rare NFTs
+    /// may count for more than the rest of their collection. The voting
+    /// power of NFTs that are already staked is updated immediately, and
+    /// member hooks are sent a cw4 `MemberChangedHook` with the old and new
+    /// voting power of their stakers. Only callable by the DAO that initialized
+    /// this voting contract.
+    SetTokenWeights { weights: Vec<TokenWeight> },
     /// Adds a hook which is called on staking / unstaking events. Only callable
     /// by the DAO that initialized this voting contract.
     AddHook { addr: String },
     /// Removes a hook which is called on staking / unstaking events. Only
     /// callable by the DAO that initialized this voting contract.
     RemoveHook { addr: String },
+    /// Adds a hook which is sent a cw4 `MemberChangedHook` when voting power
+    /// changes without staking or unstaking, such as by delegation. Only
+    /// callable by the DAO that initialized this voting contract.
+    AddMemberHook { addr: String },
+    /// Removes a member hook. Only callable by the DAO that initialized this
+    /// voting contract.
+    RemoveMemberHook { addr: String },
     /// Sets the active threshold to a new value. Only callable by the DAO that
     /// initialized this voting contract.
     UpdateActiveThreshold {
         new_threshold: Option<ActiveThreshold>,
     },
+    /// Sets the number of distinct addresses that must have NFTs
+    /// staked for the DAO to be active, or removes the requirement if
+    /// `None`. Only callable by the DAO that initialized this voting
+    /// contract.
+    UpdateUniqueStakersThreshold { count: Option<u64> },
+    /// Winds down staking for the DAO. No NFTs may be staked afterwards,
+    /// the unstaking duration, bootstrap period and unstake budget are
+    /// waived so that staked NFTs are returned as soon as they are
+    /// unstaked, and all pending claims may be claimed immediately. This
+    /// can not be undone. Only callable by the DAO that initialized this
+    /// voting contract.
+    Dissolve {},
+    /// Delegates the voting power of the sender's staked NFTs, including
+    /// NFTs staked later, to `delegate`, replacing any previous delegate.
+    /// Voting power delegated to the sender is not delegated onwards.
+    Delegate { delegate: String },
+    /// Stops delegating the sender's voting power, returning it to the
+    /// sender.
+    Undelegate {},
+    /// Imports pending claims exported from another voting module with
+    /// the `ExportClaims` query, so that NFTs being unstaked there may be
+    /// claimed from this module. This module must already own every
+    /// imported NFT, none of which may be staked or pending claim here.
+    /// `total` must be the number of NFTs in `claims`. Only callable by
+    /// the DAO that initialized this voting contract.
+    ImportClaims {
+        claims: Vec<StakerClaims>,
+        total: u64,
+    },
+}
+
+/// An address's pending claims, as exported by `ExportClaims`.
+#[cw_serde]
+pub struct StakerClaims {
+    pub address: String,
+    pub claims: Vec<ExportedClaim>,
+}
+
+#[cw_serde]
+pub struct ExportedClaim {
+    pub collection_id: String,
+    pub token_id: String,
+    pub release_at: Expiration,
+    /// The unstaking duration that applied to the claim, if known.
+    pub claim_info: Option<crate::state::ClaimInfo>,
+}
+
+#[cw_serde]
+pub struct ClaimCallback {
+    /// The contract the callback is executed on.
+    pub contract: String,
+    /// A message passed through to the callback contract.
+    pub msg: Binary,
+}
+
+/// The message executed on a staker's claim callback contract when its NFTs
+/// are claimed.
+#[cw_serde]
+pub enum ClaimCallbackMsg {
+    NftsClaimed {
+        staker: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    },
 }
 
+/// NFTs from collections other than the default are identified by
+/// `{collection_id}/{token_id}` in queries, claims and hooks.
 #[active_query]
 #[voting_module_query]
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
+    /// The module's config, including whether it has been dissolved.
     #[returns(crate::state::Config)]
     Config {},
+    /// The collections NFTs may be staked from, the voting power of
+    /// each NFT staked from them, and their unstaking durations.
+    #[returns(Vec<CollectionResponse>)]
+    Collections {},
+    /// The voting power of an NFT, whether or not it is staked, which
+    /// is its override if it has one and its collection's weight
+    /// otherwise.
+    #[returns(Uint128)]
+    TokenWeight { token_id: String },
+    /// Lists the NFTs whose weight is overridden, with their voting
+    /// power.
+    #[returns(Vec<TokenWeightResponse>)]
+    TokenWeights {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(::cw721_controllers::NftClaimsResponse)]
     NftClaims { address: String },
+    /// The unstaking duration that applied to each of an address's pending
+    /// claims, and when the NFTs were unstaked.
+    #[returns(Vec<NftClaimInfoResponse>)]
+    NftClaimInfo { address: String },
+    /// Lists every address's pending claims, in the form taken by
+    /// `ImportClaims`, for carrying them over to another voting module.
+    #[returns(Vec<StakerClaims>)]
+    ExportClaims {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(::cw_controllers::HooksResponse)]
     Hooks {},
+    #[returns(::cw_controllers::HooksResponse)]
+    MemberHooks {},
     // List the staked NFTs for a given address.
     #[returns(Vec<String>)]
     StakedNfts {
@@ -113,7 +361,207 @@ pub enum QueryMsg {
     },
     #[returns(ActiveThresholdResponse)]
     ActiveThreshold {},
+    /// The unstake budget and the state of the current epoch, if an
+    /// unstake budget is set.
+    #[returns(Option<UnstakeBudgetResponse>)]
+    UnstakeBudget {},
+    /// The unstaking duration update being applied to pending claims, if
+    /// some stakers' claims have yet to be shortened.
+    #[returns(Option<crate::state::ClaimShortening>)]
+    ClaimShortening {},
+    /// The NFTs an address has queued to be unstaked, and their
+    /// position in the queue.
+    #[returns(Vec<QueuedUnstakeResponse>)]
+    QueuedUnstakes { address: String },
+    /// An address's voting power and the total power at `height`, or
+    /// the current height if `None`, along with the entries of this
+    /// contract's storage they are derived from, so that other chains
+    /// can verify them with interchain queries of this contract's state.
+    #[returns(VotingPowerProofResponse)]
+    VotingPowerProof {
+        address: String,
+        height: Option<u64>,
+    },
+    /// Selects a staker at random, weighted by the voting power of the
+    /// NFTs they have staked, regardless of delegation, using `seed` as
+    /// the source of randomness. The same seed
+    /// selects the same staker until stakes change. Returns `None` if
+    /// nothing is staked.
+    #[returns(Option<WeightedRandomStakerResponse>)]
+    WeightedRandomStaker { seed: Binary },
+    /// The claim callback registered by an address, if any.
+    #[returns(Option<crate::state::Callback>)]
+    ClaimCallback { address: String },
+    /// The referrer an address named on its first stake, if any.
+    #[returns(Option<::cosmwasm_std::Addr>)]
+    Referrer { address: String },
+    /// The address an address has delegated its voting power to, if any,
+    /// and the voting power delegated to it.
+    #[returns(DelegationResponse)]
+    Delegation { address: String },
+    /// The number of stakers an address has referred, and the number of
+    /// NFTs they currently have staked.
+    #[returns(crate::state::ReferralStats)]
+    ReferralStats { address: String },
+    /// Lists the referral stats of every referrer, for example to reward
+    /// referrers with a rewards distributor.
+    #[returns(Vec<ReferrerStatsResponse>)]
+    ListReferralStats {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Every staked NFT whose identifier is at least `min` and less
+    /// than `max`, in lexicographic order, with its staker and voting
+    /// power. Only NFTs with a voting power of `weight` are listed if it
+    /// is set, so that frontends may list the NFTs of a series or rarity
+    /// tier one page at a time. NFTs of a collection other than the
+    /// default may be selected with `min` of `{collection_id}/` and `max`
+    /// of `{collection_id}0`.
+    #[returns(Vec<StakedNftResponse>)]
+    StakedNftsInRange {
+        min: Option<String>,
+        max: Option<String>,
+        weight: Option<Uint128>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// cw721-compatible. The staker of a staked NFT. NFTs that are
+    /// pending claim are not staked. Staked NFTs never have
+    /// approvals, so `include_expired` is ignored.
+    #[returns(::cw721::OwnerOfResponse)]
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// cw721-compatible. The number of NFTs staked.
+    #[returns(::cw721::NumTokensResponse)]
+    NumTokens {},
+    /// cw721-compatible. The NFTs staked by an address.
+    #[returns(::cw721::TokensResponse)]
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// cw721-compatible. Every staked NFT.
+    #[returns(::cw721::TokensResponse)]
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct CollectionResponse {
+    pub collection_id: String,
+    pub weight: Uint128,
+    /// The collection's override of the module's unstaking duration,
+    /// if any.
+    pub unstaking_duration_override: Option<Duration>,
+    /// The unstaking duration that applies to NFTs unstaked from the
+    /// collection.
+    pub unstaking_duration: Option<Duration>,
+}
+
+#[cw_serde]
+pub struct TokenWeightResponse {
+    pub token_id: String,
+    pub weight: Uint128,
+}
+
+#[cw_serde]
+pub struct StakedNftResponse {
+    pub token_id: String,
+    pub staker: Addr,
+    /// The voting power of the NFT.
+    pub weight: Uint128,
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct NftClaimInfoResponse {
+    pub token_id: String,
+    pub claim_info: crate::state::ClaimInfo,
+}
+
+#[cw_serde]
+pub struct UnstakeBudgetResponse {
+    pub unstake_budget: crate::state::UnstakeBudget,
+    /// The number of NFTs that may be unstaked during the current
+    /// epoch.
+    pub budget: Uint128,
+    /// The number of NFTs unstaked during the current epoch.
+    pub used: Uint128,
+    /// When the next epoch, and its budget, starts.
+    pub next_epoch_starts: Expiration,
+    /// The number of unstakes waiting in the queue.
+    pub queued: u64,
+}
+
+#[cw_serde]
+pub struct QueuedUnstakeResponse {
+    pub token_id: String,
+    /// The number of queued unstakes that will be processed before
+    /// this one.
+    pub position: u64,
+}
+
+#[cw_serde]
+pub struct ReferrerStatsResponse {
+    pub referrer: Addr,
+    pub stats: crate::state::ReferralStats,
+}
+
+#[cw_serde]
+pub struct DelegationResponse {
+    /// The address this address has delegated its voting power to.
+    pub delegate: Option<Addr>,
+    /// The voting power delegated to this address.
+    pub delegated_power: Uint128,
+}
+
+/// An entry of this contract's storage.
+#[cw_serde]
+pub struct StorageEntry {
+    /// The key of the entry, relative to this contract's storage.
+    pub key: Binary,
+    /// The value of the entry as it is encoded in storage, as of the
+    /// start of the block the response is for. `None` if there was no
+    /// entry, which counts as no voting power.
+    pub value: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct VotingPowerProofResponse {
+    pub height: u64,
+    /// The address's voting power, `staked` less `delegated_away`
+    /// plus `delegated`.
+    pub power: Uint128,
+    /// The total voting power, `total`.
+    pub total_power: Uint128,
+    /// The voting power of the NFTs the address has staked.
+    pub staked: StorageEntry,
+    /// The voting power the address has delegated to another address.
+    pub delegated_away: StorageEntry,
+    /// The voting power other addresses have delegated to the address.
+    pub delegated: StorageEntry,
+    /// The voting power of every NFT staked with this module.
+    pub total: StorageEntry,
+}
+
+#[cw_serde]
+pub struct WeightedRandomStakerResponse {
+    pub address: Addr,
+    /// The voting power of the NFTs the staker has staked, which the
+    /// selection is weighted by. Delegation is not taken into account,
+    /// so this may differ from the staker's voting power.
+    pub staked_weight: Uint128,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// The maximum number of stakers whose state is migrated to the
+    /// current layout by this migration. If stakers remain, the
+    /// contract must be migrated again (to the same code) to
+    /// continue. Defaults to 30.
+    pub limit: Option<u32>,
+}
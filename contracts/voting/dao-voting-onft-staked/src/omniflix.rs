@@ -1,18 +1,37 @@
 use cosmwasm_std::{CosmosMsg, Deps, StdError, StdResult};
 use omniflix_std::types::omniflix::onft::v1beta1::{MsgTransferOnft, OnftQuerier};
 
-pub fn query_onft_owner(deps: Deps, denom_id: &str, token_id: &str) -> StdResult<String> {
-    let res = OnftQuerier::new(&deps.querier).onft(denom_id.to_string(), token_id.to_string())?;
+use crate::ContractError;
+
+pub fn query_onft_owner(
+    deps: Deps,
+    denom_id: &str,
+    token_id: &str,
+) -> Result<String, ContractError> {
+    let res = OnftQuerier::new(&deps.querier)
+        .onft(denom_id.to_string(), token_id.to_string())
+        .map_err(|err| {
+            StdError::generic_err(format!(
+                "failed to query ONFT {token_id} in collection {denom_id}: {err}"
+            ))
+        })?;
     let owner = res
         .onft
-        .ok_or(StdError::generic_err("ONFT not found"))?
+        .ok_or_else(|| ContractError::CollectionMismatch {
+            collection_id: denom_id.to_string(),
+            token_id: token_id.to_string(),
+        })?
         .owner;
 
     Ok(owner)
 }
 
 pub fn query_onft_supply(deps: Deps, id: &str) -> StdResult<u64> {
-    let res = OnftQuerier::new(&deps.querier).supply(id.to_string(), "".to_string())?;
+    let res = OnftQuerier::new(&deps.querier)
+        .supply(id.to_string(), "".to_string())
+        .map_err(|err| {
+            StdError::generic_err(format!("failed to query supply of collection {id}: {err}"))
+        })?;
     Ok(res.amount)
 }
 
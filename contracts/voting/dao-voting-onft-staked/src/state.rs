@@ -1,118 +1,923 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Empty, StdError, StdResult, Storage, Uint128};
+use cosmwasm_std::{
+    Addr, Binary, BlockInfo, Decimal, Empty, Order, StdError, StdResult, Storage, Timestamp,
+    Uint128,
+};
 use cw721_controllers::NftClaims;
 use cw_hooks::Hooks;
 use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
-use cw_utils::Duration;
+use cw_utils::{Duration, Expiration};
 use dao_voting::threshold::ActiveThreshold;
+use sha2::{Digest, Sha256};
 
 use crate::ContractError;
 
 #[cw_serde]
 pub struct Config {
+    /// The collection NFTs are staked from when no collection is
+    /// specified, which is the first collection the module was
+    /// instantiated with.
     pub onft_collection_id: String,
     pub unstaking_duration: Option<Duration>,
+    /// No NFTs may be unstaked until this expires, while the DAO's
+    /// governance is bootstrapped.
+    #[serde(default)]
+    pub unstaking_locked_until: Option<Expiration>,
+    /// Whether the DAO has dissolved this module, after which no NFTs
+    /// may be staked and staked NFTs may be unstaked immediately.
+    #[serde(default)]
+    pub dissolved: bool,
+    /// If set, the DAO is only active once this many distinct
+    /// addresses have NFTs staked, in addition to meeting the active
+    /// threshold, so that a single holder can not activate it alone.
+    #[serde(default)]
+    pub unique_stakers_threshold: Option<u64>,
+}
+
+impl Config {
+    /// The key a staked NFT is stored under. NFTs from the default
+    /// collection are keyed by their token ID, as they were before
+    /// multiple collections were supported, and NFTs from other
+    /// collections by `{collection_id}/{token_id}`. x/onft IDs are
+    /// alphanumeric, so keys are unambiguous.
+    pub fn nft_key(&self, collection_id: &str, token_id: &str) -> String {
+        if collection_id == self.onft_collection_id {
+            token_id.to_string()
+        } else {
+            format!("{collection_id}/{token_id}")
+        }
+    }
+
+    /// The collection ID and token ID of the NFT stored under `key`.
+    pub fn parse_nft_key<'a>(&'a self, key: &'a str) -> (&'a str, &'a str) {
+        key.split_once('/')
+            .unwrap_or((self.onft_collection_id.as_str(), key))
+    }
 }
 
 pub const ACTIVE_THRESHOLD: Item<ActiveThreshold> = Item::new("active_threshold");
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const DAO: Item<Addr> = Item::new("dao");
 
+/// The collections NFTs may be staked from.
+///
+/// Map collection ID to the voting power of each NFT staked from it.
+pub const COLLECTIONS: Map<&str, Uint128> = Map::new("cl");
+
+/// The unstaking durations of collections that override the module's
+/// default `unstaking_duration`.
+///
+/// Map collection ID to unstaking duration.
+pub const COLLECTION_UNSTAKING_DURATIONS: Map<&str, Duration> = Map::new("cud");
+
+/// The unstaking duration of NFTs staked from `collection_id`, which is
+/// the collection's override if it has one and the module's default
+/// otherwise. NFTs are returned immediately once the module has been
+/// dissolved.
+pub fn unstaking_duration(
+    storage: &dyn Storage,
+    config: &Config,
+    collection_id: &str,
+) -> StdResult<Option<Duration>> {
+    if config.dissolved {
+        return Ok(None);
+    }
+    Ok(COLLECTION_UNSTAKING_DURATIONS
+        .may_load(storage, collection_id)?
+        .or(config.unstaking_duration))
+}
+
+/// The voting power of individual NFTs that override the weight of
+/// their collection, such as rare NFTs.
+///
+/// Map NFT key to voting power.
+pub const TOKEN_WEIGHTS: Map<&str, Uint128> = Map::new("tw");
+
+/// The NFTs of a collection with a weight override, so that the
+/// potential voting power of the collection may be computed without
+/// iterating over them.
+#[cw_serde]
+#[derive(Default)]
+pub struct TokenWeightTotals {
+    /// The number of NFTs with a weight override.
+    pub count: u64,
+    /// The combined voting power of those NFTs.
+    pub power: Uint128,
+}
+
+/// Map collection ID to the totals of its NFTs' weight overrides.
+pub const TOKEN_WEIGHT_TOTALS: Map<&str, TokenWeightTotals> = Map::new("twt");
+
+/// Overrides the voting power of the NFT stored under `key`, or
+/// removes its override if `None`.
+pub fn set_token_weight(
+    storage: &mut dyn Storage,
+    config: &Config,
+    key: &str,
+    weight: Option<Uint128>,
+) -> StdResult<()> {
+    let (collection_id, _) = config.parse_nft_key(key);
+    let mut totals = TOKEN_WEIGHT_TOTALS
+        .may_load(storage, collection_id)?
+        .unwrap_or_default();
+    if let Some(previous) = TOKEN_WEIGHTS.may_load(storage, key)? {
+        totals.count -= 1;
+        totals.power -= previous;
+    }
+    match weight {
+        Some(weight) => {
+            TOKEN_WEIGHTS.save(storage, key, &weight)?;
+            totals.count += 1;
+            totals.power = totals.power.checked_add(weight)?;
+        }
+        None => TOKEN_WEIGHTS.remove(storage, key),
+    }
+    if totals.count == 0 {
+        TOKEN_WEIGHT_TOTALS.remove(storage, collection_id);
+    } else {
+        TOKEN_WEIGHT_TOTALS.save(storage, collection_id, &totals)?;
+    }
+    Ok(())
+}
+
+/// The voting power of the NFT stored under `key`, which is its
+/// override if it has one and its collection's weight otherwise.
+pub fn nft_power(storage: &dyn Storage, config: &Config, key: &str) -> StdResult<Uint128> {
+    if let Some(weight) = TOKEN_WEIGHTS.may_load(storage, key)? {
+        return Ok(weight);
+    }
+    let (collection_id, _) = config.parse_nft_key(key);
+    COLLECTIONS.load(storage, collection_id)
+}
+
+/// The voting power of each NFT stored under `keys`.
+pub fn nfts_power(storage: &dyn Storage, config: &Config, keys: &[String]) -> StdResult<Uint128> {
+    keys.iter().try_fold(Uint128::zero(), |power, key| {
+        let weight = nft_power(storage, config, key)?;
+        power.checked_add(weight).map_err(StdError::overflow)
+    })
+}
+
 /// NFTs prepared to be staked. The owner must prepare the NFT before
 /// transferring and staking so the contract can verify them as the rightful
 /// owner before staking. Since ONFT transfer actions cannot include a message
 /// to execute on transfer, we can't verify who sent an ONFT, so we have to
 /// prepare it first. Once a stake is confirmed, the prepared stake is removed.
 ///
-/// Map token ID to validated preparer.
+/// Map NFT key to validated preparer.
 pub const PREPARED_ONFTS: Map<String, Addr> = Map::new("po");
 
 /// The set of NFTs currently staked by each address. The existence of
-/// an `(address, token_id)` pair implies that `address` has staked
-/// `token_id`.
+/// an `(address, key)` pair implies that `address` has staked the NFT
+/// stored under `key`.
 pub const STAKED_NFTS_PER_OWNER: Map<(&Addr, &str), Empty> = Map::new("snpw");
-/// The number of NFTs staked by an address as a function of block
-/// height.
+/// The staker each NFT held by this contract is staked by or pending
+/// claim for. NFTs held by this contract without an entry were sent to
+/// it without being staked.
+///
+/// Map NFT key to staker.
+pub const NFT_STAKERS: Map<&str, Addr> = Map::new("ns");
+/// The voting power of the NFTs staked by an address as a function of
+/// block height.
 pub const NFT_BALANCES: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
     "nb",
     "nb__checkpoints",
     "nb__changelog",
     Strategy::EveryBlock,
 );
-/// The number of NFTs staked with this contract as a function of
-/// block height.
+/// The key `TOTAL_STAKED_NFTS` is stored under.
+pub const TOTAL_STAKED_NFTS_KEY: &str = "tsn";
+/// The voting power of the NFTs staked with this contract as a
+/// function of block height.
 pub const TOTAL_STAKED_NFTS: SnapshotItem<Uint128> = SnapshotItem::new(
-    "tsn",
+    TOTAL_STAKED_NFTS_KEY,
     "tsn__checkpoints",
     "tsn__changelog",
     Strategy::EveryBlock,
 );
 
+/// The two most recent values of `TOTAL_STAKED_NFTS`, so that total
+/// power queries at recent heights, which are nearly all of them, are
+/// answered with a single read instead of a search of the snapshot
+/// changelog.
+#[cw_serde]
+pub struct TotalPowerCache {
+    /// The height of the most recent change. Changes take effect the
+    /// following block, so the total is `power` at every later height.
+    pub height: u64,
+    pub power: Uint128,
+    /// The height of the change before that. The total is
+    /// `previous_power` after this height through `height`.
+    pub previous_height: u64,
+    pub previous_power: Uint128,
+}
+
+pub const TOTAL_POWER_CACHE: Item<TotalPowerCache> = Item::new("tpc");
+
+/// The number of NFTs staked with this contract, regardless of their
+/// voting power.
+pub const STAKED_NFT_COUNT: Item<u64> = Item::new("snc");
+
+/// The addresses that currently have NFTs staked with this contract.
+/// Pending claims are not counted.
+pub const UNIQUE_STAKERS: Map<&Addr, Empty> = Map::new("ust");
+/// The number of entries in `UNIQUE_STAKERS`, used by the unique
+/// stakers threshold.
+pub const UNIQUE_STAKER_COUNT: Item<u64> = Item::new("usc");
+
+#[cw_serde]
+pub struct Callback {
+    /// The contract the callback is executed on.
+    pub contract: Addr,
+    /// The message passed through to the callback contract.
+    pub msg: Binary,
+}
+
+/// The callback executed when a contract's NFTs are claimed, for the
+/// contracts that have registered one.
+///
+/// Map staker to callback.
+pub const CLAIM_CALLBACKS: Map<&Addr, Callback> = Map::new("cc");
+
+#[cw_serde]
+pub struct UnstakeBudget {
+    /// The maximum portion of the staked NFTs that may be unstaked
+    /// during an epoch, as of when NFTs are first unstaked in the
+    /// epoch. Rounded up, so at least one NFT may be unstaked each
+    /// epoch.
+    pub max_percent: Decimal,
+    /// The length of an epoch. Epochs start at multiples of this
+    /// duration.
+    pub epoch: Duration,
+}
+
+impl UnstakeBudget {
+    /// The epoch that `block` is in.
+    pub fn current_epoch(&self, block: &BlockInfo) -> u64 {
+        match self.epoch {
+            Duration::Height(height) => block.height / height,
+            Duration::Time(time) => block.time.seconds() / time,
+        }
+    }
+
+    /// When the epoch after the one that `block` is in starts.
+    pub fn next_epoch_starts(&self, block: &BlockInfo) -> Expiration {
+        let next = self.current_epoch(block) + 1;
+        match self.epoch {
+            Duration::Height(height) => Expiration::AtHeight(next * height),
+            Duration::Time(time) => Expiration::AtTime(Timestamp::from_seconds(next * time)),
+        }
+    }
+
+    /// Loads the budget of the current epoch, starting a new epoch
+    /// with a full budget if the last one has ended.
+    pub fn load_epoch(&self, storage: &dyn Storage, block: &BlockInfo) -> StdResult<UnstakeEpoch> {
+        let epoch = self.current_epoch(block);
+        match UNSTAKE_EPOCH.may_load(storage)? {
+            Some(state) if state.epoch == epoch => Ok(state),
+            _ => Ok(UnstakeEpoch {
+                epoch,
+                budget: Uint128::from(STAKED_NFT_COUNT.may_load(storage)?.unwrap_or_default())
+                    .mul_ceil(self.max_percent),
+                used: Uint128::zero(),
+            }),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct UnstakeEpoch {
+    pub epoch: u64,
+    /// The number of NFTs that may be unstaked during this epoch.
+    pub budget: Uint128,
+    /// The number of NFTs unstaked during this epoch.
+    pub used: Uint128,
+}
+
+impl UnstakeEpoch {
+    pub fn remaining(&self) -> Uint128 {
+        self.budget.saturating_sub(self.used)
+    }
+}
+
+#[cw_serde]
+pub struct QueuedUnstake {
+    pub staker: Addr,
+    /// The key of the queued NFT.
+    pub token_id: String,
+}
+
+/// If set, the number of NFTs that may be unstaked each epoch is
+/// limited, and unstakes beyond the budget are queued for later
+/// epochs.
+pub const UNSTAKE_BUDGET: Item<UnstakeBudget> = Item::new("ub");
+/// The budget of the epoch that NFTs were last unstaked in.
+pub const UNSTAKE_EPOCH: Item<UnstakeEpoch> = Item::new("ue");
+/// Unstakes that are waiting for budget, processed in order. Queued
+/// NFTs remain staked until they are processed.
+///
+/// Map sequence number to queued unstake.
+pub const UNSTAKE_QUEUE: Map<u64, QueuedUnstake> = Map::new("uq");
+/// The sequence number of each queued unstake.
+///
+/// Map (staker, NFT key) to sequence number.
+pub const QUEUED_UNSTAKES: Map<(&Addr, &str), u64> = Map::new("qu");
+/// The sequence number of the next queued unstake.
+pub const UNSTAKE_QUEUE_NEXT: Item<u64> = Item::new("uqn");
+
+/// The sequence number of the next unstake to be processed, or the
+/// next sequence number if the queue is empty.
+pub fn unstake_queue_head(storage: &dyn Storage) -> StdResult<u64> {
+    match UNSTAKE_QUEUE
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+    {
+        Some(head) => head,
+        None => Ok(UNSTAKE_QUEUE_NEXT.may_load(storage)?.unwrap_or_default()),
+    }
+}
+
+/// Queues unstakes of `token_ids` by `staker` behind any already
+/// queued.
+pub fn queue_unstakes(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    token_ids: &[String],
+) -> StdResult<()> {
+    let mut next = UNSTAKE_QUEUE_NEXT.may_load(storage)?.unwrap_or_default();
+    for token_id in token_ids {
+        UNSTAKE_QUEUE.save(
+            storage,
+            next,
+            &QueuedUnstake {
+                staker: staker.clone(),
+                token_id: token_id.clone(),
+            },
+        )?;
+        QUEUED_UNSTAKES.save(storage, (staker, token_id), &next)?;
+        next += 1;
+    }
+    UNSTAKE_QUEUE_NEXT.save(storage, &next)
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ReferralStats {
+    /// The number of stakers that named this address as their
+    /// referrer.
+    pub referrals: u64,
+    /// The voting power of the NFTs currently staked by those
+    /// stakers.
+    pub referred_power: Uint128,
+}
+
+/// The referrer each staker named on their first stake, if any.
+///
+/// Map staker to referrer.
+pub const REFERRERS: Map<&Addr, Addr> = Map::new("rf");
+/// Map referrer to the stats of the stakers they referred.
+pub const REFERRAL_STATS: Map<&Addr, ReferralStats> = Map::new("rs");
+
+/// Records `referrer` as the referrer of `staker`, who must be
+/// staking for the first time.
+pub fn register_referral(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    referrer: &Addr,
+) -> Result<(), ContractError> {
+    if staker == referrer {
+        return Err(ContractError::SelfReferral {});
+    }
+    // every address that has staked has a balance entry, even once it
+    // has unstaked everything.
+    if NFT_BALANCES.may_load(storage, staker)?.is_some() {
+        return Err(ContractError::ReferralAfterFirstStake {});
+    }
+    REFERRERS.save(storage, staker, referrer)?;
+    REFERRAL_STATS.update(storage, referrer, |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.referrals += 1;
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+/// Updates the referred power of `staker`'s referrer, if they have one.
+fn update_referred_power(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    update: impl FnOnce(Uint128) -> StdResult<Uint128>,
+) -> StdResult<()> {
+    let Some(referrer) = REFERRERS.may_load(storage, staker)? else {
+        return Ok(());
+    };
+    let mut stats = REFERRAL_STATS.load(storage, &referrer)?;
+    stats.referred_power = update(stats.referred_power)?;
+    REFERRAL_STATS.save(storage, &referrer, &stats)
+}
+
+/// The address each staker has delegated its voting power to. Only the
+/// voting power of a staker's own staked NFTs is delegated, so voting
+/// power delegated to an address is never delegated onwards.
+///
+/// Map staker to delegate.
+pub const DELEGATES: Map<&Addr, Addr> = Map::new("dg");
+/// The voting power each staker has delegated away as a function of
+/// block height, which is the power of its staked NFTs while it has a
+/// delegate.
+pub const DELEGATED_AWAY: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "dga",
+    "dga__checkpoints",
+    "dga__changelog",
+    Strategy::EveryBlock,
+);
+/// The voting power delegated to each address as a function of block
+/// height.
+pub const DELEGATED_POWER: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "dgp",
+    "dgp__checkpoints",
+    "dgp__changelog",
+    Strategy::EveryBlock,
+);
+
+/// The voting power of `address` at `height`, or its latest voting
+/// power if `None`: the power of the NFTs it has staked, unless it has
+/// delegated it, plus the power delegated to it.
+pub fn voting_power(
+    storage: &dyn Storage,
+    address: &Addr,
+    height: Option<u64>,
+) -> StdResult<Uint128> {
+    let load = |map: &SnapshotMap<&Addr, Uint128>| -> StdResult<Uint128> {
+        let power = match height {
+            Some(height) => map.may_load_at_height(storage, address, height)?,
+            None => map.may_load(storage, address)?,
+        };
+        Ok(power.unwrap_or_default())
+    };
+    load(&NFT_BALANCES)?
+        .saturating_sub(load(&DELEGATED_AWAY)?)
+        .checked_add(load(&DELEGATED_POWER)?)
+        .map_err(StdError::overflow)
+}
+
+/// Delegates `staker`'s voting power to `delegate` as of `height`, or
+/// stops delegating it if `None`. Returns the previous delegate, whose
+/// delegated power the staker's power is removed from.
+pub fn set_delegate(
+    storage: &mut dyn Storage,
+    height: u64,
+    staker: &Addr,
+    delegate: Option<&Addr>,
+) -> StdResult<Option<Addr>> {
+    let power = NFT_BALANCES.may_load(storage, staker)?.unwrap_or_default();
+    let previous = DELEGATES.may_load(storage, staker)?;
+    if let Some(ref previous) = previous {
+        DELEGATED_POWER.update(storage, previous, height, |delegated| {
+            delegated
+                .unwrap_or_default()
+                .checked_sub(power)
+                .map_err(StdError::overflow)
+        })?;
+    }
+    match delegate {
+        Some(delegate) => {
+            DELEGATES.save(storage, staker, delegate)?;
+            DELEGATED_AWAY.save(storage, staker, &power, height)?;
+            DELEGATED_POWER.update(storage, delegate, height, |delegated| {
+                delegated
+                    .unwrap_or_default()
+                    .checked_add(power)
+                    .map_err(StdError::overflow)
+            })?;
+        }
+        None => {
+            DELEGATES.remove(storage, staker);
+            DELEGATED_AWAY.remove(storage, staker, height)?;
+        }
+    }
+    Ok(previous)
+}
+
+/// Applies a change in the voting power of `staker`'s staked NFTs to
+/// the power it has delegated, if it has a delegate.
+fn update_delegated_power(
+    storage: &mut dyn Storage,
+    height: u64,
+    staker: &Addr,
+    update: impl Fn(Option<Uint128>) -> StdResult<Uint128>,
+) -> StdResult<()> {
+    let Some(delegate) = DELEGATES.may_load(storage, staker)? else {
+        return Ok(());
+    };
+    DELEGATED_AWAY.update(storage, staker, height, &update)?;
+    DELEGATED_POWER.update(storage, &delegate, height, update)?;
+    Ok(())
+}
+
 /// The maximum number of claims that may be outstanding.
 pub const MAX_CLAIMS: u64 = 70;
 pub const NFT_CLAIMS: NftClaims = NftClaims::new("nft_claims");
 
+/// Claims backfilled when migrating from state layout 1 only know the
+/// unstaked-at value matching the unit of their duration, and the other
+/// is zero.
+#[cw_serde]
+pub struct ClaimInfo {
+    /// The unstaking duration that applies to the claim. `Height(0)` if the
+    /// claim was released early by removing the unstaking duration.
+    pub duration: Duration,
+    /// The block height at which the NFT was unstaked.
+    pub unstaked_at_height: u64,
+    /// The block time at which the NFT was unstaked.
+    pub unstaked_at_time: Timestamp,
+}
+
+/// The unstaking duration that applied to each pending claim, so that
+/// unstaking duration changes can be applied to pending claims
+/// unambiguously. Claims created before this was tracked have no entry.
+///
+/// Map (staker, NFT key) to claim info.
+pub const CLAIM_INFO: Map<(&Addr, &str), ClaimInfo> = Map::new("ci");
+
+/// A new unstaking duration being applied to pending claims with the
+/// `ShortenExisting` policy. Claims are shortened a page of stakers at
+/// a time, so that applying it never runs out of gas.
+#[cw_serde]
+pub struct ClaimShortening {
+    /// The new unstaking duration, or `None` to release claims
+    /// immediately.
+    pub duration: Option<Duration>,
+    /// The collection whose claims are shortened, or `None` for every
+    /// collection without an unstaking duration override.
+    pub collection_id: Option<String>,
+    /// The block the new duration was set at. Claims without claim
+    /// info are released at most the new duration after it.
+    pub set_at_height: u64,
+    pub set_at_time: Timestamp,
+    /// The last staker whose claims were shortened, if any.
+    pub cursor: Option<Addr>,
+}
+
+/// The unstaking duration being applied to pending claims, while some
+/// stakers' claims have yet to be shortened.
+pub const CLAIM_SHORTENING: Item<ClaimShortening> = Item::new("cs");
+
+/// The layout version of the contract's state. Contracts instantiated
+/// before the layout was versioned have no entry and use layout 1.
+///
+/// 1. Pending claims may have no claim info.
+/// 2. Every pending claim has claim info.
+/// 3. Every staked or pending claim NFT has an entry in `NFT_STAKERS`.
+/// 4. Every staker's staked weight is tracked in `STAKER_WEIGHTS`.
+/// 5. Collections and their weights are stored in `COLLECTIONS`, and
+///    the number of staked NFTs in `STAKED_NFT_COUNT`. These are recorded
+///    before the earlier layouts are migrated, so that NFTs may be staked
+///    while a migration is in progress.
+/// 6. Every address with staked NFTs is tracked in `UNIQUE_STAKERS`.
+pub const STATE_VERSION: Item<u64> = Item::new("state_version");
+pub const CURRENT_STATE_VERSION: u64 = 6;
+/// The last staker whose state was migrated, while a paginated
+/// migration to the current state layout is in progress.
+pub const MIGRATION_CURSOR: Item<Addr> = Item::new("migration_cursor");
+
+/// The index of each address that has staked in `STAKER_WEIGHTS`,
+/// starting from 1. Indexes are never reused.
+///
+/// Map staker to index.
+pub const STAKER_INDEXES: Map<&Addr, u64> = Map::new("sti");
+/// Map index to staker.
+pub const STAKERS_BY_INDEX: Map<u64, Addr> = Map::new("sbi");
+/// The number of indexes assigned to stakers.
+pub const STAKER_COUNT: Item<u64> = Item::new("stc");
+/// A Fenwick tree of the current staked weight of each staker by
+/// index, used to sample stakers weighted by it in O(log n). A
+/// staker's staked weight is the voting power of the NFTs it has
+/// staked, before any delegation. The node at index `i` holds the
+/// staked weight of the stakers with indexes in `(i - lowbit(i), i]`.
+/// Zero nodes are not stored.
+///
+/// Map node index to staked weight.
+pub const STAKER_WEIGHTS: Map<u64, Uint128> = Map::new("sw");
+
+/// The number of indexes `STAKER_WEIGHTS` has room for. The node at
+/// this index holds the total voting power.
+const STAKER_WEIGHTS_SIZE_LOG2: u32 = 32;
+const STAKER_WEIGHTS_SIZE: u64 = 1 << STAKER_WEIGHTS_SIZE_LOG2;
+
+fn lowbit(index: u64) -> u64 {
+    index & index.wrapping_neg()
+}
+
+/// Loads `staker`'s index, assigning them the next index if they
+/// don't have one yet.
+fn staker_index(storage: &mut dyn Storage, staker: &Addr) -> StdResult<u64> {
+    if let Some(index) = STAKER_INDEXES.may_load(storage, staker)? {
+        return Ok(index);
+    }
+    let index = STAKER_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    if index >= STAKER_WEIGHTS_SIZE {
+        return Err(StdError::generic_err("too many stakers"));
+    }
+    STAKER_COUNT.save(storage, &index)?;
+    STAKER_INDEXES.save(storage, staker, &index)?;
+    STAKERS_BY_INDEX.save(storage, index, staker)?;
+    Ok(index)
+}
+
+fn staker_weight_node(storage: &dyn Storage, index: u64) -> StdResult<Uint128> {
+    Ok(STAKER_WEIGHTS.may_load(storage, index)?.unwrap_or_default())
+}
+
+/// The staked weight tracked for `staker` in `STAKER_WEIGHTS`.
+pub fn staker_weight(storage: &dyn Storage, staker: &Addr) -> StdResult<Uint128> {
+    let Some(index) = STAKER_INDEXES.may_load(storage, staker)? else {
+        return Ok(Uint128::zero());
+    };
+    // the node holds the weights of `(index - lowbit(index), index]`,
+    // so the weights of its children are subtracted.
+    let mut weight = staker_weight_node(storage, index)?;
+    let parent = index - lowbit(index);
+    let mut child = index - 1;
+    while child > parent {
+        weight -= staker_weight_node(storage, child)?;
+        child -= lowbit(child);
+    }
+    Ok(weight)
+}
+
+/// The total staked weight tracked in `STAKER_WEIGHTS`.
+pub fn total_staker_weight(storage: &dyn Storage) -> StdResult<Uint128> {
+    staker_weight_node(storage, STAKER_WEIGHTS_SIZE)
+}
+
+fn update_staker_weight(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    update: impl Fn(Uint128) -> StdResult<Uint128>,
+) -> StdResult<()> {
+    let mut index = staker_index(storage, staker)?;
+    while index <= STAKER_WEIGHTS_SIZE {
+        let weight = update(staker_weight_node(storage, index)?)?;
+        if weight.is_zero() {
+            STAKER_WEIGHTS.remove(storage, index);
+        } else {
+            STAKER_WEIGHTS.save(storage, index, &weight)?;
+        }
+        index += lowbit(index);
+    }
+    Ok(())
+}
+
+/// Adds `amount` to the voting power tracked for `staker`.
+pub fn add_staker_weight(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    update_staker_weight(storage, staker, |weight| {
+        weight.checked_add(amount).map_err(StdError::overflow)
+    })
+}
+
+/// Subtracts `amount` from the voting power tracked for `staker`, or
+/// all of it if less is tracked, as is the case for stakers whose
+/// voting power has not been backfilled yet while migrating.
+pub fn sub_staker_weight(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    let amount = amount.min(staker_weight(storage, staker)?);
+    if amount.is_zero() {
+        return Ok(());
+    }
+    update_staker_weight(storage, staker, |weight| {
+        weight.checked_sub(amount).map_err(StdError::overflow)
+    })
+}
+
+/// Selects a staker at random, weighted by their staked weight, using
+/// `seed` as the source of randomness. The same seed always selects
+/// the same staker for the same stakes. Returns `None` if nothing is
+/// staked.
+pub fn sample_staker(storage: &dyn Storage, seed: &[u8]) -> StdResult<Option<(Addr, Uint128)>> {
+    let total = total_staker_weight(storage)?;
+    if total.is_zero() {
+        return Ok(None);
+    }
+    let hash = Sha256::digest(seed);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    let mut target = Uint128::new(u128::from_be_bytes(bytes) % total.u128());
+
+    // descend the tree to find the first index whose prefix sum
+    // exceeds `target`.
+    let mut index = 0;
+    for k in (0..STAKER_WEIGHTS_SIZE_LOG2).rev() {
+        let next = index + (1 << k);
+        let weight = staker_weight_node(storage, next)?;
+        if weight <= target {
+            index = next;
+            target -= weight;
+        }
+    }
+    let staker = STAKERS_BY_INDEX.load(storage, index + 1)?;
+    let weight = staker_weight(storage, &staker)?;
+    Ok(Some((staker, weight)))
+}
+
 // Hooks to contracts that will receive staking and unstaking
 // messages.
 pub const HOOKS: Hooks = Hooks::new("hooks");
 
+// Hooks to contracts that will receive cw4 `MemberChangedHook` messages
+// when voting power changes without staking or unstaking. Kept apart
+// from `HOOKS`, which only expect staking and unstaking messages.
+pub const MEMBER_HOOKS: Hooks = Hooks::new("member_hooks");
+
+/// Registers the staking of the NFTs stored under `keys`, which have
+/// a combined voting power of `power`.
 pub fn register_staked_nfts(
     storage: &mut dyn Storage,
     height: u64,
     staker: &Addr,
-    token_ids: &Vec<String>,
+    keys: &Vec<String>,
+    power: Uint128,
 ) -> StdResult<()> {
-    let count = token_ids.len() as u128;
-    let add_count = |prev: Option<Uint128>| -> StdResult<Uint128> {
+    let add_power = |prev: Option<Uint128>| -> StdResult<Uint128> {
         prev.unwrap_or_default()
-            .checked_add(Uint128::new(count))
+            .checked_add(power)
             .map_err(StdError::overflow)
     };
 
-    for token_id in token_ids {
-        PREPARED_ONFTS.remove(storage, token_id.to_string());
-        STAKED_NFTS_PER_OWNER.save(storage, (staker, token_id), &Empty::default())?;
+    for key in keys {
+        PREPARED_ONFTS.remove(storage, key.to_string());
+        STAKED_NFTS_PER_OWNER.save(storage, (staker, key), &Empty::default())?;
+        NFT_STAKERS.save(storage, key, staker)?;
+    }
+
+    NFT_BALANCES.update(storage, staker, height, add_power)?;
+    update_delegated_power(storage, height, staker, add_power)?;
+    add_staker_weight(storage, staker, power)?;
+    update_referred_power(storage, staker, |referred| {
+        referred.checked_add(power).map_err(StdError::overflow)
+    })?;
+    let count = STAKED_NFT_COUNT.may_load(storage)?.unwrap_or_default();
+    STAKED_NFT_COUNT.save(storage, &(count + keys.len() as u64))?;
+    sync_unique_staker(storage, staker)?;
+    update_total_staked_nfts(storage, height, add_power)
+}
+
+/// Adds `staker` to or removes it from `UNIQUE_STAKERS` depending on
+/// whether it has any NFTs staked, keeping `UNIQUE_STAKER_COUNT` in
+/// sync. Does nothing if the entry is already correct, so it is safe
+/// to call for stakers that have been backfilled.
+pub fn sync_unique_staker(storage: &mut dyn Storage, staker: &Addr) -> StdResult<()> {
+    let has_staked = STAKED_NFTS_PER_OWNER
+        .prefix(staker)
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    let tracked = UNIQUE_STAKERS.has(storage, staker);
+    if has_staked == tracked {
+        return Ok(());
+    }
+    let count = UNIQUE_STAKER_COUNT.may_load(storage)?.unwrap_or_default();
+    if has_staked {
+        UNIQUE_STAKERS.save(storage, staker, &Empty::default())?;
+        UNIQUE_STAKER_COUNT.save(storage, &(count + 1))
+    } else {
+        UNIQUE_STAKERS.remove(storage, staker);
+        UNIQUE_STAKER_COUNT.save(storage, &count.saturating_sub(1))
+    }
+}
+
+/// Saves the voting power staked with this contract as of `height`,
+/// keeping `TOTAL_POWER_CACHE` in sync.
+pub fn save_total_staked_nfts(
+    storage: &mut dyn Storage,
+    height: u64,
+    total: Uint128,
+) -> StdResult<()> {
+    TOTAL_STAKED_NFTS.save(storage, &total, height)?;
+    let cache = match TOTAL_POWER_CACHE.may_load(storage)? {
+        // Later changes in the same block replace the latest value, as
+        // none of them are visible until the next block.
+        Some(cache) if cache.height == height => TotalPowerCache {
+            power: total,
+            ..cache
+        },
+        Some(cache) => TotalPowerCache {
+            height,
+            power: total,
+            previous_height: cache.height,
+            previous_power: cache.power,
+        },
+        // Contracts migrated from before the cache existed don't know
+        // when the total last changed, so only the latest value is
+        // cached until the next change.
+        None => TotalPowerCache {
+            height,
+            power: total,
+            previous_height: height,
+            previous_power: total,
+        },
+    };
+    TOTAL_POWER_CACHE.save(storage, &cache)
+}
+
+/// Updates the voting power staked with this contract as of `height`.
+pub fn update_total_staked_nfts(
+    storage: &mut dyn Storage,
+    height: u64,
+    action: impl FnOnce(Option<Uint128>) -> StdResult<Uint128>,
+) -> StdResult<()> {
+    let total = action(TOTAL_STAKED_NFTS.may_load(storage)?)?;
+    save_total_staked_nfts(storage, height, total)
+}
+
+/// Loads the voting power staked with this contract at `height`,
+/// reading from `TOTAL_POWER_CACHE` when it covers the height.
+pub fn load_total_staked_nfts_at_height(storage: &dyn Storage, height: u64) -> StdResult<Uint128> {
+    if let Some(cache) = TOTAL_POWER_CACHE.may_load(storage)? {
+        if height > cache.height {
+            return Ok(cache.power);
+        }
+        if height > cache.previous_height {
+            return Ok(cache.previous_power);
+        }
     }
+    Ok(TOTAL_STAKED_NFTS
+        .may_load_at_height(storage, height)?
+        .unwrap_or_default())
+}
 
-    NFT_BALANCES.update(storage, staker, height, add_count)?;
-    TOTAL_STAKED_NFTS
-        .update(storage, height, add_count)
-        .map(|_| ())
+/// Updates the voting power of an NFT staked by `staker` from `before`
+/// to `after`, as of `height`.
+pub fn reweigh_staked_nft(
+    storage: &mut dyn Storage,
+    height: u64,
+    staker: &Addr,
+    before: Uint128,
+    after: Uint128,
+) -> StdResult<()> {
+    let reweigh = |prev: Option<Uint128>| -> StdResult<Uint128> {
+        prev.unwrap_or_default()
+            .checked_add(after)?
+            .checked_sub(before)
+            .map_err(StdError::overflow)
+    };
+    NFT_BALANCES.update(storage, staker, height, reweigh)?;
+    update_delegated_power(storage, height, staker, reweigh)?;
+    update_referred_power(storage, staker, |referred| reweigh(Some(referred)))?;
+    update_total_staked_nfts(storage, height, reweigh)?;
+    if after > before {
+        add_staker_weight(storage, staker, after - before)
+    } else {
+        sub_staker_weight(storage, staker, before - after)
+    }
 }
 
-/// Registers the unstaking of TOKEN_IDs in storage. Errors if:
+/// Registers the unstaking of the NFTs stored under `keys`, which have
+/// a combined voting power of `power`, in storage. Errors if:
 ///
-/// 1. `token_ids` is non-unique.
+/// 1. `keys` is non-unique.
 /// 2. a NFT being staked has not previously been staked.
 pub fn register_unstaked_nfts(
     storage: &mut dyn Storage,
     height: u64,
     staker: &Addr,
-    token_ids: &[String],
+    keys: &[String],
+    power: Uint128,
 ) -> Result<(), ContractError> {
-    let subtractor = |amount: u128| {
-        move |prev: Option<Uint128>| -> StdResult<Uint128> {
-            prev.expect("unstaking that which was not staked")
-                .checked_sub(Uint128::new(amount))
-                .map_err(StdError::overflow)
-        }
-    };
-
-    for token in token_ids {
-        let key = (staker, token.as_str());
-        if STAKED_NFTS_PER_OWNER.has(storage, key) {
-            STAKED_NFTS_PER_OWNER.remove(storage, key);
+    for key in keys {
+        let staked = (staker, key.as_str());
+        if STAKED_NFTS_PER_OWNER.has(storage, staked) {
+            STAKED_NFTS_PER_OWNER.remove(storage, staked);
         } else {
             return Err(ContractError::NotStaked {
-                token_id: token.clone(),
+                token_id: key.clone(),
             });
         }
     }
 
-    // invariant: token_ids has unique values. for loop asserts this.
+    // invariant: keys has unique values. for loop asserts this.
 
-    let sub_n = subtractor(token_ids.len() as u128);
-    TOTAL_STAKED_NFTS.update(storage, height, sub_n)?;
-    NFT_BALANCES.update(storage, staker, height, sub_n)?;
+    let sub_power = |prev: Option<Uint128>| -> StdResult<Uint128> {
+        prev.expect("unstaking that which was not staked")
+            .checked_sub(power)
+            .map_err(StdError::overflow)
+    };
+    update_total_staked_nfts(storage, height, sub_power)?;
+    NFT_BALANCES.update(storage, staker, height, sub_power)?;
+    update_delegated_power(storage, height, staker, sub_power)?;
+    sub_staker_weight(storage, staker, power)?;
+    update_referred_power(storage, staker, |referred| {
+        referred.checked_sub(power).map_err(StdError::overflow)
+    })?;
+    let count = STAKED_NFT_COUNT.may_load(storage)?.unwrap_or_default();
+    STAKED_NFT_COUNT.save(storage, &count.saturating_sub(keys.len() as u64))?;
+    sync_unique_staker(storage, staker)?;
     Ok(())
 }
@@ -0,0 +1,213 @@
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_multi_test::{next_block, Executor};
+use cw_utils::Duration;
+use dao_voting::threshold::ActiveThreshold;
+
+use crate::msg::{ExecuteMsg, InstantiateMsg, OnftCollection, WeightedOnftCollection};
+
+use super::{
+    execute::{
+        add_hook, cancel_stake, claim_nfts, confirm_stake_nft, mint_and_stake_nft, mint_nft,
+        prepare_stake_nft, remove_hook, send_nft, unstake_nfts, update_config,
+    },
+    is_error,
+    queries::{query_config, query_hooks, query_nft_owner, query_total_and_voting_power},
+    setup_test, CommonTest, DAO, STAKER,
+};
+
+/// Another contract, which is not the DAO, can not drive any of the
+/// DAO-only state changes, including registering itself as a hook to
+/// receive (and act on) stake changes.
+#[test]
+fn test_arbitrary_contract_cannot_manage_module() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        module,
+        nft,
+    } = setup_test(None, None);
+
+    // an arbitrary contract, here another voting module for the same
+    // collection.
+    let evil = app.instantiate_contract(
+        module_id,
+        Addr::unchecked("ekez"),
+        &InstantiateMsg {
+            onft_collections: vec![WeightedOnftCollection {
+                collection: OnftCollection::Existing { id: nft.clone() },
+                weight: Uint128::one(),
+                unstaking_duration: None,
+            }],
+            unstaking_duration: None,
+            active_threshold: None,
+            bootstrap_period: None,
+            unstake_budget: None,
+            unique_stakers_threshold: None,
+        },
+        &[],
+        "evil",
+        None,
+    )?;
+
+    add_hook(&mut app, &module, DAO, "meow")?;
+
+    let res = add_hook(&mut app, &module, evil.as_str(), evil.as_str());
+    is_error!(res => "Unauthorized");
+    let res = remove_hook(&mut app, &module, evil.as_str(), "meow");
+    is_error!(res => "Unauthorized");
+    assert_eq!(query_hooks(&app, &module)?.hooks, vec!["meow".to_string()]);
+
+    let res = update_config(&mut app, &module, evil.as_str(), Some(Duration::Height(1)));
+    is_error!(res => "Unauthorized");
+    assert_eq!(query_config(&app, &module)?.unstaking_duration, None);
+
+    let res = app.execute_contract(
+        evil.clone(),
+        module.clone(),
+        &ExecuteMsg::UpdateActiveThreshold {
+            new_threshold: Some(ActiveThreshold::Percentage {
+                percent: Decimal::percent(100),
+            }),
+        },
+        &[],
+    );
+    is_error!(res => "Unauthorized");
+
+    Ok(())
+}
+
+/// ONFT transfers can not carry a callback, so staking relies on the
+/// owner preparing the stake. Someone else can not claim an NFT that
+/// was sent to the module without being prepared.
+#[test]
+fn test_spoof_unprepared_transfer() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+
+    let res = confirm_stake_nft(&mut app, &module, "ekez", "1");
+    is_error!(res => "must be prepared by the sender");
+
+    // the module owns the NFT now, so no one may prepare it.
+    let res = prepare_stake_nft(&mut app, &module, "ekez", "1");
+    is_error!(res => "Only an NFT's owner can prepare it to be staked");
+
+    // nor may they take it.
+    let res = cancel_stake(&mut app, &module, "ekez", "1", None);
+    is_error!(res => "Only the owner or preparer can cancel a prepared stake");
+
+    app.update_block(next_block);
+    let (total, voting) = query_total_and_voting_power(&app, &module, "ekez", None)?;
+    assert_eq!(total, Uint128::zero());
+    assert_eq!(voting, Uint128::zero());
+
+    Ok(())
+}
+
+/// Replaying a stake confirmation, unstake, or claim does not change
+/// voting power or release NFTs twice.
+#[test]
+fn test_replay() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(1)), None);
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+
+    let res = confirm_stake_nft(&mut app, &module, STAKER, "1");
+    is_error!(res => "NFT 1 is already staked");
+
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    let res = unstake_nfts(&mut app, &module, STAKER, &["1"]);
+    is_error!(res => "Can not unstake that which you have not staked");
+
+    // the NFT is still held by the module pending claim, and may not be
+    // staked again.
+    let res = confirm_stake_nft(&mut app, &module, STAKER, "1");
+    is_error!(res => "NFT 1 is already staked");
+
+    app.update_block(next_block);
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::zero());
+    assert_eq!(voting, Uint128::zero());
+
+    claim_nfts(&mut app, &module, STAKER)?;
+    let res = claim_nfts(&mut app, &module, STAKER);
+    is_error!(res => "Nothing to claim");
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+
+    // once claimed, it may be staked again.
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+    confirm_stake_nft(&mut app, &module, STAKER, "1")?;
+
+    Ok(())
+}
+
+/// Cancelling a stake can not be used to take an NFT that is staked
+/// or pending claim, even by the DAO.
+#[test]
+fn test_cancel_staked() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(1)), None);
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+
+    let res = cancel_stake(&mut app, &module, DAO, "1", Some("ekez"));
+    is_error!(res => "NFT 1 is staked or pending claim");
+    let res = cancel_stake(&mut app, &module, STAKER, "1", None);
+    is_error!(res => "NFT 1 is staked or pending claim");
+
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    let res = cancel_stake(&mut app, &module, DAO, "1", Some("ekez"));
+    is_error!(res => "NFT 1 is staked or pending claim");
+
+    app.update_block(next_block);
+    claim_nfts(&mut app, &module, STAKER)?;
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+
+    Ok(())
+}
+
+/// Cancelling the same NFT more than once in a single message is
+/// rejected.
+#[test]
+fn test_cancel_duplicate_token_ids() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+
+    let res = app.execute_contract(
+        Addr::unchecked(DAO),
+        module.clone(),
+        &ExecuteMsg::CancelStake {
+            token_ids: vec!["1".to_string(), "1".to_string()],
+            collection_id: None,
+            recipient: Some(DAO.to_string()),
+        },
+        &[],
+    );
+    is_error!(res => "NFT 1 was included more than once");
+
+    Ok(())
+}
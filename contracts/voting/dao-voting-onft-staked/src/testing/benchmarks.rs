@@ -0,0 +1,211 @@
+//! Gas benchmarks of batched staking and unstaking.
+//!
+//! cw-multi-test does not meter gas, so these run against a storage that
+//! charges gas for each access using the Cosmos SDK's default KV store gas
+//! costs. Storage dominates the gas used by staking and unstaking, so this
+//! is a good estimate of the difference batching makes.
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{Addr, MemoryStorage, Order, OwnedDeps, Record, Storage, Uint128};
+
+use crate::contract::execute;
+use crate::msg::{CollectionTokenIds, ExecuteMsg};
+use crate::state::{nfts_power, register_staked_nfts, Config, COLLECTIONS, CONFIG, DAO};
+
+use super::STAKER;
+
+// The Cosmos SDK's default KV store gas costs.
+const READ_COST_FLAT: u64 = 1000;
+const READ_COST_PER_BYTE: u64 = 3;
+const WRITE_COST_FLAT: u64 = 2000;
+const WRITE_COST_PER_BYTE: u64 = 30;
+const DELETE_COST: u64 = 1000;
+const ITER_NEXT_COST_FLAT: u64 = 30;
+
+/// The number of NFTs staked from each collection in the benchmarks.
+const NFTS_PER_COLLECTION: usize = 10;
+
+/// Storage that keeps track of the gas its accesses would use.
+#[derive(Default)]
+struct MeteredStorage {
+    storage: MemoryStorage,
+    gas_used: Cell<u64>,
+}
+
+impl MeteredStorage {
+    fn consume(&self, gas: u64) {
+        self.gas_used.set(self.gas_used.get() + gas)
+    }
+
+    /// Returns the gas used since the last call, resetting it.
+    fn take_gas_used(&self) -> u64 {
+        self.gas_used.take()
+    }
+}
+
+impl Storage for MeteredStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.storage.get(key);
+        let len = key.len() + value.as_ref().map_or(0, Vec::len);
+        self.consume(READ_COST_FLAT + READ_COST_PER_BYTE * len as u64);
+        value
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        self.consume(READ_COST_FLAT);
+        Box::new(
+            self.storage
+                .range(start, end, order)
+                .inspect(|(key, value)| {
+                    let len = key.len() + value.len();
+                    self.consume(ITER_NEXT_COST_FLAT + READ_COST_PER_BYTE * len as u64);
+                }),
+        )
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.consume(WRITE_COST_FLAT + WRITE_COST_PER_BYTE * (key.len() + value.len()) as u64);
+        self.storage.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.consume(DELETE_COST);
+        self.storage.remove(key)
+    }
+}
+
+type MeteredDeps = OwnedDeps<MeteredStorage, MockApi, MockQuerier>;
+
+/// Sets up a module with a default collection, "nft", and a second
+/// collection, "rare".
+fn setup_deps() -> MeteredDeps {
+    let mut deps = OwnedDeps {
+        storage: MeteredStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    CONFIG
+        .save(
+            &mut deps.storage,
+            &Config {
+                onft_collection_id: "nft".to_string(),
+                unstaking_duration: None,
+                unstaking_locked_until: None,
+                dissolved: false,
+                unique_stakers_threshold: None,
+            },
+        )
+        .unwrap();
+    COLLECTIONS
+        .save(&mut deps.storage, "nft", &Uint128::one())
+        .unwrap();
+    COLLECTIONS
+        .save(&mut deps.storage, "rare", &Uint128::new(3))
+        .unwrap();
+    DAO.save(&mut deps.storage, &Addr::unchecked("dao"))
+        .unwrap();
+    deps.storage.take_gas_used();
+    deps
+}
+
+/// The keys of the NFTs staked in the benchmarks, from both collections.
+fn nft_keys() -> Vec<String> {
+    (0..NFTS_PER_COLLECTION)
+        .flat_map(|i| [i.to_string(), format!("rare/{i}")])
+        .collect()
+}
+
+/// Registers the staking of `keys`, returning the gas used.
+fn stake(deps: &mut MeteredDeps, keys: &[String]) -> u64 {
+    let config = CONFIG.load(&deps.storage).unwrap();
+    let power = nfts_power(&deps.storage, &config, keys).unwrap();
+    deps.storage.take_gas_used();
+    register_staked_nfts(
+        &mut deps.storage,
+        mock_env().block.height,
+        &Addr::unchecked(STAKER),
+        &keys.to_vec(),
+        power,
+    )
+    .unwrap();
+    deps.storage.take_gas_used()
+}
+
+/// Executes `msg` as the staker, returning the gas used.
+fn execute_as_staker(deps: &mut MeteredDeps, msg: ExecuteMsg) -> u64 {
+    execute(deps.as_mut(), mock_env(), mock_info(STAKER, &[]), msg).unwrap();
+    deps.storage.take_gas_used()
+}
+
+#[test]
+fn bench_stake_many() {
+    let keys = nft_keys();
+
+    let mut deps = setup_deps();
+    let one_by_one = keys
+        .iter()
+        .map(|key| stake(&mut deps, std::slice::from_ref(key)))
+        .sum::<u64>();
+
+    let mut deps = setup_deps();
+    let batched = stake(&mut deps, &keys);
+
+    // the voting power snapshots are updated once for the whole batch,
+    // which saves more than half of the gas.
+    assert!(
+        batched * 2 < one_by_one,
+        "staking {} NFTs: {one_by_one} gas one by one, {batched} gas batched",
+        keys.len()
+    );
+}
+
+#[test]
+fn bench_unstake_many() {
+    let keys = nft_keys();
+
+    let mut deps = setup_deps();
+    stake(&mut deps, &keys);
+    let one_by_one = keys
+        .iter()
+        .map(|key| {
+            let (collection_id, token_id) = key.split_once('/').unwrap_or(("nft", key));
+            execute_as_staker(
+                &mut deps,
+                ExecuteMsg::Unstake {
+                    token_ids: vec![token_id.to_string()],
+                    collection_id: Some(collection_id.to_string()),
+                },
+            )
+        })
+        .sum::<u64>();
+
+    let mut deps = setup_deps();
+    stake(&mut deps, &keys);
+    let batched = execute_as_staker(
+        &mut deps,
+        ExecuteMsg::UnstakeMany {
+            token_ids: ["nft", "rare"]
+                .into_iter()
+                .map(|collection_id| CollectionTokenIds {
+                    collection_id: Some(collection_id.to_string()),
+                    token_ids: (0..NFTS_PER_COLLECTION).map(|i| i.to_string()).collect(),
+                })
+                .collect(),
+        },
+    );
+
+    assert!(
+        batched * 2 < one_by_one,
+        "unstaking {} NFTs: {one_by_one} gas one by one, {batched} gas batched",
+        keys.len()
+    );
+}
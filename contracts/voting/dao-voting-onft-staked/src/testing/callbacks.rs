@@ -0,0 +1,134 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError,
+    StdResult,
+};
+use cw_multi_test::{next_block, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+use cw_utils::Duration;
+
+use crate::{msg::ClaimCallback, msg::ClaimCallbackMsg, state::Callback};
+
+use super::{
+    execute::{claim_nfts, claim_nfts_for, mint_and_stake_nft, set_claim_callback, unstake_nfts},
+    is_error,
+    queries::{query_claim_callback, query_nft_owner},
+    setup_test, CommonTest, DAO, STAKER,
+};
+
+/// The NFTs claimed by each callback received by the vault.
+const CLAIMED: Item<Vec<Vec<String>>> = Item::new("claimed");
+
+/// A vault that stakes NFTs and records the NFTs claimed in each callback. It
+/// fails callbacks whose message is `"fail"`.
+fn vault_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(deps: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        CLAIMED.save(deps.storage, &vec![])?;
+        Ok(Response::default())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _: Env,
+        _: MessageInfo,
+        msg: ClaimCallbackMsg,
+    ) -> StdResult<Response> {
+        let ClaimCallbackMsg::NftsClaimed { token_ids, msg, .. } = msg;
+        if msg == to_json_binary("fail")? {
+            return Err(StdError::generic_err("callback failed"));
+        }
+        CLAIMED.update(deps.storage, |mut claimed| -> StdResult<_> {
+            claimed.push(token_ids);
+            Ok(claimed)
+        })?;
+        Ok(Response::default())
+    }
+
+    fn query(deps: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        to_json_binary(&CLAIMED.load(deps.storage)?)
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// A contract staker with a claim callback may have its NFTs claimed by
+/// anyone, and is notified when they are.
+#[test]
+fn test_claim_callback() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(1)), None);
+
+    let vault_id = app.store_code(vault_contract());
+    let vault = app.instantiate_contract(
+        vault_id,
+        Addr::unchecked(DAO),
+        &Empty {},
+        &[],
+        "vault",
+        None,
+    )?;
+    let callback = |msg: &str| -> StdResult<Option<ClaimCallback>> {
+        Ok(Some(ClaimCallback {
+            contract: vault.to_string(),
+            msg: to_json_binary(msg)?,
+        }))
+    };
+
+    // only contracts may register a callback.
+    let res = set_claim_callback(&mut app, &module, STAKER, callback("")?);
+    is_error!(res => "Only smart contracts can register a claim callback");
+
+    // and only stakers with callbacks may be claimed for.
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    app.update_block(next_block);
+    let res = claim_nfts_for(&mut app, &module, "keeper", STAKER);
+    is_error!(res => "has not registered a claim callback");
+    claim_nfts(&mut app, &module, STAKER)?;
+
+    mint_and_stake_nft(&mut app, &nft, &module, vault.as_str(), "2")?;
+    mint_and_stake_nft(&mut app, &nft, &module, vault.as_str(), "3")?;
+    set_claim_callback(&mut app, &module, vault.as_str(), callback("")?)?;
+    assert_eq!(
+        query_claim_callback(&app, &module, vault.as_str())?,
+        Some(Callback {
+            contract: vault.clone(),
+            msg: to_json_binary("")?,
+        })
+    );
+
+    unstake_nfts(&mut app, &module, vault.as_str(), &["2"])?;
+    let res = claim_nfts_for(&mut app, &module, "keeper", vault.as_str());
+    is_error!(res => "No NFTs are ready to be claimed yet");
+
+    app.update_block(next_block);
+    claim_nfts_for(&mut app, &module, "keeper", vault.as_str())?;
+    assert_eq!(query_nft_owner(&app, &nft, "2")?, vault.to_string());
+    let claimed: Vec<Vec<String>> = app.wrap().query_wasm_smart(&vault, &Empty {})?;
+    assert_eq!(claimed, vec![vec!["2".to_string()]]);
+
+    // a failing callback does not prevent the claim.
+    set_claim_callback(&mut app, &module, vault.as_str(), callback("fail")?)?;
+    unstake_nfts(&mut app, &module, vault.as_str(), &["3"])?;
+    app.update_block(next_block);
+    let res = claim_nfts_for(&mut app, &module, "keeper", vault.as_str())?;
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "action" && a.value == "claim_callback_failed"));
+    assert_eq!(query_nft_owner(&app, &nft, "3")?, vault.to_string());
+    let claimed: Vec<Vec<String>> = app.wrap().query_wasm_smart(&vault, &Empty {})?;
+    assert_eq!(claimed.len(), 1);
+
+    // removing the callback opts out of others claiming.
+    set_claim_callback(&mut app, &module, vault.as_str(), None)?;
+    assert_eq!(query_claim_callback(&app, &module, vault.as_str())?, None);
+    let res = claim_nfts_for(&mut app, &module, "keeper", vault.as_str());
+    is_error!(res => "has not registered a claim callback");
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
-use crate::msg::ExecuteMsg;
+use crate::msg::{
+    ClaimCallback, CollectionTokenIds, ExecuteMsg, StakerClaims, TokenWeight,
+    UnstakingDurationPolicy,
+};
 use anyhow::Result as AnyResult;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Uint128};
 use cw_multi_test::AppResponse;
 use cw_multi_test::Executor;
 use cw_utils::Duration;
@@ -99,6 +102,7 @@ pub fn prepare_stake_nft(
         module.clone(),
         &ExecuteMsg::PrepareStake {
             token_ids: vec![token_id.to_string()],
+            collection_id: None,
         },
         &[],
     )
@@ -115,6 +119,8 @@ pub fn confirm_stake_nft(
         module.clone(),
         &ExecuteMsg::ConfirmStake {
             token_ids: vec![token_id.to_string()],
+            collection_id: None,
+            referrer: None,
         },
         &[],
     )
@@ -133,6 +139,59 @@ pub fn stake_nft(
     Ok(())
 }
 
+/// Stakes an NFT, naming `referrer` as the staker's referrer.
+pub fn stake_nft_with_referrer(
+    app: &mut OmniflixApp,
+    collection_id: &str,
+    module: &Addr,
+    sender: &str,
+    token_id: &str,
+    referrer: &str,
+) -> AnyResult<AppResponse> {
+    prepare_stake_nft(app, module, sender, token_id)?;
+    send_nft(app, collection_id, token_id, sender, module.as_str())?;
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::ConfirmStake {
+            token_ids: vec![token_id.to_string()],
+            collection_id: None,
+            referrer: Some(referrer.to_string()),
+        },
+        &[],
+    )
+}
+
+/// Stakes an NFT from a collection other than the default one.
+pub fn stake_collection_nft(
+    app: &mut OmniflixApp,
+    collection_id: &str,
+    module: &Addr,
+    sender: &str,
+    token_id: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::PrepareStake {
+            token_ids: vec![token_id.to_string()],
+            collection_id: Some(collection_id.to_string()),
+        },
+        &[],
+    )?;
+    send_nft(app, collection_id, token_id, sender, module.as_str())?;
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::ConfirmStake {
+            token_ids: vec![token_id.to_string()],
+            collection_id: Some(collection_id.to_string()),
+            referrer: None,
+        },
+        &[],
+    )
+}
+
 pub fn cancel_stake(
     app: &mut OmniflixApp,
     module: &Addr,
@@ -145,6 +204,7 @@ pub fn cancel_stake(
         module.clone(),
         &ExecuteMsg::CancelStake {
             token_ids: vec![token_id.to_string()],
+            collection_id: None,
             recipient: recipient.map(|s| s.to_string()),
         },
         &[],
@@ -175,6 +235,7 @@ pub fn unstake_nfts(
         module.clone(),
         &ExecuteMsg::Unstake {
             token_ids: token_ids.iter().map(|s| s.to_string()).collect(),
+            collection_id: None,
         },
         &[],
     )
@@ -189,7 +250,48 @@ pub fn update_config(
     app.execute_contract(
         addr!(sender),
         module.clone(),
-        &ExecuteMsg::UpdateConfig { duration },
+        &ExecuteMsg::UpdateConfig {
+            duration,
+            policy: None,
+        },
+        &[],
+    )
+}
+
+pub fn update_collection_unstaking_duration(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    collection_id: &str,
+    duration: Option<Duration>,
+    policy: Option<UnstakingDurationPolicy>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::UpdateCollectionUnstakingDuration {
+            collection_id: collection_id.to_string(),
+            duration,
+            policy,
+        },
+        &[],
+    )
+}
+
+pub fn update_config_with_policy(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    duration: Option<Duration>,
+    policy: UnstakingDurationPolicy,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::UpdateConfig {
+            duration,
+            policy: Some(policy),
+        },
         &[],
     )
 }
@@ -203,6 +305,36 @@ pub fn claim_nfts(app: &mut OmniflixApp, module: &Addr, sender: &str) -> AnyResu
     )
 }
 
+pub fn claim_nfts_for(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    staker: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::ClaimNftsFor {
+            staker: staker.to_string(),
+        },
+        &[],
+    )
+}
+
+pub fn set_claim_callback(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    callback: Option<ClaimCallback>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::SetClaimCallback { callback },
+        &[],
+    )
+}
+
 pub fn add_hook(
     app: &mut OmniflixApp,
     module: &Addr,
@@ -219,6 +351,22 @@ pub fn add_hook(
     )
 }
 
+pub fn add_member_hook(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    hook: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::AddMemberHook {
+            addr: hook.to_string(),
+        },
+        &[],
+    )
+}
+
 pub fn remove_hook(
     app: &mut OmniflixApp,
     module: &Addr,
@@ -234,3 +382,185 @@ pub fn remove_hook(
         &[],
     )
 }
+
+pub fn process_unstake_queue(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    limit: Option<u32>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::ProcessUnstakeQueue { limit },
+        &[],
+    )
+}
+
+pub fn shorten_claims(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    limit: Option<u32>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::ShortenClaims { limit },
+        &[],
+    )
+}
+
+pub fn dissolve(app: &mut OmniflixApp, module: &Addr, sender: &str) -> AnyResult<AppResponse> {
+    app.execute_contract(addr!(sender), module.clone(), &ExecuteMsg::Dissolve {}, &[])
+}
+
+pub fn import_claims(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    claims: Vec<StakerClaims>,
+    total: u64,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::ImportClaims { claims, total },
+        &[],
+    )
+}
+
+/// Unstakes NFTs from a collection other than the default one.
+pub fn unstake_collection_nfts(
+    app: &mut OmniflixApp,
+    collection_id: &str,
+    module: &Addr,
+    sender: &str,
+    token_ids: &[&str],
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::Unstake {
+            token_ids: token_ids.iter().map(|s| s.to_string()).collect(),
+            collection_id: Some(collection_id.to_string()),
+        },
+        &[],
+    )
+}
+
+pub fn set_token_weights(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    weights: &[(&str, Option<u128>)],
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::SetTokenWeights {
+            weights: weights
+                .iter()
+                .map(|(token_id, weight)| TokenWeight {
+                    collection_id: None,
+                    token_id: token_id.to_string(),
+                    weight: weight.map(Uint128::new),
+                })
+                .collect(),
+        },
+        &[],
+    )
+}
+
+pub fn delegate(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    delegate: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::Delegate {
+            delegate: delegate.to_string(),
+        },
+        &[],
+    )
+}
+
+pub fn undelegate(app: &mut OmniflixApp, module: &Addr, sender: &str) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::Undelegate {},
+        &[],
+    )
+}
+
+/// Groups `(collection_id, token_id)` pairs by collection, keeping the
+/// order collections first appear in.
+fn collection_token_ids(nfts: &[(&str, &str)]) -> Vec<CollectionTokenIds> {
+    let mut token_ids: Vec<CollectionTokenIds> = vec![];
+    for (collection_id, token_id) in nfts {
+        let collection_id = Some(collection_id.to_string());
+        match token_ids
+            .iter_mut()
+            .find(|ids| ids.collection_id == collection_id)
+        {
+            Some(ids) => ids.token_ids.push(token_id.to_string()),
+            None => token_ids.push(CollectionTokenIds {
+                collection_id,
+                token_ids: vec![token_id.to_string()],
+            }),
+        }
+    }
+    token_ids
+}
+
+/// Stakes `(collection_id, token_id)` pairs from any of the module's
+/// collections in one batch.
+pub fn stake_many_nfts(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    nfts: &[(&str, &str)],
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::PrepareStakeMany {
+            token_ids: collection_token_ids(nfts),
+        },
+        &[],
+    )?;
+    for (collection_id, token_id) in nfts {
+        send_nft(app, collection_id, token_id, sender, module.as_str())?;
+    }
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::ConfirmStakeMany {
+            token_ids: collection_token_ids(nfts),
+            referrer: None,
+        },
+        &[],
+    )
+}
+
+/// Unstakes `(collection_id, token_id)` pairs from any of the module's
+/// collections in one batch.
+pub fn unstake_many_nfts(
+    app: &mut OmniflixApp,
+    module: &Addr,
+    sender: &str,
+    nfts: &[(&str, &str)],
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        addr!(sender),
+        module.clone(),
+        &ExecuteMsg::UnstakeMany {
+            token_ids: collection_token_ids(nfts),
+        },
+        &[],
+    )
+}
@@ -42,6 +42,9 @@ fn test_hooks() {
             &Config {
                 onft_collection_id: "ekez-token".to_string(),
                 unstaking_duration: None,
+                unstaking_locked_until: None,
+                dissolved: false,
+                unique_stakers_threshold: None,
             },
         )
         .unwrap();
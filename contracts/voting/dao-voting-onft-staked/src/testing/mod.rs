@@ -1,4 +1,7 @@
+mod adversarial;
 mod app;
+mod benchmarks;
+mod callbacks;
 mod execute;
 mod hooks;
 mod omniflix_stargate;
@@ -6,13 +9,13 @@ mod queries;
 mod tests;
 
 use app::OmniflixApp;
-use cosmwasm_std::Addr;
-use cw_multi_test::Executor;
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::{AppResponse, Executor};
 use cw_utils::Duration;
 use dao_testing::contracts::onft_staked_voting_contract;
 use dao_voting::threshold::ActiveThreshold;
 
-use crate::msg::{InstantiateMsg, OnftCollection};
+use crate::msg::{InstantiateMsg, OnftCollection, WeightedOnftCollection};
 
 use self::execute::create_onft_collection;
 
@@ -41,11 +44,18 @@ pub(crate) fn setup_test(
             module_id,
             Addr::unchecked(DAO),
             &InstantiateMsg {
-                onft_collection: OnftCollection::Existing {
-                    id: nft.to_string(),
-                },
+                onft_collections: vec![WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: nft.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                }],
                 unstaking_duration,
                 active_threshold,
+                bootstrap_period: None,
+                unstake_budget: None,
+                unique_stakers_threshold: None,
             },
             &[],
             "onft_voting",
@@ -71,3 +81,20 @@ macro_rules! is_error {
 }
 
 pub(crate) use is_error;
+
+/// The attributes of each `ty` event emitted by the module, without
+/// the `_contract_address` attribute added to every wasm event.
+pub(crate) fn module_events(res: &AppResponse, ty: &str) -> Vec<Vec<(String, String)>> {
+    let ty = format!("wasm-{ty}");
+    res.events
+        .iter()
+        .filter(|e| e.ty == ty)
+        .map(|e| {
+            e.attributes
+                .iter()
+                .filter(|a| a.key != "_contract_address")
+                .map(|a| (a.key.clone(), a.value.clone()))
+                .collect()
+        })
+        .collect()
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::{Addr, StdResult, Uint128};
+use cosmwasm_std::{Addr, Binary, StdResult, Uint128};
 use cw721_controllers::NftClaimsResponse;
 use cw_controllers::HooksResponse;
 use dao_interface::voting::{
@@ -6,7 +6,15 @@ use dao_interface::voting::{
 };
 use omniflix_std::types::omniflix::onft::v1beta1::{QueryOnftRequest, QueryOnftResponse};
 
-use crate::{msg::QueryMsg, state::Config};
+use crate::{
+    msg::{
+        CollectionResponse, DelegationResponse, NftClaimInfoResponse, QueryMsg,
+        QueuedUnstakeResponse, ReferrerStatsResponse, StakedNftResponse, StakerClaims,
+        TokenWeightResponse, UnstakeBudgetResponse, VotingPowerProofResponse,
+        WeightedRandomStakerResponse,
+    },
+    state::{Callback, ClaimShortening, Config, ReferralStats},
+};
 
 use super::app::OmniflixApp;
 
@@ -15,6 +23,39 @@ pub fn query_config(app: &OmniflixApp, module: &Addr) -> StdResult<Config> {
     Ok(config)
 }
 
+pub fn query_collections(app: &OmniflixApp, module: &Addr) -> StdResult<Vec<CollectionResponse>> {
+    let collections = app
+        .wrap()
+        .query_wasm_smart(module, &QueryMsg::Collections {})?;
+    Ok(collections)
+}
+
+pub fn query_token_weight(app: &OmniflixApp, module: &Addr, token_id: &str) -> StdResult<Uint128> {
+    let weight = app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::TokenWeight {
+            token_id: token_id.to_string(),
+        },
+    )?;
+    Ok(weight)
+}
+
+pub fn query_token_weights(
+    app: &OmniflixApp,
+    module: &Addr,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+) -> StdResult<Vec<TokenWeightResponse>> {
+    let weights = app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::TokenWeights {
+            start_after: start_after.map(|s| s.to_string()),
+            limit,
+        },
+    )?;
+    Ok(weights)
+}
+
 pub fn query_claims(app: &OmniflixApp, module: &Addr, addr: &str) -> StdResult<NftClaimsResponse> {
     let claims = app.wrap().query_wasm_smart(
         module,
@@ -25,11 +66,58 @@ pub fn query_claims(app: &OmniflixApp, module: &Addr, addr: &str) -> StdResult<N
     Ok(claims)
 }
 
+pub fn query_claim_callback(
+    app: &OmniflixApp,
+    module: &Addr,
+    addr: &str,
+) -> StdResult<Option<Callback>> {
+    let callback = app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::ClaimCallback {
+            address: addr.to_string(),
+        },
+    )?;
+    Ok(callback)
+}
+
+pub fn query_claim_info(
+    app: &OmniflixApp,
+    module: &Addr,
+    addr: &str,
+) -> StdResult<Vec<NftClaimInfoResponse>> {
+    let claim_info = app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::NftClaimInfo {
+            address: addr.to_string(),
+        },
+    )?;
+    Ok(claim_info)
+}
+
+pub fn query_export_claims(
+    app: &OmniflixApp,
+    module: &Addr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<StakerClaims>> {
+    let claims = app
+        .wrap()
+        .query_wasm_smart(module, &QueryMsg::ExportClaims { start_after, limit })?;
+    Ok(claims)
+}
+
 pub fn query_hooks(app: &OmniflixApp, module: &Addr) -> StdResult<HooksResponse> {
     let hooks = app.wrap().query_wasm_smart(module, &QueryMsg::Hooks {})?;
     Ok(hooks)
 }
 
+pub fn query_member_hooks(app: &OmniflixApp, module: &Addr) -> StdResult<HooksResponse> {
+    let hooks = app
+        .wrap()
+        .query_wasm_smart(module, &QueryMsg::MemberHooks {})?;
+    Ok(hooks)
+}
+
 pub fn query_staked_nfts(
     app: &OmniflixApp,
     module: &Addr,
@@ -85,6 +173,113 @@ pub fn query_info(app: &OmniflixApp, module: &Addr) -> StdResult<InfoResponse> {
     Ok(info)
 }
 
+pub fn query_unstake_budget(
+    app: &OmniflixApp,
+    module: &Addr,
+) -> StdResult<Option<UnstakeBudgetResponse>> {
+    app.wrap()
+        .query_wasm_smart(module, &QueryMsg::UnstakeBudget {})
+}
+
+pub fn query_claim_shortening(
+    app: &OmniflixApp,
+    module: &Addr,
+) -> StdResult<Option<ClaimShortening>> {
+    app.wrap()
+        .query_wasm_smart(module, &QueryMsg::ClaimShortening {})
+}
+
+pub fn query_queued_unstakes(
+    app: &OmniflixApp,
+    module: &Addr,
+    addr: &str,
+) -> StdResult<Vec<QueuedUnstakeResponse>> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::QueuedUnstakes {
+            address: addr.to_string(),
+        },
+    )
+}
+
+pub fn query_delegation(
+    app: &OmniflixApp,
+    module: &Addr,
+    addr: &str,
+) -> StdResult<DelegationResponse> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::Delegation {
+            address: addr.to_string(),
+        },
+    )
+}
+
+pub fn query_voting_power_proof(
+    app: &OmniflixApp,
+    module: &Addr,
+    addr: &str,
+    height: Option<u64>,
+) -> StdResult<VotingPowerProofResponse> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::VotingPowerProof {
+            address: addr.to_string(),
+            height,
+        },
+    )
+}
+
+pub fn query_referrer(app: &OmniflixApp, module: &Addr, addr: &str) -> StdResult<Option<Addr>> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::Referrer {
+            address: addr.to_string(),
+        },
+    )
+}
+
+pub fn query_referral_stats(
+    app: &OmniflixApp,
+    module: &Addr,
+    addr: &str,
+) -> StdResult<ReferralStats> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::ReferralStats {
+            address: addr.to_string(),
+        },
+    )
+}
+
+pub fn query_list_referral_stats(
+    app: &OmniflixApp,
+    module: &Addr,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ReferrerStatsResponse>> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::ListReferralStats {
+            start_after: start_after.map(|s| s.to_string()),
+            limit,
+        },
+    )
+}
+
+pub fn query_weighted_random_staker(
+    app: &OmniflixApp,
+    module: &Addr,
+    seed: &[u8],
+) -> StdResult<Option<WeightedRandomStakerResponse>> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::WeightedRandomStaker {
+            seed: Binary::from(seed),
+        },
+    )
+}
+
 pub fn query_total_and_voting_power(
     app: &OmniflixApp,
     module: &Addr,
@@ -97,6 +292,79 @@ pub fn query_total_and_voting_power(
     Ok((total_power.power, voting_power.power))
 }
 
+pub fn query_owner_of(
+    app: &OmniflixApp,
+    module: &Addr,
+    token_id: &str,
+) -> StdResult<cw721::OwnerOfResponse> {
+    app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::OwnerOf {
+            token_id: token_id.to_string(),
+            include_expired: None,
+        },
+    )
+}
+
+pub fn query_num_tokens(app: &OmniflixApp, module: &Addr) -> StdResult<u64> {
+    let response: cw721::NumTokensResponse = app
+        .wrap()
+        .query_wasm_smart(module, &QueryMsg::NumTokens {})?;
+    Ok(response.count)
+}
+
+pub fn query_tokens(
+    app: &OmniflixApp,
+    module: &Addr,
+    owner: &str,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let response: cw721::TokensResponse = app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::Tokens {
+            owner: owner.to_string(),
+            start_after,
+            limit,
+        },
+    )?;
+    Ok(response.tokens)
+}
+
+pub fn query_all_tokens(
+    app: &OmniflixApp,
+    module: &Addr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let response: cw721::TokensResponse = app
+        .wrap()
+        .query_wasm_smart(module, &QueryMsg::AllTokens { start_after, limit })?;
+    Ok(response.tokens)
+}
+
+pub fn query_staked_nfts_in_range(
+    app: &OmniflixApp,
+    module: &Addr,
+    min: Option<&str>,
+    max: Option<&str>,
+    weight: Option<u128>,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+) -> StdResult<Vec<StakedNftResponse>> {
+    let nfts = app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::StakedNftsInRange {
+            min: min.map(|s| s.to_string()),
+            max: max.map(|s| s.to_string()),
+            weight: weight.map(Uint128::new),
+            start_after: start_after.map(|s| s.to_string()),
+            limit,
+        },
+    )?;
+    Ok(nfts)
+}
+
 pub fn query_nft_owner(
     app: &OmniflixApp,
     collection_id: &str,
@@ -1,31 +1,62 @@
+use cosmwasm_std::storage_keys::namespace_with_key;
 use cosmwasm_std::testing::{mock_dependencies, mock_env};
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Event, StdResult, Storage, Timestamp, Uint128};
 use cw721_controllers::{NftClaim, NftClaimsResponse};
 use cw_multi_test::{next_block, Executor};
-use cw_utils::Duration;
+use cw_utils::{Duration, Expiration};
 use dao_interface::voting::IsActiveResponse;
 use dao_voting::threshold::{ActiveThreshold, ActiveThresholdResponse};
 
-use crate::msg::OnftCollection;
+use crate::events::{
+    CLAIM_EVENT, HOOK_ADDED_EVENT, HOOK_REMOVED_EVENT, STAKE_EVENT, UNSTAKE_EVENT,
+    UNSTAKE_QUEUED_EVENT,
+};
+use crate::msg::{
+    CollectionResponse, ExportedClaim, OnftCollection, StakedNftResponse, StakerClaims,
+    StorageEntry, TokenWeightResponse, WeightedOnftCollection,
+};
+use crate::testing::app::OmniflixApp;
 use crate::testing::execute::{cancel_stake, confirm_stake_nft, prepare_stake_nft, send_nft};
 use crate::testing::queries::query_dao;
 use crate::testing::DAO;
 use crate::{
     contract::{migrate, CONTRACT_NAME, CONTRACT_VERSION},
-    msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
-    state::MAX_CLAIMS,
+    msg::{
+        ExecuteMsg, InstantiateMsg, MigrateMsg, NftClaimInfoResponse, QueryMsg,
+        UnstakingDurationPolicy,
+    },
+    state::{
+        register_staked_nfts, register_unstaked_nfts, sample_staker, staker_weight,
+        total_staker_weight, ClaimInfo, Config, ReferralStats, UnstakeBudget, CLAIM_INFO,
+        COLLECTIONS, CONFIG, CURRENT_STATE_VERSION, MAX_CLAIMS, NFT_BALANCES, NFT_CLAIMS,
+        NFT_STAKERS, STAKED_NFT_COUNT, STATE_VERSION, UNIQUE_STAKERS, UNIQUE_STAKER_COUNT,
+    },
     testing::{
         execute::{
-            claim_nfts, mint_and_stake_nft, mint_nft, stake_nft, unstake_nfts, update_config,
+            claim_nfts, create_onft_collection, delegate, dissolve, import_claims,
+            mint_and_stake_nft, mint_nft, process_unstake_queue, set_token_weights, shorten_claims,
+            stake_collection_nft, stake_many_nfts, stake_nft, stake_nft_with_referrer, undelegate,
+            unstake_collection_nfts, unstake_many_nfts, unstake_nfts,
+            update_collection_unstaking_duration, update_config, update_config_with_policy,
+        },
+        queries::{
+            query_all_tokens, query_collections, query_config, query_delegation,
+            query_export_claims, query_hooks, query_list_referral_stats, query_member_hooks,
+            query_nft_owner, query_num_tokens, query_owner_of, query_queued_unstakes,
+            query_referral_stats, query_referrer, query_staked_nfts_in_range, query_token_weight,
+            query_token_weights, query_tokens, query_total_and_voting_power, query_unstake_budget,
+            query_voting_power_proof, query_weighted_random_staker,
         },
-        queries::{query_config, query_hooks, query_nft_owner, query_total_and_voting_power},
     },
 };
 
 use super::{
-    execute::{add_hook, remove_hook},
-    is_error,
-    queries::{query_claims, query_info, query_staked_nfts, query_total_power, query_voting_power},
+    execute::{add_hook, add_member_hook, remove_hook},
+    is_error, module_events,
+    queries::{
+        query_claim_info, query_claim_shortening, query_claims, query_info, query_staked_nfts,
+        query_total_power, query_voting_power,
+    },
     setup_test, CommonTest, STAKER,
 };
 
@@ -229,6 +260,191 @@ fn test_update_config() -> anyhow::Result<()> {
     Ok(())
 }
 
+// Updating the unstaking duration with the `ShortenExisting` policy applies
+// the new duration to pending claims, measured from when they were unstaked,
+// only if it releases them sooner and is in the same units.
+#[test]
+fn test_update_config_shorten_existing_claims() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(10)), None);
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+
+    let unstaked_at = app.block_info();
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    app.update_block(next_block);
+
+    update_config_with_policy(
+        &mut app,
+        &module,
+        DAO,
+        Some(Duration::Height(4)),
+        UnstakingDurationPolicy::ShortenExisting {},
+    )?;
+
+    let claims = query_claims(&app, &module, STAKER)?;
+    assert_eq!(
+        claims.nft_claims,
+        vec![NftClaim {
+            token_id: "1".to_string(),
+            release_at: cw_utils::Expiration::AtHeight(unstaked_at.height + 4)
+        }]
+    );
+    let claim_info = query_claim_info(&app, &module, STAKER)?;
+    assert_eq!(
+        claim_info,
+        vec![NftClaimInfoResponse {
+            token_id: "1".to_string(),
+            claim_info: ClaimInfo {
+                duration: Duration::Height(4),
+                unstaked_at_height: unstaked_at.height,
+                unstaked_at_time: unstaked_at.time,
+            }
+        }]
+    );
+
+    unstake_nfts(&mut app, &module, STAKER, &["2"])?;
+    let claims = query_claims(&app, &module, STAKER)?;
+
+    // longer durations and durations in different units do not affect
+    // pending claims.
+    update_config_with_policy(
+        &mut app,
+        &module,
+        DAO,
+        Some(Duration::Height(8)),
+        UnstakingDurationPolicy::ShortenExisting {},
+    )?;
+    update_config_with_policy(
+        &mut app,
+        &module,
+        DAO,
+        Some(Duration::Time(1)),
+        UnstakingDurationPolicy::ShortenExisting {},
+    )?;
+    assert_eq!(query_claims(&app, &module, STAKER)?, claims);
+
+    let res = claim_nfts(&mut app, &module, STAKER);
+    is_error!(res => "No NFTs are ready to be claimed yet");
+
+    // removing the unstaking duration releases all pending claims.
+    update_config_with_policy(
+        &mut app,
+        &module,
+        DAO,
+        None,
+        UnstakingDurationPolicy::ShortenExisting {},
+    )?;
+    claim_nfts(&mut app, &module, STAKER)?;
+
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER.to_string());
+    assert_eq!(query_nft_owner(&app, &nft, "2")?, STAKER.to_string());
+    assert_eq!(query_claim_info(&app, &module, STAKER)?, vec![]);
+
+    Ok(())
+}
+
+#[test]
+fn test_shorten_existing_claims_in_pages() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(10)), None);
+
+    // one more staker with a claim than are shortened at once.
+    let stakers = (0..31).map(|i| format!("staker{i:02}")).collect::<Vec<_>>();
+    for (i, staker) in stakers.iter().enumerate() {
+        let token_id = i.to_string();
+        mint_and_stake_nft(&mut app, &nft, &module, staker, &token_id)?;
+        unstake_nfts(&mut app, &module, staker, &[token_id.as_str()])?;
+    }
+    let unstaked_at = app.block_info().height;
+
+    // and a claim imported without claim info.
+    mint_nft(&mut app, &nft, STAKER, "imported")?;
+    send_nft(&mut app, &nft, "imported", STAKER, module.as_str())?;
+    let release_at = Expiration::AtHeight(unstaked_at + 100);
+    import_claims(
+        &mut app,
+        &module,
+        DAO,
+        vec![StakerClaims {
+            address: STAKER.to_string(),
+            claims: vec![ExportedClaim {
+                collection_id: nft.clone(),
+                token_id: "imported".to_string(),
+                release_at,
+                claim_info: None,
+            }],
+        }],
+        1,
+    )?;
+
+    app.update_block(next_block);
+    let set_at = app.block_info().height;
+    let res = update_config_with_policy(
+        &mut app,
+        &module,
+        DAO,
+        Some(Duration::Height(4)),
+        UnstakingDurationPolicy::ShortenExisting {},
+    )?;
+    assert!(
+        res.has_event(&cosmwasm_std::Event::new("wasm").add_attribute("claims_shortened", "30"))
+    );
+
+    // the importing staker sorts first, so the last two stakers' claims are
+    // shortened later, and no other update may shorten claims until then.
+    let shortening = query_claim_shortening(&app, &module)?.unwrap();
+    assert_eq!(shortening.cursor, Some(Addr::unchecked(&stakers[28])));
+    let release = |app: &OmniflixApp, staker: &str| -> anyhow::Result<Expiration> {
+        Ok(query_claims(app, &module, staker)?.nft_claims[0].release_at)
+    };
+    assert_eq!(
+        release(&app, &stakers[0])?,
+        Expiration::AtHeight(unstaked_at + 4)
+    );
+    assert_eq!(
+        release(&app, &stakers[29])?,
+        Expiration::AtHeight(unstaked_at + 10)
+    );
+    let res = update_config_with_policy(
+        &mut app,
+        &module,
+        DAO,
+        Some(Duration::Height(2)),
+        UnstakingDurationPolicy::ShortenExisting {},
+    );
+    is_error!(res => "Pending claims are still being shortened");
+
+    shorten_claims(&mut app, &module, STAKER, None)?;
+    assert_eq!(query_claim_shortening(&app, &module)?, None);
+    for staker in &stakers[29..] {
+        assert_eq!(
+            release(&app, staker)?,
+            Expiration::AtHeight(unstaked_at + 4)
+        );
+    }
+
+    // the claim without claim info is released the new duration after it
+    // was set.
+    assert_eq!(release(&app, STAKER)?, Expiration::AtHeight(set_at + 4));
+    assert!(query_claim_info(&app, &module, STAKER)?.is_empty());
+
+    app.update_block(|b| b.height = set_at + 4);
+    claim_nfts(&mut app, &module, STAKER)?;
+    assert_eq!(query_nft_owner(&app, &nft, "imported")?, STAKER);
+
+    Ok(())
+}
+
 // I can query my pending claims. Attempting to claim with nothing to
 // claim results in an error. Attempting to claim with tokens to claim
 // results in me owning those tokens.
@@ -264,7 +480,7 @@ fn test_claims() -> anyhow::Result<()> {
 
     // Claim now exists, but is not yet expired. Nothing to claim.
     let res = claim_nfts(&mut app, &module, STAKER);
-    is_error!(res => "Nothing to claim");
+    is_error!(res => "No NFTs are ready to be claimed yet");
 
     app.update_block(next_block);
     claim_nfts(&mut app, &module, STAKER)?;
@@ -399,6 +615,95 @@ fn test_add_remove_hooks() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Each NFT staked, unstaked and claimed, and each hook, is reported
+/// with an event whose attributes are stable.
+#[test]
+fn test_stake_lifecycle_events() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(5)), None);
+
+    let attrs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+
+    let res = add_hook(&mut app, &module, DAO, "meow")?;
+    assert_eq!(
+        module_events(&res, HOOK_ADDED_EVENT),
+        vec![attrs(&[("hook", "meow")])]
+    );
+    let res = remove_hook(&mut app, &module, DAO, "meow")?;
+    assert_eq!(
+        module_events(&res, HOOK_REMOVED_EVENT),
+        vec![attrs(&[("hook", "meow")])]
+    );
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+    mint_nft(&mut app, &nft, "bob", "2")?;
+    let res = stake_collection_nft(&mut app, &nft, &module, STAKER, "1")?;
+    assert_eq!(
+        module_events(&res, STAKE_EVENT),
+        vec![attrs(&[
+            ("staker", STAKER),
+            ("collection", &nft),
+            ("token_id", "1"),
+            ("new_power", "1"),
+            ("total_power", "1"),
+        ])]
+    );
+    let res = stake_collection_nft(&mut app, &nft, &module, "bob", "2")?;
+    assert_eq!(
+        module_events(&res, STAKE_EVENT),
+        vec![attrs(&[
+            ("staker", "bob"),
+            ("collection", &nft),
+            ("token_id", "2"),
+            ("new_power", "1"),
+            ("total_power", "2"),
+        ])]
+    );
+
+    let res = unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    assert_eq!(
+        module_events(&res, UNSTAKE_EVENT),
+        vec![attrs(&[
+            ("staker", STAKER),
+            ("collection", &nft),
+            ("token_id", "1"),
+            ("new_power", "0"),
+            ("total_power", "1"),
+            ("claim_duration", &Duration::Height(5).to_string()),
+        ])]
+    );
+
+    app.update_block(|b| b.height += 5);
+    let res = claim_nfts(&mut app, &module, STAKER)?;
+    assert_eq!(
+        module_events(&res, CLAIM_EVENT),
+        vec![attrs(&[
+            ("staker", STAKER),
+            ("collection", &nft),
+            ("token_id", "1"),
+        ])]
+    );
+
+    // NFTs returned right away report no claim duration.
+    update_config(&mut app, &module, DAO, None)?;
+    let res = unstake_nfts(&mut app, &module, "bob", &["2"])?;
+    assert_eq!(
+        module_events(&res, UNSTAKE_EVENT)[0].last(),
+        Some(&("claim_duration".to_string(), "none".to_string()))
+    );
+
+    Ok(())
+}
+
 #[test]
 #[should_panic(expected = "Active threshold count must be greater than zero")]
 fn test_instantiate_zero_active_threshold_count() {
@@ -439,13 +744,20 @@ fn test_active_threshold_absolute_count() {
             module_id,
             Addr::unchecked(DAO),
             &InstantiateMsg {
-                onft_collection: OnftCollection::Existing {
-                    id: nft.to_string(),
-                },
+                onft_collections: vec![WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: nft.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                }],
                 unstaking_duration: None,
                 active_threshold: Some(ActiveThreshold::AbsoluteCount {
                     count: Uint128::new(3),
                 }),
+                bootstrap_period: None,
+                unstake_budget: None,
+                unique_stakers_threshold: None,
             },
             &[],
             "onft_voting",
@@ -494,13 +806,20 @@ fn test_active_threshold_percent() {
             module_id,
             Addr::unchecked(DAO),
             &InstantiateMsg {
-                onft_collection: OnftCollection::Existing {
-                    id: nft.to_string(),
-                },
+                onft_collections: vec![WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: nft.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                }],
                 unstaking_duration: None,
                 active_threshold: Some(ActiveThreshold::Percentage {
                     percent: Decimal::percent(20),
                 }),
+                bootstrap_period: None,
+                unstake_budget: None,
+                unique_stakers_threshold: None,
             },
             &[],
             "onft_voting",
@@ -550,13 +869,20 @@ fn test_active_threshold_percent_rounds_up() {
             module_id,
             Addr::unchecked(DAO),
             &InstantiateMsg {
-                onft_collection: OnftCollection::Existing {
-                    id: nft.to_string(),
-                },
+                onft_collections: vec![WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: nft.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                }],
                 unstaking_duration: None,
                 active_threshold: Some(ActiveThreshold::Percentage {
                     percent: Decimal::percent(50),
                 }),
+                bootstrap_period: None,
+                unstake_budget: None,
+                unique_stakers_threshold: None,
             },
             &[],
             "onft_voting",
@@ -597,6 +923,87 @@ fn test_active_threshold_percent_rounds_up() {
     assert!(is_active.active);
 }
 
+#[test]
+fn test_unique_stakers_threshold() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    let update = |app: &mut OmniflixApp, sender: &str, count: Option<u64>| {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            module.clone(),
+            &ExecuteMsg::UpdateUniqueStakersThreshold { count },
+            &[],
+        )
+    };
+
+    // only the DAO may set the threshold, and it must not be zero.
+    is_error!(update(&mut app, STAKER, Some(2)) => "Unauthorized");
+    is_error!(update(&mut app, DAO, Some(0)) => "Unique stakers threshold must be greater than zero");
+    update(&mut app, DAO, Some(2))?;
+    let config: Config = app
+        .wrap()
+        .query_wasm_smart(module.clone(), &QueryMsg::Config {})?;
+    assert_eq!(config.unique_stakers_threshold, Some(2));
+
+    let is_active = |app: &OmniflixApp| -> bool {
+        let resp: IsActiveResponse = app
+            .wrap()
+            .query_wasm_smart(module.clone(), &QueryMsg::IsActive {})
+            .unwrap();
+        resp.active
+    };
+
+    // many NFTs staked by a single address are one staker.
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+    assert!(!is_active(&app));
+
+    mint_and_stake_nft(&mut app, &nft, &module, "bob", "3")?;
+    assert!(is_active(&app));
+
+    // unstaking some NFTs keeps the staker counted.
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    assert!(is_active(&app));
+
+    unstake_nfts(&mut app, &module, STAKER, &["2"])?;
+    assert!(!is_active(&app));
+
+    // the active threshold must be met as well.
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "4")?;
+    app.update_block(next_block);
+    assert!(is_active(&app));
+    app.execute_contract(
+        Addr::unchecked(DAO),
+        module.clone(),
+        &ExecuteMsg::UpdateActiveThreshold {
+            new_threshold: Some(ActiveThreshold::AbsoluteCount {
+                count: Uint128::new(3),
+            }),
+        },
+        &[],
+    )?;
+    assert!(!is_active(&app));
+
+    update(&mut app, DAO, None)?;
+    app.execute_contract(
+        Addr::unchecked(DAO),
+        module.clone(),
+        &ExecuteMsg::UpdateActiveThreshold {
+            new_threshold: None,
+        },
+        &[],
+    )?;
+    unstake_nfts(&mut app, &module, "bob", &["3"])?;
+    assert!(is_active(&app));
+
+    Ok(())
+}
+
 #[test]
 fn test_update_active_threshold() {
     let CommonTest {
@@ -713,100 +1120,455 @@ fn test_active_threshold_percentage_lte_0() {
     );
 }
 
+/// Saves the config of a contract instantiated before collection
+/// weights were tracked.
+fn save_legacy_config(storage: &mut dyn Storage) {
+    CONFIG
+        .save(
+            storage,
+            &Config {
+                onft_collection_id: "onftdenom".to_string(),
+                unstaking_duration: None,
+                unstaking_locked_until: None,
+                dissolved: false,
+                unique_stakers_threshold: None,
+            },
+        )
+        .unwrap();
+}
+
 #[test]
 pub fn test_migrate_update_version() {
     let mut deps = mock_dependencies();
     cw2::set_contract_version(&mut deps.storage, "my-contract", "1.0.0").unwrap();
+    save_legacy_config(&mut deps.storage);
 
-    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
     let version = cw2::get_contract_version(&deps.storage).unwrap();
     assert_eq!(version.version, CONTRACT_VERSION);
     assert_eq!(version.contract, CONTRACT_NAME);
 
     // migrate again, should do nothing
-    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
     let version = cw2::get_contract_version(&deps.storage).unwrap();
     assert_eq!(version.version, CONTRACT_VERSION);
     assert_eq!(version.contract, CONTRACT_NAME);
 }
 
-/// Staking tokens has a one block delay before staked tokens are
-/// reflected in voting power. Unstaking tokens has a one block delay
-/// before the unstaking is reflected in voting power, yet you have
-/// access to the NFT. If I immediately stake an unstaked NFT, my
-/// voting power should not change.
 #[test]
-fn test_circular_stake() -> anyhow::Result<()> {
-    let CommonTest {
-        mut app,
-        module,
-        nft,
-        ..
-    } = setup_test(None, None);
+pub fn test_migrate_backfills_claim_info() {
+    let mut deps = mock_dependencies();
+    cw2::set_contract_version(&mut deps.storage, "my-contract", "1.0.0").unwrap();
+    CONFIG
+        .save(
+            &mut deps.storage,
+            &Config {
+                onft_collection_id: "onftdenom".to_string(),
+                unstaking_duration: Some(Duration::Height(10)),
+                unstaking_locked_until: None,
+                dissolved: false,
+                unique_stakers_threshold: None,
+            },
+        )
+        .unwrap();
 
-    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
-    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+    // claims created before claim info was tracked, i.e. state layout 1.
+    for staker in ["staker1", "staker2", "staker3"] {
+        NFT_CLAIMS
+            .create_nft_claims(
+                &mut deps.storage,
+                &Addr::unchecked(staker),
+                vec!["1".to_string()],
+                Expiration::AtHeight(25),
+            )
+            .unwrap();
+    }
+    NFT_CLAIMS
+        .create_nft_claims(
+            &mut deps.storage,
+            &Addr::unchecked("staker1"),
+            vec!["2".to_string()],
+            Expiration::AtTime(Timestamp::from_seconds(25)),
+        )
+        .unwrap();
 
-    app.update_block(next_block);
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(2) }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "false"));
+    assert!(CLAIM_INFO.has(&deps.storage, (&Addr::unchecked("staker2"), "1")));
+    assert!(!CLAIM_INFO.has(&deps.storage, (&Addr::unchecked("staker3"), "1")));
+    assert_eq!(STATE_VERSION.may_load(&deps.storage).unwrap(), None);
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(2) }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "true"));
+    assert_eq!(
+        STATE_VERSION.load(&deps.storage).unwrap(),
+        CURRENT_STATE_VERSION
+    );
+    assert_eq!(
+        CLAIM_INFO
+            .load(&deps.storage, (&Addr::unchecked("staker3"), "1"))
+            .unwrap(),
+        ClaimInfo {
+            duration: Duration::Height(10),
+            unstaked_at_height: 15,
+            unstaked_at_time: Timestamp::default(),
+        }
+    );
 
-    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(total, Uint128::new(2));
-    assert_eq!(voting, Uint128::new(2));
+    // claims in a different unit than the unstaking duration can not be
+    // recovered.
+    assert!(!CLAIM_INFO.has(&deps.storage, (&Addr::unchecked("staker1"), "2")));
+}
 
-    unstake_nfts(&mut app, &module, STAKER, &["1", "2"])?;
+#[test]
+pub fn test_migrate_backfills_nft_stakers() {
+    let mut deps = mock_dependencies();
+    cw2::set_contract_version(&mut deps.storage, "my-contract", "1.0.0").unwrap();
+    save_legacy_config(&mut deps.storage);
+    STATE_VERSION.save(&mut deps.storage, &2).unwrap();
+
+    let staker1 = Addr::unchecked("staker1");
+    let staker2 = Addr::unchecked("staker2");
+    register_staked_nfts(
+        &mut deps.storage,
+        1,
+        &staker1,
+        &vec!["1".to_string()],
+        Uint128::one(),
+    )
+    .unwrap();
+    register_staked_nfts(
+        &mut deps.storage,
+        1,
+        &staker2,
+        &vec!["2".to_string()],
+        Uint128::one(),
+    )
+    .unwrap();
+    NFT_CLAIMS
+        .create_nft_claims(
+            &mut deps.storage,
+            &staker2,
+            vec!["3".to_string()],
+            Expiration::AtHeight(25),
+        )
+        .unwrap();
+    // state layout 2 did not record stakers.
+    for token_id in ["1", "2"] {
+        NFT_STAKERS.remove(&mut deps.storage, token_id);
+    }
 
-    // Unchanged, one block delay.
-    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(total, Uint128::new(2));
-    assert_eq!(voting, Uint128::new(2));
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(1) }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "false"));
+    assert_eq!(NFT_STAKERS.load(&deps.storage, "1").unwrap(), staker1);
+    assert!(!NFT_STAKERS.has(&deps.storage, "2"));
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "true"));
+    assert_eq!(
+        STATE_VERSION.load(&deps.storage).unwrap(),
+        CURRENT_STATE_VERSION
+    );
+    assert_eq!(NFT_STAKERS.load(&deps.storage, "2").unwrap(), staker2);
+    assert_eq!(NFT_STAKERS.load(&deps.storage, "3").unwrap(), staker2);
+}
 
-    stake_nft(&mut app, &nft, &module, STAKER, "1")?;
-    stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+#[test]
+pub fn test_migrate_backfills_staker_weights() {
+    let mut deps = mock_dependencies();
+    cw2::set_contract_version(&mut deps.storage, "my-contract", "1.0.0").unwrap();
+    save_legacy_config(&mut deps.storage);
+    STATE_VERSION.save(&mut deps.storage, &3).unwrap();
+
+    // state layout 3 did not track staker weights.
+    let staker1 = Addr::unchecked("staker1");
+    let staker2 = Addr::unchecked("staker2");
+    NFT_BALANCES
+        .save(&mut deps.storage, &staker1, &Uint128::new(2), 1)
+        .unwrap();
+    NFT_BALANCES
+        .save(&mut deps.storage, &staker2, &Uint128::new(3), 1)
+        .unwrap();
+    assert_eq!(sample_staker(&deps.storage, b"seed").unwrap(), None);
 
-    // Unchanged.
-    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(total, Uint128::new(2));
-    assert_eq!(voting, Uint128::new(2));
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(1) }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "false"));
+    assert_eq!(
+        staker_weight(&deps.storage, &staker1).unwrap(),
+        Uint128::new(2)
+    );
+    assert_eq!(
+        staker_weight(&deps.storage, &staker2).unwrap(),
+        Uint128::zero()
+    );
 
-    app.update_block(next_block);
+    // staker2 stakes before they are migrated.
+    register_staked_nfts(
+        &mut deps.storage,
+        2,
+        &staker2,
+        &vec!["1".to_string()],
+        Uint128::one(),
+    )
+    .unwrap();
 
-    // Still unchanged.
-    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(total, Uint128::new(2));
-    assert_eq!(voting, Uint128::new(2));
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "true"));
+    assert_eq!(
+        STATE_VERSION.load(&deps.storage).unwrap(),
+        CURRENT_STATE_VERSION
+    );
+    assert_eq!(
+        staker_weight(&deps.storage, &staker2).unwrap(),
+        Uint128::new(4)
+    );
+    assert_eq!(total_staker_weight(&deps.storage).unwrap(), Uint128::new(6));
 
-    Ok(())
+    // the original collection is backfilled with a weight of one.
+    assert_eq!(
+        COLLECTIONS.load(&deps.storage, "onftdenom").unwrap(),
+        Uint128::one()
+    );
+    assert_eq!(STAKED_NFT_COUNT.load(&deps.storage).unwrap(), 1);
 }
 
-/// I can immediately unstake after staking even though voting powers
-/// aren't updated until one block later. Voting power does not change
-/// if I do this.
 #[test]
-fn test_immediate_unstake() -> anyhow::Result<()> {
+pub fn test_migrate_backfills_unique_stakers() {
+    let mut deps = mock_dependencies();
+    cw2::set_contract_version(&mut deps.storage, "my-contract", "1.0.0").unwrap();
+    save_legacy_config(&mut deps.storage);
+
+    let staker1 = Addr::unchecked("staker1");
+    let staker2 = Addr::unchecked("staker2");
+    let staker3 = Addr::unchecked("staker3");
+    for (staker, token_id) in [(&staker1, "1"), (&staker2, "2"), (&staker3, "3")] {
+        register_staked_nfts(
+            &mut deps.storage,
+            1,
+            staker,
+            &vec![token_id.to_string()],
+            Uint128::one(),
+        )
+        .unwrap();
+    }
+    register_unstaked_nfts(
+        &mut deps.storage,
+        2,
+        &staker3,
+        &["3".to_string()],
+        Uint128::one(),
+    )
+    .unwrap();
+    // state layout 5 did not track unique stakers.
+    for staker in [&staker1, &staker2] {
+        UNIQUE_STAKERS.remove(&mut deps.storage, staker);
+    }
+    UNIQUE_STAKER_COUNT.remove(&mut deps.storage);
+    STATE_VERSION.save(&mut deps.storage, &5).unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(1) }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "false"));
+    assert_eq!(UNIQUE_STAKER_COUNT.load(&deps.storage).unwrap(), 1);
+
+    // staker2 stakes again before they are migrated.
+    register_staked_nfts(
+        &mut deps.storage,
+        3,
+        &staker2,
+        &vec!["4".to_string()],
+        Uint128::one(),
+    )
+    .unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "complete" && a.value == "true"));
+    assert_eq!(
+        STATE_VERSION.load(&deps.storage).unwrap(),
+        CURRENT_STATE_VERSION
+    );
+    assert_eq!(UNIQUE_STAKER_COUNT.load(&deps.storage).unwrap(), 2);
+    assert!(!UNIQUE_STAKERS.has(&deps.storage, &staker3));
+}
+
+#[test]
+pub fn test_migrate_stake_between_pages() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
-        module,
+        module_id,
         nft,
         ..
     } = setup_test(None, None);
+    let module = app.instantiate_contract(
+        module_id,
+        Addr::unchecked(DAO),
+        &InstantiateMsg {
+            onft_collections: vec![WeightedOnftCollection {
+                collection: OnftCollection::Existing { id: nft.clone() },
+                weight: Uint128::one(),
+                unstaking_duration: None,
+            }],
+            unstaking_duration: Some(Duration::Height(10)),
+            active_threshold: None,
+            bootstrap_period: None,
+            unstake_budget: None,
+            unique_stakers_threshold: None,
+        },
+        &[],
+        "onft_voting",
+        Some(DAO.to_string()),
+    )?;
 
-    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
-    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+    for (staker, token_id) in [("staker1", "1"), ("staker2", "2")] {
+        mint_and_stake_nft(&mut app, &nft, &module, staker, token_id)?;
+        unstake_nfts(&mut app, &module, staker, &[token_id])?;
+    }
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "3")?;
 
-    unstake_nfts(&mut app, &module, STAKER, &["1", "2"])?;
+    // state layout 1 did not record collections.
+    let namespace = [b"contract_data/".as_slice(), module.as_bytes()].concat();
+    for key in [
+        STATE_VERSION.as_slice().to_vec(),
+        STAKED_NFT_COUNT.as_slice().to_vec(),
+        COLLECTIONS.key(nft.as_str()).to_vec(),
+    ] {
+        app.storage_mut()
+            .remove(&namespace_with_key(&[b"wasm", &namespace], &key));
+    }
 
-    app.update_block(next_block);
+    let res = app.migrate_contract(
+        Addr::unchecked(DAO),
+        module.clone(),
+        &MigrateMsg { limit: Some(1) },
+        module_id,
+    )?;
+    assert!(res.has_event(&Event::new("wasm").add_attribute("complete", "false")));
 
-    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(total, Uint128::zero());
-    assert_eq!(voting, Uint128::zero());
+    // NFTs may be staked before the migration completes.
+    mint_and_stake_nft(&mut app, &nft, &module, "staker4", "4")?;
+
+    let res = app.migrate_contract(
+        Addr::unchecked(DAO),
+        module.clone(),
+        &MigrateMsg { limit: None },
+        module_id,
+    )?;
+    assert!(res.has_event(&Event::new("wasm").add_attribute("complete", "true")));
+
+    app.update_block(next_block);
+    assert_eq!(
+        query_voting_power(&app, &module, "staker4", None)?.power,
+        Uint128::one()
+    );
+    assert_eq!(
+        query_total_power(&app, &module, None)?.power,
+        Uint128::new(2)
+    );
+    assert_eq!(query_export_claims(&app, &module, None, None)?.len(), 2);
 
     Ok(())
 }
 
-/// I cannot prepare/stake an NFT I do not own.
+/// Sampling a staker selects the same staker as a linear scan over
+/// the stakers' cumulative voting power.
 #[test]
-fn test_stake_unowned() -> anyhow::Result<()> {
+pub fn test_sample_staker_matches_linear_scan() {
+    use sha2::{Digest, Sha256};
+
+    let mut deps = mock_dependencies();
+    let stakers = (1..=7)
+        .map(|i| Addr::unchecked(format!("staker{i}")))
+        .collect::<Vec<_>>();
+    let mut token_id = 0;
+    for (i, staker) in stakers.iter().enumerate() {
+        let token_ids = (0..=i)
+            .map(|_| {
+                token_id += 1;
+                token_id.to_string()
+            })
+            .collect::<Vec<_>>();
+        let power = Uint128::new(token_ids.len() as u128);
+        register_staked_nfts(&mut deps.storage, 1, staker, &token_ids, power).unwrap();
+    }
+    // staker3 unstakes everything, and staker6 unstakes some of their
+    // NFTs.
+    register_unstaked_nfts(
+        &mut deps.storage,
+        2,
+        &stakers[2],
+        &["4".to_string(), "5".to_string(), "6".to_string()],
+        Uint128::new(3),
+    )
+    .unwrap();
+    register_unstaked_nfts(
+        &mut deps.storage,
+        2,
+        &stakers[5],
+        &["16".to_string()],
+        Uint128::one(),
+    )
+    .unwrap();
+
+    let weights = stakers
+        .iter()
+        .map(|staker| staker_weight(&deps.storage, staker).unwrap().u128())
+        .collect::<Vec<_>>();
+    assert_eq!(weights, vec![1, 2, 0, 4, 5, 5, 7]);
+    let total: u128 = weights.iter().sum();
+    assert_eq!(total_staker_weight(&deps.storage).unwrap().u128(), total);
+
+    let mut selected = vec![0; stakers.len()];
+    for seed in 0u32..500 {
+        let seed = seed.to_be_bytes();
+        let hash = Sha256::digest(seed);
+        let mut target = u128::from_be_bytes(hash[..16].try_into().unwrap()) % total;
+        let expected = weights
+            .iter()
+            .position(|weight| {
+                if target < *weight {
+                    true
+                } else {
+                    target -= weight;
+                    false
+                }
+            })
+            .unwrap();
+
+        let (staker, weight) = sample_staker(&deps.storage, &seed).unwrap().unwrap();
+        assert_eq!(staker, stakers[expected]);
+        assert_eq!(weight.u128(), weights[expected]);
+        selected[expected] += 1;
+    }
+    // a staker without voting power is never selected.
+    assert_eq!(selected[2], 0);
+    assert!(selected.iter().enumerate().all(|(i, n)| i == 2 || *n > 0));
+}
+
+#[test]
+fn test_weighted_random_staker() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -814,21 +1576,58 @@ fn test_stake_unowned() -> anyhow::Result<()> {
         ..
     } = setup_test(None, None);
 
-    mint_nft(&mut app, &nft, STAKER, "1")?;
+    assert_eq!(query_weighted_random_staker(&app, &module, b"seed")?, None);
 
-    let res = stake_nft(&mut app, &nft, &module, "other", "1");
-    is_error!(res => "Only an NFT's owner can prepare it to be staked");
+    for token_id in ["1", "2", "3"] {
+        mint_and_stake_nft(&mut app, &nft, &module, STAKER, token_id)?;
+    }
+    mint_and_stake_nft(&mut app, &nft, &module, "other", "4")?;
+
+    // the same seed always selects the same staker.
+    let selected = query_weighted_random_staker(&app, &module, b"seed")?.unwrap();
+    assert_eq!(
+        query_weighted_random_staker(&app, &module, b"seed")?,
+        Some(selected.clone())
+    );
+
+    let mut counts = (0, 0);
+    for seed in 0u32..100 {
+        let selected = query_weighted_random_staker(&app, &module, &seed.to_be_bytes())?.unwrap();
+        if selected.address == STAKER {
+            assert_eq!(selected.staked_weight, Uint128::new(3));
+            counts.0 += 1;
+        } else {
+            assert_eq!(selected.address, "other");
+            assert_eq!(selected.staked_weight, Uint128::new(1));
+            counts.1 += 1;
+        }
+    }
+    assert!(counts.0 > counts.1 && counts.1 > 0);
 
+    // stakers are selected by what they have staked, even once they
+    // delegate their voting power.
+    delegate(&mut app, &module, STAKER, "delegate")?;
     app.update_block(next_block);
-    let voting = query_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(voting.power, Uint128::new(0));
+    assert_eq!(
+        query_weighted_random_staker(&app, &module, b"seed")?,
+        Some(selected)
+    );
+
+    // once unstaked, a staker is no longer selected.
+    unstake_nfts(&mut app, &module, "other", &["4"])?;
+    for seed in 0u32..20 {
+        let selected = query_weighted_random_staker(&app, &module, &seed.to_be_bytes())?.unwrap();
+        assert_eq!(selected.address, STAKER);
+    }
+
+    unstake_nfts(&mut app, &module, STAKER, &["1", "2", "3"])?;
+    assert_eq!(query_weighted_random_staker(&app, &module, b"seed")?, None);
 
     Ok(())
 }
 
-/// I cannot confirm a stake before preparing it.
 #[test]
-fn test_stake_unprepared() -> anyhow::Result<()> {
+fn test_delegation() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -836,22 +1635,89 @@ fn test_stake_unprepared() -> anyhow::Result<()> {
         ..
     } = setup_test(None, None);
 
-    mint_nft(&mut app, &nft, STAKER, "1")?;
+    let power = |app: &OmniflixApp, addr: &str| -> StdResult<u128> {
+        Ok(query_voting_power(app, &module, addr, None)?.power.u128())
+    };
 
-    // attempt confirm without preparing
-    let res = confirm_stake_nft(&mut app, &module, STAKER, "1");
-    is_error!(res => "NFTs must be prepared and transferred before they can be staked");
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+    mint_and_stake_nft(&mut app, &nft, &module, "other", "3")?;
+    app.update_block(next_block);
+    let before_delegation = app.block_info().height;
 
+    delegate(&mut app, &module, STAKER, "delegate")?;
     app.update_block(next_block);
-    let voting = query_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(voting.power, Uint128::new(0));
+    assert_eq!(power(&app, STAKER)?, 0);
+    assert_eq!(power(&app, "delegate")?, 2);
+    assert_eq!(query_total_power(&app, &module, None)?.power.u128(), 3);
+    assert_eq!(
+        query_voting_power(&app, &module, STAKER, Some(before_delegation))?
+            .power
+            .u128(),
+        2
+    );
+    assert_eq!(
+        query_delegation(&app, &module, STAKER)?.delegate,
+        Some(Addr::unchecked("delegate"))
+    );
+    assert_eq!(
+        query_delegation(&app, &module, "delegate")?.delegated_power,
+        Uint128::new(2)
+    );
+
+    // delegated stakers' later stakes and unstakes move their delegate's
+    // voting power.
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "4")?;
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    app.update_block(next_block);
+    assert_eq!(power(&app, STAKER)?, 0);
+    assert_eq!(power(&app, "delegate")?, 2);
+
+    // delegated power is not delegated onwards, so it is never counted
+    // twice.
+    delegate(&mut app, &module, "other", "delegate")?;
+    delegate(&mut app, &module, "delegate", "other")?;
+    app.update_block(next_block);
+    assert_eq!(power(&app, "delegate")?, 3);
+    assert_eq!(power(&app, "other")?, 0);
+    assert_eq!(query_total_power(&app, &module, None)?.power.u128(), 3);
+
+    let res = delegate(&mut app, &module, STAKER, STAKER);
+    is_error!(res => "Can not delegate to yourself");
+    let res = delegate(&mut app, &module, STAKER, "delegate");
+    is_error!(res => "Voting power is already delegated to delegate");
+
+    // redelegating moves the power to the new delegate.
+    delegate(&mut app, &module, "other", STAKER)?;
+    app.update_block(next_block);
+    assert_eq!(power(&app, STAKER)?, 1);
+    assert_eq!(power(&app, "delegate")?, 2);
+
+    undelegate(&mut app, &module, STAKER)?;
+    app.update_block(next_block);
+    assert_eq!(power(&app, STAKER)?, 3);
+    assert_eq!(power(&app, "delegate")?, 0);
+    let res = undelegate(&mut app, &module, STAKER);
+    is_error!(res => "Voting power is not delegated");
+
+    // member hooks are notified of delegation, and staking hooks are
+    // not. this fails because meow isn't a contract.
+    add_hook(&mut app, &module, DAO, "meow")?;
+    delegate(&mut app, &module, STAKER, "delegate")?;
+    let res = add_member_hook(&mut app, &module, STAKER, "meow");
+    is_error!(res => "Unauthorized");
+    add_member_hook(&mut app, &module, DAO, "meow")?;
+    let hooks = query_member_hooks(&app, &module)?;
+    assert_eq!(hooks.hooks, vec!["meow".to_string()]);
+    undelegate(&mut app, &module, STAKER).unwrap_err();
 
     Ok(())
 }
 
-/// I cannot confirm a stake before preparing it and transferring NFT.
+/// Voting power proofs name the storage entries voting power is derived
+/// from, with their values as of the start of the block.
 #[test]
-fn test_stake_prepared_untransferred() -> anyhow::Result<()> {
+fn test_voting_power_proof() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -859,25 +1725,59 @@ fn test_stake_prepared_untransferred() -> anyhow::Result<()> {
         ..
     } = setup_test(None, None);
 
-    mint_nft(&mut app, &nft, STAKER, "1")?;
+    let before_stake = app.block_info().height;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+    delegate(&mut app, &module, STAKER, "delegate")?;
+    app.update_block(next_block);
 
-    // prepare but don't transfer
-    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    // the entries' values are those stored in the contract's state.
+    let raw = |app: &OmniflixApp, entry: &StorageEntry| {
+        app.wrap()
+            .query_wasm_raw(&module, entry.key.clone())
+            .unwrap()
+            .map(Binary::from)
+    };
+    let proof = query_voting_power_proof(&app, &module, STAKER, None)?;
+    assert_eq!(proof.height, app.block_info().height);
+    assert_eq!(proof.power, Uint128::zero());
+    assert_eq!(proof.total_power, Uint128::new(2));
+    assert_eq!(proof.staked.value, Some(Binary::from(br#""2""#)));
+    assert_eq!(proof.delegated_away.value, Some(Binary::from(br#""2""#)));
+    assert_eq!(proof.delegated.value, None);
+    assert_eq!(proof.total.value, Some(Binary::from(br#""2""#)));
+    for entry in [
+        &proof.staked,
+        &proof.delegated_away,
+        &proof.delegated,
+        &proof.total,
+    ] {
+        assert_eq!(raw(&app, entry), entry.value);
+    }
 
-    // attempt confirm
-    let res = confirm_stake_nft(&mut app, &module, STAKER, "1");
-    is_error!(res => "NFTs must be prepared and transferred before they can be staked");
+    let delegated = query_voting_power_proof(&app, &module, "delegate", None)?;
+    assert_eq!(delegated.power, Uint128::new(2));
+    assert_eq!(delegated.staked.value, None);
+    assert_eq!(delegated.delegated.value, Some(Binary::from(br#""2""#)));
+    assert_eq!(raw(&app, &delegated.delegated), delegated.delegated.value);
+    assert_eq!(
+        delegated.power,
+        query_voting_power(&app, &module, "delegate", None)?.power
+    );
 
-    app.update_block(next_block);
-    let voting = query_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(voting.power, Uint128::new(0));
+    // past heights report the values as of then, keyed the same.
+    let past = query_voting_power_proof(&app, &module, STAKER, Some(before_stake))?;
+    assert_eq!(past.power, Uint128::zero());
+    assert_eq!(past.total_power, Uint128::zero());
+    assert_eq!(past.staked.key, proof.staked.key);
+    assert_eq!(past.staked.value, None);
+    assert_eq!(past.total.value, None);
 
     Ok(())
 }
 
-/// I cannot confirm a stake that someone else prepared.
 #[test]
-fn test_stake_prepared_confirm_other_owner() -> anyhow::Result<()> {
+fn test_referrals() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -886,27 +1786,95 @@ fn test_stake_prepared_confirm_other_owner() -> anyhow::Result<()> {
     } = setup_test(None, None);
 
     mint_nft(&mut app, &nft, STAKER, "1")?;
+    let res = stake_nft_with_referrer(&mut app, &nft, &module, STAKER, "1", "referrer")?;
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .any(|a| a.key == "referrer" && a.value == "referrer"));
+    assert_eq!(
+        query_referrer(&app, &module, STAKER)?,
+        Some(Addr::unchecked("referrer"))
+    );
+    assert_eq!(
+        query_referral_stats(&app, &module, "referrer")?,
+        ReferralStats {
+            referrals: 1,
+            referred_power: Uint128::new(1),
+        }
+    );
 
-    // prepare
-    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    // a referrer may only be named on an address's first stake, but
+    // later stakes still count towards their referrer's power.
+    mint_nft(&mut app, &nft, STAKER, "2")?;
+    let res = stake_nft_with_referrer(&mut app, &nft, &module, STAKER, "2", "other");
+    is_error!(res => "A referrer may only be named on an address's first stake");
+    confirm_stake_nft(&mut app, &module, STAKER, "2")?;
+    assert_eq!(
+        query_referrer(&app, &module, STAKER)?,
+        Some(Addr::unchecked("referrer"))
+    );
 
-    // transfer to voting contract
-    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+    mint_nft(&mut app, &nft, "other", "3")?;
+    stake_nft_with_referrer(&mut app, &nft, &module, "other", "3", "referrer")?;
+    assert_eq!(
+        query_referral_stats(&app, &module, "referrer")?,
+        ReferralStats {
+            referrals: 2,
+            referred_power: Uint128::new(3),
+        }
+    );
 
-    // attempt confirm
-    let res = confirm_stake_nft(&mut app, &module, "other", "1");
-    is_error!(res => "NFTs must be prepared and transferred before they can be staked");
+    mint_nft(&mut app, &nft, "third", "4")?;
+    let res = stake_nft_with_referrer(&mut app, &nft, &module, "third", "4", "third");
+    is_error!(res => "Can not refer yourself");
+    app.execute_contract(
+        Addr::unchecked("third"),
+        module.clone(),
+        &ExecuteMsg::ConfirmStake {
+            token_ids: vec!["4".to_string()],
+            collection_id: None,
+            referrer: Some("other".to_string()),
+        },
+        &[],
+    )?;
 
-    app.update_block(next_block);
-    let voting = query_voting_power(&app, &module, "other", None)?;
-    assert_eq!(voting.power, Uint128::new(0));
+    // unstaking reduces the referrer's power.
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    assert_eq!(
+        query_list_referral_stats(&app, &module, None, None)?
+            .into_iter()
+            .map(|r| (
+                r.referrer.into_string(),
+                r.stats.referrals,
+                r.stats.referred_power
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("other".to_string(), 1, Uint128::new(1)),
+            ("referrer".to_string(), 2, Uint128::new(2)),
+        ]
+    );
+    let page = query_list_referral_stats(&app, &module, Some("other"), Some(1))?;
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].referrer, "referrer");
+
+    assert_eq!(query_referrer(&app, &module, "nobody")?, None);
+    assert_eq!(
+        query_referral_stats(&app, &module, "nobody")?,
+        ReferralStats::default()
+    );
 
     Ok(())
 }
 
-/// I can override a prepared stake.
+/// Staking tokens has a one block delay before staked tokens are
+/// reflected in voting power. Unstaking tokens has a one block delay
+/// before the unstaking is reflected in voting power, yet you have
+/// access to the NFT. If I immediately stake an unstaked NFT, my
+/// voting power should not change.
 #[test]
-fn test_override_prepared() -> anyhow::Result<()> {
+fn test_circular_stake() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -914,34 +1882,45 @@ fn test_override_prepared() -> anyhow::Result<()> {
         ..
     } = setup_test(None, None);
 
-    mint_nft(&mut app, &nft, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
 
-    // prepare
-    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    app.update_block(next_block);
 
-    // transfer to someone else
-    send_nft(&mut app, &nft, "1", STAKER, "other")?;
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(2));
+    assert_eq!(voting, Uint128::new(2));
 
-    // override previous owner's prepare
-    prepare_stake_nft(&mut app, &module, "other", "1")?;
+    unstake_nfts(&mut app, &module, STAKER, &["1", "2"])?;
 
-    // transfer to voting contract
-    send_nft(&mut app, &nft, "1", "other", module.as_str())?;
+    // Unchanged, one block delay.
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(2));
+    assert_eq!(voting, Uint128::new(2));
 
-    // confirm
-    confirm_stake_nft(&mut app, &module, "other", "1")?;
+    stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+
+    // Unchanged.
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(2));
+    assert_eq!(voting, Uint128::new(2));
 
     app.update_block(next_block);
-    let voting = query_voting_power(&app, &module, "other", None)?;
-    assert_eq!(voting.power, Uint128::new(1));
+
+    // Still unchanged.
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(2));
+    assert_eq!(voting, Uint128::new(2));
 
     Ok(())
 }
 
-/// I can determine what my voting power _will_ be after staking by
-/// asking for my voting power one block in the future.
+/// I can immediately unstake after staking even though voting powers
+/// aren't updated until one block later. Voting power does not change
+/// if I do this.
 #[test]
-fn test_query_the_future() -> anyhow::Result<()> {
+fn test_immediate_unstake() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -950,87 +1929,224 @@ fn test_query_the_future() -> anyhow::Result<()> {
     } = setup_test(None, None);
 
     mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
 
-    // Future voting power will be one under current conditions.
-    let voting = query_voting_power(&app, &module, STAKER, Some(app.block_info().height + 100))?;
-    assert_eq!(voting.power, Uint128::new(1));
-
-    // Current voting power is zero.
-    let voting = query_voting_power(&app, &module, STAKER, None)?;
-    assert_eq!(voting.power, Uint128::new(0));
-
-    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    unstake_nfts(&mut app, &module, STAKER, &["1", "2"])?;
 
-    // Future voting power is now zero.
-    let voting = query_voting_power(&app, &module, STAKER, Some(app.block_info().height + 100))?;
-    assert_eq!(voting.power, Uint128::zero());
+    app.update_block(next_block);
 
-    Ok(())
-}
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::zero());
+    assert_eq!(voting, Uint128::zero());
 
-/// I can not unstake more than one NFT in a TX in order to bypass the
-/// MAX_CLAIMS limit.
-#[test]
-fn test_bypass_max_claims() -> anyhow::Result<()> {
-    let CommonTest {
-        mut app,
-        module,
-        nft,
-        ..
-    } = setup_test(Some(Duration::Height(1)), None);
-    let mut to_stake = vec![];
-    for i in 1..(MAX_CLAIMS + 10) {
-        let i_str = &i.to_string();
-        mint_and_stake_nft(&mut app, &nft, &module, STAKER, i_str)?;
-        if i < MAX_CLAIMS {
-            // unstake MAX_CLAMS - 1 NFTs
-            unstake_nfts(&mut app, &module, STAKER, &[i_str])?;
-        } else {
-            // push rest of NFT ids to vec
-            to_stake.push(i_str.clone());
-        }
-    }
-    let binding = to_stake.iter().map(|s| s.as_str()).collect::<Vec<_>>();
-    let to_stake_slice: &[&str] = binding.as_slice();
-    let res = unstake_nfts(&mut app, &module, STAKER, to_stake_slice);
-    is_error!(res => "Too many outstanding claims. Claim some tokens before unstaking more.");
     Ok(())
 }
 
-/// I can cancel my own prepared stake.
+/// No NFTs may be unstaked during the bootstrap period, regardless
+/// of the unstaking duration.
 #[test]
-fn test_preparer_cancel_prepared_stake() -> anyhow::Result<()> {
+fn test_bootstrap_period() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
-        module,
+        module_id,
         nft,
         ..
     } = setup_test(None, None);
 
-    mint_nft(&mut app, &nft, STAKER, "1")?;
+    let module = app.instantiate_contract(
+        module_id,
+        Addr::unchecked(DAO),
+        &InstantiateMsg {
+            onft_collections: vec![WeightedOnftCollection {
+                collection: OnftCollection::Existing {
+                    id: nft.to_string(),
+                },
+                weight: Uint128::one(),
+                unstaking_duration: None,
+            }],
+            unstaking_duration: None,
+            active_threshold: None,
+            bootstrap_period: Some(Duration::Height(10)),
+            unstake_budget: None,
+            unique_stakers_threshold: None,
+        },
+        &[],
+        "onft_voting",
+        None,
+    )?;
 
-    // prepare but don't transfer
-    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    let until = Expiration::AtHeight(app.block_info().height + 10);
+    assert_eq!(
+        query_config(&app, &module)?.unstaking_locked_until,
+        Some(until)
+    );
 
-    // cancel
-    cancel_stake(&mut app, &module, STAKER, "1", None)?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
 
-    // prepare and transfer
-    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
-    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+    let res = unstake_nfts(&mut app, &module, STAKER, &["1"]);
+    is_error!(res => "Unstaking is locked until expiration height: ");
 
-    // voting contract has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, module.to_string());
+    app.update_block(|b| b.height += 9);
+    let res = unstake_nfts(&mut app, &module, STAKER, &["1"]);
+    is_error!(res => "Unstaking is locked");
 
-    // cancel
-    cancel_stake(&mut app, &module, STAKER, "1", None)?;
+    app.update_block(next_block);
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
 
-    // original preparer has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, STAKER.to_string());
+    Ok(())
+}
+
+/// Unstakes beyond the epoch's budget are queued, keep their voting
+/// power, and are unstaked in order once the next epoch begins.
+#[test]
+fn test_unstake_budget() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    let instantiate = |app: &mut OmniflixApp, unstake_budget| {
+        app.instantiate_contract(
+            module_id,
+            Addr::unchecked(DAO),
+            &InstantiateMsg {
+                onft_collections: vec![WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: nft.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                }],
+                unstaking_duration: None,
+                active_threshold: None,
+                bootstrap_period: None,
+                unstake_budget: Some(unstake_budget),
+                unique_stakers_threshold: None,
+            },
+            &[],
+            "onft_voting",
+            None,
+        )
+    };
+
+    let res = instantiate(
+        &mut app,
+        UnstakeBudget {
+            max_percent: Decimal::zero(),
+            epoch: Duration::Height(10),
+        },
+    );
+    is_error!(res => "Unstake budget must have a non-zero epoch");
+    let res = instantiate(
+        &mut app,
+        UnstakeBudget {
+            max_percent: Decimal::percent(50),
+            epoch: Duration::Time(0),
+        },
+    );
+    is_error!(res => "Unstake budget must have a non-zero epoch");
+
+    let module = instantiate(
+        &mut app,
+        UnstakeBudget {
+            max_percent: Decimal::percent(50),
+            epoch: Duration::Height(10),
+        },
+    )?;
+
+    // start at the beginning of an epoch.
+    app.update_block(|b| b.height += 10 - b.height % 10);
+    for token_id in ["1", "2", "3", "4"] {
+        mint_and_stake_nft(&mut app, &nft, &module, STAKER, token_id)?;
+    }
+
+    let res = unstake_nfts(&mut app, &module, STAKER, &["1", "2", "3"])?;
+    assert_eq!(module_events(&res, UNSTAKE_EVENT).len(), 2);
+    assert_eq!(
+        module_events(&res, UNSTAKE_QUEUED_EVENT),
+        vec![vec![
+            ("staker".to_string(), STAKER.to_string()),
+            ("collection".to_string(), nft.clone()),
+            ("token_id".to_string(), "3".to_string()),
+        ]]
+    );
+    let res = unstake_nfts(&mut app, &module, STAKER, &["3"]);
+    is_error!(res => "NFT 3 is already queued to be unstaked");
+    unstake_nfts(&mut app, &module, STAKER, &["4"])?;
+
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &nft, "2")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &nft, "3")?, module.to_string());
+
+    let budget = query_unstake_budget(&app, &module)?.unwrap();
+    assert_eq!(budget.budget, Uint128::new(2));
+    assert_eq!(budget.used, Uint128::new(2));
+    assert_eq!(budget.queued, 2);
+    assert_eq!(
+        budget.next_epoch_starts,
+        Expiration::AtHeight(app.block_info().height + 10)
+    );
+    let queued = query_queued_unstakes(&app, &module, STAKER)?;
+    assert_eq!(
+        queued
+            .iter()
+            .map(|q| (q.token_id.as_str(), q.position))
+            .collect::<Vec<_>>(),
+        vec![("3", 0), ("4", 1)]
+    );
+
+    // queued NFTs keep their voting power.
+    app.update_block(next_block);
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(2));
+    assert_eq!(voting, Uint128::new(2));
+
+    // the budget is spent until the next epoch.
+    process_unstake_queue(&mut app, &module, "anyone", None)?;
+    assert_eq!(query_unstake_budget(&app, &module)?.unwrap().queued, 2);
+
+    // the next epoch's budget is 50% of the remaining 2 NFTs.
+    app.update_block(|b| b.height += 9);
+    let res = process_unstake_queue(&mut app, &module, "anyone", None)?;
+    assert_eq!(module_events(&res, UNSTAKE_EVENT).len(), 1);
+    assert_eq!(query_nft_owner(&app, &nft, "3")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &nft, "4")?, module.to_string());
+    let queued = query_queued_unstakes(&app, &module, STAKER)?;
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].position, 0);
+
+    app.update_block(|b| b.height += 10);
+    process_unstake_queue(&mut app, &module, "anyone", None)?;
+    assert_eq!(query_nft_owner(&app, &nft, "4")?, STAKER);
+    assert!(query_queued_unstakes(&app, &module, STAKER)?.is_empty());
+    assert_eq!(query_unstake_budget(&app, &module)?.unwrap().queued, 0);
+
+    app.update_block(next_block);
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::zero());
+    assert_eq!(voting, Uint128::zero());
+
+    Ok(())
+}
+
+/// I cannot prepare/stake an NFT I do not own.
+#[test]
+fn test_stake_unowned() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    let res = stake_nft(&mut app, &nft, &module, "other", "1");
+    is_error!(res => "Only an NFT's owner can prepare it to be staked");
 
-    // no voting power
     app.update_block(next_block);
     let voting = query_voting_power(&app, &module, STAKER, None)?;
     assert_eq!(voting.power, Uint128::new(0));
@@ -1038,9 +2154,9 @@ fn test_preparer_cancel_prepared_stake() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// I cannot cancel someone else's prepared stake, unless I own it.
+/// I cannot confirm a stake before preparing it.
 #[test]
-fn test_no_cancel_other_prepared_stake() -> anyhow::Result<()> {
+fn test_stake_unprepared() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -1050,24 +2166,46 @@ fn test_no_cancel_other_prepared_stake() -> anyhow::Result<()> {
 
     mint_nft(&mut app, &nft, STAKER, "1")?;
 
-    // prepare
+    // attempt confirm without preparing
+    let res = confirm_stake_nft(&mut app, &module, STAKER, "1");
+    is_error!(res => "NFT 1 must be prepared by the sender before it can be staked");
+
+    app.update_block(next_block);
+    let voting = query_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(voting.power, Uint128::new(0));
+
+    Ok(())
+}
+
+/// I cannot confirm a stake before preparing it and transferring NFT.
+#[test]
+fn test_stake_prepared_untransferred() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    // prepare but don't transfer
     prepare_stake_nft(&mut app, &module, STAKER, "1")?;
 
-    // cancel from other
-    let res = cancel_stake(&mut app, &module, "other", "1", None);
-    is_error!(res => "Only the owner or preparer can cancel a prepared stake");
+    // attempt confirm
+    let res = confirm_stake_nft(&mut app, &module, STAKER, "1");
+    is_error!(res => "NFT 1 must be transferred to the staking contract before it can be staked");
 
-    // transfer to other
-    send_nft(&mut app, &nft, "1", STAKER, "other")?;
-    // cancel from other
-    cancel_stake(&mut app, &module, "other", "1", None)?;
+    app.update_block(next_block);
+    let voting = query_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(voting.power, Uint128::new(0));
 
     Ok(())
 }
 
-/// The DAO can cancel a prepared stake.
+/// I cannot confirm a stake that someone else prepared.
 #[test]
-fn test_dao_cancel_stake() -> anyhow::Result<()> {
+fn test_stake_prepared_confirm_other_owner() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -1080,15 +2218,49 @@ fn test_dao_cancel_stake() -> anyhow::Result<()> {
     // prepare
     prepare_stake_nft(&mut app, &module, STAKER, "1")?;
 
-    // cancel from DAO
-    cancel_stake(&mut app, &module, DAO, "1", None)?;
+    // transfer to voting contract
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+
+    // attempt confirm
+    let res = confirm_stake_nft(&mut app, &module, "other", "1");
+    is_error!(res => "NFT 1 must be prepared by the sender before it can be staked");
+
+    app.update_block(next_block);
+    let voting = query_voting_power(&app, &module, "other", None)?;
+    assert_eq!(voting.power, Uint128::new(0));
 
     Ok(())
 }
 
-/// The DAO can cancel a prepared stake and send back to the preparer.
+/// I cannot prepare or confirm a stake for an NFT I already staked.
 #[test]
-fn test_dao_cancel_stake_and_return_to_preparer() -> anyhow::Result<()> {
+fn test_stake_already_staked() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+
+    let res = prepare_stake_nft(&mut app, &module, STAKER, "1");
+    is_error!(res => "NFT 1 is already staked");
+
+    let res = confirm_stake_nft(&mut app, &module, STAKER, "1");
+    is_error!(res => "NFT 1 is already staked");
+
+    // someone else is told they do not own it.
+    let res = prepare_stake_nft(&mut app, &module, "other", "1");
+    is_error!(res => "Only an NFT's owner can prepare it to be staked (not the owner of 1)");
+
+    Ok(())
+}
+
+/// I cannot stake an NFT that is not in the DAO's collection, nor the
+/// same NFT twice in one message.
+#[test]
+fn test_stake_invalid_token_ids() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -1096,30 +2268,67 @@ fn test_dao_cancel_stake_and_return_to_preparer() -> anyhow::Result<()> {
         ..
     } = setup_test(None, None);
 
+    let res = prepare_stake_nft(&mut app, &module, STAKER, "404");
+    is_error!(res => "NFT 404 does not exist in collection nft");
+
     mint_nft(&mut app, &nft, STAKER, "1")?;
+    let res = app.execute_contract(
+        Addr::unchecked(STAKER),
+        module.clone(),
+        &ExecuteMsg::PrepareStake {
+            token_ids: vec!["1".to_string(), "1".to_string()],
+            collection_id: None,
+        },
+        &[],
+    );
+    is_error!(res => "NFT 1 was included more than once");
 
-    // prepare and transfer
     prepare_stake_nft(&mut app, &module, STAKER, "1")?;
-    // transfer
     send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+    let res = app.execute_contract(
+        Addr::unchecked(STAKER),
+        module.clone(),
+        &ExecuteMsg::ConfirmStake {
+            token_ids: vec!["1".to_string(), "1".to_string()],
+            collection_id: None,
+            referrer: None,
+        },
+        &[],
+    );
+    is_error!(res => "NFT 1 was included more than once");
 
-    // voting contract has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, module.to_string());
+    let res = app.execute_contract(
+        Addr::unchecked(STAKER),
+        module.clone(),
+        &ExecuteMsg::ConfirmStake {
+            token_ids: vec![],
+            collection_id: None,
+            referrer: None,
+        },
+        &[],
+    );
+    is_error!(res => "Can't stake zero NFTs.");
 
-    // cancel from DAO
-    cancel_stake(&mut app, &module, DAO, "1", None)?;
+    Ok(())
+}
 
-    // preparer has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, STAKER);
+/// Queries with invalid addresses describe the offending address.
+#[test]
+fn test_query_invalid_address() {
+    let CommonTest { app, module, .. } = setup_test(None, None);
 
-    Ok(())
+    let res: cosmwasm_std::StdResult<NftClaimsResponse> = app.wrap().query_wasm_smart(
+        module,
+        &QueryMsg::NftClaims {
+            address: "".to_string(),
+        },
+    );
+    is_error!(res => "invalid address ()");
 }
 
-/// The DAO can cancel a prepared stake and send to anyone.
+/// I can override a prepared stake.
 #[test]
-fn test_dao_cancel_stake_and_send_to_anyone() -> anyhow::Result<()> {
+fn test_override_prepared() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -1129,31 +2338,32 @@ fn test_dao_cancel_stake_and_send_to_anyone() -> anyhow::Result<()> {
 
     mint_nft(&mut app, &nft, STAKER, "1")?;
 
-    // prepare and transfer
+    // prepare
     prepare_stake_nft(&mut app, &module, STAKER, "1")?;
-    // transfer
-    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
 
-    // voting contract has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, module.to_string());
+    // transfer to someone else
+    send_nft(&mut app, &nft, "1", STAKER, "other")?;
 
-    // cancel from DAO and send to other
-    cancel_stake(&mut app, &module, DAO, "1", Some("other"))?;
+    // override previous owner's prepare
+    prepare_stake_nft(&mut app, &module, "other", "1")?;
 
-    // other has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, "other");
+    // transfer to voting contract
+    send_nft(&mut app, &nft, "1", "other", module.as_str())?;
 
-    // other can stake
-    stake_nft(&mut app, &nft, &module, "other", "1")?;
+    // confirm
+    confirm_stake_nft(&mut app, &module, "other", "1")?;
+
+    app.update_block(next_block);
+    let voting = query_voting_power(&app, &module, "other", None)?;
+    assert_eq!(voting.power, Uint128::new(1));
 
     Ok(())
 }
 
-/// The DAO must specify a recipient if no one prepared the NFT.
+/// I can determine what my voting power _will_ be after staking by
+/// asking for my voting power one block in the future.
 #[test]
-fn test_dao_cancel_stake_must_have_recipient() -> anyhow::Result<()> {
+fn test_query_the_future() -> anyhow::Result<()> {
     let CommonTest {
         mut app,
         module,
@@ -1161,28 +2371,1092 @@ fn test_dao_cancel_stake_must_have_recipient() -> anyhow::Result<()> {
         ..
     } = setup_test(None, None);
 
-    mint_nft(&mut app, &nft, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
 
-    // transfer without preparing
-    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+    // Future voting power will be one under current conditions.
+    let voting = query_voting_power(&app, &module, STAKER, Some(app.block_info().height + 100))?;
+    assert_eq!(voting.power, Uint128::new(1));
 
-    // voting contract has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, module.to_string());
+    // Current voting power is zero.
+    let voting = query_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(voting.power, Uint128::new(0));
 
-    // cancel from DAO without prepared stake requires recipient
-    let res = cancel_stake(&mut app, &module, DAO, "1", None);
-    is_error!(res => "Recipient must be set when the DAO is cancelling a stake that was not prepared");
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
 
-    // cancel from DAO and send back to staker
-    cancel_stake(&mut app, &module, DAO, "1", Some(STAKER))?;
+    // Future voting power is now zero.
+    let voting = query_voting_power(&app, &module, STAKER, Some(app.block_info().height + 100))?;
+    assert_eq!(voting.power, Uint128::zero());
 
-    // staker has the NFT
-    let owner = query_nft_owner(&app, &nft, "1")?;
-    assert_eq!(owner, STAKER);
+    Ok(())
+}
 
-    // staker can stake
-    stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+/// Total power is correct at every height whether or not the cache of
+/// recent values covers it, including when the total changes several
+/// times in one block.
+#[test]
+fn test_total_power_cache() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    let first = app.block_info().height;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+
+    app.update_block(next_block);
+    let second = app.block_info().height;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "3")?;
+
+    app.update_block(next_block);
+    let third = app.block_info().height;
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "4")?;
+    unstake_nfts(&mut app, &module, STAKER, &["2", "3"])?;
+
+    app.update_block(next_block);
+
+    for (height, expected) in [
+        (first, 0),
+        (second, 2),
+        (third, 3),
+        (third + 1, 1),
+        (third + 100, 1),
+    ] {
+        let total = query_total_power(&app, &module, Some(height))?;
+        assert_eq!(total.power, Uint128::new(expected), "height {height}");
+    }
+
+    Ok(())
+}
+
+/// I can not unstake more than one NFT in a TX in order to bypass the
+/// MAX_CLAIMS limit.
+#[test]
+fn test_bypass_max_claims() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(1)), None);
+    let mut to_stake = vec![];
+    for i in 1..(MAX_CLAIMS + 10) {
+        let i_str = &i.to_string();
+        mint_and_stake_nft(&mut app, &nft, &module, STAKER, i_str)?;
+        if i < MAX_CLAIMS {
+            // unstake MAX_CLAMS - 1 NFTs
+            unstake_nfts(&mut app, &module, STAKER, &[i_str])?;
+        } else {
+            // push rest of NFT ids to vec
+            to_stake.push(i_str.clone());
+        }
+    }
+    let binding = to_stake.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    let to_stake_slice: &[&str] = binding.as_slice();
+    let res = unstake_nfts(&mut app, &module, STAKER, to_stake_slice);
+    is_error!(res => "Too many outstanding claims. Claim some tokens before unstaking more.");
+    Ok(())
+}
+
+/// I can cancel my own prepared stake.
+#[test]
+fn test_preparer_cancel_prepared_stake() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    // prepare but don't transfer
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+
+    // cancel
+    cancel_stake(&mut app, &module, STAKER, "1", None)?;
+
+    // prepare and transfer
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+
+    // voting contract has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, module.to_string());
+
+    // cancel
+    cancel_stake(&mut app, &module, STAKER, "1", None)?;
+
+    // original preparer has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, STAKER.to_string());
+
+    // no voting power
+    app.update_block(next_block);
+    let voting = query_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(voting.power, Uint128::new(0));
+
+    Ok(())
+}
+
+/// I cannot cancel someone else's prepared stake, unless I own it.
+#[test]
+fn test_no_cancel_other_prepared_stake() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    // prepare
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+
+    // cancel from other
+    let res = cancel_stake(&mut app, &module, "other", "1", None);
+    is_error!(res => "Only the owner or preparer can cancel a prepared stake");
+
+    // transfer to other
+    send_nft(&mut app, &nft, "1", STAKER, "other")?;
+    // cancel from other
+    cancel_stake(&mut app, &module, "other", "1", None)?;
+
+    Ok(())
+}
+
+/// The DAO can cancel a prepared stake.
+#[test]
+fn test_dao_cancel_stake() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    // prepare
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+
+    // cancel from DAO
+    cancel_stake(&mut app, &module, DAO, "1", None)?;
+
+    Ok(())
+}
+
+/// The DAO can cancel a prepared stake and send back to the preparer.
+#[test]
+fn test_dao_cancel_stake_and_return_to_preparer() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    // prepare and transfer
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    // transfer
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+
+    // voting contract has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, module.to_string());
+
+    // cancel from DAO
+    cancel_stake(&mut app, &module, DAO, "1", None)?;
+
+    // preparer has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, STAKER);
+
+    Ok(())
+}
+
+/// The DAO can cancel a prepared stake and send to anyone.
+#[test]
+fn test_dao_cancel_stake_and_send_to_anyone() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    // prepare and transfer
+    prepare_stake_nft(&mut app, &module, STAKER, "1")?;
+    // transfer
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+
+    // voting contract has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, module.to_string());
+
+    // cancel from DAO and send to other
+    cancel_stake(&mut app, &module, DAO, "1", Some("other"))?;
+
+    // other has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, "other");
+
+    // other can stake
+    stake_nft(&mut app, &nft, &module, "other", "1")?;
+
+    Ok(())
+}
+
+/// The DAO must specify a recipient if no one prepared the NFT.
+#[test]
+fn test_dao_cancel_stake_must_have_recipient() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+
+    // transfer without preparing
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+
+    // voting contract has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, module.to_string());
+
+    // cancel from DAO without prepared stake requires recipient
+    let res = cancel_stake(&mut app, &module, DAO, "1", None);
+    is_error!(res => "Recipient must be set when the DAO is cancelling a stake that was not prepared");
+
+    // cancel from DAO and send back to staker
+    cancel_stake(&mut app, &module, DAO, "1", Some(STAKER))?;
+
+    // staker has the NFT
+    let owner = query_nft_owner(&app, &nft, "1")?;
+    assert_eq!(owner, STAKER);
+
+    // staker can stake
+    stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_cw721_query_facade() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(Some(Duration::Height(1)), None);
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+    mint_and_stake_nft(&mut app, &nft, &module, "other", "3")?;
+
+    assert_eq!(
+        query_owner_of(&app, &module, "1")?,
+        cw721::OwnerOfResponse {
+            owner: STAKER.to_string(),
+            approvals: vec![],
+        }
+    );
+    assert_eq!(query_owner_of(&app, &module, "3")?.owner, "other");
+    assert_eq!(query_num_tokens(&app, &module)?, 3);
+    assert_eq!(query_tokens(&app, &module, STAKER, None, None)?, ["1", "2"]);
+    assert_eq!(
+        query_tokens(&app, &module, STAKER, Some("1".to_string()), None)?,
+        ["2"]
+    );
+    assert_eq!(
+        query_all_tokens(&app, &module, None, None)?,
+        ["1", "2", "3"]
+    );
+    assert_eq!(
+        query_all_tokens(&app, &module, Some("1".to_string()), Some(1))?,
+        ["2"]
+    );
+
+    // NFTs pending claim are no longer staked.
+    unstake_nfts(&mut app, &module, STAKER, &["2"])?;
+    assert!(query_owner_of(&app, &module, "2").is_err());
+    assert_eq!(query_num_tokens(&app, &module)?, 2);
+    assert_eq!(query_tokens(&app, &module, STAKER, None, None)?, ["1"]);
+    assert_eq!(query_all_tokens(&app, &module, None, None)?, ["1", "3"]);
+
+    // NFTs that were never staked have no owner.
+    assert!(query_owner_of(&app, &module, "4").is_err());
+
+    Ok(())
+}
+
+/// Dissolving disables staking, releases pending claims, and lets
+/// staked and queued NFTs be unstaked immediately.
+#[test]
+fn test_dissolve() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    let module = app.instantiate_contract(
+        module_id,
+        Addr::unchecked(DAO),
+        &InstantiateMsg {
+            onft_collections: vec![WeightedOnftCollection {
+                collection: OnftCollection::Existing {
+                    id: nft.to_string(),
+                },
+                weight: Uint128::one(),
+                unstaking_duration: None,
+            }],
+            unstaking_duration: Some(Duration::Height(100)),
+            active_threshold: None,
+            bootstrap_period: None,
+            unstake_budget: Some(UnstakeBudget {
+                max_percent: Decimal::percent(25),
+                epoch: Duration::Height(10),
+            }),
+            unique_stakers_threshold: None,
+        },
+        &[],
+        "onft_voting",
+        None,
+    )?;
+
+    // start at the beginning of an epoch.
+    app.update_block(|b| b.height += 10 - b.height % 10);
+    for token_id in ["1", "2", "3", "4"] {
+        mint_and_stake_nft(&mut app, &nft, &module, STAKER, token_id)?;
+    }
+
+    // 1 is pending claim and 2 is queued.
+    unstake_nfts(&mut app, &module, STAKER, &["1", "2"])?;
+    assert_eq!(query_claims(&app, &module, STAKER)?.nft_claims.len(), 1);
+    assert_eq!(query_unstake_budget(&app, &module)?.unwrap().queued, 1);
+
+    let res = dissolve(&mut app, &module, STAKER);
+    is_error!(res => "Unauthorized");
+
+    dissolve(&mut app, &module, DAO)?;
+    let config = query_config(&app, &module)?;
+    assert!(config.dissolved);
+    assert_eq!(config.unstaking_duration, None);
+
+    let res = dissolve(&mut app, &module, DAO);
+    is_error!(res => "This voting module has been dissolved");
+    let res = update_config(&mut app, &module, DAO, Some(Duration::Height(1)));
+    is_error!(res => "This voting module has been dissolved");
+
+    // pending claims are released.
+    claim_nfts(&mut app, &module, STAKER)?;
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+
+    // queued and staked NFTs are returned immediately, regardless of
+    // the unstake budget.
+    unstake_nfts(&mut app, &module, STAKER, &["2", "3"])?;
+    assert_eq!(query_nft_owner(&app, &nft, "2")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &nft, "3")?, STAKER);
+    assert_eq!(query_unstake_budget(&app, &module)?.unwrap().queued, 0);
+    assert!(query_queued_unstakes(&app, &module, STAKER)?.is_empty());
+
+    // no new stakes.
+    mint_nft(&mut app, &nft, STAKER, "5")?;
+    let res = prepare_stake_nft(&mut app, &module, STAKER, "5");
+    is_error!(res => "This voting module has been dissolved");
+
+    app.update_block(next_block);
+    let (total, voting) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(1));
+    assert_eq!(voting, Uint128::new(1));
+
+    unstake_nfts(&mut app, &module, STAKER, &["4"])?;
+    assert_eq!(query_nft_owner(&app, &nft, "4")?, STAKER);
+
+    Ok(())
+}
+
+#[test]
+fn test_dissolve_releases_claims_without_info() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    let module = app.instantiate_contract(
+        module_id,
+        Addr::unchecked(DAO),
+        &InstantiateMsg {
+            onft_collections: vec![WeightedOnftCollection {
+                collection: OnftCollection::Existing {
+                    id: nft.to_string(),
+                },
+                weight: Uint128::one(),
+                unstaking_duration: None,
+            }],
+            unstaking_duration: Some(Duration::Height(100)),
+            active_threshold: None,
+            bootstrap_period: None,
+            unstake_budget: None,
+            unique_stakers_threshold: None,
+        },
+        &[],
+        "onft_voting",
+        None,
+    )?;
+
+    // a claim imported without its unstaking info.
+    mint_nft(&mut app, &nft, STAKER, "1")?;
+    send_nft(&mut app, &nft, "1", STAKER, module.as_str())?;
+    let release_at = Expiration::AtHeight(app.block_info().height + 100);
+    import_claims(
+        &mut app,
+        &module,
+        DAO,
+        vec![StakerClaims {
+            address: STAKER.to_string(),
+            claims: vec![ExportedClaim {
+                collection_id: nft.clone(),
+                token_id: "1".to_string(),
+                release_at,
+                claim_info: None,
+            }],
+        }],
+        1,
+    )?;
+    assert!(query_claim_info(&app, &module, STAKER)?.is_empty());
+    let res = claim_nfts(&mut app, &module, STAKER);
+    is_error!(res => "No NFTs are ready to be claimed yet");
+
+    dissolve(&mut app, &module, DAO)?;
+    claim_nfts(&mut app, &module, STAKER)?;
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+    assert!(query_claims(&app, &module, STAKER)?.nft_claims.is_empty());
+
+    Ok(())
+}
+
+/// NFTs may be staked from every configured collection, and each
+/// staked NFT counts for its collection's weight.
+#[test]
+fn test_multiple_collections() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        nft,
+        ..
+    } = setup_test(None, None);
+    let rare = create_onft_collection(&mut app, "rare", DAO, DAO);
+    let other = create_onft_collection(&mut app, "other", DAO, DAO);
+
+    let weighted = |id: &str, weight: u128| WeightedOnftCollection {
+        collection: OnftCollection::Existing { id: id.to_string() },
+        weight: Uint128::new(weight),
+        unstaking_duration: None,
+    };
+    let instantiate = |app: &mut OmniflixApp, collections: Vec<WeightedOnftCollection>| {
+        app.instantiate_contract(
+            module_id,
+            Addr::unchecked(DAO),
+            &InstantiateMsg {
+                onft_collections: collections,
+                unstaking_duration: None,
+                active_threshold: None,
+                bootstrap_period: None,
+                unstake_budget: None,
+                unique_stakers_threshold: None,
+            },
+            &[],
+            "onft_voting",
+            None,
+        )
+    };
+
+    let res = instantiate(&mut app, vec![]);
+    is_error!(res => "At least one collection must be staked");
+    let res = instantiate(&mut app, vec![weighted(&nft, 1), weighted(&nft, 2)]);
+    is_error!(res => "Collection nft was included more than once");
+    let res = instantiate(&mut app, vec![weighted(&nft, 1), weighted(&rare, 0)]);
+    is_error!(res => "Collection rare must have a non-zero weight");
+
+    let module = instantiate(&mut app, vec![weighted(&nft, 1), weighted(&rare, 3)])?;
+    assert_eq!(query_config(&app, &module)?.onft_collection_id, nft);
+    assert_eq!(
+        query_collections(&app, &module)?,
+        vec![
+            CollectionResponse {
+                collection_id: nft.clone(),
+                weight: Uint128::one(),
+                unstaking_duration_override: None,
+                unstaking_duration: None,
+            },
+            CollectionResponse {
+                collection_id: rare.clone(),
+                weight: Uint128::new(3),
+                unstaking_duration_override: None,
+                unstaking_duration: None,
+            },
+        ]
+    );
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_nft(&mut app, &rare, STAKER, "1")?;
+    stake_collection_nft(&mut app, &rare, &module, STAKER, "1")?;
+
+    mint_nft(&mut app, &other, STAKER, "1")?;
+    let res = stake_collection_nft(&mut app, &other, &module, STAKER, "1");
+    is_error!(res => "Collection other can not be staked with this module");
+
+    app.update_block(next_block);
+    let (total, personal) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(4));
+    assert_eq!(personal, Uint128::new(4));
+    assert_eq!(query_num_tokens(&app, &module)?, 2);
+    assert_eq!(
+        query_staked_nfts(&app, &module, STAKER, None, None)?,
+        ["1", "rare/1"]
+    );
+
+    // the same token ID in the default collection is a different NFT.
+    unstake_collection_nfts(&mut app, &rare, &module, STAKER, &["1"])?;
+    assert_eq!(query_nft_owner(&app, &rare, "1")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, module.to_string());
+
+    let res = unstake_collection_nfts(&mut app, &rare, &module, STAKER, &["1"]);
+    is_error!(res => "Can not unstake that which you have not staked (unstaking rare/1)");
+
+    app.update_block(next_block);
+    let (total, personal) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::one());
+    assert_eq!(personal, Uint128::one());
+    assert_eq!(query_num_tokens(&app, &module)?, 1);
+
+    Ok(())
+}
+
+/// Individual NFTs may override their collection's weight, which
+/// updates the voting power of staked NFTs right away.
+#[test]
+fn test_token_weights() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    let power = |app: &OmniflixApp, addr: &str| -> StdResult<u128> {
+        Ok(query_voting_power(app, &module, addr, None)?.power.u128())
+    };
+    let total = |app: &OmniflixApp| -> StdResult<u128> {
+        Ok(query_total_power(app, &module, None)?.power.u128())
+    };
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "2")?;
+    mint_nft(&mut app, &nft, "other", "3")?;
+
+    let res = set_token_weights(&mut app, &module, STAKER, &[("1", Some(10))]);
+    is_error!(res => "Unauthorized");
+    let res = set_token_weights(&mut app, &module, DAO, &[("1", Some(0))]);
+    is_error!(res => "NFT 1 must have a non-zero weight");
+
+    // staked and unstaked NFTs may be weighted.
+    set_token_weights(&mut app, &module, DAO, &[("1", Some(10)), ("3", Some(5))])?;
+    app.update_block(next_block);
+    assert_eq!(power(&app, STAKER)?, 11);
+    assert_eq!(total(&app)?, 11);
+    assert_eq!(query_token_weight(&app, &module, "1")?, Uint128::new(10));
+    assert_eq!(query_token_weight(&app, &module, "2")?, Uint128::one());
+    assert_eq!(
+        query_token_weights(&app, &module, None, None)?,
+        vec![
+            TokenWeightResponse {
+                token_id: "1".to_string(),
+                weight: Uint128::new(10),
+            },
+            TokenWeightResponse {
+                token_id: "3".to_string(),
+                weight: Uint128::new(5),
+            },
+        ]
+    );
+    assert_eq!(
+        query_token_weights(&app, &module, Some("1"), Some(1))?.len(),
+        1
+    );
+
+    // the weights of a delegated staker's NFTs move their delegate's
+    // voting power.
+    delegate(&mut app, &module, STAKER, "delegate")?;
+    set_token_weights(&mut app, &module, DAO, &[("2", Some(4)), ("1", None)])?;
+    app.update_block(next_block);
+    assert_eq!(power(&app, STAKER)?, 0);
+    assert_eq!(power(&app, "delegate")?, 5);
+    assert_eq!(total(&app)?, 5);
+
+    // NFTs are unstaked with their current weight.
+    unstake_nfts(&mut app, &module, STAKER, &["2"])?;
+    app.update_block(next_block);
+    assert_eq!(power(&app, "delegate")?, 1);
+    assert_eq!(total(&app)?, 1);
+
+    // the potential power counts NFTs with their overrides: 1 + 4 + 5.
+    let threshold = |count: u128| ExecuteMsg::UpdateActiveThreshold {
+        new_threshold: Some(ActiveThreshold::AbsoluteCount {
+            count: Uint128::new(count),
+        }),
+    };
+    app.execute_contract(Addr::unchecked(DAO), module.clone(), &threshold(10), &[])?;
+    let res = app.execute_contract(Addr::unchecked(DAO), module.clone(), &threshold(11), &[]);
+    is_error!(res => "Absolute count threshold cannot be greater than the total token supply");
+
+    // member hooks are notified of weight changes of staked NFTs, and
+    // staking hooks are not. this fails because meow isn't a contract.
+    add_hook(&mut app, &module, DAO, "meow")?;
+    set_token_weights(&mut app, &module, DAO, &[("1", Some(2))])?;
+    add_member_hook(&mut app, &module, DAO, "meow")?;
+    set_token_weights(&mut app, &module, DAO, &[("2", Some(2))])?;
+    set_token_weights(&mut app, &module, DAO, &[("1", Some(3))]).unwrap_err();
+
+    Ok(())
+}
+
+/// Staked NFTs may be listed by range of identifier and by voting
+/// power.
+#[test]
+fn test_staked_nfts_in_range() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    for token_id in ["a1", "a2", "a3", "b1", "b2"] {
+        mint_and_stake_nft(&mut app, &nft, &module, STAKER, token_id)?;
+    }
+    mint_and_stake_nft(&mut app, &nft, &module, "other", "a4")?;
+    unstake_nfts(&mut app, &module, STAKER, &["a2"])?;
+    set_token_weights(&mut app, &module, DAO, &[("a3", Some(5)), ("b2", Some(5))])?;
+
+    let token_ids =
+        |nfts: Vec<StakedNftResponse>| nfts.into_iter().map(|nft| nft.token_id).collect::<Vec<_>>();
+    let range = |app: &OmniflixApp,
+                 min: Option<&str>,
+                 max: Option<&str>,
+                 weight: Option<u128>,
+                 start_after: Option<&str>,
+                 limit: Option<u32>| {
+        query_staked_nfts_in_range(app, &module, min, max, weight, start_after, limit)
+            .map(token_ids)
+    };
+
+    // NFTs that are pending claim are skipped.
+    assert_eq!(
+        query_staked_nfts_in_range(&app, &module, Some("a"), Some("b"), None, None, None)?,
+        vec![
+            StakedNftResponse {
+                token_id: "a1".to_string(),
+                staker: Addr::unchecked(STAKER),
+                weight: Uint128::one(),
+            },
+            StakedNftResponse {
+                token_id: "a3".to_string(),
+                staker: Addr::unchecked(STAKER),
+                weight: Uint128::new(5),
+            },
+            StakedNftResponse {
+                token_id: "a4".to_string(),
+                staker: Addr::unchecked("other"),
+                weight: Uint128::one(),
+            },
+        ]
+    );
+    assert_eq!(
+        range(&app, None, None, None, None, None)?,
+        ["a1", "a3", "a4", "b1", "b2"]
+    );
+    assert_eq!(
+        range(&app, Some("a3"), None, None, None, None)?,
+        ["a3", "a4", "b1", "b2"]
+    );
+
+    // pages resume after `start_after`, but never below `min`.
+    assert_eq!(
+        range(&app, Some("a"), Some("b"), None, None, Some(2))?,
+        ["a1", "a3"]
+    );
+    assert_eq!(
+        range(&app, Some("a"), Some("b"), None, Some("a3"), Some(2))?,
+        ["a4"]
+    );
+    assert_eq!(
+        range(&app, Some("b"), None, None, Some("a3"), None)?,
+        ["b1", "b2"]
+    );
+
+    // only NFTs with the given voting power are listed.
+    assert_eq!(range(&app, None, None, Some(5), None, None)?, ["a3", "b2"]);
+    assert_eq!(range(&app, None, None, Some(5), Some("a3"), None)?, ["b2"]);
+    assert_eq!(range(&app, Some("b"), None, Some(1), None, None)?, ["b1"]);
+
+    Ok(())
+}
+
+/// NFTs from several collections may be staked and unstaked in one
+/// batch.
+#[test]
+fn test_batch_stake_unstake() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        nft,
+        ..
+    } = setup_test(None, None);
+    let rare = create_onft_collection(&mut app, "rare", DAO, DAO);
+
+    let module = app.instantiate_contract(
+        module_id,
+        Addr::unchecked(DAO),
+        &InstantiateMsg {
+            onft_collections: vec![
+                WeightedOnftCollection {
+                    collection: OnftCollection::Existing { id: nft.clone() },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                },
+                WeightedOnftCollection {
+                    collection: OnftCollection::Existing { id: rare.clone() },
+                    weight: Uint128::new(3),
+                    unstaking_duration: None,
+                },
+            ],
+            unstaking_duration: None,
+            active_threshold: None,
+            bootstrap_period: None,
+            unstake_budget: None,
+            unique_stakers_threshold: None,
+        },
+        &[],
+        "onft_voting",
+        None,
+    )?;
+
+    for token_id in ["1", "2"] {
+        mint_nft(&mut app, &nft, STAKER, token_id)?;
+        mint_nft(&mut app, &rare, STAKER, token_id)?;
+    }
+
+    let res = stake_many_nfts(&mut app, &module, STAKER, &[]);
+    is_error!(res => "Can't stake zero NFTs.");
+    let res = stake_many_nfts(&mut app, &module, STAKER, &[(&rare, "1"), (&rare, "1")]);
+    is_error!(res => "NFT 1 was included more than once");
+
+    // the same token ID in different collections is a different NFT.
+    stake_many_nfts(
+        &mut app,
+        &module,
+        STAKER,
+        &[(&nft, "1"), (&rare, "1"), (&nft, "2"), (&rare, "2")],
+    )?;
+
+    app.update_block(next_block);
+    let (total, personal) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::new(8));
+    assert_eq!(personal, Uint128::new(8));
+    assert_eq!(query_num_tokens(&app, &module)?, 4);
+    assert_eq!(
+        query_staked_nfts(&app, &module, STAKER, None, None)?,
+        ["1", "2", "rare/1", "rare/2"]
+    );
+
+    let res = unstake_many_nfts(&mut app, &module, STAKER, &[]);
+    is_error!(res => "Can't unstake zero NFTs.");
+    let res = unstake_many_nfts(&mut app, &module, STAKER, &[(&rare, "1"), ("other", "1")]);
+    is_error!(res => "Collection other can not be staked with this module");
+    let res = unstake_many_nfts(&mut app, &module, STAKER, &[(&rare, "1"), (&rare, "1")]);
+    is_error!(res => "Can not unstake that which you have not staked (unstaking rare/1)");
+
+    unstake_many_nfts(
+        &mut app,
+        &module,
+        STAKER,
+        &[(&nft, "1"), (&rare, "1"), (&rare, "2")],
+    )?;
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &rare, "1")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &rare, "2")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &nft, "2")?, module.to_string());
+
+    app.update_block(next_block);
+    let (total, personal) = query_total_and_voting_power(&app, &module, STAKER, None)?;
+    assert_eq!(total, Uint128::one());
+    assert_eq!(personal, Uint128::one());
+    assert_eq!(query_num_tokens(&app, &module)?, 1);
+
+    Ok(())
+}
+
+/// Collections may override the module's unstaking duration, and
+/// NFTs unstaked from them are claimable once their collection's
+/// duration has passed.
+#[test]
+fn test_collection_unstaking_durations() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        nft,
+        ..
+    } = setup_test(None, None);
+    let rare = create_onft_collection(&mut app, "rare", DAO, DAO);
+
+    let module = app.instantiate_contract(
+        module_id,
+        Addr::unchecked(DAO),
+        &InstantiateMsg {
+            onft_collections: vec![
+                WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: nft.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                },
+                WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: rare.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: Some(Duration::Height(20)),
+                },
+            ],
+            unstaking_duration: Some(Duration::Height(10)),
+            active_threshold: None,
+            bootstrap_period: None,
+            unstake_budget: None,
+            unique_stakers_threshold: None,
+        },
+        &[],
+        "onft_voting",
+        None,
+    )?;
+    assert_eq!(
+        query_collections(&app, &module)?,
+        vec![
+            CollectionResponse {
+                collection_id: nft.clone(),
+                weight: Uint128::one(),
+                unstaking_duration_override: None,
+                unstaking_duration: Some(Duration::Height(10)),
+            },
+            CollectionResponse {
+                collection_id: rare.clone(),
+                weight: Uint128::one(),
+                unstaking_duration_override: Some(Duration::Height(20)),
+                unstaking_duration: Some(Duration::Height(20)),
+            },
+        ]
+    );
+
+    mint_and_stake_nft(&mut app, &nft, &module, STAKER, "1")?;
+    mint_nft(&mut app, &rare, STAKER, "1")?;
+    stake_collection_nft(&mut app, &rare, &module, STAKER, "1")?;
+    unstake_nfts(&mut app, &module, STAKER, &["1"])?;
+    unstake_collection_nfts(&mut app, &rare, &module, STAKER, &["1"])?;
+
+    let height = app.block_info().height;
+    assert_eq!(
+        query_claims(&app, &module, STAKER)?.nft_claims,
+        vec![
+            NftClaim {
+                token_id: "1".to_string(),
+                release_at: Expiration::AtHeight(height + 10),
+            },
+            NftClaim {
+                token_id: "rare/1".to_string(),
+                release_at: Expiration::AtHeight(height + 20),
+            },
+        ]
+    );
+
+    app.update_block(|block| block.height += 10);
+    claim_nfts(&mut app, &module, STAKER)?;
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &rare, "1")?, module.to_string());
+
+    app.update_block(|block| block.height += 10);
+    claim_nfts(&mut app, &module, STAKER)?;
+    assert_eq!(query_nft_owner(&app, &rare, "1")?, STAKER);
+
+    // only the DAO may update a collection's unstaking duration, and
+    // only for collections the module stakes.
+    let res = update_collection_unstaking_duration(&mut app, &module, STAKER, &rare, None, None);
+    is_error!(res => "Unauthorized");
+    let res = update_collection_unstaking_duration(&mut app, &module, DAO, "other", None, None);
+    is_error!(res => "Collection other can not be staked with this module");
+
+    // shortening the override may apply to pending claims.
+    stake_collection_nft(&mut app, &rare, &module, STAKER, "1")?;
+    unstake_collection_nfts(&mut app, &rare, &module, STAKER, &["1"])?;
+    let height = app.block_info().height;
+    update_collection_unstaking_duration(
+        &mut app,
+        &module,
+        DAO,
+        &rare,
+        Some(Duration::Height(5)),
+        Some(UnstakingDurationPolicy::ShortenExisting {}),
+    )?;
+    assert_eq!(
+        query_claims(&app, &module, STAKER)?.nft_claims,
+        vec![NftClaim {
+            token_id: "rare/1".to_string(),
+            release_at: Expiration::AtHeight(height + 5),
+        }]
+    );
+
+    // shortening the default leaves collections with overrides alone.
+    mint_nft(&mut app, &rare, STAKER, "2")?;
+    stake_collection_nft(&mut app, &rare, &module, STAKER, "2")?;
+    unstake_collection_nfts(&mut app, &rare, &module, STAKER, &["2"])?;
+    update_config_with_policy(
+        &mut app,
+        &module,
+        DAO,
+        Some(Duration::Height(1)),
+        UnstakingDurationPolicy::ShortenExisting {},
+    )?;
+    assert_eq!(
+        query_claims(&app, &module, STAKER)?.nft_claims[1],
+        NftClaim {
+            token_id: "rare/2".to_string(),
+            release_at: Expiration::AtHeight(app.block_info().height + 5),
+        }
+    );
+
+    // removing the override falls back to the module's default.
+    update_collection_unstaking_duration(&mut app, &module, DAO, &rare, None, None)?;
+    assert_eq!(
+        query_collections(&app, &module)?[1].unstaking_duration,
+        Some(Duration::Height(1))
+    );
+
+    Ok(())
+}
+
+/// Pending claims exported from one module can be imported into another
+/// that holds the NFTs, and claimed from there.
+#[test]
+fn test_export_import_claims() -> anyhow::Result<()> {
+    let CommonTest {
+        mut app,
+        module_id,
+        nft,
+        ..
+    } = setup_test(None, None);
+
+    let mut instantiate = || {
+        app.instantiate_contract(
+            module_id,
+            Addr::unchecked(DAO),
+            &InstantiateMsg {
+                onft_collections: vec![WeightedOnftCollection {
+                    collection: OnftCollection::Existing {
+                        id: nft.to_string(),
+                    },
+                    weight: Uint128::one(),
+                    unstaking_duration: None,
+                }],
+                unstaking_duration: Some(Duration::Height(10)),
+                active_threshold: None,
+                bootstrap_period: None,
+                unstake_budget: None,
+                unique_stakers_threshold: None,
+            },
+            &[],
+            "onft_voting",
+            None,
+        )
+    };
+    let old = instantiate()?;
+    let new = instantiate()?;
+
+    for token_id in ["1", "2", "3"] {
+        mint_and_stake_nft(&mut app, &nft, &old, STAKER, token_id)?;
+    }
+    unstake_nfts(&mut app, &old, STAKER, &["1", "2"])?;
+    let release_at = Expiration::AtHeight(app.block_info().height + 10);
+
+    let claims = query_export_claims(&app, &old, None, None)?;
+    assert_eq!(
+        claims,
+        vec![StakerClaims {
+            address: STAKER.to_string(),
+            claims: ["1", "2"]
+                .into_iter()
+                .map(|token_id| ExportedClaim {
+                    collection_id: nft.clone(),
+                    token_id: token_id.to_string(),
+                    release_at,
+                    claim_info: Some(ClaimInfo {
+                        duration: Duration::Height(10),
+                        unstaked_at_height: app.block_info().height,
+                        unstaked_at_time: app.block_info().time,
+                    }),
+                })
+                .collect(),
+        }]
+    );
+    assert!(query_export_claims(&app, &old, Some(STAKER.to_string()), None)?.is_empty());
+
+    // the new module must hold the NFTs first.
+    let res = import_claims(&mut app, &new, DAO, claims.clone(), 2);
+    is_error!(res => "NFT 1 must be owned by this module before its claim can be imported");
+    for token_id in ["1", "2"] {
+        send_nft(&mut app, &nft, token_id, old.as_str(), new.as_str())?;
+    }
+
+    let res = import_claims(&mut app, &new, STAKER, claims.clone(), 2);
+    is_error!(res => "Unauthorized");
+    let res = import_claims(&mut app, &new, DAO, claims.clone(), 3);
+    is_error!(res => "Expected to import 3 claims, but got 2");
+
+    let mut duplicated = claims.clone();
+    let claim = duplicated[0].claims[0].clone();
+    duplicated[0].claims.push(claim);
+    let res = import_claims(&mut app, &new, DAO, duplicated, 3);
+    is_error!(res => "NFT 1 is already staked");
+
+    import_claims(&mut app, &new, DAO, claims.clone(), 2)?;
+    assert_eq!(query_export_claims(&app, &new, None, None)?, claims);
+    assert_eq!(
+        query_claims(&app, &new, STAKER)?,
+        query_claims(&app, &old, STAKER)?
+    );
+
+    let res = import_claims(&mut app, &new, DAO, claims, 2);
+    is_error!(res => "NFT 1 is already staked");
+
+    // imported claims are released as they would have been.
+    app.update_block(|b| b.height += 10);
+    claim_nfts(&mut app, &new, STAKER)?;
+    assert_eq!(query_nft_owner(&app, &nft, "1")?, STAKER);
+    assert_eq!(query_nft_owner(&app, &nft, "2")?, STAKER);
 
     Ok(())
 }
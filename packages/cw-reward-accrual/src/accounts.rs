@@ -0,0 +1,161 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage, Uint128, Uint256};
+use cw_storage_plus::{Item, Map};
+
+use crate::{math::get_accrued_rewards, AccrualError, Epoch};
+
+/// a single stream of rewards: the epoch it is emitted in and the amount
+/// funded for that epoch.
+#[cw_serde]
+pub struct Stream {
+    /// the epoch rewards are currently emitted in
+    pub epoch: Epoch,
+    /// the amount of rewards funded for the epoch
+    pub funded_amount: Uint128,
+}
+
+/// an address's share of a single stream of rewards.
+#[cw_serde]
+#[derive(Default)]
+pub struct Account {
+    /// rewards that have been accounted for but not yet claimed.
+    pub pending_rewards: Uint128,
+    /// the rewards earned per unit voting power that have already been
+    /// accounted for (added to pending and maybe claimed).
+    pub accounted_for_rewards_puvp: Uint256,
+}
+
+impl Account {
+    /// add the rewards earned by voting_power since this account was last
+    /// updated to its pending rewards, and mark it as accounted for up to
+    /// total_earned_puvp.
+    pub fn accrue(&mut self, voting_power: Uint128, total_earned_puvp: Uint256) -> StdResult<()> {
+        let accrued = get_accrued_rewards(
+            voting_power,
+            total_earned_puvp,
+            self.accounted_for_rewards_puvp,
+        )?;
+        self.pending_rewards = self.pending_rewards.checked_add(accrued)?;
+        self.accounted_for_rewards_puvp = total_earned_puvp;
+        Ok(())
+    }
+}
+
+/// Tracks a single stream of rewards and the rewards of each address accruing
+/// from it, stored under namespaces of the contract's choosing. A contract may
+/// track any number of streams side by side with different namespaces.
+pub struct Accounts<'a> {
+    users: Map<'a, Addr, Account>,
+    global: Item<'a, Stream>,
+}
+
+impl<'a> Accounts<'a> {
+    pub const fn new(users_namespace: &'a str, global_namespace: &'a str) -> Self {
+        Self {
+            users: Map::new(users_namespace),
+            global: Item::new(global_namespace),
+        }
+    }
+
+    /// load the stream, which must have been saved with `save_stream`.
+    pub fn load_stream(&self, storage: &dyn Storage) -> StdResult<Stream> {
+        self.global.load(storage)
+    }
+
+    /// save the stream, e.g. when it is created, funded, or its emission rate
+    /// changes. the stream should be brought up to date with `update_stream`
+    /// before it is changed.
+    pub fn save_stream(&self, storage: &mut dyn Storage, stream: &Stream) -> StdResult<()> {
+        self.global.save(storage, stream)
+    }
+
+    /// the total rewards earned per unit voting power up to the current block,
+    /// without saving them. `get_total_power` is only called if rewards have
+    /// been emitted since the stream was last updated.
+    pub fn query_total_earned_puvp(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        get_total_power: impl FnOnce() -> StdResult<Uint128>,
+    ) -> StdResult<Uint256> {
+        let stream = self.global.load(storage)?;
+        stream
+            .epoch
+            .get_totals(block, stream.funded_amount, get_total_power)
+            .map(|(puvp, _)| puvp)
+    }
+
+    /// bring the stream up to date with the current block, returning the total
+    /// rewards earned per unit voting power. must be called before the total
+    /// voting power changes.
+    pub fn update_stream(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        get_total_power: impl FnOnce() -> StdResult<Uint128>,
+    ) -> Result<Uint256, AccrualError> {
+        let mut stream = self.global.load(storage)?;
+        let (total_earned_puvp, emitted) =
+            stream
+                .epoch
+                .get_totals(block, stream.funded_amount, get_total_power)?;
+        stream.epoch.total_earned_puvp = total_earned_puvp;
+        stream.epoch.emitted = emitted;
+        stream.epoch.bump_last_updated(block)?;
+        self.global.save(storage, &stream)?;
+        Ok(total_earned_puvp)
+    }
+
+    /// load an address's account, which is empty if it has never accrued
+    /// rewards.
+    pub fn load(&self, storage: &dyn Storage, addr: &Addr) -> StdResult<Account> {
+        Ok(self
+            .users
+            .may_load(storage, addr.clone())?
+            .unwrap_or_default())
+    }
+
+    /// the rewards that would be pending for an address with voting_power if it
+    /// were updated now, without saving them.
+    pub fn query_pending(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        addr: &Addr,
+        voting_power: Uint128,
+        get_total_power: impl FnOnce() -> StdResult<Uint128>,
+    ) -> StdResult<Uint128> {
+        let total_earned_puvp = self.query_total_earned_puvp(storage, block, get_total_power)?;
+        let mut account = self.load(storage, addr)?;
+        account.accrue(voting_power, total_earned_puvp)?;
+        Ok(account.pending_rewards)
+    }
+
+    /// bring the stream up to date and account for the rewards an address has
+    /// earned with voting_power. must be called before the address's voting
+    /// power changes.
+    pub fn update(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        addr: &Addr,
+        voting_power: Uint128,
+        get_total_power: impl FnOnce() -> StdResult<Uint128>,
+    ) -> Result<Account, AccrualError> {
+        let total_earned_puvp = self.update_stream(storage, block, get_total_power)?;
+        let mut account = self.load(storage, addr)?;
+        account.accrue(voting_power, total_earned_puvp)?;
+        self.users.save(storage, addr.clone(), &account)?;
+        Ok(account)
+    }
+
+    /// reset an address's pending rewards, returning the amount that was
+    /// pending. the address should be updated first so that all its rewards
+    /// are pending.
+    pub fn claim(&self, storage: &mut dyn Storage, addr: &Addr) -> StdResult<Uint128> {
+        let mut account = self.load(storage, addr)?;
+        let claimed = std::mem::take(&mut account.pending_rewards);
+        self.users.save(storage, addr.clone(), &account)?;
+        Ok(claimed)
+    }
+}
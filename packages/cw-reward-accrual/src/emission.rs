@@ -0,0 +1,296 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{BlockInfo, Decimal, Decimal256, StdError, StdResult, Uint128, Uint64};
+use cw_utils::{Duration, Expiration};
+use std::cmp::min;
+
+use crate::{math::get_duration_scalar, AccrualError};
+
+/// defines how many tokens (amount) should be distributed per amount of time
+/// (duration). e.g. 5udenom per hour.
+#[cw_serde]
+pub enum EmissionRate {
+    /// rewards are paused
+    Paused {},
+    /// rewards are distributed immediately
+    Immediate {},
+    /// rewards are distributed at a constant rate
+    Linear {
+        /// amount of tokens to distribute per amount of time
+        amount: Uint128,
+        /// duration of time to distribute amount
+        duration: Duration,
+        /// whether or not reward distribution is continuous: whether future
+        /// funding after distribution finishes should be applied to the past,
+        /// or rewards are paused once all funding has been distributed. all
+        /// continuously backfilled rewards are distributed based on the current
+        /// voting power.
+        continuous: bool,
+    },
+    /// rewards are distributed at a constant rate with no end, for as long as
+    /// funding remains. distribution pauses once all funding has been emitted
+    /// and resumes from the block it is next funded, never backfilling the gap.
+    LinearPerpetual {
+        /// amount of tokens to distribute per amount of time
+        amount: Uint128,
+        /// duration of time to distribute amount
+        duration: Duration,
+    },
+    /// rewards are distributed at a rate that decays each period, e.g.
+    /// halving every period for a `decay_factor` of 0.5. within a period,
+    /// rewards are distributed at a constant rate. a decaying rate emits at
+    /// most `initial_amount / (1 - decay_factor)` in total.
+    Decay {
+        /// amount of tokens to distribute in the first period
+        initial_amount: Uint128,
+        /// duration of each period
+        duration: Duration,
+        /// the fraction of the previous period's amount distributed in each
+        /// period after the first. must be greater than 0 and less than 1.
+        decay_factor: Decimal,
+    },
+}
+
+impl EmissionRate {
+    /// validate non-zero amount and duration if necessary
+    pub fn validate(&self) -> Result<(), AccrualError> {
+        match self {
+            EmissionRate::Paused {} => Ok(()),
+            EmissionRate::Immediate {} => Ok(()),
+            EmissionRate::Linear {
+                amount, duration, ..
+            }
+            | EmissionRate::LinearPerpetual { amount, duration } => {
+                if *amount == Uint128::zero() {
+                    return Err(AccrualError::InvalidEmissionRateFieldZero {
+                        field: "amount".to_string(),
+                    });
+                }
+                if get_duration_scalar(duration) == 0 {
+                    return Err(AccrualError::InvalidEmissionRateFieldZero {
+                        field: "duration".to_string(),
+                    });
+                }
+                Ok(())
+            }
+            EmissionRate::Decay {
+                initial_amount,
+                duration,
+                decay_factor,
+            } => {
+                if initial_amount.is_zero() {
+                    return Err(AccrualError::InvalidEmissionRateFieldZero {
+                        field: "initial_amount".to_string(),
+                    });
+                }
+                if get_duration_scalar(duration) == 0 {
+                    return Err(AccrualError::InvalidEmissionRateFieldZero {
+                        field: "duration".to_string(),
+                    });
+                }
+                if decay_factor.is_zero() {
+                    return Err(AccrualError::InvalidEmissionRateFieldZero {
+                        field: "decay_factor".to_string(),
+                    });
+                }
+                if *decay_factor >= Decimal::one() {
+                    return Err(AccrualError::InvalidDecayFactor {});
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// find the duration of the funded period given funded amount. e.g. if the
+    /// funded amount is twice the emission rate amount, the funded period
+    /// should be twice the emission rate duration, since the funded amount
+    /// takes two emission cycles to be distributed.
+    pub fn get_funded_period_duration(
+        &self,
+        funded_amount: Uint128,
+    ) -> StdResult<Option<Duration>> {
+        match self {
+            // if rewards are paused, return no duration
+            EmissionRate::Paused {} => Ok(None),
+            // if rewards are immediate, return no duration
+            EmissionRate::Immediate {} => Ok(None),
+            // if rewards are linear, calculate based on funded amount
+            EmissionRate::Linear {
+                amount, duration, ..
+            } => Ok(Some(get_linear_funded_duration(
+                *amount,
+                duration,
+                funded_amount,
+            )?)),
+            // if rewards are perpetual, the funded period never ends
+            EmissionRate::LinearPerpetual { .. } => Ok(None),
+            // if rewards decay, calculate based on funded amount
+            EmissionRate::Decay {
+                initial_amount,
+                duration,
+                decay_factor,
+            } => Ok(Some(get_decay_funded_duration(
+                *initial_amount,
+                duration,
+                *decay_factor,
+                funded_amount,
+            )?)),
+        }
+    }
+
+    /// the start of an epoch with this emission rate beginning at the current
+    /// block. emission rates without a duration never start, as they are not
+    /// emitted over time.
+    pub fn get_started_at(&self, current_block: &BlockInfo) -> Expiration {
+        match self {
+            EmissionRate::Paused {} => Expiration::Never {},
+            EmissionRate::Immediate {} => Expiration::Never {},
+            EmissionRate::Linear { duration, .. }
+            | EmissionRate::LinearPerpetual { duration, .. }
+            | EmissionRate::Decay { duration, .. } => match duration {
+                Duration::Height(_) => Expiration::AtHeight(current_block.height),
+                Duration::Time(_) => Expiration::AtTime(current_block.time),
+            },
+        }
+    }
+}
+
+/// find the duration it takes to emit funded_amount at a linear emission rate
+/// of amount per duration.
+pub fn get_linear_funded_duration(
+    amount: Uint128,
+    duration: &Duration,
+    funded_amount: Uint128,
+) -> StdResult<Duration> {
+    let amount_to_emission_rate_ratio = Decimal::from_ratio(funded_amount, amount);
+
+    let funded_duration = match duration {
+        Duration::Height(h) => {
+            let duration_height = Uint128::from(*h)
+                .checked_mul_floor(amount_to_emission_rate_ratio)
+                .map_err(|e| StdError::generic_err(e.to_string()))?;
+            let duration = Uint64::try_from(duration_height)?.u64();
+            Duration::Height(duration)
+        }
+        Duration::Time(t) => {
+            let duration_time = Uint128::from(*t)
+                .checked_mul_floor(amount_to_emission_rate_ratio)
+                .map_err(|e| StdError::generic_err(e.to_string()))?;
+            let duration = Uint64::try_from(duration_time)?.u64();
+            Duration::Time(duration)
+        }
+    };
+
+    Ok(funded_duration)
+}
+
+/// find the amount emitted by a decaying emission rate in the first `elapsed`
+/// units of time since it started.
+pub fn get_decay_emitted(
+    initial_amount: Uint128,
+    duration: &Duration,
+    decay_factor: Decimal,
+    elapsed: u64,
+) -> StdResult<Uint128> {
+    let scalar = get_duration_scalar(duration);
+    let emitted = get_exact_decay_emitted(
+        initial_amount,
+        decay_factor,
+        elapsed / scalar,
+        Decimal256::from_ratio(elapsed % scalar, scalar),
+    )?;
+    Ok(Uint128::try_from(emitted.to_uint_floor())?)
+}
+
+/// find the duration it takes to emit funded_amount at a decaying emission
+/// rate. if the rate decays before emitting all of funded_amount, this is the
+/// longest duration that can be added to the current time.
+pub fn get_decay_funded_duration(
+    initial_amount: Uint128,
+    duration: &Duration,
+    decay_factor: Decimal,
+    funded_amount: Uint128,
+) -> StdResult<Duration> {
+    let scalar = get_duration_scalar(duration);
+    let funded = Decimal256::from_atomics(funded_amount, 0).map_err(to_std_error)?;
+    let emitted_after = |periods| {
+        get_exact_decay_emitted(initial_amount, decay_factor, periods, Decimal256::zero())
+    };
+
+    // the end of the funded period must not overflow once added to the
+    // current time.
+    let max_periods = min(u32::MAX as u64, u64::MAX / 2 / scalar);
+    if emitted_after(max_periods)? <= funded {
+        return Ok(with_scalar(duration, max_periods * scalar));
+    }
+
+    // find the last complete period that is funded. at most the funded
+    // amount is emitted after `low` periods, and more after `high` periods.
+    let (mut low, mut high) = (0, max_periods);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if emitted_after(mid)? <= funded {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    // then find how much of the next period the rest of the funds cover.
+    let period_amount = Decimal256::from_atomics(initial_amount, 0)
+        .map_err(to_std_error)?
+        .checked_mul(get_decay_remaining(decay_factor, low)?)?;
+    let fraction = funded
+        .checked_sub(emitted_after(low)?)?
+        .checked_div(period_amount)
+        .map_err(to_std_error)?;
+    let partial = fraction
+        .checked_mul(Decimal256::from_atomics(scalar, 0).map_err(to_std_error)?)?
+        .to_uint_floor();
+    let partial = min(Uint64::try_from(partial)?.u64(), scalar);
+
+    Ok(with_scalar(duration, low * scalar + partial))
+}
+
+/// the exact amount emitted by a decaying emission rate after `periods`
+/// complete periods and `fraction` of the next. the amounts emitted in
+/// complete periods form a geometric series.
+fn get_exact_decay_emitted(
+    initial_amount: Uint128,
+    decay_factor: Decimal,
+    periods: u64,
+    fraction: Decimal256,
+) -> StdResult<Decimal256> {
+    let initial_amount = Decimal256::from_atomics(initial_amount, 0).map_err(to_std_error)?;
+    let remaining = get_decay_remaining(decay_factor, periods)?;
+
+    let complete = initial_amount
+        .checked_mul(Decimal256::one().checked_sub(remaining)?)?
+        .checked_div(Decimal256::one().checked_sub(decay_factor.into())?)
+        .map_err(to_std_error)?;
+    let partial = initial_amount
+        .checked_mul(remaining)?
+        .checked_mul(fraction)?;
+
+    Ok(complete.checked_add(partial)?)
+}
+
+/// the fraction of the initial amount a decaying emission rate emits in the
+/// period after `periods` complete periods.
+fn get_decay_remaining(decay_factor: Decimal, periods: u64) -> StdResult<Decimal256> {
+    // decay factors are less than one, so their powers round down to zero long
+    // before `u32::MAX` periods, except for factors that are within a
+    // billionth of one.
+    Ok(Decimal256::from(decay_factor).checked_pow(u32::try_from(periods).unwrap_or(u32::MAX))?)
+}
+
+/// a duration in the same unit as `duration`.
+fn with_scalar(duration: &Duration, scalar: u64) -> Duration {
+    match duration {
+        Duration::Height(_) => Duration::Height(scalar),
+        Duration::Time(_) => Duration::Time(scalar),
+    }
+}
+
+fn to_std_error(err: impl ToString) -> StdError {
+    StdError::generic_err(err.to_string())
+}
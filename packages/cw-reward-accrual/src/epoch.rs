@@ -0,0 +1,281 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{ensure, BlockInfo, StdError, StdResult, Uint128, Uint256};
+use cw_utils::{Duration, Expiration};
+use std::cmp::min;
+
+use crate::{
+    get_decay_emitted,
+    math::{get_duration_scalar, get_exp_diff, get_rewards_puvp, scale_factor},
+    AccrualError, EmissionRate,
+};
+
+#[cw_serde]
+pub struct Epoch {
+    /// reward emission rate
+    pub emission_rate: EmissionRate,
+    /// the time when the current reward distribution period started. period
+    /// finishes iff it reaches its end.
+    pub started_at: Expiration,
+    /// the time when all funded rewards are allocated to users and thus the
+    /// distribution period ends.
+    pub ends_at: Expiration,
+    /// total rewards earned per unit voting power from started_at to
+    /// last_updated_total_earned_puvp
+    pub total_earned_puvp: Uint256,
+    /// time when total_earned_puvp was last updated
+    pub last_updated_total_earned_puvp: Expiration,
+    /// total rewards emitted from started_at to last_updated_total_earned_puvp.
+    /// only tracked for perpetual emission, which has no ends_at to derive it
+    /// from and must never emit more than the funded amount.
+    #[serde(default)]
+    pub emitted: Uint128,
+}
+
+impl Epoch {
+    /// bump the last_updated_total_earned_puvp field to the minimum of the
+    /// current block and ends_at since rewards cannot be distributed after
+    /// ends_at. this is necessary in the case that a future funding backfills
+    /// rewards after they've finished distributing. in order to compute over
+    /// the missed space, last_updated can never be greater than ends_at. if
+    /// ends_at is never, the epoch must be paused, so it should never be
+    /// updated. errors if a height-based last_updated would move backwards, as
+    /// the range between the two heights would be counted twice.
+    pub fn bump_last_updated(&mut self, current_block: &BlockInfo) -> Result<(), AccrualError> {
+        match self.distributable_until(current_block) {
+            Expiration::Never {} => {
+                self.last_updated_total_earned_puvp = Expiration::Never {};
+            }
+            Expiration::AtHeight(ends_at_height) => {
+                let height = min(current_block.height, ends_at_height);
+                if let Expiration::AtHeight(last_updated) = self.last_updated_total_earned_puvp {
+                    ensure!(
+                        height >= last_updated,
+                        AccrualError::HeightRegression {
+                            last_updated,
+                            height
+                        }
+                    );
+                }
+                self.last_updated_total_earned_puvp = Expiration::AtHeight(height);
+            }
+            Expiration::AtTime(ends_at_time) => {
+                self.last_updated_total_earned_puvp =
+                    Expiration::AtTime(min(current_block.time, ends_at_time));
+            }
+        }
+        Ok(())
+    }
+
+    /// the time until which rewards can be distributed. this is ends_at for
+    /// all emission rates except perpetual, which has no end and can always
+    /// distribute up to the current block.
+    pub fn distributable_until(&self, current_block: &BlockInfo) -> Expiration {
+        match self.emission_rate {
+            EmissionRate::LinearPerpetual { duration, .. } => match duration {
+                Duration::Height(_) => Expiration::AtHeight(current_block.height),
+                Duration::Time(_) => Expiration::AtTime(current_block.time),
+            },
+            _ => self.ends_at,
+        }
+    }
+
+    /// Returns the latest time when rewards were distributed. Works by
+    /// comparing `current_block` with the distribution end time:
+    /// - If the end is `Never`, then no rewards are currently being
+    ///   distributed, so return the last update.
+    /// - If the end is `AtHeight(h)` or `AtTime(t)`, we compare the current
+    ///   block height or time with `h` or `t` respectively.
+    /// - If current block respective value is before the end, rewards are still
+    ///   being distributed. We therefore return the current block `height` or
+    ///   `time`, as this block is the most recent time rewards were
+    ///   distributed.
+    /// - If current block respective value is after the end, rewards are no
+    ///   longer being distributed. We therefore return the end `height` or
+    ///   `time`, as that was the last date where rewards were distributed.
+    pub fn get_latest_distribution_time(&self, current_block: &BlockInfo) -> Expiration {
+        match self.distributable_until(current_block) {
+            Expiration::Never {} => self.last_updated_total_earned_puvp,
+            Expiration::AtHeight(ends_at_height) => {
+                Expiration::AtHeight(min(current_block.height, ends_at_height))
+            }
+            Expiration::AtTime(ends_at_time) => {
+                Expiration::AtTime(min(current_block.time, ends_at_time))
+            }
+        }
+    }
+
+    /// get the total rewards to be distributed in this epoch given the amount
+    /// funded for it.
+    pub fn get_total_rewards(&self, funded_amount: Uint128) -> StdResult<Uint128> {
+        match self.emission_rate {
+            EmissionRate::Paused {} => Ok(Uint128::zero()),
+            EmissionRate::Immediate {} => Ok(funded_amount),
+            EmissionRate::Linear {
+                amount, duration, ..
+            } => {
+                let epoch_duration = get_exp_diff(&self.ends_at, &self.started_at)?;
+
+                amount
+                    .checked_multiply_ratio(epoch_duration, get_duration_scalar(&duration))
+                    .map_err(|e| StdError::generic_err(e.to_string()))
+            }
+            EmissionRate::LinearPerpetual { .. } => Ok(self.emitted),
+            EmissionRate::Decay {
+                initial_amount,
+                duration,
+                decay_factor,
+            } => get_decay_emitted(
+                initial_amount,
+                &duration,
+                decay_factor,
+                get_exp_diff(&self.ends_at, &self.started_at)?,
+            ),
+        }
+    }
+
+    /// Calculate the total rewards per unit voting power and the total rewards
+    /// emitted in this epoch up to the current block. only perpetual emission
+    /// tracks the emitted amount, which is returned unchanged for other
+    /// emission rates. `get_total_power` is only called if rewards have been
+    /// emitted since the last update.
+    pub fn get_totals(
+        &self,
+        current_block: &BlockInfo,
+        funded_amount: Uint128,
+        get_total_power: impl FnOnce() -> StdResult<Uint128>,
+    ) -> StdResult<(Uint256, Uint128)> {
+        let curr = self.total_earned_puvp;
+        let emitted = self.emitted;
+
+        match self.emission_rate {
+            EmissionRate::Paused {} => Ok((Uint256::zero(), emitted)),
+            // this is updated manually during funding, so just return it here.
+            EmissionRate::Immediate {} => Ok((curr, emitted)),
+            EmissionRate::Linear {
+                amount, duration, ..
+            } => {
+                // get the duration from the last time rewards were updated to
+                // the last time rewards were distributed. this will be 0 if the
+                // rewards were updated at or after the last time rewards were
+                // distributed.
+                let new_reward_distribution_duration: Uint128 = get_exp_diff(
+                    &self.get_latest_distribution_time(current_block),
+                    &self.last_updated_total_earned_puvp,
+                )?
+                .into();
+
+                // no need to query total voting power and do math if
+                // distribution is already up to date.
+                if new_reward_distribution_duration.is_zero() {
+                    return Ok((curr, emitted));
+                }
+
+                // if no voting power is registered, no one should receive
+                // rewards.
+                let prev_total_power = get_total_power()?;
+                if prev_total_power.is_zero() {
+                    return Ok((curr, emitted));
+                }
+
+                // count intervals of the rewards emission that have passed
+                // since the last update which need to be distributed
+                let complete_distribution_periods = new_reward_distribution_duration
+                    .checked_div(get_duration_scalar(&duration).into())?;
+
+                // It is impossible for this to overflow as total rewards can
+                // never exceed max value of Uint128 as total tokens in
+                // existence cannot exceed Uint128 (because the bank module Coin
+                // type uses Uint128).
+                let new_rewards_distributed = amount
+                    .full_mul(complete_distribution_periods)
+                    .checked_mul(scale_factor())?;
+
+                // the new rewards per unit voting power that have been
+                // distributed since the last update
+                let new_rewards_puvp =
+                    new_rewards_distributed.checked_div(prev_total_power.into())?;
+                Ok((curr.checked_add(new_rewards_puvp)?, emitted))
+            }
+            EmissionRate::LinearPerpetual { amount, duration } => {
+                // once all funding has been emitted, rewards are paused until
+                // the next funding.
+                let unemitted = funded_amount.checked_sub(emitted)?;
+                if unemitted.is_zero() {
+                    return Ok((curr, emitted));
+                }
+
+                // get the duration from the last time rewards were updated to
+                // the current block, since perpetual emission has no end.
+                let new_reward_distribution_duration = get_exp_diff(
+                    &self.get_latest_distribution_time(current_block),
+                    &self.last_updated_total_earned_puvp,
+                )?;
+                if new_reward_distribution_duration == 0 {
+                    return Ok((curr, emitted));
+                }
+
+                // if no voting power is registered, no one should receive
+                // rewards, so nothing is emitted and the funds remain for
+                // later.
+                let prev_total_power = get_total_power()?;
+                if prev_total_power.is_zero() {
+                    return Ok((curr, emitted));
+                }
+
+                // never emit more than remains of the funded amount.
+                let new_emitted = amount
+                    .checked_multiply_ratio(
+                        new_reward_distribution_duration,
+                        get_duration_scalar(&duration),
+                    )
+                    .map_err(|e| StdError::generic_err(e.to_string()))?
+                    .min(unemitted);
+
+                Ok((
+                    curr.checked_add(get_rewards_puvp(new_emitted, prev_total_power)?)?,
+                    emitted.checked_add(new_emitted)?,
+                ))
+            }
+            EmissionRate::Decay {
+                initial_amount,
+                duration,
+                decay_factor,
+            } => {
+                // get the time elapsed since the epoch started as of the last
+                // update and as of the last time rewards were distributed.
+                let last_updated =
+                    get_exp_diff(&self.last_updated_total_earned_puvp, &self.started_at)?;
+                let latest = get_exp_diff(
+                    &self.get_latest_distribution_time(current_block),
+                    &self.started_at,
+                )?;
+                if latest <= last_updated {
+                    return Ok((curr, emitted));
+                }
+
+                // if no voting power is registered, no one should receive
+                // rewards.
+                let prev_total_power = get_total_power()?;
+                if prev_total_power.is_zero() {
+                    return Ok((curr, emitted));
+                }
+
+                // the rewards emitted between the two, which follow the decay
+                // schedule from when the epoch started.
+                let new_rewards_distributed =
+                    get_decay_emitted(initial_amount, &duration, decay_factor, latest)?
+                        .saturating_sub(get_decay_emitted(
+                            initial_amount,
+                            &duration,
+                            decay_factor,
+                            last_updated,
+                        )?);
+
+                Ok((
+                    curr.checked_add(get_rewards_puvp(new_rewards_distributed, prev_total_power)?)?,
+                    emitted,
+                ))
+            }
+        }
+    }
+}
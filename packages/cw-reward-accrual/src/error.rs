@@ -0,0 +1,17 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AccrualError {
+    #[error(transparent)]
+    Std(#[from] StdError),
+
+    #[error("Invalid emission rate: {field} cannot be zero")]
+    InvalidEmissionRateFieldZero { field: String },
+
+    #[error("Invalid emission rate: decay_factor must be less than one")]
+    InvalidDecayFactor {},
+
+    #[error("Block height {height} is before the last accounted for height {last_updated}")]
+    HeightRegression { last_updated: u64, height: u64 },
+}
@@ -0,0 +1,20 @@
+#![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
+
+mod accounts;
+mod emission;
+mod epoch;
+mod error;
+mod math;
+
+#[cfg(test)]
+mod tests;
+
+pub use accounts::{Account, Accounts, Stream};
+pub use emission::{
+    get_decay_emitted, get_decay_funded_duration, get_linear_funded_duration, EmissionRate,
+};
+pub use epoch::Epoch;
+pub use error::AccrualError;
+pub use math::{
+    get_accrued_rewards, get_duration_scalar, get_exp_diff, get_rewards_puvp, scale_factor,
+};
@@ -0,0 +1,75 @@
+use cosmwasm_std::{StdError, StdResult, Uint128, Uint256};
+use cw_utils::{Duration, Expiration};
+
+/// the factor rewards per unit voting power are scaled up by to retain
+/// precision when dividing by the total voting power.
+pub fn scale_factor() -> Uint256 {
+    Uint256::from(10u8).pow(39)
+}
+
+/// returns underlying scalar value for a given duration.
+/// if the duration is in blocks, returns the block height.
+/// if the duration is in time, returns the time in seconds.
+pub fn get_duration_scalar(duration: &Duration) -> u64 {
+    match duration {
+        Duration::Height(h) => *h,
+        Duration::Time(t) => *t,
+    }
+}
+
+/// Calculate the duration from start to end. If the end is at or before the
+/// start, return 0. The first argument is end, and the second is start.
+pub fn get_exp_diff(end: &Expiration, start: &Expiration) -> StdResult<u64> {
+    match (end, start) {
+        (Expiration::AtHeight(end), Expiration::AtHeight(start)) => {
+            if end > start {
+                Ok(end - start)
+            } else {
+                Ok(0)
+            }
+        }
+        (Expiration::AtTime(end), Expiration::AtTime(start)) => {
+            if end > start {
+                Ok(end.seconds() - start.seconds())
+            } else {
+                Ok(0)
+            }
+        }
+        (Expiration::Never {}, Expiration::Never {}) => Ok(0),
+        _ => Err(StdError::generic_err(format!(
+            "incompatible expirations: got end {:?}, start {:?}",
+            end, start
+        ))),
+    }
+}
+
+/// the rewards per unit voting power earned by distributing amount over the
+/// total voting power.
+pub fn get_rewards_puvp(amount: Uint128, total_power: Uint128) -> StdResult<Uint256> {
+    Ok(Uint256::from(amount)
+        // this can never overflow since amount is a Uint128
+        .checked_mul(scale_factor())?
+        .checked_div(total_power.into())?)
+}
+
+/// the rewards earned by voting_power since the total rewards earned per unit
+/// voting power increased from accounted_for_puvp to total_earned_puvp.
+pub fn get_accrued_rewards(
+    voting_power: Uint128,
+    total_earned_puvp: Uint256,
+    accounted_for_puvp: Uint256,
+) -> StdResult<Uint128> {
+    // calculate the difference between the current total reward per unit
+    // voting power distributed and the latest reward per unit voting power
+    // accounted for.
+    let reward_factor = total_earned_puvp.checked_sub(accounted_for_puvp)?;
+
+    // calculate the amount of rewards earned:
+    // voting_power * reward_factor / scale_factor
+    let accrued_rewards_amount: Uint128 = Uint256::from(voting_power)
+        .checked_mul(reward_factor)?
+        .checked_div(scale_factor())?
+        .try_into()?;
+
+    Ok(accrued_rewards_amount)
+}
@@ -0,0 +1,330 @@
+use cosmwasm_std::{
+    testing::{mock_dependencies, mock_env},
+    Addr, BlockInfo, Decimal, StdResult, Uint128, Uint256,
+};
+use cw_utils::{Duration, Expiration};
+
+use crate::{scale_factor, Accounts, AccrualError, EmissionRate, Epoch, Stream};
+
+fn block_at(height: u64) -> BlockInfo {
+    let mut block = mock_env().block;
+    block.height = height;
+    block
+}
+
+fn new_epoch(emission_rate: EmissionRate, funded_amount: Uint128) -> Epoch {
+    let block = block_at(0);
+    let started_at = emission_rate.get_started_at(&block);
+    let ends_at = match emission_rate
+        .get_funded_period_duration(funded_amount)
+        .unwrap()
+    {
+        Some(duration) => duration.after(&block),
+        None => Expiration::Never {},
+    };
+    Epoch {
+        emission_rate,
+        started_at,
+        ends_at,
+        total_earned_puvp: Uint256::zero(),
+        last_updated_total_earned_puvp: started_at,
+        emitted: Uint128::zero(),
+    }
+}
+
+fn puvp(amount: u128, total_power: u128) -> Uint256 {
+    Uint256::from(amount) * scale_factor() / Uint256::from(total_power)
+}
+
+#[test]
+fn test_validate_emission_rate() {
+    assert_eq!(EmissionRate::Paused {}.validate(), Ok(()));
+    assert_eq!(
+        EmissionRate::Linear {
+            amount: Uint128::zero(),
+            duration: Duration::Height(1),
+            continuous: false,
+        }
+        .validate(),
+        Err(AccrualError::InvalidEmissionRateFieldZero {
+            field: "amount".to_string()
+        })
+    );
+    assert_eq!(
+        EmissionRate::LinearPerpetual {
+            amount: Uint128::one(),
+            duration: Duration::Time(0),
+        }
+        .validate(),
+        Err(AccrualError::InvalidEmissionRateFieldZero {
+            field: "duration".to_string()
+        })
+    );
+    assert_eq!(
+        EmissionRate::Decay {
+            initial_amount: Uint128::one(),
+            duration: Duration::Height(1),
+            decay_factor: Decimal::zero(),
+        }
+        .validate(),
+        Err(AccrualError::InvalidEmissionRateFieldZero {
+            field: "decay_factor".to_string()
+        })
+    );
+    assert_eq!(
+        EmissionRate::Decay {
+            initial_amount: Uint128::one(),
+            duration: Duration::Height(1),
+            decay_factor: Decimal::one(),
+        }
+        .validate(),
+        Err(AccrualError::InvalidDecayFactor {})
+    );
+}
+
+#[test]
+fn test_linear_totals() {
+    let total_power = || -> StdResult<Uint128> { Ok(Uint128::new(5)) };
+    let funded = Uint128::new(1000);
+    let mut epoch = new_epoch(
+        EmissionRate::Linear {
+            amount: Uint128::new(10),
+            duration: Duration::Height(1),
+            continuous: false,
+        },
+        funded,
+    );
+    assert_eq!(epoch.ends_at, Expiration::AtHeight(100));
+    assert_eq!(epoch.get_total_rewards(funded).unwrap(), funded);
+
+    let (total, emitted) = epoch
+        .get_totals(&block_at(10), funded, total_power)
+        .unwrap();
+    assert_eq!(total, puvp(100, 5));
+    assert_eq!(emitted, Uint128::zero());
+
+    // rewards stop accruing once the epoch ends.
+    epoch.total_earned_puvp = total;
+    epoch.bump_last_updated(&block_at(10)).unwrap();
+    let (total, _) = epoch
+        .get_totals(&block_at(150), funded, total_power)
+        .unwrap();
+    assert_eq!(total, puvp(1000, 5));
+
+    epoch.total_earned_puvp = total;
+    epoch.bump_last_updated(&block_at(150)).unwrap();
+    assert_eq!(
+        epoch.last_updated_total_earned_puvp,
+        Expiration::AtHeight(100)
+    );
+
+    // total power is not queried once up to date.
+    let (total, _) = epoch
+        .get_totals(&block_at(200), funded, || panic!("queried total power"))
+        .unwrap();
+    assert_eq!(total, puvp(1000, 5));
+}
+
+#[test]
+fn test_perpetual_totals() {
+    let total_power = || -> StdResult<Uint128> { Ok(Uint128::new(5)) };
+    let funded = Uint128::new(100);
+    let mut epoch = new_epoch(
+        EmissionRate::LinearPerpetual {
+            amount: Uint128::new(10),
+            duration: Duration::Height(1),
+        },
+        funded,
+    );
+    assert_eq!(epoch.ends_at, Expiration::Never {});
+
+    let (total, emitted) = epoch.get_totals(&block_at(4), funded, total_power).unwrap();
+    assert_eq!(total, puvp(40, 5));
+    assert_eq!(emitted, Uint128::new(40));
+
+    // never emits more than was funded.
+    epoch.total_earned_puvp = total;
+    epoch.emitted = emitted;
+    epoch.bump_last_updated(&block_at(4)).unwrap();
+    let (total, emitted) = epoch
+        .get_totals(&block_at(1000), funded, total_power)
+        .unwrap();
+    assert_eq!(total, puvp(100, 5));
+    assert_eq!(emitted, funded);
+
+    epoch.emitted = emitted;
+    assert_eq!(epoch.get_total_rewards(funded).unwrap(), funded);
+}
+
+#[test]
+fn test_decay_totals() {
+    let total_power = || -> StdResult<Uint128> { Ok(Uint128::new(5)) };
+    let halving = EmissionRate::Decay {
+        initial_amount: Uint128::new(1000),
+        duration: Duration::Height(10),
+        decay_factor: Decimal::percent(50),
+    };
+
+    // funding runs out partway through a period.
+    assert_eq!(
+        halving
+            .get_funded_period_duration(Uint128::new(1200))
+            .unwrap(),
+        Some(Duration::Height(14))
+    );
+
+    let funded = Uint128::new(1500);
+    let mut epoch = new_epoch(halving.clone(), funded);
+    assert_eq!(epoch.ends_at, Expiration::AtHeight(20));
+    assert_eq!(epoch.get_total_rewards(funded).unwrap(), funded);
+
+    // rewards are emitted at a constant rate within each period.
+    let (total, emitted) = epoch.get_totals(&block_at(5), funded, total_power).unwrap();
+    assert_eq!(total, puvp(500, 5));
+    assert_eq!(emitted, Uint128::zero());
+
+    // and at half the rate in the next period.
+    epoch.total_earned_puvp = total;
+    epoch.bump_last_updated(&block_at(5)).unwrap();
+    let (total, _) = epoch
+        .get_totals(&block_at(15), funded, total_power)
+        .unwrap();
+    assert_eq!(total, puvp(1250, 5));
+
+    // rewards stop accruing once the epoch ends.
+    epoch.total_earned_puvp = total;
+    epoch.bump_last_updated(&block_at(15)).unwrap();
+    let (total, _) = epoch
+        .get_totals(&block_at(100), funded, total_power)
+        .unwrap();
+    assert_eq!(total, puvp(1500, 5));
+
+    // a decaying rate never emits more than initial_amount / (1 -
+    // decay_factor), so funding beyond that is never emitted.
+    let funded = Uint128::new(5000);
+    let epoch = new_epoch(halving, funded);
+    assert!(matches!(epoch.ends_at, Expiration::AtHeight(h) if h > 1_000_000));
+    let (total, _) = epoch
+        .get_totals(&block_at(1_000_000), funded, total_power)
+        .unwrap();
+    assert_eq!(total, puvp(2000, 5));
+}
+
+#[test]
+fn test_no_voting_power_no_rewards() {
+    let funded = Uint128::new(100);
+    let epoch = new_epoch(
+        EmissionRate::LinearPerpetual {
+            amount: Uint128::new(10),
+            duration: Duration::Height(1),
+        },
+        funded,
+    );
+    let (total, emitted) = epoch
+        .get_totals(&block_at(4), funded, || Ok(Uint128::zero()))
+        .unwrap();
+    assert_eq!(total, Uint256::zero());
+    assert_eq!(emitted, Uint128::zero());
+}
+
+#[test]
+fn test_height_regression() {
+    let mut epoch = new_epoch(
+        EmissionRate::Linear {
+            amount: Uint128::new(10),
+            duration: Duration::Height(1),
+            continuous: false,
+        },
+        Uint128::new(1000),
+    );
+    epoch.bump_last_updated(&block_at(10)).unwrap();
+    assert_eq!(
+        epoch.bump_last_updated(&block_at(9)),
+        Err(AccrualError::HeightRegression {
+            last_updated: 10,
+            height: 9
+        })
+    );
+}
+
+#[test]
+fn test_accounts() {
+    let storage = &mut mock_dependencies().storage;
+    let accounts = Accounts::new("a", "a_stream");
+    let other = Accounts::new("b", "b_stream");
+    let alice = Addr::unchecked("alice");
+    let total_power = || -> StdResult<Uint128> { Ok(Uint128::new(5)) };
+
+    let funded = Uint128::new(1000);
+    let stream = Stream {
+        epoch: new_epoch(
+            EmissionRate::Linear {
+                amount: Uint128::new(10),
+                duration: Duration::Height(1),
+                continuous: false,
+            },
+            funded,
+        ),
+        funded_amount: funded,
+    };
+    accounts.save_stream(storage, &stream).unwrap();
+    other.save_stream(storage, &stream).unwrap();
+
+    // alice has 2 of the 5 voting power, so earns 4 per block.
+    assert_eq!(
+        accounts
+            .query_pending(storage, &block_at(10), &alice, Uint128::new(2), total_power)
+            .unwrap(),
+        Uint128::new(40)
+    );
+    // querying does not update the stream or the account.
+    assert_eq!(accounts.load_stream(storage).unwrap(), stream);
+    assert_eq!(
+        accounts.load(storage, &alice).unwrap().pending_rewards,
+        Uint128::zero()
+    );
+
+    let account = accounts
+        .update(storage, &block_at(10), &alice, Uint128::new(2), total_power)
+        .unwrap();
+    assert_eq!(account.pending_rewards, Uint128::new(40));
+    assert_eq!(account.accounted_for_rewards_puvp, puvp(100, 5));
+    let updated = accounts.load_stream(storage).unwrap();
+    assert_eq!(updated.epoch.total_earned_puvp, puvp(100, 5));
+    assert_eq!(
+        updated.epoch.last_updated_total_earned_puvp,
+        Expiration::AtHeight(10)
+    );
+
+    // only rewards earned since the last update accrue.
+    let account = accounts
+        .update(storage, &block_at(15), &alice, Uint128::new(1), total_power)
+        .unwrap();
+    assert_eq!(account.pending_rewards, Uint128::new(50));
+
+    // namespaces are independent.
+    assert_eq!(other.load_stream(storage).unwrap(), stream);
+    assert_eq!(
+        other.load(storage, &alice).unwrap().pending_rewards,
+        Uint128::zero()
+    );
+
+    assert_eq!(accounts.claim(storage, &alice).unwrap(), Uint128::new(50));
+    assert_eq!(accounts.claim(storage, &alice).unwrap(), Uint128::zero());
+    assert_eq!(
+        accounts
+            .load(storage, &alice)
+            .unwrap()
+            .accounted_for_rewards_puvp,
+        puvp(150, 5)
+    );
+
+    // updates can not move the stream backwards.
+    assert_eq!(
+        accounts.update(storage, &block_at(14), &alice, Uint128::new(1), total_power),
+        Err(AccrualError::HeightRegression {
+            last_updated: 15,
+            height: 14
+        })
+    );
+}
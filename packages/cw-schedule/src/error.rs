@@ -0,0 +1,18 @@
+use cosmwasm_std::Uint128;
+use thiserror::Error;
+use wynd_utils::CurveError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ScheduleError {
+    #[error(transparent)]
+    Curve(#[from] CurveError),
+
+    #[error("schedule values must be in [0, total]. got [{min}, {max}]")]
+    Range { min: Uint128, max: Uint128 },
+
+    #[error("can not release a constant amount, specify two or more points")]
+    Constant,
+
+    #[error("cliffs must be non-zero and shorter than the schedule")]
+    InvalidCliff,
+}
@@ -0,0 +1,11 @@
+#![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
+
+mod error;
+mod schedule;
+
+#[cfg(test)]
+mod tests;
+
+pub use error::ScheduleError;
+pub use schedule::{get_curve_bounds, get_curve_points, Schedule};
+pub use wynd_utils::{Curve, CurveError, PiecewiseLinear, SaturatingLinear};
@@ -0,0 +1,108 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+use wynd_utils::{Curve, PiecewiseLinear, SaturatingLinear};
+
+use crate::error::ScheduleError;
+
+#[cw_serde]
+pub enum Schedule {
+    /// Releases linearally from `0` to `total`.
+    SaturatingLinear,
+    /// Releases by linearally interpolating between the provided
+    /// (seconds, amount) points. The first amount must be zero and
+    /// the last amount the total. `seconds` are seconds since the
+    /// schedule's start time.
+    ///
+    /// There is a problem in the underlying Curve library that
+    /// doesn't allow zero start values, so the first value of
+    /// `seconds` must be > 1. To start at a particular time (if you
+    /// need that level of percision), subtract one from the true
+    /// start time, and make the first `seconds` value `1`.
+    ///
+    /// <https://github.com/cosmorama/wynddao/pull/4>
+    PiecewiseLinear(Vec<(u64, Uint128)>),
+    /// Releases nothing until `cliff_seconds` after the start time,
+    /// at which point the amount that would have been released
+    /// linearally is released at once. Releases linearally to
+    /// `total` after that. `cliff_seconds` must be non-zero and less
+    /// than the duration.
+    Cliff { cliff_seconds: u64 },
+}
+
+impl Schedule {
+    /// Gets the curve of the amount released `t` seconds after the
+    /// start time. For a curve to be valid:
+    ///
+    /// 1. it must start at 0,
+    /// 2. it must end at total,
+    /// 3. it must never decrease.
+    ///
+    /// Piecewise curves must have at least two steps. One step would
+    /// be a constant release (why would you want this?).
+    ///
+    /// A schedule is valid if `total` is zero: nothing will ever be
+    /// released. Consumers should consider validating that `total`
+    /// is non-zero.
+    pub fn into_curve(self, total: Uint128, duration_seconds: u64) -> Result<Curve, ScheduleError> {
+        let c = match self {
+            Schedule::SaturatingLinear => {
+                Curve::saturating_linear((0, 0), (duration_seconds, total.u128()))
+            }
+            Schedule::PiecewiseLinear(steps) => {
+                if steps.len() < 2 {
+                    return Err(ScheduleError::Constant);
+                }
+                Curve::PiecewiseLinear(PiecewiseLinear { steps })
+            }
+            Schedule::Cliff { cliff_seconds } => {
+                if cliff_seconds == 0 || cliff_seconds >= duration_seconds {
+                    return Err(ScheduleError::InvalidCliff);
+                }
+                let at_cliff = total.multiply_ratio(cliff_seconds, duration_seconds);
+                Curve::PiecewiseLinear(PiecewiseLinear {
+                    steps: vec![
+                        (cliff_seconds - 1, Uint128::zero()),
+                        (cliff_seconds, at_cliff),
+                        (duration_seconds, total),
+                    ],
+                })
+            }
+        };
+        c.validate_monotonic_increasing()?; // => max >= curve(t) \forall t
+        let range = c.range();
+        if range != (0, total.u128()) {
+            return Err(ScheduleError::Range {
+                min: Uint128::new(range.0),
+                max: Uint128::new(range.1),
+            });
+        }
+        Ok(c)
+    }
+}
+
+/// Gets the `(seconds, amount)` points that `curve` interpolates
+/// between, or `None` if it is constant.
+pub fn get_curve_points(curve: &Curve) -> Option<Vec<(u64, Uint128)>> {
+    match curve {
+        Curve::Constant { .. } => None,
+        Curve::SaturatingLinear(SaturatingLinear {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }) => Some(vec![(*min_x, *min_y), (*max_x, *max_y)]),
+        Curve::PiecewiseLinear(PiecewiseLinear { steps }) => Some(steps.clone()),
+    }
+}
+
+/// Gets the `(start, end)` seconds of `curve`, or `None` if it is
+/// constant.
+pub fn get_curve_bounds(curve: &Curve) -> Option<(u64, u64)> {
+    match curve {
+        Curve::Constant { .. } => None,
+        Curve::SaturatingLinear(SaturatingLinear { min_x, max_x, .. }) => Some((*min_x, *max_x)),
+        Curve::PiecewiseLinear(PiecewiseLinear { steps }) => {
+            Some((steps[0].0, steps[steps.len() - 1].0))
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use cosmwasm_std::Uint128;
+use wynd_utils::{Curve, CurveError};
+
+use crate::{get_curve_bounds, get_curve_points, Schedule, ScheduleError};
+
+#[test]
+fn test_saturating_linear() {
+    let curve = Schedule::SaturatingLinear
+        .into_curve(Uint128::new(100), 10)
+        .unwrap();
+    assert_eq!(curve.value(0), Uint128::zero());
+    assert_eq!(curve.value(5), Uint128::new(50));
+    assert_eq!(curve.value(10), Uint128::new(100));
+    assert_eq!(curve.value(20), Uint128::new(100));
+
+    assert_eq!(
+        get_curve_points(&curve),
+        Some(vec![(0, Uint128::zero()), (10, Uint128::new(100))])
+    );
+    assert_eq!(get_curve_bounds(&curve), Some((0, 10)));
+}
+
+#[test]
+fn test_piecewise_linear() {
+    let steps = vec![
+        (1, Uint128::zero()),
+        (10, Uint128::new(90)),
+        (20, Uint128::new(100)),
+    ];
+    let curve = Schedule::PiecewiseLinear(steps.clone())
+        .into_curve(Uint128::new(100), 20)
+        .unwrap();
+    assert_eq!(curve.value(10), Uint128::new(90));
+    assert_eq!(curve.value(15), Uint128::new(95));
+
+    assert_eq!(get_curve_points(&curve), Some(steps));
+    assert_eq!(get_curve_bounds(&curve), Some((1, 20)));
+
+    assert_eq!(
+        Schedule::PiecewiseLinear(vec![(1, Uint128::zero())]).into_curve(Uint128::zero(), 20),
+        Err(ScheduleError::Constant)
+    );
+    assert_eq!(
+        Schedule::PiecewiseLinear(vec![(10, Uint128::zero()), (1, Uint128::new(100))])
+            .into_curve(Uint128::new(100), 20),
+        Err(ScheduleError::Curve(CurveError::PointsOutOfOrder))
+    );
+    assert_eq!(
+        Schedule::PiecewiseLinear(vec![(1, Uint128::zero()), (10, Uint128::new(90))])
+            .into_curve(Uint128::new(100), 20),
+        Err(ScheduleError::Range {
+            min: Uint128::zero(),
+            max: Uint128::new(90)
+        })
+    );
+}
+
+#[test]
+fn test_cliff() {
+    let curve = Schedule::Cliff { cliff_seconds: 25 }
+        .into_curve(Uint128::new(100), 100)
+        .unwrap();
+    assert_eq!(curve.value(24), Uint128::zero());
+    assert_eq!(curve.value(25), Uint128::new(25));
+    assert_eq!(curve.value(50), Uint128::new(50));
+    assert_eq!(get_curve_bounds(&curve), Some((24, 100)));
+
+    for cliff_seconds in [0, 100, 101] {
+        assert_eq!(
+            Schedule::Cliff { cliff_seconds }.into_curve(Uint128::new(100), 100),
+            Err(ScheduleError::InvalidCliff)
+        );
+    }
+}
+
+#[test]
+fn test_constant_curve() {
+    let curve = Curve::Constant { y: Uint128::new(1) };
+    assert_eq!(get_curve_points(&curve), None);
+    assert_eq!(get_curve_bounds(&curve), None);
+}
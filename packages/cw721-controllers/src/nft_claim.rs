@@ -1,6 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, BlockInfo, CustomQuery, Deps, StdResult, Storage};
-use cw_storage_plus::Map;
+use cosmwasm_std::{Addr, BlockInfo, CustomQuery, Deps, Order, StdResult, Storage};
+use cw_storage_plus::{Bound, Map};
 use cw_utils::Expiration;
 
 #[cw_serde]
@@ -85,6 +85,53 @@ impl<'a> NftClaims<'a> {
         Ok(to_send)
     }
 
+    /// Removes all of ADDR's claims, whether or not they have matured,
+    /// and returns the token IDs they were for. Useful once claims no
+    /// longer need to wait, e.g. after unstaking has been waived.
+    pub fn claim_all_nfts(&self, storage: &mut dyn Storage, addr: &Addr) -> StdResult<Vec<String>> {
+        let nft_claims = self.0.may_load(storage, addr)?.unwrap_or_default();
+        self.0.remove(storage, addr);
+        Ok(nft_claims.into_iter().map(|c| c.token_id).collect())
+    }
+
+    /// Sets the release time of ADDR's claim for TOKEN_ID. Does
+    /// nothing if ADDR has no claim for TOKEN_ID.
+    pub fn set_release_at(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        token_id: &str,
+        release_at: Expiration,
+    ) -> StdResult<()> {
+        if let Some(mut nft_claims) = self.0.may_load(storage, addr)? {
+            for claim in nft_claims.iter_mut().filter(|c| c.token_id == token_id) {
+                claim.release_at = release_at;
+            }
+            self.0.save(storage, addr, &nft_claims)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the claims of up to LIMIT addresses in ascending
+    /// order, starting after START_AFTER. Useful for paginated
+    /// migrations over all outstanding claims.
+    pub fn range_claims(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<&Addr>,
+        limit: usize,
+    ) -> StdResult<Vec<(Addr, Vec<NftClaim>)>> {
+        self.0
+            .range(
+                storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect()
+    }
+
     pub fn query_claims<Q: CustomQuery>(
         &self,
         deps: Deps<Q>,
@@ -97,10 +144,7 @@ impl<'a> NftClaims<'a> {
 
 #[cfg(test)]
 mod test {
-    use cosmwasm_std::{
-        testing::{mock_dependencies, mock_env},
-        Order,
-    };
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
 
     use super::*;
     const TEST_BAYC_TOKEN_ID: &str = "BAYC";
@@ -402,4 +446,143 @@ mod test {
 
         assert_eq!(queried_claims.nft_claims.len(), 0);
     }
+
+    #[test]
+    fn test_set_release_at() {
+        let mut deps = mock_dependencies();
+        let claims = NftClaims::new("claims");
+
+        claims
+            .create_nft_claims(
+                deps.as_mut().storage,
+                &Addr::unchecked("addr"),
+                vec![
+                    TEST_BAYC_TOKEN_ID.to_string(),
+                    TEST_CRYPTO_PUNKS_TOKEN_ID.to_string(),
+                ],
+                Expiration::AtHeight(10),
+            )
+            .unwrap();
+
+        claims
+            .set_release_at(
+                deps.as_mut().storage,
+                &Addr::unchecked("addr"),
+                TEST_BAYC_TOKEN_ID,
+                Expiration::AtHeight(5),
+            )
+            .unwrap();
+
+        // setting the release of a non-existent claim does nothing
+        claims
+            .set_release_at(
+                deps.as_mut().storage,
+                &Addr::unchecked("addr2"),
+                TEST_BAYC_TOKEN_ID,
+                Expiration::AtHeight(5),
+            )
+            .unwrap();
+
+        let queried_claims = claims
+            .query_claims(deps.as_ref(), &Addr::unchecked("addr"))
+            .unwrap();
+        assert_eq!(
+            queried_claims.nft_claims,
+            vec![
+                NftClaim::new(TEST_BAYC_TOKEN_ID.to_string(), Expiration::AtHeight(5)),
+                NftClaim::new(
+                    TEST_CRYPTO_PUNKS_TOKEN_ID.to_string(),
+                    Expiration::AtHeight(10)
+                ),
+            ]
+        );
+        assert!(!claims
+            .0
+            .has(deps.as_ref().storage, &Addr::unchecked("addr2")));
+    }
+
+    #[test]
+    fn test_claim_all_nfts() {
+        let mut deps = mock_dependencies();
+        let claims = NftClaims::new("claims");
+
+        claims
+            .create_nft_claims(
+                deps.as_mut().storage,
+                &Addr::unchecked("addr"),
+                vec![TEST_BAYC_TOKEN_ID.to_string()],
+                Expiration::AtHeight(10),
+            )
+            .unwrap();
+        claims
+            .create_nft_claims(
+                deps.as_mut().storage,
+                &Addr::unchecked("addr"),
+                vec![TEST_CRYPTO_PUNKS_TOKEN_ID.to_string()],
+                Expiration::Never {},
+            )
+            .unwrap();
+
+        // claims are released regardless of their maturity
+        let released = claims
+            .claim_all_nfts(deps.as_mut().storage, &Addr::unchecked("addr"))
+            .unwrap();
+        assert_eq!(
+            released,
+            vec![
+                TEST_BAYC_TOKEN_ID.to_string(),
+                TEST_CRYPTO_PUNKS_TOKEN_ID.to_string()
+            ]
+        );
+        assert!(!claims
+            .0
+            .has(deps.as_ref().storage, &Addr::unchecked("addr")));
+
+        let released = claims
+            .claim_all_nfts(deps.as_mut().storage, &Addr::unchecked("addr"))
+            .unwrap();
+        assert!(released.is_empty());
+    }
+
+    #[test]
+    fn test_range_claims() {
+        let mut deps = mock_dependencies();
+        let claims = NftClaims::new("claims");
+
+        for addr in ["addr1", "addr2", "addr3"] {
+            claims
+                .create_nft_claims(
+                    deps.as_mut().storage,
+                    &Addr::unchecked(addr),
+                    vec![TEST_BAYC_TOKEN_ID.to_string()],
+                    TEST_EXPIRATION,
+                )
+                .unwrap();
+        }
+
+        let page = claims.range_claims(deps.as_ref().storage, None, 2).unwrap();
+        assert_eq!(
+            page.iter()
+                .map(|(addr, _)| addr.as_str())
+                .collect::<Vec<_>>(),
+            vec!["addr1", "addr2"]
+        );
+        assert_eq!(
+            page[0].1,
+            vec![NftClaim::new(
+                TEST_BAYC_TOKEN_ID.to_string(),
+                TEST_EXPIRATION
+            )]
+        );
+
+        let page = claims
+            .range_claims(deps.as_ref().storage, Some(&page[1].0), 2)
+            .unwrap();
+        assert_eq!(
+            page.iter()
+                .map(|(addr, _)| addr.as_str())
+                .collect::<Vec<_>>(),
+            vec!["addr3"]
+        );
+    }
 }